@@ -1,3 +1,36 @@
+pub mod msfrpc;
+pub mod suggest;
+pub mod vuln;
+
+/// A service found on a host, as a scanner reports it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceInfo {
+    pub host: String,
+    pub port: u16,
+    /// Service name, e.g. "ssh" or "ftp"
+    pub name: String,
+    /// Server software, e.g. "OpenSSH" or "vsftpd"
+    pub product: Option<String>,
+    pub version: Option<String>,
+    pub banner: Option<String>,
+    /// "tcp" or "udp"
+    pub transport: &'static str,
+}
+
+impl Default for ServiceInfo {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 0,
+            name: String::new(),
+            product: None,
+            version: None,
+            banner: None,
+            transport: "tcp",
+        }
+    }
+}
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
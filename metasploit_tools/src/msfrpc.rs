@@ -0,0 +1,339 @@
+use crate::ServiceInfo;
+use rmpv::Value;
+use std::time::{Duration, Instant};
+
+/// Where msfrpcd listens by default (`msfrpcd -P PASS` serves HTTPS here)
+pub const DEFAULT_URL: &str = "https://127.0.0.1:55553/api/";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Carries one MessagePack-encoded call to msfrpcd and returns the encoded answer
+pub trait Transport {
+    fn post(&self, body: Vec<u8>) -> Result<Vec<u8>, String>;
+}
+
+/// msfrpcd over HTTP(S)
+pub struct HttpTransport {
+    client: reqwest::blocking::Client,
+    url: String,
+}
+
+impl HttpTransport {
+    /// Certificates are verified unless `accept_invalid_certs`, which msfrpcd's
+    /// default self-signed one needs; only use it for a daemon on a trusted path.
+    pub fn new(url: &str, accept_invalid_certs: bool) -> Result<Self, String> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .danger_accept_invalid_certs(accept_invalid_certs)
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+        Ok(Self {
+            client,
+            url: url.to_string(),
+        })
+    }
+}
+
+impl Transport for HttpTransport {
+    fn post(&self, body: Vec<u8>) -> Result<Vec<u8>, String> {
+        let resp = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "binary/message-pack")
+            .body(body)
+            .send()
+            .map_err(|e| format!("Failed to reach msfrpcd at {}: {}", self.url, e))?;
+        // Failed calls come back as 500 with an error map, which `call` reports
+        let bytes = resp
+            .bytes()
+            .map_err(|e| format!("Failed to read msfrpcd response: {}", e))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Outcome of one auxiliary module run against one service
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleRun {
+    pub module: String,
+    pub host: String,
+    pub port: u16,
+    /// "completed", "errored" or "timeout"
+    pub status: String,
+    pub result: serde_json::Value,
+}
+
+impl ModuleRun {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "module": self.module,
+            "host": self.host,
+            "port": self.port,
+            "status": self.status,
+            "result": self.result,
+        })
+    }
+}
+
+/// An authenticated msfrpcd session
+pub struct MsfRpcClient<T: Transport = HttpTransport> {
+    transport: T,
+    token: String,
+}
+
+impl MsfRpcClient<HttpTransport> {
+    /// Logs in to the msfrpcd at `url`, e.g. `DEFAULT_URL`; see
+    /// `HttpTransport::new` for `accept_invalid_certs`.
+    pub fn connect(
+        url: &str,
+        username: &str,
+        password: &str,
+        accept_invalid_certs: bool,
+    ) -> Result<Self, String> {
+        Self::login(
+            HttpTransport::new(url, accept_invalid_certs)?,
+            username,
+            password,
+        )
+    }
+}
+
+impl<T: Transport> MsfRpcClient<T> {
+    pub fn login(transport: T, username: &str, password: &str) -> Result<Self, String> {
+        let answer = send(
+            &transport,
+            "auth.login",
+            vec![Value::from(username), Value::from(password)],
+        )?;
+        let token = field(&answer, "token")
+            .and_then(as_text)
+            .ok_or("msfrpcd login failed: no token in the answer")?;
+        Ok(Self { transport, token })
+    }
+
+    /// Calls `method` with the session token and `args`.
+    pub fn call(&self, method: &str, args: Vec<Value>) -> Result<Value, String> {
+        let mut params = vec![Value::from(self.token.as_str())];
+        params.extend(args);
+        send(&self.transport, method, params)
+    }
+
+    /// Creates `workspace` (or reuses it) and makes it the current one.
+    pub fn create_workspace(&self, workspace: &str) -> Result<(), String> {
+        self.call("db.add_workspace", vec![Value::from(workspace)])?;
+        self.call("db.set_workspace", vec![Value::from(workspace)])?;
+        Ok(())
+    }
+
+    /// Records the hosts and services of `services` in `workspace`; returns
+    /// how many services were imported.
+    pub fn import_services(
+        &self,
+        workspace: &str,
+        services: &[ServiceInfo],
+    ) -> Result<usize, String> {
+        let mut hosts: Vec<&str> = services.iter().map(|s| s.host.as_str()).collect();
+        hosts.sort_unstable();
+        hosts.dedup();
+        for host in hosts {
+            self.call(
+                "db.report_host",
+                vec![map(vec![
+                    ("workspace", workspace.into()),
+                    ("host", host.into()),
+                ])],
+            )?;
+        }
+        for service in services {
+            let info = [service.product.as_deref(), service.version.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ");
+            self.call(
+                "db.report_service",
+                vec![map(vec![
+                    ("workspace", workspace.into()),
+                    ("host", service.host.as_str().into()),
+                    ("port", service.port.into()),
+                    ("proto", service.transport.into()),
+                    ("name", service.name.as_str().into()),
+                    ("info", info.into()),
+                ])],
+            )?;
+        }
+        Ok(services.len())
+    }
+
+    /// Starts an auxiliary module ("auxiliary/scanner/ssh/ssh_version")
+    /// against `service` and returns the run's UUID. Only auxiliary modules
+    /// are launched: they scan, they do not exploit.
+    pub fn run_module(&self, module: &str, service: &ServiceInfo) -> Result<String, String> {
+        let name = module
+            .strip_prefix("auxiliary/")
+            .ok_or_else(|| format!("Only auxiliary modules are launched, not {}", module))?;
+        let options = map(vec![
+            ("RHOSTS", service.host.as_str().into()),
+            ("RPORT", service.port.to_string().into()),
+        ]);
+        let answer = self.call(
+            "module.execute",
+            vec![Value::from("auxiliary"), Value::from(name), options],
+        )?;
+        field(&answer, "uuid")
+            .and_then(as_text)
+            .ok_or_else(|| format!("msfrpcd did not start {}", module))
+    }
+
+    /// Status and result of the run `uuid`; `None` while it is still running.
+    pub fn module_results(
+        &self,
+        uuid: &str,
+    ) -> Result<Option<(String, serde_json::Value)>, String> {
+        let answer = self.call("module.results", vec![Value::from(uuid)])?;
+        let status = field(&answer, "status")
+            .and_then(as_text)
+            .unwrap_or_default();
+        match status.as_str() {
+            "completed" => Ok(Some((
+                status,
+                field(&answer, "result").map(to_json).unwrap_or_default(),
+            ))),
+            "errored" => Ok(Some((
+                status,
+                field(&answer, "error").map(to_json).unwrap_or_default(),
+            ))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Runs each (module, service) pair and waits up to `timeout` for all of
+    /// them. A module that fails to start is reported as errored.
+    pub fn launch_scanners(
+        &self,
+        runs: &[(String, ServiceInfo)],
+        timeout: Duration,
+    ) -> Vec<ModuleRun> {
+        let mut pending: Vec<(usize, String)> = Vec::new();
+        let mut results: Vec<ModuleRun> = runs
+            .iter()
+            .enumerate()
+            .map(|(i, (module, service))| {
+                let (status, result) = match self.run_module(module, service) {
+                    Ok(uuid) => {
+                        pending.push((i, uuid));
+                        ("timeout".to_string(), serde_json::Value::Null)
+                    }
+                    Err(e) => ("errored".to_string(), serde_json::Value::from(e)),
+                };
+                ModuleRun {
+                    module: module.clone(),
+                    host: service.host.clone(),
+                    port: service.port,
+                    status,
+                    result,
+                }
+            })
+            .collect();
+
+        let deadline = Instant::now() + timeout;
+        while !pending.is_empty() {
+            pending.retain(|(i, uuid)| match self.module_results(uuid) {
+                Ok(Some((status, result))) => {
+                    results[*i].status = status;
+                    results[*i].result = result;
+                    false
+                }
+                Ok(None) => true,
+                Err(e) => {
+                    results[*i].status = "errored".to_string();
+                    results[*i].result = serde_json::Value::from(e);
+                    false
+                }
+            });
+            if pending.is_empty() || Instant::now() >= deadline {
+                break;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        results
+    }
+}
+
+/// All `runs` as one JSON array.
+pub fn runs_to_json(runs: &[ModuleRun]) -> serde_json::Value {
+    serde_json::Value::Array(runs.iter().map(ModuleRun::to_json).collect())
+}
+
+/// Sends `[method, params...]` and decodes the answer, turning msfrpcd's
+/// error maps into errors.
+fn send<T: Transport>(transport: &T, method: &str, params: Vec<Value>) -> Result<Value, String> {
+    let mut request = vec![Value::from(method)];
+    request.extend(params);
+    let mut body = Vec::new();
+    rmpv::encode::write_value(&mut body, &Value::Array(request))
+        .map_err(|e| format!("Failed to encode {} call: {}", method, e))?;
+    let answer = transport.post(body)?;
+    let answer = rmpv::decode::read_value(&mut answer.as_slice())
+        .map_err(|e| format!("Invalid msfrpcd answer to {}: {}", method, e))?;
+    if field(&answer, "error").and_then(Value::as_bool) == Some(true) {
+        let message = field(&answer, "error_message")
+            .and_then(as_text)
+            .unwrap_or_else(|| "unknown error".to_string());
+        return Err(format!("{} failed: {}", method, message));
+    }
+    Ok(answer)
+}
+
+fn map(entries: Vec<(&str, Value)>) -> Value {
+    Value::Map(
+        entries
+            .into_iter()
+            .map(|(key, value)| (Value::from(key), value))
+            .collect(),
+    )
+}
+
+/// msfrpcd sends some strings as binary, so keys and text are read as either.
+fn as_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => s.as_str().map(str::to_string),
+        Value::Binary(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+        _ => None,
+    }
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    value
+        .as_map()?
+        .iter()
+        .find(|(k, _)| as_text(k).as_deref() == Some(key))
+        .map(|(_, v)| v)
+}
+
+/// A MessagePack value as JSON; binary strings become text.
+pub fn to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Nil => serde_json::Value::Null,
+        Value::Boolean(b) => serde_json::Value::from(*b),
+        Value::Integer(i) => i
+            .as_i64()
+            .map(serde_json::Value::from)
+            .or_else(|| i.as_u64().map(serde_json::Value::from))
+            .unwrap_or_default(),
+        Value::F32(f) => serde_json::Value::from(*f),
+        Value::F64(f) => serde_json::Value::from(*f),
+        Value::String(_) | Value::Binary(_) => as_text(value)
+            .map(serde_json::Value::from)
+            .unwrap_or_default(),
+        Value::Array(items) => serde_json::Value::Array(items.iter().map(to_json).collect()),
+        Value::Map(entries) => serde_json::Value::Object(
+            entries
+                .iter()
+                .map(|(k, v)| (as_text(k).unwrap_or_else(|| k.to_string()), to_json(v)))
+                .collect(),
+        ),
+        Value::Ext(_, bytes) => {
+            serde_json::Value::from(String::from_utf8_lossy(bytes).into_owned())
+        }
+    }
+}
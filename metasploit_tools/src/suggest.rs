@@ -0,0 +1,179 @@
+use crate::ServiceInfo;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Rules compiled into the library; a user rules file adds to them
+pub const BUILTIN_RULES: &str = include_str!("../rules/msf_rules.toml");
+
+/// A Metasploit module worth trying against a service, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleSuggestion {
+    /// Full module path, e.g. "auxiliary/scanner/ssh/ssh_version"
+    pub module: String,
+    pub reason: String,
+}
+
+impl ModuleSuggestion {
+    pub fn is_exploit(&self) -> bool {
+        self.module.starts_with("exploit/")
+    }
+}
+
+/// A rules file as written
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    services: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    banner: Vec<BannerEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BannerEntry {
+    pattern: String,
+    modules: Vec<String>,
+}
+
+/// Banner pattern (case-insensitive) that pins down a vulnerable release,
+/// and the modules written for it
+#[derive(Debug, Clone)]
+pub struct BannerRule {
+    pub regex: Regex,
+    pub modules: Vec<String>,
+}
+
+/// Which modules to suggest for which services
+#[derive(Debug, Clone, Default)]
+pub struct SuggestionRules {
+    /// Lowercase service name to modules
+    services: BTreeMap<String, Vec<String>>,
+    banners: Vec<BannerRule>,
+}
+
+impl SuggestionRules {
+    /// Parses a rules file in the format of `rules/msf_rules.toml`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let file: RulesFile =
+            toml::from_str(text).map_err(|e| format!("Invalid suggestion rules: {}", e))?;
+        let banners = file
+            .banner
+            .into_iter()
+            .map(|entry| {
+                let regex = Regex::new(&format!("(?i){}", entry.pattern))
+                    .map_err(|e| format!("Invalid banner pattern \"{}\": {}", entry.pattern, e))?;
+                Ok(BannerRule {
+                    regex,
+                    modules: entry.modules,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        let services = file
+            .services
+            .into_iter()
+            .map(|(name, modules)| (name.to_ascii_lowercase(), modules))
+            .collect();
+        Ok(Self { services, banners })
+    }
+
+    /// The rules compiled into the library.
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_RULES).expect("built-in suggestion rules are valid")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// The built-in rules plus the user's rules file, if there is one.
+    pub fn load_default() -> Result<Self, String> {
+        let mut rules = Self::builtin();
+        if let Some(path) = user_rules_path().filter(|path| path.exists()) {
+            rules.extend(Self::load(&path)?);
+        }
+        Ok(rules)
+    }
+
+    /// Adds `other`'s rules: its service lists replace those of the same
+    /// services, and its banner rules are tried after these.
+    pub fn extend(&mut self, other: SuggestionRules) {
+        self.services.extend(other.services);
+        self.banners.extend(other.banners);
+    }
+
+    pub fn service_modules(&self, name: &str) -> &[String] {
+        self.services
+            .get(&name.to_ascii_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn banner_rules(&self) -> &[BannerRule] {
+        &self.banners
+    }
+
+    /// Modules to try against `service`: the ones its banner or its product
+    /// and version match a rule for first, then the generic ones for its
+    /// kind of service.
+    pub fn suggest(&self, service: &ServiceInfo) -> Vec<ModuleSuggestion> {
+        // A banner parsed into product and version may no longer read the
+        // way a rule spells it, and a rule may be keyed on either form
+        let product = [service.product.as_deref(), service.version.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let texts: Vec<&str> = [service.banner.as_deref().unwrap_or_default(), &product]
+            .into_iter()
+            .filter(|text| !text.is_empty())
+            .collect();
+        let mut suggestions: Vec<ModuleSuggestion> = Vec::new();
+        let mut add = |module: &str, reason: String| {
+            if !suggestions.iter().any(|s| s.module == module) {
+                suggestions.push(ModuleSuggestion {
+                    module: module.to_string(),
+                    reason,
+                });
+            }
+        };
+        for rule in &self.banners {
+            if let Some(found) = texts.iter().find_map(|text| rule.regex.find(text)) {
+                for module in &rule.modules {
+                    add(module, format!("banner matches \"{}\"", found.as_str()));
+                }
+            }
+        }
+        let name = service.name.to_ascii_lowercase();
+        for module in self.service_modules(&name) {
+            add(module, format!("{} service", name));
+        }
+        suggestions
+    }
+}
+
+/// ~/.config/netscan/msf_rules.toml, or under $XDG_CONFIG_HOME when set.
+pub fn user_rules_path() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("netscan").join("msf_rules.toml"))
+}
+
+/// Rules `suggest_modules` uses, loaded once; a broken user file is kept as its error
+static RULES: Lazy<Result<SuggestionRules, String>> = Lazy::new(SuggestionRules::load_default);
+
+/// The built-in rules plus the user's rules file, loaded on first use.
+pub fn default_rules() -> Result<&'static SuggestionRules, String> {
+    RULES.as_ref().map_err(Clone::clone)
+}
+
+/// Modules to try against `service` under the built-in and user rules.
+pub fn suggest_modules(service: &ServiceInfo) -> Result<Vec<ModuleSuggestion>, String> {
+    Ok(default_rules()?.suggest(service))
+}
@@ -0,0 +1,248 @@
+use crate::ServiceInfo;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::path::Path;
+
+/// NVD CVE API 2.0; a feed is its answer to a keyword search, cached locally
+pub const NVD_API_URL: &str = "https://services.nvd.nist.gov/rest/json/cves/2.0";
+
+/// CVSS versions in order of preference
+const CVSS_METRICS: &[&str] = &[
+    "cvssMetricV40",
+    "cvssMetricV31",
+    "cvssMetricV30",
+    "cvssMetricV2",
+];
+
+/// A known vulnerability of a service's product and version
+#[derive(Debug, Clone, PartialEq)]
+pub struct CveMatch {
+    /// e.g. "CVE-2011-2523"
+    pub id: String,
+    /// Base score of the newest CVSS version the feed scores it with
+    pub cvss: Option<f64>,
+    /// e.g. "CRITICAL"
+    pub severity: Option<String>,
+    pub summary: String,
+}
+
+/// Vulnerable software from a CPE match criterion
+#[derive(Debug, Clone)]
+struct CpeMatch {
+    product: String,
+    /// Exact version, or None for any version within the bounds
+    version: Option<String>,
+    start_including: Option<String>,
+    start_excluding: Option<String>,
+    end_including: Option<String>,
+    end_excluding: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct CveRecord {
+    cve: CveMatch,
+    affected: Vec<CpeMatch>,
+}
+
+/// CVEs of a cached NVD JSON feed
+#[derive(Debug, Clone, Default)]
+pub struct CveFeed {
+    records: Vec<CveRecord>,
+}
+
+impl CveFeed {
+    /// Reads a feed downloaded to `path`, e.g. by `netutil::fetch_and_cache`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read CVE feed {}: {}", path.display(), e))?;
+        Self::parse(&json)
+    }
+
+    /// Parses an NVD CVE API 2.0 response.
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let root: Value =
+            serde_json::from_str(json).map_err(|e| format!("Invalid CVE feed: {}", e))?;
+        let vulnerabilities = root["vulnerabilities"]
+            .as_array()
+            .ok_or("Invalid CVE feed: no \"vulnerabilities\" list")?;
+        let records = vulnerabilities
+            .iter()
+            .filter_map(|entry| parse_record(&entry["cve"]))
+            .collect();
+        Ok(Self { records })
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// CVEs affecting the product and version of `service`, highest CVSS
+    /// score first. Without both, nothing can be matched.
+    pub fn lookup(&self, service: &ServiceInfo) -> Vec<CveMatch> {
+        let (Some(product), Some(version)) = (&service.product, &service.version) else {
+            return Vec::new();
+        };
+        let product = normalize_product(product);
+        let mut matches: Vec<CveMatch> = self
+            .records
+            .iter()
+            .filter(|record| {
+                record
+                    .affected
+                    .iter()
+                    .any(|cpe| cpe.product == product && cpe.covers(version))
+            })
+            .map(|record| record.cve.clone())
+            .collect();
+        matches.sort_by(|a, b| {
+            b.cvss
+                .partial_cmp(&a.cvss)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        matches.dedup_by(|a, b| a.id == b.id);
+        matches
+    }
+}
+
+/// The NVD query whose answer feeds lookups for `product`.
+pub fn nvd_url(product: &str) -> String {
+    let keyword: String = product
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '+' })
+        .collect();
+    format!("{}?keywordSearch={}", NVD_API_URL, keyword)
+}
+
+impl CpeMatch {
+    /// "cpe:2.3:a:vendor:product:version:update:..." and its version bounds.
+    fn parse(entry: &Value) -> Option<Self> {
+        if entry["vulnerable"].as_bool() != Some(true) {
+            return None;
+        }
+        let fields: Vec<&str> = entry["criteria"].as_str()?.split(':').collect();
+        if fields.len() < 7 || fields[2] != "a" {
+            return None;
+        }
+        let version = match (fields[5], fields[6]) {
+            ("*" | "-", _) => None,
+            (version, "*" | "-") => Some(version.to_string()),
+            (version, update) => Some(format!("{}{}", version, update)),
+        };
+        let bound = |key: &str| entry[key].as_str().map(str::to_string);
+        Some(Self {
+            product: normalize_product(fields[4]),
+            version,
+            start_including: bound("versionStartIncluding"),
+            start_excluding: bound("versionStartExcluding"),
+            end_including: bound("versionEndIncluding"),
+            end_excluding: bound("versionEndExcluding"),
+        })
+    }
+
+    fn covers(&self, version: &str) -> bool {
+        if let Some(exact) = &self.version {
+            return same_release(exact, version);
+        }
+        let cmp = |bound: &Option<String>| bound.as_deref().map(|b| compare_versions(version, b));
+        cmp(&self.start_including).is_none_or(|o| o != Ordering::Less)
+            && cmp(&self.start_excluding).is_none_or(|o| o == Ordering::Greater)
+            && cmp(&self.end_including).is_none_or(|o| o != Ordering::Greater)
+            && cmp(&self.end_excluding).is_none_or(|o| o == Ordering::Less)
+    }
+}
+
+fn parse_record(cve: &Value) -> Option<CveRecord> {
+    let id = cve["id"].as_str()?.to_string();
+    let summary = cve["descriptions"]
+        .as_array()
+        .and_then(|descriptions| {
+            descriptions
+                .iter()
+                .find(|d| d["lang"] == "en")
+                .and_then(|d| d["value"].as_str())
+        })
+        .unwrap_or_default()
+        .to_string();
+    let metric = CVSS_METRICS
+        .iter()
+        .find_map(|key| cve["metrics"][key].as_array().and_then(|m| m.first()));
+    let cvss = metric.and_then(|m| m["cvssData"]["baseScore"].as_f64());
+    // CVSS v2 keeps the severity beside the data, later versions inside it
+    let severity = metric
+        .and_then(|m| {
+            m["cvssData"]["baseSeverity"]
+                .as_str()
+                .or(m["baseSeverity"].as_str())
+        })
+        .map(str::to_string);
+    let affected = cve["configurations"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|config| config["nodes"].as_array().into_iter().flatten())
+        .filter(|node| node["negate"].as_bool() != Some(true))
+        .flat_map(|node| node["cpeMatch"].as_array().into_iter().flatten())
+        .filter_map(CpeMatch::parse)
+        .collect();
+    Some(CveRecord {
+        cve: CveMatch {
+            id,
+            cvss,
+            severity,
+            summary,
+        },
+        affected,
+    })
+}
+
+/// "Pure-FTPd" and "pure-ftpd" both become "pure_ftpd", as CPE names them.
+fn normalize_product(product: &str) -> String {
+    product.trim().to_ascii_lowercase().replace([' ', '-'], "_")
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Part {
+    Number(u64),
+    Text(String),
+}
+
+fn parts(version: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut chars = version.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                digits.push(d);
+            }
+            parts.push(Part::Number(digits.parse().unwrap_or(u64::MAX)));
+        } else if c.is_ascii_alphabetic() {
+            let mut text = String::new();
+            while let Some(l) = chars.next_if(char::is_ascii_alphabetic) {
+                text.push(l.to_ascii_lowercase());
+            }
+            parts.push(Part::Text(text));
+        } else {
+            chars.next();
+        }
+    }
+    parts
+}
+
+/// Orders versions part by part: "1.3.3" < "1.3.3c" < "1.3.10", "8.9" < "8.9p1".
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    parts(a).cmp(&parts(b))
+}
+
+/// Whether `version` is the release `exact` names; "8.9" covers the "8.9p1"
+/// patch level, but not "8.9.1".
+fn same_release(exact: &str, version: &str) -> bool {
+    let exact = parts(exact);
+    let version = parts(version);
+    version.starts_with(&exact) && matches!(version.get(exact.len()), None | Some(Part::Text(_)))
+}
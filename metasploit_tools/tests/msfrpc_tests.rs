@@ -0,0 +1,162 @@
+use metasploit_tools::ServiceInfo;
+use metasploit_tools::msfrpc::{self, MsfRpcClient, Transport};
+use rmpv::Value;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Answers calls by method name and records each decoded request
+struct MockRpc {
+    calls: Mutex<Vec<Value>>,
+    answer: fn(&str, &[Value]) -> Value,
+}
+
+impl MockRpc {
+    fn new(answer: fn(&str, &[Value]) -> Value) -> Self {
+        Self {
+            calls: Mutex::new(Vec::new()),
+            answer,
+        }
+    }
+}
+
+impl Transport for &MockRpc {
+    fn post(&self, body: Vec<u8>) -> Result<Vec<u8>, String> {
+        let request = rmpv::decode::read_value(&mut body.as_slice()).unwrap();
+        let items = request.as_array().unwrap().clone();
+        self.calls.lock().unwrap().push(request);
+        let answer = (self.answer)(items[0].as_str().unwrap(), &items[1..]);
+        let mut out = Vec::new();
+        rmpv::encode::write_value(&mut out, &answer).unwrap();
+        Ok(out)
+    }
+}
+
+fn map(entries: &[(&str, Value)]) -> Value {
+    Value::Map(
+        entries
+            .iter()
+            .map(|(k, v)| (Value::from(*k), v.clone()))
+            .collect(),
+    )
+}
+
+fn answer(method: &str, params: &[Value]) -> Value {
+    match method {
+        "auth.login" if params[1].as_str() == Some("secret") => map(&[
+            ("result", "success".into()),
+            // msfrpcd sends some strings as binary
+            ("token", Value::Binary(b"TEMP123".to_vec())),
+        ]),
+        "auth.login" => map(&[
+            ("error", true.into()),
+            ("error_message", "Login Failed".into()),
+        ]),
+        "module.execute" => map(&[("job_id", 1.into()), ("uuid", "run-1".into())]),
+        "module.results" => map(&[
+            ("status", "completed".into()),
+            (
+                "result",
+                map(&[("10.0.0.5", Value::Array(vec!["SSH-2.0-OpenSSH_8.9".into()]))]),
+            ),
+        ]),
+        _ => map(&[("result", "success".into())]),
+    }
+}
+
+fn ssh() -> ServiceInfo {
+    ServiceInfo {
+        host: "10.0.0.5".to_string(),
+        port: 22,
+        name: "ssh".to_string(),
+        product: Some("OpenSSH".to_string()),
+        version: Some("8.9p1".to_string()),
+        banner: None,
+        transport: "tcp",
+    }
+}
+
+fn methods(rpc: &MockRpc) -> Vec<String> {
+    rpc.calls
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|call| call[0].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_login_failure_is_an_error() {
+    let rpc = MockRpc::new(answer);
+    let err = MsfRpcClient::login(&rpc, "msf", "wrong").err().unwrap();
+    assert_eq!(err, "auth.login failed: Login Failed");
+}
+
+#[test]
+fn test_workspace_and_import_use_the_session_token() {
+    let rpc = MockRpc::new(answer);
+    let client = MsfRpcClient::login(&rpc, "msf", "secret").unwrap();
+    client.create_workspace("netscan").unwrap();
+    let mut snmp = ssh();
+    snmp.port = 161;
+    snmp.name = "snmp".to_string();
+    snmp.transport = "udp";
+    assert_eq!(
+        client.import_services("netscan", &[ssh(), snmp]).unwrap(),
+        2
+    );
+
+    assert_eq!(
+        methods(&rpc),
+        [
+            "auth.login",
+            "db.add_workspace",
+            "db.set_workspace",
+            "db.report_host",
+            "db.report_service",
+            "db.report_service",
+        ]
+    );
+    let calls = rpc.calls.lock().unwrap();
+    assert_eq!(calls[1][1].as_str(), Some("TEMP123"));
+    let service = msfrpc::to_json(&calls[4][2]);
+    assert_eq!(service["host"], "10.0.0.5");
+    assert_eq!(service["port"], 22);
+    assert_eq!(service["info"], "OpenSSH 8.9p1");
+    assert_eq!(service["proto"], "tcp");
+    assert_eq!(msfrpc::to_json(&calls[5][2])["proto"], "udp");
+}
+
+#[test]
+fn test_scanners_run_and_report_json() {
+    let rpc = MockRpc::new(answer);
+    let client = MsfRpcClient::login(&rpc, "msf", "secret").unwrap();
+    let runs = client.launch_scanners(
+        &[
+            ("auxiliary/scanner/ssh/ssh_version".to_string(), ssh()),
+            ("exploit/unix/ftp/vsftpd_234_backdoor".to_string(), ssh()),
+        ],
+        Duration::from_secs(5),
+    );
+
+    let json = msfrpc::runs_to_json(&runs);
+    assert_eq!(json[0]["status"], "completed");
+    assert_eq!(json[0]["result"]["10.0.0.5"][0], "SSH-2.0-OpenSSH_8.9");
+    // Exploits are never launched
+    assert_eq!(json[1]["status"], "errored");
+    assert_eq!(
+        methods(&rpc)
+            .iter()
+            .filter(|m| *m == "module.execute")
+            .count(),
+        1
+    );
+
+    let calls = rpc.calls.lock().unwrap();
+    let execute = calls
+        .iter()
+        .find(|c| c[0].as_str() == Some("module.execute"))
+        .unwrap();
+    assert_eq!(execute[2].as_str(), Some("auxiliary"));
+    assert_eq!(execute[3].as_str(), Some("scanner/ssh/ssh_version"));
+    assert_eq!(msfrpc::to_json(&execute[4])["RPORT"], "22");
+}
@@ -0,0 +1,146 @@
+use metasploit_tools::ServiceInfo;
+use metasploit_tools::suggest::{SuggestionRules, suggest_modules};
+
+fn service(name: &str, banner: Option<&str>) -> ServiceInfo {
+    ServiceInfo {
+        host: "10.0.0.5".to_string(),
+        port: 21,
+        name: name.to_string(),
+        product: None,
+        version: None,
+        banner: banner.map(str::to_string),
+        transport: "tcp",
+    }
+}
+
+fn modules(service: &ServiceInfo) -> Vec<String> {
+    suggest_modules(service)
+        .unwrap()
+        .into_iter()
+        .map(|s| s.module)
+        .collect()
+}
+
+#[test]
+fn test_banner_rules_come_before_generic_modules() {
+    let suggestions = suggest_modules(&service("ftp", Some("220 (vsFTPd 2.3.4)"))).unwrap();
+    assert_eq!(
+        suggestions[0].module,
+        "exploit/unix/ftp/vsftpd_234_backdoor"
+    );
+    assert!(suggestions[0].is_exploit());
+    assert_eq!(suggestions[0].reason, "banner matches \"vsFTPd 2.3.4\"");
+    assert_eq!(
+        modules(&service("ftp", Some("220 (vsFTPd 2.3.4)")))[1..],
+        [
+            "auxiliary/scanner/ftp/ftp_version",
+            "auxiliary/scanner/ftp/anonymous"
+        ]
+    );
+}
+
+#[test]
+fn test_rules_are_version_specific() {
+    let proftpd = |banner| modules(&service("ftp", Some(banner)));
+    assert!(
+        proftpd("220 ProFTPD 1.3.3c Server")
+            .contains(&"exploit/unix/ftp/proftpd_133c_backdoor".to_string())
+    );
+    assert!(
+        !proftpd("220 ProFTPD 1.3.3 Server")
+            .contains(&"exploit/unix/ftp/proftpd_133c_backdoor".to_string())
+    );
+    assert!(
+        proftpd("220 ProFTPD 1.3.5 Server")
+            .contains(&"exploit/unix/ftp/proftpd_modcopy_exec".to_string())
+    );
+    assert!(
+        !proftpd("220 ProFTPD 1.3.5e Server")
+            .contains(&"exploit/unix/ftp/proftpd_modcopy_exec".to_string())
+    );
+    assert!(
+        !modules(&service("ftp", Some("220 (vsFTPd 3.0.3)")))
+            .iter()
+            .any(|m| m.starts_with("exploit/"))
+    );
+
+    let ssh = |banner| modules(&service("ssh", Some(banner)));
+    assert!(
+        ssh("SSH-2.0-OpenSSH_7.4").contains(&"auxiliary/scanner/ssh/ssh_enumusers".to_string())
+    );
+    assert!(
+        !ssh("SSH-2.0-OpenSSH_8.9p1 Ubuntu-3")
+            .contains(&"auxiliary/scanner/ssh/ssh_enumusers".to_string())
+    );
+}
+
+#[test]
+fn test_product_and_version_stand_in_for_a_missing_banner() {
+    let mut ftp = service("FTP", None);
+    ftp.product = Some("vsftpd".to_string());
+    ftp.version = Some("2.3.4".to_string());
+    assert_eq!(modules(&ftp)[0], "exploit/unix/ftp/vsftpd_234_backdoor");
+    assert!(modules(&service("gopher", None)).is_empty());
+}
+
+#[test]
+fn test_product_and_version_match_alongside_a_banner() {
+    // The banner names no version; the detector read it from elsewhere
+    let mut ftp = service("ftp", Some("220 Welcome to the FTP service"));
+    ftp.product = Some("vsftpd".to_string());
+    ftp.version = Some("2.3.4".to_string());
+    assert_eq!(modules(&ftp)[0], "exploit/unix/ftp/vsftpd_234_backdoor");
+
+    // Rules matching the banner itself still fire
+    let mut ssh = service("ssh", Some("SSH-2.0-OpenSSH_7.4"));
+    ssh.product = Some("OpenSSH".to_string());
+    ssh.version = Some("7.4".to_string());
+    assert!(modules(&ssh).contains(&"auxiliary/scanner/ssh/ssh_enumusers".to_string()));
+}
+
+#[test]
+fn test_user_rules_extend_the_builtin_ones() {
+    let mut rules = SuggestionRules::builtin();
+    assert!(!rules.banner_rules().is_empty());
+    rules.extend(
+        SuggestionRules::parse(
+            r#"
+[services]
+FTP = ["auxiliary/scanner/ftp/ftp_login"]
+gopher = ["auxiliary/scanner/gopher/gopher_gophermap"]
+
+[[banner]]
+pattern = 'acme-ftpd 1\.0'
+modules = ["exploit/unix/ftp/acme_backdoor"]
+"#,
+        )
+        .unwrap(),
+    );
+    let suggested: Vec<String> = rules
+        .suggest(&service("ftp", Some("220 ACME-FTPd 1.0 ready")))
+        .into_iter()
+        .map(|s| s.module)
+        .collect();
+    assert_eq!(
+        suggested,
+        [
+            "exploit/unix/ftp/acme_backdoor",
+            "auxiliary/scanner/ftp/ftp_login"
+        ]
+    );
+    assert_eq!(rules.service_modules("gopher").len(), 1);
+    // Services the user file does not mention keep their built-in modules
+    assert_eq!(rules.service_modules("ssh").len(), 2);
+}
+
+#[test]
+fn test_invalid_rules_are_rejected() {
+    assert!(SuggestionRules::parse("[[banner]]\npattern = '('\nmodules = []").is_err());
+    assert!(SuggestionRules::parse("[service]\nssh = []").is_err());
+    assert!(
+        SuggestionRules::parse("")
+            .unwrap()
+            .service_modules("ssh")
+            .is_empty()
+    );
+}
@@ -0,0 +1,102 @@
+use metasploit_tools::ServiceInfo;
+use metasploit_tools::vuln::{self, CveFeed};
+use std::cmp::Ordering;
+
+const FEED: &str = r#"{
+  "resultsPerPage": 3,
+  "vulnerabilities": [
+    {"cve": {
+      "id": "CVE-2011-2523",
+      "descriptions": [{"lang": "en", "value": "vsftpd 2.3.4 contains a backdoor."}],
+      "metrics": {"cvssMetricV31": [{"cvssData": {"baseScore": 9.8, "baseSeverity": "CRITICAL"}}]},
+      "configurations": [{"nodes": [{"operator": "OR", "negate": false, "cpeMatch": [
+        {"vulnerable": true, "criteria": "cpe:2.3:a:vsftpd_project:vsftpd:2.3.4:*:*:*:*:*:*:*"}
+      ]}]}]
+    }},
+    {"cve": {
+      "id": "CVE-2015-1419",
+      "descriptions": [{"lang": "en", "value": "vsftpd before 3.0.3 deny_file bypass."}],
+      "metrics": {"cvssMetricV2": [{"cvssData": {"baseScore": 5.0}, "baseSeverity": "MEDIUM"}]},
+      "configurations": [{"nodes": [{"operator": "OR", "negate": false, "cpeMatch": [
+        {"vulnerable": true, "criteria": "cpe:2.3:a:vsftpd_project:vsftpd:*:*:*:*:*:*:*:*",
+         "versionEndExcluding": "3.0.3"}
+      ]}]}]
+    }},
+    {"cve": {
+      "id": "CVE-2023-38408",
+      "descriptions": [{"lang": "en", "value": "ssh-agent PKCS#11 remote code execution."}],
+      "metrics": {"cvssMetricV31": [{"cvssData": {"baseScore": 9.8, "baseSeverity": "CRITICAL"}}]},
+      "configurations": [{"nodes": [{"operator": "OR", "negate": false, "cpeMatch": [
+        {"vulnerable": true, "criteria": "cpe:2.3:a:openbsd:openssh:*:*:*:*:*:*:*:*",
+         "versionEndExcluding": "9.3"},
+        {"vulnerable": true, "criteria": "cpe:2.3:a:openbsd:openssh:9.3:-:*:*:*:*:*:*"}
+      ]}]}]
+    }}
+  ]
+}"#;
+
+fn service(product: &str, version: &str) -> ServiceInfo {
+    ServiceInfo {
+        host: "10.0.0.5".to_string(),
+        port: 21,
+        name: "ftp".to_string(),
+        product: Some(product.to_string()),
+        version: Some(version.to_string()),
+        banner: None,
+        transport: "tcp",
+    }
+}
+
+fn ids(feed: &CveFeed, service: &ServiceInfo) -> Vec<String> {
+    feed.lookup(service).into_iter().map(|cve| cve.id).collect()
+}
+
+#[test]
+fn test_exact_versions_and_ranges_match() {
+    let feed = CveFeed::parse(FEED).unwrap();
+    assert_eq!(feed.len(), 3);
+
+    // Highest score first
+    assert_eq!(
+        ids(&feed, &service("vsftpd", "2.3.4")),
+        ["CVE-2011-2523", "CVE-2015-1419"]
+    );
+    assert_eq!(ids(&feed, &service("vsftpd", "3.0.2")), ["CVE-2015-1419"]);
+    assert!(ids(&feed, &service("vsftpd", "3.0.3")).is_empty());
+    assert_eq!(ids(&feed, &service("OpenSSH", "8.9p1")), ["CVE-2023-38408"]);
+    assert_eq!(ids(&feed, &service("OpenSSH", "9.3p1")), ["CVE-2023-38408"]);
+    assert!(ids(&feed, &service("OpenSSH", "9.3.1")).is_empty());
+    assert!(ids(&feed, &service("ProFTPD", "1.3.3c")).is_empty());
+}
+
+#[test]
+fn test_matches_carry_scores_and_summaries() {
+    let feed = CveFeed::parse(FEED).unwrap();
+    let matches = feed.lookup(&service("vsftpd", "2.0.5"));
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].id, "CVE-2015-1419");
+    assert_eq!(matches[0].cvss, Some(5.0));
+    assert_eq!(matches[0].severity.as_deref(), Some("MEDIUM"));
+    assert!(matches[0].summary.contains("deny_file"));
+}
+
+#[test]
+fn test_unversioned_services_match_nothing() {
+    let feed = CveFeed::parse(FEED).unwrap();
+    let mut unversioned = service("vsftpd", "");
+    unversioned.version = None;
+    assert!(feed.lookup(&unversioned).is_empty());
+    assert!(CveFeed::parse("{\"message\": \"rate limited\"}").is_err());
+}
+
+#[test]
+fn test_version_ordering() {
+    assert_eq!(vuln::compare_versions("1.3.3", "1.3.3c"), Ordering::Less);
+    assert_eq!(vuln::compare_versions("1.3.3c", "1.3.10"), Ordering::Less);
+    assert_eq!(vuln::compare_versions("8.9p1", "8.9"), Ordering::Greater);
+    assert_eq!(vuln::compare_versions("2.3.4", "2.3.4"), Ordering::Equal);
+    assert_eq!(
+        vuln::nvd_url("Pure-FTPd"),
+        "https://services.nvd.nist.gov/rest/json/cves/2.0?keywordSearch=Pure+FTPd"
+    );
+}
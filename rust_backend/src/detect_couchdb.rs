@@ -0,0 +1,99 @@
+use crate::detect_http;
+use crate::scanners::service_detection::{DetectError, Detection};
+use std::net::IpAddr;
+
+pub const COUCHDB_PORT: u16 = 5984;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CouchDbDetection {
+    pub detected: bool,
+    pub version: Option<String>,
+    /// Whether `/_all_dbs` refused to list databases without credentials
+    pub auth_required: bool,
+    /// Databases listed by `/_all_dbs` when it was readable
+    pub databases: Option<usize>,
+    pub error: Option<DetectError>,
+}
+
+impl CouchDbDetection {
+    pub fn anonymous_read(&self) -> bool {
+        self.databases.is_some()
+    }
+
+    pub fn summary(&self) -> String {
+        let access = match (self.databases, self.auth_required) {
+            (Some(count), _) => format!("no authentication, {} database(s) listed", count),
+            (None, true) => "authentication required".to_string(),
+            (None, false) => "database listing unavailable".to_string(),
+        };
+        match &self.version {
+            Some(version) => format!("CouchDB {} ({})", version, access),
+            None => format!("CouchDB ({})", access),
+        }
+    }
+}
+
+impl From<CouchDbDetection> for Detection {
+    fn from(couchdb: CouchDbDetection) -> Self {
+        Detection::new(couchdb.detected, couchdb.summary(), couchdb.error)
+    }
+}
+
+/// Version from the welcome document CouchDB serves on `GET /`.
+pub fn parse_welcome(response: &str) -> Option<Option<String>> {
+    let (status, body) = detect_http::split_response(response)?;
+    if status != 200 || detect_http::json_string(body, "couchdb").is_none() {
+        return None;
+    }
+    Some(detect_http::json_string(body, "version"))
+}
+
+/// Number of names in a `/_all_dbs` JSON array.
+pub fn count_databases(body: &str) -> Option<usize> {
+    let list = body.trim().strip_prefix('[')?.strip_suffix(']')?.trim();
+    if list.is_empty() {
+        return Some(0);
+    }
+    Some(list.split(',').count())
+}
+
+/// Sends `GET /` for the version, then `GET /_all_dbs` to see whether data
+/// is readable without credentials.
+pub async fn detect(ip: IpAddr, port: u16) -> CouchDbDetection {
+    let mut detection = CouchDbDetection {
+        detected: false,
+        version: None,
+        auth_required: false,
+        databases: None,
+        error: None,
+    };
+    let response = match detect_http::fetch(ip, port, "/").await {
+        Ok(response) => response,
+        Err(e) => {
+            detection.error = Some(e);
+            return detection;
+        }
+    };
+    match detect_http::split_response(&response) {
+        Some((401, _)) if response.contains("\"unauthorized\"") => {
+            detection.detected = true;
+            detection.auth_required = true;
+            return detection;
+        }
+        _ => {}
+    }
+    let Some(version) = parse_welcome(&response) else {
+        detection.error = Some(DetectError::Mismatch("Not a CouchDB server".to_string()));
+        return detection;
+    };
+    detection.detected = true;
+    detection.version = version;
+    if let Ok(listing) = detect_http::fetch(ip, port, "/_all_dbs").await {
+        match detect_http::split_response(&listing) {
+            Some((200, body)) => detection.databases = count_databases(body),
+            Some((401 | 403, _)) => detection.auth_required = true,
+            _ => {}
+        }
+    }
+    detection
+}
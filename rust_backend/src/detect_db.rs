@@ -0,0 +1,499 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const MYSQL_PORT: u16 = 3306;
+pub const POSTGRES_PORT: u16 = 5432;
+pub const REDIS_PORT: u16 = 6379;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Replies larger than this are not read
+const MAX_REPLY: usize = 64 * 1024;
+
+const MYSQL_PROTOCOL_V10: u8 = 10;
+const MYSQL_ERROR_PACKET: u8 = 0xff;
+/// Request code of a PostgreSQL SSLRequest (1234.5679)
+const POSTGRES_SSL_REQUEST: i32 = 80_877_103;
+/// Protocol 3.0 in a StartupMessage
+const POSTGRES_PROTOCOL_V3: i32 = 196_608;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MysqlDetection {
+    pub detected: bool,
+    /// Server version from the greeting, e.g. "8.0.36" or "10.11.6-MariaDB"
+    pub version: Option<String>,
+    /// Default authentication plugin, e.g. "caching_sha2_password"
+    pub auth_plugin: Option<String>,
+    /// Why the server refused the connection before the handshake, e.g.
+    /// "Host '10.0.0.9' is not allowed to connect to this MySQL server"
+    pub refused: Option<String>,
+    pub error: Option<DetectError>,
+}
+
+impl MysqlDetection {
+    /// A server that sent a greeting, before it is parsed
+    fn answered() -> Self {
+        Self {
+            detected: true,
+            version: None,
+            auth_plugin: None,
+            refused: None,
+            error: None,
+        }
+    }
+
+    fn failed(error: DetectError) -> Self {
+        Self {
+            detected: false,
+            error: Some(error),
+            ..Self::answered()
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let product = match &self.version {
+            Some(version) if version.contains("MariaDB") => "MariaDB",
+            _ => "MySQL",
+        };
+        let mut summary = product.to_string();
+        if let Some(version) = &self.version {
+            // MariaDB prefixes "5.5.5-" for old clients: "5.5.5-10.11.6-MariaDB"
+            let version = version.strip_prefix("5.5.5-").unwrap_or(version);
+            summary.push_str(&format!(" {}", version.trim_end_matches("-MariaDB")));
+        }
+        match (&self.refused, &self.auth_plugin) {
+            (Some(reason), _) => summary.push_str(&format!(" (refused: {})", reason)),
+            (None, Some(plugin)) => summary.push_str(&format!(" (auth: {})", plugin)),
+            (None, None) => {}
+        }
+        summary
+    }
+}
+
+impl From<MysqlDetection> for Detection {
+    fn from(mysql: MysqlDetection) -> Self {
+        Detection::new(mysql.detected, mysql.summary(), mysql.error)
+    }
+}
+
+/// Parses the first packet a MySQL server sends: the v10 handshake, or an
+/// error packet when it refuses the client outright.
+pub fn parse_mysql_greeting(packet: &[u8]) -> Option<MysqlDetection> {
+    let len = u32::from_le_bytes([*packet.first()?, *packet.get(1)?, *packet.get(2)?, 0]);
+    let payload = packet.get(4..4 + len as usize)?;
+    let mut detection = MysqlDetection::answered();
+    match *payload.first()? {
+        MYSQL_ERROR_PACKET => {
+            let message = payload.get(3..)?;
+            detection.refused = Some(String::from_utf8_lossy(message).trim().to_string());
+            Some(detection)
+        }
+        MYSQL_PROTOCOL_V10 => {
+            let rest = &payload[1..];
+            let version_end = rest.iter().position(|b| *b == 0)?;
+            let version = std::str::from_utf8(&rest[..version_end]).ok()?;
+            if version.is_empty() || !version.starts_with(|c: char| c.is_ascii_digit()) {
+                return None;
+            }
+            detection.version = Some(version.to_string());
+            // connection id (4), auth data part 1 (8), filler (1), capabilities (2),
+            // charset (1), status (2), capabilities (2), auth data length (1), reserved (10)
+            let fixed = rest.get(version_end + 1 + 31..);
+            let auth_data_len = rest.get(version_end + 1 + 20).copied().unwrap_or(0);
+            if let Some(tail) = fixed {
+                let part2 = (auth_data_len as usize).saturating_sub(8).max(13);
+                if let Some(name) = tail.get(part2..) {
+                    let end = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+                    let plugin = String::from_utf8_lossy(&name[..end]).to_string();
+                    detection.auth_plugin = Some(plugin).filter(|p| !p.is_empty());
+                }
+            }
+            Some(detection)
+        }
+        _ => None,
+    }
+}
+
+/// Reads whatever the server sends first, up to `MAX_REPLY` bytes.
+async fn read_reply(stream: &mut TcpStream) -> Result<Vec<u8>, DetectError> {
+    let mut buf = vec![0u8; MAX_REPLY];
+    match tokio::time::timeout(PROBE_TIMEOUT, stream.read(&mut buf)).await {
+        Ok(Ok(0)) => Err(DetectError::Mismatch("Connection closed".to_string())),
+        Ok(Ok(n)) => {
+            buf.truncate(n);
+            Ok(buf)
+        }
+        Ok(Err(e)) => Err(DetectError::Connect(format!("Read failed: {}", e))),
+        Err(_) => Err(DetectError::Timeout("No response".to_string())),
+    }
+}
+
+async fn connect(ip: IpAddr, port: u16) -> Result<TcpStream, DetectError> {
+    match tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    {
+        Ok(Ok(stream)) => Ok(stream),
+        _ => Err(DetectError::Connect("Connection failed".to_string())),
+    }
+}
+
+/// Reads the greeting MySQL and MariaDB send on connect; nothing is sent.
+pub async fn detect_mysql(ip: IpAddr, port: u16) -> MysqlDetection {
+    let mut stream = match connect(ip, port).await {
+        Ok(stream) => stream,
+        Err(e) => return MysqlDetection::failed(e),
+    };
+    match read_reply(&mut stream).await {
+        Ok(greeting) => parse_mysql_greeting(&greeting).unwrap_or_else(|| {
+            MysqlDetection::failed(DetectError::Mismatch("Not a MySQL greeting".to_string()))
+        }),
+        Err(e) => MysqlDetection::failed(e),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostgresDetection {
+    pub detected: bool,
+    /// Whether the server accepted an SSLRequest; `None` if it was not answered
+    pub ssl: Option<bool>,
+    /// Authentication the server asked for, e.g. "SCRAM-SHA-256", or "none"
+    /// when it let the probe in without credentials
+    pub auth: Option<String>,
+    /// `server_version`, reported only once a session is established
+    pub version: Option<String>,
+    /// Error the server answered the startup with, e.g. "no pg_hba.conf entry for host ..."
+    pub refused: Option<String>,
+    pub error: Option<DetectError>,
+}
+
+impl PostgresDetection {
+    /// A server that answered, before anything is known about it
+    fn answered(ssl: Option<bool>) -> Self {
+        Self {
+            detected: true,
+            ssl,
+            auth: None,
+            version: None,
+            refused: None,
+            error: None,
+        }
+    }
+
+    fn failed(error: DetectError) -> Self {
+        Self {
+            detected: false,
+            error: Some(error),
+            ..Self::answered(None)
+        }
+    }
+
+    /// Whether the server let the probe in without a password.
+    pub fn trust_auth(&self) -> bool {
+        self.auth.as_deref() == Some("none")
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        match self.ssl {
+            Some(true) => parts.push("SSL supported".to_string()),
+            Some(false) => parts.push("no SSL".to_string()),
+            None => {}
+        }
+        if let Some(auth) = &self.auth {
+            parts.push(match auth.as_str() {
+                "none" => "no authentication".to_string(),
+                auth => format!("auth: {}", auth),
+            });
+        }
+        if let Some(reason) = &self.refused {
+            parts.push(format!("refused: {}", reason));
+        }
+        let version = self
+            .version
+            .as_ref()
+            .map(|version| format!(" {}", version))
+            .unwrap_or_default();
+        if parts.is_empty() {
+            return format!("PostgreSQL{}", version);
+        }
+        format!("PostgreSQL{} ({})", version, parts.join(", "))
+    }
+}
+
+impl From<PostgresDetection> for Detection {
+    fn from(postgres: PostgresDetection) -> Self {
+        Detection::new(postgres.detected, postgres.summary(), postgres.error)
+    }
+}
+
+/// SSLRequest: asks whether the server speaks TLS, answered with one byte.
+pub fn build_ssl_request() -> Vec<u8> {
+    let mut request = 8i32.to_be_bytes().to_vec();
+    request.extend_from_slice(&POSTGRES_SSL_REQUEST.to_be_bytes());
+    request
+}
+
+/// Protocol 3.0 StartupMessage for `user` on `database`.
+pub fn build_startup_message(user: &str, database: &str) -> Vec<u8> {
+    let mut body = POSTGRES_PROTOCOL_V3.to_be_bytes().to_vec();
+    for (key, value) in [("user", user), ("database", database)] {
+        body.extend_from_slice(key.as_bytes());
+        body.push(0);
+        body.extend_from_slice(value.as_bytes());
+        body.push(0);
+    }
+    body.push(0);
+    let mut message = ((body.len() + 4) as i32).to_be_bytes().to_vec();
+    message.extend(body);
+    message
+}
+
+/// Reads the messages a server answers a StartupMessage with: an
+/// authentication request, an error, or (without authentication) the
+/// session parameters including `server_version`.
+pub fn parse_startup_response(reply: &[u8]) -> Option<PostgresDetection> {
+    let mut detection = PostgresDetection::answered(None);
+    let mut parsed = false;
+    let mut rest = reply;
+    while let Some((&kind, tail)) = rest.split_first() {
+        let len = i32::from_be_bytes(tail.get(..4)?.try_into().ok()?) as usize;
+        let Some(body) = tail.get(4..len.max(4)) else {
+            break; // truncated by the read; keep what was parsed
+        };
+        match kind {
+            b'R' => {
+                let code = i32::from_be_bytes(body.get(..4)?.try_into().ok()?);
+                detection.auth = Some(match code {
+                    0 => "none".to_string(),
+                    3 => "cleartext password".to_string(),
+                    5 => "MD5".to_string(),
+                    7 => "GSSAPI".to_string(),
+                    9 => "SSPI".to_string(),
+                    10 => body[4..]
+                        .split(|b| *b == 0)
+                        .filter(|name| !name.is_empty())
+                        .map(|name| String::from_utf8_lossy(name).to_string())
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                    other => format!("method {}", other),
+                });
+            }
+            b'S' => {
+                let mut fields = body.split(|b| *b == 0);
+                if fields.next() == Some(b"server_version") {
+                    detection.version = fields
+                        .next()
+                        .map(|v| String::from_utf8_lossy(v).to_string());
+                }
+            }
+            b'E' => {
+                detection.refused = body
+                    .split(|b| *b == 0)
+                    .find_map(|field| field.strip_prefix(b"M"))
+                    .map(|message| String::from_utf8_lossy(message).to_string());
+            }
+            b'K' | b'Z' | b'N' => {}
+            _ => return None,
+        }
+        parsed = true;
+        rest = &tail[len.max(4)..];
+    }
+    parsed.then_some(detection)
+}
+
+/// Asks for SSL, then sends a StartupMessage for a user that should not
+/// exist. The server names the authentication it wants (or refuses the
+/// host) before any credentials are involved; the probe never logs in.
+pub async fn detect_postgres(ip: IpAddr, port: u16) -> PostgresDetection {
+    let mut stream = match connect(ip, port).await {
+        Ok(stream) => stream,
+        Err(e) => return PostgresDetection::failed(e),
+    };
+    if let Err(e) = stream.write_all(&build_ssl_request()).await {
+        return PostgresDetection::failed(DetectError::Connect(format!("Write failed: {}", e)));
+    }
+    let ssl = match read_reply(&mut stream).await {
+        Ok(reply) if reply == b"S" => true,
+        Ok(reply) if reply == b"N" => false,
+        Ok(_) => {
+            return PostgresDetection::failed(DetectError::Mismatch(
+                "Not a PostgreSQL response".to_string(),
+            ));
+        }
+        Err(e) => return PostgresDetection::failed(e),
+    };
+    // After 'S' the server expects a TLS handshake, so start over in plaintext
+    if ssl {
+        stream = match connect(ip, port).await {
+            Ok(stream) => stream,
+            Err(_) => return PostgresDetection::answered(Some(true)),
+        };
+    }
+    let startup = build_startup_message("netscan", "netscan");
+    let reply = match stream.write_all(&startup).await {
+        Ok(()) => read_reply(&mut stream).await,
+        Err(e) => Err(DetectError::Connect(format!("Write failed: {}", e))),
+    };
+    // The SSL answer already identified the server; the startup reply adds detail
+    let mut detection = reply
+        .ok()
+        .and_then(|reply| parse_startup_response(&reply))
+        .unwrap_or_else(|| PostgresDetection::answered(None));
+    detection.ssl = Some(ssl);
+    detection
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedisDetection {
+    pub detected: bool,
+    /// `redis_version` from INFO
+    pub version: Option<String>,
+    /// `redis_mode`: standalone, cluster or sentinel
+    pub mode: Option<String>,
+    /// `os` from INFO, e.g. "Linux 6.1.0-18-amd64 x86_64"
+    pub os: Option<String>,
+    /// Whether PING was refused with NOAUTH
+    pub auth_required: bool,
+    /// Whether the server refused remote clients in protected mode
+    pub protected_mode: bool,
+    pub error: Option<DetectError>,
+}
+
+impl RedisDetection {
+    /// A server that answered PING, before anything else is known about it
+    fn answered() -> Self {
+        Self {
+            detected: true,
+            version: None,
+            mode: None,
+            os: None,
+            auth_required: false,
+            protected_mode: false,
+            error: None,
+        }
+    }
+
+    fn failed(error: DetectError) -> Self {
+        Self {
+            detected: false,
+            error: Some(error),
+            ..Self::answered()
+        }
+    }
+
+    /// Whether commands ran without credentials.
+    pub fn anonymous_access(&self) -> bool {
+        self.detected && !self.auth_required && !self.protected_mode
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(mode) = &self.mode {
+            parts.push(mode.clone());
+        }
+        parts.push(
+            if self.protected_mode {
+                "protected mode"
+            } else if self.auth_required {
+                "authentication required"
+            } else {
+                "no authentication"
+            }
+            .to_string(),
+        );
+        match &self.version {
+            Some(version) => format!("Redis {} ({})", version, parts.join(", ")),
+            None => format!("Redis ({})", parts.join(", ")),
+        }
+    }
+}
+
+impl From<RedisDetection> for Detection {
+    fn from(redis: RedisDetection) -> Self {
+        Detection::new(redis.detected, redis.summary(), redis.error)
+    }
+}
+
+/// `command` as a RESP array of bulk strings.
+pub fn build_redis_command(command: &[&str]) -> Vec<u8> {
+    let mut request = format!("*{}\r\n", command.len());
+    for arg in command {
+        request.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
+    }
+    request.into_bytes()
+}
+
+/// Reads a PING reply: `+PONG`, or an error such as `-NOAUTH ...`.
+pub fn parse_ping_reply(reply: &[u8]) -> Option<RedisDetection> {
+    let line = std::str::from_utf8(reply).ok()?.lines().next()?;
+    let mut detection = RedisDetection::answered();
+    if line == "+PONG" {
+        return Some(detection);
+    }
+    let error = line.strip_prefix('-')?;
+    if error.starts_with("NOAUTH") || error.starts_with("WRONGPASS") {
+        detection.auth_required = true;
+    } else if error.starts_with("DENIED") {
+        detection.protected_mode = true;
+    } else {
+        return None;
+    }
+    Some(detection)
+}
+
+/// Fills the version, mode and OS from an `INFO server` reply.
+pub fn parse_info(info: &str, detection: &mut RedisDetection) {
+    for line in info.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        match key {
+            "redis_version" => detection.version = value,
+            "redis_mode" => detection.mode = value,
+            "os" => detection.os = value,
+            _ => {}
+        }
+    }
+}
+
+/// Sends PING, and INFO server when the server answers without credentials.
+/// Both are read-only.
+pub async fn detect_redis(ip: IpAddr, port: u16) -> RedisDetection {
+    let mut stream = match connect(ip, port).await {
+        Ok(stream) => stream,
+        Err(e) => return RedisDetection::failed(e),
+    };
+    if let Err(e) = stream.write_all(&build_redis_command(&["PING"])).await {
+        return RedisDetection::failed(DetectError::Connect(format!("Write failed: {}", e)));
+    }
+    let mut detection = match read_reply(&mut stream).await {
+        Ok(reply) => match parse_ping_reply(&reply) {
+            Some(detection) => detection,
+            None => {
+                return RedisDetection::failed(DetectError::Mismatch(
+                    "Not a Redis reply".to_string(),
+                ));
+            }
+        },
+        Err(e) => return RedisDetection::failed(e),
+    };
+    if !detection.anonymous_access() {
+        return detection;
+    }
+    if stream
+        .write_all(&build_redis_command(&["INFO", "server"]))
+        .await
+        .is_ok()
+        && let Ok(info) = read_reply(&mut stream).await
+    {
+        parse_info(&String::from_utf8_lossy(&info), &mut detection);
+    }
+    detection
+}
@@ -1,20 +1,28 @@
-use std::net::{Ipv4Addr, SocketAddr};
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
 use std::time::Duration;
 use tokio::net::UdpSocket;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DnsDetection {
     pub detected: bool,
-    pub error: Option<String>,
+    pub error: Option<DetectError>,
 }
 
-pub async fn detect(ip: Ipv4Addr, port: u16) -> DnsDetection {
-    let socket = match UdpSocket::bind("0.0.0.0:0").await {
+impl From<DnsDetection> for Detection {
+    fn from(dns: DnsDetection) -> Self {
+        Detection::new(dns.detected, "DNS".to_string(), dns.error)
+    }
+}
+
+pub async fn detect(ip: IpAddr, port: u16) -> DnsDetection {
+    let socket = match UdpSocket::bind(sockets::local_bind_addr(ip)).await {
         Ok(s) => s,
         Err(e) => {
             return DnsDetection {
                 detected: false,
-                error: Some(format!("Bind failed: {e}")),
+                error: Some(DetectError::Connect(format!("Bind failed: {}", e))),
             }
         }
     };
@@ -33,14 +41,11 @@ pub async fn detect(ip: Ipv4Addr, port: u16) -> DnsDetection {
         0x00, 0x01, // QCLASS=IN
     ];
     let _ = socket
-        .send_to(&query, SocketAddr::new(ip.into(), port))
+        .send_to(&query, sockets::socket_addr(ip, port))
         .await;
     let mut buf = [0u8; 512];
-    if let Some(Ok((n, _))) =
-        tokio::time::timeout(Duration::from_secs(2), socket.recv_from(&mut buf))
-            .await
-            .ok()
-    {
+    let reply = tokio::time::timeout(Duration::from_secs(2), socket.recv_from(&mut buf)).await;
+    if let Ok(Ok((n, _))) = reply {
         if n > 0 {
             return DnsDetection {
                 detected: true,
@@ -50,7 +55,7 @@ pub async fn detect(ip: Ipv4Addr, port: u16) -> DnsDetection {
     }
     DnsDetection {
         detected: false,
-        error: Some("No DNS response".to_string()),
+        error: Some(DetectError::unrecognized(reply.is_err(), "No DNS response")),
     }
 }
 
@@ -61,7 +66,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_detect_dns_on_localhost() {
-        let ip = Ipv4Addr::LOCALHOST;
+        let ip = Ipv4Addr::LOCALHOST.into();
         let port = 53;
         let result = detect(ip, port).await;
         assert!(result.detected || result.error.is_some());
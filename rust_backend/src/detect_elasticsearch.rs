@@ -0,0 +1,101 @@
+use crate::detect_http;
+use crate::scanners::service_detection::{DetectError, Detection};
+use std::net::IpAddr;
+
+pub const ELASTICSEARCH_PORT: u16 = 9200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElasticsearchDetection {
+    pub detected: bool,
+    pub cluster_name: Option<String>,
+    pub version: Option<String>,
+    /// "opensearch" when the node reports that distribution
+    pub distribution: Option<String>,
+    /// Whether the root endpoint asked for credentials
+    pub auth_required: bool,
+    pub error: Option<DetectError>,
+}
+
+impl ElasticsearchDetection {
+    /// Whether the cluster answers without credentials
+    pub fn anonymous_read(&self) -> bool {
+        self.detected && !self.auth_required
+    }
+
+    pub fn summary(&self) -> String {
+        let product = match self.distribution.as_deref() {
+            Some("opensearch") => "OpenSearch",
+            _ => "Elasticsearch",
+        };
+        let mut parts = Vec::new();
+        if let Some(cluster) = &self.cluster_name {
+            parts.push(format!("cluster {}", cluster));
+        }
+        parts.push(if self.auth_required {
+            "authentication required".to_string()
+        } else {
+            "no authentication".to_string()
+        });
+        match &self.version {
+            Some(version) => format!("{} {} ({})", product, version, parts.join(", ")),
+            None => format!("{} ({})", product, parts.join(", ")),
+        }
+    }
+}
+
+impl From<ElasticsearchDetection> for Detection {
+    fn from(elasticsearch: ElasticsearchDetection) -> Self {
+        Detection::new(elasticsearch.detected, elasticsearch.summary(), elasticsearch.error)
+    }
+}
+
+/// Reads the root endpoint (`GET /`) of an HTTP response from a node.
+pub fn parse_root(response: &str) -> Option<ElasticsearchDetection> {
+    let (status, body) = detect_http::split_response(response)?;
+    let lower = response.to_ascii_lowercase();
+    if status == 401 {
+        // security plugin challenge, e.g. Basic realm="security"
+        if !lower.contains("realm=\"security\"") && !lower.contains("elastic") {
+            return None;
+        }
+        return Some(ElasticsearchDetection {
+            detected: true,
+            cluster_name: None,
+            version: None,
+            distribution: None,
+            auth_required: true,
+            error: None,
+        });
+    }
+    if status != 200 || !(body.contains("\"cluster_name\"") && body.contains("\"version\"")) {
+        return None;
+    }
+    Some(ElasticsearchDetection {
+        detected: true,
+        cluster_name: detect_http::json_string(body, "cluster_name"),
+        version: detect_http::json_string(body, "number"),
+        distribution: detect_http::json_string(body, "distribution"),
+        auth_required: false,
+        error: None,
+    })
+}
+
+/// Sends `GET /` and reports the version and whether credentials are required.
+pub async fn detect(ip: IpAddr, port: u16) -> ElasticsearchDetection {
+    let failed = |error: DetectError| ElasticsearchDetection {
+        detected: false,
+        cluster_name: None,
+        version: None,
+        distribution: None,
+        auth_required: false,
+        error: Some(error),
+    };
+    match detect_http::fetch(ip, port, "/").await {
+        Ok(response) => {
+            parse_root(&response).unwrap_or_else(|| {
+                failed(DetectError::Mismatch("Not an Elasticsearch node".to_string()))
+            })
+        }
+        Err(e) => failed(e),
+    }
+}
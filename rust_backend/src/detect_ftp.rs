@@ -1,24 +1,31 @@
-use std::net::Ipv4Addr;
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
 use std::time::Duration;
 use tokio::net::TcpStream;
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FtpDetection {
     pub detected: bool,
     pub banner: Option<String>,
-    pub error: Option<String>,
+    pub error: Option<DetectError>,
 }
 
-pub async fn detect(ip: Ipv4Addr, port: u16) -> FtpDetection {
-    let addr = (ip, port);
+impl From<FtpDetection> for Detection {
+    fn from(ftp: FtpDetection) -> Self {
+        Detection::new(ftp.detected, "FTP".to_string(), ftp.error).with_banner(ftp.banner)
+    }
+}
+
+pub async fn detect(ip: IpAddr, port: u16) -> FtpDetection {
+    let addr = sockets::socket_addr(ip, port);
     if let Ok(Ok(mut stream)) =
         tokio::time::timeout(Duration::from_secs(5), TcpStream::connect(addr)).await
     {
         let mut buf = vec![0u8; 256];
-        if let Ok(Ok(n)) =
-            tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await
-        {
+        let read = tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await;
+        if let Ok(Ok(n)) = read {
             let banner = String::from_utf8_lossy(&buf[..n]).to_string();
             if banner.contains("FTP") {
                 return FtpDetection {
@@ -31,13 +38,37 @@ pub async fn detect(ip: Ipv4Addr, port: u16) -> FtpDetection {
         FtpDetection {
             detected: false,
             banner: None,
-            error: Some("No FTP banner".to_string()),
+            error: Some(DetectError::unrecognized(read.is_err(), "No FTP banner")),
         }
     } else {
         FtpDetection {
             detected: false,
             banner: None,
-            error: Some("Connection failed".to_string()),
+            error: Some(DetectError::Connect("Connection failed".to_string())),
         }
     }
+}
+
+/// Asks the server to upgrade with `AUTH TLS` (RFC 4217) and reports whether
+/// it agreed. The connection is dropped before any handshake.
+pub async fn supports_auth_tls(ip: IpAddr, port: u16) -> Result<bool, String> {
+    let Ok(Ok(mut stream)) =
+        tokio::time::timeout(Duration::from_secs(5), TcpStream::connect(sockets::socket_addr(ip, port))).await
+    else {
+        return Err("Connection failed".to_string());
+    };
+    let mut buf = vec![0u8; 512];
+    match tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await {
+        Ok(Ok(n)) if String::from_utf8_lossy(&buf[..n]).starts_with("220") => {}
+        _ => return Err("No FTP greeting".to_string()),
+    }
+    stream
+        .write_all(b"AUTH TLS\r\n")
+        .await
+        .map_err(|e| format!("Write failed: {}", e))?;
+    match tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await {
+        Ok(Ok(n)) => Ok(String::from_utf8_lossy(&buf[..n]).starts_with("234")),
+        Ok(Err(e)) => Err(format!("Read failed: {}", e)),
+        Err(_) => Err("No reply to AUTH TLS".to_string()),
+    }
 }
\ No newline at end of file
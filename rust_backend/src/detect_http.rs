@@ -1,4 +1,6 @@
-use std::net::Ipv4Addr;
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
 use std::time::Duration;
 use tokio::net::TcpStream;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -7,19 +9,24 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 pub struct HttpDetection {
     pub detected: bool,
     pub banner: Option<String>,
-    pub error: Option<String>,
+    pub error: Option<DetectError>,
 }
 
-pub async fn detect(ip: Ipv4Addr, port: u16) -> HttpDetection {
-    let addr = (ip, port);
+impl From<HttpDetection> for Detection {
+    fn from(http: HttpDetection) -> Self {
+        Detection::new(http.detected, "HTTP".to_string(), http.error)
+    }
+}
+
+pub async fn detect(ip: IpAddr, port: u16) -> HttpDetection {
+    let addr = sockets::socket_addr(ip, port);
     if let Ok(Ok(mut stream)) =
         tokio::time::timeout(Duration::from_secs(5), TcpStream::connect(addr)).await
     {
         let _ = stream.write_all(b"HEAD / HTTP/1.0\r\n\r\n").await;
         let mut buf = vec![0u8; 512];
-        if let Ok(Ok(n)) =
-            tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await
-        {
+        let read = tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await;
+        if let Ok(Ok(n)) = read {
             let banner = String::from_utf8_lossy(&buf[..n]).to_string();
             if banner.contains("HTTP/1.0") || banner.contains("HTTP/1.1") {
                 return HttpDetection {
@@ -32,13 +39,137 @@ pub async fn detect(ip: Ipv4Addr, port: u16) -> HttpDetection {
         HttpDetection {
             detected: false,
             banner: None,
-            error: Some("No HTTP banner".to_string()),
+            error: Some(DetectError::unrecognized(read.is_err(), "No HTTP banner")),
         }
     } else {
         HttpDetection {
             detected: false,
             banner: None,
-            error: Some("Connection failed".to_string()),
+            error: Some(DetectError::Connect("Connection failed".to_string())),
+        }
+    }
+}
+
+/// Words in a page title that suggest a management interface
+const ADMIN_TITLE_WORDS: &[&str] = &[
+    "admin", "login", "log in", "sign in", "management", "configuration", "router", "dashboard",
+];
+
+/// Fetches `path` with a plain GET and returns the raw response (headers and
+/// the start of the body).
+pub async fn fetch(ip: IpAddr, port: u16, path: &str) -> Result<String, DetectError> {
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: netscan\r\n\r\n",
+        path,
+        sockets::uri_host(ip)
+    );
+    exchange(ip, port, &request).await
+}
+
+/// POSTs `body` to `path` and returns the raw response, like `fetch`.
+pub async fn post(
+    ip: IpAddr,
+    port: u16,
+    path: &str,
+    content_type: &str,
+    body: &str,
+) -> Result<String, DetectError> {
+    let request = format!(
+        "POST {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: netscan\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n{}",
+        path,
+        sockets::uri_host(ip),
+        content_type,
+        body.len(),
+        body
+    );
+    exchange(ip, port, &request).await
+}
+
+async fn exchange(ip: IpAddr, port: u16, request: &str) -> Result<String, DetectError> {
+    let Ok(Ok(mut stream)) =
+        tokio::time::timeout(Duration::from_secs(5), TcpStream::connect(sockets::socket_addr(ip, port))).await
+    else {
+        return Err(DetectError::Connect("Connection failed".to_string()));
+    };
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| DetectError::Connect(format!("Write failed: {}", e)))?;
+    let mut response = Vec::new();
+    let mut buf = vec![0u8; 4096];
+    let mut timed_out = false;
+    while response.len() < 16384 {
+        match tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await {
+            Ok(Ok(0)) => break,
+            Ok(Ok(n)) => response.extend_from_slice(&buf[..n]),
+            Ok(Err(e)) => return Err(DetectError::Connect(format!("Read failed: {}", e))),
+            Err(_) => {
+                timed_out = true;
+                break;
+            }
         }
     }
-}
\ No newline at end of file
+    if response.is_empty() {
+        return Err(DetectError::unrecognized(timed_out, "No HTTP response"));
+    }
+    Ok(String::from_utf8_lossy(&response).to_string())
+}
+
+/// Why a response looks like an administration panel, if it does.
+pub fn admin_panel_hint(response: &str) -> Option<String> {
+    let lower = response.to_lowercase();
+    if let Some(line) = lower.lines().find(|l| l.starts_with("www-authenticate:")) {
+        return Some(format!("asks for credentials ({})", line.trim()));
+    }
+    if lower.contains("type=\"password\"") || lower.contains("type='password'") {
+        return Some("serves a password form".to_string());
+    }
+    let title = lower
+        .split_once("<title>")
+        .and_then(|(_, rest)| rest.split_once("</title>"))
+        .map(|(title, _)| title.trim().to_string())?;
+    ADMIN_TITLE_WORDS
+        .iter()
+        .any(|word| title.contains(word))
+        .then(|| format!("page title \"{}\"", title))
+}
+/// Status code and body of a raw HTTP response.
+pub fn split_response(response: &str) -> Option<(u16, &str)> {
+    let status = response
+        .strip_prefix("HTTP/1.")?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()?;
+    let body = response
+        .split_once("\r\n\r\n")
+        .map(|(_, body)| body)
+        .unwrap_or_default();
+    Some((status, body))
+}
+
+/// Value of the first `"key": "value"` string member in a JSON body.
+pub fn json_string(body: &str, key: &str) -> Option<String> {
+    let needle = format!("\"{}\"", key);
+    let mut rest = body;
+    while let Some(start) = rest.find(&needle) {
+        rest = &rest[start + needle.len()..];
+        let Some(value) = rest.trim_start().strip_prefix(':') else {
+            continue;
+        };
+        let Some(value) = value.trim_start().strip_prefix('"') else {
+            continue;
+        };
+        let mut out = String::new();
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Some(out),
+                '\\' => out.extend(chars.next()),
+                c => out.push(c),
+            }
+        }
+        return None;
+    }
+    None
+}
@@ -0,0 +1,140 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const IMAP_PORT: u16 = 143;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImapDetection {
+    pub detected: bool,
+    /// Greeting text after the status, e.g. "Dovecot ready."
+    pub banner: Option<String>,
+    /// Capabilities from the greeting or a CAPABILITY command, e.g.
+    /// ["IMAP4rev1", "STARTTLS", "LOGINDISABLED"]
+    pub capabilities: Vec<String>,
+    /// Whether the server greeted with PREAUTH, i.e. logged the client in unasked
+    pub preauth: bool,
+    pub error: Option<DetectError>,
+}
+
+impl ImapDetection {
+    fn failed(error: DetectError) -> Self {
+        Self {
+            detected: false,
+            banner: None,
+            capabilities: Vec::new(),
+            preauth: false,
+            error: Some(error),
+        }
+    }
+
+    fn has(&self, capability: &str) -> bool {
+        self.capabilities
+            .iter()
+            .any(|cap| cap.eq_ignore_ascii_case(capability))
+    }
+
+    pub fn starttls(&self) -> bool {
+        self.has("STARTTLS")
+    }
+
+    /// Whether plaintext LOGIN is refused until TLS is up.
+    pub fn login_disabled(&self) -> bool {
+        self.has("LOGINDISABLED")
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = "IMAP".to_string();
+        if let Some(banner) = self.banner.as_deref().filter(|b| !b.is_empty()) {
+            summary.push_str(&format!(" ({})", banner));
+        }
+        if self.preauth {
+            summary.push_str(", preauthenticated");
+        } else if !self.capabilities.is_empty() && !self.starttls() {
+            summary.push_str(", no STARTTLS");
+        } else if self.starttls() && !self.login_disabled() {
+            summary.push_str(", plaintext login allowed");
+        }
+        summary
+    }
+}
+
+impl From<ImapDetection> for Detection {
+    fn from(imap: ImapDetection) -> Self {
+        Detection::new(imap.detected, imap.summary(), imap.error)
+    }
+}
+
+/// Capability names in a "[CAPABILITY ...]" response code or a
+/// "* CAPABILITY ..." line.
+fn capabilities_in(text: &str) -> Option<Vec<String>> {
+    let list = match text.find("[CAPABILITY ") {
+        Some(start) => {
+            let rest = &text[start + "[CAPABILITY ".len()..];
+            &rest[..rest.find(']')?]
+        }
+        None => text.strip_prefix("* CAPABILITY ")?,
+    };
+    Some(list.split_whitespace().map(String::from).collect())
+}
+
+/// Parses an untagged OK or PREAUTH greeting (RFC 9051 § 7.1).
+pub fn parse_greeting(text: &str) -> Option<ImapDetection> {
+    let line = text.lines().next()?;
+    let (preauth, rest) = if let Some(rest) = line.strip_prefix("* OK") {
+        (false, rest)
+    } else {
+        (true, line.strip_prefix("* PREAUTH")?)
+    };
+    let capabilities = capabilities_in(rest).unwrap_or_default();
+    // The human-readable part follows any response code
+    let banner = match rest.rfind(']') {
+        Some(end) => &rest[end + 1..],
+        None => rest,
+    };
+    Some(ImapDetection {
+        detected: true,
+        banner: Some(banner.trim().to_string()),
+        capabilities,
+        preauth,
+        error: None,
+    })
+}
+
+/// Capabilities from the reply to "a1 CAPABILITY".
+pub fn parse_capability_response(text: &str) -> Vec<String> {
+    text.lines().find_map(capabilities_in).unwrap_or_default()
+}
+
+/// Reads the greeting and, unless it already listed them, asks for the
+/// server's capabilities. Nothing is authenticated.
+pub async fn detect(ip: IpAddr, port: u16) -> ImapDetection {
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return ImapDetection::failed(DetectError::Connect("Connection failed".to_string()));
+    };
+    let mut buf = vec![0u8; 2048];
+    let read = tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await;
+    let greeting = match &read {
+        Ok(Ok(n)) => parse_greeting(&String::from_utf8_lossy(&buf[..*n])),
+        _ => None,
+    };
+    let Some(mut detection) = greeting else {
+        return ImapDetection::failed(DetectError::unrecognized(read.is_err(), "No IMAP greeting"));
+    };
+    if detection.capabilities.is_empty()
+        && stream.write_all(b"a1 CAPABILITY\r\n").await.is_ok()
+        && let Ok(Ok(n)) = tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await
+    {
+        detection.capabilities = parse_capability_response(&String::from_utf8_lossy(&buf[..n]));
+    }
+    let _ = stream.write_all(b"a2 LOGOUT\r\n").await;
+    detection
+}
@@ -0,0 +1,200 @@
+use crate::utils::ber::{self, Tlv};
+use crate::utils::sockets;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const AS_REQ: u8 = 10; // [APPLICATION 10]
+const AS_REP: u8 = 11; // [APPLICATION 11]
+const KRB_ERROR: u8 = 30; // [APPLICATION 30]
+
+const NT_PRINCIPAL: i64 = 1;
+const NT_SRV_INST: i64 = 2;
+const ETYPE_AES256: i64 = 18;
+const ETYPE_AES128: i64 = 17;
+const ETYPE_RC4_HMAC: i64 = 23;
+
+/// Principal the probe asks a ticket for; it should not exist
+pub const PROBE_PRINCIPAL: &str = "netscan-probe";
+
+/// KDC error codes the probe commonly sees (RFC 4120 section 7.5.9)
+pub fn error_name(code: i64) -> &'static str {
+    match code {
+        6 => "KDC_ERR_C_PRINCIPAL_UNKNOWN",
+        14 => "KDC_ERR_ETYPE_NOSUPP",
+        18 => "KDC_ERR_CLIENT_REVOKED",
+        24 => "KDC_ERR_PREAUTH_FAILED",
+        25 => "KDC_ERR_PREAUTH_REQUIRED",
+        37 => "KRB_AP_ERR_SKEW",
+        68 => "KDC_ERR_WRONG_REALM",
+        _ => "KRB_ERROR",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KerberosDetection {
+    pub detected: bool,
+    /// Realm the KDC answered for
+    pub realm: Option<String>,
+    /// KDC clock from the error's stime field
+    pub server_time: Option<DateTime<Utc>>,
+    pub error_code: Option<i64>,
+    /// The KDC issued an AS-REP without pre-authentication (AS-REP roastable)
+    pub as_rep_without_preauth: bool,
+    pub error: Option<String>,
+}
+
+fn principal(name_type: i64, names: &[&str]) -> Vec<u8> {
+    let strings: Vec<Vec<u8>> = names
+        .iter()
+        .map(|n| ber::tlv(ber::TAG_GENERAL_STRING, n.as_bytes()))
+        .collect();
+    ber::sequence(&[
+        ber::constructed(ber::context(0), &[ber::integer(name_type)]),
+        ber::constructed(ber::context(1), &[ber::sequence(&strings)]),
+    ])
+}
+
+/// AS-REQ for `PROBE_PRINCIPAL@realm` with no pre-authentication data.
+pub fn build_as_req(realm: &str, nonce: u32) -> Vec<u8> {
+    let till = ber::tlv(ber::TAG_GENERALIZED_TIME, b"20370913024805Z");
+    let body = ber::sequence(&[
+        // forwardable, renewable, canonicalize
+        ber::constructed(
+            ber::context(0),
+            &[ber::tlv(
+                ber::TAG_BIT_STRING,
+                &[0x00, 0x40, 0x81, 0x00, 0x10],
+            )],
+        ),
+        ber::constructed(
+            ber::context(1),
+            &[principal(NT_PRINCIPAL, &[PROBE_PRINCIPAL])],
+        ),
+        ber::constructed(
+            ber::context(2),
+            &[ber::tlv(ber::TAG_GENERAL_STRING, realm.as_bytes())],
+        ),
+        ber::constructed(
+            ber::context(3),
+            &[principal(NT_SRV_INST, &["krbtgt", realm])],
+        ),
+        ber::constructed(ber::context(5), &[till]),
+        ber::constructed(ber::context(7), &[ber::integer(i64::from(nonce))]),
+        ber::constructed(
+            ber::context(8),
+            &[ber::sequence(&[
+                ber::integer(ETYPE_AES256),
+                ber::integer(ETYPE_AES128),
+                ber::integer(ETYPE_RC4_HMAC),
+            ])],
+        ),
+    ]);
+    ber::constructed(
+        ber::application(AS_REQ),
+        &[ber::sequence(&[
+            ber::constructed(ber::context(1), &[ber::integer(5)]),
+            ber::constructed(ber::context(2), &[ber::integer(i64::from(AS_REQ))]),
+            ber::constructed(ber::context(4), &[body]),
+        ])],
+    )
+}
+
+fn parse_kerberos_time(field: Tlv<'_>) -> Option<DateTime<Utc>> {
+    let text = field.child(ber::TAG_GENERALIZED_TIME)?.as_str();
+    NaiveDateTime::parse_from_str(&text, "%Y%m%d%H%M%SZ")
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+/// Parses a KDC reply (without the TCP length prefix).
+pub fn parse_reply(reply: &[u8]) -> Option<KerberosDetection> {
+    let (message, _) = ber::read_tlv(reply)?;
+    if message.tag == ber::application(AS_REP) {
+        let realm = message
+            .child(ber::TAG_SEQUENCE)?
+            .child(ber::context(3))
+            .and_then(|r| r.child(ber::TAG_GENERAL_STRING))
+            .map(|r| r.as_str());
+        return Some(KerberosDetection {
+            detected: true,
+            realm,
+            server_time: None,
+            error_code: None,
+            as_rep_without_preauth: true,
+            error: None,
+        });
+    }
+    if message.tag != ber::application(KRB_ERROR) {
+        return None;
+    }
+    let fields = message.child(ber::TAG_SEQUENCE)?;
+    let error_code = fields
+        .child(ber::context(6))
+        .and_then(|f| f.child(ber::TAG_INTEGER))
+        .and_then(|i| i.as_integer());
+    let realm = fields
+        .child(ber::context(9))
+        .and_then(|f| f.child(ber::TAG_GENERAL_STRING))
+        .map(|r| r.as_str());
+    Some(KerberosDetection {
+        detected: true,
+        realm,
+        server_time: fields.child(ber::context(4)).and_then(parse_kerberos_time),
+        error_code,
+        as_rep_without_preauth: false,
+        error: None,
+    })
+}
+
+/// Sends an unauthenticated AS-REQ to the KDC at `ip:port` over TCP.
+pub async fn detect(ip: IpAddr, port: u16, realm: &str) -> KerberosDetection {
+    let failed = |error: String| KerberosDetection {
+        detected: false,
+        realm: None,
+        server_time: None,
+        error_code: None,
+        as_rep_without_preauth: false,
+        error: Some(error),
+    };
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return failed("Connection failed".to_string());
+    };
+
+    let request = build_as_req(realm, rand_nonce());
+    let mut framed = (request.len() as u32).to_be_bytes().to_vec();
+    framed.extend(request);
+    if let Err(e) = stream.write_all(&framed).await {
+        return failed(format!("Write failed: {}", e));
+    }
+
+    let mut len_buf = [0u8; 4];
+    let read = async {
+        stream.read_exact(&mut len_buf).await?;
+        let len = u32::from_be_bytes(len_buf) as usize;
+        let mut reply = vec![0u8; len.min(65536)];
+        stream.read_exact(&mut reply).await?;
+        Ok::<_, std::io::Error>(reply)
+    };
+    match tokio::time::timeout(Duration::from_secs(5), read).await {
+        Ok(Ok(reply)) => {
+            parse_reply(&reply).unwrap_or_else(|| failed("Not a Kerberos reply".to_string()))
+        }
+        Ok(Err(e)) => failed(format!("Read failed: {}", e)),
+        Err(_) => failed("Timed out waiting for KDC reply".to_string()),
+    }
+}
+
+fn rand_nonce() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() & 0x7fff_ffff)
+        .unwrap_or(0x1234_5678)
+}
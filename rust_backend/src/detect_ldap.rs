@@ -0,0 +1,158 @@
+use crate::utils::ber::{self, Tlv};
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// rootDSE attributes worth reporting on a directory server
+pub const ROOT_DSE_ATTRIBUTES: &[&str] = &[
+    "defaultNamingContext",
+    "dnsHostName",
+    "ldapServiceName",
+    "serverName",
+    "domainFunctionality",
+    "forestFunctionality",
+    "domainControllerFunctionality",
+    "supportedLDAPVersion",
+    "supportedSASLMechanisms",
+    "currentTime",
+];
+
+const SEARCH_REQUEST: u8 = 3; // [APPLICATION 3]
+const SEARCH_RESULT_ENTRY: u8 = 4; // [APPLICATION 4]
+const FILTER_PRESENT: u8 = 0x87; // [7] primitive
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LdapDetection {
+    pub detected: bool,
+    /// rootDSE attributes returned to an anonymous bind, in response order
+    pub root_dse: Vec<(String, Vec<String>)>,
+    pub error: Option<String>,
+}
+
+impl LdapDetection {
+    /// First value of a rootDSE attribute (case-insensitive name).
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.root_dse
+            .iter()
+            .find(|(attr, _)| attr.eq_ignore_ascii_case(name))
+            .and_then(|(_, values)| values.first())
+            .map(String::as_str)
+    }
+
+    /// Kerberos realm from ldapServiceName ("corp.example.com:dc01$@CORP.EXAMPLE.COM"),
+    /// falling back to the naming context ("DC=corp,DC=example,DC=com").
+    pub fn realm(&self) -> Option<String> {
+        if let Some(service) = self.attribute("ldapServiceName")
+            && let Some((_, realm)) = service.rsplit_once('@')
+        {
+            return Some(realm.to_string());
+        }
+        let context = self.attribute("defaultNamingContext")?;
+        let labels: Vec<&str> = context
+            .split(',')
+            .filter_map(|part| {
+                let (key, value) = part.trim().split_once('=')?;
+                key.eq_ignore_ascii_case("DC").then_some(value)
+            })
+            .collect();
+        (!labels.is_empty()).then(|| labels.join(".").to_uppercase())
+    }
+}
+
+/// Anonymous search of the rootDSE (base "", scope base, filter (objectClass=*)).
+pub fn build_root_dse_request(message_id: i64) -> Vec<u8> {
+    let attributes: Vec<Vec<u8>> = ROOT_DSE_ATTRIBUTES
+        .iter()
+        .map(|a| ber::octet_string(a.as_bytes()))
+        .collect();
+    let search = ber::constructed(
+        ber::application(SEARCH_REQUEST),
+        &[
+            ber::octet_string(b""),
+            ber::integer_with_tag(ber::TAG_ENUMERATED, 0), // baseObject
+            ber::integer_with_tag(ber::TAG_ENUMERATED, 0), // neverDerefAliases
+            ber::integer(0),                               // sizeLimit
+            ber::integer(0),                               // timeLimit
+            ber::tlv(ber::TAG_BOOLEAN, &[0x00]),           // typesOnly
+            ber::tlv(FILTER_PRESENT, b"objectClass"),
+            ber::sequence(&attributes),
+        ],
+    );
+    ber::sequence(&[ber::integer(message_id), search])
+}
+
+/// Extracts attributes from a SearchResultEntry LDAPMessage.
+pub fn parse_search_entry(message: &[u8]) -> Option<Vec<(String, Vec<String>)>> {
+    let (envelope, _) = ber::read_tlv(message)?;
+    let entry = envelope.child(ber::application(SEARCH_RESULT_ENTRY))?;
+    let fields = entry.children()?;
+    let attributes = fields.get(1)?.children()?;
+    attributes
+        .iter()
+        .map(|attr| {
+            let parts = attr.children()?;
+            let name = parts.first()?.as_str();
+            let values = parts.get(1)?.children()?.iter().map(Tlv::as_str).collect();
+            Some((name, values))
+        })
+        .collect()
+}
+
+async fn read_message(stream: &mut TcpStream, buf: &mut Vec<u8>) -> Result<Vec<u8>, String> {
+    loop {
+        if let Some(len) = ber::encoded_len(buf)
+            && buf.len() >= len
+        {
+            return Ok(buf.drain(..len).collect());
+        }
+        let mut chunk = [0u8; 4096];
+        let n = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut chunk))
+            .await
+            .map_err(|_| "Timed out waiting for LDAP response".to_string())?
+            .map_err(|e| format!("Read failed: {}", e))?;
+        if n == 0 {
+            return Err("Connection closed by server".to_string());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+pub async fn detect(ip: IpAddr, port: u16) -> LdapDetection {
+    let failed = |error: String| LdapDetection {
+        detected: false,
+        root_dse: Vec::new(),
+        error: Some(error),
+    };
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return failed("Connection failed".to_string());
+    };
+    if let Err(e) = stream.write_all(&build_root_dse_request(1)).await {
+        return failed(format!("Write failed: {}", e));
+    }
+
+    let mut buf = Vec::new();
+    match read_message(&mut stream, &mut buf).await {
+        Ok(message) => match parse_search_entry(&message) {
+            Some(root_dse) => LdapDetection {
+                detected: true,
+                root_dse,
+                error: None,
+            },
+            // A well-formed LDAPMessage without an entry still means LDAP is listening
+            None if message.first() == Some(&ber::TAG_SEQUENCE) => LdapDetection {
+                detected: true,
+                root_dse: Vec::new(),
+                error: Some("rootDSE not readable anonymously".to_string()),
+            },
+            None => failed("Not an LDAP response".to_string()),
+        },
+        Err(e) => failed(e),
+    }
+}
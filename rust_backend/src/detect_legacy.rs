@@ -0,0 +1,228 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// User names sent in r-service handshakes; never a real login attempt
+const PROBE_USER: &str = "netscan";
+/// Text the echo probe expects to get back
+const ECHO_PROBE: &[u8] = b"netscan-echo\r\n";
+/// Line length of the RFC 864 character generator pattern
+const CHARGEN_LINE_LEN: usize = 72;
+
+/// Legacy TCP services that predate authentication or encryption
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LegacyService {
+    Echo,
+    Daytime,
+    Chargen,
+    Finger,
+    Rexec,
+    Rlogin,
+    Rsh,
+}
+
+impl LegacyService {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LegacyService::Echo => "echo",
+            LegacyService::Daytime => "daytime",
+            LegacyService::Chargen => "chargen",
+            LegacyService::Finger => "finger",
+            LegacyService::Rexec => "rexec",
+            LegacyService::Rlogin => "rlogin",
+            LegacyService::Rsh => "rsh",
+        }
+    }
+
+    pub fn default_port(&self) -> u16 {
+        match self {
+            LegacyService::Echo => 7,
+            LegacyService::Daytime => 13,
+            LegacyService::Chargen => 19,
+            LegacyService::Finger => 79,
+            LegacyService::Rexec => 512,
+            LegacyService::Rlogin => 513,
+            LegacyService::Rsh => 514,
+        }
+    }
+
+    /// Service normally listening on `port`, if any.
+    pub fn from_port(port: u16) -> Option<Self> {
+        [
+            LegacyService::Echo,
+            LegacyService::Daytime,
+            LegacyService::Chargen,
+            LegacyService::Finger,
+            LegacyService::Rexec,
+            LegacyService::Rlogin,
+            LegacyService::Rsh,
+        ]
+        .into_iter()
+        .find(|service| service.default_port() == port)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyDetection {
+    pub detected: bool,
+    pub service: LegacyService,
+    /// First line of what the service sent back
+    pub response: Option<String>,
+    pub error: Option<DetectError>,
+}
+
+impl LegacyDetection {
+    pub fn summary(&self) -> String {
+        match &self.response {
+            Some(response) if !response.is_empty() => {
+                format!("{} ({})", self.service.label(), response)
+            }
+            _ => self.service.label().to_string(),
+        }
+    }
+}
+
+impl From<LegacyDetection> for Detection {
+    fn from(legacy: LegacyDetection) -> Self {
+        Detection::new(legacy.detected, legacy.summary(), legacy.error)
+    }
+}
+
+/// Client handshake for rexec, rlogin and rsh (all NUL-separated fields).
+pub fn build_r_request(service: LegacyService) -> Vec<u8> {
+    let fields: Vec<&str> = match service {
+        // stderr port, user, password, command
+        LegacyService::Rexec => vec!["0", PROBE_USER, "", "true"],
+        // empty, local user, remote user, terminal/speed
+        LegacyService::Rlogin => vec!["", PROBE_USER, PROBE_USER, "dumb/9600"],
+        // stderr port, local user, remote user, command
+        _ => vec!["0", PROBE_USER, PROBE_USER, "true"],
+    };
+    let mut request = Vec::new();
+    for field in fields {
+        request.extend_from_slice(field.as_bytes());
+        request.push(0);
+    }
+    request
+}
+
+/// r-services answer with a status byte: 0 for success, 1 followed by an
+/// error message. Returns the message (empty on success).
+pub fn parse_r_reply(reply: &[u8]) -> Option<String> {
+    match reply.first()? {
+        0 => Some(String::new()),
+        1 => Some(first_line(&reply[1..])),
+        _ => None,
+    }
+}
+
+/// Whether `data` looks like the rotating printable-ASCII chargen pattern.
+pub fn is_chargen_stream(data: &[u8]) -> bool {
+    let lines: Vec<&[u8]> = data
+        .split(|b| *b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .collect();
+    // Only whole lines count: the last element may be cut short
+    let complete = &lines[..lines.len().saturating_sub(1)];
+    complete.len() >= 2
+        && complete.iter().all(|line| {
+            line.len() == CHARGEN_LINE_LEN && line.iter().all(|b| (b' '..=b'~').contains(b))
+        })
+        && complete
+            .windows(2)
+            .all(|pair| pair[0][1..] == pair[1][..CHARGEN_LINE_LEN - 1])
+}
+
+fn first_line(data: &[u8]) -> String {
+    String::from_utf8_lossy(data)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn is_text(data: &[u8]) -> bool {
+    !data.is_empty()
+        && data
+            .iter()
+            .all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace())
+}
+
+/// Reads until `limit` bytes arrive, the peer closes or it goes quiet.
+async fn read_some(stream: &mut TcpStream, limit: usize) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 512];
+    while data.len() < limit {
+        match tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await {
+            Ok(Ok(n)) if n > 0 => data.extend_from_slice(&buf[..n]),
+            _ => break,
+        }
+    }
+    data
+}
+
+/// Speaks just enough of `service` to confirm it is what listens on `port`.
+pub async fn detect(ip: IpAddr, port: u16, service: LegacyService) -> LegacyDetection {
+    let failed = |error: DetectError| LegacyDetection {
+        detected: false,
+        service,
+        response: None,
+        error: Some(error),
+    };
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return failed(DetectError::Connect("Connection failed".to_string()));
+    };
+
+    let request: Vec<u8> = match service {
+        LegacyService::Echo => ECHO_PROBE.to_vec(),
+        LegacyService::Finger => b"\r\n".to_vec(),
+        LegacyService::Rexec | LegacyService::Rlogin | LegacyService::Rsh => {
+            build_r_request(service)
+        }
+        LegacyService::Daytime | LegacyService::Chargen => Vec::new(),
+    };
+    if !request.is_empty() && stream.write_all(&request).await.is_err() {
+        return failed(DetectError::Connect("Write failed".to_string()));
+    }
+    let limit = match service {
+        LegacyService::Echo => ECHO_PROBE.len(),
+        LegacyService::Chargen => 4 * (CHARGEN_LINE_LEN + 2),
+        _ => 1024,
+    };
+    let reply = read_some(&mut stream, limit).await;
+
+    let response = match service {
+        LegacyService::Echo => (reply == ECHO_PROBE).then(|| "echoed probe".to_string()),
+        LegacyService::Chargen => is_chargen_stream(&reply).then(|| "character stream".to_string()),
+        LegacyService::Daytime => {
+            (is_text(&reply) && reply.iter().any(u8::is_ascii_digit)).then(|| first_line(&reply))
+        }
+        LegacyService::Finger => is_text(&reply).then(|| first_line(&reply)),
+        LegacyService::Rexec | LegacyService::Rlogin | LegacyService::Rsh => parse_r_reply(&reply),
+    };
+    match response {
+        Some(response) => LegacyDetection {
+            detected: true,
+            service,
+            response: Some(response),
+            error: None,
+        },
+        None if reply.is_empty() => failed(DetectError::Timeout(format!(
+            "No {} response",
+            service.label()
+        ))),
+        None => failed(DetectError::Mismatch(format!(
+            "Unexpected {} response",
+            service.label()
+        ))),
+    }
+}
@@ -0,0 +1,315 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const MONGODB_PORT: u16 = 27017;
+
+const OP_REPLY: i32 = 1;
+const OP_QUERY: i32 = 2004;
+const OP_MSG: i32 = 2013;
+/// First wire version that accepts OP_MSG (MongoDB 3.6)
+const OP_MSG_WIRE_VERSION: i32 = 6;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Replies larger than this are not read
+const MAX_REPLY: usize = 1 << 20;
+
+/// The BSON element types the detector reads; everything else is skipped
+#[derive(Debug, Clone, PartialEq)]
+pub enum BsonValue {
+    Double(f64),
+    String(String),
+    /// Embedded document or array, still encoded
+    Document(Vec<u8>),
+    Bool(bool),
+    Int32(i32),
+    Int64(i64),
+    Other,
+}
+
+impl BsonValue {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            BsonValue::Double(v) => Some(*v as i64),
+            BsonValue::Int32(v) => Some(i64::from(*v)),
+            BsonValue::Int64(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MongoDetection {
+    pub detected: bool,
+    pub version: Option<String>,
+    pub max_wire_version: Option<i32>,
+    /// Replica set the node belongs to
+    pub set_name: Option<String>,
+    /// Whether listDatabases was refused as unauthorized
+    pub auth_required: bool,
+    /// Databases listed without credentials
+    pub databases: Option<usize>,
+    pub error: Option<DetectError>,
+}
+
+impl MongoDetection {
+    pub fn anonymous_read(&self) -> bool {
+        self.databases.is_some()
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(set) = &self.set_name {
+            parts.push(format!("replica set {}", set));
+        }
+        parts.push(match (self.databases, self.auth_required) {
+            (Some(count), _) => format!("no authentication, {} database(s) listed", count),
+            (None, true) => "authentication required".to_string(),
+            (None, false) => "database listing unavailable".to_string(),
+        });
+        let version = match (&self.version, self.max_wire_version) {
+            (Some(version), _) => format!(" {}", version),
+            (None, Some(wire)) => format!(" (wire version {})", wire),
+            (None, None) => String::new(),
+        };
+        format!("MongoDB{} ({})", version, parts.join(", "))
+    }
+}
+
+impl From<MongoDetection> for Detection {
+    fn from(mongodb: MongoDetection) -> Self {
+        Detection::new(mongodb.detected, mongodb.summary(), mongodb.error)
+    }
+}
+
+/// Encodes a flat document of int32, bool and string fields.
+pub fn build_document(fields: &[(&str, BsonValue)]) -> Vec<u8> {
+    let mut doc = vec![0, 0, 0, 0];
+    for (name, value) in fields {
+        let (kind, bytes) = match value {
+            BsonValue::Int32(v) => (0x10, v.to_le_bytes().to_vec()),
+            BsonValue::Bool(v) => (0x08, vec![u8::from(*v)]),
+            BsonValue::String(s) => {
+                let mut bytes = ((s.len() + 1) as i32).to_le_bytes().to_vec();
+                bytes.extend_from_slice(s.as_bytes());
+                bytes.push(0);
+                (0x02, bytes)
+            }
+            _ => continue,
+        };
+        doc.push(kind);
+        doc.extend_from_slice(name.as_bytes());
+        doc.push(0);
+        doc.extend_from_slice(&bytes);
+    }
+    doc.push(0);
+    let len = doc.len() as i32;
+    doc[..4].copy_from_slice(&len.to_le_bytes());
+    doc
+}
+
+/// Decodes the top-level elements of a BSON document.
+pub fn parse_document(doc: &[u8]) -> Option<Vec<(String, BsonValue)>> {
+    let len = usize::try_from(i32::from_le_bytes(doc.get(..4)?.try_into().ok()?)).ok()?;
+    let mut rest = doc.get(4..len.checked_sub(1)?)?;
+    let mut fields = Vec::new();
+    while let Some((&kind, tail)) = rest.split_first() {
+        let name_end = tail.iter().position(|b| *b == 0)?;
+        let name = String::from_utf8_lossy(&tail[..name_end]).to_string();
+        let data = &tail[name_end + 1..];
+        let read_i32 = |at: usize| -> Option<usize> {
+            // A negative length is hostile or corrupt, never a size
+            usize::try_from(i32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?)).ok()
+        };
+        let (value, size) = match kind {
+            0x01 => (
+                BsonValue::Double(f64::from_le_bytes(data.get(..8)?.try_into().ok()?)),
+                8,
+            ),
+            0x02 => {
+                let size = read_i32(0)?;
+                let text = data.get(4..4usize.checked_add(size.checked_sub(1)?)?)?;
+                (
+                    BsonValue::String(String::from_utf8_lossy(text).to_string()),
+                    size.checked_add(4)?,
+                )
+            }
+            0x03 | 0x04 => {
+                let size = read_i32(0)?;
+                (BsonValue::Document(data.get(..size)?.to_vec()), size)
+            }
+            0x05 => (BsonValue::Other, read_i32(0)?.checked_add(5)?),
+            0x07 => (BsonValue::Other, 12),
+            0x08 => (BsonValue::Bool(*data.first()? != 0), 1),
+            0x0a => (BsonValue::Other, 0),
+            0x10 => (
+                BsonValue::Int32(i32::from_le_bytes(data.get(..4)?.try_into().ok()?)),
+                4,
+            ),
+            0x12 => (
+                BsonValue::Int64(i64::from_le_bytes(data.get(..8)?.try_into().ok()?)),
+                8,
+            ),
+            0x09 | 0x11 => (BsonValue::Other, 8),
+            0x13 => (BsonValue::Other, 16),
+            _ => return None,
+        };
+        fields.push((name, value));
+        rest = data.get(size..)?;
+    }
+    Some(fields)
+}
+
+fn field<'a>(fields: &'a [(String, BsonValue)], name: &str) -> Option<&'a BsonValue> {
+    fields
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+}
+
+fn string_field(fields: &[(String, BsonValue)], name: &str) -> Option<String> {
+    match field(fields, name)? {
+        BsonValue::String(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+fn frame(request_id: i32, op_code: i32, payload: &[u8]) -> Vec<u8> {
+    let mut message = ((16 + payload.len()) as i32).to_le_bytes().to_vec();
+    message.extend_from_slice(&request_id.to_le_bytes());
+    message.extend_from_slice(&0i32.to_le_bytes());
+    message.extend_from_slice(&op_code.to_le_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+/// Legacy OP_QUERY `isMaster` on admin.$cmd, answered by every server version.
+pub fn build_is_master(request_id: i32) -> Vec<u8> {
+    let mut payload = 0i32.to_le_bytes().to_vec();
+    payload.extend_from_slice(b"admin.$cmd\0");
+    payload.extend_from_slice(&0i32.to_le_bytes());
+    payload.extend_from_slice(&(-1i32).to_le_bytes());
+    payload.extend_from_slice(&build_document(&[("isMaster", BsonValue::Int32(1))]));
+    frame(request_id, OP_QUERY, &payload)
+}
+
+/// OP_MSG carrying a single command document.
+pub fn build_command(request_id: i32, command: &[(&str, BsonValue)]) -> Vec<u8> {
+    let mut payload = 0u32.to_le_bytes().to_vec();
+    payload.push(0);
+    payload.extend_from_slice(&build_document(command));
+    frame(request_id, OP_MSG, &payload)
+}
+
+/// Reply document of an OP_REPLY or OP_MSG message.
+pub fn parse_reply(message: &[u8]) -> Option<Vec<(String, BsonValue)>> {
+    let op_code = i32::from_le_bytes(message.get(12..16)?.try_into().ok()?);
+    let doc = match op_code {
+        OP_REPLY => message.get(36..)?,
+        OP_MSG if *message.get(20)? == 0 => message.get(21..)?,
+        _ => return None,
+    };
+    parse_document(doc)
+}
+
+async fn command(
+    stream: &mut TcpStream,
+    request: &[u8],
+) -> Result<Vec<(String, BsonValue)>, DetectError> {
+    let exchange = async {
+        stream.write_all(request).await?;
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await?;
+        let len = i32::from_le_bytes(header) as usize;
+        if !(16..=MAX_REPLY).contains(&len) {
+            return Err(std::io::Error::other("bad message length"));
+        }
+        let mut message = header.to_vec();
+        message.resize(len, 0);
+        stream.read_exact(&mut message[4..]).await?;
+        Ok(message)
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, exchange).await {
+        Ok(Ok(message)) => parse_reply(&message)
+            .ok_or_else(|| DetectError::Mismatch("Not a MongoDB reply".to_string())),
+        // The port took the connection but did not speak the wire protocol
+        Ok(Err(e)) => Err(DetectError::Mismatch(format!(
+            "MongoDB exchange failed: {}",
+            e
+        ))),
+        Err(_) => Err(DetectError::Timeout("No MongoDB response".to_string())),
+    }
+}
+
+/// Sends isMaster, then (on servers that speak OP_MSG) buildInfo for the
+/// version and listDatabases to see whether data is readable without
+/// credentials. All three commands are read-only.
+pub async fn detect(ip: IpAddr, port: u16) -> MongoDetection {
+    let mut detection = MongoDetection {
+        detected: false,
+        version: None,
+        max_wire_version: None,
+        set_name: None,
+        auth_required: false,
+        databases: None,
+        error: None,
+    };
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        detection.error = Some(DetectError::Connect("Connection failed".to_string()));
+        return detection;
+    };
+    let hello = match command(&mut stream, &build_is_master(1)).await {
+        Ok(hello) => hello,
+        Err(e) => {
+            detection.error = Some(e);
+            return detection;
+        }
+    };
+    if field(&hello, "ismaster").is_none() && field(&hello, "isWritablePrimary").is_none() {
+        detection.error = Some(DetectError::Mismatch("Not a MongoDB reply".to_string()));
+        return detection;
+    }
+    detection.detected = true;
+    detection.max_wire_version = field(&hello, "maxWireVersion")
+        .and_then(BsonValue::as_i64)
+        .map(|v| v as i32);
+    detection.set_name = string_field(&hello, "setName");
+    if detection.max_wire_version.unwrap_or_default() < OP_MSG_WIRE_VERSION {
+        return detection;
+    }
+
+    let admin = || ("$db", BsonValue::String("admin".to_string()));
+    let build_info = build_command(2, &[("buildInfo", BsonValue::Int32(1)), admin()]);
+    if let Ok(info) = command(&mut stream, &build_info).await {
+        detection.version = string_field(&info, "version");
+    }
+    let list = build_command(
+        3,
+        &[
+            ("listDatabases", BsonValue::Int32(1)),
+            ("nameOnly", BsonValue::Bool(true)),
+            admin(),
+        ],
+    );
+    if let Ok(reply) = command(&mut stream, &list).await {
+        let ok = field(&reply, "ok").and_then(BsonValue::as_i64) == Some(1);
+        match field(&reply, "databases") {
+            Some(BsonValue::Document(list)) if ok => {
+                detection.databases = parse_document(list).map(|dbs| dbs.len())
+            }
+            _ => {
+                detection.auth_required = field(&reply, "code").and_then(BsonValue::as_i64)
+                    == Some(13)
+                    || string_field(&reply, "codeName").as_deref() == Some("Unauthorized")
+            }
+        }
+    }
+    detection
+}
@@ -0,0 +1,76 @@
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+
+/// Seconds between 1900-01-01 (NTP epoch) and 1970-01-01
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+const NTP_PACKET_LEN: usize = 48;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NtpDetection {
+    pub detected: bool,
+    pub stratum: Option<u8>,
+    /// Server clock minus local clock, in seconds (positive: server is ahead)
+    pub offset_secs: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// SNTP client request (LI 0, version 3, mode 3).
+pub fn build_request() -> [u8; NTP_PACKET_LEN] {
+    let mut packet = [0u8; NTP_PACKET_LEN];
+    packet[0] = 0x1b;
+    packet
+}
+
+/// Stratum and transmit time (seconds since the Unix epoch) from a server reply.
+pub fn parse_response(reply: &[u8]) -> Option<(u8, f64)> {
+    if reply.len() < NTP_PACKET_LEN || reply[0] & 0x07 != 4 {
+        return None; // Not a server-mode packet
+    }
+    let seconds = u32::from_be_bytes(reply[40..44].try_into().ok()?) as u64;
+    let fraction = u32::from_be_bytes(reply[44..48].try_into().ok()?) as f64 / 4_294_967_296.0;
+    if seconds < NTP_UNIX_OFFSET {
+        return None;
+    }
+    Some((reply[1], (seconds - NTP_UNIX_OFFSET) as f64 + fraction))
+}
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+pub async fn detect(ip: IpAddr, port: u16) -> NtpDetection {
+    let failed = |error: String| NtpDetection {
+        detected: false,
+        stratum: None,
+        offset_secs: None,
+        error: Some(error),
+    };
+    let exchange = async {
+        let socket = UdpSocket::bind(sockets::local_bind_addr(ip)).await?;
+        socket.connect(sockets::socket_addr(ip, port)).await?;
+        let sent = unix_now();
+        socket.send(&build_request()).await?;
+        let mut buf = [0u8; 512];
+        let n = socket.recv(&mut buf).await?;
+        let received = unix_now();
+        Ok::<_, std::io::Error>((buf[..n].to_vec(), (sent + received) / 2.0))
+    };
+    match tokio::time::timeout(Duration::from_secs(3), exchange).await {
+        Ok(Ok((reply, local_midpoint))) => match parse_response(&reply) {
+            Some((stratum, server_time)) => NtpDetection {
+                detected: true,
+                stratum: Some(stratum),
+                offset_secs: Some(server_time - local_midpoint),
+                error: None,
+            },
+            None => failed("Not an NTP server response".to_string()),
+        },
+        Ok(Err(e)) => failed(format!("NTP exchange failed: {}", e)),
+        Err(_) => failed("No NTP response".to_string()),
+    }
+}
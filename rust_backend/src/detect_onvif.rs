@@ -0,0 +1,258 @@
+use crate::detect_http;
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+pub const WS_DISCOVERY_PORT: u16 = 3702;
+/// Device service path mandated by the ONVIF core specification
+pub const DEVICE_SERVICE_PATH: &str = "/onvif/device_service";
+
+const SOAP_CONTENT_TYPE: &str = "application/soap+xml; charset=utf-8";
+
+/// Where a device says its services live and how it describes itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeMatch {
+    /// Device service URLs
+    pub xaddrs: Vec<String>,
+    pub scopes: Vec<String>,
+}
+
+impl ProbeMatch {
+    /// Value of an onvif://www.onvif.org/<kind>/<value> scope, e.g. "hardware" or "name".
+    pub fn scope_value(&self, kind: &str) -> Option<String> {
+        let prefix = format!("onvif://www.onvif.org/{}/", kind);
+        self.scopes
+            .iter()
+            .find_map(|scope| scope.strip_prefix(&prefix))
+            .map(|value| value.replace("%20", " "))
+    }
+}
+
+/// GetDeviceInformation answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnvifDeviceInfo {
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub firmware: Option<String>,
+    pub serial: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnvifDetection {
+    pub detected: bool,
+    pub probe_match: Option<ProbeMatch>,
+    pub device: Option<OnvifDeviceInfo>,
+    /// Whether GetDeviceInformation needed credentials
+    pub auth_required: bool,
+    pub error: Option<DetectError>,
+}
+
+impl OnvifDetection {
+    /// Manufacturer from device information, else the discovery name scope.
+    pub fn vendor(&self) -> Option<String> {
+        self.device
+            .as_ref()
+            .and_then(|device| device.manufacturer.clone())
+            .or_else(|| self.probe_match.as_ref()?.scope_value("name"))
+    }
+
+    /// Model from device information, else the discovery hardware scope.
+    pub fn model(&self) -> Option<String> {
+        self.device
+            .as_ref()
+            .and_then(|device| device.model.clone())
+            .or_else(|| self.probe_match.as_ref()?.scope_value("hardware"))
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        let identity: Vec<String> = [self.vendor(), self.model()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if !identity.is_empty() {
+            parts.push(identity.join(" "));
+        }
+        if let Some(firmware) = self.device.as_ref().and_then(|d| d.firmware.as_ref()) {
+            parts.push(format!("firmware {}", firmware));
+        }
+        if self.auth_required {
+            parts.push("device info requires authentication".to_string());
+        }
+        if parts.is_empty() {
+            "ONVIF".to_string()
+        } else {
+            format!("ONVIF ({})", parts.join(", "))
+        }
+    }
+}
+
+impl From<OnvifDetection> for Detection {
+    fn from(onvif: OnvifDetection) -> Self {
+        Detection::new(onvif.detected, onvif.summary(), onvif.error)
+    }
+}
+
+/// Text of the first element named `name`, ignoring its namespace prefix.
+pub fn xml_text<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let end = rest.find('>')?;
+        let tag = &rest[..end];
+        let tag_name = tag.split_whitespace().next().unwrap_or_default();
+        let local = tag_name.rsplit(':').next().unwrap_or(tag_name);
+        if local == name && !tag.starts_with('/') && !tag.ends_with('/') {
+            let content = &rest[end + 1..];
+            return Some(content[..content.find("</")?].trim());
+        }
+        rest = &rest[end + 1..];
+    }
+    None
+}
+
+/// WS-Discovery Probe for ONVIF video transmitters.
+pub fn build_probe(message_id: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+<e:Envelope xmlns:e=\"http://www.w3.org/2003/05/soap-envelope\" \
+xmlns:w=\"http://schemas.xmlsoap.org/ws/2004/08/addressing\" \
+xmlns:d=\"http://schemas.xmlsoap.org/ws/2005/04/discovery\" \
+xmlns:dn=\"http://www.onvif.org/ver10/network/wsdl\">\
+<e:Header><w:MessageID>uuid:{}</w:MessageID>\
+<w:To e:mustUnderstand=\"true\">urn:schemas-xmlsoap-org:ws:2005:04:discovery</w:To>\
+<w:Action e:mustUnderstand=\"true\">http://schemas.xmlsoap.org/ws/2005/04/discovery/Probe</w:Action>\
+</e:Header><e:Body><d:Probe><d:Types>dn:NetworkVideoTransmitter</d:Types></d:Probe></e:Body>\
+</e:Envelope>",
+        message_id
+    )
+}
+
+pub fn parse_probe_match(xml: &str) -> Option<ProbeMatch> {
+    xml_text(xml, "ProbeMatch")?;
+    let split = |name| {
+        xml_text(xml, name)
+            .map(|text| text.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+    Some(ProbeMatch {
+        xaddrs: split("XAddrs"),
+        scopes: split("Scopes"),
+    })
+}
+
+pub fn build_get_device_information() -> String {
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+<s:Envelope xmlns:s=\"http://www.w3.org/2003/05/soap-envelope\">\
+<s:Body xmlns:tds=\"http://www.onvif.org/ver10/device/wsdl\"><tds:GetDeviceInformation/></s:Body>\
+</s:Envelope>"
+        .to_string()
+}
+
+pub fn parse_device_information(xml: &str) -> Option<OnvifDeviceInfo> {
+    xml_text(xml, "GetDeviceInformationResponse")?;
+    let field = |name| xml_text(xml, name).map(str::to_string);
+    Some(OnvifDeviceInfo {
+        manufacturer: field("Manufacturer"),
+        model: field("Model"),
+        firmware: field("FirmwareVersion"),
+        serial: field("SerialNumber"),
+    })
+}
+
+/// Port and path of an http:// device service URL.
+pub fn xaddr_target(xaddr: &str) -> Option<(u16, String)> {
+    let rest = xaddr.strip_prefix("http://")?;
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    // skip past a bracketed IPv6 literal so its colons are not read as the port
+    let host_end = authority.rfind(']').map_or(0, |i| i + 1);
+    let port = match authority[host_end..].rsplit_once(':') {
+        Some((_, port)) => port.parse().ok()?,
+        None => 80,
+    };
+    let path = if path.is_empty() { "/" } else { path };
+    Some((port, path.to_string()))
+}
+
+/// Sends a unicast WS-Discovery probe to `ip`.
+pub async fn discover(ip: IpAddr, port: u16) -> Result<ProbeMatch, DetectError> {
+    let exchange = async {
+        let socket = UdpSocket::bind(sockets::local_bind_addr(ip)).await?;
+        let probe = build_probe("6e657473-6361-6e00-0000-000000000001");
+        socket
+            .send_to(probe.as_bytes(), sockets::socket_addr(ip, port))
+            .await?;
+        let mut buf = vec![0u8; 8192];
+        loop {
+            let (n, from) = socket.recv_from(&mut buf).await?;
+            if from.ip() == ip {
+                return Ok::<_, std::io::Error>(String::from_utf8_lossy(&buf[..n]).to_string());
+            }
+        }
+    };
+    match tokio::time::timeout(Duration::from_secs(3), exchange).await {
+        Ok(Ok(reply)) => parse_probe_match(&reply)
+            .ok_or_else(|| DetectError::Mismatch("Not a ProbeMatch".to_string())),
+        Ok(Err(e)) => Err(DetectError::Connect(format!("WS-Discovery failed: {}", e))),
+        Err(_) => Err(DetectError::Timeout("No WS-Discovery response".to_string())),
+    }
+}
+
+/// Runs WS-Discovery, then asks the device service (the one discovery
+/// advertised for `ip`, else `port` and the standard path) for
+/// GetDeviceInformation without credentials.
+pub async fn detect(ip: IpAddr, port: u16) -> OnvifDetection {
+    let mut detection = OnvifDetection {
+        detected: false,
+        probe_match: None,
+        device: None,
+        auth_required: false,
+        error: None,
+    };
+    match discover(ip, WS_DISCOVERY_PORT).await {
+        Ok(probe_match) => {
+            detection.detected = true;
+            detection.probe_match = Some(probe_match);
+        }
+        Err(e) => detection.error = Some(e),
+    }
+    let (service_port, path) = detection
+        .probe_match
+        .as_ref()
+        .and_then(|probe| {
+            probe
+                .xaddrs
+                .iter()
+                .filter(|xaddr| xaddr.contains(&ip.to_string()))
+                .find_map(|xaddr| xaddr_target(xaddr))
+        })
+        .unwrap_or((port, DEVICE_SERVICE_PATH.to_string()));
+
+    let body = build_get_device_information();
+    match detect_http::post(ip, service_port, &path, SOAP_CONTENT_TYPE, &body).await {
+        Ok(response) => {
+            if let Some(device) = parse_device_information(&response) {
+                detection.detected = true;
+                detection.device = Some(device);
+            } else if response.contains(" 401 ") || response.contains("NotAuthorized") {
+                detection.detected = true;
+                detection.auth_required = true;
+            } else {
+                // The port answered, which says more than a silent discovery
+                detection.error =
+                    Some(DetectError::Mismatch("No ONVIF device service".to_string()));
+            }
+        }
+        Err(e) => {
+            detection.error.get_or_insert(e);
+        }
+    }
+    if detection.detected {
+        detection.error = None;
+    } else if detection.error.is_none() {
+        detection.error = Some(DetectError::Mismatch("No ONVIF device service".to_string()));
+    }
+    detection
+}
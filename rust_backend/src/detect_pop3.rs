@@ -0,0 +1,116 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const POP3_PORT: u16 = 110;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pop3Detection {
+    pub detected: bool,
+    /// Greeting after "+OK", e.g. "Dovecot ready."
+    pub banner: Option<String>,
+    /// CAPA response lines, e.g. ["TOP", "UIDL", "STLS", "SASL PLAIN"]
+    pub capabilities: Vec<String>,
+    pub error: Option<DetectError>,
+}
+
+impl Pop3Detection {
+    fn failed(error: DetectError) -> Self {
+        Self {
+            detected: false,
+            banner: None,
+            capabilities: Vec::new(),
+            error: Some(error),
+        }
+    }
+
+    /// Whether the server offers STLS to upgrade to TLS.
+    pub fn starttls(&self) -> bool {
+        self.capabilities.iter().any(|cap| cap == "STLS")
+    }
+
+    /// Server software named by the IMPLEMENTATION capability.
+    pub fn implementation(&self) -> Option<&str> {
+        self.capabilities
+            .iter()
+            .find_map(|cap| cap.strip_prefix("IMPLEMENTATION "))
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = "POP3".to_string();
+        if let Some(name) = self.implementation() {
+            summary.push_str(&format!(" ({})", name));
+        } else if let Some(banner) = self.banner.as_deref().filter(|b| !b.is_empty()) {
+            summary.push_str(&format!(" ({})", banner));
+        }
+        if !self.capabilities.is_empty() && !self.starttls() {
+            summary.push_str(", no STLS");
+        }
+        summary
+    }
+}
+
+impl From<Pop3Detection> for Detection {
+    fn from(pop3: Pop3Detection) -> Self {
+        Detection::new(pop3.detected, pop3.summary(), pop3.error)
+    }
+}
+
+/// The greeting text if `line` is a POP3 "+OK" greeting.
+pub fn parse_greeting(line: &str) -> Option<String> {
+    let line = line.lines().next()?;
+    let rest = line.strip_prefix("+OK")?;
+    Some(rest.trim().to_string())
+}
+
+/// Capability lines of a multi-line CAPA response, terminated by ".".
+pub fn parse_capa(response: &str) -> Option<Vec<String>> {
+    let mut lines = response.lines();
+    lines.next()?.strip_prefix("+OK")?;
+    Some(
+        lines
+            .take_while(|line| *line != ".")
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect(),
+    )
+}
+
+/// Reads the "+OK" greeting, then asks for CAPA (RFC 2449). Servers without
+/// CAPA are still detected by the greeting.
+pub async fn detect(ip: IpAddr, port: u16) -> Pop3Detection {
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return Pop3Detection::failed(DetectError::Connect("Connection failed".to_string()));
+    };
+    let mut buf = vec![0u8; 1024];
+    let read = tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await;
+    let banner = match &read {
+        Ok(Ok(n)) => parse_greeting(&String::from_utf8_lossy(&buf[..*n])),
+        _ => None,
+    };
+    let Some(banner) = banner else {
+        return Pop3Detection::failed(DetectError::unrecognized(read.is_err(), "No POP3 greeting"));
+    };
+    let mut detection = Pop3Detection {
+        detected: true,
+        banner: Some(banner),
+        capabilities: Vec::new(),
+        error: None,
+    };
+    if stream.write_all(b"CAPA\r\n").await.is_ok()
+        && let Ok(Ok(n)) = tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await
+    {
+        detection.capabilities =
+            parse_capa(&String::from_utf8_lossy(&buf[..n])).unwrap_or_default();
+    }
+    let _ = stream.write_all(b"QUIT\r\n").await;
+    detection
+}
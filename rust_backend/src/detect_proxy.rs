@@ -0,0 +1,226 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const SOCKS_PORTS: &[u16] = &[1080, 1081, 9050];
+pub const HTTP_PROXY_PORTS: &[u16] = &[3128, 8080, 8118, 8888];
+/// CONNECT target for the HTTP proxy probe; the tunnel is closed unused
+pub const SAFE_CONNECT_TARGET: &str = "example.com:443";
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Authentication method a SOCKS5 server selected (RFC 1928)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocksMethod {
+    NoAuth,
+    Gssapi,
+    UsernamePassword,
+    Other(u8),
+    /// 0xff, none of the offered methods are acceptable
+    Refused,
+}
+
+impl SocksMethod {
+    pub fn from_byte(byte: u8) -> Self {
+        match byte {
+            0x00 => SocksMethod::NoAuth,
+            0x01 => SocksMethod::Gssapi,
+            0x02 => SocksMethod::UsernamePassword,
+            0xff => SocksMethod::Refused,
+            other => SocksMethod::Other(other),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            SocksMethod::NoAuth => "no authentication".to_string(),
+            SocksMethod::Gssapi => "GSSAPI".to_string(),
+            SocksMethod::UsernamePassword => "username/password".to_string(),
+            SocksMethod::Other(method) => format!("method 0x{:02x}", method),
+            SocksMethod::Refused => "no acceptable method".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocksDetection {
+    pub detected: bool,
+    pub method: Option<SocksMethod>,
+    pub error: Option<DetectError>,
+}
+
+impl SocksDetection {
+    /// Whether anyone can relay through it
+    pub fn open(&self) -> bool {
+        self.method == Some(SocksMethod::NoAuth)
+    }
+
+    pub fn summary(&self) -> String {
+        match self.method {
+            Some(method) => format!("SOCKS5 ({})", method.label()),
+            None => "SOCKS5".to_string(),
+        }
+    }
+}
+
+impl From<SocksDetection> for Detection {
+    fn from(socks: SocksDetection) -> Self {
+        Detection::new(socks.detected, socks.summary(), socks.error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpProxyDetection {
+    pub detected: bool,
+    /// Status of the CONNECT response
+    pub status: Option<u16>,
+    pub server: Option<String>,
+    pub error: Option<DetectError>,
+}
+
+impl HttpProxyDetection {
+    /// Whether the proxy opened a tunnel without credentials
+    pub fn open(&self) -> bool {
+        self.status == Some(200)
+    }
+
+    pub fn auth_required(&self) -> bool {
+        self.status == Some(407)
+    }
+
+    pub fn summary(&self) -> String {
+        let access = if self.open() {
+            format!("CONNECT {} allowed", SAFE_CONNECT_TARGET)
+        } else if self.auth_required() {
+            "authentication required".to_string()
+        } else {
+            format!("CONNECT refused ({})", self.status.unwrap_or_default())
+        };
+        match &self.server {
+            Some(server) => format!("HTTP proxy ({}, {})", server, access),
+            None => format!("HTTP proxy ({})", access),
+        }
+    }
+}
+
+impl From<HttpProxyDetection> for Detection {
+    fn from(proxy: HttpProxyDetection) -> Self {
+        Detection::new(proxy.detected, proxy.summary(), proxy.error)
+    }
+}
+
+/// SOCKS5 greeting offering no-auth and username/password.
+pub fn build_socks_greeting() -> Vec<u8> {
+    vec![0x05, 0x02, 0x00, 0x02]
+}
+
+pub fn parse_socks_reply(reply: &[u8]) -> Option<SocksMethod> {
+    match reply {
+        [0x05, method, ..] => Some(SocksMethod::from_byte(*method)),
+        _ => None,
+    }
+}
+
+pub fn build_connect_request(target: &str) -> String {
+    format!(
+        "CONNECT {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: netscan\r\n\r\n",
+        target, target
+    )
+}
+
+/// Status and Server/Via header of a CONNECT response.
+pub fn parse_connect_response(text: &str) -> Option<(u16, Option<String>)> {
+    let mut lines = text.lines();
+    let status_line = lines.next()?;
+    if !status_line.starts_with("HTTP/1.") {
+        return None;
+    }
+    let status = status_line.split_whitespace().nth(1)?.parse().ok()?;
+    let server = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| {
+            let name = name.trim();
+            name.eq_ignore_ascii_case("server") || name.eq_ignore_ascii_case("via")
+        })
+        .map(|(_, value)| value.trim().to_string());
+    Some((status, server))
+}
+
+async fn exchange(ip: IpAddr, port: u16, request: &[u8]) -> Result<Vec<u8>, DetectError> {
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return Err(DetectError::Connect("Connection failed".to_string()));
+    };
+    let exchange = async {
+        stream.write_all(request).await?;
+        let mut buf = vec![0u8; 2048];
+        let n = stream.read(&mut buf).await?;
+        Ok::<_, std::io::Error>(buf[..n].to_vec())
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, exchange).await {
+        Ok(Ok(reply)) => Ok(reply),
+        Ok(Err(e)) => Err(DetectError::Connect(format!("Proxy probe failed: {}", e))),
+        Err(_) => Err(DetectError::Timeout("No proxy response".to_string())),
+    }
+}
+
+/// Sends the SOCKS5 greeting and records the method the server picks. No
+/// CONNECT is issued.
+pub async fn detect_socks(ip: IpAddr, port: u16) -> SocksDetection {
+    let failed = |error: DetectError| SocksDetection {
+        detected: false,
+        method: None,
+        error: Some(error),
+    };
+    let reply = match exchange(ip, port, &build_socks_greeting()).await {
+        Ok(reply) => reply,
+        Err(e) => return failed(e),
+    };
+    match parse_socks_reply(&reply) {
+        Some(method) => SocksDetection {
+            detected: true,
+            method: Some(method),
+            error: None,
+        },
+        None => failed(DetectError::Mismatch("Not a SOCKS5 reply".to_string())),
+    }
+}
+
+/// Asks the proxy to CONNECT to `SAFE_CONNECT_TARGET`; the tunnel is dropped
+/// as soon as the status line arrives.
+pub async fn detect_http_proxy(ip: IpAddr, port: u16) -> HttpProxyDetection {
+    let failed = |error: DetectError| HttpProxyDetection {
+        detected: false,
+        status: None,
+        server: None,
+        error: Some(error),
+    };
+    let request = build_connect_request(SAFE_CONNECT_TARGET);
+    let reply = match exchange(ip, port, request.as_bytes()).await {
+        Ok(reply) => reply,
+        Err(e) => return failed(e),
+    };
+    match parse_connect_response(&String::from_utf8_lossy(&reply)) {
+        // web servers answer CONNECT with 400/405/501; only proxy-like
+        // answers count
+        Some((status, server)) if matches!(status, 200 | 403 | 407) => HttpProxyDetection {
+            detected: true,
+            status: Some(status),
+            server,
+            error: None,
+        },
+        Some((status, _)) => failed(DetectError::Mismatch(format!(
+            "CONNECT answered with {}",
+            status
+        ))),
+        None => failed(DetectError::Mismatch("Not an HTTP response".to_string())),
+    }
+}
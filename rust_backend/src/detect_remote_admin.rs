@@ -0,0 +1,389 @@
+use crate::detect_tls;
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use futures::future::join_all;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Leading bytes of every TeamViewer packet (protocol v1 and v2)
+const TEAMVIEWER_MAGIC: &[[u8; 2]] = &[[0x17, 0x24], [0x11, 0x30]];
+/// TeamViewer CMD_PING in v1 framing
+const TEAMVIEWER_PING: &[u8] = &[0x17, 0x24, 0x10, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00];
+/// Prefix of Steam in-home streaming discovery datagrams
+const STEAM_DISCOVERY_MAGIC: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x21, 0x4c, 0x5f, 0xa0];
+pub const STEAM_DISCOVERY_PORT: u16 = 27036;
+/// RendezvousMessage fields of RustDesk's RegisterPeer and its response
+const RUSTDESK_REGISTER_PEER: u64 = 6;
+const RUSTDESK_REGISTER_PEER_RESPONSE: u64 = 7;
+/// Peer id announced to a RustDesk rendezvous server
+const RUSTDESK_PROBE_ID: &str = "netscan-probe";
+
+/// Remote-access and remote-play software commonly installed outside IT's view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RemoteAdminTool {
+    TeamViewer,
+    AnyDesk,
+    RustDesk,
+    Steam,
+}
+
+impl RemoteAdminTool {
+    pub const ALL: [RemoteAdminTool; 4] = [
+        RemoteAdminTool::TeamViewer,
+        RemoteAdminTool::AnyDesk,
+        RemoteAdminTool::RustDesk,
+        RemoteAdminTool::Steam,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RemoteAdminTool::TeamViewer => "TeamViewer",
+            RemoteAdminTool::AnyDesk => "AnyDesk",
+            RemoteAdminTool::RustDesk => "RustDesk",
+            RemoteAdminTool::Steam => "Steam Remote Play",
+        }
+    }
+
+    /// TCP ports the tool listens on
+    pub fn tcp_ports(&self) -> &'static [u16] {
+        match self {
+            RemoteAdminTool::TeamViewer => &[5938],
+            RemoteAdminTool::AnyDesk => &[7070],
+            // hbbs, hbbr, web clients and the client's direct-IP listener
+            RemoteAdminTool::RustDesk => &[21115, 21116, 21117, 21118, 21119],
+            RemoteAdminTool::Steam => &[27036, 27037],
+        }
+    }
+
+    /// UDP ports used for discovery or NAT traversal
+    pub fn discovery_ports(&self) -> &'static [u16] {
+        match self {
+            RemoteAdminTool::TeamViewer => &[5938],
+            RemoteAdminTool::AnyDesk => &[50001, 50002, 50003],
+            RemoteAdminTool::RustDesk => &[21116],
+            RemoteAdminTool::Steam => &[STEAM_DISCOVERY_PORT],
+        }
+    }
+
+    pub fn from_port(port: u16) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|tool| tool.tcp_ports().contains(&port))
+    }
+
+    pub fn from_discovery_port(port: u16) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|tool| tool.discovery_ports().contains(&port))
+    }
+
+    /// Datagram sent to the tool's discovery ports
+    pub fn discovery_probe(&self) -> Vec<u8> {
+        match self {
+            RemoteAdminTool::TeamViewer => TEAMVIEWER_PING.to_vec(),
+            // AnyDesk's discovery format is undocumented; any datagram draws its beacon
+            RemoteAdminTool::AnyDesk => vec![0; 4],
+            RemoteAdminTool::RustDesk => build_rustdesk_register_peer(RUSTDESK_PROBE_ID),
+            RemoteAdminTool::Steam => build_steam_discovery(0x6e6574736361),
+        }
+    }
+
+    /// What a reply to `discovery_probe` shows, or `None` if it is not the tool's.
+    pub fn parse_discovery_reply(&self, reply: &[u8]) -> Option<String> {
+        match self {
+            RemoteAdminTool::TeamViewer => {
+                is_teamviewer_reply(reply).then(|| "TeamViewer framing over UDP".to_string())
+            }
+            RemoteAdminTool::AnyDesk => String::from_utf8_lossy(reply)
+                .to_lowercase()
+                .contains("anydesk")
+                .then(|| "discovery beacon".to_string()),
+            RemoteAdminTool::RustDesk => protobuf_bytes(reply, RUSTDESK_REGISTER_PEER_RESPONSE)
+                .map(|_| "rendezvous server".to_string()),
+            RemoteAdminTool::Steam => parse_steam_status(reply)
+                .map(|name| format!("host {}", name).trim_end().to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteAdminDetection {
+    pub detected: bool,
+    pub tool: RemoteAdminTool,
+    pub port: u16,
+    /// Whether the protocol answered, rather than only the port being open
+    pub confirmed: bool,
+    pub detail: Option<String>,
+    pub error: Option<DetectError>,
+}
+
+impl RemoteAdminDetection {
+    pub fn summary(&self) -> String {
+        let evidence = if self.confirmed {
+            "confirmed"
+        } else {
+            "port match"
+        };
+        match &self.detail {
+            Some(detail) => format!("{} ({}, {})", self.tool.label(), evidence, detail),
+            None => format!("{} ({})", self.tool.label(), evidence),
+        }
+    }
+}
+
+impl From<RemoteAdminDetection> for Detection {
+    fn from(remote: RemoteAdminDetection) -> Self {
+        let summary = remote.summary();
+        let error = match remote.error {
+            None if !remote.detected => Some(DetectError::Mismatch(format!(
+                "{} port, but no matching reply",
+                remote.tool.label()
+            ))),
+            error => error,
+        };
+        Detection::new(remote.detected, summary, error)
+    }
+}
+
+/// Whether `reply` starts with TeamViewer packet framing.
+pub fn is_teamviewer_reply(reply: &[u8]) -> bool {
+    reply.len() >= 2 && TEAMVIEWER_MAGIC.iter().any(|magic| reply[..2] == *magic)
+}
+
+fn push_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(input: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in input.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// First length-delimited protobuf field `field` in `message`.
+fn protobuf_bytes(mut message: &[u8], field: u64) -> Option<&[u8]> {
+    while !message.is_empty() {
+        let (key, used) = read_varint(message)?;
+        message = &message[used..];
+        let len = match key & 0x7 {
+            0 => read_varint(message)?.1,
+            1 => 8,
+            5 => 4,
+            2 => {
+                let (len, used) = read_varint(message)?;
+                message = &message[used..];
+                if key >> 3 == field {
+                    return message.get(..len as usize);
+                }
+                len as usize
+            }
+            _ => return None,
+        };
+        message = message.get(len..)?;
+    }
+    None
+}
+
+/// Steam discovery request (CMsgRemoteClientBroadcastDiscovery).
+pub fn build_steam_discovery(client_id: u64) -> Vec<u8> {
+    let mut header = vec![0x08];
+    push_varint(&mut header, client_id);
+    header.extend_from_slice(&[0x10, 0x01]); // msg_type = discovery
+    let body = [0x08, 0x01]; // seq_num = 1
+    let mut packet = STEAM_DISCOVERY_MAGIC.to_vec();
+    packet.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    packet.extend_from_slice(&header);
+    packet.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    packet.extend_from_slice(&body);
+    packet
+}
+
+/// RustDesk rendezvous RegisterPeer for `id`, which a server answers with a
+/// RegisterPeerResponse.
+pub fn build_rustdesk_register_peer(id: &str) -> Vec<u8> {
+    let mut register = vec![0x0a]; // id = 1, length-delimited
+    push_varint(&mut register, id.len() as u64);
+    register.extend_from_slice(id.as_bytes());
+    let mut message = Vec::new();
+    push_varint(&mut message, (RUSTDESK_REGISTER_PEER << 3) | 2);
+    push_varint(&mut message, register.len() as u64);
+    message.extend_from_slice(&register);
+    message
+}
+
+/// Host name from a Steam discovery status reply; `Some("")` when the reply
+/// is valid but carries no name.
+pub fn parse_steam_status(reply: &[u8]) -> Option<String> {
+    let rest = reply.strip_prefix(STEAM_DISCOVERY_MAGIC)?;
+    let header_len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+    let rest = rest.get(4 + header_len..)?;
+    let body_len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+    let body = rest.get(4..4 + body_len)?;
+    Some(
+        protobuf_bytes(body, 4)
+            .map(|name| String::from_utf8_lossy(name).to_string())
+            .unwrap_or_default(),
+    )
+}
+
+async fn probe_teamviewer(ip: IpAddr, port: u16) -> Result<Option<String>, DetectError> {
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return Err(DetectError::Connect("Connection failed".to_string()));
+    };
+    let exchange = async {
+        stream.write_all(TEAMVIEWER_PING).await?;
+        let mut buf = [0u8; 64];
+        let n = stream.read(&mut buf).await?;
+        Ok::<_, std::io::Error>(buf[..n].to_vec())
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, exchange).await {
+        Ok(Ok(reply)) if is_teamviewer_reply(&reply) => Ok(Some("TeamViewer framing".to_string())),
+        Ok(Ok(_)) => Ok(None),
+        Ok(Err(e)) => Err(DetectError::Connect(format!(
+            "TeamViewer probe failed: {}",
+            e
+        ))),
+        Err(_) => Ok(None),
+    }
+}
+
+async fn probe_anydesk(ip: IpAddr, port: u16) -> Result<Option<String>, DetectError> {
+    let der = detect_tls::fetch_certificate(ip, port).await?;
+    Ok(detect_tls::parse_certificate(&der)
+        .filter(|cert| cert.subject.to_lowercase().contains("anydesk"))
+        .map(|cert| format!("certificate {}", cert.subject)))
+}
+
+/// Sends `tool`'s discovery probe to UDP `port`. Detected only when the
+/// reply is the tool's.
+pub async fn discover(ip: IpAddr, tool: RemoteAdminTool, port: u16) -> RemoteAdminDetection {
+    let exchange = async {
+        let socket = UdpSocket::bind(sockets::local_bind_addr(ip)).await?;
+        socket.connect(sockets::socket_addr(ip, port)).await?;
+        socket.send(&tool.discovery_probe()).await?;
+        let mut buf = [0u8; 2048];
+        let n = socket.recv(&mut buf).await?;
+        Ok::<_, std::io::Error>(buf[..n].to_vec())
+    };
+    let (detail, error) = match tokio::time::timeout(PROBE_TIMEOUT, exchange).await {
+        Ok(Ok(reply)) => match tool.parse_discovery_reply(&reply) {
+            Some(detail) => (Some(detail), None),
+            None => (
+                None,
+                Some(DetectError::Mismatch(format!(
+                    "Not a {} reply",
+                    tool.label()
+                ))),
+            ),
+        },
+        Ok(Err(e)) => (
+            None,
+            Some(DetectError::Connect(format!(
+                "{} discovery failed: {}",
+                tool.label(),
+                e
+            ))),
+        ),
+        Err(_) => (
+            None,
+            Some(DetectError::Timeout(format!(
+                "No {} discovery response",
+                tool.label()
+            ))),
+        ),
+    };
+    RemoteAdminDetection {
+        detected: detail.is_some(),
+        tool,
+        port,
+        confirmed: detail.is_some(),
+        detail,
+        error,
+    }
+}
+
+/// The first of `tool`'s discovery ports that answers as the tool, probed together.
+pub async fn discover_any(ip: IpAddr, tool: RemoteAdminTool) -> Option<RemoteAdminDetection> {
+    join_all(
+        tool.discovery_ports()
+            .iter()
+            .map(|&port| discover(ip, tool, port)),
+    )
+    .await
+    .into_iter()
+    .find(|detection| detection.detected)
+}
+
+async fn accepts_connection(ip: IpAddr, port: u16) -> Result<Option<String>, DetectError> {
+    match tokio::time::timeout(
+        PROBE_TIMEOUT,
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    {
+        Ok(Ok(_)) => Ok(None),
+        _ => Err(DetectError::Connect("Connection failed".to_string())),
+    }
+}
+
+/// Identifies the remote-access tool behind TCP `port`. When the port's own
+/// probe is inconclusive (RustDesk and Steam have no safe unauthenticated
+/// one), the tool's discovery ports are asked; only a matching reply counts
+/// as detected.
+pub async fn detect(ip: IpAddr, port: u16) -> Option<RemoteAdminDetection> {
+    let tool = RemoteAdminTool::from_port(port)?;
+    let probe = match tool {
+        RemoteAdminTool::TeamViewer => probe_teamviewer(ip, port).await,
+        RemoteAdminTool::AnyDesk => probe_anydesk(ip, port).await,
+        RemoteAdminTool::RustDesk | RemoteAdminTool::Steam => accepts_connection(ip, port).await,
+    };
+    let (detail, error) = match probe {
+        Ok(Some(detail)) => (Some(detail), None),
+        Ok(None) => match discover_any(ip, tool).await {
+            Some(discovered) => (discovered.detail, None),
+            None => (None, None),
+        },
+        Err(e) => (None, Some(e)),
+    };
+    Some(RemoteAdminDetection {
+        detected: detail.is_some(),
+        tool,
+        port,
+        confirmed: detail.is_some(),
+        detail,
+        error,
+    })
+}
+
+/// Runs `detect` on every open port that belongs to a known remote-access
+/// tool. Ports that answered without confirming the tool are kept as port
+/// matches (`detected` false); ports whose probe failed are left out.
+pub async fn identify(ip: IpAddr, open_ports: &[u16]) -> Vec<RemoteAdminDetection> {
+    let mut found = Vec::new();
+    for port in open_ports {
+        if let Some(detection) = detect(ip, *port).await
+            && detection.error.is_none()
+        {
+            found.push(detection);
+        }
+    }
+    found
+}
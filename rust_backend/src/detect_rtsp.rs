@@ -0,0 +1,185 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const RTSP_PORT: u16 = 554;
+
+/// Stream paths used by common camera firmware, tried in order by DESCRIBE
+pub const COMMON_STREAM_PATHS: &[&str] = &[
+    "/",
+    "/live",
+    "/stream1",
+    "/h264",
+    "/Streaming/Channels/101",
+    "/cam/realmonitor?channel=1&subtype=0",
+    "/videoMain",
+];
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Status line and headers of an RTSP response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtspResponse {
+    pub status: u16,
+    pub server: Option<String>,
+    /// Methods from the Public header
+    pub public: Vec<String>,
+    pub www_authenticate: Option<String>,
+    pub content_type: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtspDetection {
+    pub detected: bool,
+    pub server: Option<String>,
+    pub methods: Vec<String>,
+    /// First stream path that DESCRIBE returned without credentials
+    pub anonymous_stream: Option<String>,
+    /// Whether DESCRIBE was refused with a credentials challenge
+    pub auth_required: bool,
+    pub error: Option<DetectError>,
+}
+
+impl RtspDetection {
+    pub fn summary(&self) -> String {
+        let access = match (&self.anonymous_stream, self.auth_required) {
+            (Some(path), _) => format!("anonymous stream at {}", path),
+            (None, true) => "authentication required".to_string(),
+            (None, false) => "no stream found".to_string(),
+        };
+        match &self.server {
+            Some(server) => format!("RTSP ({}, {})", server, access),
+            None => format!("RTSP ({})", access),
+        }
+    }
+}
+
+impl From<RtspDetection> for Detection {
+    fn from(rtsp: RtspDetection) -> Self {
+        Detection::new(rtsp.detected, rtsp.summary(), rtsp.error)
+    }
+}
+
+pub fn build_request(method: &str, ip: IpAddr, port: u16, path: &str, cseq: u32) -> String {
+    let accept = if method == "DESCRIBE" {
+        "Accept: application/sdp\r\n"
+    } else {
+        ""
+    };
+    format!(
+        "{} rtsp://{}{} RTSP/1.0\r\nCSeq: {}\r\n{}User-Agent: netscan\r\n\r\n",
+        method,
+        SocketAddr::new(ip, port),
+        path,
+        cseq,
+        accept
+    )
+}
+
+pub fn parse_response(text: &str) -> Option<RtspResponse> {
+    let mut lines = text.lines();
+    let status = lines
+        .next()?
+        .strip_prefix("RTSP/1.0 ")?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    let mut response = RtspResponse {
+        status,
+        server: None,
+        public: Vec::new(),
+        www_authenticate: None,
+        content_type: None,
+    };
+    for line in lines.take_while(|line| !line.is_empty()) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "server" => response.server = Some(value),
+            "public" => {
+                response.public = value
+                    .split(',')
+                    .map(|method| method.trim().to_string())
+                    .filter(|method| !method.is_empty())
+                    .collect()
+            }
+            "www-authenticate" => response.www_authenticate = Some(value),
+            "content-type" => response.content_type = Some(value),
+            _ => {}
+        }
+    }
+    Some(response)
+}
+
+async fn exchange(stream: &mut TcpStream, request: &str) -> Result<RtspResponse, DetectError> {
+    let exchange = async {
+        stream.write_all(request.as_bytes()).await?;
+        let mut buf = vec![0u8; 4096];
+        let n = stream.read(&mut buf).await?;
+        Ok::<_, std::io::Error>(String::from_utf8_lossy(&buf[..n]).to_string())
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, exchange).await {
+        Ok(Ok(reply)) => parse_response(&reply)
+            .ok_or_else(|| DetectError::Mismatch("Not an RTSP response".to_string())),
+        Ok(Err(e)) => Err(DetectError::Connect(format!("RTSP exchange failed: {}", e))),
+        Err(_) => Err(DetectError::Timeout("No RTSP response".to_string())),
+    }
+}
+
+/// Sends OPTIONS, then DESCRIBE for the common stream paths until one is
+/// served or refused with a credentials challenge.
+pub async fn detect(ip: IpAddr, port: u16) -> RtspDetection {
+    let failed = |error: DetectError| RtspDetection {
+        detected: false,
+        server: None,
+        methods: Vec::new(),
+        anonymous_stream: None,
+        auth_required: false,
+        error: Some(error),
+    };
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return failed(DetectError::Connect("Connection failed".to_string()));
+    };
+    let options = match exchange(&mut stream, &build_request("OPTIONS", ip, port, "/", 1)).await {
+        Ok(options) => options,
+        Err(e) => return failed(e),
+    };
+    let mut detection = RtspDetection {
+        detected: true,
+        server: options.server,
+        methods: options.public,
+        anonymous_stream: None,
+        auth_required: false,
+        error: None,
+    };
+    for (cseq, path) in (2..).zip(COMMON_STREAM_PATHS) {
+        let request = build_request("DESCRIBE", ip, port, path, cseq);
+        let Ok(describe) = exchange(&mut stream, &request).await else {
+            break;
+        };
+        let is_sdp = describe
+            .content_type
+            .as_deref()
+            .is_some_and(|ct| ct.contains("sdp"));
+        if describe.status == 200 && is_sdp {
+            detection.anonymous_stream = Some(path.to_string());
+            break;
+        }
+        if describe.status == 401 {
+            detection.auth_required = true;
+            break;
+        }
+    }
+    detection
+}
@@ -0,0 +1,303 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+
+pub const SIP_PORT: u16 = 5060;
+pub const SIPS_PORT: u16 = 5061;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Manufacturers recognized in Server/User-Agent headers, as (needle, name)
+const SIP_VENDORS: &[(&str, &str)] = &[
+    ("yealink", "Yealink"),
+    ("cisco", "Cisco"),
+    ("polycom", "Polycom"),
+    ("grandstream", "Grandstream"),
+    ("snom", "snom"),
+    ("avaya", "Avaya"),
+    ("mitel", "Mitel"),
+    ("fanvil", "Fanvil"),
+    ("gigaset", "Gigaset"),
+    ("panasonic", "Panasonic"),
+    ("linksys", "Linksys"),
+    ("audiocodes", "AudioCodes"),
+    ("3cx", "3CX"),
+    ("fpbx", "FreePBX"),
+    ("asterisk", "Asterisk"),
+    ("freeswitch", "FreeSWITCH"),
+    ("kamailio", "Kamailio"),
+    ("opensips", "OpenSIPS"),
+    ("fritz", "AVM"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SipTransport {
+    Udp,
+    Tcp,
+    Tls,
+}
+
+impl SipTransport {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SipTransport::Udp => "UDP",
+            SipTransport::Tcp => "TCP",
+            SipTransport::Tls => "TLS",
+        }
+    }
+}
+
+/// Parts of a SIP response the detector cares about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SipResponse {
+    pub status: u16,
+    pub reason: String,
+    pub server: Option<String>,
+    pub user_agent: Option<String>,
+    /// Methods from the Allow header
+    pub allow: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SipDetection {
+    pub detected: bool,
+    pub transport: Option<SipTransport>,
+    pub status: Option<u16>,
+    /// Server or User-Agent header, whichever the device sent
+    pub agent: Option<String>,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    pub methods: Vec<String>,
+    pub error: Option<DetectError>,
+}
+
+impl SipDetection {
+    fn failed(error: DetectError) -> Self {
+        Self {
+            detected: false,
+            transport: None,
+            status: None,
+            agent: None,
+            vendor: None,
+            model: None,
+            methods: Vec::new(),
+            error: Some(error),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let transport = self.transport.map_or("?", |t| t.label());
+        match &self.agent {
+            Some(agent) => format!("SIP over {} ({})", transport, agent),
+            None => format!("SIP over {}", transport),
+        }
+    }
+}
+
+impl From<SipDetection> for Detection {
+    fn from(sip: SipDetection) -> Self {
+        let detection = Detection::new(sip.detected, sip.summary(), sip.error);
+        match sip.transport {
+            Some(SipTransport::Udp) => detection.over("udp"),
+            Some(SipTransport::Tcp | SipTransport::Tls) => detection.over("tcp"),
+            None => detection,
+        }
+    }
+}
+
+/// OPTIONS request; `local` goes into Via/From/Contact so replies route back.
+pub fn build_options_request(
+    ip: IpAddr,
+    port: u16,
+    local: SocketAddr,
+    transport: SipTransport,
+) -> String {
+    let scheme = if transport == SipTransport::Tls {
+        "sips"
+    } else {
+        "sip"
+    };
+    format!(
+        "OPTIONS {scheme}:probe@{target} SIP/2.0\r\n\
+Via: SIP/2.0/{via} {local};branch=z9hG4bKnetscan{lport};rport\r\n\
+Max-Forwards: 70\r\n\
+To: <{scheme}:probe@{host}>\r\n\
+From: <{scheme}:netscan@{lip}>;tag=netscan{lport}\r\n\
+Call-ID: netscan-{lport}@{lip}\r\n\
+CSeq: 1 OPTIONS\r\n\
+Contact: <{scheme}:netscan@{local}>\r\n\
+Accept: application/sdp\r\n\
+Content-Length: 0\r\n\r\n",
+        via = transport.label(),
+        target = SocketAddr::new(ip, port),
+        host = sockets::uri_host(ip),
+        lip = sockets::uri_host(local.ip()),
+        lport = local.port(),
+    )
+}
+
+pub fn parse_response(text: &str) -> Option<SipResponse> {
+    let mut lines = text.lines();
+    let status_line = lines.next()?.strip_prefix("SIP/2.0 ")?;
+    let (status, reason) = status_line.split_once(' ').unwrap_or((status_line, ""));
+    let mut response = SipResponse {
+        status: status.trim().parse().ok()?,
+        reason: reason.trim().to_string(),
+        server: None,
+        user_agent: None,
+        allow: Vec::new(),
+    };
+    for line in lines.take_while(|line| !line.is_empty()) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "server" => response.server = Some(value),
+            "user-agent" => response.user_agent = Some(value),
+            "allow" => {
+                response.allow = value
+                    .split(',')
+                    .map(|method| method.trim().to_string())
+                    .filter(|method| !method.is_empty())
+                    .collect()
+            }
+            _ => {}
+        }
+    }
+    Some(response)
+}
+
+/// Vendor and model from a Server/User-Agent value such as
+/// "Yealink SIP-T46S 66.86.0.15" or "Cisco-CP8845/12.8.1".
+pub fn parse_user_agent(agent: &str) -> (Option<String>, Option<String>) {
+    let lower = agent.to_ascii_lowercase();
+    let Some((needle, vendor)) = SIP_VENDORS
+        .iter()
+        .find(|(needle, _)| lower.contains(needle))
+    else {
+        return (None, None);
+    };
+    let start = lower.find(needle).unwrap_or(0) + needle.len();
+    let model = agent[start..]
+        .trim_start_matches(|c: char| c == '-' || c == '_' || c == '/' || c.is_whitespace())
+        .split(|c: char| c == '/' || c.is_whitespace())
+        .next()
+        .filter(|token| !token.is_empty() && !token.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string);
+    (Some(vendor.to_string()), model)
+}
+
+async fn exchange_udp(ip: IpAddr, port: u16) -> Result<String, DetectError> {
+    let exchange = async {
+        let socket = UdpSocket::bind(sockets::local_bind_addr(ip)).await?;
+        socket.connect(sockets::socket_addr(ip, port)).await?;
+        let request = build_options_request(ip, port, socket.local_addr()?, SipTransport::Udp);
+        socket.send(request.as_bytes()).await?;
+        let mut buf = [0u8; 4096];
+        let n = socket.recv(&mut buf).await?;
+        Ok::<_, std::io::Error>(String::from_utf8_lossy(&buf[..n]).to_string())
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, exchange).await {
+        Ok(Ok(reply)) => Ok(reply),
+        Ok(Err(e)) => Err(DetectError::Connect(format!("SIP exchange failed: {}", e))),
+        Err(_) => Err(DetectError::Timeout("No SIP response".to_string())),
+    }
+}
+
+async fn exchange_stream<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    request: &str,
+) -> Result<String, DetectError> {
+    let exchange = async {
+        stream.write_all(request.as_bytes()).await?;
+        let mut buf = [0u8; 4096];
+        let n = stream.read(&mut buf).await?;
+        Ok::<_, std::io::Error>(String::from_utf8_lossy(&buf[..n]).to_string())
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, exchange).await {
+        Ok(Ok(reply)) => Ok(reply),
+        Ok(Err(e)) => Err(DetectError::Connect(format!("SIP exchange failed: {}", e))),
+        Err(_) => Err(DetectError::Timeout("No SIP response".to_string())),
+    }
+}
+
+async fn exchange_tcp(ip: IpAddr, port: u16, tls: bool) -> Result<String, DetectError> {
+    let Ok(Ok(stream)) = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return Err(DetectError::Connect("Connection failed".to_string()));
+    };
+    let local = stream
+        .local_addr()
+        .map_err(|e| DetectError::Connect(format!("SIP exchange failed: {}", e)))?;
+    if !tls {
+        let request = build_options_request(ip, port, local, SipTransport::Tcp);
+        let mut stream = stream;
+        return exchange_stream(&mut stream, &request).await;
+    }
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(|e| DetectError::Connect(format!("TLS setup failed: {}", e)))?;
+    let connector = tokio_native_tls::TlsConnector::from(connector);
+    let mut tls_stream =
+        tokio::time::timeout(PROBE_TIMEOUT, connector.connect(&ip.to_string(), stream))
+            .await
+            .map_err(|_| DetectError::Timeout("Timed out during TLS handshake".to_string()))?
+            .map_err(|e| DetectError::Mismatch(format!("TLS handshake failed: {}", e)))?;
+    let request = build_options_request(ip, port, local, SipTransport::Tls);
+    exchange_stream(&mut tls_stream, &request).await
+}
+
+/// Sends SIP OPTIONS and reads the identifying headers. Port 5061 is tried
+/// over TLS; other ports over UDP first, then TCP.
+pub async fn detect(ip: IpAddr, port: u16) -> SipDetection {
+    let attempts: &[SipTransport] = if port == SIPS_PORT {
+        &[SipTransport::Tls]
+    } else {
+        &[SipTransport::Udp, SipTransport::Tcp]
+    };
+    let mut last_error = DetectError::Mismatch("SIP detection failed".to_string());
+    for transport in attempts {
+        let reply = match transport {
+            SipTransport::Udp => exchange_udp(ip, port).await,
+            SipTransport::Tcp => exchange_tcp(ip, port, false).await,
+            SipTransport::Tls => exchange_tcp(ip, port, true).await,
+        };
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(e) => {
+                last_error = e;
+                continue;
+            }
+        };
+        let Some(response) = parse_response(&reply) else {
+            last_error = DetectError::Mismatch("Not a SIP response".to_string());
+            continue;
+        };
+        let agent = response.server.or(response.user_agent);
+        let (vendor, model) = agent
+            .as_deref()
+            .map(parse_user_agent)
+            .unwrap_or((None, None));
+        return SipDetection {
+            detected: true,
+            transport: Some(*transport),
+            status: Some(response.status),
+            agent,
+            vendor,
+            model,
+            methods: response.allow,
+            error: None,
+        };
+    }
+    SipDetection::failed(last_error)
+}
@@ -0,0 +1,545 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const SMB1_MAGIC: &[u8; 4] = b"\xffSMB";
+const SMB1_NEGOTIATE: u8 = 0x72;
+const SMB1_HEADER_LEN: usize = 32;
+/// The only SMB1 dialect modern servers still speak
+const SMB1_DIALECT: &[u8] = b"NT LM 0.12";
+const SMB2_MAGIC: &[u8; 4] = b"\xfeSMB";
+const SMB2_HEADER_LEN: usize = 64;
+const SMB2_NEGOTIATE: u16 = 0x0000;
+const SMB2_SESSION_SETUP: u16 = 0x0001;
+/// Dialects offered: 2.0.2, 2.1, 3.0, 3.0.2 (3.1.1 needs negotiate contexts)
+const SMB2_DIALECTS: [u16; 4] = [0x0202, 0x0210, 0x0300, 0x0302];
+
+const SIGNING_ENABLED: u16 = 0x0001;
+const SIGNING_REQUIRED: u16 = 0x0002;
+const SMB1_SIGNING_ENABLED: u8 = 0x04;
+const SMB1_SIGNING_REQUIRED: u8 = 0x08;
+
+/// DER-encoded SPNEGO mechanism OIDs and the names they are reported as
+const AUTH_MECHANISMS: &[(&[u8], &str)] = &[
+    (
+        &[
+            0x06, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x02, 0x0a,
+        ],
+        "NTLM",
+    ),
+    (
+        &[
+            0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x12, 0x01, 0x02, 0x02,
+        ],
+        "Kerberos",
+    ),
+    (
+        &[
+            0x06, 0x09, 0x2a, 0x86, 0x48, 0x82, 0xf7, 0x12, 0x01, 0x02, 0x02,
+        ],
+        "MS Kerberos",
+    ),
+    (
+        &[
+            0x06, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x02, 0x1e,
+        ],
+        "NEGOEX",
+    ),
+];
+
+/// NetBIOS session service port; SMB there needs a session request first
+pub const NETBIOS_SESSION_PORT: u16 = 139;
+const NBSS_SESSION_REQUEST: u8 = 0x81;
+const NBSS_POSITIVE_RESPONSE: u8 = 0x82;
+
+const NTLMSSP_SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+const NTLM_NEGOTIATE_VERSION: u32 = 0x0200_0000;
+/// Unicode, request target, NTLM, always sign, extended session security,
+/// target info, version, 128- and 56-bit keys
+const NTLM_NEGOTIATE_FLAGS: u32 = 0xe288_8205;
+/// AV pair IDs in the challenge's target info (MS-NLMP 2.2.2.1)
+const AV_NB_COMPUTER_NAME: u16 = 1;
+const AV_NB_DOMAIN_NAME: u16 = 2;
+const AV_DNS_COMPUTER_NAME: u16 = 3;
+const AV_DNS_DOMAIN_NAME: u16 = 4;
+
+/// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01
+const FILETIME_UNIX_OFFSET: u64 = 11_644_473_600;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmbDetection {
+    pub detected: bool,
+    /// Negotiated dialect, e.g. "3.0.2" (or "NT LM 0.12" for SMB1-only servers)
+    pub dialect: Option<String>,
+    pub signing_enabled: bool,
+    pub signing_required: bool,
+    /// The server negotiated the legacy SMB1 dialect when offered alone
+    pub smb1_accepted: bool,
+    /// Authentication mechanisms advertised in the negotiate security blob
+    pub auth_mechanisms: Vec<String>,
+    /// Server clock from the negotiate response, seconds since the Unix epoch
+    pub server_time: Option<i64>,
+    /// Names and version the server disclosed in its NTLM challenge
+    pub host_info: Option<SmbHostInfo>,
+    pub error: Option<DetectError>,
+}
+
+/// What an NTLM challenge reveals about a server before any authentication
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SmbHostInfo {
+    /// NetBIOS computer name, e.g. "WS01"
+    pub netbios_name: Option<String>,
+    /// NetBIOS domain or workgroup, e.g. "CORP" or "WORKGROUP"
+    pub netbios_domain: Option<String>,
+    pub dns_name: Option<String>,
+    pub dns_domain: Option<String>,
+    /// Windows version the server reports: (major, minor, build)
+    pub os_version: Option<(u8, u8, u16)>,
+}
+
+impl SmbHostInfo {
+    /// Windows release matching `os_version`, e.g. "Windows (10/Server 2016+)".
+    pub fn windows_release(&self) -> Option<&'static str> {
+        let (major, minor, _) = self.os_version?;
+        Some(match (major, minor) {
+            (10, _) => "Windows (10/Server 2016+)",
+            (6, 3) => "Windows (8.1/Server 2012 R2)",
+            (6, 2) => "Windows (8/Server 2012)",
+            (6, 1) => "Windows (7/Server 2008 R2)",
+            (6, 0) => "Windows (Vista/Server 2008)",
+            (5, _) => "Windows (XP/Server 2003)",
+            _ => return None,
+        })
+    }
+}
+
+impl From<SmbDetection> for Detection {
+    fn from(smb: SmbDetection) -> Self {
+        Detection::new(smb.detected, smb.summary(), smb.error)
+    }
+}
+
+pub fn dialect_name(revision: u16) -> String {
+    match revision {
+        0x0202 => "2.0.2".to_string(),
+        0x0210 => "2.1".to_string(),
+        0x0300 => "3.0".to_string(),
+        0x0302 => "3.0.2".to_string(),
+        0x0311 => "3.1.1".to_string(),
+        other => format!("0x{:04x}", other),
+    }
+}
+
+/// SMB2 header for `command`, the `message_id`th request on the connection.
+fn smb2_header(command: u16, message_id: u64) -> Vec<u8> {
+    let mut smb = Vec::with_capacity(SMB2_HEADER_LEN);
+    smb.extend_from_slice(SMB2_MAGIC);
+    smb.extend_from_slice(&64u16.to_le_bytes()); // StructureSize
+    smb.extend_from_slice(&0u16.to_le_bytes()); // CreditCharge
+    smb.extend_from_slice(&0u32.to_le_bytes()); // Status
+    smb.extend_from_slice(&command.to_le_bytes());
+    smb.extend_from_slice(&1u16.to_le_bytes()); // CreditRequest
+    smb.extend_from_slice(&0u32.to_le_bytes()); // Flags
+    smb.extend_from_slice(&0u32.to_le_bytes()); // NextCommand
+    smb.extend_from_slice(&message_id.to_le_bytes());
+    smb.extend_from_slice(&0u32.to_le_bytes()); // Reserved
+    smb.extend_from_slice(&0u32.to_le_bytes()); // TreeId
+    smb.extend_from_slice(&0u64.to_le_bytes()); // SessionId
+    smb.extend_from_slice(&[0u8; 16]); // Signature
+    smb
+}
+
+/// SMB2 NEGOTIATE request wrapped in a NetBIOS session header.
+pub fn build_negotiate_request() -> Vec<u8> {
+    let mut smb = smb2_header(SMB2_NEGOTIATE, 0);
+
+    smb.extend_from_slice(&36u16.to_le_bytes()); // StructureSize
+    smb.extend_from_slice(&(SMB2_DIALECTS.len() as u16).to_le_bytes());
+    smb.extend_from_slice(&SIGNING_ENABLED.to_le_bytes()); // SecurityMode
+    smb.extend_from_slice(&0u16.to_le_bytes()); // Reserved
+    smb.extend_from_slice(&0u32.to_le_bytes()); // Capabilities
+    smb.extend_from_slice(b"netscan-probe-id"); // ClientGuid
+    smb.extend_from_slice(&0u64.to_le_bytes()); // ClientStartTime
+    for dialect in SMB2_DIALECTS {
+        smb.extend_from_slice(&dialect.to_le_bytes());
+    }
+
+    netbios_frame(smb)
+}
+
+/// SMB2 SESSION_SETUP carrying an NTLM NEGOTIATE message, sent after the
+/// negotiate on the same connection. The server answers with an NTLM
+/// challenge naming itself; the session is never completed.
+pub fn build_session_setup_request() -> Vec<u8> {
+    let mut ntlm = NTLMSSP_SIGNATURE.to_vec();
+    ntlm.extend_from_slice(&1u32.to_le_bytes()); // NEGOTIATE_MESSAGE
+    ntlm.extend_from_slice(&NTLM_NEGOTIATE_FLAGS.to_le_bytes());
+    ntlm.extend_from_slice(&[0u8; 8]); // DomainNameFields
+    ntlm.extend_from_slice(&[0u8; 8]); // WorkstationFields
+    ntlm.extend_from_slice(&[6, 1, 0xb1, 0x1d, 0, 0, 0, 15]); // Version 6.1.7601, NTLMv15
+
+    let mut smb = smb2_header(SMB2_SESSION_SETUP, 1);
+    smb.extend_from_slice(&25u16.to_le_bytes()); // StructureSize
+    smb.push(0); // Flags
+    smb.push(SIGNING_ENABLED as u8); // SecurityMode
+    smb.extend_from_slice(&0u32.to_le_bytes()); // Capabilities
+    smb.extend_from_slice(&0u32.to_le_bytes()); // Channel
+    smb.extend_from_slice(&((SMB2_HEADER_LEN + 24) as u16).to_le_bytes()); // SecurityBufferOffset
+    smb.extend_from_slice(&(ntlm.len() as u16).to_le_bytes());
+    smb.extend_from_slice(&0u64.to_le_bytes()); // PreviousSessionId
+    smb.extend(ntlm);
+
+    netbios_frame(smb)
+}
+
+/// NetBIOS session request asking for `*SMBSERVER`, the wildcard name any
+/// SMB server on port 139 answers to.
+pub fn build_session_request() -> Vec<u8> {
+    let mut names = Vec::with_capacity(68);
+    for name in [b"*SMBSERVER".as_slice(), b"NETSCAN"] {
+        let mut padded = [b' '; 16];
+        padded[..name.len()].copy_from_slice(name);
+        // First-level encoding (RFC 1001 § 14.1): each nibble becomes a letter
+        names.push(32);
+        for byte in padded {
+            names.push(b'A' + (byte >> 4));
+            names.push(b'A' + (byte & 0x0f));
+        }
+        names.push(0);
+    }
+    let mut packet = vec![NBSS_SESSION_REQUEST];
+    packet.extend_from_slice(&(names.len() as u32).to_be_bytes()[1..]);
+    packet.extend(names);
+    packet
+}
+
+impl SmbDetection {
+    fn failed(error: DetectError) -> Self {
+        Self {
+            detected: false,
+            dialect: None,
+            signing_enabled: false,
+            signing_required: false,
+            smb1_accepted: false,
+            auth_mechanisms: Vec::new(),
+            server_time: None,
+            host_info: None,
+            error: Some(error),
+        }
+    }
+
+    /// Whether NTLM is offered for authentication.
+    pub fn ntlm_offered(&self) -> bool {
+        self.auth_mechanisms.iter().any(|m| m == "NTLM")
+    }
+
+    /// One-line description, e.g. "SMB 3.0.2 (signing required, SMB1 rejected)".
+    pub fn summary(&self) -> String {
+        let signing = if self.signing_required {
+            "signing required"
+        } else if self.signing_enabled {
+            "signing enabled, not required"
+        } else {
+            "signing disabled"
+        };
+        let smb1 = if self.smb1_accepted {
+            "SMB1 accepted"
+        } else {
+            "SMB1 rejected"
+        };
+        let mut summary = format!(
+            "SMB {} ({}, {}",
+            self.dialect.as_deref().unwrap_or("unknown"),
+            signing,
+            smb1
+        );
+        if !self.auth_mechanisms.is_empty() {
+            summary.push_str(&format!(", auth: {}", self.auth_mechanisms.join("/")));
+        }
+        if let Some(info) = &self.host_info {
+            if let Some(name) = &info.netbios_name {
+                summary.push_str(&format!(", name {}", name));
+            }
+            if let Some(domain) = &info.netbios_domain {
+                summary.push_str(&format!(", domain {}", domain));
+            }
+            if let Some((major, minor, build)) = info.os_version {
+                summary.push_str(&format!(", Windows {}.{} build {}", major, minor, build));
+            }
+        }
+        summary.push(')');
+        summary
+    }
+}
+
+/// Wraps an SMB message in a NetBIOS session header.
+fn netbios_frame(smb: Vec<u8>) -> Vec<u8> {
+    let mut packet = vec![0x00];
+    packet.extend_from_slice(&(smb.len() as u32).to_be_bytes()[1..]);
+    packet.extend(smb);
+    packet
+}
+
+/// SMB1 NEGOTIATE request offering only "NT LM 0.12", wrapped in a NetBIOS
+/// session header. Servers with SMB1 disabled drop the connection.
+pub fn build_smb1_negotiate_request() -> Vec<u8> {
+    let mut smb = Vec::with_capacity(SMB1_HEADER_LEN + 3 + SMB1_DIALECT.len() + 2);
+    smb.extend_from_slice(SMB1_MAGIC);
+    smb.push(SMB1_NEGOTIATE);
+    smb.extend_from_slice(&0u32.to_le_bytes()); // Status
+    smb.push(0x18); // Flags: canonical paths, case-insensitive
+    smb.extend_from_slice(&0xc801u16.to_le_bytes()); // Flags2: unicode, NT status, long names
+    smb.extend_from_slice(&[0u8; 12]); // PIDHigh, SecurityFeatures, Reserved
+    smb.extend_from_slice(&0u16.to_le_bytes()); // TID
+    smb.extend_from_slice(&0xfeffu16.to_le_bytes()); // PIDLow
+    smb.extend_from_slice(&0u16.to_le_bytes()); // UID
+    smb.extend_from_slice(&0u16.to_le_bytes()); // MID
+
+    smb.push(0); // WordCount
+    smb.extend_from_slice(&((SMB1_DIALECT.len() + 2) as u16).to_le_bytes()); // ByteCount
+    smb.push(0x02); // Dialect buffer format
+    smb.extend_from_slice(SMB1_DIALECT);
+    smb.push(0);
+
+    netbios_frame(smb)
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn le_u64(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Parses an SMB2 NEGOTIATE response (without the NetBIOS header).
+pub fn parse_negotiate_response(smb: &[u8]) -> Option<SmbDetection> {
+    if smb.get(..4)? != SMB2_MAGIC {
+        return None;
+    }
+    let body = smb.get(SMB2_HEADER_LEN..)?;
+    let security_mode = le_u16(body, 2)?;
+    let dialect = le_u16(body, 4)?;
+    let server_time = le_u64(body, 40)
+        .filter(|t| *t > 0)
+        .map(|filetime| (filetime / 10_000_000) as i64 - FILETIME_UNIX_OFFSET as i64);
+    // The security buffer offset is relative to the start of the SMB2 header
+    let security_blob = match (le_u16(body, 56), le_u16(body, 58)) {
+        (Some(offset), Some(len)) => smb
+            .get(offset as usize..offset as usize + len as usize)
+            .unwrap_or(&[]),
+        _ => &[],
+    };
+    Some(SmbDetection {
+        detected: true,
+        dialect: Some(dialect_name(dialect)),
+        signing_enabled: security_mode & SIGNING_ENABLED != 0,
+        signing_required: security_mode & SIGNING_REQUIRED != 0,
+        smb1_accepted: false,
+        auth_mechanisms: auth_mechanisms(security_blob),
+        server_time,
+        host_info: None,
+        error: None,
+    })
+}
+
+/// Names of the SPNEGO mechanisms listed in a negotiate security blob.
+pub fn auth_mechanisms(blob: &[u8]) -> Vec<String> {
+    AUTH_MECHANISMS
+        .iter()
+        .filter(|(oid, _)| blob.windows(oid.len()).any(|w| w == *oid))
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Parses an SMB1 NEGOTIATE response (without the NetBIOS header). Returns
+/// `None` unless the server accepted the offered dialect.
+pub fn parse_smb1_negotiate_response(smb: &[u8]) -> Option<SmbDetection> {
+    if smb.get(..4)? != SMB1_MAGIC || *smb.get(4)? != SMB1_NEGOTIATE {
+        return None;
+    }
+    let status = u32::from_le_bytes(smb.get(5..9)?.try_into().ok()?);
+    let word_count = *smb.get(SMB1_HEADER_LEN)?;
+    let dialect_index = le_u16(smb, SMB1_HEADER_LEN + 1)?;
+    if status != 0 || word_count == 0 || dialect_index == 0xffff {
+        return None;
+    }
+    let security_mode = *smb.get(SMB1_HEADER_LEN + 3)?;
+    Some(SmbDetection {
+        detected: true,
+        dialect: Some(String::from_utf8_lossy(SMB1_DIALECT).into_owned()),
+        signing_enabled: security_mode & SMB1_SIGNING_ENABLED != 0,
+        signing_required: security_mode & SMB1_SIGNING_REQUIRED != 0,
+        smb1_accepted: true,
+        auth_mechanisms: Vec::new(),
+        server_time: None,
+        host_info: None,
+        error: None,
+    })
+}
+
+/// Parses the SMB2 SESSION_SETUP response to `build_session_setup_request`
+/// (without the NetBIOS header) for the NTLM challenge it carries.
+pub fn parse_session_setup_response(smb: &[u8]) -> Option<SmbHostInfo> {
+    if smb.get(..4)? != SMB2_MAGIC || le_u16(smb, 12)? != SMB2_SESSION_SETUP {
+        return None;
+    }
+    let body = smb.get(SMB2_HEADER_LEN..)?;
+    let offset = le_u16(body, 4)? as usize;
+    let len = le_u16(body, 6)? as usize;
+    let blob = smb.get(offset..offset + len)?;
+    // The challenge may come wrapped in SPNEGO
+    let start = blob
+        .windows(NTLMSSP_SIGNATURE.len())
+        .position(|window| window == NTLMSSP_SIGNATURE)?;
+    parse_ntlm_challenge(&blob[start..])
+}
+
+/// Reads the names and version out of an NTLM CHALLENGE message.
+pub fn parse_ntlm_challenge(message: &[u8]) -> Option<SmbHostInfo> {
+    if message.get(..8)? != NTLMSSP_SIGNATURE || le_u32(message, 8)? != 2 {
+        return None;
+    }
+    let flags = le_u32(message, 20)?;
+    let mut info = SmbHostInfo::default();
+    if flags & NTLM_NEGOTIATE_VERSION != 0
+        && let Some(version) = message.get(48..52)
+    {
+        info.os_version = Some((version[0], version[1], le_u16(version, 2)?));
+    }
+    let target_len = le_u16(message, 40)? as usize;
+    let target_offset = le_u32(message, 44)? as usize;
+    let mut pairs = message.get(target_offset..target_offset + target_len)?;
+    while let (Some(id), Some(len)) = (le_u16(pairs, 0), le_u16(pairs, 2)) {
+        let Some(value) = pairs.get(4..4 + len as usize) else {
+            break;
+        };
+        let text = || Some(utf16le(value)).filter(|text| !text.is_empty());
+        match id {
+            0 => break, // MsvAvEOL
+            AV_NB_COMPUTER_NAME => info.netbios_name = text(),
+            AV_NB_DOMAIN_NAME => info.netbios_domain = text(),
+            AV_DNS_COMPUTER_NAME => info.dns_name = text(),
+            AV_DNS_DOMAIN_NAME => info.dns_domain = text(),
+            _ => {}
+        }
+        pairs = &pairs[4 + len as usize..];
+    }
+    Some(info)
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn utf16le(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Connects to the SMB server, opening a NetBIOS session first on port 139.
+async fn connect(ip: IpAddr, port: u16) -> Result<TcpStream, DetectError> {
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return Err(DetectError::Connect("Connection failed".to_string()));
+    };
+    if port == NETBIOS_SESSION_PORT {
+        let reply = send(&mut stream, &build_session_request()).await?;
+        if reply.first() != Some(&NBSS_POSITIVE_RESPONSE) {
+            return Err(DetectError::Connect(
+                "NetBIOS session request refused".to_string(),
+            ));
+        }
+    }
+    Ok(stream)
+}
+
+/// Sends `request` and returns the raw 4-byte NetBIOS header of the reply
+/// followed by its payload.
+async fn send(stream: &mut TcpStream, request: &[u8]) -> Result<Vec<u8>, DetectError> {
+    stream
+        .write_all(request)
+        .await
+        .map_err(|e| DetectError::Connect(format!("Write failed: {}", e)))?;
+
+    let read = async {
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await?;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let mut reply = header.to_vec();
+        reply.resize(4 + len, 0);
+        stream.read_exact(&mut reply[4..]).await?;
+        Ok::<_, std::io::Error>(reply)
+    };
+    match tokio::time::timeout(Duration::from_secs(5), read).await {
+        Ok(Ok(reply)) => Ok(reply),
+        // A server that hangs up mid-reply does not speak SMB
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+            Err(DetectError::Mismatch(format!("Read failed: {}", e)))
+        }
+        Ok(Err(e)) => Err(DetectError::Connect(format!("Read failed: {}", e))),
+        Err(_) => Err(DetectError::Timeout(
+            "Timed out waiting for SMB response".to_string(),
+        )),
+    }
+}
+
+/// Sends one NetBIOS-framed request on `stream` and returns the SMB payload of the reply.
+async fn exchange_on(stream: &mut TcpStream, request: &[u8]) -> Result<Vec<u8>, DetectError> {
+    let mut reply = send(stream, request).await?;
+    Ok(reply.split_off(4))
+}
+
+/// Sends one NetBIOS-framed request on a new connection and returns the SMB payload of the reply.
+async fn exchange(ip: IpAddr, port: u16, request: &[u8]) -> Result<Vec<u8>, DetectError> {
+    let mut stream = connect(ip, port).await?;
+    exchange_on(&mut stream, request).await
+}
+
+/// Negotiates SMB2 to read the signing policy and starts an NTLM session
+/// setup for the server's names, then offers SMB1 alone to see whether the
+/// server still accepts it.
+pub async fn detect(ip: IpAddr, port: u16) -> SmbDetection {
+    let mut stream = match connect(ip, port).await {
+        Ok(stream) => stream,
+        Err(e) => return SmbDetection::failed(e),
+    };
+    let mut smb2 = match exchange_on(&mut stream, &build_negotiate_request()).await {
+        Ok(reply) => parse_negotiate_response(&reply).unwrap_or_else(|| {
+            SmbDetection::failed(DetectError::Mismatch(
+                "Not an SMB2 negotiate response".to_string(),
+            ))
+        }),
+        Err(e) => return SmbDetection::failed(e),
+    };
+    if smb2.detected {
+        smb2.host_info = exchange_on(&mut stream, &build_session_setup_request())
+            .await
+            .ok()
+            .and_then(|reply| parse_session_setup_response(&reply));
+    }
+    drop(stream);
+    let smb1 = exchange(ip, port, &build_smb1_negotiate_request())
+        .await
+        .ok()
+        .and_then(|reply| parse_smb1_negotiate_response(&reply));
+
+    match smb1 {
+        Some(smb1) if !smb2.detected => smb1,
+        Some(_) => SmbDetection {
+            smb1_accepted: true,
+            ..smb2
+        },
+        None => smb2,
+    }
+}
@@ -1,4 +1,6 @@
-use std::net::Ipv4Addr;
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
 use std::time::Duration;
 use tokio::net::TcpStream;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -7,18 +9,23 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 pub struct SmtpDetection {
     pub detected: bool,
     pub banner: Option<String>,
-    pub error: Option<String>,
+    pub error: Option<DetectError>,
 }
 
-pub async fn detect(ip: Ipv4Addr, port: u16) -> SmtpDetection {
-    let addr = (ip, port);
+impl From<SmtpDetection> for Detection {
+    fn from(smtp: SmtpDetection) -> Self {
+        Detection::new(smtp.detected, "SMTP".to_string(), smtp.error).with_banner(smtp.banner)
+    }
+}
+
+pub async fn detect(ip: IpAddr, port: u16) -> SmtpDetection {
+    let addr = sockets::socket_addr(ip, port);
     if let Ok(Ok(mut stream)) =
         tokio::time::timeout(Duration::from_secs(5), TcpStream::connect(addr)).await
     {
         let mut buf = vec![0u8; 256];
-        if let Ok(Ok(n)) =
-            tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await
-        {
+        let read = tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await;
+        if let Ok(Ok(n)) = read {
             let banner = String::from_utf8_lossy(&buf[..n]).to_string();
             if banner.contains("SMTP") || banner.contains("ESMTP") {
                 return SmtpDetection {
@@ -31,13 +38,13 @@ pub async fn detect(ip: Ipv4Addr, port: u16) -> SmtpDetection {
         SmtpDetection {
             detected: false,
             banner: None,
-            error: Some("No SMTP banner".to_string()),
+            error: Some(DetectError::unrecognized(read.is_err(), "No SMTP banner")),
         }
     } else {
         SmtpDetection {
             detected: false,
             banner: None,
-            error: Some("Connection failed".to_string()),
+            error: Some(DetectError::Connect("Connection failed".to_string())),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,303 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::ber;
+use crate::utils::sockets;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+/// Community strings tried by the probe, most common first
+pub const DEFAULT_COMMUNITIES: &[&str] = &["public", "private"];
+
+/// sysDescr.0 (1.3.6.1.2.1.1.1.0)
+const OID_SYS_DESCR: &[u8] = &[0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00];
+const PDU_GET_REQUEST: u8 = 0xa0;
+const PDU_RESPONSE: u8 = 0xa2;
+const PDU_REPORT: u8 = 0xa8;
+/// User-based Security Model, the only one SNMPv3 agents implement in practice
+const USM_SECURITY_MODEL: i64 = 3;
+/// msgFlags with only the reportable bit: no auth, no priv
+const MSG_FLAGS_REPORTABLE: u8 = 0x04;
+const MAX_MESSAGE_SIZE: i64 = 65507;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SnmpVersion {
+    V1,
+    V2c,
+}
+
+impl SnmpVersion {
+    /// Value of the message version field.
+    pub fn wire(&self) -> i64 {
+        match self {
+            SnmpVersion::V1 => 0,
+            SnmpVersion::V2c => 1,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SnmpVersion::V1 => "SNMPv1",
+            SnmpVersion::V2c => "SNMPv2c",
+        }
+    }
+}
+
+/// Authoritative engine an SNMPv3 agent revealed during discovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnmpEngine {
+    pub engine_id: Vec<u8>,
+    pub boots: i64,
+    /// Seconds since the engine last booted
+    pub time: i64,
+}
+
+impl SnmpEngine {
+    pub fn id_hex(&self) -> String {
+        self.engine_id
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// IANA enterprise number of the vendor, for RFC 3411 format engine IDs.
+    pub fn enterprise(&self) -> Option<u32> {
+        let prefix: [u8; 4] = self.engine_id.get(..4)?.try_into().ok()?;
+        let value = u32::from_be_bytes(prefix);
+        (value & 0x8000_0000 != 0).then_some(value & 0x7fff_ffff)
+    }
+
+    /// Engine ID in hex with the vendor and the embedded address or text
+    /// decoded where the format allows it.
+    pub fn describe(&self) -> String {
+        let Some(enterprise) = self.enterprise() else {
+            return self.id_hex();
+        };
+        let data = self.engine_id.get(5..).unwrap_or_default();
+        let embedded = match self.engine_id.get(4) {
+            Some(1) if data.len() == 4 => Some(format!(
+                "IPv4 {}",
+                Ipv4Addr::new(data[0], data[1], data[2], data[3])
+            )),
+            Some(3) if data.len() == 6 => Some(format!(
+                "MAC {}",
+                data.iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<Vec<_>>()
+                    .join(":")
+            )),
+            Some(4) => Some(format!("text \"{}\"", String::from_utf8_lossy(data))),
+            _ => None,
+        };
+        match embedded {
+            Some(embedded) => format!(
+                "{} (enterprise {}, {})",
+                self.id_hex(),
+                enterprise,
+                embedded
+            ),
+            None => format!("{} (enterprise {})", self.id_hex(), enterprise),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnmpDetection {
+    pub detected: bool,
+    /// Community-based versions that answered
+    pub versions: Vec<SnmpVersion>,
+    /// Community string the agent accepted
+    pub community: Option<String>,
+    pub sys_descr: Option<String>,
+    /// Engine reported by SNMPv3 discovery
+    pub v3: Option<SnmpEngine>,
+    pub error: Option<DetectError>,
+}
+
+impl SnmpDetection {
+    /// Whether v1/v2c with a guessable community still answers.
+    pub fn community_enabled(&self) -> bool {
+        !self.versions.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(engine) = &self.v3 {
+            parts.push(format!("SNMPv3 engine {}", engine.describe()));
+        }
+        if self.community_enabled() {
+            let versions: Vec<&str> = self.versions.iter().map(|v| v.label()).collect();
+            let label = if self.v3.is_some() {
+                "v1/v2c fallback enabled"
+            } else {
+                "community access"
+            };
+            parts.push(format!(
+                "{}: {} with \"{}\"",
+                label,
+                versions.join(", "),
+                self.community.as_deref().unwrap_or_default()
+            ));
+        } else if self.v3.is_some() {
+            parts.push("v1/v2c disabled".to_string());
+        }
+        format!("SNMP ({})", parts.join("; "))
+    }
+}
+
+impl From<SnmpDetection> for Detection {
+    fn from(snmp: SnmpDetection) -> Self {
+        Detection::new(snmp.detected, snmp.summary(), snmp.error)
+    }
+}
+
+/// GetRequest for sysDescr.0 using community-based authentication.
+pub fn build_get_request(version: SnmpVersion, community: &str, request_id: i64) -> Vec<u8> {
+    let varbind = ber::sequence(&[
+        ber::tlv(ber::TAG_OBJECT_IDENTIFIER, OID_SYS_DESCR),
+        ber::tlv(ber::TAG_NULL, &[]),
+    ]);
+    let pdu = ber::constructed(
+        PDU_GET_REQUEST,
+        &[
+            ber::integer(request_id),
+            ber::integer(0), // error-status
+            ber::integer(0), // error-index
+            ber::sequence(&[varbind]),
+        ],
+    );
+    ber::sequence(&[
+        ber::integer(version.wire()),
+        ber::octet_string(community.as_bytes()),
+        pdu,
+    ])
+}
+
+/// Parses a GetResponse, returning the sysDescr value when the agent
+/// answered without an error.
+pub fn parse_response(reply: &[u8]) -> Option<String> {
+    let (message, _) = ber::read_tlv(reply)?;
+    let pdu = message.child(PDU_RESPONSE)?;
+    let fields = pdu.children()?;
+    if fields.get(1)?.as_integer()? != 0 {
+        return None; // error-status set, e.g. noSuchName
+    }
+    let varbind = fields.get(3)?.children()?.into_iter().next()?;
+    let value = varbind.children()?.get(1).copied()?;
+    Some(value.as_str())
+}
+
+/// Unauthenticated SNMPv3 GetRequest with an empty engine ID. Agents answer
+/// with a usmStatsUnknownEngineIDs report that carries their engine ID.
+pub fn build_discovery_request(msg_id: i64) -> Vec<u8> {
+    let global_data = ber::sequence(&[
+        ber::integer(msg_id),
+        ber::integer(MAX_MESSAGE_SIZE),
+        ber::octet_string(&[MSG_FLAGS_REPORTABLE]),
+        ber::integer(USM_SECURITY_MODEL),
+    ]);
+    let security_parameters = ber::sequence(&[
+        ber::octet_string(&[]), // msgAuthoritativeEngineID
+        ber::integer(0),        // msgAuthoritativeEngineBoots
+        ber::integer(0),        // msgAuthoritativeEngineTime
+        ber::octet_string(&[]), // msgUserName
+        ber::octet_string(&[]), // msgAuthenticationParameters
+        ber::octet_string(&[]), // msgPrivacyParameters
+    ]);
+    let pdu = ber::constructed(
+        PDU_GET_REQUEST,
+        &[
+            ber::integer(msg_id),
+            ber::integer(0),
+            ber::integer(0),
+            ber::sequence(&[]),
+        ],
+    );
+    let scoped_pdu = ber::sequence(&[ber::octet_string(&[]), ber::octet_string(&[]), pdu]);
+    ber::sequence(&[
+        ber::integer(3),
+        global_data,
+        ber::octet_string(&security_parameters),
+        scoped_pdu,
+    ])
+}
+
+/// Extracts the authoritative engine from a discovery report.
+pub fn parse_discovery_response(reply: &[u8]) -> Option<SnmpEngine> {
+    let (message, _) = ber::read_tlv(reply)?;
+    let fields = message.children()?;
+    if fields.first()?.as_integer()? != 3 {
+        return None;
+    }
+    let (usm, _) = ber::read_tlv(fields.get(2)?.value)?;
+    let usm = usm.children()?;
+    let engine_id = usm.first()?.value.to_vec();
+    if engine_id.is_empty() {
+        return None;
+    }
+    // Plaintext scoped PDU should be a report; anything else isn't discovery
+    let scoped = fields.get(3)?.children()?;
+    if scoped.get(2)?.tag != PDU_REPORT {
+        return None;
+    }
+    Some(SnmpEngine {
+        engine_id,
+        boots: usm.get(1)?.as_integer()?,
+        time: usm.get(2)?.as_integer()?,
+    })
+}
+
+async fn exchange(ip: IpAddr, port: u16, request: &[u8]) -> Result<Vec<u8>, DetectError> {
+    let exchange = async {
+        let socket = UdpSocket::bind(sockets::local_bind_addr(ip)).await?;
+        socket.connect(sockets::socket_addr(ip, port)).await?;
+        socket.send(request).await?;
+        let mut buf = [0u8; 1500];
+        let n = socket.recv(&mut buf).await?;
+        Ok::<_, std::io::Error>(buf[..n].to_vec())
+    };
+    match tokio::time::timeout(Duration::from_secs(2), exchange).await {
+        Ok(Ok(reply)) => Ok(reply),
+        Ok(Err(e)) => Err(DetectError::Connect(format!("SNMP exchange failed: {}", e))),
+        Err(_) => Err(DetectError::Timeout("No SNMP response".to_string())),
+    }
+}
+
+/// Runs SNMPv3 engine discovery, then tries SNMPv1 and v2c GetRequests with
+/// the given communities and records which versions answer.
+pub async fn detect(ip: IpAddr, port: u16, communities: &[&str]) -> SnmpDetection {
+    let mut detection = SnmpDetection {
+        detected: false,
+        versions: Vec::new(),
+        community: None,
+        sys_descr: None,
+        v3: None,
+        error: None,
+    };
+    match exchange(ip, port, &build_discovery_request(0x4e53)).await {
+        Ok(reply) => detection.v3 = parse_discovery_response(&reply),
+        Err(e) => detection.error = Some(e),
+    }
+    for community in communities {
+        for (request_id, version) in [SnmpVersion::V1, SnmpVersion::V2c].into_iter().enumerate() {
+            let request = build_get_request(version, community, request_id as i64 + 1);
+            match exchange(ip, port, &request).await {
+                Ok(reply) => {
+                    if let Some(descr) = parse_response(&reply) {
+                        detection.versions.push(version);
+                        detection.sys_descr.get_or_insert(descr);
+                    }
+                }
+                Err(e) => detection.error = Some(e),
+            }
+        }
+        if !detection.versions.is_empty() {
+            detection.community = Some(community.to_string());
+            break;
+        }
+    }
+    detection.detected = detection.v3.is_some() || detection.community_enabled();
+    if detection.detected {
+        detection.error = None;
+    }
+    detection
+}
@@ -1,4 +1,6 @@
-use std::net::Ipv4Addr;
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
 use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
@@ -7,18 +9,23 @@ use tokio::net::TcpStream;
 pub struct SshDetection {
     pub banner: Option<String>,
     pub detected: bool,
-    pub error: Option<String>,
+    pub error: Option<DetectError>,
 }
 
-pub async fn detect(ip: Ipv4Addr, port: u16) -> SshDetection {
-    let addr = (ip, port);
+impl From<SshDetection> for Detection {
+    fn from(ssh: SshDetection) -> Self {
+        Detection::new(ssh.detected, "SSH".to_string(), ssh.error).with_banner(ssh.banner)
+    }
+}
+
+pub async fn detect(ip: IpAddr, port: u16) -> SshDetection {
+    let addr = sockets::socket_addr(ip, port);
     if let Ok(Ok(mut stream)) =
         tokio::time::timeout(Duration::from_secs(8), TcpStream::connect(addr)).await
     {
         let mut buf = vec![0u8; 256];
-        if let Ok(Ok(n)) =
-            tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await
-        {
+        let first = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await;
+        if let Ok(Ok(n)) = first {
             let banner = String::from_utf8_lossy(&buf[..n]).to_string();
             if banner.starts_with("SSH-") {
                 return SshDetection {
@@ -30,9 +37,8 @@ pub async fn detect(ip: Ipv4Addr, port: u16) -> SshDetection {
         }
         let _ = stream.write_all(b"\n").await;
         let mut buf2 = vec![0u8; 256];
-        if let Ok(Ok(n)) =
-            tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf2)).await
-        {
+        let second = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf2)).await;
+        if let Ok(Ok(n)) = second {
             let banner = String::from_utf8_lossy(&buf2[..n]).to_string();
             if banner.starts_with("SSH-") {
                 return SshDetection {
@@ -45,13 +51,16 @@ pub async fn detect(ip: Ipv4Addr, port: u16) -> SshDetection {
         SshDetection {
             banner: None,
             detected: false,
-            error: Some("No SSH banner found".to_string()),
+            error: Some(DetectError::unrecognized(
+                first.is_err() && second.is_err(),
+                "No SSH banner found",
+            )),
         }
     } else {
         SshDetection {
             banner: None,
             detected: false,
-            error: Some("Connection failed".to_string()),
+            error: Some(DetectError::Connect("Connection failed".to_string())),
         }
     }
 }
@@ -63,7 +72,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_detect_ssh_on_localhost() {
-        let ip = Ipv4Addr::LOCALHOST;
+        let ip = Ipv4Addr::LOCALHOST.into();
         let port = 22;
         let result = detect(ip, port).await;
         assert!(result.detected || result.error.is_some());
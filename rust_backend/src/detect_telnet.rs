@@ -0,0 +1,147 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::banners;
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const TELNET_IAC: u8 = 0xff;
+/// Words a login prompt or pre-login banner of a Telnet service contains
+const PROMPT_KEYWORDS: &[&str] = &[
+    "login:",
+    "username:",
+    "password:",
+    "user access verification",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelnetDetection {
+    pub detected: bool,
+    /// Options the server asked for or offered, by name, e.g. ["ECHO", "NAWS"]
+    pub options: Vec<String>,
+    /// Last non-empty line the server printed, usually its login prompt
+    pub prompt: Option<String>,
+    pub error: Option<DetectError>,
+}
+
+impl TelnetDetection {
+    fn failed(error: DetectError) -> Self {
+        Self {
+            detected: false,
+            options: Vec::new(),
+            prompt: None,
+            error: Some(error),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(prompt) = &self.prompt {
+            parts.push(format!("prompt \"{}\"", prompt));
+        }
+        if !self.options.is_empty() {
+            parts.push(format!("options {}", self.options.join("/")));
+        }
+        if parts.is_empty() {
+            return "Telnet".to_string();
+        }
+        format!("Telnet ({})", parts.join(", "))
+    }
+}
+
+impl From<TelnetDetection> for Detection {
+    fn from(telnet: TelnetDetection) -> Self {
+        Detection::new(telnet.detected, telnet.summary(), telnet.error)
+    }
+}
+
+/// Name of a Telnet option code (RFC 855 and the IANA registry).
+pub fn option_name(option: u8) -> String {
+    match option {
+        0 => "BINARY".to_string(),
+        1 => "ECHO".to_string(),
+        3 => "SUPPRESS-GO-AHEAD".to_string(),
+        5 => "STATUS".to_string(),
+        24 => "TERMINAL-TYPE".to_string(),
+        31 => "NAWS".to_string(),
+        32 => "TERMINAL-SPEED".to_string(),
+        33 => "LFLOW".to_string(),
+        34 => "LINEMODE".to_string(),
+        35 => "X-DISPLAY-LOCATION".to_string(),
+        36 => "ENVIRON".to_string(),
+        39 => "NEW-ENVIRON".to_string(),
+        other => format!("option {}", other),
+    }
+}
+
+/// Reads what a Telnet server sent: option negotiation or a login prompt
+/// identify it.
+pub fn parse_greeting(input: &[u8]) -> Option<TelnetDetection> {
+    let (text, replies) = banners::strip_telnet_negotiation(input);
+    let mut options: Vec<String> = Vec::new();
+    // Every refusal is IAC, DONT/WONT, option
+    for option in replies.chunks_exact(3).map(|reply| option_name(reply[2])) {
+        if !options.contains(&option) {
+            options.push(option);
+        }
+    }
+    let text = String::from_utf8_lossy(&text);
+    let prompt = text
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map(String::from);
+    let negotiated = input.first() == Some(&TELNET_IAC);
+    let lower = text.to_lowercase();
+    if !negotiated
+        && !PROMPT_KEYWORDS
+            .iter()
+            .any(|keyword| lower.contains(keyword))
+    {
+        return None;
+    }
+    Some(TelnetDetection {
+        detected: true,
+        options,
+        prompt,
+        error: None,
+    })
+}
+
+/// Reads the server's opening bytes, refuses every option it proposes and
+/// reads on until its prompt appears.
+pub async fn detect(ip: IpAddr, port: u16) -> TelnetDetection {
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return TelnetDetection::failed(DetectError::Connect("Connection failed".to_string()));
+    };
+    let mut received = Vec::new();
+    let mut buf = [0u8; 1024];
+    // Negotiation usually arrives before the prompt, so allow a few rounds
+    for _ in 0..4 {
+        let n = match tokio::time::timeout(Duration::from_secs(2), stream.read(&mut buf)).await {
+            Ok(Ok(n)) if n > 0 => n,
+            _ => break,
+        };
+        let (_, replies) = banners::strip_telnet_negotiation(&buf[..n]);
+        received.extend_from_slice(&buf[..n]);
+        if replies.is_empty() {
+            break;
+        }
+        if stream.write_all(&replies).await.is_err() {
+            break;
+        }
+    }
+    // Nothing at all arrived before the reads gave up
+    parse_greeting(&received).unwrap_or_else(|| {
+        TelnetDetection::failed(DetectError::unrecognized(
+            received.is_empty(),
+            "No Telnet negotiation or login prompt",
+        ))
+    })
+}
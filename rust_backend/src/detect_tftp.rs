@@ -0,0 +1,114 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+/// File requested by the probe; servers answer with "file not found" or the data
+pub const PROBE_FILENAME: &str = "netscan-probe.txt";
+
+const OPCODE_READ_REQUEST: u16 = 1;
+const OPCODE_DATA: u16 = 3;
+const OPCODE_ERROR: u16 = 5;
+
+/// How a TFTP server answered the read request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TftpReply {
+    /// The server started sending the file
+    Data {
+        block: u16,
+    },
+    Error {
+        code: u16,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TftpDetection {
+    pub detected: bool,
+    pub reply: Option<TftpReply>,
+    pub error: Option<DetectError>,
+}
+
+impl TftpDetection {
+    pub fn summary(&self) -> String {
+        match &self.reply {
+            Some(TftpReply::Data { .. }) => format!("TFTP (served {})", PROBE_FILENAME),
+            Some(TftpReply::Error { code, message }) => {
+                format!("TFTP (error {}: {})", code, message)
+            }
+            None => "TFTP".to_string(),
+        }
+    }
+}
+
+impl From<TftpDetection> for Detection {
+    fn from(tftp: TftpDetection) -> Self {
+        Detection::new(tftp.detected, tftp.summary(), tftp.error)
+    }
+}
+
+/// RRQ packet (RFC 1350) for `filename` in octet mode.
+pub fn build_read_request(filename: &str) -> Vec<u8> {
+    let mut packet = OPCODE_READ_REQUEST.to_be_bytes().to_vec();
+    packet.extend_from_slice(filename.as_bytes());
+    packet.push(0);
+    packet.extend_from_slice(b"octet\0");
+    packet
+}
+
+pub fn parse_response(reply: &[u8]) -> Option<TftpReply> {
+    let opcode = u16::from_be_bytes(reply.get(..2)?.try_into().ok()?);
+    let value = u16::from_be_bytes(reply.get(2..4)?.try_into().ok()?);
+    match opcode {
+        OPCODE_DATA => Some(TftpReply::Data { block: value }),
+        OPCODE_ERROR => {
+            let text = &reply[4..];
+            let end = text.iter().position(|b| *b == 0).unwrap_or(text.len());
+            Some(TftpReply::Error {
+                code: value,
+                message: String::from_utf8_lossy(&text[..end]).to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Sends a read request and waits for DATA or ERROR. The server answers from
+/// a fresh port, so any datagram from `ip` is accepted.
+pub async fn detect(ip: IpAddr, port: u16) -> TftpDetection {
+    let failed = |error: DetectError| TftpDetection {
+        detected: false,
+        reply: None,
+        error: Some(error),
+    };
+    let exchange = async {
+        let socket = UdpSocket::bind(sockets::local_bind_addr(ip)).await?;
+        socket
+            .send_to(
+                &build_read_request(PROBE_FILENAME),
+                sockets::socket_addr(ip, port),
+            )
+            .await?;
+        let mut buf = [0u8; 600];
+        loop {
+            let (n, from) = socket.recv_from(&mut buf).await?;
+            if from.ip() == ip {
+                return Ok::<_, std::io::Error>(buf[..n].to_vec());
+            }
+        }
+    };
+    match tokio::time::timeout(Duration::from_secs(3), exchange).await {
+        Ok(Ok(reply)) => match parse_response(&reply) {
+            Some(reply) => TftpDetection {
+                detected: true,
+                reply: Some(reply),
+                error: None,
+            },
+            None => failed(DetectError::Mismatch("Not a TFTP reply".to_string())),
+        },
+        Ok(Err(e)) => failed(DetectError::Connect(format!("TFTP exchange failed: {}", e))),
+        Err(_) => failed(DetectError::Timeout("No TFTP response".to_string())),
+    }
+}
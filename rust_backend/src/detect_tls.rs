@@ -0,0 +1,660 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::ber::{self, Tlv};
+use crate::utils::sockets;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const CONTENT_HANDSHAKE: u8 = 0x16;
+const CONTENT_ALERT: u8 = 0x15;
+const HANDSHAKE_CLIENT_HELLO: u8 = 0x01;
+const HANDSHAKE_SERVER_HELLO: u8 = 0x02;
+const EXT_SERVER_NAME: u16 = 0x0000;
+const EXT_SUPPORTED_GROUPS: u16 = 0x000a;
+const EXT_EC_POINT_FORMATS: u16 = 0x000b;
+const EXT_SIGNATURE_ALGORITHMS: u16 = 0x000d;
+const EXT_ALPN: u16 = 0x0010;
+const EXT_SUPPORTED_VERSIONS: u16 = 0x002b;
+const EXT_KEY_SHARE: u16 = 0x0033;
+const EXT_RENEGOTIATION_INFO: u16 = 0xff01;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Application protocols offered when asking a server which one it speaks
+pub const ALPN_PROTOCOLS: &[&str] = &["h2", "http/1.1"];
+
+/// OIDs (DER content bytes) of the name attributes used to label certificates
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const OID_ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0a];
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+/// Named curves and their key sizes in bits
+const EC_CURVES: &[(&[u8], u32)] = &[
+    (&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07], 256),
+    (&[0x2b, 0x81, 0x04, 0x00, 0x22], 384),
+    (&[0x2b, 0x81, 0x04, 0x00, 0x23], 521),
+];
+/// GeneralName choices in a subjectAltName (context-specific, primitive)
+const SAN_DNS_NAME: u8 = 0x82;
+const SAN_IP_ADDRESS: u8 = 0x87;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TlsVersion {
+    Ssl3,
+    Tls10,
+    Tls11,
+    Tls12,
+    Tls13,
+}
+
+impl TlsVersion {
+    pub const ALL: [TlsVersion; 5] = [
+        TlsVersion::Ssl3,
+        TlsVersion::Tls10,
+        TlsVersion::Tls11,
+        TlsVersion::Tls12,
+        TlsVersion::Tls13,
+    ];
+
+    /// Protocol version as it appears on the wire.
+    pub fn wire(&self) -> u16 {
+        match self {
+            TlsVersion::Ssl3 => 0x0300,
+            TlsVersion::Tls10 => 0x0301,
+            TlsVersion::Tls11 => 0x0302,
+            TlsVersion::Tls12 => 0x0303,
+            TlsVersion::Tls13 => 0x0304,
+        }
+    }
+
+    pub fn from_wire(version: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.wire() == version)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TlsVersion::Ssl3 => "SSLv3",
+            TlsVersion::Tls10 => "TLS 1.0",
+            TlsVersion::Tls11 => "TLS 1.1",
+            TlsVersion::Tls12 => "TLS 1.2",
+            TlsVersion::Tls13 => "TLS 1.3",
+        }
+    }
+}
+
+/// How much a cipher suite can be trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CipherStrength {
+    Strong,
+    /// Deprecated but not trivially broken (RC4, 3DES)
+    Weak,
+    /// No real protection (NULL, EXPORT, single DES, anonymous)
+    Insecure,
+}
+
+/// Cipher suites offered to SSLv3-TLS 1.2 servers: a bounded probe set
+/// covering modern suites and the legacy ones worth flagging.
+pub const CIPHER_SUITES: &[(u16, &str, CipherStrength)] = &[
+    (
+        0xc02b,
+        "ECDHE-ECDSA-AES128-GCM-SHA256",
+        CipherStrength::Strong,
+    ),
+    (
+        0xc02c,
+        "ECDHE-ECDSA-AES256-GCM-SHA384",
+        CipherStrength::Strong,
+    ),
+    (
+        0xc02f,
+        "ECDHE-RSA-AES128-GCM-SHA256",
+        CipherStrength::Strong,
+    ),
+    (
+        0xc030,
+        "ECDHE-RSA-AES256-GCM-SHA384",
+        CipherStrength::Strong,
+    ),
+    (
+        0xcca8,
+        "ECDHE-RSA-CHACHA20-POLY1305",
+        CipherStrength::Strong,
+    ),
+    (
+        0xcca9,
+        "ECDHE-ECDSA-CHACHA20-POLY1305",
+        CipherStrength::Strong,
+    ),
+    (0x009e, "DHE-RSA-AES128-GCM-SHA256", CipherStrength::Strong),
+    (0x009c, "RSA-AES128-GCM-SHA256", CipherStrength::Strong),
+    (0x009d, "RSA-AES256-GCM-SHA384", CipherStrength::Strong),
+    (0xc013, "ECDHE-RSA-AES128-SHA", CipherStrength::Strong),
+    (0xc014, "ECDHE-RSA-AES256-SHA", CipherStrength::Strong),
+    (0x002f, "RSA-AES128-SHA", CipherStrength::Strong),
+    (0x0035, "RSA-AES256-SHA", CipherStrength::Strong),
+    (0xc012, "ECDHE-RSA-3DES-EDE-CBC-SHA", CipherStrength::Weak),
+    (0x000a, "RSA-3DES-EDE-CBC-SHA", CipherStrength::Weak),
+    (0xc011, "ECDHE-RSA-RC4-128-SHA", CipherStrength::Weak),
+    (0x0005, "RSA-RC4-128-SHA", CipherStrength::Weak),
+    (0x0004, "RSA-RC4-128-MD5", CipherStrength::Weak),
+    (0x0009, "RSA-DES-CBC-SHA", CipherStrength::Insecure),
+    (0x0008, "RSA-EXPORT-DES40-CBC-SHA", CipherStrength::Insecure),
+    (0x0003, "RSA-EXPORT-RC4-40-MD5", CipherStrength::Insecure),
+    (0x0034, "DH-anon-AES128-SHA", CipherStrength::Insecure),
+    (0x0018, "DH-anon-RC4-128-MD5", CipherStrength::Insecure),
+    (0x0002, "RSA-NULL-SHA", CipherStrength::Insecure),
+    (0x0001, "RSA-NULL-MD5", CipherStrength::Insecure),
+];
+
+/// TLS 1.3 suites; all of them are strong
+pub const TLS13_CIPHER_SUITES: &[(u16, &str, CipherStrength)] = &[
+    (0x1301, "TLS_AES_128_GCM_SHA256", CipherStrength::Strong),
+    (0x1302, "TLS_AES_256_GCM_SHA384", CipherStrength::Strong),
+    (
+        0x1303,
+        "TLS_CHACHA20_POLY1305_SHA256",
+        CipherStrength::Strong,
+    ),
+];
+
+/// Name and strength of a cipher suite from the probe set.
+pub fn cipher_info(id: u16) -> Option<(&'static str, CipherStrength)> {
+    CIPHER_SUITES
+        .iter()
+        .chain(TLS13_CIPHER_SUITES)
+        .find(|(suite, _, _)| *suite == id)
+        .map(|(_, name, strength)| (*name, *strength))
+}
+
+/// What the server chose in its ServerHello (or HelloRetryRequest)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerHello {
+    pub version: u16,
+    pub cipher: u16,
+}
+
+/// Application protocol the server picked from the ClientHello's ALPN list.
+/// TLS 1.3 servers send it encrypted, so only a 1.2 ServerHello carries it.
+pub fn parse_alpn(reply: &[u8]) -> Option<String> {
+    let extensions = server_hello_extensions(reply)?;
+    let body = extensions
+        .into_iter()
+        .find(|(id, _)| *id == EXT_ALPN)
+        .map(|(_, body)| body)?;
+    // protocol_name_list with exactly one entry: u16 list length, u8 name length, name
+    let len = *body.get(2)? as usize;
+    let name = body.get(3..3 + len)?;
+    Some(String::from_utf8_lossy(name).into_owned())
+}
+
+fn extension(id: u16, body: &[u8]) -> Vec<u8> {
+    let mut ext = id.to_be_bytes().to_vec();
+    ext.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    ext.extend_from_slice(body);
+    ext
+}
+
+/// Prefixes `body` with its length as a big-endian u16.
+fn with_u16_len(body: &[u8]) -> Vec<u8> {
+    let mut out = (body.len() as u16).to_be_bytes().to_vec();
+    out.extend_from_slice(body);
+    out
+}
+
+/// A ClientHello record offering only `version` and the given cipher suites.
+pub fn build_client_hello(
+    version: TlsVersion,
+    ciphers: &[u16],
+    server_name: Option<&str>,
+) -> Vec<u8> {
+    build_client_hello_with_alpn(version, ciphers, server_name, &[])
+}
+
+/// `build_client_hello` that also offers the application protocols `alpn`.
+pub fn build_client_hello_with_alpn(
+    version: TlsVersion,
+    ciphers: &[u16],
+    server_name: Option<&str>,
+    alpn: &[&str],
+) -> Vec<u8> {
+    // TLS 1.3 is negotiated through supported_versions; the legacy field stays at 1.2
+    let client_version = version.min(TlsVersion::Tls12).wire();
+    let mut hello = client_version.to_be_bytes().to_vec();
+    hello.extend_from_slice(&[0x4e; 32]); // Random
+    hello.push(0); // Session ID
+    let suites: Vec<u8> = ciphers.iter().flat_map(|c| c.to_be_bytes()).collect();
+    hello.extend(with_u16_len(&suites));
+    hello.extend_from_slice(&[1, 0]); // Compression: null only
+
+    if version != TlsVersion::Ssl3 {
+        let mut extensions = Vec::new();
+        if let Some(name) = server_name {
+            let mut entry = vec![0]; // host_name
+            entry.extend(with_u16_len(name.as_bytes()));
+            extensions.extend(extension(EXT_SERVER_NAME, &with_u16_len(&entry)));
+        }
+        // x25519, secp256r1, secp384r1
+        extensions.extend(extension(
+            EXT_SUPPORTED_GROUPS,
+            &with_u16_len(&[0x00, 0x1d, 0x00, 0x17, 0x00, 0x18]),
+        ));
+        extensions.extend(extension(EXT_EC_POINT_FORMATS, &[1, 0]));
+        let signature_algorithms: Vec<u8> = [
+            0x0403u16, 0x0804, 0x0401, 0x0503, 0x0805, 0x0501, 0x0601, 0x0201,
+        ]
+        .iter()
+        .flat_map(|a| a.to_be_bytes())
+        .collect();
+        extensions.extend(extension(
+            EXT_SIGNATURE_ALGORITHMS,
+            &with_u16_len(&signature_algorithms),
+        ));
+        if version == TlsVersion::Tls13 {
+            extensions.extend(extension(EXT_SUPPORTED_VERSIONS, &[2, 0x03, 0x04]));
+            // No shares: the server answers with a HelloRetryRequest, which
+            // is enough to learn it speaks TLS 1.3 without doing key exchange
+            extensions.extend(extension(EXT_KEY_SHARE, &[0, 0]));
+        }
+        if !alpn.is_empty() {
+            let names: Vec<u8> = alpn
+                .iter()
+                .flat_map(|name| {
+                    let mut entry = vec![name.len() as u8];
+                    entry.extend_from_slice(name.as_bytes());
+                    entry
+                })
+                .collect();
+            extensions.extend(extension(EXT_ALPN, &with_u16_len(&names)));
+        }
+        extensions.extend(extension(EXT_RENEGOTIATION_INFO, &[0]));
+        hello.extend(with_u16_len(&extensions));
+    }
+
+    let mut handshake = vec![HANDSHAKE_CLIENT_HELLO];
+    handshake.extend_from_slice(&(hello.len() as u32).to_be_bytes()[1..]);
+    handshake.extend(hello);
+
+    let record_version = if version == TlsVersion::Ssl3 {
+        0x0300u16
+    } else {
+        0x0301
+    };
+    let mut record = vec![CONTENT_HANDSHAKE];
+    record.extend_from_slice(&record_version.to_be_bytes());
+    record.extend(with_u16_len(&handshake));
+    record
+}
+
+fn be_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+/// Parses the first record of a server's reply. Returns `None` for alerts
+/// and anything that is not a ServerHello.
+pub fn parse_server_hello(reply: &[u8]) -> Option<ServerHello> {
+    let hello = server_hello_body(reply)?;
+    let mut version = be_u16(hello, 0)?;
+    let session_id_len = *hello.get(34)? as usize;
+    let cipher = be_u16(hello, 35 + session_id_len)?;
+    for (id, body) in server_hello_extensions(reply)? {
+        if id == EXT_SUPPORTED_VERSIONS {
+            version = be_u16(body, 0)?;
+        }
+    }
+    Some(ServerHello { version, cipher })
+}
+
+/// The ServerHello message in the first record of `reply`, without its header.
+fn server_hello_body(reply: &[u8]) -> Option<&[u8]> {
+    if *reply.first()? != CONTENT_HANDSHAKE {
+        return None;
+    }
+    let handshake = reply.get(5..)?;
+    if *handshake.first()? != HANDSHAKE_SERVER_HELLO {
+        return None;
+    }
+    handshake.get(4..)
+}
+
+/// (id, body) of each ServerHello extension; empty if it has none.
+fn server_hello_extensions(reply: &[u8]) -> Option<Vec<(u16, &[u8])>> {
+    let hello = server_hello_body(reply)?;
+    let session_id_len = *hello.get(34)? as usize;
+    let at = 35 + session_id_len + 3; // cipher suite and compression method
+    let Some(extensions_len) = be_u16(hello, at) else {
+        return Some(Vec::new());
+    };
+    let extensions = hello.get(at + 2..at + 2 + extensions_len as usize)?;
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while let (Some(id), Some(len)) = (be_u16(extensions, pos), be_u16(extensions, pos + 2)) {
+        found.push((id, extensions.get(pos + 4..pos + 4 + len as usize)?));
+        pos += 4 + len as usize;
+    }
+    Some(found)
+}
+
+/// Sends one ClientHello and returns the server's choice, if it accepted.
+pub async fn probe(
+    ip: IpAddr,
+    port: u16,
+    version: TlsVersion,
+    ciphers: &[u16],
+) -> Result<Option<ServerHello>, String> {
+    let reply = exchange_hello(ip, port, &build_client_hello(version, ciphers, None)).await?;
+    Ok(reply.as_deref().and_then(parse_server_hello))
+}
+
+/// Offers `ALPN_PROTOCOLS` over TLS 1.2 and returns the one the server picked.
+pub async fn negotiate_alpn(ip: IpAddr, port: u16) -> Result<Option<String>, String> {
+    let ciphers: Vec<u16> = CIPHER_SUITES.iter().map(|(suite, _, _)| *suite).collect();
+    let hello = build_client_hello_with_alpn(TlsVersion::Tls12, &ciphers, None, ALPN_PROTOCOLS);
+    let reply = exchange_hello(ip, port, &hello).await?;
+    Ok(reply.as_deref().and_then(parse_alpn))
+}
+
+/// Sends a ClientHello record and returns the first record of the reply, or
+/// `None` if the server refused it.
+async fn exchange_hello(ip: IpAddr, port: u16, hello: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return Err("Connection failed".to_string());
+    };
+    stream
+        .write_all(hello)
+        .await
+        .map_err(|e| format!("Write failed: {}", e))?;
+
+    let read = async {
+        let mut header = [0u8; 5];
+        stream.read_exact(&mut header).await?;
+        let len = u16::from_be_bytes([header[3], header[4]]) as usize;
+        let mut reply = header.to_vec();
+        reply.resize(5 + len, 0);
+        stream.read_exact(&mut reply[5..]).await?;
+        Ok::<_, std::io::Error>(reply)
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, read).await {
+        Ok(Ok(reply)) if reply[0] == CONTENT_ALERT => Ok(None),
+        Ok(Ok(reply)) => Ok(Some(reply)),
+        // Servers commonly just close the connection on a hello they dislike
+        Ok(Err(_)) => Ok(None),
+        Err(_) => Err("Timed out waiting for ServerHello".to_string()),
+    }
+}
+
+/// Identity, names, key and validity of an X.509 certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateInfo {
+    /// Serial number in hex; unique together with the issuer
+    pub serial: String,
+    pub subject: String,
+    pub issuer: String,
+    /// DNS names and IP addresses from the subjectAltName extension
+    pub sans: Vec<String>,
+    /// Public key algorithm, e.g. "RSA" or "EC"
+    pub key_algorithm: String,
+    pub key_bits: Option<u32>,
+    /// Hash of the SubjectPublicKeyInfo; changes whenever the key does
+    pub key_fingerprint: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    /// Issuer and subject names are identical
+    pub self_signed: bool,
+}
+
+impl CertificateInfo {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now > self.not_after
+    }
+
+    pub fn days_until_expiry(&self, now: DateTime<Utc>) -> i64 {
+        (self.not_after - now).num_days()
+    }
+
+    /// "RSA 2048" style description of the public key.
+    pub fn key_description(&self) -> String {
+        match self.key_bits {
+            Some(bits) => format!("{} {}", self.key_algorithm, bits),
+            None => self.key_algorithm.clone(),
+        }
+    }
+}
+
+fn parse_x509_time(field: &Tlv<'_>) -> Option<DateTime<Utc>> {
+    let text = field.as_str();
+    let parsed = match field.tag {
+        // Two-digit years: 50-99 are 19xx, 00-49 are 20xx (RFC 5280 4.1.2.5.1)
+        ber::TAG_UTC_TIME => {
+            let century = if text.get(..2)?.parse::<u8>().ok()? >= 50 {
+                "19"
+            } else {
+                "20"
+            };
+            NaiveDateTime::parse_from_str(&format!("{}{}", century, text), "%Y%m%d%H%M%SZ")
+        }
+        ber::TAG_GENERALIZED_TIME => NaiveDateTime::parse_from_str(&text, "%Y%m%d%H%M%SZ"),
+        _ => return None,
+    };
+    parsed.ok().map(|t| t.and_utc())
+}
+
+fn name_attribute(name: &Tlv<'_>, oid: &[u8]) -> Option<String> {
+    name.children()?
+        .iter()
+        .filter_map(|rdn| rdn.children())
+        .flatten()
+        .filter_map(|pair| pair.children())
+        .find(|pair| pair.first().map(|o| o.value) == Some(oid))
+        .and_then(|pair| pair.get(1).map(Tlv::as_str))
+}
+
+fn name_label(name: &Tlv<'_>) -> String {
+    name_attribute(name, OID_COMMON_NAME)
+        .or_else(|| name_attribute(name, OID_ORGANIZATION))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Bit length of a big-endian unsigned integer.
+fn bit_length(bytes: &[u8]) -> u32 {
+    let significant: Vec<u8> = bytes.iter().copied().skip_while(|b| *b == 0).collect();
+    match significant.first() {
+        Some(top) => (significant.len() as u32 - 1) * 8 + (8 - top.leading_zeros()),
+        None => 0,
+    }
+}
+
+/// Algorithm name and key size from a SubjectPublicKeyInfo.
+fn public_key(spki: &Tlv<'_>) -> (String, Option<u32>) {
+    let parts = spki.children().unwrap_or_default();
+    let algorithm = parts.first().and_then(|a| a.children()).unwrap_or_default();
+    let oid = algorithm.first().map(|o| o.value).unwrap_or_default();
+    if oid == OID_RSA_ENCRYPTION {
+        // BIT STRING: one byte of unused-bit count, then RSAPublicKey { modulus, exponent }
+        let modulus = parts
+            .get(1)
+            .and_then(|key| key.value.get(1..))
+            .and_then(|key| ber::read_tlv(key))
+            .and_then(|(rsa, _)| rsa.child(ber::TAG_INTEGER));
+        ("RSA".to_string(), modulus.map(|m| bit_length(m.value)))
+    } else if oid == OID_EC_PUBLIC_KEY {
+        let curve = algorithm.get(1).map(|c| c.value).unwrap_or_default();
+        let bits = EC_CURVES
+            .iter()
+            .find(|(oid, _)| *oid == curve)
+            .map(|(_, bits)| *bits);
+        ("EC".to_string(), bits)
+    } else if oid == OID_ED25519 {
+        ("Ed25519".to_string(), Some(256))
+    } else {
+        ("unknown".to_string(), None)
+    }
+}
+
+/// DNS names and IP addresses from the extensions field (`[3]`) of a TBSCertificate.
+fn subject_alt_names(extensions: &Tlv<'_>) -> Vec<String> {
+    let Some(list) = extensions
+        .child(ber::TAG_SEQUENCE)
+        .and_then(|seq| seq.children())
+    else {
+        return Vec::new();
+    };
+    let Some(san) = list
+        .iter()
+        .filter_map(|ext| ext.children())
+        .find(|ext| ext.first().map(|o| o.value) == Some(OID_SUBJECT_ALT_NAME))
+        .and_then(|ext| ext.last().copied())
+    else {
+        return Vec::new();
+    };
+    let names = ber::read_tlv(san.value)
+        .and_then(|(seq, _)| seq.children())
+        .unwrap_or_default();
+    names
+        .iter()
+        .filter_map(|name| match (name.tag, name.value.len()) {
+            (SAN_DNS_NAME, _) => Some(name.as_str()),
+            (SAN_IP_ADDRESS, 4) => {
+                let octets: [u8; 4] = name.value.try_into().ok()?;
+                Some(Ipv4Addr::from(octets).to_string())
+            }
+            (SAN_IP_ADDRESS, 16) => {
+                let octets: [u8; 16] = name.value.try_into().ok()?;
+                Some(Ipv6Addr::from(octets).to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// FNV-1a hash of a public key, enough to notice that a key was replaced.
+fn key_fingerprint(spki: &[u8]) -> String {
+    let hash = spki.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Parses the fields the TLS grading and certificate inventory need from a
+/// DER certificate.
+pub fn parse_certificate(der: &[u8]) -> Option<CertificateInfo> {
+    let (certificate, _) = ber::read_tlv(der)?;
+    let tbs = certificate.child(ber::TAG_SEQUENCE)?;
+    let mut fields = tbs.children()?.into_iter().peekable();
+    if fields.peek()?.tag == ber::context(0) {
+        fields.next(); // Explicit version
+    }
+    let serial = fields.next()?;
+    let _signature = fields.next()?;
+    let issuer = fields.next()?;
+    let validity = fields.next()?.children()?;
+    let subject = fields.next()?;
+    let spki = fields.next()?;
+    let (key_algorithm, key_bits) = public_key(&spki);
+    let sans = fields
+        .find(|field| field.tag == ber::context(3))
+        .map(|extensions| subject_alt_names(&extensions))
+        .unwrap_or_default();
+    Some(CertificateInfo {
+        serial: serial.value.iter().map(|b| format!("{:02x}", b)).collect(),
+        subject: name_label(&subject),
+        issuer: name_label(&issuer),
+        sans,
+        key_algorithm,
+        key_bits,
+        key_fingerprint: key_fingerprint(spki.value),
+        not_before: parse_x509_time(validity.first()?)?,
+        not_after: parse_x509_time(validity.get(1)?)?,
+        self_signed: issuer.value == subject.value,
+    })
+}
+
+/// Completes a handshake (without validating the chain) to read the server
+/// certificate. Works whatever protocol version the server prefers.
+pub async fn fetch_certificate(ip: IpAddr, port: u16) -> Result<Vec<u8>, DetectError> {
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(|e| DetectError::Connect(format!("TLS setup failed: {}", e)))?;
+    let connector = tokio_native_tls::TlsConnector::from(connector);
+    let Ok(Ok(stream)) = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return Err(DetectError::Connect("Connection failed".to_string()));
+    };
+    let tls = tokio::time::timeout(PROBE_TIMEOUT, connector.connect(&ip.to_string(), stream))
+        .await
+        .map_err(|_| DetectError::Timeout("Timed out during TLS handshake".to_string()))?
+        .map_err(|e| DetectError::Mismatch(format!("TLS handshake failed: {}", e)))?;
+    let certificate = tls
+        .get_ref()
+        .peer_certificate()
+        .map_err(|e| DetectError::Mismatch(format!("Could not read certificate: {}", e)))?
+        .ok_or_else(|| DetectError::Mismatch("Server sent no certificate".to_string()))?;
+    certificate
+        .to_der()
+        .map_err(|e| DetectError::Mismatch(format!("Could not encode certificate: {}", e)))
+}
+
+/// Result of probing a port for a TLS service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsDetection {
+    pub detected: bool,
+    pub certificate: Option<CertificateInfo>,
+    /// Application protocol the server negotiated, e.g. "h2"
+    pub alpn: Option<String>,
+    pub error: Option<DetectError>,
+}
+
+impl TlsDetection {
+    /// "HTTPS" when the server negotiated an HTTP protocol, else "TLS".
+    pub fn summary(&self) -> String {
+        match self.alpn.as_deref() {
+            Some(alpn) if alpn == "h2" || alpn.starts_with("http/") => "HTTPS".to_string(),
+            _ => "TLS".to_string(),
+        }
+    }
+}
+
+impl From<TlsDetection> for Detection {
+    fn from(tls: TlsDetection) -> Self {
+        let summary = tls.summary();
+        Detection::new(tls.detected, summary, tls.error).with_tls(tls.certificate, tls.alpn)
+    }
+}
+
+/// Completes a TLS handshake with the port and reads its certificate and
+/// negotiated application protocol.
+pub async fn detect(ip: IpAddr, port: u16) -> TlsDetection {
+    let der = match fetch_certificate(ip, port).await {
+        Ok(der) => der,
+        Err(e) => {
+            return TlsDetection {
+                detected: false,
+                certificate: None,
+                alpn: None,
+                error: Some(e),
+            };
+        }
+    };
+    // A server that only speaks TLS 1.3 keeps its choice encrypted; that is not a failure
+    let alpn = negotiate_alpn(ip, port).await.ok().flatten();
+    TlsDetection {
+        detected: true,
+        certificate: parse_certificate(&der),
+        alpn,
+        error: None,
+    }
+}
@@ -0,0 +1,95 @@
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// RFB security type that lets anyone in without a password
+pub const SECURITY_NONE: u8 = 1;
+pub const SECURITY_VNC_AUTH: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VncDetection {
+    pub detected: bool,
+    /// Protocol version the server announced, e.g. "3.8"
+    pub version: Option<String>,
+    /// Security types offered by the server
+    pub security_types: Vec<u8>,
+    pub error: Option<String>,
+}
+
+impl VncDetection {
+    /// Whether the server lets clients connect without authenticating.
+    pub fn no_auth(&self) -> bool {
+        self.security_types.contains(&SECURITY_NONE)
+    }
+}
+
+/// Parses the 12-byte ProtocolVersion greeting ("RFB 003.008\n") into (major, minor).
+pub fn parse_version(greeting: &[u8]) -> Option<(u16, u16)> {
+    let text = std::str::from_utf8(greeting).ok()?;
+    let version = text.strip_prefix("RFB ")?.trim_end();
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// Reads the server greeting and the security types it offers. Stops before
+/// authenticating, so no session is opened.
+pub async fn detect(ip: IpAddr, port: u16) -> VncDetection {
+    let failed = |error: String| VncDetection {
+        detected: false,
+        version: None,
+        security_types: Vec::new(),
+        error: Some(error),
+    };
+    let Ok(Ok(mut stream)) = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    else {
+        return failed("Connection failed".to_string());
+    };
+
+    let handshake = async {
+        let mut greeting = [0u8; 12];
+        stream.read_exact(&mut greeting).await?;
+        let Some((major, minor)) = parse_version(&greeting) else {
+            return Ok(None);
+        };
+        // Answer with the highest version both sides speak (3.3, 3.7 or 3.8)
+        let minor = if major > 3 { 8 } else { minor.min(8) };
+        let reply_minor = match minor {
+            0..=6 => 3,
+            7 => 7,
+            _ => 8,
+        };
+        stream
+            .write_all(format!("RFB 003.{:03}\n", reply_minor).as_bytes())
+            .await?;
+        let types = if reply_minor == 3 {
+            // 3.3: the server picks a single type as a u32
+            let mut chosen = [0u8; 4];
+            stream.read_exact(&mut chosen).await?;
+            vec![u32::from_be_bytes(chosen) as u8]
+        } else {
+            let mut count = [0u8; 1];
+            stream.read_exact(&mut count).await?;
+            let mut types = vec![0u8; count[0] as usize];
+            stream.read_exact(&mut types).await?;
+            types
+        };
+        Ok::<_, std::io::Error>(Some((format!("{}.{}", major, minor), types)))
+    };
+    match tokio::time::timeout(Duration::from_secs(5), handshake).await {
+        Ok(Ok(Some((version, security_types)))) => VncDetection {
+            detected: true,
+            version: Some(version),
+            security_types,
+            error: None,
+        },
+        Ok(Ok(None)) => failed("Not an RFB greeting".to_string()),
+        Ok(Err(e)) => failed(format!("Handshake failed: {}", e)),
+        Err(_) => failed("Timed out during RFB handshake".to_string()),
+    }
+}
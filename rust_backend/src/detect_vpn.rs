@@ -0,0 +1,258 @@
+use crate::scanners::service_detection::{DetectError, Detection};
+use crate::utils::sockets;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+pub const WIREGUARD_PORT: u16 = 51820;
+pub const OPENVPN_PORT: u16 = 1194;
+pub const IKE_PORT: u16 = 500;
+pub const IKE_NAT_T_PORT: u16 = 4500;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Initiator cookie / session id / sender index the probes use
+const PROBE_ID: u64 = 0x6e65_7473_6361_6e21;
+
+const OPENVPN_HARD_RESET_CLIENT_V2: u8 = 7;
+const OPENVPN_HARD_RESET_SERVER_V2: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VpnKind {
+    WireGuard,
+    OpenVpn,
+    Ike,
+}
+
+impl VpnKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            VpnKind::WireGuard => "WireGuard",
+            VpnKind::OpenVpn => "OpenVPN",
+            VpnKind::Ike => "IKE/IPsec",
+        }
+    }
+
+    pub fn from_port(port: u16) -> Option<Self> {
+        match port {
+            WIREGUARD_PORT => Some(VpnKind::WireGuard),
+            OPENVPN_PORT => Some(VpnKind::OpenVpn),
+            IKE_PORT | IKE_NAT_T_PORT => Some(VpnKind::Ike),
+            _ => None,
+        }
+    }
+}
+
+/// IKE header fields of a reply to the Main Mode probe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IkeResponse {
+    pub major_version: u8,
+    pub exchange_type: u8,
+    /// Whether the first payload is a Notification (e.g. NO-PROPOSAL-CHOSEN)
+    pub notification: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VpnDetection {
+    pub detected: bool,
+    pub kind: VpnKind,
+    pub detail: Option<String>,
+    pub error: Option<DetectError>,
+}
+
+impl VpnDetection {
+    pub fn summary(&self) -> String {
+        match &self.detail {
+            Some(detail) => format!("{} ({})", self.kind.label(), detail),
+            None => self.kind.label().to_string(),
+        }
+    }
+}
+
+impl From<VpnDetection> for Detection {
+    fn from(vpn: VpnDetection) -> Self {
+        Detection::new(vpn.detected, vpn.summary(), vpn.error)
+    }
+}
+
+/// P_CONTROL_HARD_RESET_CLIENT_V2 without tls-auth: opcode/key id, session
+/// id, empty ack array, packet id 0.
+pub fn build_openvpn_reset(session_id: u64) -> Vec<u8> {
+    let mut packet = vec![OPENVPN_HARD_RESET_CLIENT_V2 << 3];
+    packet.extend_from_slice(&session_id.to_be_bytes());
+    packet.push(0);
+    packet.extend_from_slice(&0u32.to_be_bytes());
+    packet
+}
+
+/// Server session id of a P_CONTROL_HARD_RESET_SERVER_V2 that acknowledges
+/// `session_id`.
+pub fn parse_openvpn_reset(reply: &[u8], session_id: u64) -> Option<u64> {
+    if reply.first()? >> 3 != OPENVPN_HARD_RESET_SERVER_V2 {
+        return None;
+    }
+    let server_session = u64::from_be_bytes(reply.get(1..9)?.try_into().ok()?);
+    let acks = *reply.get(9)? as usize;
+    if acks > 0 {
+        let start = 10 + acks * 4;
+        let remote = u64::from_be_bytes(reply.get(start..start + 8)?.try_into().ok()?);
+        if remote != session_id {
+            return None;
+        }
+    }
+    Some(server_session)
+}
+
+/// IKEv1 Main Mode request with a single AES/SHA1/PSK/MODP1024 proposal,
+/// enough for any IKE daemon to answer or reject.
+pub fn build_ike_main_mode(cookie: u64) -> Vec<u8> {
+    let attributes: [(u16, u16); 7] = [
+        (1, 7),      // encryption: AES-CBC
+        (14, 128),   // key length
+        (2, 2),      // hash: SHA1
+        (3, 1),      // authentication: pre-shared key
+        (4, 2),      // group: MODP1024
+        (11, 1),     // life type: seconds
+        (12, 28800), // life duration
+    ];
+    let mut transform = vec![0, 0, 0, 0, 1, 1, 0, 0];
+    for (kind, value) in attributes {
+        transform.extend_from_slice(&(0x8000 | kind).to_be_bytes());
+        transform.extend_from_slice(&value.to_be_bytes());
+    }
+    let transform_len = transform.len() as u16;
+    transform[2..4].copy_from_slice(&transform_len.to_be_bytes());
+
+    let mut proposal = vec![0, 0, 0, 0, 1, 1, 0, 1];
+    proposal.extend_from_slice(&transform);
+    let proposal_len = proposal.len() as u16;
+    proposal[2..4].copy_from_slice(&proposal_len.to_be_bytes());
+
+    let mut sa = vec![0, 0, 0, 0];
+    sa.extend_from_slice(&1u32.to_be_bytes()); // DOI: IPsec
+    sa.extend_from_slice(&1u32.to_be_bytes()); // situation: identity only
+    sa.extend_from_slice(&proposal);
+    let sa_len = sa.len() as u16;
+    sa[2..4].copy_from_slice(&sa_len.to_be_bytes());
+
+    let mut packet = cookie.to_be_bytes().to_vec();
+    packet.extend_from_slice(&[0; 8]);
+    packet.extend_from_slice(&[1, 0x10, 2, 0]); // next payload SA, v1.0, Main Mode
+    packet.extend_from_slice(&0u32.to_be_bytes());
+    packet.extend_from_slice(&((28 + sa.len()) as u32).to_be_bytes());
+    packet.extend_from_slice(&sa);
+    packet
+}
+
+/// Header of an IKE reply that echoes `cookie` as the initiator cookie.
+pub fn parse_ike_response(reply: &[u8], cookie: u64) -> Option<IkeResponse> {
+    if reply.len() < 28 || reply[..8] != cookie.to_be_bytes() {
+        return None;
+    }
+    let major_version = reply[17] >> 4;
+    if major_version != 1 && major_version != 2 {
+        return None;
+    }
+    Some(IkeResponse {
+        major_version,
+        exchange_type: reply[18],
+        // Notification is payload 11 in IKEv1 and 41 in IKEv2
+        notification: reply[16] == 11 || reply[16] == 41,
+    })
+}
+
+/// Handshake initiation of the right shape. Its MAC cannot be valid without
+/// the peer's public key, so a conforming peer stays silent.
+pub fn build_wireguard_initiation(sender_index: u32) -> Vec<u8> {
+    let mut packet = vec![1, 0, 0, 0];
+    packet.extend_from_slice(&sender_index.to_le_bytes());
+    packet.resize(148, 0);
+    packet
+}
+
+/// Whether `reply` is a handshake response or cookie reply to `sender_index`.
+pub fn is_wireguard_reply(reply: &[u8], sender_index: u32) -> bool {
+    let receiver = match reply.first() {
+        Some(2) if reply.len() == 92 => reply.get(8..12),
+        Some(3) if reply.len() == 64 => reply.get(4..8),
+        _ => None,
+    };
+    receiver == Some(&sender_index.to_le_bytes()[..])
+}
+
+async fn exchange(ip: IpAddr, port: u16, request: &[u8]) -> Result<Vec<u8>, DetectError> {
+    let exchange = async {
+        let socket = UdpSocket::bind(sockets::local_bind_addr(ip)).await?;
+        socket.connect(sockets::socket_addr(ip, port)).await?;
+        socket.send(request).await?;
+        let mut buf = [0u8; 2048];
+        let n = socket.recv(&mut buf).await?;
+        Ok::<_, std::io::Error>(buf[..n].to_vec())
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, exchange).await {
+        Ok(Ok(reply)) => Ok(reply),
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+            Err(DetectError::Connect("Port closed".to_string()))
+        }
+        Ok(Err(e)) => Err(DetectError::Connect(format!("VPN probe failed: {}", e))),
+        Err(_) => Err(DetectError::Timeout("No response".to_string())),
+    }
+}
+
+/// Sends the handshake initiation for `kind` over UDP. OpenVPN servers using
+/// tls-auth/tls-crypt and WireGuard peers do not answer unauthenticated
+/// probes; their silence is reported as an error, not as absence.
+pub async fn detect(ip: IpAddr, port: u16, kind: VpnKind) -> VpnDetection {
+    let request = match kind {
+        VpnKind::WireGuard => build_wireguard_initiation(PROBE_ID as u32),
+        VpnKind::OpenVpn => build_openvpn_reset(PROBE_ID),
+        VpnKind::Ike => build_ike_main_mode(PROBE_ID),
+    };
+    let mut detection = VpnDetection {
+        detected: false,
+        kind,
+        detail: None,
+        error: None,
+    };
+    let reply = match exchange(ip, port, &request).await {
+        Ok(reply) => reply,
+        Err(e) => {
+            detection.error = Some(match (kind, &e) {
+                (VpnKind::WireGuard, DetectError::Timeout(_)) => e.with_message(
+                    "No response (WireGuard ignores unauthenticated peers)".to_string(),
+                ),
+                (VpnKind::OpenVpn, DetectError::Timeout(_)) => {
+                    e.with_message("No response (server may require tls-auth)".to_string())
+                }
+                _ => e,
+            });
+            return detection;
+        }
+    };
+    let detail = match kind {
+        VpnKind::WireGuard => {
+            is_wireguard_reply(&reply, PROBE_ID as u32).then(|| "handshake reply".to_string())
+        }
+        VpnKind::OpenVpn => parse_openvpn_reset(&reply, PROBE_ID)
+            .map(|session| format!("hard reset acknowledged, session {:016x}", session)),
+        VpnKind::Ike => parse_ike_response(&reply, PROBE_ID).map(|ike| {
+            if ike.notification {
+                format!("IKEv{} notification", ike.major_version)
+            } else {
+                format!("IKEv{} exchange {}", ike.major_version, ike.exchange_type)
+            }
+        }),
+    };
+    match detail {
+        Some(detail) => {
+            detection.detected = true;
+            detection.detail = Some(detail);
+        }
+        None => {
+            detection.error = Some(DetectError::Mismatch(format!(
+                "Not a {} reply",
+                kind.label()
+            )))
+        }
+    }
+    detection
+}
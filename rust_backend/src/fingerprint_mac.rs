@@ -1,5 +1,12 @@
+use crate::scanners::arpcache::{self, ArpSegment};
+use crate::utils::{oui, routing};
 use std::net::Ipv4Addr;
 
+/// The kernel's IPv4 neighbour table
+const ARP_TABLE_PATH: &str = "/proc/net/arp";
+/// ATF_COM: the entry is complete (the host answered)
+const ARP_FLAG_COMPLETE: u32 = 0x2;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MacFingerprint {
     pub mac: Option<String>,
@@ -7,11 +14,68 @@ pub struct MacFingerprint {
     pub error: Option<String>,
 }
 
+/// MAC address of `ip` in the contents of /proc/net/arp, if its entry is complete.
+pub fn mac_from_arp_table(contents: &str, ip: Ipv4Addr) -> Option<String> {
+    contents.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [address, _hw_type, flags, mac, ..] = fields.as_slice() else {
+            return None;
+        };
+        let flags = u32::from_str_radix(flags.trim_start_matches("0x"), 16).ok()?;
+        (address.parse::<Ipv4Addr>().ok()? == ip && flags & ARP_FLAG_COMPLETE != 0)
+            .then(|| mac.to_ascii_lowercase())
+    })
+}
+
+/// Finds the MAC of `ip` and looks up the NIC vendor from its OUI. Uses the
+/// ARP cache that discovery filled, then the kernel's neighbour table, and
+/// only then asks the host through the shared resolver.
 pub async fn fingerprint(ip: Ipv4Addr) -> MacFingerprint {
-    // Placeholder: implement ARP or use a crate like `pnet` or `mac_address`
-    MacFingerprint {
-        mac: None,
-        vendor: None,
-        error: Some("Not implemented".to_string()),
+    fingerprint_on(ip, None).await
+}
+
+/// `fingerprint`, asking through `interface` (`--interface`) instead of the
+/// one routing to `ip`. Interfaces that cannot ARP are reported as the error.
+pub async fn fingerprint_on(ip: Ipv4Addr, interface: Option<&str>) -> MacFingerprint {
+    match find_mac(ip, interface).await {
+        Ok(mac) => MacFingerprint {
+            vendor: oui::lookup(&mac),
+            mac: Some(mac),
+            error: None,
+        },
+        Err(e) => MacFingerprint {
+            mac: None,
+            vendor: None,
+            error: Some(e),
+        },
     }
-}
\ No newline at end of file
+}
+
+async fn find_mac(ip: Ipv4Addr, interface: Option<&str>) -> Result<String, String> {
+    if let Some(mac) = arpcache::cached(ip) {
+        return Ok(mac.to_string());
+    }
+    if let Some(mac) = tokio::fs::read_to_string(ARP_TABLE_PATH)
+        .await
+        .ok()
+        .and_then(|table| mac_from_arp_table(&table, ip))
+    {
+        return Ok(mac);
+    }
+    let segment = match interface {
+        Some(name) => ArpSegment::new(Some(name), ip, None, None)?,
+        None => {
+            // ARP only reaches hosts on a directly connected segment
+            let route = routing::select_source(ip, None)?;
+            if route.gateway.is_some() {
+                return Err("No ARP entry (host is not on a local segment)".to_string());
+            }
+            ArpSegment::new(Some(&route.interface), ip, route.source_ip, None)?
+        }
+    };
+    arpcache::resolve(&segment, vec![ip])
+        .await?
+        .first()
+        .map(|(_, mac)| mac.to_string())
+        .ok_or_else(|| "No ARP reply".to_string())
+}
@@ -6,6 +6,29 @@ pub mod detect_http;
 pub mod detect_smtp;
 pub mod detect_ftp;
 pub mod fingerprint_mac;
+pub mod detect_ldap;
+pub mod detect_kerberos;
+pub mod detect_smb;
+pub mod detect_ntp;
+pub mod detect_tls;
+pub mod detect_snmp;
+pub mod detect_vnc;
+pub mod detect_tftp;
+pub mod detect_legacy;
+pub mod detect_sip;
+pub mod detect_rtsp;
+pub mod detect_onvif;
+pub mod detect_remote_admin;
+pub mod detect_proxy;
+pub mod detect_vpn;
+pub mod detect_elasticsearch;
+pub mod detect_mongodb;
+pub mod detect_couchdb;
+pub mod detect_db;
+pub mod detect_pop3;
+pub mod detect_imap;
+pub mod detect_telnet;
+pub mod report;
 
 
 pub fn add(left: u64, right: u64) -> u64 {
@@ -1,9 +1,43 @@
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
-use rust_backend::scanners::service_detection::{self, Protocol};
-use rust_backend::scanners::{pingsweep, tcpscan, udpscan};
-use rust_backend::utils::{fingerprinting, prettyprint};
-use std::net::{IpAddr, Ipv4Addr};
+use rust_backend::detect_remote_admin;
+use rust_backend::scanners::pingsweep::LiveHost;
+use rust_backend::scanners::service_detection::{self, Protocol, ProtocolSelection};
+use rust_backend::scanners::{
+    arpsweep, dc_audit, linklocal, ndpsweep, pingsweep, synscan, tcpscan, tls_audit, udpscan, weak_protocols,
+};
+use rust_backend::utils::annotations::{self, AnnotationStore, Verdict};
+use rust_backend::utils::banners::{self, BannerInventory};
+use rust_backend::utils::cert_inventory::CertificateInventory;
+use rust_backend::utils::compliance::{Check, ComplianceMatrix, ComplianceProfile};
+use rust_backend::utils::findings::Finding;
+use rust_backend::utils::scan_config::{self, ScanConfig};
+use rust_backend::utils::schedule::{self, Schedule, ScanWindow};
+use rust_backend::utils::segments::{self, Segment};
+use rust_backend::utils::inventory::{self, Inventory};
+use rust_backend::utils::redact::{self, Redactor};
+use rust_backend::utils::{monitor, os_fusion, sightings, oui, ports_presets, reports, roles};
+use rust_backend::utils::targets::{self, ExclusionList, TargetSpec};
+use rust_backend::utils::{autotune, fastpath, fingerprinting, prettyprint, progress, quicklook, ratelimit, rdns, replay, routing, selftest, sockets, warnings};
+use rust_backend::report::ScanReport;
+use rust_backend::utils::storage::ScanHistory;
+use rust_backend::utils::msf::{self, CveLookup, MsfSuggestions};
+use metasploit_tools::suggest;
+use rust_backend::utils::host_notes::{self, HostNotes};
+use rust_backend::utils::hooks::{self, FindingHook, HookFilter};
+use rust_backend::utils::scan_diff;
+use rust_backend::utils::timing::RunTimings;
+use rust_backend::utils::timing_template::{self, TimingTemplate};
+use rust_backend::utils::listeners::{self, ListenerAudit};
+use rust_backend::utils::i18n::{self, Locale, Msg};
+use rust_backend::utils::logging;
+use rust_backend::utils::errors::{AbortCode, RunError};
+use rust_backend::utils::prettyprint::{Column, RowFilter, SortKey, TableLayout};
+use tracing::level_filters::LevelFilter;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::time::Instant;
 use local_ip_address::local_ip;
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -17,6 +51,29 @@ pub enum ProtocolArg {
     Pop3,
     Imap,
     Telnet,
+    Smb,
+    Snmp,
+    Tftp,
+    Echo,
+    Daytime,
+    Chargen,
+    Finger,
+    Rexec,
+    Rlogin,
+    Rsh,
+    Sip,
+    Rtsp,
+    Onvif,
+    RemoteAdmin,
+    Socks,
+    HttpProxy,
+    Vpn,
+    Elasticsearch,
+    Mongodb,
+    Couchdb,
+    Mysql,
+    Postgres,
+    Redis,
 }
 
 impl ProtocolArg {
@@ -31,55 +88,416 @@ impl ProtocolArg {
             ProtocolArg::Pop3 => Protocol::Pop3,
             ProtocolArg::Imap => Protocol::Imap,
             ProtocolArg::Telnet => Protocol::Telnet,
+            ProtocolArg::Smb => Protocol::Smb,
+            ProtocolArg::Snmp => Protocol::Snmp,
+            ProtocolArg::Tftp => Protocol::Tftp,
+            ProtocolArg::Echo => Protocol::Echo,
+            ProtocolArg::Daytime => Protocol::Daytime,
+            ProtocolArg::Chargen => Protocol::Chargen,
+            ProtocolArg::Finger => Protocol::Finger,
+            ProtocolArg::Rexec => Protocol::Rexec,
+            ProtocolArg::Rlogin => Protocol::Rlogin,
+            ProtocolArg::Rsh => Protocol::Rsh,
+            ProtocolArg::Sip => Protocol::Sip,
+            ProtocolArg::Rtsp => Protocol::Rtsp,
+            ProtocolArg::Onvif => Protocol::Onvif,
+            ProtocolArg::RemoteAdmin => Protocol::RemoteAdmin,
+            ProtocolArg::Socks => Protocol::Socks,
+            ProtocolArg::HttpProxy => Protocol::HttpProxy,
+            ProtocolArg::Vpn => Protocol::Vpn,
+            ProtocolArg::Elasticsearch => Protocol::Elasticsearch,
+            ProtocolArg::Mongodb => Protocol::Mongodb,
+            ProtocolArg::Couchdb => Protocol::Couchdb,
+            ProtocolArg::Mysql => Protocol::Mysql,
+            ProtocolArg::Postgres => Protocol::Postgres,
+            ProtocolArg::Redis => Protocol::Redis,
         }
     }
 }
 
-#[derive(Parser, Debug)]
+/// Presets that bundle scan options for a specific audit
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileArg {
+    /// Active Directory domain controller exposure audit
+    DcAudit,
+}
+
+/// Control sets findings can be checked against
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComplianceArg {
+    Pci,
+    CisBasic,
+}
+
+impl From<ComplianceArg> for ComplianceProfile {
+    fn from(arg: ComplianceArg) -> Self {
+        match arg {
+            ComplianceArg::Pci => ComplianceProfile::Pci,
+            ComplianceArg::CisBasic => ComplianceProfile::CisBasic,
+        }
+    }
+}
+
+/// Columns of the service table
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnArg {
+    Port,
+    Service,
+    Version,
+    Status,
+    Latency,
+    Banner,
+    Error,
+}
+
+impl From<ColumnArg> for Column {
+    fn from(arg: ColumnArg) -> Self {
+        match arg {
+            ColumnArg::Port => Column::Port,
+            ColumnArg::Service => Column::Service,
+            ColumnArg::Version => Column::Version,
+            ColumnArg::Status => Column::Status,
+            ColumnArg::Latency => Column::Latency,
+            ColumnArg::Banner => Column::Banner,
+            ColumnArg::Error => Column::Error,
+        }
+    }
+}
+
+/// Orders for the service table rows
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortArg {
+    Port,
+    Service,
+    Latency,
+}
+
+impl From<SortArg> for SortKey {
+    fn from(arg: SortArg) -> Self {
+        match arg {
+            SortArg::Port => SortKey::Port,
+            SortArg::Service => SortKey::Service,
+            SortArg::Latency => SortKey::Latency,
+        }
+    }
+}
+
+/// Service table rows to keep
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnlyArg {
+    Open,
+    Detected,
+}
+
+impl From<OnlyArg> for RowFilter {
+    fn from(arg: OnlyArg) -> Self {
+        match arg {
+            OnlyArg::Open => RowFilter::Open,
+            OnlyArg::Detected => RowFilter::Detected,
+        }
+    }
+}
+
+/// Timing templates, by name or by their -T level
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimingArg {
+    #[value(alias = "0")]
+    Paranoid,
+    #[value(alias = "1")]
+    Sneaky,
+    #[value(alias = "2")]
+    Polite,
+    #[value(alias = "3")]
+    Normal,
+    #[value(alias = "4")]
+    Aggressive,
+    #[value(alias = "5")]
+    Insane,
+}
+
+impl From<TimingArg> for TimingTemplate {
+    fn from(arg: TimingArg) -> Self {
+        match arg {
+            TimingArg::Paranoid => TimingTemplate::Paranoid,
+            TimingArg::Sneaky => TimingTemplate::Sneaky,
+            TimingArg::Polite => TimingTemplate::Polite,
+            TimingArg::Normal => TimingTemplate::Normal,
+            TimingArg::Aggressive => TimingTemplate::Aggressive,
+            TimingArg::Insane => TimingTemplate::Insane,
+        }
+    }
+}
+
+/// Most detailed diagnostics to log
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogLevelArg {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevelArg> for LevelFilter {
+    fn from(arg: LogLevelArg) -> Self {
+        match arg {
+            LogLevelArg::Off => LevelFilter::OFF,
+            LogLevelArg::Error => LevelFilter::ERROR,
+            LogLevelArg::Warn => LevelFilter::WARN,
+            LogLevelArg::Info => LevelFilter::INFO,
+            LogLevelArg::Debug => LevelFilter::DEBUG,
+            LogLevelArg::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// How a failed run is reported
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Parser, Clone, Debug)]
 #[command(
     name = "NetScan",
     version,
     author,
+    subcommand_negates_reqs = true,
     about = "A fast, flexible, and extensible network scanner with host discovery, fingerprinting, and service detection.",
     long_about = "NetScan always performs live host discovery (ping sweep) before any scan or detection. \
 You can scan a single IP or an entire subnet. \
 All scans and detections operate only on discovered live hosts. \
-You must specify which ports and protocols to scan or detect—there are no defaults. \
+You must specify which ports to scan or detect—there are no defaults; service detection picks the detectors usual for each port unless --protocols names them. \
 Features include TCP/UDP port scanning, service detection, and host fingerprinting (OS/vendor/etc).",
     after_help = "\
 EXAMPLES:
     netscan --ip 192.168.1.1 --ports 22,80 --protocols ssh,http --service-detection
+    netscan --ip 192.168.1.0/24 --ports 1-1024 --service-detection
     netscan --ip 192.168.1.0/24 --tcpscan --ports 22,80,443
+    netscan --ip 10.0.0.0/24 --tcpscan --top-ports 100
+    sudo netscan --ip 192.168.1.0/24 --syn --ports 1-1024
     netscan --ip 10.0.0.5 --ports 21,22,25 --protocols ftp,ssh,smtp --service-detection
     netscan --ip 127.0.0.1 --ports 8080 --protocols http --service-detection
+    netscan --ip 10.0.0.5 --ports 443,8443 --protocols https --service-detection
+    netscan --ip 10.0.0.0/24 --ports 445 --protocols smb --service-detection
+    netscan --ip 10.0.0.0/24 --ports 161 --protocols snmp --service-detection
+    netscan --ip 10.0.0.0/24 --ports 5060,5061 --protocols sip --service-detection
+    netscan --ip 10.0.0.0/24 --ports 80,554 --protocols rtsp,onvif --service-detection
+    netscan --ip 10.0.0.0/24 --ports 5938,7070,21118,27036 --protocols remote-admin --service-detection
+    netscan --ip 10.0.0.0/24 --ports 1080,3128 --protocols socks,http-proxy --service-detection
+    netscan --ip 10.0.0.1 --ports 500,1194,51820 --protocols vpn --service-detection
+    netscan --ip 10.0.0.0/24 --ports 5984,9200,27017 --protocols elasticsearch,mongodb,couchdb --service-detection
+    netscan --ip 10.0.0.0/24 --ports 3306,5432,6379 --protocols mysql,postgres,redis --service-detection
+    netscan --ip 10.0.0.0/24 --ports 7,13,19,69,79,512-514 --protocols tftp,echo,daytime,chargen,finger,rexec,rlogin,rsh --service-detection
+    netscan --ip 192.168.1.0/24 --ports 1-1024 --protocols ssh,http,https,smtp --pipeline
+    netscan --ip 10.0.0.0/24 --ports 1-65535 --protocols ssh,http --pipeline --collect-unknowns samples/
+    netscan --ip 10.0.0.0/24 --fingerprint --tcpscan --ports 1-1024 --history scans.db
+    netscan --ip 10.0.0.5 --ports 22,80 --protocols ssh,http --service-detection --log-level debug --log-json 2> log.jsonl
+    netscan --ip 10.0.0.0/24 --ports 22 --tcpscan --output json | tail -n 1 | jq -r .error.code
+    netscan --ip 10.0.0.0/24 --tcpscan --ports 22,80 -vv
+    netscan --ip 10.0.0.0/24 --tcpscan --ports 22,80 --ascii > scan.log
+    netscan --ip 10.0.0.0/24 --ports 21,22,25,80 --service-detection --columns port,service,version,latency,banner --banner-width 60
+    netscan --ip 10.0.0.0/16 --ports 1-1024 --service-detection --pipeline --only detected --sort-by service
+    netscan --ip 10.0.0.0/24 --ports 80,443,8080 --protocols http,https --pipeline --on-finding \"shot.sh {ip} {port} {service}\"
     netscan --ip 192.168.1.0/24 --fingerprint
+    netscan --ip 192.168.1.0/24 --arp --inventory inventory.csv
+    netscan --ip 10.0.0.0/24 --ports 21,22,23,443 --banners --tls-grade --redact --redact-key vendor-ticket-42
+    netscan --ip 10.0.0.0/16 --tcpscan --ports 1-1024 --timeout 800ms --concurrency 512 --retries 1
+    netscan --ip 192.168.1.0/24 --tcpscan --udpscan --ports 1-1024 --retries 2 --retry-delay 200ms
+    netscan --ip 10.0.0.0/24 --tcpscan --ports 1-1024 -T4 --adaptive-timing
+    netscan --ip 10.0.0.0/24 --tcpscan --ports 1-1024 --max-rate 50
+    netscan --ip 127.0.0.1 --tcpscan --ports 1-65535 --service-detection
+    netscan --ip host.example.com --tcpscan --ports 22,443
+    netscan --ip 192.168.1.20 --auto-expand --tcpscan --ports 22
+    netscan --ip 10.0.0.0/22 --ptr-sweep --tcpscan --top-ports 20
+    netscan --ip 10.0.0.0/24,10.0.0.128/25,gw.example.com --tcpscan --ports 22
+    cat cidrs.txt | netscan --ip - --tcpscan --ports 22,443
+    netscan --ip 192.168.1.10-50 --ip 10.0.0.0/28 --target-file targets.txt --tcpscan --ports 22
+    netscan --ip 192.168.1.0/24 --exclude 192.168.1.5,192.168.1.0/28 --exclude-file production.txt --tcpscan --ports 22
+    netscan --ip 10.20.0.0/24 --arp --interface eth1 --vlan 20 --tcpscan --ports 22
+    netscan --ip fd00:10::/120 --arp --interface eth1 --tcpscan --ports 22
+    sudo netscan --ip 192.168.1.0/24 --link-local --interface eth0 --tcpscan --ports 22,80
+    netscan --interface eth1=10.20.0.0/24 --interface eth2=10.30.0.0/24 --tcpscan --ports 22,443
+    netscan --ip 10.0.0.0/24 --profile dc-audit
+    netscan --ip 10.0.0.0/24 --ports 443,636,8443 --tls-grade
+    netscan --ip 10.0.0.0/24 --ports 21,23,80,445,5900,8080 --weak-protocols
+    netscan --ip 10.0.0.0/24 --ports 21-445 --compliance pci
+    netscan --ip 10.0.0.0/24 --ports 21,22,23 --banners
+    netscan --ip 10.0.0.0/24 --ports 21,22,80,3306 --suggest-msf
+    netscan --ip 10.0.0.0/24 --ports 443 --tls-grade --monitor 3600 --cert-expiry-days 21
+    netscan --ip 10.0.0.0/24 --ports 1-1024 --tcpscan --monitor 3600 --window \"Mon-Fri 22:00-05:00 Europe/Berlin\"
+    netscan ack 3f9a1c2e7b04 --note \"Planned CA migration\"
+    netscan annotate 9d41e07a5c3b --as accepted-risk --comment \"Legacy printer, isolated VLAN\"
+    netscan note 10.0.0.42 --text \"Lab printer, ignore telnet\"
+    netscan host 10.0.0.5
+    netscan host 10.0.0.5 --top-ports 20
+    netscan replay samples/
+    netscan selftest
+    netscan local --expect 22,443,udp/123
+    netscan diff --history scans.db
 
 OPTIONS:
     --fingerprint         Attempt OS/vendor fingerprinting on live hosts
+                          (as root, the TTL, window and TCP options of a SYN/ACK also classify the OS family)
+                          (as root, an IGMP query also lists the multicast groups on-link hosts are members of)
+    --update-oui          Download the IEEE OUI registry so fingerprinting names more NIC vendors
     --tcpscan             Perform TCP port scan on live hosts
-    --udpscan             Perform UDP port scan on live hosts
-    --service-detection   Detect services on live hosts/ports (requires --ports and --protocols)
+    --syn                 Half-open TCP scan with raw SYN packets (implies --tcpscan; needs root, else falls back to connect)
+    --udpscan             Perform UDP port scan on live hosts; ports are open, closed, filtered or open|filtered
+                          (as root, ICMP unreachable messages are sniffed to tell closed from filtered)
+                          DNS, TFTP, NTP, NetBIOS-NS, SNMP, SSDP, mDNS and SIP ports get a payload their service answers
+    --service-detection   Detect services on live hosts/ports (requires --ports; detectors are chosen per port unless --protocols is given)
+    --pipeline            TCP-scan first, then detect services only on the open ports found; UDP detectors (DNS,
+                          SNMP, TFTP, SIP, VPN) still try their ports and report only the ones that answer
+    --suggest-msf         Suggest Metasploit modules for each detected service, version-specific ones first when its
+                          banner matches a rule (implies --service-detection); also writes netscan_msf_suggestions.csv
+    --cve-lookup          Report known CVEs of each detected product version as findings, from NVD feeds cached in
+                          --state-dir for a week (implies --service-detection)
     -p, --ports           Ports to scan (comma-separated or ranges, e.g. 22,80,443,1000-1010) [REQUIRED for scan/service-detection]
-    -r, --protocols       Protocols to detect (comma-separated, e.g. ssh,ftp,smtp) [REQUIRED for service-detection]
-    -i, --ip              Target IPv4 address or subnet (CIDR)
-    -v, --verbose         Enable verbose output
+    --top-ports           Scan the N most common TCP ports (1-100) instead of listing --ports
+    -r, --protocols       Only run these detectors, on every port (comma-separated, e.g. ssh,ftp,smtp); without it, each port
+                          gets the detectors usual for it, and ports none claims get a generic banner grab
+    -i, --ip              Target IPv4/IPv6 address, range (192.168.1.10-50), subnet (CIDR) or hostname; comma-separate or
+                          repeat for several (overlaps are scanned once); \"-\" reads them from stdin, one per line
+    --target-file         Read more targets from FILE, one or more per line (# comments)
+    --exclude             Addresses and networks never to probe, comma-separated; dropped before discovery and scanning
+    --exclude-file        Same as --exclude, read from FILE (one or more per line, # comments)
+    --auto-expand         Scan the whole on-link subnet of a single --ip address, at most the /22 around it (otherwise
+                          it is only suggested)
+    --source-ip           Source IPv4 or IPv6 address to scan from (multi-homed scanners)
+    --arp                 Discover hosts with an ARP sweep (ICMPv6 neighbor discovery for IPv6) instead of ICMP
+    --link-local          Also enumerate on-link IPv6 hosts whose addresses are unknown: all-nodes ping (ff02::1) from
+                          each interface address plus the neighbor cache; SLAAC addresses with an EUI-64 identifier
+                          are flagged, and routable addresses found are scanned
+    --interface           Interface for raw-socket operations; repeat as IFACE=TARGETS to scan segments one after
+                          another, each with its probes bound to its interface and its own report files
+    --vlan                802.1Q VLAN ID to tag raw-socket frames with; only replies tagged with it are accepted
+                          (not with --syn, whose packets the kernel sends untagged)
+    --profile             Audit preset (dc-audit: scan AD ports, deep-probe likely domain controllers)
+    --tls-grade           Grade TLS versions, cipher suites and certificates on open ports (implies --tcpscan);
+                          also writes a certificate inventory to netscan_cert_inventory.csv
+    --weak-protocols      List Telnet, cleartext FTP, SNMPv1/v2c, SMBv1, HTTP admin panels, open VNC, TFTP, legacy services and anonymous camera streams (implies --tcpscan)
+    --banners             Capture SSH, Telnet and FTP login banners verbatim and flag missing legal warnings (implies --tcpscan);
+                          also writes them to netscan_banners.csv
+    --compliance          Check findings against pci or cis-basic controls and print a pass/fail matrix per host;
+                          controls no check could judge on a host (e.g. TLS with no TLS endpoint) show as N/A,
+                          and cis-basic probes SMB signing on every host with 445 open
+    --monitor             Daemon mode: rescan every SECS seconds; alerts on expiring or changed certificates and tracks service availability
+    --state-dir           Where monitor mode keeps state and alerts.log (default .netscan); mute rules go in mutes.conf there
+    --cert-expiry-days    Expiry alert threshold for monitor mode (default 30)
+    --window              Monitor mode only scans inside this window, e.g. \"Mon-Fri 22:00-05:00 Europe/Berlin\"; repeat for several.
+                          A pass still running when the window closes is stopped
+    ack ALERT_ID          Acknowledge a monitor alert as expected so it stops alerting (no ID lists the history)
+    annotate FINDING_ID   Mark a finding --as false-positive or accepted-risk; reports footnote it instead (--clear undoes)
+    note IP               Attach a --text note to a host; runs recorded with --history list and store it, following the
+                          host by MAC when its address changes (no IP lists the notes, --clear removes them)
+    host IP               Quick look at one host: discovery, TCP 1-1024 plus common higher ports, common UDP ports,
+                          service detection, fingerprinting and TLS grading in one report, within --budget (default 2m)
+    replay DIR            Run service detection against saved samples and pcaps, no network needed; add
+                          \"expect: SERVICE\" to a sample to fail the run when detection stops matching it
+    selftest              Check the detectors against servers emulated on 127.0.0.1 before scanning a real network
+    local                 List this machine's listening TCP and UDP sockets with their processes, from /proc (inside a
+                          container, its own); --expect PORTS fails if any other port is reachable from outside
+    diff [OLD] [NEW]      Compare two runs of the --history database: new and gone hosts, opened and closed ports,
+                          changed services; defaults to the latest run against the previous one of the same targets
+    --max-bandwidth       Cap outgoing scan traffic, e.g. 5mbps (live throughput is shown while scanning)
+    --max-rate            Cap outgoing probes (TCP, UDP, ICMP and service detection) to PPS packets per second
+    --timeout             Per-probe timeout, e.g. 500ms or 2s (default depends on the scan: 3s TCP, 4s UDP, 5s ping)
+    --concurrency         Probes in flight at once (default for TCP and UDP port probes: derived from ulimit -n, free
+                          memory and free conntrack entries, between 16 and 4096, 64 if none can be read; discovery,
+                          DNS and service detection keep their own defaults)
+    --retries             Extra attempts for probes that got no answer (default 0; 1 for --syn)
+    -T, --timing          Timing template like nmap's -T0 to -T5: paranoid (0) and sneaky (1) send one probe at a time,
+                          5 min and 15s apart; polite (2) 8 at a time, 400ms apart; normal (3) keeps the defaults;
+                          aggressive (4) and insane (5) cut the timeout to 1.25s and 300ms. --timeout, --concurrency,
+                          --retries and --max-rate override the template
+    --adaptive-timing     Measure each host's round trip during the ping sweep and time out its probes after four
+                          round trips plus 50ms (at least 100ms, at most --timeout)
+    --retry-delay         Wait before the first retry of a TCP, UDP or ping probe, doubled before each further one (at
+                          most 10s); the scan summaries count the probes that needed more than one attempt
+    --no-fast-path        When every target is this machine (loopback, localhost or its own addresses), netscan skips
+                          discovery and --max-rate/--max-bandwidth and defaults to a 300ms timeout, 1024 probes in
+                          flight and no retries; this keeps the usual behavior instead
+    --collect-unknowns    Save responses no detector recognized to DIR (addresses anonymized), one file per sample
+    --history             Record every run (hosts, ports, services, fingerprints, host notes) in the SQLite database FILE
+    --on-finding          Run CMD for every open port; {ip}, {port}, {transport} and {service} are filled in
+                          (no shell involved; at most --on-finding-concurrency at once, default 4)
+    --on-finding-filter   Only run --on-finding for ports matching FILTER: comma-separated service:NAME, port:N and
+                          transport:tcp|udp terms, e.g. service:http,service:https; a port must match one term of
+                          each kind given
+    --inventory           CSV of known devices (ip, mac, hostname, owner, role): labels hosts in reports and flags unknown devices; in monitor mode, alerts on devices not in it
+    --redact              Pseudonymize IPs, MACs and hostnames in CSV reports, findings, the history and console output
+                          so they can be shared; a keyed hash maps each value to the same pseudonym every time, so
+                          correlations survive
+    --redact-key          Key for --redact: the same key gives the same pseudonyms across runs (default random)
+    --no-dns              Skip reverse DNS (PTR) lookups; live hosts are otherwise shown with their names
+    --ptr-sweep           Resolve PTR records for the whole target range first; named hosts count as live even if
+                          they ignore ICMP/ARP, and are scanned first
+    -v, --verbose         Per-host progress and raw probe errors; -vv adds per-port probe results, -vvv raw protocol
+                          traces (the same as --log-level info, debug and trace)
+    --log-level           Diagnostics logged to stderr: off, error, warn (default), info (every host), debug (every
+                          port probe), trace (raw banners and datagrams); reports stay on stdout
+    --log-json            Log diagnostics as JSON lines, each with its host/port span
+    --output              text (default) or json: a run that aborts prints {\"error\": {\"code\", \"message\",
+                          \"hint\"}} as the last line on stdout instead of a message on stderr, e.g. code
+                          invalid_target, permission_denied or no_interface; the exit status stays 1
+    --columns             Service table columns, in order: port, service, version, status, latency, banner, error
+                          (default port,service,status,error)
+    --banner-width        Longest banner shown in the banner column (default 40); longer ones end in \"...\"
+    --sort-by             Order service table rows by port, service (identified first) or latency (slowest first)
+    --only                Show only open ports (they answered) or detected ones (service identified) in the service
+                          table; a footer counts the rows left out
+    --ascii               ASCII-only output for serial consoles and log processors: emoji are dropped, other glyphs
+                          replaced one for one so tables stay aligned
+    --lang                Language of the status messages: en or de (default from LC_ALL, LC_MESSAGES or LANG);
+                          result tables, reports, CSV, JSON and error codes stay English so scripts parsing them
+                          keep working
 
 NOTES:
     - Live host discovery is always performed first.
     - All scans and detections operate only on discovered live hosts.
     - You must specify --ports for any scan or detection.
-    - You must specify --protocols for service detection.
+    - Without --protocols, service detection picks detectors by port.
     - Run as root for best results (especially for ping sweep).
 "
 )]
 pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
     #[arg(
         short,
-        long,
+        long = "ip",
         value_name = "IP",
-        help = "Target IPv4 address or subnet (e.g., 192.168.1.1 or 192.168.1.0/24)"
+        action = clap::ArgAction::Append,
+        required_unless_present_any = ["interface", "target_file"],
+        help = "Target IPv4/IPv6 address, range, subnet or hostname, comma-separated or repeated for several (e.g., 192.168.1.1, 192.168.1.10-50, 192.168.1.0/24, fd00::/120 or host.example.com); - reads them from stdin"
+    )]
+    ips: Vec<String>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Read targets from FILE, one or more per line (# starts a comment); combines with --ip"
+    )]
+    target_file: Option<PathBuf>,
+    /// Every --ip, stdin and --target-file target as one comma-separated list
+    #[arg(skip)]
+    ip: Option<String>,
+    #[arg(
+        long,
+        value_name = "TARGETS",
+        global = true,
+        help = "Addresses and networks never to probe, comma-separated (e.g. 192.168.1.5,192.168.1.0/28)"
+    )]
+    exclude: Option<String>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        global = true,
+        help = "File of addresses and networks never to probe, one or more per line (# starts a comment)"
     )]
-    ip: String,
+    exclude_file: Option<PathBuf>,
     #[arg(
         short = 'p',
         long,
@@ -87,187 +505,2492 @@ pub struct Cli {
         help = "Ports to scan (comma-separated or ranges, e.g. 22,80,443,1000-1010). REQUIRED for scan/service-detection."
     )]
     ports: Option<String>,
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with = "ports",
+        help = "Scan the N most common TCP ports (1-100) instead of listing --ports"
+    )]
+    top_ports: Option<usize>,
     #[arg(
         short = 'r',
         long,
         value_name = "PROTOCOLS",
         value_enum,
         use_value_delimiter = true,
-        help = "Protocols to detect (comma-separated, e.g. ssh,ftp,smtp). REQUIRED for service-detection."
+        help = "Only run these detectors (comma-separated, e.g. ssh,ftp,smtp); by default each port gets the detectors usual for it"
     )]
     protocols: Option<Vec<ProtocolArg>>,
-    #[arg(short, long, help = "Enable verbose output")]
-    verbose: bool,
+    #[arg(
+        long,
+        value_name = "COLUMNS",
+        value_enum,
+        use_value_delimiter = true,
+        global = true,
+        help = "Columns of the service table, in order (comma-separated, e.g. port,service,version,latency,banner)"
+    )]
+    columns: Option<Vec<ColumnArg>>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = prettyprint::DEFAULT_BANNER_WIDTH,
+        global = true,
+        help = "Cut banners in the service table to N characters"
+    )]
+    banner_width: usize,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "KEY",
+        global = true,
+        help = "Order service table rows by port, service name or latency (slowest first)"
+    )]
+    sort_by: Option<SortArg>,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ROWS",
+        global = true,
+        help = "Only show ports that answered (open) or whose service was identified (detected)"
+    )]
+    only: Option<OnlyArg>,
+    #[arg(
+        short,
+        long,
+        action = clap::ArgAction::Count,
+        global = true,
+        help = "More output: -v per-host progress, -vv per-port probe results, -vvv raw protocol traces"
+    )]
+    verbose: u8,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "LEVEL",
+        default_value = "warn",
+        global = true,
+        help = "Diagnostics to log to stderr; info adds a line per host, debug one per probe, trace raw banners"
+    )]
+    log_level: LogLevelArg,
+    #[arg(long, global = true, help = "Log diagnostics as JSON lines, with the host/port spans they belong to")]
+    log_json: bool,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        default_value = "text",
+        global = true,
+        help = "Report a run that aborts as text on stderr, or as a JSON error object (code, message, hint) on stdout"
+    )]
+    output: OutputFormat,
+    #[arg(
+        long,
+        global = true,
+        help = "Print only ASCII: drop emoji and replace other non-ASCII glyphs, keeping columns aligned"
+    )]
+    ascii: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "LANG",
+        value_parser = i18n::parse_locale,
+        help = "Language of the status messages, en or de (default from LC_ALL, LC_MESSAGES or LANG); result tables, reports, CSV and JSON stay English"
+    )]
+    lang: Option<Locale>,
+    #[arg(long, help = "Skip reverse DNS lookups of live hosts")]
+    no_dns: bool,
+    #[arg(
+        long,
+        conflicts_with = "no_dns",
+        help = "Resolve PTR records for every target before probing; named hosts are scanned first, even if discovery misses them"
+    )]
+    ptr_sweep: bool,
+    #[arg(
+        long,
+        help = "When --ip is a single local address, scan its whole on-link subnet instead (at most the /22 around it)"
+    )]
+    auto_expand: bool,
+    #[arg(
+        long,
+        value_name = "CSV",
+        help = "Inventory of known devices (columns ip, mac, hostname, owner, role); hosts missing from it are flagged"
+    )]
+    inventory: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        help = "Pseudonymize IP addresses, MAC addresses and hostnames in reports, the history and console output"
+    )]
+    redact: bool,
+    #[arg(
+        long,
+        value_name = "KEY",
+        requires = "redact",
+        global = true,
+        help = "Key for --redact; the same key gives the same pseudonyms across runs (default: random per run)"
+    )]
+    redact_key: Option<String>,
     #[arg(long, help = "Fingerprint live hosts after discovery")]
     fingerprint: bool,
+    #[arg(long, help = "Download the IEEE OUI registry for MAC vendor lookups (refreshed when older than 30 days)")]
+    update_oui: bool,
     #[arg(long, help = "Perform TCP scan on live hosts")]
     tcpscan: bool,
+    #[arg(
+        long,
+        help = "Scan TCP with raw SYN packets instead of full connects (implies --tcpscan; needs root, falls back to connect scan)"
+    )]
+    syn: bool,
     #[arg(long, help = "Perform UDP scan on live hosts")]
     udpscan: bool,
     #[arg(long, help = "Perform service detection on live hosts")]
     service_detection: bool,
+    #[arg(
+        long,
+        value_name = "IP",
+        help = "Source IPv4 or IPv6 address to scan from; targets of the other family keep the routing table's choice (default: chosen per target from the routing table)"
+    )]
+    source_ip: Option<IpAddr>,
+    #[arg(long, help = "Discover hosts with an ARP sweep (neighbor discovery for IPv6) instead of ICMP (local segment, requires root)")]
+    arp: bool,
+    #[arg(
+        long,
+        help = "Also enumerate on-link IPv6 hosts, even ones whose addresses are unknown: ping ff02::1 and read the neighbor cache (requires root)"
+    )]
+    link_local: bool,
+    #[arg(
+        long,
+        value_name = "IFACE[=TARGETS]",
+        value_parser = Segment::parse,
+        help = "Interface for raw-socket operations (default: from routing table); repeat as IFACE=TARGETS to scan several segments, one after another"
+    )]
+    interface: Vec<Segment>,
+    #[arg(
+        long,
+        value_name = "ID",
+        value_parser = clap::value_parser!(u16).range(1..=4094),
+        conflicts_with = "syn",
+        help = "Tag raw-socket frames with this 802.1Q VLAN ID (for trunk/SPAN-connected scanners; not with --syn, whose packets go through the kernel untagged)"
+    )]
+    vlan: Option<u16>,
+    #[arg(
+        long,
+        value_name = "RATE",
+        value_parser = ratelimit::parse_bandwidth,
+        help = "Cap outgoing scan traffic (e.g. 5mbps, 512kbps)"
+    )]
+    max_bandwidth: Option<u64>,
+    #[arg(
+        long,
+        value_name = "PPS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        global = true,
+        help = "Cap outgoing probes to PPS packets per second, e.g. to stay under IDS thresholds"
+    )]
+    max_rate: Option<u64>,
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = scan_config::parse_timeout,
+        help = "Per-probe timeout for scans and service detection (e.g. 500ms, 2s)"
+    )]
+    timeout: Option<std::time::Duration>,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..=65536),
+        help = "Probes in flight at once (port probes default to a budget derived from the open-file limit, free memory and conntrack headroom)"
+    )]
+    concurrency: Option<u64>,
+    #[arg(long, value_name = "N", help = "Extra attempts for probes that got no answer")]
+    retries: Option<usize>,
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = scan_config::parse_timeout,
+        requires = "retries",
+        help = "Wait before the first retry, doubling before each further one (e.g. 200ms); helps on lossy Wi-Fi"
+    )]
+    retry_delay: Option<std::time::Duration>,
+    #[arg(
+        short = 'T',
+        long = "timing",
+        value_enum,
+        value_name = "TEMPLATE",
+        help = "Timing template, 0-5 or paranoid, sneaky, polite, normal, aggressive, insane; sets timeout, concurrency, retries and probe spacing not given otherwise"
+    )]
+    timing: Option<TimingArg>,
+    #[arg(
+        long,
+        help = "Time out each host's probes after a few of its round trips instead of the fixed timeout; hosts that did not answer a ping are timed with a TCP handshake"
+    )]
+    adaptive_timing: bool,
+    #[arg(
+        long,
+        help = "Scan this machine (127.0.0.1, ::1, localhost or its own addresses) like any other host: with discovery, the usual timeouts and rate caps"
+    )]
+    no_fast_path: bool,
+    /// Every target is this machine, so discovery and pacing are skipped
+    #[arg(skip)]
+    local_fast: bool,
+    /// Concurrency derived from the system limits when --concurrency is not given
+    #[arg(skip)]
+    auto_concurrency: Option<usize>,
+    /// Interface of the --interface IFACE=TARGETS segment this run scans
+    #[arg(skip)]
+    segment: Option<String>,
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Save anonymized responses no detector recognized to DIR, as samples for writing new probes"
+    )]
+    collect_unknowns: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        global = true,
+        help = "Record every run (hosts, ports, services, fingerprints) in this SQLite database"
+    )]
+    history: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "CMD",
+        help = "Command to run for every open port, e.g. \"shot.sh {ip} {port} {service}\""
+    )]
+    on_finding: Option<String>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = hooks::DEFAULT_HOOK_CONCURRENCY,
+        help = "How many --on-finding commands run at once"
+    )]
+    on_finding_concurrency: usize,
+    #[arg(
+        long,
+        value_name = "FILTER",
+        value_parser = HookFilter::parse,
+        help = "Only run --on-finding for ports matching service:NAME, port:N or transport:tcp|udp terms (comma-separated)"
+    )]
+    on_finding_filter: Option<HookFilter>,
+    #[arg(
+        long,
+        value_name = "PROFILE",
+        value_enum,
+        help = "Audit preset; dc-audit TCP-scans AD ports and deep-probes likely domain controllers"
+    )]
+    profile: Option<ProfileArg>,
+    #[arg(long, help = "Grade the TLS configuration of every open port that speaks TLS (implies --tcpscan)")]
+    tls_grade: bool,
+    #[arg(long, help = "Summarize inherently weak services on open ports as a remediation list (implies --tcpscan)")]
+    weak_protocols: bool,
+    #[arg(long, help = "Capture pre-auth SSH/Telnet/FTP banners and flag missing legal warnings (implies --tcpscan)")]
+    banners: bool,
+    #[arg(
+        long,
+        help = "Run service detection only on ports the TCP scan found open, plus UDP detectors on their ports (implies --tcpscan and --service-detection)"
+    )]
+    pipeline: bool,
+    #[arg(
+        long,
+        help = "Suggest Metasploit modules for every detected service and write them to netscan_msf_suggestions.csv (implies --service-detection)"
+    )]
+    suggest_msf: bool,
+    #[arg(
+        long,
+        help = "Report known CVEs of detected product versions as findings, from NVD feeds cached in --state-dir (implies --service-detection)"
+    )]
+    cve_lookup: bool,
+    #[arg(
+        long,
+        value_name = "PROFILE",
+        value_enum,
+        help = "Check findings against a control set (pci, cis-basic); implies --tls-grade, --weak-protocols and --banners"
+    )]
+    compliance: Option<ComplianceArg>,
+    #[arg(
+        long,
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Run as a monitor daemon: rescan every SECS seconds and alert on changes since the previous run"
+    )]
+    monitor: Option<u64>,
+    #[arg(
+        long,
+        value_name = "DIR",
+        default_value = monitor::DEFAULT_STATE_DIR,
+        global = true,
+        help = "Directory where monitor mode keeps results between runs"
+    )]
+    state_dir: PathBuf,
+    #[arg(
+        long,
+        value_name = "DAYS",
+        default_value_t = monitor::DEFAULT_CERT_EXPIRY_DAYS,
+        help = "In monitor mode, alert on certificates expiring within DAYS days"
+    )]
+    cert_expiry_days: i64,
+    #[arg(
+        long,
+        value_name = "WINDOW",
+        value_parser = ScanWindow::parse,
+        help = "In monitor mode, only scan inside this maintenance window (e.g. \"Mon-Fri 22:00-05:00 Europe/Berlin\"); repeat for several"
+    )]
+    window: Vec<ScanWindow>,
 }
 
-fn parse_ports(ports_str: &str) -> Vec<u16> {
-    let mut ports = Vec::new();
-    for part in ports_str.split(',') {
-        if let Some((start, end)) = part.split_once('-') {
-            if let (Ok(start), Ok(end)) = (start.trim().parse::<u16>(), end.trim().parse::<u16>()) {
-                ports.extend(start..=end);
+impl Cli {
+    /// The --ip target list; clap requires it unless a subcommand runs instead.
+    fn target(&self) -> &str {
+        self.ip.as_deref().unwrap_or_default()
+    }
+
+    /// --protocols as given, or per-port selection when it is omitted.
+    fn protocol_selection(&self) -> ProtocolSelection {
+        match &self.protocols {
+            Some(protocols) => {
+                ProtocolSelection::Only(protocols.iter().map(|p| p.to_protocol()).collect())
             }
-        } else if let Ok(port) = part.trim().parse::<u16>() {
-            ports.push(port);
+            None => ProtocolSelection::Auto,
         }
     }
-    ports.sort_unstable();
-    ports.dedup();
-    ports
-}
 
-#[tokio::main]
-async fn main() {
-    let cli = Cli::parse();
-
-    println!("{}", "🛰️  NetScan - Network Service Scanner".bold().blue());
-    println!("{}", "---------------------------------".blue());
+    fn table_layout(&self) -> TableLayout {
+        let mut layout = TableLayout {
+            banner_width: self.banner_width,
+            sort_by: self.sort_by.map(SortKey::from),
+            only: self.only.map(RowFilter::from),
+            ..TableLayout::default()
+        };
+        if let Some(columns) = &self.columns {
+            layout.columns = columns.iter().map(|&c| Column::from(c)).collect();
+        }
+        layout
+    }
 
-    // 1. Always perform live host discovery (ping sweep)
-    let subnet = if cli.ip.contains('/') {
-        cli.ip.clone()
-    } else {
-        format!("{}/32", cli.ip)
-    };
-    println!(
-        "{}",
-        format!("🔎 Performing ping sweep on {subnet}...").yellow()
-    );
-    let live_hosts = match pingsweep::ping_sweep(&subnet).await {
-        Ok(result) => {
-            let hosts = result.get_live_hosts().clone();
-            println!("{} live hosts found.", hosts.len());
-            for h in &hosts {
-                println!("  {}", h.to_string().green());
-            }
-            if hosts.is_empty() {
-                println!("{}", "No live hosts found. Exiting.".red());
-                return;
+    /// Joins every --ip (reading stdin for "-") and the --target-file into one list.
+    fn target_list(&self) -> Result<Option<String>, String> {
+        let mut lists = Vec::new();
+        for ip in &self.ips {
+            if ip == targets::STDIN_TARGETS {
+                lists.push(targets::read_stdin_targets()?);
+            } else {
+                lists.push(ip.clone());
             }
-            hosts
         }
-        Err(e) => {
-            eprintln!("Ping sweep failed: {}", e);
-            return;
+        if let Some(path) = &self.target_file {
+            lists.push(targets::read_target_file(path)?);
         }
-    };
+        Ok((!lists.is_empty()).then(|| lists.join(",")))
+    }
 
-    // --- SKIP LOCAL HOST (robust version) ---
-    let local_ip = match local_ip() {
-        Ok(IpAddr::V4(ip)) => Some(ip),
-        _ => {
-            eprintln!("Could not determine local IPv4 address, skipping local host filtering.");
-            None
+    /// --exclude and --exclude-file combined.
+    fn exclusions(&self) -> Result<ExclusionList, String> {
+        let mut exclusions = ExclusionList::new();
+        if let Some(list) = &self.exclude {
+            exclusions.add_list(list)?;
         }
-    };
-    let live_hosts: Vec<Ipv4Addr> = match local_ip {
-        Some(local) => live_hosts.into_iter().filter(|ip| *ip != local).collect(),
-        None => live_hosts,
-    };
-
-    // --- Require user to specify ports for all scans/service-detection ---
-    if cli.tcpscan || cli.udpscan || cli.service_detection || cli.fingerprint {
-        if cli.ports.is_none() {
-            eprintln!("You must specify --ports for scanning, fingerprinting, or service detection.");
-            std::process::exit(1);
+        if let Some(path) = &self.exclude_file {
+            exclusions.add_file(path)?;
         }
+        Ok(exclusions)
     }
-    // --- Require user to specify protocols for service-detection ---
-    if cli.service_detection && cli.protocols.is_none() {
-        eprintln!("You must specify --protocols for service detection.");
-        std::process::exit(1);
+
+    /// --timeout, --concurrency and --retries for the scanners, the timing
+    /// template filling in what they leave unset.
+    fn scan_config(&self) -> ScanConfig {
+        let mut config = ScanConfig {
+            timeout: self.timeout,
+            concurrency: self.concurrency.map(|n| n as usize),
+            retries: self.retries,
+            retry_delay: self.retry_delay,
+            host_timeouts: None,
+            progress: None,
+            collect_unknowns: self.collect_unknowns.clone(),
+            interface: self.segment.clone(),
+        };
+        if let Some(timing) = self.timing {
+            config = TimingTemplate::from(timing).apply(config);
+        }
+        if self.local_fast {
+            config = fastpath::tune(config);
+        }
+        config
     }
 
-    // Parse ports once for all relevant operations
-    let ports: Vec<u16> = cli.ports.as_ref().map(|s| parse_ports(s)).unwrap_or_default();
+    /// Interface for raw-socket operations, if one was given.
+    fn interface(&self) -> Option<&str> {
+        self.interface.first().map(|segment| segment.interface.as_str())
+    }
 
-    // 2. Fingerprinting (if requested)
-    if cli.fingerprint {
-        println!("{}", "🕵️  Fingerprinting live hosts...".cyan());
-        let fingerprints = futures::future::join_all(
-            live_hosts
-                .iter()
-                .map(|&ip| fingerprinting::fingerprint_host(ip, &ports)),
-        )
-        .await;
-        for fp in fingerprints {
-            println!(
-                "{}\n  {}: {}\n  {}: {}\n  {}: {}\n  {}: {}",
-                format!("{}", fp.ip).bold().yellow(),
-                "OS".bold().blue(),
-                fp.os.as_deref().unwrap_or("Unknown").green(),
-                "Vendor".bold().blue(),
-                fp.vendor.as_deref().unwrap_or("Unknown").green(),
-                "Serial".bold().blue(),
-                fp.serial.as_deref().unwrap_or("Unknown").green(),
-                "Details".bold().blue(),
-                fp.details
-                    .as_deref()
-                    .map(|d| d.replace('\n', "\n    "))
-                    .unwrap_or_else(|| "None".to_string())
-                    .normal()
-            );
-            println!("{}", "-".repeat(60).dimmed());
+    /// `--source-ip` if it is IPv4, for the IPv4-only routing table and ARP.
+    fn source_ipv4(&self) -> Option<Ipv4Addr> {
+        match self.source_ip {
+            Some(IpAddr::V4(ip)) => Some(ip),
+            _ => None,
         }
     }
 
-    // 3. TCP scan (if requested)
-    if cli.tcpscan {
-        if !ports.is_empty() {
-            let min_port = *ports.first().unwrap();
-            let max_port = *ports.last().unwrap();
-            let port_range = min_port..(max_port + 1); // Range<u16>
-            println!("{}", "🔗 Performing TCP scan...".cyan());
-            let tcp_result = tcpscan::tcp_scan(&live_hosts, port_range).await;
-            tcp_result.print_summary();
+    /// Where to write the report `file`; each segment gets its own.
+    fn output_file(&self, file: &str) -> String {
+        match &self.segment {
+            Some(interface) => segments::output_path(file, interface),
+            None => file.to_string(),
         }
     }
+}
 
-    // 4. UDP scan (if requested)
+#[derive(Subcommand, Clone, Debug)]
+enum Command {
+    /// Acknowledge a monitor-mode alert as an expected change so it stops alerting
+    Ack {
+        #[arg(
+            value_name = "ALERT_ID",
+            help = "ID shown next to the alert and in alerts.log; omit to list past acknowledgements"
+        )]
+        id: Option<String>,
+        #[arg(long, value_name = "TEXT", default_value = "", help = "Why the change is expected")]
+        note: String,
+    },
+    /// Mark a finding as a false positive or an accepted risk so reports suppress it
+    Annotate {
+        #[arg(
+            value_name = "FINDING_ID",
+            help = "ID shown next to the finding in the findings report; omit to list annotations"
+        )]
+        id: Option<String>,
+        #[arg(long = "as", value_enum, value_name = "VERDICT", help = "Verdict for the finding")]
+        verdict: Option<VerdictArg>,
+        #[arg(long, value_name = "TEXT", default_value = "", help = "Why the finding is not a problem")]
+        comment: String,
+        #[arg(long, conflicts_with = "verdict", help = "Remove the annotation so the finding is reported again")]
+        clear: bool,
+    },
+    /// Attach a note to a host that reports recorded with --history carry forward
+    Note {
+        #[arg(value_name = "IP", help = "Address of the host; omit to list all notes")]
+        host: Option<IpAddr>,
+        #[arg(long, value_name = "TEXT", help = "The note, e.g. \"Lab printer, ignore telnet\"")]
+        text: Option<String>,
+        #[arg(
+            long,
+            value_name = "MAC",
+            value_parser = parse_mac,
+            help = "MAC of the host, so the note follows it to new addresses (default: as monitor mode last saw it)"
+        )]
+        mac: Option<String>,
+        #[arg(long, conflicts_with = "text", help = "Remove the host's notes")]
+        clear: bool,
+    },
+    /// Look at one host in depth within a fixed time budget and print one consolidated report
+    Host {
+        #[arg(value_name = "IP", help = "Address of the host to examine")]
+        target: IpAddr,
+        #[arg(
+            long,
+            value_name = "DURATION",
+            value_parser = scan_config::parse_timeout,
+            default_value = "2m",
+            help = "Hard limit for the whole look; a phase that does not fit keeps what it finished"
+        )]
+        budget: std::time::Duration,
+    },
+    /// Run service detection against saved samples instead of the network, to regression-test detectors
+    Replay {
+        #[arg(
+            value_name = "DIR",
+            help = "Corpus of --collect-unknowns sample files (*.txt, optionally with an \"expect: SERVICE\" line) and *.pcap captures"
+        )]
+        corpus: PathBuf,
+        #[arg(
+            short = 'r',
+            long,
+            value_name = "PROTOCOLS",
+            value_enum,
+            use_value_delimiter = true,
+            help = "Detectors to run (default: those registered for each sample's port)"
+        )]
+        protocols: Vec<ProtocolArg>,
+    },
+    /// Check every detector against a server emulating its protocol on 127.0.0.1
+    Selftest,
+    /// List the sockets listening on this machine and the processes behind them
+    Local {
+        #[arg(
+            long,
+            value_name = "PORTS",
+            value_parser = listeners::parse_expected,
+            help = "Allowed ports, e.g. 22,443,udp/53 (bare ports are TCP); fail if anything else is reachable from outside"
+        )]
+        expect: Option<BTreeSet<(&'static str, u16)>>,
+    },
+    /// Compare two runs recorded with --history
+    Diff {
+        #[arg(value_name = "OLD", help = "Earlier run ID (default: the previous run of NEW's targets)")]
+        old: Option<i64>,
+        #[arg(value_name = "NEW", help = "Later run ID (default: the latest run of OLD's targets, or the latest run)")]
+        new: Option<i64>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum VerdictArg {
+    FalsePositive,
+    AcceptedRisk,
+}
+
+impl From<VerdictArg> for Verdict {
+    fn from(arg: VerdictArg) -> Self {
+        match arg {
+            VerdictArg::FalsePositive => Verdict::FalsePositive,
+            VerdictArg::AcceptedRisk => Verdict::AcceptedRisk,
+        }
+    }
+}
+
+/// `netscan ack`: acknowledges an alert, or lists the acknowledgement history.
+fn run_ack(state_dir: &std::path::Path, id: Option<&str>, note: &str) -> Result<(), String> {
+    let state = monitor::MonitorState::open(state_dir)?;
+    let Some(id) = id else {
+        let acks = state.acknowledgements()?;
+        if acks.is_empty() {
+            println!("{}", Msg::NoAcks.text().yellow());
+        }
+        for ack in acks {
+            println!(
+                "{} {} {}",
+                ack.at.format("%Y-%m-%d %H:%M").to_string().dimmed(),
+                ack.id.bold(),
+                ack.alert
+            );
+            if !ack.note.is_empty() {
+                println!("    {}", ack.note);
+            }
+        }
+        return Ok(());
+    };
+    let ack = state.acknowledge(id, note, chrono::Utc::now())?;
+    println!("{} {}", Msg::Acknowledged.with(&[("id", &ack.id)]).green(), ack.alert);
+    Ok(())
+}
+
+/// `netscan annotate`: annotates or un-annotates a finding, or lists the annotations.
+fn run_annotate(
+    state_dir: &std::path::Path,
+    id: Option<&str>,
+    verdict: Option<VerdictArg>,
+    comment: &str,
+    clear: bool,
+) -> Result<(), String> {
+    let mut store = AnnotationStore::load(state_dir)?;
+    let Some(id) = id else {
+        if store.is_empty() {
+            println!("{}", Msg::NoAnnotations.text().yellow());
+        }
+        for annotation in store.annotations() {
+            println!(
+                "{} {} [{}] {}",
+                annotation.at.format("%Y-%m-%d %H:%M").to_string().dimmed(),
+                annotation.id.bold(),
+                annotation.verdict,
+                annotation.finding
+            );
+            if !annotation.comment.is_empty() {
+                println!("    {}", annotation.comment);
+            }
+        }
+        return Ok(());
+    };
+    if clear {
+        store
+            .remove(id)
+            .ok_or_else(|| format!("Finding {} is not annotated", id))?;
+        store.save()?;
+        println!("{}", Msg::ClearedAnnotation.with(&[("id", &id)]).green());
+        return Ok(());
+    }
+    let verdict = verdict.ok_or("Give a verdict with --as false-positive|accepted-risk, or --clear")?;
+    let annotation = store
+        .annotate(id, verdict.into(), comment, chrono::Utc::now())?
+        .clone();
+    store.save()?;
+    println!(
+        "{} {}",
+        Msg::Marked.with(&[("id", &annotation.id), ("verdict", &annotation.verdict)]).green(),
+        annotation.finding
+    );
+    Ok(())
+}
+
+fn parse_mac(text: &str) -> Result<String, String> {
+    inventory::normalize_mac(text).ok_or_else(|| format!("Invalid MAC address: {}", text))
+}
+
+/// `netscan note`: attaches a note to a host or removes its notes, or lists all notes.
+fn run_note(
+    state_dir: &std::path::Path,
+    host: Option<IpAddr>,
+    text: Option<&str>,
+    mac: Option<&str>,
+    clear: bool,
+) -> Result<(), String> {
+    let mut notes = HostNotes::load(state_dir)?;
+    let Some(ip) = host else {
+        if notes.is_empty() {
+            println!("{}", Msg::NoHostNotes.text().yellow());
+        }
+        for note in notes.notes() {
+            println!(
+                "{} {} {}",
+                note.at.format("%Y-%m-%d %H:%M").to_string().dimmed(),
+                note.key().bold(),
+                note.note
+            );
+        }
+        return Ok(());
+    };
+    let mac = match mac {
+        Some(mac) => Some(mac.to_string()),
+        None => monitor::MonitorState::open(state_dir)?
+            .sightings()?
+            .devices()
+            .find(|device| device.ip == ip)
+            .and_then(|device| device.mac.clone()),
+    };
+    if clear {
+        let removed = notes.clear(ip, mac.as_deref());
+        if removed == 0 {
+            return Err(format!("{} has no notes", ip));
+        }
+        notes.save()?;
+        println!("{}", Msg::RemovedNotes.with(&[("count", &removed), ("host", &ip)]).green());
+        return Ok(());
+    }
+    let text = text.ok_or("Give the note with --text, or --clear")?;
+    let key = notes.add(ip, mac.as_deref(), text, chrono::Utc::now()).key();
+    notes.save()?;
+    println!("{} {}", Msg::Noted.with(&[("host", &key)]).green(), text);
+    Ok(())
+}
+
+/// Carries what the user recorded about each host into `report`: its notes,
+/// the verdicts on its findings and the acknowledgement of its
+/// unapproved-device alert, following hosts to their current address by MAC.
+fn attach_host_notes(
+    state_dir: &std::path::Path,
+    annotations: &AnnotationStore,
+    report: &mut ScanReport,
+) {
+    let mut notes = HostNotes::load(state_dir).unwrap_or_else(|e| {
+        tracing::warn!("{}", e);
+        HostNotes::empty(state_dir)
+    });
+    let acks = if state_dir.exists() {
+        monitor::MonitorState::open(state_dir)
+            .and_then(|state| state.acknowledgements())
+            .unwrap_or_else(|e| {
+                tracing::warn!("{}", e);
+                Vec::new()
+            })
+    } else {
+        Vec::new()
+    };
+    if notes.is_empty() && annotations.is_empty() && acks.is_empty() {
+        return;
+    }
+    let hosts: Vec<(IpAddr, Option<String>)> = report
+        .hosts
+        .iter()
+        .filter(|host| host.alive)
+        .map(|host| (host.ip, inventory::discovered_mac(host.ip)))
+        .collect();
+    let mut changed = false;
+    for (ip, mac) in &hosts {
+        changed |= notes.carry_forward(*ip, mac.as_deref());
+        let mut texts: Vec<String> = notes
+            .for_host(*ip, mac.as_deref())
+            .into_iter()
+            .map(|note| note.note.clone())
+            .collect();
+        if let Some(mac) = mac {
+            for annotation in annotations.for_host(mac) {
+                let mut text = format!("{}: {}", annotation.verdict, annotation.finding);
+                if !annotation.comment.is_empty() {
+                    text.push_str(&format!(" ({})", annotation.comment));
+                }
+                texts.push(text);
+            }
+        }
+        let alert = monitor::device_alert_id(&sightings::device_key(*ip, mac.as_deref()));
+        for ack in acks.iter().filter(|ack| ack.id == alert) {
+            let mut text = format!("acknowledged: {}", ack.alert);
+            if !ack.note.is_empty() {
+                text.push_str(&format!(" ({})", ack.note));
+            }
+            texts.push(text);
+        }
+        report.add_notes(*ip, texts);
+    }
+    host_notes::print_notes(&notes, &hosts);
+    if changed && let Err(e) = notes.save() {
+        tracing::warn!("{}", e);
+    }
+}
+
+/// `netscan diff`: prints what changed between two recorded runs. With one
+/// ID, that run is compared with the latest run of the same targets; with
+/// none, the latest run is compared with the one before it.
+fn run_diff(path: &std::path::Path, old: Option<i64>, new: Option<i64>) -> Result<(), String> {
+    let history = ScanHistory::open(path)?;
+    let runs = history.runs(None)?;
+    let find = |id: i64| {
+        runs.iter()
+            .find(|run| run.id == id)
+            .ok_or_else(|| format!("No run #{} in {}", id, path.display()))
+    };
+    let (before, after) = match (old, new) {
+        (Some(old), Some(new)) => (find(old)?, find(new)?),
+        (Some(old), None) => {
+            let before = find(old)?;
+            // Runs are newest first, so the ones ahead of OLD are later
+            let after = runs
+                .iter()
+                .take_while(|run| run.id != before.id)
+                .find(|run| run.targets == before.targets)
+                .ok_or_else(|| format!("No later run of {} to compare with", before.targets))?;
+            (before, after)
+        }
+        (None, new) => {
+            let after = match new {
+                Some(new) => find(new)?,
+                None => runs
+                    .first()
+                    .ok_or_else(|| format!("No runs recorded in {}", path.display()))?,
+            };
+            let before = runs
+                .iter()
+                .skip_while(|run| run.id != after.id)
+                .skip(1)
+                .find(|run| run.targets == after.targets)
+                .ok_or_else(|| format!("No earlier run of {} recorded", after.targets))?;
+            (before, after)
+        }
+    };
+    println!(
+        "{}",
+        prettyprint::console(&format!(
+            "🔀 Run #{} ({}, {}) -> run #{} ({}, {})",
+            before.id,
+            before.targets,
+            before.started.format("%Y-%m-%d %H:%M"),
+            after.id,
+            after.targets,
+            after.started.format("%Y-%m-%d %H:%M")
+        ))
+        .bold()
+        .blue()
+    );
+    scan_diff::diff(&history.load(before.id)?, &history.load(after.id)?).print();
+    Ok(())
+}
+
+/// `netscan replay`: detects the service of every sample in `corpus` and
+/// fails if any contradicts its expectation.
+async fn run_replay(corpus: &std::path::Path, protocols: &[ProtocolArg]) -> Result<(), String> {
+    let samples = replay::load_corpus(corpus)?;
+    if samples.is_empty() {
+        return Err(format!("No samples (*.txt, *.pcap) in {}", corpus.display()));
+    }
+    let protocols: Vec<Protocol> = protocols.iter().map(|p| p.to_protocol()).collect();
+    let registry = service_detection::registry();
+    let mut failed = 0;
+    for sample in &samples {
+        let outcome = replay::replay(&registry, sample, &protocols).await?;
+        let service = outcome.service.clone().unwrap_or_else(|| "-".to_string());
+        let verdict = match outcome.passed() {
+            Some(true) => "PASS".green(),
+            Some(false) => {
+                failed += 1;
+                format!("FAIL (expected {})", outcome.expected.as_deref().unwrap_or_default()).red()
+            }
+            None => "".normal(),
+        };
+        println!("{:<40} {:>5}  {}  {}", outcome.name, outcome.port, service, verdict);
+    }
+    if failed > 0 {
+        return Err(format!("{} of {} samples did not match their expectation", failed, samples.len()));
+    }
+    println!("{}", Msg::Replayed.with(&[("count", &samples.len())]).green());
+    Ok(())
+}
+
+/// `netscan selftest`: runs service detection against the emulated servers
+/// and fails if any protocol's detector misses.
+async fn run_selftest() -> Result<(), String> {
+    let registry = service_detection::registry();
+    let outcomes = selftest::run(&registry).await;
+    let mut failed = 0;
+    for (protocol, outcome) in &outcomes {
+        let (service, verdict) = match outcome {
+            Ok(outcome) if selftest::passed(outcome) => {
+                (outcome.service.clone().unwrap_or_default(), "PASS".green())
+            }
+            Ok(outcome) => {
+                failed += 1;
+                (outcome.service.clone().unwrap_or_else(|| "-".to_string()), "FAIL".red())
+            }
+            Err(e) => {
+                failed += 1;
+                (e.clone(), "FAIL".red())
+            }
+        };
+        println!("{:<14} {}  {}", protocol.label(), verdict, service);
+    }
+    let uncovered: Vec<&str> = selftest::uncovered().iter().map(|p| p.label()).collect();
+    println!("{}", Msg::NotEmulated.with(&[("protocols", &uncovered.join(", "))]).dimmed());
+    if failed > 0 {
+        return Err(format!("{} of {} detectors failed the self-test", failed, outcomes.len()));
+    }
+    println!("{}", Msg::AllEmulatedDetected.with(&[("count", &outcomes.len())]).green());
+    Ok(())
+}
+
+/// `netscan local`: lists the listening sockets of this machine and, given
+/// an allowlist, fails if a port outside it is reachable from other machines.
+fn run_local(expect: Option<&BTreeSet<(&'static str, u16)>>) -> Result<(), String> {
+    let sockets = listeners::read_listeners()?;
+    if sockets.is_empty() {
+        println!("{}", Msg::NothingListening.text().yellow());
+    }
+    for socket in &sockets {
+        let owner = socket.owner.as_ref().map_or_else(
+            || "-".dimmed().to_string(),
+            |owner| format!("{} ({})", owner.name, owner.pid),
+        );
+        let endpoint = socket.to_string();
+        let endpoint = if socket.is_exposed() { endpoint.bold() } else { endpoint.dimmed() };
+        println!("{:<48} {}", endpoint, owner);
+    }
+    if sockets.iter().any(|socket| socket.owner.is_none()) {
+        println!("{}", Msg::OwnersRootOnly.text().dimmed());
+    }
+    let Some(expect) = expect else {
+        return Ok(());
+    };
+    let audit = ListenerAudit::new(&sockets, expect);
+    for (transport, port) in &audit.missing {
+        println!("{}", Msg::AllowedNoListener.with(&[("port", &format!("{}/{}", transport, port))]).dimmed());
+    }
+    for socket in &audit.unexpected {
+        let owner = socket.owner.as_ref().map_or("unknown process", |owner| owner.name.as_str());
+        println!("{}", Msg::NotAllowed.with(&[("socket", socket), ("owner", &owner)]).red());
+    }
+    if !audit.passed() {
+        return Err(format!("{} listener(s) outside the allowed ports", audit.unexpected.len()));
+    }
+    println!("{}", Msg::OnlyAllowedReachable.text().green());
+    Ok(())
+}
+
+fn parse_ports(ports_str: &str) -> Vec<u16> {
+    let mut ports = Vec::new();
+    for part in ports_str.split(',') {
+        if let Some((start, end)) = part.split_once('-') {
+            if let (Ok(start), Ok(end)) = (start.trim().parse::<u16>(), end.trim().parse::<u16>()) {
+                ports.extend(start..=end);
+            }
+        } else if let Ok(port) = part.trim().parse::<u16>() {
+            ports.push(port);
+        }
+    }
+    ports.sort_unstable();
+    ports.dedup();
+    ports
+}
+
+/// Scan a hostname target over both address families (no ping sweep).
+async fn scan_hostname(cli: &Cli) -> Result<(), RunError> {
+    let ports: Vec<u16> = cli.ports.as_ref().map(|s| parse_ports(s)).unwrap_or_default();
+    if ports.is_empty() {
+        return Err(RunError::new(
+            AbortCode::InvalidArguments,
+            "You must specify --ports when scanning a hostname.",
+        ));
+    }
+    if cli.udpscan || cli.fingerprint {
+        tracing::warn!("UDP scan and fingerprinting require an IP address or subnet; skipping.");
+    }
+    if cli.syn {
+        tracing::warn!("SYN scan requires an IP address or subnet; using a connect scan.");
+    }
+    let scan_config = cli.scan_config();
+
+    if cli.tcpscan {
+        println!(
+            "{}",
+            Msg::DualStackScan.with(&[("host", &redact::name(cli.target()))]).cyan()
+        );
+        let config = scan_config.with_default_concurrency(cli.auto_concurrency);
+        match tcpscan::tcp_scan_host(cli.target(), ports.clone(), &config).await {
+            Ok(result) => result.print_summary(),
+            Err(e) => tracing::warn!("TCP scan failed: {}", e),
+        }
+    }
+
+    if cli.service_detection {
+        let selection = cli.protocol_selection();
+        let config = scan_config.with_default_concurrency(cli.auto_concurrency);
+        let results = service_detection::service_scan_host(cli.target(), &ports, &selection, &config).await;
+        prettyprint::print_service_table(
+            &Msg::DetectedServices.with(&[("host", &redact::name(cli.target()))]),
+            &results,
+            &cli.table_layout(),
+        );
+        for res in &results {
+            if let Some(family) = res.family {
+                println!("  {}", Msg::AnsweredOver.with(&[("port", &res.port), ("family", &family)]));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Tells where `what` was written, or why writing `path` failed.
+fn report_written(what: Msg, path: &str, result: std::io::Result<()>) {
+    match result {
+        Ok(()) => println!("{}", Msg::ReportWritten.with(&[("what", &what), ("path", &path)]).cyan()),
+        Err(e) => tracing::error!("{}", Msg::WriteFailed.with(&[("path", &path), ("error", &e)])),
+    }
+}
+
+/// Reports `error` the way --output asks: a JSON object on stdout, or a logged error.
+fn report_error(cli: &Cli, error: &RunError) {
+    match cli.output {
+        OutputFormat::Json => println!("{}", error.to_json()),
+        OutputFormat::Text => tracing::error!("{}", error),
+    }
+}
+
+/// Reports `error` and ends the run with exit status 1.
+fn abort(cli: &Cli, error: RunError) -> ! {
+    report_error(cli, &error);
+    std::process::exit(1);
+}
+
+#[tokio::main]
+async fn main() {
+    let mut cli = Cli::parse();
+    if cli.ascii {
+        prettyprint::install_ascii_console();
+    }
+    let level = LevelFilter::from(cli.log_level).max(logging::verbosity_level(cli.verbose));
+    if let Err(e) = logging::init(level, cli.log_json) {
+        eprintln!("{}", e);
+    }
+
+    i18n::install(cli.lang.unwrap_or_else(Locale::from_env));
+    println!("{}", Msg::Banner.text().bold().blue());
+    println!("{}", "---------------------------------".blue());
+
+    // Before anything that prints hosts, so `netscan --redact diff` can be shared too
+    if cli.redact {
+        let redactor = match &cli.redact_key {
+            Some(key) => Redactor::new(key.as_bytes()),
+            None => Redactor::random(),
+        };
+        redact::install(redactor);
+        println!(
+            "{}",
+            Msg::RedactedReports.text().dimmed()
+        );
+    }
+
+    if let Some(Command::Ack { id, note }) = &cli.command {
+        if let Err(e) = run_ack(&cli.state_dir, id.as_deref(), note) {
+            abort(&cli, RunError::new(AbortCode::StateError, e));
+        }
+        return;
+    }
+    if let Some(Command::Annotate {
+        id,
+        verdict,
+        comment,
+        clear,
+    }) = &cli.command
+    {
+        if let Err(e) = run_annotate(&cli.state_dir, id.as_deref(), *verdict, comment, *clear) {
+            abort(&cli, RunError::new(AbortCode::StateError, e));
+        }
+        return;
+    }
+    if let Some(Command::Note {
+        host,
+        text,
+        mac,
+        clear,
+    }) = &cli.command
+    {
+        if let Err(e) = run_note(&cli.state_dir, *host, text.as_deref(), mac.as_deref(), *clear) {
+            abort(&cli, RunError::new(AbortCode::StateError, e));
+        }
+        return;
+    }
+    if let Some(Command::Host { target, budget }) = &cli.command {
+        match cli.exclusions() {
+            Ok(exclusions) if exclusions.contains(*target) => abort(
+                &cli,
+                RunError::new(
+                    AbortCode::NothingToScan,
+                    format!("{} is excluded from scanning.", target),
+                ),
+            ),
+            Ok(_) => {}
+            Err(e) => {
+                abort(&cli, RunError::new(AbortCode::InvalidArguments, e));
+            }
+        }
+        // --ports or --top-ports replace the default TCP set
+        let tcp_ports = match (cli.top_ports, &cli.ports) {
+            (Some(n), _) => match ports_presets::top_ports(n) {
+                Ok(ports) => ports.to_vec(),
+                Err(e) => abort(&cli, RunError::new(AbortCode::InvalidArguments, e)),
+            },
+            (None, Some(spec)) => parse_ports(spec),
+            (None, None) => quicklook::default_tcp_ports(),
+        };
+        if tcp_ports.is_empty() {
+            abort(
+                &cli,
+                RunError::new(AbortCode::InvalidArguments, "--ports names no valid port."),
+            );
+        }
+        ratelimit::global().set_max_rate(cli.max_rate);
+        println!(
+            "{}",
+            Msg::Examining.with(&[("target", target), ("budget", &budget.as_secs())]).cyan()
+        );
+        quicklook::quick_look(*target, cli.interface(), &tcp_ports, *budget, &cli.scan_config())
+            .await
+            .print();
+        return;
+    }
+    if let Some(Command::Diff { old, new }) = &cli.command {
+        let result = match &cli.history {
+            Some(path) => run_diff(path, *old, *new),
+            None => Err("netscan diff needs --history FILE".to_string()),
+        };
+        if let Err(e) = result {
+            abort(&cli, RunError::new(AbortCode::StateError, e));
+        }
+        return;
+    }
+    if let Some(Command::Selftest) = &cli.command {
+        if let Err(e) = run_selftest().await {
+            abort(&cli, RunError::new(AbortCode::CheckFailed, e));
+        }
+        return;
+    }
+    if let Some(Command::Local { expect }) = &cli.command {
+        if let Err(e) = run_local(expect.as_ref()) {
+            abort(&cli, RunError::new(AbortCode::CheckFailed, e));
+        }
+        return;
+    }
+    if let Some(Command::Replay { corpus, protocols }) = &cli.command {
+        if let Err(e) = run_replay(corpus, protocols).await {
+            abort(&cli, RunError::new(AbortCode::InvalidArguments, e));
+        }
+        return;
+    }
+
+    if let Some(path) = &cli.inventory {
+        match Inventory::load(path) {
+            Ok(known) => {
+                println!(
+                    "{}",
+                    Msg::Inventory.with(&[("count", &known.len())]).dimmed()
+                );
+                inventory::install(known);
+            }
+            Err(e) => {
+                abort(&cli, RunError::new(AbortCode::StateError, e));
+            }
+        }
+    }
+
+    match cli.target_list() {
+        Ok(list) => cli.ip = list,
+        Err(e) => {
+            abort(&cli, RunError::new(AbortCode::InvalidTarget, e));
+        }
+    }
+
+    if let Some(n) = cli.top_ports {
+        match ports_presets::top_ports_spec(n) {
+            Ok(ports) => cli.ports = Some(ports),
+            Err(e) => {
+                abort(&cli, RunError::new(AbortCode::InvalidArguments, e));
+            }
+        }
+    }
+
+    // A lone local address usually means "my network": name its broadcast domain
+    if let Some(Ok(IpAddr::V4(ip))) = cli.ip.as_deref().map(str::parse::<IpAddr>)
+        && let Some(subnet) = routing::local_subnet(ip)
+    {
+        if cli.auto_expand {
+            let expanded = subnet.around(ip, routing::MAX_AUTO_EXPAND_HOSTS);
+            if expanded != subnet {
+                println!(
+                    "{}",
+                    Msg::ExpansionCapped
+                        .with(&[
+                            ("subnet", &subnet),
+                            ("max", &routing::MAX_AUTO_EXPAND_HOSTS),
+                            ("cidr", &subnet.cidr()),
+                        ])
+                        .dimmed()
+                );
+            }
+            println!("{}", Msg::Expanding.with(&[("target", &ip), ("subnet", &expanded)]).yellow());
+            cli.ip = Some(expanded.cidr());
+        } else {
+            println!(
+                "{}",
+                Msg::OnLinkHint
+                    .with(&[("target", &ip), ("subnet", &subnet), ("cidr", &subnet.cidr())])
+                    .dimmed()
+            );
+        }
+    } else if cli.auto_expand {
+        tracing::warn!("--auto-expand needs a single IPv4 address on a local subnet; scanning as given.");
+    }
+
+    if let Some(timing) = cli.timing {
+        println!("{}", Msg::TimingTemplate.with(&[("template", &TimingTemplate::from(timing))]).yellow());
+    }
+    let template_concurrency = cli
+        .timing
+        .and_then(|timing| TimingTemplate::from(timing).concurrency());
+    if cli.concurrency.is_none() && template_concurrency.is_none() {
+        let limits = autotune::SystemLimits::read();
+        let concurrency = limits.concurrency();
+        cli.auto_concurrency = Some(concurrency);
+        tracing::info!("{}", Msg::ProbesInFlight.with(&[("count", &concurrency), ("limits", &limits)]));
+    }
+
+    // This machine answers at once: no discovery, long timeouts or pacing needed
+    if !cli.no_fast_path
+        && let Ok(specs) = targets::parse_targets(cli.target())
+        && fastpath::all_local(&specs, &fastpath::local_addresses())
+    {
+        cli.local_fast = true;
+        let capped = cli.max_rate.take().is_some() | cli.max_bandwidth.take().is_some();
+        if capped {
+            println!("{}", Msg::RateCapsLifted.text().yellow());
+        }
+        println!("{}", Msg::LocalTargets.text().yellow());
+    }
+
+    let dc_profile = cli.profile == Some(ProfileArg::DcAudit);
+    if dc_profile {
+        // The profile is a preset: it needs the TCP scan to classify hosts
+        cli.tcpscan = true;
+        if cli.ports.is_none() {
+            let ports: Vec<String> = dc_audit::DC_AUDIT_PORTS
+                .iter()
+                .map(|p| p.to_string())
+                .collect();
+            cli.ports = Some(ports.join(","));
+        }
+        println!(
+            "{}",
+            Msg::DcAuditProfile.text().yellow()
+        );
+    }
+
+    if cli.compliance.is_some() {
+        // Compliance controls are judged on the TLS, weak-protocol and banner findings
+        cli.tls_grade = true;
+        cli.weak_protocols = true;
+        cli.banners = true;
+    }
+    if cli.pipeline || cli.suggest_msf || cli.cve_lookup {
+        cli.service_detection = true;
+    }
+    // A broken rules file is reported before the scan, not after it
+    if cli.suggest_msf
+        && let Err(e) = suggest::default_rules()
+    {
+        abort(&cli, RunError::new(AbortCode::InvalidArguments, e));
+    }
+    if cli.tls_grade || cli.weak_protocols || cli.banners || cli.syn || cli.pipeline {
+        cli.tcpscan = true;
+    }
+
+    let Some(interval) = cli.monitor else {
+        if !cli.window.is_empty() {
+            tracing::warn!("--window only applies to monitor mode (--monitor); ignoring.");
+        }
+        if let Err(e) = run_segments(&cli).await {
+            abort(&cli, e);
+        }
+        return;
+    };
+    let state = match monitor::MonitorState::open(&cli.state_dir) {
+        Ok(state) => state,
+        Err(e) => {
+            abort(&cli, RunError::new(AbortCode::StateError, e));
+        }
+    };
+    println!(
+        "{}",
+        Msg::MonitorMode
+            .with(&[("seconds", &interval), ("path", &state.dir().display())])
+            .yellow()
+    );
+    if !cli.tcpscan {
+        println!("{}", Msg::AvailabilityNeedsTcpScan.text().dimmed());
+    } else if !cli.tls_grade {
+        println!("{}", Msg::CertAlertsNeedTlsGrade.text().dimmed());
+    }
+    if inventory::installed().is_none() {
+        println!("{}", Msg::DeviceAlertsNeedInventory.text().dimmed());
+    }
+    match state.mutes() {
+        Ok(mutes) => {
+            for rule in mutes.rules() {
+                println!("{}", Msg::MutingAlerts.with(&[("rule", &rule)]).dimmed());
+            }
+        }
+        Err(e) => tracing::warn!("{}", e),
+    }
+    // Outside every window the rate limiter holds all probes; the enforcer
+    // releases it when the next window opens
+    let windows = Schedule::new(cli.window.clone());
+    if !windows.is_empty() {
+        for window in windows.windows() {
+            println!("{}", Msg::ScanWindow.with(&[("window", &window)]).yellow());
+        }
+        ratelimit::global().set_paused(!windows.is_open(chrono::Utc::now()));
+        tokio::spawn(schedule::enforce(windows.clone(), ratelimit::global()));
+    }
+    loop {
+        if ratelimit::global().is_paused() {
+            let now = chrono::Utc::now();
+            println!(
+                "{}",
+                Msg::OutsideWindows
+                    .with(&[("next", &schedule::describe_next(windows.next_change(now)))])
+                    .yellow()
+            );
+            ratelimit::global().wait_while_paused().await;
+        }
+        // A pass still running when its window closes is stopped, not only
+        // throttled: discovery and service detection do not all go through
+        // the rate limiter
+        let pass = tokio::select! {
+            result = run_segments(&cli) => Some(result),
+            _ = ratelimit::global().wait_until_paused(), if !windows.is_empty() => None,
+        };
+        let runs = match pass {
+            Some(Ok(runs)) => runs,
+            None => {
+                println!("{}", Msg::PassStopped.text().yellow());
+                continue;
+            }
+            Some(Err(e)) => {
+                // One failed pass says nothing about the network; try the next
+                report_error(&cli, &e);
+                println!("{}", Msg::NextScan.with(&[("seconds", &interval)]).dimmed());
+                tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+                continue;
+            }
+        };
+        let mut certificates = CertificateInventory::new();
+        for run in &runs {
+            certificates.merge(&run.certificates);
+        }
+        if cli.tls_grade
+            && let Err(e) = monitor::check_certificates(
+                &state,
+                &certificates,
+                chrono::Utc::now(),
+                cli.cert_expiry_days,
+            )
+        {
+            tracing::warn!("{}", e);
+        }
+        for run in &runs {
+            if let Some(Err(e)) =
+                monitor::check_installed_inventory(&state, &run.live_hosts, chrono::Utc::now())
+            {
+                tracing::warn!("{}", e);
+            }
+        }
+        for run in runs {
+            if let Some(tcp) = run.tcp
+                && let Err(e) = monitor::check_availability(
+                    &state,
+                    &run.targets,
+                    &tcp.ports,
+                    &tcp.open,
+                    chrono::Utc::now(),
+                )
+            {
+                tracing::warn!("{}", e);
+            }
+        }
+        println!("{}", Msg::NextScan.with(&[("seconds", &interval)]).dimmed());
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+    }
+}
+
+/// What one pass over the targets observed, for monitor mode to compare across runs
+#[derive(Default)]
+struct ScanRun {
+    certificates: CertificateInventory,
+    /// Every address the run covered, whether or not it answered discovery
+    targets: Vec<IpAddr>,
+    live_hosts: Vec<IpAddr>,
+    tcp: Option<TcpCoverage>,
+    /// Everything found, per host, for --history
+    report: ScanReport,
+    /// Findings of the post-scan checks, if any ran, for `netscan annotate`
+    findings: Option<BTreeMap<IpAddr, Vec<Finding>>>,
+}
+
+/// Ports the TCP scan of a run covered and the ones it found open
+struct TcpCoverage {
+    ports: Vec<u16>,
+    open: Vec<(IpAddr, u16)>,
+}
+
+/// Records each run in the --history database, with the targets it covered.
+fn record_history(
+    cli: &Cli,
+    started: chrono::DateTime<chrono::Utc>,
+    runs: &[(String, &ScanRun)],
+) {
+    let Some(path) = &cli.history else {
+        return;
+    };
+    let mut history = match ScanHistory::open(path) {
+        Ok(history) => history,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return;
+        }
+    };
+    for (targets, run) in runs {
+        // Under --redact the history holds pseudonyms only
+        match history.record(
+            started,
+            chrono::Utc::now(),
+            &redact::text(targets),
+            &run.report.redacted(),
+        ) {
+            Ok(id) => println!(
+                "{}",
+                Msg::RunRecorded
+                    .with(&[
+                        ("id", &id),
+                        ("targets", &redact::text(targets)),
+                        ("path", &path.display()),
+                    ])
+                    .cyan()
+            ),
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+}
+
+/// Scans the segments mapped with --interface IFACE=TARGETS one after
+/// another, or the --ip targets once when no segment has its own targets.
+/// A segment that fails is skipped; the run fails only if every one did.
+async fn run_segments(cli: &Cli) -> Result<Vec<ScanRun>, RunError> {
+    let started = chrono::Utc::now();
+    if cli.ip.is_some()
+        && cli.interface.len() <= 1
+        && cli.interface.iter().all(|s| s.targets.is_none())
+    {
+        let run = run_scan(cli).await?;
+        record_history(cli, started, &[(cli.target().to_string(), &run)]);
+        save_findings(cli, &[&run]);
+        return Ok(vec![run]);
+    }
+    let plan = segments::plan(&cli.interface, cli.ip.as_deref())
+        .map_err(|e| RunError::new(AbortCode::InvalidArguments, e))?;
+    let mut outcomes = Vec::new();
+    for (segment, targets) in &plan {
+        println!(
+            "{}",
+            Msg::SegmentScan
+                .with(&[("interface", &segment.interface), ("targets", targets)])
+                .yellow()
+        );
+        let mut segment_cli = cli.clone();
+        segment_cli.ip = Some(targets.clone());
+        segment_cli.interface = vec![Segment {
+            interface: segment.interface.clone(),
+            targets: None,
+        }];
+        segment_cli.segment = Some(segment.interface.clone());
+        // Sourcing connect scans from the interface's address keeps them on its segment
+        if cli.source_ip.is_none() {
+            segment_cli.source_ip = routing::interface_ipv4(&segment.interface).map(IpAddr::V4);
+        }
+        let outcome = run_scan(&segment_cli).await;
+        if let Err(e) = &outcome {
+            tracing::error!("Segment {} failed: {}", segment.interface, e);
+        }
+        outcomes.push(outcome);
+    }
+    let recorded: Vec<(String, &ScanRun)> = plan
+        .iter()
+        .zip(&outcomes)
+        .filter_map(|((_, targets), outcome)| Some((targets.clone(), outcome.as_ref().ok()?)))
+        .collect();
+    record_history(cli, started, &recorded);
+    let succeeded: Vec<&ScanRun> = recorded.iter().map(|(_, run)| *run).collect();
+    save_findings(cli, &succeeded);
+
+    println!("\n{}", Msg::Segments.text().bold().underline().blue());
+    println!(
+        "{:<12} {:<32} {:>10} {:>10}",
+        "Interface".bold().cyan(),
+        "Targets".bold().cyan(),
+        "Live hosts".bold().cyan(),
+        "Open ports".bold().cyan()
+    );
+    for ((segment, targets), outcome) in plan.iter().zip(&outcomes) {
+        let Ok(run) = outcome else {
+            println!(
+                "{:<12} {:<32} {:>10} {:>10}",
+                segment.interface,
+                targets,
+                "failed".red(),
+                "-"
+            );
+            continue;
+        };
+        let open: &[(IpAddr, u16)] = run.tcp.as_ref().map_or(&[], |tcp| &tcp.open);
+        println!(
+            "{:<12} {:<32} {:>10} {:>10}",
+            segment.interface,
+            targets,
+            run.live_hosts.len(),
+            if run.tcp.is_some() { open.len().to_string() } else { "-".to_string() }
+        );
+        for &(ip, port) in open {
+            println!(
+                "  {} {}",
+                std::net::SocketAddr::new(ip, port).to_string().green(),
+                format!("via {}", segment.interface).dimmed()
+            );
+        }
+    }
+    let mut runs = Vec::new();
+    let mut first_error = None;
+    for outcome in outcomes {
+        match outcome {
+            Ok(run) => runs.push(run),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    match first_error {
+        Some(e) if runs.is_empty() => Err(e),
+        _ => Ok(runs),
+    }
+}
+
+/// Saves the findings of the runs that did post-scan checks in one file,
+/// so `netscan annotate` can look up the IDs of every segment.
+fn save_findings(cli: &Cli, runs: &[&ScanRun]) {
+    let mut findings: BTreeMap<IpAddr, Vec<Finding>> = BTreeMap::new();
+    let mut checked = false;
+    for run_findings in runs.iter().filter_map(|run| run.findings.as_ref()) {
+        checked = true;
+        for (ip, list) in run_findings {
+            findings.entry(*ip).or_default().extend(list.iter().cloned());
+        }
+    }
+    if checked
+        && let Err(e) =
+            annotations::save_findings(&cli.state_dir, &findings, inventory::discovered_mac)
+    {
+        tracing::warn!("{}", e);
+    }
+}
+
+/// Prints a port found open while the scan goes on.
+fn print_open_port(ip: IpAddr, port: u16) {
+    println!(
+        "\r  {} {}",
+        rdns::label(ip).green(),
+        Msg::PortOpen.with(&[("port", &port)]).green()
+    );
+}
+
+/// Runs the --on-finding command for every open port of the run that passes `filter`.
+async fn run_finding_hooks(
+    command: &str,
+    concurrency: usize,
+    filter: Option<&HookFilter>,
+    report: &ScanReport,
+) {
+    let hook = match FindingHook::new(command, concurrency) {
+        Ok(hook) => hook,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return;
+        }
+    };
+    let mut events = hooks::events(report);
+    let found = events.len();
+    if let Some(filter) = filter {
+        events.retain(|event| filter.matches(event));
+    }
+    if events.is_empty() {
+        if found > 0 {
+            println!(
+                "{}",
+                Msg::NoFindingPassed.with(&[("count", &found)]).dimmed()
+            );
+        }
+        return;
+    }
+    println!(
+        "{}",
+        Msg::RunningHooks.with(&[("count", &events.len())]).cyan()
+    );
+    let outcomes = hook.run(events).await;
+    let failed: Vec<_> = outcomes.iter().filter(|o| o.result.is_err()).collect();
+    for outcome in &failed {
+        if let Err(e) = &outcome.result {
+            tracing::warn!("{}:{}: {}", outcome.event.ip, outcome.event.port, e);
+        }
+    }
+    println!(
+        "{}",
+        Msg::HooksDone
+            .with(&[("succeeded", &(outcomes.len() - failed.len())), ("failed", &failed.len())])
+            .cyan()
+    );
+}
+
+fn ipv4_only(hosts: &[IpAddr]) -> Vec<Ipv4Addr> {
+    hosts
+        .iter()
+        .filter_map(|ip| match ip {
+            IpAddr::V4(ip) => Some(*ip),
+            IpAddr::V6(_) => None,
+        })
+        .collect()
+}
+
+fn ipv6_only(hosts: &[IpAddr]) -> Vec<Ipv6Addr> {
+    hosts
+        .iter()
+        .filter_map(|ip| match ip {
+            IpAddr::V6(ip) => Some(*ip),
+            IpAddr::V4(_) => None,
+        })
+        .collect()
+}
+
+/// Enumerates the IPv6 hosts on --interface (or on every IPv6 interface) and
+/// returns their routable addresses; link-local ones need a scope to be scanned.
+async fn enumerate_ipv6_links(cli: &Cli, exclusions: &ExclusionList) -> Vec<IpAddr> {
+    let interfaces = match cli.interface() {
+        Some(name) => vec![name.to_string()],
+        None => linklocal::ipv6_interfaces(),
+    };
+    let mut found = Vec::new();
+    for interface in interfaces {
+        println!(
+            "{}",
+            Msg::EnumeratingIpv6.with(&[("interface", &interface)]).yellow()
+        );
+        match linklocal::enumerate_link(&interface, cli.vlan).await {
+            Ok(result) => {
+                result.print_summary();
+                for ip in result.scan_addresses() {
+                    if ip.is_unicast_link_local() {
+                        sockets::register_link_scope(ip, result.get_scope_id());
+                    }
+                    if !exclusions.contains(IpAddr::V6(ip)) {
+                        found.push(IpAddr::V6(ip));
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("IPv6 link enumeration on {} failed: {}", interface, e),
+        }
+    }
+    found
+}
+
+async fn run_scan(cli: &Cli) -> Result<ScanRun, RunError> {
+    let mut run = ScanRun::default();
+    let dc_profile = cli.profile == Some(ProfileArg::DcAudit);
+
+    let specs = match targets::parse_targets(cli.target()) {
+        Ok(specs) => specs,
+        Err(e) => {
+            return Err(RunError::new(AbortCode::InvalidTarget, e));
+        }
+    };
+
+    let exclusions = match cli.exclusions() {
+        Ok(exclusions) => exclusions,
+        Err(e) => {
+            return Err(RunError::new(AbortCode::InvalidArguments, e));
+        }
+    };
+
+    // A lone hostname target skips the ICMP sweep and races its A/AAAA records instead,
+    // unless its addresses have to be checked against exclusions first
+    if let [TargetSpec::Hostname(_)] = specs.as_slice()
+        && exclusions.is_empty()
+    {
+        scan_hostname(cli).await?;
+        return Ok(run);
+    }
+
+    // Overlapping targets are deduplicated by canonical address before any probe is sent
+    let mut target_set = match targets::resolve_targets(&specs).await {
+        Ok(set) => set,
+        Err(e) => {
+            return Err(RunError::new(AbortCode::InvalidTarget, e));
+        }
+    };
+    let excluded = target_set.exclude(&exclusions);
+    if excluded > 0 {
+        println!(
+            "{}",
+            Msg::TargetsExcluded.with(&[("excluded", &excluded), ("left", &target_set.len())]).yellow()
+        );
+    }
+    if target_set.is_empty() {
+        return Err(RunError::new(
+            AbortCode::NothingToScan,
+            "Every target is excluded; nothing to scan.",
+        ));
+    }
+    run.targets = target_set.keys().map(|key| key.ip()).collect();
+    if target_set.duplicates() > 0 {
+        println!(
+            "{}",
+            Msg::DuplicatesMerged
+                .with(&[("duplicates", &target_set.duplicates()), ("unique", &target_set.len())])
+                .yellow()
+        );
+    }
+    if let Some(source) = cli.source_ip {
+        if routing::interface_for_ip(source).is_none() {
+            return Err(RunError::new(
+                AbortCode::NoInterface,
+                format!("Source IP {} is not assigned to any local interface", source),
+            ));
+        }
+        if target_set.hosts().iter().any(|ip| ip.is_ipv4() != source.is_ipv4()) {
+            tracing::warn!(
+                "--source-ip {} cannot reach targets of the other address family; they are probed from the routing table's choice",
+                source
+            );
+        }
+    }
+
+    ratelimit::global().set_max_bandwidth(cli.max_bandwidth);
+    if let Some(bps) = cli.max_bandwidth {
+        println!(
+            "{}",
+            Msg::BandwidthCapped.with(&[("bandwidth", &ratelimit::format_bandwidth(bps))]).yellow()
+        );
+    }
+    ratelimit::global().set_max_rate(cli.max_rate);
+    if let Some(pps) = cli.max_rate {
+        println!("{}", Msg::ProbeRateCapped.with(&[("rate", &pps)]).yellow());
+    } else if let Some(delay) = cli
+        .timing
+        .and_then(|timing| TimingTemplate::from(timing).probe_delay())
+    {
+        ratelimit::global().set_probe_interval(Some(delay));
+        println!(
+            "{}",
+            Msg::ProbesSpaced.with(&[("delay", &format!("{:?}", delay))]).yellow()
+        );
+    }
+
+    if cli.vlan.is_some() && !cli.arp && !cli.link_local {
+        tracing::warn!("--vlan only applies to raw-socket operations (--arp, --link-local); ignoring.");
+    }
+
+    // Names in DNS are a discovery signal of their own on ICMP-filtered networks
+    let mut timings = RunTimings::new();
+    let mut ptr_named: Vec<IpAddr> = Vec::new();
+    if cli.ptr_sweep {
+        let started = Instant::now();
+        println!(
+            "{}",
+            Msg::ResolvingPtr.with(&[("count", &target_set.len())]).yellow()
+        );
+        ptr_named = rdns::ptr_sweep(&target_set.hosts(), &cli.scan_config())
+            .await
+            .into_iter()
+            .map(|host| host.ip)
+            .collect();
+        println!("{}", Msg::PtrRecords.with(&[("count", &ptr_named.len())]));
+        timings.record("PTR sweep", started.elapsed());
+    }
+
+    // 1. Always perform live host discovery (ping sweep, or ARP/NDP sweep with --arp)
+    let started = Instant::now();
+    let mut rtts = BTreeMap::new();
+    let mut ttls = BTreeMap::new();
+    let mut ping_retried = BTreeMap::new();
+    let discovered: Result<Vec<IpAddr>, RunError> = if cli.local_fast {
+        // This machine is up by definition
+        Ok(target_set.hosts())
+    } else if cli.arp {
+        if let Some(name) = cli.interface()
+            && !routing::interface_exists(name)
+        {
+            return Err(RunError::new(
+                AbortCode::NoInterface,
+                format!("Interface {} not found", name),
+            ));
+        }
+        if !warnings::has_raw_socket_privilege() {
+            return Err(RunError::new(
+                AbortCode::PermissionDenied,
+                "ARP and neighbor discovery need root or CAP_NET_RAW",
+            ));
+        }
+        // ARP and NDP sweep whole networks and ranges; hosts named on their own get a /32 or /128 sweep
+        let mut subnets: Vec<(String, Vec<IpAddr>)> = specs
+            .iter()
+            .filter_map(|spec| match spec {
+                TargetSpec::Network(cidr) => {
+                    Some((cidr.clone(), pingsweep::parse_network(cidr).unwrap_or_default()))
+                }
+                TargetSpec::Range(first, last) => {
+                    Some((spec.to_string(), targets::expand_range(*first, *last)))
+                }
+                _ => None,
+            })
+            .collect();
+        for key in target_set.keys() {
+            let in_network = target_set.sources(key).is_some_and(|sources| {
+                sources.iter().any(|s| subnets.iter().any(|(subnet, _)| subnet == s))
+            });
+            if !in_network {
+                match key.ip() {
+                    IpAddr::V4(ip) => subnets.push((format!("{}/32", ip), vec![key.ip()])),
+                    IpAddr::V6(ip) => subnets.push((format!("{}/128", ip), vec![key.ip()])),
+                }
+            }
+        }
+        let mut found = Vec::new();
+        let mut failure = None;
+        for (subnet, hosts) in &subnets {
+            // Excluded hosts inside a swept network are never asked
+            let hosts: Vec<IpAddr> = hosts
+                .iter()
+                .copied()
+                .filter(|ip| !exclusions.contains(*ip))
+                .collect();
+            if hosts.is_empty() {
+                continue;
+            }
+            if subnet.contains(':') {
+                println!(
+                    "{}",
+                    Msg::NeighborSweep.with(&[("target", subnet)]).yellow()
+                );
+                match ndpsweep::ndp_sweep_hosts(ipv6_only(&hosts), cli.interface(), cli.vlan).await {
+                    Ok(result) => found.extend(
+                        result.get_live_hosts().iter().map(|(ip, _)| IpAddr::V6(*ip)),
+                    ),
+                    Err(e) => failure = Some(format!("Neighbor discovery sweep failed: {}", e)),
+                }
+                continue;
+            }
+            println!(
+                "{}",
+                Msg::ArpSweep.with(&[("target", subnet)]).yellow()
+            );
+            match arpsweep::arp_sweep_hosts(ipv4_only(&hosts), cli.interface(), cli.vlan, cli.source_ipv4())
+                .await
+            {
+                Ok(result) => found.extend(
+                    result.get_live_hosts().iter().map(|(ip, _)| IpAddr::V4(*ip)),
+                ),
+                Err(e) => failure = Some(format!("ARP sweep failed: {}", e)),
+            }
+        }
+        match failure {
+            Some(e) if found.is_empty() => Err(RunError::new(AbortCode::DiscoveryFailed, e)),
+            _ => Ok(targets::merge_duplicates(found, |ip| (*ip).into(), |_, _| {})),
+        }
+    } else {
+        println!(
+            "{}",
+            Msg::PingSweep.with(&[("target", &cli.target())]).yellow()
+        );
+        let (progress, reporter) = progress::spawn_reporter("Ping sweep");
+        let sweep =
+            pingsweep::ping_sweep_hosts(target_set.hosts(), &cli.scan_config().with_progress(progress))
+                .await;
+        reporter.abort();
+        eprintln!();
+        rtts = sweep.get_rtts().clone();
+        ttls = sweep.get_ttls().clone();
+        ping_retried = sweep.get_retried().clone();
+        Ok(sweep.get_live_hosts().clone())
+    };
+    // IPv6 hosts on the link, found without knowing their addresses
+    let discovered = match discovered {
+        Ok(mut hosts) if cli.link_local => {
+            hosts.extend(enumerate_ipv6_links(cli, &exclusions).await);
+            Ok(targets::merge_duplicates(hosts, |ip| (*ip).into(), |_, _| {}))
+        }
+        other => other,
+    };
+    let live_hosts: Vec<IpAddr> = match discovered {
+        Ok(hosts) => {
+            let found: HashSet<IpAddr> = hosts.iter().copied().collect();
+            let missed = ptr_named.iter().filter(|ip| !found.contains(ip)).count();
+            let hosts = rdns::prioritize(&hosts, &ptr_named);
+            println!("{}", Msg::LiveHostsFound.with(&[("count", &hosts.len())]));
+            if missed > 0 {
+                println!(
+                    "{}",
+                    Msg::PtrOnlyHosts.with(&[("count", &missed)]).yellow()
+                );
+            }
+            let named = if cli.no_dns {
+                hosts
+                    .iter()
+                    .map(|&ip| LiveHost { ip, hostname: None })
+                    .collect()
+            } else {
+                rdns::resolve_hosts(&hosts, &cli.scan_config()).await
+            };
+            run.report.add_discovery(&named);
+            run.report.add_ping_attempts(&ping_retried);
+            // Pseudonyms in address order, before the first host is shown
+            redact::register(
+                named.iter().map(|h| h.ip),
+                named.iter().filter_map(|h| h.hostname.as_deref()),
+            );
+            for h in &named {
+                // The routing table lookup only covers IPv4
+                let route = match h.ip {
+                    IpAddr::V4(ip) => routing::select_source(ip, cli.source_ipv4()).ok(),
+                    IpAddr::V6(_) => None,
+                };
+                match route {
+                    Some(route) => println!(
+                        "  {} {}",
+                        rdns::label(h.ip).green(),
+                        format!("({})", route).dimmed()
+                    ),
+                    None => println!("  {}", rdns::label(h.ip).green()),
+                }
+            }
+            if let Some(known) = inventory::installed() {
+                // Devices acknowledged in monitor mode are approved
+                let unknown = monitor::unapproved_hosts(
+                    &cli.state_dir,
+                    known,
+                    &hosts,
+                    inventory::discovered_mac,
+                );
+                if !unknown.is_empty() {
+                    println!(
+                        "{}",
+                        Msg::UnknownDevices.with(&[("count", &unknown.len())]).yellow().bold()
+                    );
+                    for ip in unknown {
+                        println!("  {}", rdns::label(ip).yellow());
+                    }
+                }
+            }
+            if hosts.is_empty() {
+                println!("{}", Msg::NoLiveHosts.text().red());
+                return Ok(run);
+            }
+            hosts
+        }
+        Err(e) => return Err(e),
+    };
+
+    timings.record("Discovery", started.elapsed());
+
+    // --- SKIP LOCAL HOST (robust version) ---
+    // Unless this machine is the target, as on the fast path
+    let local_ip = if cli.local_fast {
+        None
+    } else {
+        match local_ip() {
+            Ok(ip) => Some(ip),
+            Err(_) => {
+                tracing::warn!("Could not determine local IPv4 address, skipping local host filtering.");
+                None
+            }
+        }
+    };
+    let live_hosts = fastpath::hosts_to_scan(live_hosts, local_ip, cli.local_fast);
+    run.live_hosts = live_hosts.clone();
+
+    // --- Require user to specify ports for all scans/service-detection ---
+    if cli.tcpscan || cli.udpscan || cli.service_detection || cli.fingerprint {
+        if cli.ports.is_none() {
+            return Err(RunError::new(
+                AbortCode::InvalidArguments,
+                "You must specify --ports for scanning, fingerprinting, or service detection.",
+            ));
+        }
+    }
+
+    // Parse ports once for all relevant operations
+    let ports: Vec<u16> = cli.ports.as_ref().map(|s| parse_ports(s)).unwrap_or_default();
+
+    // Warn up front about host conditions that would skew the results
+    let planned_connections = live_hosts.len() * ports.len();
+    let mut scan_config = cli.scan_config();
+    if cli.adaptive_timing {
+        // ARP, NDP and --local-fast discovery measure no round trips; neither do hosts found without a ping
+        let unmeasured: Vec<IpAddr> = live_hosts
+            .iter()
+            .copied()
+            .filter(|ip| !rtts.contains_key(ip))
+            .collect();
+        if !unmeasured.is_empty() && !ports.is_empty() {
+            rtts.extend(
+                tcpscan::measure_rtts(&unmeasured, &ports, cli.source_ip, &scan_config)
+                    .await,
+            );
+        }
+        if rtts.is_empty() {
+            tracing::warn!("--adaptive-timing measured no round trips; using fixed timeouts.");
+        } else {
+            let timeouts = timing_template::adaptive_timeouts(&rtts);
+            if let (Some(fastest), Some(slowest)) = (timeouts.values().min(), timeouts.values().max()) {
+                println!(
+                    "{}",
+                    Msg::AdaptiveTimeouts
+                        .with(&[
+                            ("count", &timeouts.len()),
+                            ("fastest", &format!("{:?}", fastest)),
+                            ("slowest", &format!("{:?}", slowest)),
+                        ])
+                        .yellow()
+                );
+            }
+            scan_config = scan_config.with_host_timeouts(timeouts);
+        }
+    }
+    // The budget from the system limits is for the port probes alone
+    let probe_config = scan_config.with_default_concurrency(cli.auto_concurrency);
+    let concurrency = probe_config.concurrency_or(tcpscan::MAX_CONCURRENT_TASKS);
+    for warning in warnings::check_environment(planned_connections, concurrency) {
+        tracing::warn!("{}", warning);
+    }
+
+    // 2. Fingerprinting (if requested)
+    if cli.fingerprint {
+        if cli.update_oui {
+            match tokio::task::spawn_blocking(oui::update_cache).await {
+                Ok(Ok(count)) => println!(
+                    "{}",
+                    Msg::OuiRegistry.with(&[("count", &count), ("path", &oui::CACHE_PATH)]).dimmed()
+                ),
+                Ok(Err(e)) => tracing::warn!("{}; using the built-in vendor table.", e),
+                Err(e) => tracing::warn!("OUI update failed: {}", e),
+            }
+        }
+        println!("{}", Msg::Fingerprinting.text().cyan());
+        let started = Instant::now();
+        let (fingerprints, per_host): (Vec<_>, Vec<_>) =
+            futures::future::join_all(live_hosts.iter().map(|&ip| {
+                let (ports, ttl) = (&ports, ttls.get(&ip).copied());
+                async move {
+                    let host_started = Instant::now();
+                    let fp = fingerprinting::fingerprint_host(ip, ports, cli.interface(), ttl).await;
+                    (fp, (ip, host_started.elapsed()))
+                }
+            }))
+            .await
+            .into_iter()
+            .unzip();
+        timings.record_hosts("Fingerprinting", started.elapsed(), per_host);
+        let fingerprints =
+            targets::merge_duplicates(fingerprints, |fp| fp.ip.into(), |a, b| a.merge(b));
+        for mut fp in fingerprints {
+            if let (Some(_), IpAddr::V4(ip)) = (cli.source_ipv4(), fp.ip) {
+                fp.egress = routing::select_source(ip, cli.source_ipv4())
+                    .ok()
+                    .map(|route| route.to_string());
+            }
+            println!(
+                "{}\n  {}: {}\n  {}: {}\n  {}: {}\n  {}: {}\n  {}: {}\n  {}: {}",
+                rdns::label(fp.ip).bold().yellow(),
+                "OS".bold().blue(),
+                if fp.os_guesses.is_empty() {
+                    fp.os.clone().unwrap_or_else(|| "Unknown".to_string())
+                } else {
+                    os_fusion::format_guesses(&fp.os_guesses)
+                }
+                .green(),
+                "Vendor".bold().blue(),
+                fp.vendor.as_deref().unwrap_or("Unknown").green(),
+                "Model".bold().blue(),
+                fp.model.as_deref().unwrap_or("Unknown").green(),
+                "Serial".bold().blue(),
+                fp.serial.as_deref().unwrap_or("Unknown").green(),
+                "Egress".bold().blue(),
+                fp.egress.as_deref().unwrap_or("Unknown").green(),
+                "Details".bold().blue(),
+                fp.details
+                    .as_deref()
+                    .map(|d| d.replace('\n', "\n    "))
+                    .unwrap_or_else(|| "None".to_string())
+                    .normal()
+            );
+            if !fp.evidence.is_empty() {
+                println!("  {}:", "Evidence".bold().blue());
+                for evidence in &fp.evidence {
+                    println!("    {}", evidence.to_string().dimmed());
+                }
+            }
+            run.report.add_fingerprint(&fp);
+            println!("{}", "-".repeat(60).dimmed());
+        }
+    }
+
+    // Findings of the post-scan checks and the CVE lookup, reported once both ran
+    let mut host_findings: BTreeMap<IpAddr, Vec<Finding>> = BTreeMap::new();
+    // Annotated findings are left out of the check summaries and CSVs, and
+    // footnoted in the findings report
+    let mut annotations = AnnotationStore::load(&cli.state_dir).unwrap_or_else(|e| {
+        tracing::warn!("{}; reporting all findings.", e);
+        AnnotationStore::empty(&cli.state_dir)
+    });
+    for ip in &live_hosts {
+        annotations.follow(*ip, inventory::discovered_mac(*ip).as_deref());
+    }
+    // Checks that examined each host, which compliance controls are judged on
+    let mut assessed: HashMap<IpAddr, Vec<Check>> = HashMap::new();
+
+    // 3. TCP scan (if requested)
+    let mut open_by_host = None;
+    if cli.tcpscan {
+        if !ports.is_empty() {
+            let source_ip = cli.source_ip;
+            let started = Instant::now();
+            // Open ports are shown host by host as each is finished, unless so
+            // many answered that the host is suspect; the summary once all are
+            let mut syn_hosts = None;
+            if cli.syn {
+                println!("{}", Msg::TcpSynScan.text().cyan());
+                let (progress, reporter) = progress::spawn_reporter("TCP SYN scan");
+                let config = probe_config.with_progress(progress);
+                match synscan::syn_scan_stream(&live_hosts, ports.clone(), source_ip, &config) {
+                    Ok(hosts) => syn_hosts = Some((hosts, reporter)),
+                    Err(e) => {
+                        reporter.abort();
+                        tracing::warn!("{}; falling back to a connect scan.", e);
+                    }
+                }
+            }
+            let mut result = tcpscan::TcpScanResult::new();
+            if let Some((mut hosts, reporter)) = syn_hosts {
+                while let Some(host) = hosts.recv().await {
+                    for (ip, port) in host.get_trusted_open_ports() {
+                        print_open_port(ip, port);
+                    }
+                    result.merge(host);
+                }
+                reporter.abort();
+            } else {
+                if !cli.syn {
+                    println!("{}", Msg::TcpScan.text().cyan());
+                }
+                let (progress, reporter) = progress::spawn_reporter("TCP scan");
+                let config = probe_config.with_progress(progress);
+                let mut probes =
+                    tcpscan::tcp_scan_stream(&live_hosts, ports.clone(), source_ip, &config);
+                let mut probed: HashMap<IpAddr, usize> = HashMap::new();
+                while let Some(probe) = probes.recv().await {
+                    let ip = probe.ip;
+                    result.add(probe);
+                    let count = probed.entry(ip).or_default();
+                    *count += 1;
+                    if *count == ports.len() && !result.is_suspect(ip) {
+                        for (_, port) in result.get_open_ports().iter().filter(|(host, _)| *host == ip) {
+                            print_open_port(ip, port);
+                        }
+                    }
+                }
+                reporter.abort();
+            }
+            let tcp_result = result;
+            eprintln!();
+            timings.record("TCP scan", started.elapsed());
+            let started = Instant::now();
+            run.tcp = Some(TcpCoverage {
+                ports: ports.clone(),
+                open: tcp_result.get_trusted_open_ports(),
+            });
+            open_by_host = Some(tcp_result.open_ports_by_host());
+            run.report.add_tcp(&tcp_result);
+            tcp_result.print_summary();
+            if cli.verbose > 0 {
+                tcp_result.print_error_details();
+            }
+            let mut likely_dcs = Vec::new();
+            let mut weak = weak_protocols::WeakProtocolReport::new();
+            let mut login_banners = BannerInventory::new();
+            let probe_smb_signing = cli
+                .compliance
+                .is_some_and(|profile| ComplianceProfile::from(profile).uses(Check::SmbSigning));
+            for ip in &live_hosts {
+                let open: Vec<u16> = tcp_result
+                    .get_trusted_open_ports()
+                    .into_iter()
+                    .filter(|(host, _)| host == ip)
+                    .map(|(_, port)| port)
+                    .collect();
+                prettyprint::print_likely_roles(*ip, &open);
+                let remote_tools = detect_remote_admin::identify(*ip, &open).await;
+                prettyprint::print_remote_admin_tools(*ip, &remote_tools);
+                if cli.weak_protocols {
+                    let exposures = weak_protocols::check_host(*ip, &open).await;
+                    host_findings
+                        .entry(*ip)
+                        .or_default()
+                        .extend(exposures.iter().map(|e| e.to_finding()));
+                    weak.extend(
+                        exposures
+                            .into_iter()
+                            .filter(|e| !annotations.suppresses(*ip, &e.to_finding()))
+                            .collect(),
+                    );
+                    assessed.entry(*ip).or_default().push(Check::WeakProtocols);
+                }
+                if cli.banners {
+                    let captured = banners::capture_host(*ip, &open).await;
+                    host_findings
+                        .entry(*ip)
+                        .or_default()
+                        .extend(captured.iter().filter_map(|b| b.finding()));
+                    if captured.iter().any(|b| b.is_judged()) {
+                        assessed.entry(*ip).or_default().push(Check::LoginBanners);
+                    }
+                    login_banners.extend(
+                        captured
+                            .into_iter()
+                            .filter(|b| {
+                                !b.finding().is_some_and(|f| annotations.suppresses(*ip, &f))
+                            })
+                            .collect(),
+                    );
+                }
+                if dc_profile && roles::has_role(&open, roles::DOMAIN_CONTROLLER) {
+                    likely_dcs.push((*ip, open));
+                } else if probe_smb_signing
+                    && open.contains(&445)
+                    && let Some(finding) = dc_audit::audit_smb_signing(*ip).await
+                {
+                    host_findings.entry(*ip).or_default().push(finding);
+                    assessed.entry(*ip).or_default().push(Check::SmbSigning);
+                }
+            }
+            if cli.weak_protocols {
+                weak.print_summary();
+            }
+            if dc_profile {
+                if likely_dcs.is_empty() {
+                    println!("{}", Msg::NoLikelyDcs.text().yellow());
+                }
+                for (ip, open) in &likely_dcs {
+                    let mut report = dc_audit::audit_domain_controller(*ip, open).await;
+                    if report.smb.as_ref().is_some_and(|smb| smb.detected) {
+                        assessed.entry(*ip).or_default().push(Check::SmbSigning);
+                    }
+                    host_findings
+                        .entry(*ip)
+                        .or_default()
+                        .extend(report.findings.iter().cloned());
+                    report.findings.retain(|f| !annotations.suppresses(*ip, f));
+                    report.print_summary();
+                }
+            }
+            let mut graded = 0;
+            if cli.tls_grade {
+                println!("{}", Msg::GradingTls.text().cyan());
+                for (ip, port) in tcp_result.get_trusted_open_ports() {
+                    let mut report = tls_audit::audit_tls_endpoint(ip, port).await;
+                    host_findings
+                        .entry(ip)
+                        .or_default()
+                        .extend(report.findings.iter().cloned());
+                    report.findings.retain(|f| !annotations.suppresses(ip, f));
+                    if report.is_tls() {
+                        report.print_summary();
+                        graded += 1;
+                        assessed.entry(ip).or_default().push(Check::TlsGrade);
+                    }
+                    if let Some(cert) = &report.certificate {
+                        run.certificates.record(ip, port, cert);
+                    }
+                }
+                if graded == 0 {
+                    println!("{}", Msg::NoTlsEndpoints.text().yellow());
+                }
+            }
+            // Every certificate name is known now: register them before banners
+            // or the inventory, which may mention any of them, are shown or written
+            redact::register(std::iter::empty(), run.certificates.names());
+            if cli.banners {
+                login_banners.print_summary();
+                if !login_banners.is_empty() {
+                    let file = cli.output_file("netscan_banners.csv");
+                    report_written(
+                        Msg::LoginBanners,
+                        &file,
+                        reports::write_banners_csv(&file, &login_banners),
+                    );
+                }
+            }
+            if cli.tls_grade && graded > 0 {
+                run.certificates.print_report(chrono::Utc::now());
+                let file = cli.output_file("netscan_cert_inventory.csv");
+                report_written(
+                    Msg::CertificateInventory,
+                    &file,
+                    reports::write_certificate_inventory_csv(&file, &run.certificates),
+                );
+            }
+            timings.record("Post-scan checks", started.elapsed());
+        }
+    }
+
+    // 4. UDP scan (if requested)
     if cli.udpscan {
         if !ports.is_empty() {
-            let min_port = *ports.first().unwrap();
-            let max_port = *ports.last().unwrap();
-            let port_range = min_port..(max_port + 1); // Range<u16>
-            println!("{}", "🔗 Performing UDP scan...".cyan());
-            let udp_result = udpscan::udp_scan(&live_hosts, port_range).await;
+            println!("{}", Msg::UdpScan.text().cyan());
+            let started = Instant::now();
+            let (progress, reporter) = progress::spawn_reporter("UDP scan");
+            let udp_result = udpscan::udp_scan_from(
+                &live_hosts,
+                ports.clone(),
+                cli.source_ip,
+                &probe_config.with_progress(progress),
+            )
+            .await;
+            reporter.abort();
+            eprintln!();
+            timings.record("UDP scan", started.elapsed());
+            run.report.add_udp(&udp_result);
             udp_result.print_summary();
+            if cli.verbose > 0 {
+                udp_result.print_error_details();
+            }
         }
     }
 
     // 5. Service detection (if requested)
     if cli.service_detection {
-        let protocols: Vec<Protocol> = cli
-            .protocols
-            .as_ref()
-            .unwrap()
-            .iter()
-            .map(|p| p.to_protocol())
-            .collect();
-        for ip in &live_hosts {
-            let results =
-                service_detection::service_scan(*ip, Some(ports.clone()), &protocols).await;
-            prettyprint::pretty_print_service_results(
-                &format!("Detected Services for {}", ip),
-                &results,
+        let selection = cli.protocol_selection();
+        let layout = cli.table_layout();
+        // In pipeline mode only the ports the TCP scan found open are probed
+        let open_by_host = open_by_host.filter(|_| cli.pipeline);
+        if let Some(open) = &open_by_host {
+            let open_count: usize = open.values().map(Vec::len).sum();
+            println!(
+                "{}",
+                Msg::PipelineScan.with(&[("ports", &open_count), ("hosts", &open.len())]).cyan()
             );
-            let _ = rust_backend::utils::reports::append_summary_to_csv(
-                "netscan_protocol_summary.csv",
-                &ip.to_string(),
-                &results,
+        }
+        let (progress, reporter) = progress::spawn_reporter("Service detection");
+        let config = scan_config.with_progress(progress);
+        let started = Instant::now();
+        let mut per_host = Vec::new();
+        let registry = service_detection::registry();
+        let mut msf_suggestions = MsfSuggestions::new();
+        // Every host's ports share one concurrency budget, so a slow host does not stall the rest
+        let mut hosts = match &open_by_host {
+            Some(open) => {
+                service_detection::pipeline_scan_stream(&live_hosts, &ports, open, &selection, &config)
+            }
+            None => {
+                let endpoints: Vec<(IpAddr, u16)> = live_hosts
+                    .iter()
+                    .flat_map(|ip| ports.iter().map(move |port| (*ip, *port)))
+                    .collect();
+                service_detection::service_scan_many_stream(&endpoints, &selection, &config)
+            }
+        };
+        // Each host's table is shown as soon as its last port is done
+        let mut by_host = Vec::new();
+        let summary_file = cli.output_file("netscan_protocol_summary.csv");
+        let mut summary_written = Ok(());
+        while let Some(host) = hosts.recv().await {
+            let (ip, results) = (&host.ip, &host.results);
+            prettyprint::print_service_table(
+                &Msg::DetectedServices.with(&[("host", &rdns::label(*ip))]),
+                results,
+                &layout,
             );
+            run.report.add_services(*ip, results);
+            if cli.suggest_msf
+                && let Err(e) = msf_suggestions.add_results(&registry, *ip, results)
+            {
+                tracing::warn!("{}", e);
+            }
+            let answered: Vec<u16> = results
+                .iter()
+                .filter(|res| res.error.is_none())
+                .map(|res| res.port)
+                .collect();
+            prettyprint::print_likely_roles(*ip, &answered);
+            let appended = reports::append_summary_to_csv(&summary_file, &ip.to_string(), results);
+            // The first failure is the one worth reporting
+            if summary_written.is_ok() {
+                summary_written = appended;
+            }
+            per_host.push((host.ip, host.elapsed));
+            by_host.push((host.ip, host.results));
         }
-        println!(
-            "{}",
-            "📄 Protocol failure summary appended to netscan_protocol_summary.csv".cyan()
+        timings.record_hosts("Service detection", started.elapsed(), per_host);
+        reporter.abort();
+        eprintln!();
+        if !by_host.is_empty() {
+            report_written(Msg::ProtocolStatistics, &summary_file, summary_written);
+        }
+        if cli.suggest_msf {
+            msf_suggestions.print_report();
+            let file = cli.output_file("netscan_msf_suggestions.csv");
+            report_written(
+                Msg::MsfSuggestions,
+                &file,
+                reports::write_msf_suggestions_csv(&file, &msf_suggestions),
+            );
+        }
+        if cli.cve_lookup {
+            println!("{}", Msg::CveLookup.text().cyan());
+            let started = Instant::now();
+            let mut services = Vec::new();
+            for (ip, results) in &by_host {
+                services.extend(
+                    results
+                        .iter()
+                        .filter_map(|result| msf::service_info(&registry, *ip, result)),
+                );
+            }
+            let mut lookup = CveLookup::new(&cli.state_dir.join("cve"));
+            // The feeds are downloaded with a blocking client
+            let fetched = tokio::task::spawn_blocking(move || {
+                let errors = lookup.fetch(&services);
+                (lookup, services, errors)
+            })
+            .await;
+            match fetched {
+                Ok((lookup, services, errors)) => {
+                    for e in errors {
+                        tracing::warn!("{}", e);
+                    }
+                    for service in &services {
+                        if let Ok(ip) = service.host.parse::<IpAddr>() {
+                            host_findings
+                                .entry(ip)
+                                .or_default()
+                                .extend(lookup.findings(service));
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("CVE lookup failed: {}", e),
+            }
+            timings.record("CVE lookup", started.elapsed());
+        }
+    }
+
+    // 6. Findings report (if the post-scan checks or the CVE lookup ran)
+    let checked =
+        run.tcp.is_some() && (cli.weak_protocols || cli.banners || cli.tls_grade || dc_profile);
+    if checked || cli.cve_lookup {
+        if annotations.carry_forward(&host_findings)
+            && let Err(e) = annotations.save()
+        {
+            tracing::warn!("{}", e);
+        }
+        let annotated = annotations.apply(&host_findings);
+        annotated.print_report();
+        run.findings = Some(host_findings);
+        if let Some(profile) = cli.compliance.filter(|_| checked) {
+            let mut matrix = ComplianceMatrix::new(profile.into());
+            for ip in &live_hosts {
+                matrix.add_host(
+                    *ip,
+                    annotated.reported_for(*ip),
+                    assessed.get(ip).map(Vec::as_slice).unwrap_or_default(),
+                );
+            }
+            matrix.print_matrix();
+            let file = cli.output_file("netscan_compliance.csv");
+            report_written(
+                Msg::ComplianceMatrix,
+                &file,
+                reports::write_compliance_csv(&file, &matrix),
+            );
+        }
+    }
+
+    let retried = run.report.hosts.iter().any(|host| {
+        host.ping_attempts.is_some()
+            || [&host.tcp, &host.udp]
+                .into_iter()
+                .flatten()
+                .any(|ports| !ports.retried.is_empty())
+    });
+    if retried {
+        let file = cli.output_file("netscan_retries.csv");
+        report_written(
+            Msg::RetriedProbes,
+            &file,
+            reports::write_retries_csv(&file, &run.report),
         );
     }
+
+    attach_host_notes(&cli.state_dir, &annotations, &mut run.report);
+
+    if let Some(command) = &cli.on_finding {
+        let started = Instant::now();
+        run_finding_hooks(
+            command,
+            cli.on_finding_concurrency,
+            cli.on_finding_filter.as_ref(),
+            &run.report,
+        )
+        .await;
+        timings.record("Finding hooks", started.elapsed());
+    }
+    timings.print();
+
+    println!(
+        "{}",
+        Msg::TrafficSent.with(&[("traffic", &ratelimit::global().snapshot())]).dimmed()
+    );
+    let storms = warnings::storm_warnings();
+    if !storms.is_empty() {
+        tracing::warn!("{}", Msg::EnvironmentWarnings.text());
+        for warning in storms {
+            tracing::warn!("{}", warning);
+        }
+    }
+
+    Ok(run)
 }
\ No newline at end of file
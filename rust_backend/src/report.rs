@@ -0,0 +1,321 @@
+use crate::scanners::pingsweep::LiveHost;
+use crate::scanners::service_detection::{DetectError, Protocol, ServiceDetectionResult};
+use crate::scanners::tcpscan::TcpScanResult;
+use crate::scanners::udpscan::{UdpPortState, UdpScanResult};
+use crate::utils::compact::{OpenPorts, PortStates};
+use crate::utils::errors::ErrorCategory;
+use crate::utils::fingerprinting::HostFingerprintResult;
+use crate::utils::prettyprint;
+use crate::utils::redact;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+/// Everything one scan learned, per host, across discovery, fingerprinting,
+/// the TCP/UDP scans and service detection
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ScanReport {
+    /// Sorted by address
+    pub hosts: Vec<HostReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HostReport {
+    pub ip: IpAddr,
+    pub hostname: Option<String>,
+    /// Whether the host answered discovery
+    pub alive: bool,
+    /// Echo requests the host needed before it answered the ping sweep,
+    /// when it needed more than one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ping_attempts: Option<usize>,
+    pub fingerprint: Option<FingerprintReport>,
+    pub tcp: Option<PortsReport>,
+    pub udp: Option<PortsReport>,
+    pub services: Vec<ServiceReport>,
+    /// Notes, finding verdicts and acknowledgements the user attached to the
+    /// host, carried over from earlier runs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FingerprintReport {
+    pub os: Option<String>,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub details: Option<String>,
+    /// Candidate operating systems with their confidence (0 to 1), most likely first
+    pub os_guesses: Vec<(String, f64)>,
+}
+
+/// Outcome of a port scan on one host
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PortsReport {
+    pub open: Vec<u16>,
+    /// Every port probed, ascending; empty when unknown (runs recorded
+    /// before it was kept). Serialized as ranges, e.g. "1-1024,3389".
+    #[serde(
+        serialize_with = "serialize_port_ranges",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub scanned: Vec<u16>,
+    /// Failed probes per category, e.g. {"refused": 1020, "timeout": 3}
+    pub errors: BTreeMap<String, usize>,
+    /// UDP only: probed ports per nmap state, serialized as ranges, e.g.
+    /// {"closed": "1-52,54-1024", "open|filtered": "53"}
+    #[serde(
+        serialize_with = "serialize_udp_states",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub states: BTreeMap<UdpPortState, Vec<u16>>,
+    /// Probes that took more than one attempt, with how many they took
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub retried: BTreeMap<u16, usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ServiceReport {
+    pub port: u16,
+    pub service: Option<String>,
+    pub error: Option<String>,
+    /// Detectors that ran without identifying the service
+    pub protocol_failures: Vec<ProtocolFailure>,
+}
+
+/// Serialized as {"protocol": "imap", "kind": "timeout", "message": "..."}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProtocolFailure {
+    pub protocol: Protocol,
+    #[serde(flatten)]
+    pub error: DetectError,
+}
+
+impl HostReport {
+    fn new(ip: IpAddr) -> Self {
+        Self {
+            ip,
+            hostname: None,
+            alive: false,
+            ping_attempts: None,
+            fingerprint: None,
+            tcp: None,
+            udp: None,
+            services: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    fn redacted(&self) -> Self {
+        let text = |value: &Option<String>| value.as_deref().map(redact::text);
+        Self {
+            ip: redact::ip(self.ip),
+            hostname: self.hostname.as_deref().map(redact::hostname),
+            alive: self.alive,
+            ping_attempts: self.ping_attempts,
+            fingerprint: self.fingerprint.as_ref().map(|fp| FingerprintReport {
+                details: text(&fp.details),
+                ..fp.clone()
+            }),
+            tcp: self.tcp.clone(),
+            udp: self.udp.clone(),
+            services: self
+                .services
+                .iter()
+                .map(|service| ServiceReport {
+                    port: service.port,
+                    service: text(&service.service),
+                    error: text(&service.error),
+                    protocol_failures: service
+                        .protocol_failures
+                        .iter()
+                        .map(|failure| ProtocolFailure {
+                            protocol: failure.protocol,
+                            error: match &failure.error {
+                                DetectError::Unsupported => DetectError::Unsupported,
+                                DetectError::Connect(m) => DetectError::Connect(redact::text(m)),
+                                DetectError::Timeout(m) => DetectError::Timeout(redact::text(m)),
+                                DetectError::Mismatch(m) => DetectError::Mismatch(redact::text(m)),
+                            },
+                        })
+                        .collect(),
+                })
+                .collect(),
+            notes: self.notes.iter().map(|note| redact::text(note)).collect(),
+        }
+    }
+}
+
+impl ScanReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The report with addresses, names and free text pseudonymized under
+    /// `--redact`, e.g. to store in the history; a copy when it is off.
+    pub fn redacted(&self) -> ScanReport {
+        if !redact::enabled() {
+            return self.clone();
+        }
+        let mut hosts: Vec<HostReport> = self.hosts.iter().map(HostReport::redacted).collect();
+        hosts.sort_by_key(|host| host.ip);
+        ScanReport { hosts }
+    }
+
+    pub fn host(&self, ip: IpAddr) -> Option<&HostReport> {
+        self.hosts.iter().find(|host| host.ip == ip)
+    }
+
+    fn host_mut(&mut self, ip: IpAddr) -> &mut HostReport {
+        let index = match self.hosts.binary_search_by(|host| host.ip.cmp(&ip)) {
+            Ok(index) => index,
+            Err(index) => {
+                self.hosts.insert(index, HostReport::new(ip));
+                index
+            }
+        };
+        &mut self.hosts[index]
+    }
+
+    /// Marks the hosts that answered discovery as alive.
+    pub fn add_discovery(&mut self, live_hosts: &[LiveHost]) {
+        for live in live_hosts {
+            let host = self.host_mut(live.ip);
+            host.alive = true;
+            if live.hostname.is_some() {
+                host.hostname = live.hostname.clone();
+            }
+        }
+    }
+
+    /// Records the echo requests each host in `retried` needed, as
+    /// `PingSweepResult::get_retried` gives them.
+    pub fn add_ping_attempts(&mut self, retried: &BTreeMap<IpAddr, usize>) {
+        for (ip, attempts) in retried {
+            self.host_mut(*ip).ping_attempts = Some(*attempts);
+        }
+    }
+
+    pub fn add_fingerprint(&mut self, fingerprint: &HostFingerprintResult) {
+        self.host_mut(fingerprint.ip).fingerprint = Some(FingerprintReport {
+            os: fingerprint.os.clone(),
+            vendor: fingerprint.vendor.clone(),
+            model: fingerprint.model.clone(),
+            serial: fingerprint.serial.clone(),
+            details: fingerprint.details.clone(),
+            os_guesses: fingerprint
+                .os_guesses
+                .iter()
+                .map(|guess| (guess.os.clone(), guess.confidence))
+                .collect(),
+        });
+    }
+
+    pub fn add_tcp(&mut self, result: &TcpScanResult) {
+        for (ip, ports) in ports_by_host(
+            result.get_open_ports(),
+            result.get_error_counts(),
+            result.get_port_states(),
+        ) {
+            self.host_mut(ip).tcp = Some(ports);
+        }
+        for ((ip, port), attempts) in result.get_retried() {
+            if let Some(tcp) = self.host_mut(*ip).tcp.as_mut() {
+                tcp.retried.insert(*port, *attempts);
+            }
+        }
+    }
+
+    pub fn add_udp(&mut self, result: &UdpScanResult) {
+        for (ip, ports) in ports_by_host(
+            result.get_open_ports(),
+            result.get_error_counts(),
+            result.get_port_states(),
+        ) {
+            self.host_mut(ip).udp = Some(ports);
+        }
+        for (ip, states) in result.get_port_states() {
+            let Some(udp) = self.host_mut(*ip).udp.as_mut() else {
+                continue;
+            };
+            for (port, state) in states.iter() {
+                udp.states
+                    .entry(UdpPortState::from(state))
+                    .or_default()
+                    .push(port);
+            }
+        }
+        for ((ip, port), attempts) in result.get_retried() {
+            if let Some(udp) = self.host_mut(*ip).udp.as_mut() {
+                udp.retried.insert(*port, *attempts);
+            }
+        }
+    }
+
+    /// Adds service detection results for `ip`, replacing earlier ones for
+    /// the same ports.
+    pub fn add_services(&mut self, ip: IpAddr, results: &[ServiceDetectionResult]) {
+        let services = &mut self.host_mut(ip).services;
+        for result in results {
+            services.retain(|service| service.port != result.port);
+            services.push(ServiceReport {
+                port: result.port,
+                service: result.service.clone(),
+                error: result.error.clone(),
+                protocol_failures: result
+                    .protocol_failures
+                    .iter()
+                    .map(|(protocol, error)| ProtocolFailure {
+                        protocol: *protocol,
+                        error: error.clone(),
+                    })
+                    .collect(),
+            });
+        }
+        services.sort_by_key(|service| service.port);
+    }
+
+    /// Attaches what the user recorded about the host at `ip`, if the report has it.
+    pub fn add_notes(&mut self, ip: IpAddr, notes: Vec<String>) {
+        if let Some(host) = self.hosts.iter_mut().find(|host| host.ip == ip) {
+            host.notes = notes;
+        }
+    }
+}
+
+fn ports_by_host(
+    open: OpenPorts<'_>,
+    error_counts: &BTreeMap<IpAddr, BTreeMap<ErrorCategory, usize>>,
+    states: &BTreeMap<IpAddr, PortStates>,
+) -> BTreeMap<IpAddr, PortsReport> {
+    let mut by_host: BTreeMap<IpAddr, PortsReport> = BTreeMap::new();
+    for (ip, port) in open.iter() {
+        by_host.entry(ip).or_default().open.push(port);
+    }
+    for (ip, states) in states {
+        by_host.entry(*ip).or_default().scanned = states.iter().map(|(port, _)| port).collect();
+    }
+    for (ip, counts) in error_counts {
+        let errors = &mut by_host.entry(*ip).or_default().errors;
+        for (category, count) in counts.iter().filter(|(_, count)| **count > 0) {
+            errors.insert(category.to_string(), *count);
+        }
+    }
+    by_host
+}
+
+fn serialize_port_ranges<S: Serializer>(ports: &[u16], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&prettyprint::format_port_ranges(ports))
+}
+
+fn serialize_udp_states<S: Serializer>(
+    states: &BTreeMap<UdpPortState, Vec<u16>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(
+        states
+            .iter()
+            .map(|(state, ports)| (state.label(), prettyprint::format_port_ranges(ports))),
+    )
+}
@@ -0,0 +1,372 @@
+use crate::scanners::arpsweep::{build_arp_request, parse_arp_reply};
+use crate::utils::{routing, vlan};
+use once_cell::sync::Lazy;
+use pnet::datalink::{self, Channel, DataLinkReceiver, DataLinkSender, MacAddr, NetworkInterface};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// How long an answer is reused before the host is asked again
+pub const CACHE_TTL: Duration = Duration::from_secs(60);
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2); // How long to wait for replies after asking
+const REPLY_BACKLOG: usize = 1024; // Replies buffered per interface for slow waiters
+
+/// Classic BPF program (code, jt, jf, k) passing only ARP frames, untagged or
+/// 802.1Q-tagged, so the receive thread never wakes for other traffic
+pub const ARP_FILTER: [(u16, u8, u8, u32); 7] = [
+    (0x28, 0, 0, 12),     // ldh [12]            ethertype
+    (0x15, 3, 0, 0x0806), // jeq #ARP            -> accept
+    (0x15, 0, 3, 0x8100), // jeq #802.1Q         else drop
+    (0x28, 0, 0, 16),     // ldh [16]            inner ethertype
+    (0x15, 0, 1, 0x0806), // jeq #ARP            else drop
+    (0x06, 0, 0, 0xffff), // accept: ret #65535
+    (0x06, 0, 0, 0),      // drop: ret #0
+];
+
+/// Where ARP requests go out and who they claim to come from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArpSegment {
+    pub interface: String,
+    pub src_mac: MacAddr,
+    pub src_ip: Ipv4Addr,
+    pub vlan_id: Option<u16>,
+}
+
+impl ArpSegment {
+    /// The segment behind `interface`, or behind the interface routing to
+    /// `target` when none is named. `source_ip` overrides the interface address.
+    pub fn new(
+        interface: Option<&str>,
+        target: Ipv4Addr,
+        source_ip: Option<Ipv4Addr>,
+        vlan_id: Option<u16>,
+    ) -> Result<Self, String> {
+        let name = match interface {
+            Some(name) => name.to_string(),
+            None => routing::select_source(target, None)?.interface,
+        };
+        let iface = datalink::interfaces()
+            .into_iter()
+            .find(|iface| iface.name == name)
+            .ok_or_else(|| format!("Interface {name} not found"))?;
+        let src_mac = arp_capable(&iface)?;
+        let src_ip = source_ip
+            .or_else(|| {
+                iface.ips.iter().find_map(|net| match net.ip() {
+                    IpAddr::V4(ip) => Some(ip),
+                    IpAddr::V6(_) => None,
+                })
+            })
+            .ok_or_else(|| format!("Interface {} has no IPv4 address", iface.name))?;
+        Ok(Self {
+            interface: iface.name,
+            src_mac,
+            src_ip,
+            vlan_id,
+        })
+    }
+}
+
+/// MAC address `iface` sends ARP from, or why it cannot: loopback, VPN and
+/// other point-to-point links have no Ethernet segment to ask.
+pub fn arp_capable(iface: &NetworkInterface) -> Result<MacAddr, String> {
+    if iface.is_loopback() {
+        return Err(format!(
+            "Interface {} is a loopback interface; ARP needs an Ethernet segment",
+            iface.name
+        ));
+    }
+    if !iface.is_up() {
+        return Err(format!("Interface {} is down", iface.name));
+    }
+    match iface.mac.filter(|mac| *mac != MacAddr::zero()) {
+        Some(mac) if !iface.is_point_to_point() => Ok(mac),
+        _ => Err(format!(
+            "Interface {} has no MAC address (point-to-point or VPN link); ARP needs an Ethernet segment",
+            iface.name
+        )),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    mac: MacAddr,
+    interface: String,
+    vlan_id: Option<u16>,
+    seen: Instant,
+}
+
+/// ARP answers by IPv4 address, shared by discovery and fingerprinting
+#[derive(Debug, Clone, Default)]
+pub struct ArpCache {
+    entries: HashMap<Ipv4Addr, CacheEntry>,
+}
+
+impl ArpCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, ip: Ipv4Addr, mac: MacAddr, segment: &ArpSegment, now: Instant) {
+        self.entries.insert(
+            ip,
+            CacheEntry {
+                mac,
+                interface: segment.interface.clone(),
+                vlan_id: segment.vlan_id,
+                seen: now,
+            },
+        );
+    }
+
+    /// MAC of `ip` if it answered within `CACHE_TTL` on any segment.
+    pub fn get(&self, ip: Ipv4Addr, now: Instant) -> Option<MacAddr> {
+        self.entries
+            .get(&ip)
+            .filter(|entry| now.duration_since(entry.seen) < CACHE_TTL)
+            .map(|entry| entry.mac)
+    }
+
+    /// MAC of `ip` if it answered within `CACHE_TTL` on `segment`'s interface and VLAN.
+    pub fn get_on(&self, ip: Ipv4Addr, segment: &ArpSegment, now: Instant) -> Option<MacAddr> {
+        self.entries
+            .get(&ip)
+            .filter(|entry| {
+                entry.interface == segment.interface && entry.vlan_id == segment.vlan_id
+            })
+            .and_then(|_| self.get(ip, now))
+    }
+
+    /// Drops entries older than `CACHE_TTL`.
+    pub fn expire(&mut self, now: Instant) {
+        self.entries
+            .retain(|_, entry| now.duration_since(entry.seen) < CACHE_TTL);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+static CACHE: Lazy<Mutex<ArpCache>> = Lazy::new(|| Mutex::new(ArpCache::new()));
+
+/// Answered (IP, MAC) pairs of one lookup
+type Answers = Result<Vec<(Ipv4Addr, MacAddr)>, String>;
+
+/// An ARP reply seen on an interface: sender IP, sender MAC and 802.1Q tag
+type Reply = (Ipv4Addr, MacAddr, Option<u16>);
+
+/// The datalink channel of one interface. A dedicated thread drains its
+/// receive side and hands ARP replies to the waiting lookups, so any number
+/// of lookups can be in flight without blocking the runtime.
+struct Link {
+    tx: Mutex<Box<dyn DataLinkSender>>,
+    replies: broadcast::Sender<Reply>,
+    /// When each address (and VLAN) was last asked, so overlapping lookups share a request
+    requested: Mutex<HashMap<(Ipv4Addr, Option<u16>), Instant>>,
+    alive: Arc<AtomicBool>,
+}
+
+impl Link {
+    /// Sends a request for each of `ips` not already asked within `REPLY_TIMEOUT`.
+    fn request(&self, segment: &ArpSegment, ips: &HashSet<Ipv4Addr>) -> Result<(), String> {
+        let now = Instant::now();
+        let due: Vec<Ipv4Addr> = {
+            let mut requested = self.requested.lock().unwrap();
+            requested.retain(|_, asked| now.duration_since(*asked) < REPLY_TIMEOUT);
+            ips.iter()
+                .copied()
+                .filter(|&ip| requested.insert((ip, segment.vlan_id), now).is_none())
+                .collect()
+        };
+        let mut tx = self.tx.lock().unwrap();
+        for ip in due {
+            let frame = build_arp_request(segment.src_mac, segment.src_ip, ip, segment.vlan_id)?;
+            if let Some(Err(e)) = tx.send_to(&frame, None) {
+                return Err(format!("Failed to send ARP request to {}: {}", ip, e));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Open channels by interface name; they stay open for the life of the process.
+static LINKS: Lazy<Mutex<HashMap<String, Arc<Link>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// MAC of `ip` if it answered recently, without sending anything.
+pub fn cached(ip: Ipv4Addr) -> Option<MacAddr> {
+    CACHE.lock().unwrap().get(ip, Instant::now())
+}
+
+/// Resolves `ips` on `segment` and returns the ones that answered (requires
+/// root). Hosts answered within `CACHE_TTL` are not asked again, and an
+/// address another lookup has just asked for is not asked twice.
+pub async fn resolve(segment: &ArpSegment, ips: Vec<Ipv4Addr>) -> Answers {
+    let mut pending: HashSet<Ipv4Addr> = {
+        let mut cache = CACHE.lock().unwrap();
+        let now = Instant::now();
+        cache.expire(now);
+        ips.iter()
+            .copied()
+            .filter(|&ip| cache.get_on(ip, segment, now).is_none())
+            .collect()
+    };
+    if !pending.is_empty() {
+        let link = link(&segment.interface)?;
+        // Subscribe before asking so no reply slips past
+        let mut replies = link.replies.subscribe();
+        link.request(segment, &pending)?;
+        let deadline = tokio::time::Instant::now() + REPLY_TIMEOUT;
+        while !pending.is_empty() {
+            let (ip, mac, frame_vlan) =
+                match tokio::time::timeout_at(deadline, replies.recv()).await {
+                    Ok(Ok(reply)) => reply,
+                    Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                    Ok(Err(broadcast::error::RecvError::Closed)) => {
+                        return Err(format!("ARP receiver on {} stopped", segment.interface));
+                    }
+                    Err(_) => break, // the rest did not answer
+                };
+            // On a trunk, only accept replies from the VLAN that was probed
+            if !vlan::accepts(segment.vlan_id, frame_vlan) {
+                continue;
+            }
+            // Replies to other lookups are still worth keeping
+            CACHE
+                .lock()
+                .unwrap()
+                .insert(ip, mac, segment, Instant::now());
+            pending.remove(&ip);
+        }
+    }
+
+    let cache = CACHE.lock().unwrap();
+    let now = Instant::now();
+    Ok(ips
+        .iter()
+        .filter_map(|&ip| Some((ip, cache.get_on(ip, segment, now)?)))
+        .collect())
+}
+
+/// The open channel of `interface`, opening it (and its receive thread) on first use.
+fn link(interface: &str) -> Result<Arc<Link>, String> {
+    let mut links = LINKS.lock().unwrap();
+    if let Some(link) = links
+        .get(interface)
+        .filter(|link| link.alive.load(Ordering::Relaxed))
+    {
+        return Ok(link.clone());
+    }
+    let link = Arc::new(open_link(interface)?);
+    links.insert(interface.to_string(), link.clone());
+    Ok(link)
+}
+
+fn open_link(name: &str) -> Result<Link, String> {
+    let iface = datalink::interfaces()
+        .into_iter()
+        .find(|iface| iface.name == name)
+        .ok_or_else(|| format!("Interface {name} not found"))?;
+    let config = datalink::Config {
+        socket_fd: arp_socket()?,
+        promiscuous: false, // replies are addressed to us
+        ..Default::default()
+    };
+    let (tx, rx) = match datalink::channel(&iface, config) {
+        Ok(Channel::Ethernet(tx, rx)) => (tx, rx),
+        Ok(_) => return Err(format!("Unsupported channel type on {}", iface.name)),
+        Err(e) => {
+            return Err(format!(
+                "Failed to open datalink channel on {}: {}",
+                iface.name, e
+            ));
+        }
+    };
+    let (replies, _) = broadcast::channel(REPLY_BACKLOG);
+    let alive = Arc::new(AtomicBool::new(true));
+    std::thread::Builder::new()
+        .name(format!("arp-rx-{}", iface.name))
+        .spawn({
+            let replies = replies.clone();
+            let alive = alive.clone();
+            move || receive(rx, replies, alive)
+        })
+        .map_err(|e| format!("Failed to start the ARP receiver on {}: {}", iface.name, e))?;
+    Ok(Link {
+        tx: Mutex::new(tx),
+        replies,
+        requested: Mutex::new(HashMap::new()),
+        alive,
+    })
+}
+
+/// Passes on every ARP reply read from `rx` until the channel fails.
+fn receive(
+    mut rx: Box<dyn DataLinkReceiver>,
+    replies: broadcast::Sender<Reply>,
+    alive: Arc<AtomicBool>,
+) {
+    loop {
+        match rx.next() {
+            Ok(frame) => {
+                if let Some(reply) = parse_arp_reply(frame) {
+                    let _ = replies.send(reply); // nobody may be waiting
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        }
+    }
+    alive.store(false, Ordering::Relaxed); // the next lookup reopens the channel
+}
+
+/// A raw socket with `ARP_FILTER` attached, for pnet to build the channel on.
+#[cfg(target_os = "linux")]
+fn arp_socket() -> Result<Option<i32>, String> {
+    let protocol = (libc::ETH_P_ALL as u16).to_be() as i32;
+    // SAFETY: plain socket(2)/setsockopt(2) calls; `program` outlives the setsockopt
+    let fd = unsafe { libc::socket(libc::AF_PACKET, libc::SOCK_RAW, protocol) };
+    if fd == -1 {
+        return Err(format!(
+            "Failed to open raw socket (ARP needs root): {}",
+            io::Error::last_os_error()
+        ));
+    }
+    let mut program: Vec<libc::sock_filter> = ARP_FILTER
+        .iter()
+        .map(|&(code, jt, jf, k)| libc::sock_filter { code, jt, jf, k })
+        .collect();
+    let fprog = libc::sock_fprog {
+        len: program.len() as u16,
+        filter: program.as_mut_ptr(),
+    };
+    let attached = unsafe {
+        libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_ATTACH_FILTER,
+            &fprog as *const libc::sock_fprog as *const libc::c_void,
+            std::mem::size_of::<libc::sock_fprog>() as libc::socklen_t,
+        )
+    };
+    if attached == -1 {
+        let e = io::Error::last_os_error();
+        unsafe { libc::close(fd) };
+        return Err(format!("Failed to attach the ARP packet filter: {}", e));
+    }
+    Ok(Some(fd))
+}
+
+/// Other platforms read every frame and filter in `parse_arp_reply`.
+#[cfg(not(target_os = "linux"))]
+fn arp_socket() -> Result<Option<i32>, String> {
+    Ok(None)
+}
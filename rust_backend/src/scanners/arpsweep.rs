@@ -0,0 +1,131 @@
+use crate::scanners::arpcache::{self, ArpSegment};
+use crate::scanners::pingsweep::parse_subnet;
+use crate::utils::vlan;
+use pnet::datalink::MacAddr;
+use pnet::packet::arp::{ArpHardwareTypes, ArpOperations, ArpPacket, MutableArpPacket};
+use pnet::packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
+use pnet::packet::{MutablePacket, Packet};
+use std::net::Ipv4Addr;
+
+const ARP_FRAME_SIZE: usize = 42; // Ethernet (14) + ARP (28)
+
+/// Struct to store the results of the ARP sweep
+#[derive(Debug)]
+pub struct ArpSweepResult {
+    live_hosts: Vec<(Ipv4Addr, MacAddr)>,
+    interface: String,
+    vlan_id: Option<u16>,
+}
+
+impl ArpSweepResult {
+    pub fn new(interface: String, vlan_id: Option<u16>) -> Self {
+        Self {
+            live_hosts: Vec::new(),
+            interface,
+            vlan_id,
+        }
+    }
+
+    pub fn add_live_host(&mut self, ip: Ipv4Addr, mac: MacAddr) {
+        if !self.live_hosts.iter().any(|(known, _)| *known == ip) {
+            self.live_hosts.push((ip, mac));
+        }
+    }
+
+    pub fn get_live_hosts(&self) -> &Vec<(Ipv4Addr, MacAddr)> {
+        &self.live_hosts
+    }
+
+    pub fn get_interface(&self) -> &str {
+        &self.interface
+    }
+
+    pub fn get_vlan_id(&self) -> Option<u16> {
+        self.vlan_id
+    }
+
+    pub fn print_summary(&self) {
+        println!("ARP sweep completed on {}.", self.interface);
+        if let Some(id) = self.vlan_id {
+            println!("VLAN: {}", id);
+        }
+        println!("Total live hosts: {}", self.live_hosts.len());
+    }
+}
+
+/// Builds a broadcast ARP who-has request, 802.1Q-tagged when `vlan_id` is set.
+pub fn build_arp_request(
+    src_mac: MacAddr,
+    src_ip: Ipv4Addr,
+    target_ip: Ipv4Addr,
+    vlan_id: Option<u16>,
+) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![0u8; ARP_FRAME_SIZE];
+    {
+        let mut ethernet =
+            MutableEthernetPacket::new(&mut buffer).ok_or("Failed to create Ethernet packet")?;
+        ethernet.set_destination(MacAddr::broadcast());
+        ethernet.set_source(src_mac);
+        ethernet.set_ethertype(EtherTypes::Arp);
+
+        let mut arp = MutableArpPacket::new(ethernet.payload_mut())
+            .ok_or("Failed to create ARP packet")?;
+        arp.set_hardware_type(ArpHardwareTypes::Ethernet);
+        arp.set_protocol_type(EtherTypes::Ipv4);
+        arp.set_hw_addr_len(6);
+        arp.set_proto_addr_len(4);
+        arp.set_operation(ArpOperations::Request);
+        arp.set_sender_hw_addr(src_mac);
+        arp.set_sender_proto_addr(src_ip);
+        arp.set_target_hw_addr(MacAddr::zero());
+        arp.set_target_proto_addr(target_ip);
+    }
+    vlan::encapsulate(buffer, vlan_id)
+}
+
+/// Parses an ARP reply frame (tagged or untagged) into (sender IP, sender MAC, VLAN ID).
+pub fn parse_arp_reply(frame: &[u8]) -> Option<(Ipv4Addr, MacAddr, Option<u16>)> {
+    let vlan_id = vlan::frame_vlan_id(frame);
+    let untagged = vlan::untag_frame(frame);
+    let ethernet = EthernetPacket::new(&untagged)?;
+    if ethernet.get_ethertype() != EtherTypes::Arp {
+        return None;
+    }
+    let arp = ArpPacket::new(ethernet.payload())?;
+    if arp.get_operation() != ArpOperations::Reply {
+        return None;
+    }
+    Some((arp.get_sender_proto_addr(), arp.get_sender_hw_addr(), vlan_id))
+}
+
+/// Function to perform an ARP sweep of a subnet on the local segment (requires root).
+/// With `vlan_id` set, requests are 802.1Q-tagged so a trunk port can reach that VLAN.
+/// Requests go through the shared resolver, so hosts it answered recently are not asked again.
+pub async fn arp_sweep(
+    subnet: &str,
+    interface: Option<&str>,
+    vlan_id: Option<u16>,
+    source_ip: Option<Ipv4Addr>,
+) -> Result<ArpSweepResult, String> {
+    arp_sweep_hosts(parse_subnet(subnet)?, interface, vlan_id, source_ip).await
+}
+
+/// `arp_sweep` over an explicit host list, e.g. a subnet with excluded hosts removed.
+pub async fn arp_sweep_hosts(
+    ips: Vec<Ipv4Addr>,
+    interface: Option<&str>,
+    vlan_id: Option<u16>,
+    source_ip: Option<Ipv4Addr>,
+) -> Result<ArpSweepResult, String> {
+    if let Some(id) = vlan_id {
+        vlan::validate_vlan_id(id)?;
+    }
+    let first_ip = *ips.first().ok_or("Empty subnet")?;
+    let segment = ArpSegment::new(interface, first_ip, source_ip, vlan_id)?;
+
+    let mut result = ArpSweepResult::new(segment.interface.clone(), vlan_id);
+    for (ip, mac) in arpcache::resolve(&segment, ips).await? {
+        result.add_live_host(ip, mac);
+    }
+    Ok(result)
+}
@@ -0,0 +1,287 @@
+use crate::detect_kerberos::{self, KerberosDetection};
+use crate::detect_ldap::{self, LdapDetection};
+use crate::detect_ntp::{self, NtpDetection};
+use crate::detect_smb::{self, SmbDetection};
+use crate::utils::findings::{self, Finding, Severity, code};
+use crate::utils::prettyprint;
+use colored::*;
+use std::net::IpAddr;
+
+/// Ports the dc-audit profile scans when no --ports are given
+pub const DC_AUDIT_PORTS: &[u16] = &[53, 88, 135, 139, 389, 445, 464, 636, 3268, 3269];
+/// Kerberos rejects tickets when clocks differ by more than this (default policy)
+pub const MAX_KERBEROS_SKEW_SECS: f64 = 300.0;
+/// Realm used for the Kerberos probe when LDAP did not reveal the real one
+const FALLBACK_REALM: &str = "NETSCAN.INVALID";
+
+/// Consolidated exposure summary for a likely domain controller
+#[derive(Debug, Clone)]
+pub struct DcAuditReport {
+    pub ip: IpAddr,
+    pub open_ports: Vec<u16>,
+    pub ldap: Option<LdapDetection>,
+    pub kerberos: Option<KerberosDetection>,
+    pub smb: Option<SmbDetection>,
+    pub ntp: Option<NtpDetection>,
+    /// Seconds of local clock time at the moment of the probes
+    pub local_time: i64,
+    pub findings: Vec<Finding>,
+}
+
+fn functional_level_name(level: i64) -> &'static str {
+    match level {
+        0 => "Windows 2000",
+        1 => "Windows Server 2003 interim",
+        2 => "Windows Server 2003",
+        3 => "Windows Server 2008",
+        4 => "Windows Server 2008 R2",
+        5 => "Windows Server 2012",
+        6 => "Windows Server 2012 R2",
+        7 => "Windows Server 2016",
+        10 => "Windows Server 2025",
+        _ => "unknown",
+    }
+}
+
+impl DcAuditReport {
+    pub fn new(ip: IpAddr, open_ports: &[u16]) -> Self {
+        Self {
+            ip,
+            open_ports: open_ports.to_vec(),
+            ldap: None,
+            kerberos: None,
+            smb: None,
+            ntp: None,
+            local_time: chrono::Utc::now().timestamp(),
+            findings: Vec::new(),
+        }
+    }
+
+    /// Kerberos realm as learned from LDAP or the KDC itself.
+    pub fn realm(&self) -> Option<String> {
+        self.ldap
+            .as_ref()
+            .and_then(|ldap| ldap.realm())
+            .or_else(|| self.kerberos.as_ref().and_then(|krb| krb.realm.clone()))
+    }
+
+    /// Best available estimate of the DC clock offset from ours, in seconds.
+    pub fn clock_skew_secs(&self) -> Option<f64> {
+        if let Some(offset) = self.ntp.as_ref().and_then(|ntp| ntp.offset_secs) {
+            return Some(offset);
+        }
+        let remote = self
+            .kerberos
+            .as_ref()
+            .and_then(|krb| krb.server_time.map(|t| t.timestamp()))
+            .or_else(|| self.smb.as_ref().and_then(|smb| smb.server_time))?;
+        Some((remote - self.local_time) as f64)
+    }
+
+    /// Derives findings from the probe results gathered so far.
+    pub fn assess(&mut self) {
+        let mut found = Vec::new();
+
+        if let Some(smb) = self.smb.as_ref().filter(|smb| smb.detected) {
+            if smb.smb1_accepted {
+                found.push(
+                    Finding::new(
+                        Severity::High,
+                        Some(445),
+                        "SMB1 accepted",
+                        "The legacy SMB1 dialect is still enabled; disable it to remove \
+                     EternalBlue-class exposure"
+                            .to_string(),
+                    )
+                    .with_code(code::SMB1_ACCEPTED),
+                );
+            }
+            found.push(smb_signing_finding(smb));
+        }
+
+        if let Some(ldap) = self.ldap.as_ref().filter(|ldap| ldap.detected) {
+            if !ldap.root_dse.is_empty() {
+                found.push(
+                    Finding::new(
+                        Severity::Info,
+                        Some(389),
+                        "Anonymous rootDSE read",
+                        format!(
+                            "{} ({})",
+                            ldap.attribute("dnsHostName").unwrap_or("unknown host"),
+                            ldap.attribute("defaultNamingContext")
+                                .unwrap_or("no naming context")
+                        ),
+                    )
+                    .with_code(code::ANONYMOUS_ROOT_DSE),
+                );
+            }
+            if let Some(level) = ldap
+                .attribute("domainFunctionality")
+                .and_then(|l| l.parse::<i64>().ok())
+                && level < 6
+            {
+                found.push(Finding::new(
+                    Severity::Medium,
+                    Some(389),
+                    "Outdated domain functional level",
+                    format!(
+                        "{} ({}); newer Kerberos and authentication hardening is unavailable",
+                        functional_level_name(level),
+                        level
+                    ),
+                ));
+            }
+            if !self.open_ports.contains(&636) {
+                found.push(Finding::new(
+                    Severity::Medium,
+                    Some(389),
+                    "No LDAPS listener",
+                    "Only cleartext LDAP is offered; binds are exposed unless LDAP signing \
+                     and channel binding are enforced"
+                        .to_string(),
+                ));
+            }
+        }
+
+        if let Some(krb) = self.kerberos.as_ref().filter(|krb| krb.detected) {
+            if krb.as_rep_without_preauth {
+                found.push(Finding::new(
+                    Severity::High,
+                    Some(88),
+                    "AS-REP issued without pre-authentication",
+                    "The KDC returned a ticket for an unauthenticated request (AS-REP roasting)"
+                        .to_string(),
+                )
+                .with_code(code::AS_REP_WITHOUT_PREAUTH));
+            } else {
+                found.push(Finding::new(
+                    Severity::Info,
+                    Some(88),
+                    "Kerberos KDC",
+                    format!(
+                        "Realm {}, answered {}",
+                        krb.realm.as_deref().unwrap_or("unknown"),
+                        krb.error_code
+                            .map(detect_kerberos::error_name)
+                            .unwrap_or("without an error code")
+                    ),
+                ));
+            }
+        }
+
+        match &self.ntp {
+            Some(ntp) if !ntp.detected => found.push(Finding::new(
+                Severity::Low,
+                Some(123),
+                "Time service not answering",
+                "Domain members normally sync time from the DC over NTP (123/udp)".to_string(),
+            )),
+            _ => {}
+        }
+        if let Some(skew) = self.clock_skew_secs()
+            && skew.abs() > MAX_KERBEROS_SKEW_SECS
+        {
+            found.push(Finding::new(
+                Severity::Medium,
+                None,
+                "Clock skew exceeds Kerberos tolerance",
+                format!(
+                    "DC clock differs from ours by {:.0}s (limit {:.0}s)",
+                    skew, MAX_KERBEROS_SKEW_SECS
+                ),
+            ));
+        }
+
+        findings::sort_by_severity(&mut found);
+        self.findings = found;
+    }
+
+    pub fn print_summary(&self) {
+        println!(
+            "\n{}",
+            prettyprint::console(&format!(
+                "🏛️  Domain controller exposure summary for {}",
+                self.ip
+            ))
+            .bold()
+            .underline()
+            .blue()
+        );
+        println!(
+            "  {}: {}",
+            "Realm".bold(),
+            self.realm().unwrap_or_else(|| "Unknown".to_string())
+        );
+        if let Some(host) = self.ldap.as_ref().and_then(|l| l.attribute("dnsHostName")) {
+            println!("  {}: {}", "DNS host name".bold(), host);
+        }
+        if let Some(smb) = self.smb.as_ref().filter(|smb| smb.detected) {
+            println!("  {}: {}", "SMB".bold(), smb.summary());
+        }
+        if let Some(skew) = self.clock_skew_secs() {
+            println!("  {}: {:+.1}s", "Clock offset".bold(), skew);
+        }
+        for finding in &self.findings {
+            println!(
+                "  [{}] {}: {}",
+                finding.severity.colored(),
+                finding.title.bold(),
+                finding.detail
+            );
+        }
+        println!("{}", "-".repeat(70).dimmed());
+    }
+}
+
+/// Whether SMB signing is enforced on a host that answered the SMB probe.
+pub fn smb_signing_finding(smb: &SmbDetection) -> Finding {
+    if smb.signing_required {
+        Finding::new(
+            Severity::Info,
+            Some(445),
+            "SMB signing required",
+            format!("Dialect {}", smb.dialect.as_deref().unwrap_or("unknown")),
+        )
+    } else {
+        Finding::new(
+            Severity::High,
+            Some(445),
+            "SMB signing not required",
+            "Unsigned SMB sessions allow NTLM relay to this host; enforce \
+             'Digitally sign communications (always)'"
+                .to_string(),
+        )
+        .with_code(code::SMB_SIGNING_NOT_REQUIRED)
+    }
+}
+
+/// Probes only SMB signing, for hosts that are not deep-probed as DCs;
+/// `None` when nothing answered SMB on 445.
+pub async fn audit_smb_signing(ip: IpAddr) -> Option<Finding> {
+    let smb = detect_smb::detect(ip, 445).await;
+    smb.detected.then(|| smb_signing_finding(&smb))
+}
+
+/// Runs the DC deep probes (LDAP rootDSE, Kerberos realm, SMB signing, time
+/// service) against `ip` and assesses the results.
+pub async fn audit_domain_controller(ip: IpAddr, open_ports: &[u16]) -> DcAuditReport {
+    let mut report = DcAuditReport::new(ip, open_ports);
+
+    let ldap_port = if open_ports.contains(&389) { 389 } else { 3268 };
+    let (ldap, smb, ntp) = tokio::join!(
+        detect_ldap::detect(ip, ldap_port),
+        detect_smb::detect(ip, 445),
+        detect_ntp::detect(ip, 123)
+    );
+    report.ldap = Some(ldap);
+    report.smb = Some(smb);
+    report.ntp = Some(ntp);
+
+    // The AS-REQ needs a realm, which the rootDSE usually gives away
+    let realm = report.realm().unwrap_or_else(|| FALLBACK_REALM.to_string());
+    report.kerberos = Some(detect_kerberos::detect(ip, 88, &realm).await);
+
+    report.assess();
+    report
+}
@@ -0,0 +1,170 @@
+use crate::utils::errors::ErrorCategory;
+use pnet::packet::Packet;
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::transport::{
+    TransportChannelType, TransportProtocol, icmp_packet_iter, icmpv6_packet_iter,
+    transport_channel,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How often the listener threads check whether they should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+const ICMP_DEST_UNREACHABLE: u8 = 3;
+const ICMP_PORT_UNREACHABLE: u8 = 3;
+const ICMPV6_DEST_UNREACHABLE: u8 = 1;
+const ICMPV6_PORT_UNREACHABLE: u8 = 4;
+const UDP: u8 = 17;
+
+/// UDP ports an ICMP error was received for, by target address and port
+pub type UnreachablePorts = HashMap<(IpAddr, u16), ErrorCategory>;
+
+/// What an ICMP destination-unreachable code says about a UDP port, per
+/// nmap: port unreachable means closed (`Refused`), the other codes that a
+/// filter sends mean filtered (`Unreachable`).
+fn category_v4(code: u8) -> Option<ErrorCategory> {
+    match code {
+        ICMP_PORT_UNREACHABLE => Some(ErrorCategory::Refused),
+        0 | 1 | 2 | 9 | 10 | 13 => Some(ErrorCategory::Unreachable),
+        _ => None,
+    }
+}
+
+fn category_v6(code: u8) -> Option<ErrorCategory> {
+    match code {
+        ICMPV6_PORT_UNREACHABLE => Some(ErrorCategory::Refused),
+        0 | 1 | 3 | 5 | 6 => Some(ErrorCategory::Unreachable),
+        _ => None,
+    }
+}
+
+/// Reads an ICMPv4 message: for a destination unreachable quoting a UDP
+/// datagram, the datagram's destination address and port and what the code means.
+pub fn parse_icmpv4(message: &[u8]) -> Option<(IpAddr, u16, ErrorCategory)> {
+    if *message.first()? != ICMP_DEST_UNREACHABLE {
+        return None;
+    }
+    let category = category_v4(*message.get(1)?)?;
+    let quoted = message.get(8..)?;
+    let header_len = usize::from(quoted.first()? & 0x0f) * 4;
+    if header_len < 20 || *quoted.get(9)? != UDP {
+        return None;
+    }
+    let dst: [u8; 4] = quoted.get(16..20)?.try_into().ok()?;
+    let udp = quoted.get(header_len..header_len + 4)?;
+    let port = u16::from_be_bytes([udp[2], udp[3]]);
+    Some((IpAddr::V4(Ipv4Addr::from(dst)), port, category))
+}
+
+/// `parse_icmpv4` for ICMPv6; extension headers in the quoted packet are not followed.
+pub fn parse_icmpv6(message: &[u8]) -> Option<(IpAddr, u16, ErrorCategory)> {
+    if *message.first()? != ICMPV6_DEST_UNREACHABLE {
+        return None;
+    }
+    let category = category_v6(*message.get(1)?)?;
+    let quoted = message.get(8..)?;
+    if *quoted.get(6)? != UDP {
+        return None;
+    }
+    let dst: [u8; 16] = quoted.get(24..40)?.try_into().ok()?;
+    let udp = quoted.get(40..44)?;
+    let port = u16::from_be_bytes([udp[2], udp[3]]);
+    Some((IpAddr::V6(Ipv6Addr::from(dst)), port, category))
+}
+
+/// Collects ICMP and ICMPv6 destination-unreachable messages on raw sockets
+/// (requires root) while a UDP scan runs.
+pub struct UnreachableListener {
+    stop: Arc<AtomicBool>,
+    seen: Arc<Mutex<UnreachablePorts>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl UnreachableListener {
+    /// Opens the raw sockets; fails without the privileges to do so.
+    pub fn start() -> Result<Self, String> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let seen = Arc::new(Mutex::new(UnreachablePorts::new()));
+        let mut threads = Vec::new();
+        for ipv6 in [false, true] {
+            let (ready_tx, ready_rx) = mpsc::channel();
+            let stop = stop.clone();
+            let seen = seen.clone();
+            threads.push(std::thread::spawn(move || {
+                listen(ipv6, &stop, &seen, ready_tx)
+            }));
+            let ready = ready_rx.recv().unwrap_or_else(|e| Err(e.to_string()));
+            // IPv4 is required; a host without IPv6 just gets no ICMPv6 listener
+            if let Err(e) = ready
+                && !ipv6
+            {
+                return Err(e);
+            }
+        }
+        Ok(Self {
+            stop,
+            seen,
+            threads,
+        })
+    }
+
+    /// Stops listening and returns what was heard.
+    pub fn stop(self) -> UnreachablePorts {
+        self.stop.store(true, Ordering::Relaxed);
+        for thread in self.threads {
+            let _ = thread.join();
+        }
+        std::mem::take(&mut *self.seen.lock().unwrap())
+    }
+}
+
+fn listen(
+    ipv6: bool,
+    stop: &AtomicBool,
+    seen: &Mutex<UnreachablePorts>,
+    ready: mpsc::Sender<Result<(), String>>,
+) {
+    let protocol = if ipv6 {
+        TransportProtocol::Ipv6(IpNextHeaderProtocols::Icmpv6)
+    } else {
+        TransportProtocol::Ipv4(IpNextHeaderProtocols::Icmp)
+    };
+    let mut rx = match transport_channel(4096, TransportChannelType::Layer4(protocol)) {
+        Ok((_, rx)) => rx,
+        Err(e) => {
+            let _ = ready.send(Err(format!("Failed to listen for ICMP errors: {}", e)));
+            return;
+        }
+    };
+    let _ = ready.send(Ok(()));
+    let record = |unreachable: Option<(IpAddr, u16, ErrorCategory)>| {
+        if let Some((ip, port, category)) = unreachable {
+            tracing::trace!(%ip, port, %category, "ICMP unreachable");
+            seen.lock().unwrap().insert((ip, port), category);
+        }
+    };
+    if ipv6 {
+        let mut iter = icmpv6_packet_iter(&mut rx);
+        while !stop.load(Ordering::Relaxed) {
+            match iter.next_with_timeout(POLL_INTERVAL) {
+                Ok(Some((packet, _))) => record(parse_icmpv6(packet.packet())),
+                Ok(None) => {}
+                Err(_) => break,
+            }
+        }
+    } else {
+        let mut iter = icmp_packet_iter(&mut rx);
+        while !stop.load(Ordering::Relaxed) {
+            match iter.next_with_timeout(POLL_INTERVAL) {
+                Ok(Some((packet, _))) => record(parse_icmpv4(packet.packet())),
+                Ok(None) => {}
+                Err(_) => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::scanners::arpcache::ArpSegment;
+use crate::utils::{routing, vlan};
+use once_cell::sync::Lazy;
+use pnet::datalink::{self, Channel, MacAddr};
+use pnet::packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv4::{self, Ipv4Packet, MutableIpv4Packet};
+use pnet::packet::{MutablePacket, Packet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const ALL_SYSTEMS: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 1);
+/// IPv4 header with the Router Alert option (RFC 2113) queries carry
+const IP_HEADER_LEN: usize = 24;
+const QUERY_LEN: usize = 12;
+const QUERY_FRAME_SIZE: usize = 14 + IP_HEADER_LEN + QUERY_LEN;
+/// Hosts spread their reports over this many tenths of a second
+const MAX_RESPONSE_CODE: u8 = 20;
+/// Reports arriving after the response window are still accepted this long
+const LISTEN_MARGIN: Duration = Duration::from_secs(1);
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+const MEMBERSHIP_QUERY: u8 = 0x11;
+const V1_MEMBERSHIP_REPORT: u8 = 0x12;
+const V2_MEMBERSHIP_REPORT: u8 = 0x16;
+const V3_MEMBERSHIP_REPORT: u8 = 0x22;
+/// IGMPv3 group record types that leave the group when they list no source
+const MODE_IS_INCLUDE: u8 = 1;
+const CHANGE_TO_INCLUDE_MODE: u8 = 3;
+const BLOCK_OLD_SOURCES: u8 = 6;
+
+/// Well-known groups and the protocols that join them
+const KNOWN_GROUPS: &[(Ipv4Addr, &str)] = &[
+    (Ipv4Addr::new(224, 0, 0, 2), "all routers"),
+    (Ipv4Addr::new(224, 0, 0, 5), "OSPF"),
+    (Ipv4Addr::new(224, 0, 0, 6), "OSPF DR"),
+    (Ipv4Addr::new(224, 0, 0, 9), "RIPv2"),
+    (Ipv4Addr::new(224, 0, 0, 13), "PIM"),
+    (Ipv4Addr::new(224, 0, 0, 18), "VRRP"),
+    (Ipv4Addr::new(224, 0, 0, 102), "HSRPv2"),
+    (Ipv4Addr::new(224, 0, 0, 107), "PTP peer delay"),
+    (Ipv4Addr::new(224, 0, 0, 251), "mDNS"),
+    (Ipv4Addr::new(224, 0, 0, 252), "LLMNR"),
+    (Ipv4Addr::new(224, 0, 1, 1), "NTP"),
+    (Ipv4Addr::new(224, 0, 1, 60), "HP device discovery"),
+    (Ipv4Addr::new(224, 0, 1, 129), "PTP"),
+    (Ipv4Addr::new(224, 0, 23, 12), "KNXnet/IP"),
+    (Ipv4Addr::new(239, 255, 255, 250), "SSDP"),
+    (Ipv4Addr::new(239, 255, 255, 253), "SLP"),
+];
+
+/// What a multicast group is used for, if it is a well-known one.
+pub fn group_name(group: Ipv4Addr) -> Option<&'static str> {
+    if let Some((_, name)) = KNOWN_GROUPS.iter().find(|(known, _)| *known == group) {
+        return Some(name);
+    }
+    match group.octets() {
+        // Source-specific multicast: IPTV and other one-to-many streams
+        [232, ..] => Some("source-specific stream"),
+        // Administratively scoped: site-local streaming and cluster heartbeats
+        [239, ..] => Some("site-local"),
+        _ => None,
+    }
+}
+
+/// "224.0.0.251 (mDNS)", or just the address for unknown groups.
+pub fn describe_group(group: Ipv4Addr) -> String {
+    match group_name(group) {
+        Some(name) => format!("{} ({})", group, name),
+        None => group.to_string(),
+    }
+}
+
+/// Multicast groups each host on a segment reported membership of
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupMemberships {
+    hosts: BTreeMap<Ipv4Addr, BTreeSet<Ipv4Addr>>,
+}
+
+impl GroupMemberships {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a report from `host`; reports may list no group at all.
+    pub fn add_report(&mut self, host: Ipv4Addr, groups: Vec<Ipv4Addr>) {
+        if !groups.is_empty() {
+            self.hosts.entry(host).or_default().extend(groups);
+        }
+    }
+
+    /// Groups `host` is a member of, in address order.
+    pub fn groups(&self, host: Ipv4Addr) -> Vec<Ipv4Addr> {
+        self.hosts
+            .get(&host)
+            .map(|groups| groups.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Hosts that reported any membership.
+    pub fn hosts(&self) -> impl Iterator<Item = Ipv4Addr> + '_ {
+        self.hosts.keys().copied()
+    }
+}
+
+/// Builds an IGMPv3 general query to all systems (224.0.0.1). IGMPv1 and v2
+/// hosts take it for a query of their own version (RFC 3376 7.2.1).
+pub fn build_query(
+    src_mac: MacAddr,
+    src_ip: Ipv4Addr,
+    vlan_id: Option<u16>,
+) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![0u8; QUERY_FRAME_SIZE];
+    {
+        let mut ethernet =
+            MutableEthernetPacket::new(&mut buffer).ok_or("Failed to create Ethernet packet")?;
+        // 01:00:5e plus the low 23 bits of 224.0.0.1
+        ethernet.set_destination(MacAddr::new(0x01, 0x00, 0x5e, 0x00, 0x00, 0x01));
+        ethernet.set_source(src_mac);
+        ethernet.set_ethertype(EtherTypes::Ipv4);
+    }
+    let mut query = [0u8; QUERY_LEN];
+    query[0] = MEMBERSHIP_QUERY;
+    query[1] = MAX_RESPONSE_CODE;
+    // Group 0.0.0.0 (general query), robustness variable 2, query interval 125s
+    query[8] = 0x02;
+    query[9] = 125;
+    let checksum = pnet::util::checksum(&query, 1);
+    query[2..4].copy_from_slice(&checksum.to_be_bytes());
+    {
+        let mut ip =
+            MutableIpv4Packet::new(&mut buffer[14..]).ok_or("Failed to create IPv4 packet")?;
+        ip.set_version(4);
+        ip.set_header_length((IP_HEADER_LEN / 4) as u8);
+        // Internetwork control, as routers send queries
+        ip.set_dscp(48);
+        ip.set_total_length((IP_HEADER_LEN + QUERY_LEN) as u16);
+        ip.set_ttl(1);
+        ip.set_next_level_protocol(IpNextHeaderProtocols::Igmp);
+        ip.set_source(src_ip);
+        ip.set_destination(ALL_SYSTEMS);
+        // Router Alert, value 0: examine this packet
+        ip.packet_mut()[20..24].copy_from_slice(&[0x94, 0x04, 0x00, 0x00]);
+        ip.packet_mut()[IP_HEADER_LEN..].copy_from_slice(&query);
+        let checksum = ipv4::checksum(&ip.to_immutable());
+        ip.set_checksum(checksum);
+    }
+    vlan::encapsulate(buffer, vlan_id)
+}
+
+/// Parses a membership report frame (tagged or untagged) into the reporting
+/// host and the groups it says it is a member of.
+pub fn parse_report(frame: &[u8]) -> Option<(Ipv4Addr, Vec<Ipv4Addr>)> {
+    let untagged = vlan::untag_frame(frame);
+    let ethernet = EthernetPacket::new(&untagged)?;
+    if ethernet.get_ethertype() != EtherTypes::Ipv4 {
+        return None;
+    }
+    let ip = Ipv4Packet::new(ethernet.payload())?;
+    if ip.get_next_level_protocol() != IpNextHeaderProtocols::Igmp {
+        return None;
+    }
+    let igmp = ip.payload();
+    let group_at = |offset: usize| -> Option<Ipv4Addr> {
+        let octets: [u8; 4] = igmp.get(offset..offset + 4)?.try_into().ok()?;
+        Some(Ipv4Addr::from(octets))
+    };
+    let groups = match *igmp.first()? {
+        V1_MEMBERSHIP_REPORT | V2_MEMBERSHIP_REPORT => vec![group_at(4)?],
+        V3_MEMBERSHIP_REPORT => {
+            let records = u16::from_be_bytes([*igmp.get(6)?, *igmp.get(7)?]);
+            let mut groups = Vec::new();
+            let mut offset = 8;
+            for _ in 0..records {
+                let record_type = *igmp.get(offset)?;
+                let aux_words = *igmp.get(offset + 1)? as usize;
+                let sources =
+                    u16::from_be_bytes([*igmp.get(offset + 2)?, *igmp.get(offset + 3)?]) as usize;
+                let group = group_at(offset + 4)?;
+                let leaving = record_type == BLOCK_OLD_SOURCES
+                    || (matches!(record_type, MODE_IS_INCLUDE | CHANGE_TO_INCLUDE_MODE)
+                        && sources == 0);
+                if !leaving {
+                    groups.push(group);
+                }
+                offset += 8 + 4 * sources + 4 * aux_words;
+            }
+            groups
+        }
+        _ => return None,
+    };
+    Some((ip.get_source(), groups))
+}
+
+/// Sends a general query on `segment` and collects the membership reports
+/// (requires root). Snooping switches treat the querying port as a router
+/// port, so they forward the reports to it. IGMPv2 hosts skip their report
+/// when another member of the group answered first, so a v2 group may list
+/// only one of its members.
+fn query_blocking(segment: ArpSegment) -> Result<GroupMemberships, String> {
+    let iface = datalink::interfaces()
+        .into_iter()
+        .find(|iface| iface.name == segment.interface)
+        .ok_or_else(|| format!("Interface {} not found", segment.interface))?;
+    let config = datalink::Config {
+        read_timeout: Some(READ_TIMEOUT),
+        // v2 reports are addressed to the group they report
+        promiscuous: true,
+        ..Default::default()
+    };
+    let (mut tx, mut rx) = match datalink::channel(&iface, config) {
+        Ok(Channel::Ethernet(tx, rx)) => (tx, rx),
+        Ok(_) => return Err(format!("Unsupported channel type on {}", iface.name)),
+        Err(e) => {
+            return Err(format!(
+                "Failed to open datalink channel on {} (IGMP queries need root): {}",
+                iface.name, e
+            ));
+        }
+    };
+    let frame = build_query(segment.src_mac, segment.src_ip, segment.vlan_id)?;
+    if let Some(Err(e)) = tx.send_to(&frame, None) {
+        return Err(format!(
+            "Failed to send IGMP query on {}: {}",
+            iface.name, e
+        ));
+    }
+
+    let mut memberships = GroupMemberships::new();
+    let deadline =
+        Instant::now() + Duration::from_millis(u64::from(MAX_RESPONSE_CODE) * 100) + LISTEN_MARGIN;
+    while Instant::now() < deadline {
+        let Ok(frame) = rx.next() else {
+            continue; // read timeout
+        };
+        if let Some((host, groups)) = parse_report(frame)
+            && host != segment.src_ip
+        {
+            memberships.add_report(host, groups);
+        }
+    }
+    Ok(memberships)
+}
+
+/// Queries `segment` for its multicast group memberships.
+pub async fn query(segment: ArpSegment) -> Result<GroupMemberships, String> {
+    tokio::task::spawn_blocking(move || query_blocking(segment))
+        .await
+        .map_err(|e| format!("IGMP query task failed: {}", e))?
+}
+
+type SharedQuery = Arc<tokio::sync::OnceCell<Result<GroupMemberships, String>>>;
+
+/// One query per interface, shared by every host fingerprinted on it
+static QUERIES: Lazy<Mutex<HashMap<String, SharedQuery>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Multicast groups `ip` is a member of. The first lookup on a segment
+/// queries it; the others reuse its reports. Hosts behind a router are not
+/// reached by queries, so they have none.
+pub async fn groups_of(ip: Ipv4Addr, interface: Option<&str>) -> Result<Vec<Ipv4Addr>, String> {
+    let name = match (interface, routing::local_subnet(ip)) {
+        (Some(name), _) => name.to_string(),
+        (None, Some(subnet)) => subnet.interface,
+        (None, None) => return Ok(Vec::new()),
+    };
+    let shared = QUERIES
+        .lock()
+        .unwrap()
+        .entry(name.clone())
+        .or_default()
+        .clone();
+    let memberships = shared
+        .get_or_init(|| async move {
+            let segment = ArpSegment::new(Some(&name), ip, None, None)?;
+            query(segment).await
+        })
+        .await
+        .clone()?;
+    Ok(memberships.groups(ip))
+}
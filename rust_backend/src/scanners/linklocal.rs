@@ -0,0 +1,370 @@
+use crate::scanners::ndpsweep::multicast_mac;
+use crate::utils::{oui, vlan};
+use pnet::datalink::{self, Channel, MacAddr, NetworkInterface};
+use pnet::packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
+use pnet::packet::icmpv6::{self, Icmpv6Code, Icmpv6Packet, Icmpv6Types, MutableIcmpv6Packet};
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv6::{Ipv6Packet, MutableIpv6Packet};
+use pnet::packet::{MutablePacket, Packet};
+use std::collections::BTreeSet;
+use std::net::{IpAddr, Ipv6Addr};
+use std::time::{Duration, Instant};
+
+const ALL_NODES: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1);
+const IPV6_HEADER_LEN: usize = 40;
+/// Echo request header (8) and a short payload (8)
+const ECHO_LEN: usize = 16;
+const ECHO_FRAME_SIZE: usize = 14 + IPV6_HEADER_LEN + ECHO_LEN;
+const ECHO_PAYLOAD: &[u8; 8] = b"netscan6";
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// An on-link IPv6 host, by link-layer address, with every address it
+/// answered from or the neighbor cache lists for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipv6Neighbor {
+    pub mac: MacAddr,
+    pub addresses: BTreeSet<Ipv6Addr>,
+    /// The neighbor cache marks it as a router
+    pub router: bool,
+}
+
+impl Ipv6Neighbor {
+    pub fn link_local(&self) -> impl Iterator<Item = Ipv6Addr> + '_ {
+        self.addresses
+            .iter()
+            .copied()
+            .filter(Ipv6Addr::is_unicast_link_local)
+    }
+
+    /// Addresses other hosts can reach it on: global and unique local ones.
+    pub fn routable(&self) -> impl Iterator<Item = Ipv6Addr> + '_ {
+        self.addresses
+            .iter()
+            .copied()
+            .filter(|ip| !ip.is_unicast_link_local())
+    }
+
+    /// Whether an address was formed from the MAC (SLAAC with EUI-64), so
+    /// the host can be tracked across networks by it.
+    pub fn uses_eui64(&self) -> bool {
+        self.addresses
+            .iter()
+            .any(|&ip| eui64_mac(ip) == Some(self.mac))
+    }
+}
+
+/// On-link IPv6 hosts found on one interface
+#[derive(Debug, Clone)]
+pub struct LinkEnumeration {
+    interface: String,
+    /// Index of the interface, the scope of the link-local addresses on it
+    scope_id: u32,
+    neighbors: Vec<Ipv6Neighbor>,
+}
+
+impl LinkEnumeration {
+    pub fn new(interface: String, scope_id: u32) -> Self {
+        Self {
+            interface,
+            scope_id,
+            neighbors: Vec::new(),
+        }
+    }
+
+    /// Records that `mac` uses `ip`, merging hosts by link-layer address.
+    pub fn add(&mut self, ip: Ipv6Addr, mac: MacAddr, router: bool) {
+        match self.neighbors.iter_mut().find(|n| n.mac == mac) {
+            Some(neighbor) => {
+                neighbor.addresses.insert(ip);
+                neighbor.router |= router;
+            }
+            None => self.neighbors.push(Ipv6Neighbor {
+                mac,
+                addresses: BTreeSet::from([ip]),
+                router,
+            }),
+        }
+    }
+
+    pub fn get_interface(&self) -> &str {
+        &self.interface
+    }
+
+    pub fn get_scope_id(&self) -> u32 {
+        self.scope_id
+    }
+
+    pub fn get_neighbors(&self) -> &Vec<Ipv6Neighbor> {
+        &self.neighbors
+    }
+
+    /// Addresses to scan: the routable ones of every neighbor, and the
+    /// link-local ones of a neighbor that has nothing else.
+    pub fn scan_addresses(&self) -> Vec<Ipv6Addr> {
+        self.neighbors
+            .iter()
+            .flat_map(|n| {
+                let routable: Vec<Ipv6Addr> = n.routable().collect();
+                if routable.is_empty() {
+                    n.link_local().collect()
+                } else {
+                    routable
+                }
+            })
+            .collect()
+    }
+
+    pub fn print_summary(&self) {
+        println!("IPv6 link enumeration completed on {}.", self.interface);
+        for neighbor in &self.neighbors {
+            let addresses: Vec<String> =
+                neighbor.addresses.iter().map(|ip| ip.to_string()).collect();
+            let mut notes = Vec::new();
+            if let Some(vendor) = oui::lookup(&neighbor.mac.to_string()) {
+                notes.push(vendor);
+            }
+            if neighbor.router {
+                notes.push("router".to_string());
+            }
+            if neighbor.uses_eui64() {
+                notes.push("EUI-64 address".to_string());
+            }
+            if neighbor.routable().next().is_none() {
+                notes.push("link-local only".to_string());
+            }
+            println!(
+                "  {}  {}  [{}]",
+                neighbor.mac,
+                addresses.join(", "),
+                notes.join(", ")
+            );
+        }
+        println!("Total on-link IPv6 hosts: {}", self.neighbors.len());
+    }
+}
+
+/// MAC address embedded in an EUI-64 interface identifier (RFC 4291
+/// appendix A): ff:fe in the middle and the universal/local bit flipped.
+pub fn eui64_mac(ip: Ipv6Addr) -> Option<MacAddr> {
+    let o = ip.octets();
+    (o[11] == 0xff && o[12] == 0xfe)
+        .then(|| MacAddr::new(o[8] ^ 0x02, o[9], o[10], o[13], o[14], o[15]))
+}
+
+/// Builds an ICMPv6 echo request from `src_ip` to all nodes (ff02::1).
+/// Hosts answer from an address of the source's scope, so a request from a
+/// global address draws out global addresses.
+pub fn build_all_nodes_echo(
+    src_mac: MacAddr,
+    src_ip: Ipv6Addr,
+    identifier: u16,
+    vlan_id: Option<u16>,
+) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![0u8; ECHO_FRAME_SIZE];
+    {
+        let mut ethernet =
+            MutableEthernetPacket::new(&mut buffer).ok_or("Failed to create Ethernet packet")?;
+        ethernet.set_destination(multicast_mac(ALL_NODES));
+        ethernet.set_source(src_mac);
+        ethernet.set_ethertype(EtherTypes::Ipv6);
+
+        let mut ipv6 =
+            MutableIpv6Packet::new(ethernet.payload_mut()).ok_or("Failed to create IPv6 packet")?;
+        ipv6.set_version(6);
+        ipv6.set_payload_length(ECHO_LEN as u16);
+        ipv6.set_next_header(IpNextHeaderProtocols::Icmpv6);
+        ipv6.set_hop_limit(1);
+        ipv6.set_source(src_ip);
+        ipv6.set_destination(ALL_NODES);
+
+        let mut echo =
+            MutableIcmpv6Packet::new(ipv6.payload_mut()).ok_or("Failed to create ICMPv6 packet")?;
+        echo.set_icmpv6_type(Icmpv6Types::EchoRequest);
+        echo.set_icmpv6_code(Icmpv6Code(0));
+        // Identifier and sequence number, then the payload
+        let mut body = identifier.to_be_bytes().to_vec();
+        body.extend_from_slice(&[0, 1]);
+        body.extend_from_slice(ECHO_PAYLOAD);
+        echo.set_payload(&body);
+        let checksum = {
+            let view = Icmpv6Packet::new(echo.packet()).ok_or("Failed to create ICMPv6 packet")?;
+            icmpv6::checksum(&view, &src_ip, &ALL_NODES)
+        };
+        echo.set_checksum(checksum);
+    }
+    vlan::encapsulate(buffer, vlan_id)
+}
+
+/// Parses an echo reply frame (tagged or untagged) to a request with
+/// `identifier` into (source IP, source MAC, VLAN ID).
+pub fn parse_echo_reply(frame: &[u8], identifier: u16) -> Option<(Ipv6Addr, MacAddr, Option<u16>)> {
+    let vlan_id = vlan::frame_vlan_id(frame);
+    let untagged = vlan::untag_frame(frame);
+    let ethernet = EthernetPacket::new(&untagged)?;
+    if ethernet.get_ethertype() != EtherTypes::Ipv6 {
+        return None;
+    }
+    let ipv6 = Ipv6Packet::new(ethernet.payload())?;
+    if ipv6.get_next_header() != IpNextHeaderProtocols::Icmpv6 {
+        return None;
+    }
+    let reply = Icmpv6Packet::new(ipv6.payload())?;
+    if reply.get_icmpv6_type() != Icmpv6Types::EchoReply {
+        return None;
+    }
+    if reply.payload().get(..2)? != identifier.to_be_bytes() {
+        return None;
+    }
+    Some((ipv6.get_source(), ethernet.get_source(), vlan_id))
+}
+
+/// Neighbors of `interface` in `ip -6 neigh show` output, as (IP, MAC,
+/// router). Entries without a link-layer address (FAILED, INCOMPLETE) are skipped.
+pub fn parse_neighbor_table(output: &str, interface: &str) -> Vec<(Ipv6Addr, MacAddr, bool)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let ip: Ipv6Addr = fields.first()?.parse().ok()?;
+            let after = |key: &str| {
+                fields
+                    .iter()
+                    .position(|field| *field == key)
+                    .and_then(|i| fields.get(i + 1))
+            };
+            if after("dev").is_some_and(|dev| *dev != interface) {
+                return None;
+            }
+            let mac: MacAddr = after("lladdr")?.parse().ok()?;
+            Some((ip, mac, fields.contains(&"router")))
+        })
+        .collect()
+}
+
+/// The kernel's IPv6 neighbor cache for `interface`; Linux only exposes it
+/// over netlink, which `ip` speaks.
+pub fn read_neighbor_cache(interface: &str) -> Result<Vec<(Ipv6Addr, MacAddr, bool)>, String> {
+    let output = std::process::Command::new("ip")
+        .args(["-6", "neigh", "show", "dev", interface])
+        .output()
+        .map_err(|e| format!("Failed to run ip -6 neigh: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "ip -6 neigh failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(parse_neighbor_table(
+        &String::from_utf8_lossy(&output.stdout),
+        interface,
+    ))
+}
+
+/// Interfaces worth enumerating: up, not loopback, with a MAC and a
+/// link-local address.
+pub fn ipv6_interfaces() -> Vec<String> {
+    datalink::interfaces()
+        .into_iter()
+        .filter(|iface| iface.is_up() && !iface.is_loopback() && iface.mac.is_some())
+        .filter(|iface| !source_addresses(iface).is_empty())
+        .map(|iface| iface.name)
+        .collect()
+}
+
+/// IPv6 addresses of `iface`, link-local first.
+fn source_addresses(iface: &NetworkInterface) -> Vec<Ipv6Addr> {
+    let mut addresses: Vec<Ipv6Addr> = iface
+        .ips
+        .iter()
+        .filter_map(|net| match net.ip() {
+            IpAddr::V6(ip) => Some(ip),
+            IpAddr::V4(_) => None,
+        })
+        .collect();
+    addresses.sort_by_key(|ip| !ip.is_unicast_link_local());
+    addresses
+}
+
+/// Enumerates the IPv6 hosts on `interface` (requires root): pings all
+/// nodes from each of its addresses, then adds what the kernel's neighbor
+/// cache knows, which includes hosts that ignore multicast pings but spoke
+/// to us before.
+pub async fn enumerate_link(
+    interface: &str,
+    vlan_id: Option<u16>,
+) -> Result<LinkEnumeration, String> {
+    if let Some(id) = vlan_id {
+        vlan::validate_vlan_id(id)?;
+    }
+    let iface = datalink::interfaces()
+        .into_iter()
+        .find(|iface| iface.name == interface)
+        .ok_or_else(|| format!("Interface {} not found", interface))?;
+    let mut result = tokio::task::spawn_blocking(move || ping_all_nodes_blocking(iface, vlan_id))
+        .await
+        .map_err(|e| format!("IPv6 link enumeration task failed: {}", e))??;
+    // Replies to our pings are in the cache now too; a missing `ip` only loses the extras
+    match read_neighbor_cache(interface) {
+        Ok(entries) => {
+            for (ip, mac, router) in entries {
+                result.add(ip, mac, router);
+            }
+        }
+        Err(e) => tracing::debug!(interface, "neighbor cache skipped: {}", e),
+    }
+    Ok(result)
+}
+
+fn ping_all_nodes_blocking(
+    iface: NetworkInterface,
+    vlan_id: Option<u16>,
+) -> Result<LinkEnumeration, String> {
+    let src_mac = iface
+        .mac
+        .ok_or_else(|| format!("Interface {} has no MAC address", iface.name))?;
+    let sources = source_addresses(&iface);
+    if sources.is_empty() {
+        return Err(format!("Interface {} has no IPv6 address", iface.name));
+    }
+    let config = datalink::Config {
+        read_timeout: Some(READ_TIMEOUT),
+        ..Default::default()
+    };
+    let (mut tx, mut rx) = match datalink::channel(&iface, config) {
+        Ok(Channel::Ethernet(tx, rx)) => (tx, rx),
+        Ok(_) => return Err(format!("Unsupported channel type on {}", iface.name)),
+        Err(e) => {
+            return Err(format!(
+                "Failed to open datalink channel on {}: {}",
+                iface.name, e
+            ));
+        }
+    };
+
+    let identifier = std::process::id() as u16;
+    for &src_ip in &sources {
+        let frame = build_all_nodes_echo(src_mac, src_ip, identifier, vlan_id)?;
+        if let Some(Err(e)) = tx.send_to(&frame, None) {
+            return Err(format!("Failed to ping ff02::1 from {}: {}", src_ip, e));
+        }
+    }
+
+    let mut result = LinkEnumeration::new(iface.name.clone(), iface.index);
+    let deadline = Instant::now() + REPLY_TIMEOUT;
+    while Instant::now() < deadline {
+        let Ok(frame) = rx.next() else {
+            continue; // read timeout
+        };
+        if let Some((ip, mac, frame_vlan)) = parse_echo_reply(frame, identifier) {
+            // On a trunk, only accept replies from the VLAN that was probed
+            if !vlan::accepts(vlan_id, frame_vlan) {
+                continue;
+            }
+            if mac != src_mac {
+                result.add(ip, mac, false);
+            }
+        }
+    }
+    Ok(result)
+}
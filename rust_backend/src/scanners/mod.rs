@@ -2,3 +2,15 @@ pub mod service_detection;
 pub mod pingsweep;
 pub mod tcpscan;
 pub mod udpscan;
+pub mod arpsweep;
+pub mod dc_audit;
+pub mod tls_audit;
+pub mod weak_protocols;
+pub mod ndpsweep;
+pub mod synscan;
+pub mod arpcache;
+pub mod icmp_unreachable;
+pub mod udp_probes;
+pub mod os_fingerprint;
+pub mod igmp;
+pub mod linklocal;
\ No newline at end of file
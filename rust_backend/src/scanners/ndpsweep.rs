@@ -0,0 +1,292 @@
+use crate::scanners::pingsweep::parse_network;
+use crate::utils::vlan;
+use pnet::datalink::{self, Channel, MacAddr, NetworkInterface};
+use pnet::packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
+use pnet::packet::icmpv6::ndp::{
+    MutableNeighborSolicitPacket, NdpOption, NdpOptionTypes, NeighborAdvertPacket,
+};
+use pnet::packet::icmpv6::{self, Icmpv6Code, Icmpv6Packet, Icmpv6Types};
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv6::{Ipv6Packet, MutableIpv6Packet};
+use pnet::packet::{MutablePacket, Packet};
+use std::net::{IpAddr, Ipv6Addr};
+use std::time::{Duration, Instant};
+
+const IPV6_HEADER_LEN: usize = 40;
+const NS_LEN: usize = 32; // Neighbor Solicitation (24) + source link-layer address option (8)
+const NS_FRAME_SIZE: usize = 14 + IPV6_HEADER_LEN + NS_LEN;
+const NDP_HOP_LIMIT: u8 = 255; // RFC 4861 § 7.1.1: anything else is dropped by the receiver
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2); // How long to collect replies after the last solicitation
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Struct to store the results of the neighbor discovery sweep
+#[derive(Debug)]
+pub struct NdpSweepResult {
+    live_hosts: Vec<(Ipv6Addr, MacAddr)>,
+    interface: String,
+    vlan_id: Option<u16>,
+}
+
+impl NdpSweepResult {
+    pub fn new(interface: String, vlan_id: Option<u16>) -> Self {
+        Self {
+            live_hosts: Vec::new(),
+            interface,
+            vlan_id,
+        }
+    }
+
+    pub fn add_live_host(&mut self, ip: Ipv6Addr, mac: MacAddr) {
+        if !self.live_hosts.iter().any(|(known, _)| *known == ip) {
+            self.live_hosts.push((ip, mac));
+        }
+    }
+
+    pub fn get_live_hosts(&self) -> &Vec<(Ipv6Addr, MacAddr)> {
+        &self.live_hosts
+    }
+
+    pub fn get_interface(&self) -> &str {
+        &self.interface
+    }
+
+    pub fn get_vlan_id(&self) -> Option<u16> {
+        self.vlan_id
+    }
+
+    pub fn print_summary(&self) {
+        println!("Neighbor discovery sweep completed on {}.", self.interface);
+        if let Some(id) = self.vlan_id {
+            println!("VLAN: {}", id);
+        }
+        println!("Total live hosts: {}", self.live_hosts.len());
+    }
+}
+
+/// Solicited-node multicast group of `target` (ff02::1:ffXX:XXXX, RFC 4291 § 2.7.1).
+pub fn solicited_node_multicast(target: Ipv6Addr) -> Ipv6Addr {
+    let octets = target.octets();
+    Ipv6Addr::new(
+        0xff02,
+        0,
+        0,
+        0,
+        0,
+        1,
+        0xff00 | u16::from(octets[13]),
+        u16::from_be_bytes([octets[14], octets[15]]),
+    )
+}
+
+/// Ethernet address an IPv6 multicast group maps to (33:33 + low 32 bits, RFC 2464 § 7).
+pub fn multicast_mac(group: Ipv6Addr) -> MacAddr {
+    let octets = group.octets();
+    MacAddr::new(0x33, 0x33, octets[12], octets[13], octets[14], octets[15])
+}
+
+/// Builds a Neighbor Solicitation for `target_ip`, sent to its solicited-node
+/// group and 802.1Q-tagged when `vlan_id` is set.
+pub fn build_neighbor_solicitation(
+    src_mac: MacAddr,
+    src_ip: Ipv6Addr,
+    target_ip: Ipv6Addr,
+    vlan_id: Option<u16>,
+) -> Result<Vec<u8>, String> {
+    let group = solicited_node_multicast(target_ip);
+    let mut buffer = vec![0u8; NS_FRAME_SIZE];
+    {
+        let mut ethernet =
+            MutableEthernetPacket::new(&mut buffer).ok_or("Failed to create Ethernet packet")?;
+        ethernet.set_destination(multicast_mac(group));
+        ethernet.set_source(src_mac);
+        ethernet.set_ethertype(EtherTypes::Ipv6);
+
+        let mut ipv6 =
+            MutableIpv6Packet::new(ethernet.payload_mut()).ok_or("Failed to create IPv6 packet")?;
+        ipv6.set_version(6);
+        ipv6.set_payload_length(NS_LEN as u16);
+        ipv6.set_next_header(IpNextHeaderProtocols::Icmpv6);
+        ipv6.set_hop_limit(NDP_HOP_LIMIT);
+        ipv6.set_source(src_ip);
+        ipv6.set_destination(group);
+
+        let mut solicit = MutableNeighborSolicitPacket::new(ipv6.payload_mut())
+            .ok_or("Failed to create Neighbor Solicitation")?;
+        solicit.set_icmpv6_type(Icmpv6Types::NeighborSolicit);
+        solicit.set_icmpv6_code(Icmpv6Code(0));
+        solicit.set_target_addr(target_ip);
+        solicit.set_options(&[NdpOption {
+            option_type: NdpOptionTypes::SourceLLAddr,
+            length: 1,
+            data: src_mac.octets().to_vec(),
+        }]);
+        let checksum = {
+            let view =
+                Icmpv6Packet::new(solicit.packet()).ok_or("Failed to create ICMPv6 packet")?;
+            icmpv6::checksum(&view, &src_ip, &group)
+        };
+        solicit.set_checksum(checksum);
+    }
+    vlan::encapsulate(buffer, vlan_id)
+}
+
+/// Parses a Neighbor Advertisement frame (tagged or untagged) into
+/// (target IP, link-layer address, VLAN ID).
+pub fn parse_neighbor_advertisement(frame: &[u8]) -> Option<(Ipv6Addr, MacAddr, Option<u16>)> {
+    let vlan_id = vlan::frame_vlan_id(frame);
+    let untagged = vlan::untag_frame(frame);
+    let ethernet = EthernetPacket::new(&untagged)?;
+    if ethernet.get_ethertype() != EtherTypes::Ipv6 {
+        return None;
+    }
+    let ipv6 = Ipv6Packet::new(ethernet.payload())?;
+    if ipv6.get_next_header() != IpNextHeaderProtocols::Icmpv6 {
+        return None;
+    }
+    let advert = NeighborAdvertPacket::new(ipv6.payload())?;
+    if advert.get_icmpv6_type() != Icmpv6Types::NeighborAdvert {
+        return None;
+    }
+    // The target link-layer address option is authoritative; fall back to the frame source
+    let mac = advert
+        .get_options()
+        .iter()
+        .find(|option| option.option_type == NdpOptionTypes::TargetLLAddr && option.data.len() >= 6)
+        .map(|option| {
+            let d = &option.data;
+            MacAddr::new(d[0], d[1], d[2], d[3], d[4], d[5])
+        })
+        .unwrap_or_else(|| ethernet.get_source());
+    Some((advert.get_target_addr(), mac, vlan_id))
+}
+
+/// Picks the interface to sweep from: the named one, or the one with an IPv6
+/// network containing `first_ip`.
+fn select_interface(name: Option<&str>, first_ip: Ipv6Addr) -> Result<NetworkInterface, String> {
+    let interfaces = datalink::interfaces();
+    match name {
+        Some(name) => interfaces
+            .into_iter()
+            .find(|iface| iface.name == name)
+            .ok_or_else(|| format!("Interface {name} not found")),
+        None => interfaces
+            .into_iter()
+            .find(|iface| {
+                iface
+                    .ips
+                    .iter()
+                    .any(|net| net.contains(IpAddr::V6(first_ip)))
+            })
+            .ok_or_else(|| format!("No interface is on-link for {first_ip}; use --interface")),
+    }
+}
+
+/// Source address for solicitations: the interface's link-local address if
+/// it has one, else any of its IPv6 addresses.
+fn source_address(iface: &NetworkInterface) -> Option<Ipv6Addr> {
+    let addresses: Vec<Ipv6Addr> = iface
+        .ips
+        .iter()
+        .filter_map(|net| match net.ip() {
+            IpAddr::V6(ip) => Some(ip),
+            IpAddr::V4(_) => None,
+        })
+        .collect();
+    addresses
+        .iter()
+        .find(|ip| ip.is_unicast_link_local())
+        .or(addresses.first())
+        .copied()
+}
+
+/// Function to perform an ICMPv6 neighbor discovery sweep of an IPv6 network
+/// on the local segment (requires root). The IPv6 counterpart of `arp_sweep`.
+pub async fn ndp_sweep(
+    network: &str,
+    interface: Option<&str>,
+    vlan_id: Option<u16>,
+) -> Result<NdpSweepResult, String> {
+    let ips: Vec<Ipv6Addr> = parse_network(network)?
+        .into_iter()
+        .filter_map(|ip| match ip {
+            IpAddr::V6(ip) => Some(ip),
+            IpAddr::V4(_) => None,
+        })
+        .collect();
+    ndp_sweep_hosts(ips, interface, vlan_id).await
+}
+
+/// `ndp_sweep` over an explicit host list, e.g. a network with excluded hosts removed.
+pub async fn ndp_sweep_hosts(
+    ips: Vec<Ipv6Addr>,
+    interface: Option<&str>,
+    vlan_id: Option<u16>,
+) -> Result<NdpSweepResult, String> {
+    if let Some(id) = vlan_id {
+        vlan::validate_vlan_id(id)?;
+    }
+    let first_ip = *ips.first().ok_or("Not an IPv6 network")?;
+    let iface = select_interface(interface, first_ip)?;
+
+    let src_mac = iface
+        .mac
+        .ok_or_else(|| format!("Interface {} has no MAC address", iface.name))?;
+    let src_ip = source_address(&iface)
+        .ok_or_else(|| format!("Interface {} has no IPv6 address", iface.name))?;
+
+    tokio::task::spawn_blocking(move || sweep_blocking(iface, ips, src_mac, src_ip, vlan_id))
+        .await
+        .map_err(|e| format!("Neighbor discovery task failed: {e}"))?
+}
+
+fn sweep_blocking(
+    iface: NetworkInterface,
+    ips: Vec<Ipv6Addr>,
+    src_mac: MacAddr,
+    src_ip: Ipv6Addr,
+    vlan_id: Option<u16>,
+) -> Result<NdpSweepResult, String> {
+    let config = datalink::Config {
+        read_timeout: Some(READ_TIMEOUT),
+        ..Default::default()
+    };
+    let (mut tx, mut rx) = match datalink::channel(&iface, config) {
+        Ok(Channel::Ethernet(tx, rx)) => (tx, rx),
+        Ok(_) => return Err(format!("Unsupported channel type on {}", iface.name)),
+        Err(e) => {
+            return Err(format!(
+                "Failed to open datalink channel on {}: {}",
+                iface.name, e
+            ));
+        }
+    };
+
+    for &ip in &ips {
+        let frame = build_neighbor_solicitation(src_mac, src_ip, ip, vlan_id)?;
+        if let Some(Err(e)) = tx.send_to(&frame, None) {
+            return Err(format!(
+                "Failed to send Neighbor Solicitation to {}: {}",
+                ip, e
+            ));
+        }
+    }
+
+    let mut result = NdpSweepResult::new(iface.name.clone(), vlan_id);
+    let deadline = Instant::now() + REPLY_TIMEOUT;
+    while Instant::now() < deadline {
+        let Ok(frame) = rx.next() else {
+            continue; // read timeout
+        };
+        if let Some((ip, mac, frame_vlan)) = parse_neighbor_advertisement(frame) {
+            // On a trunk, only accept replies from the VLAN that was probed
+            if !vlan::accepts(vlan_id, frame_vlan) {
+                continue;
+            }
+            if ips.contains(&ip) {
+                result.add_live_host(ip, mac);
+            }
+        }
+    }
+
+    Ok(result)
+}
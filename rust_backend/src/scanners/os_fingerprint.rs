@@ -0,0 +1,398 @@
+use crate::scanners::synscan::{self, SynReply};
+use crate::utils::evidence::{Attribute, Evidence, Source};
+use crate::utils::os_fusion::OsGuess;
+use crate::utils::ratelimit;
+use pnet::packet::Packet;
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv4::Ipv4Packet;
+use pnet::packet::tcp::{self, MutableTcpPacket, TcpFlags, TcpOption, TcpOptionNumbers, TcpPacket};
+use pnet::transport::{
+    TransportChannelType, TransportProtocol, ipv4_packet_iter, transport_channel,
+};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant};
+
+/// 20-byte header + MSS, SACK permitted, timestamps, NOP and window scale
+const PROBE_HEADER_LEN: usize = 40;
+const PROBE_WINDOW: u16 = 64240;
+const PROBE_MSS: u16 = 1460;
+const PROBE_WSCALE: u8 = 7;
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+/// Ports probed at most; one SYN/ACK is enough
+const MAX_PROBED_PORTS: usize = 8;
+/// Below this a stack match is not worth reporting
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// A TCP option as it appears in a SYN/ACK
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpOptionKind {
+    Eol,
+    Nop,
+    Mss,
+    WindowScale,
+    SackPermitted,
+    Timestamp,
+    Other(u8),
+}
+
+impl TcpOptionKind {
+    fn from_number(number: u8) -> Self {
+        match number {
+            0 => TcpOptionKind::Eol,
+            1 => TcpOptionKind::Nop,
+            2 => TcpOptionKind::Mss,
+            3 => TcpOptionKind::WindowScale,
+            4 => TcpOptionKind::SackPermitted,
+            8 => TcpOptionKind::Timestamp,
+            other => TcpOptionKind::Other(other),
+        }
+    }
+
+    /// Short name used in option layouts, p0f style.
+    pub fn label(&self) -> &'static str {
+        match self {
+            TcpOptionKind::Eol => "eol",
+            TcpOptionKind::Nop => "nop",
+            TcpOptionKind::Mss => "mss",
+            TcpOptionKind::WindowScale => "ws",
+            TcpOptionKind::SackPermitted => "sok",
+            TcpOptionKind::Timestamp => "ts",
+            TcpOptionKind::Other(_) => "?",
+        }
+    }
+}
+
+/// What a SYN/ACK gives away about the stack that sent it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackSignature {
+    /// IP TTL as received, after the hops on the way
+    pub ttl: u8,
+    pub window: u16,
+    /// Options in the order they were sent
+    pub options: Vec<TcpOptionKind>,
+    pub mss: Option<u16>,
+    pub window_scale: Option<u8>,
+}
+
+impl StackSignature {
+    /// Reads the signature of a SYN/ACK that arrived with IP TTL `ttl`.
+    pub fn from_reply(ttl: u8, segment: &TcpPacket) -> Self {
+        let mut signature = Self {
+            ttl,
+            window: segment.get_window(),
+            options: Vec::new(),
+            mss: None,
+            window_scale: None,
+        };
+        for option in segment.get_options_iter() {
+            let number = option.get_number();
+            let value = option.payload();
+            if number == TcpOptionNumbers::MSS && value.len() == 2 {
+                signature.mss = Some(u16::from_be_bytes([value[0], value[1]]));
+            } else if number == TcpOptionNumbers::WSCALE && value.len() == 1 {
+                signature.window_scale = Some(value[0]);
+            }
+            signature.options.push(TcpOptionKind::from_number(number.0));
+        }
+        signature
+    }
+
+    /// The TTL the host started with: the next common default at or above
+    /// what arrived (32, 64, 128 or 255).
+    pub fn initial_ttl(&self) -> u8 {
+        [32, 64, 128]
+            .into_iter()
+            .find(|&initial| self.ttl <= initial)
+            .unwrap_or(255)
+    }
+
+    /// Option order as one string, e.g. "mss,sok,ts,nop,ws".
+    pub fn layout(&self) -> String {
+        self.options
+            .iter()
+            .map(TcpOptionKind::label)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl fmt::Display for StackSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TTL {}, window {}, options {}",
+            self.ttl,
+            self.window,
+            if self.options.is_empty() {
+                "none".to_string()
+            } else {
+                self.layout()
+            }
+        )?;
+        if let Some(scale) = self.window_scale {
+            write!(f, ", wscale {}", scale)?;
+        }
+        Ok(())
+    }
+}
+
+/// How a family of stacks answers a SYN that offers every common option
+struct StackProfile {
+    os: &'static str,
+    initial_ttls: &'static [u8],
+    layouts: &'static [&'static str],
+    windows: &'static [u16],
+    /// Window scale it sends; `None` for stacks that leave the option out
+    window_scale: Option<u8>,
+}
+
+// The option layout tells the families apart best, the initial TTL next;
+// window sizes vary with the MSS and tuning, so they only break ties
+const TTL_WEIGHT: f64 = 0.35;
+const LAYOUT_WEIGHT: f64 = 0.4;
+const WINDOW_WEIGHT: f64 = 0.15;
+const WSCALE_WEIGHT: f64 = 0.1;
+
+const PROFILES: &[StackProfile] = &[
+    StackProfile {
+        os: "Linux",
+        initial_ttls: &[64],
+        layouts: &[
+            "mss,sok,ts,nop,ws",
+            "mss,nop,nop,sok,nop,ws",
+            "mss,nop,nop,ts,nop,ws",
+        ],
+        windows: &[65160, 64240, 43440, 28960, 27960, 14480, 5792],
+        window_scale: Some(7),
+    },
+    StackProfile {
+        os: "Windows",
+        initial_ttls: &[128],
+        layouts: &[
+            "mss,nop,ws,nop,nop,sok",
+            "mss,nop,ws,sok,ts",
+            "mss,nop,nop,sok",
+        ],
+        windows: &[65535, 64240, 8192],
+        window_scale: Some(8),
+    },
+    StackProfile {
+        os: "BSD",
+        initial_ttls: &[64],
+        layouts: &["mss,nop,ws,sok,ts", "mss,nop,ws,nop,nop,ts,sok,eol,eol"],
+        windows: &[65535],
+        window_scale: Some(6),
+    },
+    StackProfile {
+        os: "Embedded",
+        initial_ttls: &[255, 64, 32],
+        layouts: &["mss", ""],
+        windows: &[512, 1024, 1460, 2048, 2144, 2920, 4096, 5744, 5840, 8192],
+        window_scale: None,
+    },
+];
+
+impl StackProfile {
+    fn score(&self, signature: &StackSignature) -> f64 {
+        let layout = signature.layout();
+        [
+            (
+                self.initial_ttls.contains(&signature.initial_ttl()),
+                TTL_WEIGHT,
+            ),
+            (self.layouts.contains(&layout.as_str()), LAYOUT_WEIGHT),
+            (self.windows.contains(&signature.window), WINDOW_WEIGHT),
+            (self.window_scale == signature.window_scale, WSCALE_WEIGHT),
+        ]
+        .iter()
+        .filter(|(matched, _)| *matched)
+        .map(|(_, weight)| weight)
+        .sum()
+    }
+}
+
+/// Ranks the OS families `signature` could come from, most likely first.
+pub fn rank(signature: &StackSignature) -> Vec<OsGuess> {
+    let mut guesses: Vec<OsGuess> = PROFILES
+        .iter()
+        .map(|profile| OsGuess {
+            os: profile.os.to_string(),
+            confidence: profile.score(signature),
+        })
+        .filter(|guess| guess.confidence > 0.0)
+        .collect();
+    guesses.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    guesses
+}
+
+/// The OS family `signature` most likely comes from, if it clearly matches one.
+pub fn classify(signature: &StackSignature) -> Option<OsGuess> {
+    let guesses = rank(signature);
+    let best = guesses.first()?;
+    // A tie means the signature does not tell the families apart
+    let tied = guesses
+        .get(1)
+        .is_some_and(|second| second.confidence == best.confidence);
+    (best.confidence >= MIN_CONFIDENCE && !tied).then(|| best.clone())
+}
+
+/// Stack signature of one host and what it points to
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFingerprint {
+    /// Port whose SYN/ACK was read
+    pub port: u16,
+    pub signature: StackSignature,
+    pub guess: Option<OsGuess>,
+}
+
+impl StackFingerprint {
+    pub fn new(port: u16, signature: StackSignature) -> Self {
+        let guess = classify(&signature);
+        Self {
+            port,
+            signature,
+            guess,
+        }
+    }
+
+    /// OS evidence for the host fingerprint, when the stack matched a family.
+    pub fn evidence(&self) -> Option<Evidence> {
+        let guess = self.guess.as_ref()?;
+        Some(
+            Evidence::new(
+                Attribute::Os,
+                &guess.os,
+                Source::Stack(self.signature.to_string()),
+            )
+            .on_port(self.port),
+        )
+    }
+}
+
+impl fmt::Display for StackFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "port {}: {}", self.port, self.signature)?;
+        match &self.guess {
+            Some(guess) => write!(f, " -> {}", guess),
+            None => write!(f, " -> no match"),
+        }
+    }
+}
+
+/// SYN offering MSS, SACK, timestamps and window scaling, so the reply
+/// shows which of them the stack supports and in what order it lists them.
+pub fn build_probe(
+    src: Ipv4Addr,
+    dst: Ipv4Addr,
+    src_port: u16,
+    dst_port: u16,
+    seq: u32,
+) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![0u8; PROBE_HEADER_LEN];
+    let mut syn = MutableTcpPacket::new(&mut buffer).ok_or("Failed to create TCP packet")?;
+    syn.set_source(src_port);
+    syn.set_destination(dst_port);
+    syn.set_sequence(seq);
+    syn.set_data_offset((PROBE_HEADER_LEN / 4) as u8);
+    syn.set_flags(TcpFlags::SYN);
+    syn.set_window(PROBE_WINDOW);
+    syn.set_options(&[
+        TcpOption::mss(PROBE_MSS),
+        TcpOption::sack_perm(),
+        TcpOption::timestamp(seq, 0),
+        TcpOption::nop(),
+        TcpOption::wscale(PROBE_WSCALE),
+    ]);
+    let checksum = tcp::ipv4_checksum(&syn.to_immutable(), &src, &dst);
+    syn.set_checksum(checksum);
+    Ok(buffer)
+}
+
+/// The answering port and stack signature of an IPv4 packet carrying a
+/// SYN/ACK to the probe sent from `src_port` with sequence number `seq`.
+pub fn read_reply(packet: &Ipv4Packet, src_port: u16, seq: u32) -> Option<(u16, StackSignature)> {
+    if packet.get_next_level_protocol() != IpNextHeaderProtocols::Tcp {
+        return None;
+    }
+    let segment = TcpPacket::new(packet.payload())?;
+    match synscan::classify_reply(&segment, src_port, seq)? {
+        (port, SynReply::Open) => {
+            Some((port, StackSignature::from_reply(packet.get_ttl(), &segment)))
+        }
+        (_, SynReply::Closed) => None,
+    }
+}
+
+/// Sends probe SYNs to `ports` of `ip` and reads the first SYN/ACK.
+/// Blocking: runs on its own thread. Needs root for the raw sockets.
+fn probe_blocking(
+    ip: Ipv4Addr,
+    ports: Vec<u16>,
+    runtime: tokio::runtime::Handle,
+) -> Result<Option<StackFingerprint>, String> {
+    let source = match synscan::route_source(IpAddr::V4(ip))? {
+        IpAddr::V4(source) => source,
+        IpAddr::V6(source) => return Err(format!("Source {} cannot reach {}", source, ip)),
+    };
+    let (mut tx, _) = transport_channel(
+        4096,
+        TransportChannelType::Layer4(TransportProtocol::Ipv4(IpNextHeaderProtocols::Tcp)),
+    )
+    .map_err(|e| {
+        format!(
+            "Failed to open raw TCP socket (stack fingerprinting needs root): {}",
+            e
+        )
+    })?;
+    // Replies are read with their IP header for the TTL
+    let (_, mut rx) = transport_channel(
+        4096,
+        TransportChannelType::Layer3(IpNextHeaderProtocols::Tcp),
+    )
+    .map_err(|e| {
+        format!(
+            "Failed to open raw IP socket (stack fingerprinting needs root): {}",
+            e
+        )
+    })?;
+
+    let src_port = 50_000 + (std::process::id() % 10_000) as u16;
+    let seq = (std::process::id() as u32).rotate_left(8) ^ u32::from(ip);
+    for &port in &ports {
+        runtime.block_on(ratelimit::acquire(ratelimit::TCP_SYN_BYTES));
+        let syn = build_probe(source, ip, src_port, port, seq)?;
+        let packet = TcpPacket::new(&syn).ok_or("Failed to create TCP packet")?;
+        tx.send_to(packet, IpAddr::V4(ip))
+            .map_err(|e| format!("Failed to send SYN to {}:{}: {}", ip, port, e))?;
+    }
+
+    let deadline = Instant::now() + REPLY_TIMEOUT;
+    let mut iter = ipv4_packet_iter(&mut rx);
+    while Instant::now() < deadline {
+        let Ok(Some((packet, addr))) = iter.next_with_timeout(READ_TIMEOUT) else {
+            continue; // read timeout
+        };
+        if addr != IpAddr::V4(ip) {
+            continue;
+        }
+        if let Some((port, signature)) = read_reply(&packet, src_port, seq) {
+            return Ok(Some(StackFingerprint::new(port, signature)));
+        }
+    }
+    Ok(None)
+}
+
+/// Fingerprints the TCP/IP stack of `ip` from a SYN/ACK on one of `ports`
+/// (the first few are tried). `Ok(None)` when no port answered with one.
+pub async fn fingerprint(ip: Ipv4Addr, ports: &[u16]) -> Result<Option<StackFingerprint>, String> {
+    let ports: Vec<u16> = ports.iter().copied().take(MAX_PROBED_PORTS).collect();
+    if ports.is_empty() {
+        return Ok(None);
+    }
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || probe_blocking(ip, ports, runtime))
+        .await
+        .map_err(|e| format!("Stack fingerprint task failed: {}", e))?
+}
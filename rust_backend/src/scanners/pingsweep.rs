@@ -1,23 +1,55 @@
 use pnet::packet::icmp::{IcmpTypes};
 use pnet::packet::icmp::echo_request::MutableEchoRequestPacket;
 use pnet::packet::icmp::IcmpPacket;
+use pnet::packet::icmpv6::echo_request::MutableEchoRequestPacket as MutableEchoRequestV6Packet;
+use pnet::packet::icmpv6::{Icmpv6Packet, Icmpv6Types};
+use pnet::packet::ip::IpNextHeaderProtocols;
 use pnet::packet::Packet;
-use pnet::transport::{icmp_packet_iter, transport_channel, TransportChannelType, TransportProtocol};
-use std::net::{IpAddr, Ipv4Addr};
+use pnet::transport::{
+    icmpv6_packet_iter, ipv4_packet_iter, transport_channel, TransportChannelType,
+    TransportProtocol,
+};
+use crate::utils::ratelimit;
+use crate::utils::progress::{Phase, ProgressEvent};
+use crate::utils::scan_config::ScanConfig;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
 
 const ICMP_PACKET_SIZE: usize = 64;
 const MAX_CONCURRENT_TASKS: usize = 64; // Limit the number of concurrent tasks
 const TIMEOUT_SECONDS: u64 = 5; // Timeout for ICMP response
+/// Longest IPv6 prefix that is still swept address by address (a /112, 65536 hosts)
+pub const MIN_IPV6_PREFIX: u32 = 112;
+
+/// A host that answered discovery, with its PTR name when reverse DNS ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveHost {
+    pub ip: IpAddr,
+    pub hostname: Option<String>,
+}
+
+impl fmt::Display for LiveHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.hostname {
+            Some(name) => write!(f, "{} ({})", self.ip, name),
+            None => write!(f, "{}", self.ip),
+        }
+    }
+}
 
 /// Struct to store the results of the ping sweep
 #[derive(Debug)] // Ensure the syntax is correct and Debug is properly imported
 pub struct PingSweepResult {
-    live_hosts: Vec<Ipv4Addr>,
-    not_alive_hosts: Vec<Ipv4Addr>,
-    errors: Vec<(Ipv4Addr, String)>, // Store errors with IPs
+    live_hosts: Vec<IpAddr>,
+    not_alive_hosts: Vec<IpAddr>,
+    errors: Vec<(IpAddr, String)>, // Store errors with IPs
+    retried: BTreeMap<IpAddr, usize>, // Echo requests live hosts needed, where more than one
+    rtts: BTreeMap<IpAddr, Duration>, // Round trip of the echo each live host answered
+    ttls: BTreeMap<IpAddr, u8>, // IP TTL the echo reply of each live IPv4 host arrived with
 }
 
 impl PingSweepResult {
@@ -26,43 +58,98 @@ impl PingSweepResult {
             live_hosts: Vec::new(),
             not_alive_hosts: Vec::new(),
             errors: Vec::new(),
+            retried: BTreeMap::new(),
+            rtts: BTreeMap::new(),
+            ttls: BTreeMap::new(),
         }
     }
 
-    pub fn add_live_host(&mut self, ip: Ipv4Addr) {
+    pub fn add_live_host(&mut self, ip: IpAddr) {
         self.live_hosts.push(ip);
     }
 
-    pub fn add_not_alive_host(&mut self, ip: Ipv4Addr) {
+    pub fn add_not_alive_host(&mut self, ip: IpAddr) {
         self.not_alive_hosts.push(ip);
     }
 
-    pub fn add_error(&mut self, ip: Ipv4Addr, error: String) {
+    pub fn add_error(&mut self, ip: IpAddr, error: String) {
         self.errors.push((ip, error));
     }
 
-    pub fn get_live_hosts(&self) -> &Vec<Ipv4Addr> {
+    pub fn get_live_hosts(&self) -> &Vec<IpAddr> {
         &self.live_hosts
     }
 
-    pub fn get_not_alive_hosts(&self) -> &Vec<Ipv4Addr> {
+    pub fn get_not_alive_hosts(&self) -> &Vec<IpAddr> {
         &self.not_alive_hosts
     }
 
-    pub fn get_errors(&self) -> &Vec<(Ipv4Addr, String)> {
+    pub fn get_errors(&self) -> &Vec<(IpAddr, String)> {
         &self.errors
     }
 
+    /// Records that `ip` answered the last of `attempts` echo requests.
+    pub fn record_attempts(&mut self, ip: IpAddr, attempts: usize) {
+        if attempts > 1 {
+            self.retried.insert(ip, attempts);
+        }
+    }
+
+    /// Echo requests `ip` needed before it answered (1 unless it was retried).
+    pub fn get_attempts(&self, ip: IpAddr) -> usize {
+        self.retried.get(&ip).copied().unwrap_or(1)
+    }
+
+    pub fn record_rtt(&mut self, ip: IpAddr, rtt: Duration) {
+        self.rtts.insert(ip, rtt);
+    }
+
+    /// Round trip times of the live hosts
+    pub fn get_rtts(&self) -> &BTreeMap<IpAddr, Duration> {
+        &self.rtts
+    }
+
+    pub fn record_ttl(&mut self, ip: IpAddr, ttl: u8) {
+        self.ttls.insert(ip, ttl);
+    }
+
+    /// IP TTLs the echo replies of the live IPv4 hosts arrived with
+    pub fn get_ttls(&self) -> &BTreeMap<IpAddr, u8> {
+        &self.ttls
+    }
+
+    /// Hosts that answered only after more than one echo request, with how many they needed
+    pub fn get_retried(&self) -> &BTreeMap<IpAddr, usize> {
+        &self.retried
+    }
+
     pub fn print_summary(&self) {
         println!("Ping sweep completed.");
         println!("Total live hosts: {}", self.live_hosts.len());
         println!("Total not-alive hosts: {}", self.not_alive_hosts.len());
         println!("Total errors: {}", self.errors.len());
+        if !self.retried.is_empty() {
+            println!("Hosts retried: {}", self.retried.len());
+        }
     }
 }
 
-/// Function to check if a host is alive using ICMP Echo Request
-fn is_host_alive(ip: Ipv4Addr) -> Result<bool, String> {
+/// An echo reply; `ttl` is the IP TTL it arrived with, IPv4 only since raw
+/// IPv6 sockets do not hand over the header
+struct EchoReply {
+    ttl: Option<u8>,
+    rtt: Duration, // From sending the request, after the sockets exist
+}
+
+/// Function to check if a host is alive using ICMP (or ICMPv6) Echo Request
+fn is_host_alive(ip: IpAddr, timeout: Duration) -> Result<Option<EchoReply>, String> {
+    match ip {
+        IpAddr::V4(ip) => is_host_alive_v4(ip, timeout),
+        IpAddr::V6(ip) => is_host_alive_v6(ip, timeout),
+    }
+}
+
+fn is_host_alive_v4(ip: Ipv4Addr, timeout_duration: Duration) -> Result<Option<EchoReply>, String> {
     let mut buffer = [0u8; ICMP_PACKET_SIZE];
     let mut packet = MutableEchoRequestPacket::new(&mut buffer).unwrap();
 
@@ -75,67 +162,147 @@ fn is_host_alive(ip: Ipv4Addr) -> Result<bool, String> {
     let checksum = pnet::packet::icmp::checksum(&icmp_packet);
     packet.set_checksum(checksum);
 
-    let (mut tx, mut rx) = transport_channel(
+    let (mut tx, _) = transport_channel(
         1024,
         TransportChannelType::Layer4(TransportProtocol::Ipv4(pnet::packet::ip::IpNextHeaderProtocols::Icmp)),
     )
     .map_err(|e| format!("Failed to create transport channel: {}", e))?;
+    // Replies are read with their IP header for the TTL
+    let (_, mut rx) = transport_channel(1024, TransportChannelType::Layer3(IpNextHeaderProtocols::Icmp))
+        .map_err(|e| format!("Failed to create transport channel: {}", e))?;
 
     let target = IpAddr::V4(ip);
+    let sent = Instant::now();
     tx.send_to(packet, target)
         .map_err(|e| format!("Failed to send ICMP request to {}: {}", ip, e))?;
 
-    let mut iter = icmp_packet_iter(&mut rx);
+    let mut iter = ipv4_packet_iter(&mut rx);
 
-    let timeout_duration = Duration::from_secs(TIMEOUT_SECONDS);
     match iter.next_with_timeout(timeout_duration) {
         Ok(Some((packet, addr))) => {
-            if addr == target {
-                if let Some(icmp_packet) = IcmpPacket::new(packet.packet()) {
-                    if icmp_packet.get_icmp_type() == IcmpTypes::EchoReply {
-                        return Ok(true);
-                    }
-                }
+            if addr == target
+                && IcmpPacket::new(packet.payload())
+                    .is_some_and(|reply| reply.get_icmp_type() == IcmpTypes::EchoReply)
+            {
+                return Ok(Some(EchoReply {
+                    ttl: Some(packet.get_ttl()),
+                    rtt: sent.elapsed(),
+                }));
+            }
+        }
+        Ok(None) => {
+            return Ok(None); // No response within timeout
+        }
+        Err(e) => {
+            return Err(format!("Error receiving response: {}", e));
+        }
+    }
+
+    Ok(None)
+}
+
+fn is_host_alive_v6(ip: Ipv6Addr, timeout_duration: Duration) -> Result<Option<EchoReply>, String> {
+    let mut buffer = [0u8; ICMP_PACKET_SIZE];
+    let mut packet = MutableEchoRequestV6Packet::new(&mut buffer).unwrap();
+
+    // The kernel fills in the checksum on raw ICMPv6 sockets (RFC 3542 § 3.1)
+    packet.set_icmpv6_type(Icmpv6Types::EchoRequest);
+    packet.set_sequence_number(1);
+    packet.set_identifier(1);
+
+    let (mut tx, mut rx) = transport_channel(
+        1024,
+        TransportChannelType::Layer4(TransportProtocol::Ipv6(IpNextHeaderProtocols::Icmpv6)),
+    )
+    .map_err(|e| format!("Failed to create transport channel: {}", e))?;
+
+    let target = IpAddr::V6(ip);
+    let sent = Instant::now();
+    tx.send_to(packet, target)
+        .map_err(|e| format!("Failed to send ICMPv6 request to {}: {}", ip, e))?;
+
+    let mut iter = icmpv6_packet_iter(&mut rx);
+
+    match iter.next_with_timeout(timeout_duration) {
+        Ok(Some((packet, addr))) => {
+            if addr == target
+                && Icmpv6Packet::new(packet.packet())
+                    .is_some_and(|reply| reply.get_icmpv6_type() == Icmpv6Types::EchoReply)
+            {
+                return Ok(Some(EchoReply {
+                    ttl: None,
+                    rtt: sent.elapsed(),
+                }));
             }
         }
         Ok(None) => {
-            return Ok(false); // No response within timeout
+            return Ok(None); // No response within timeout
         }
         Err(e) => {
             return Err(format!("Error receiving response: {}", e));
         }
     }
 
-    Ok(false)
+    Ok(None)
 }
 
 /// Function to perform a ping sweep on a given subnet
 pub async fn ping_sweep(subnet: &str) -> Result<PingSweepResult, String> {
-    let ips = parse_subnet(subnet)?;
+    let ips = parse_network(subnet)?;
+    Ok(ping_sweep_hosts(ips, &ScanConfig::default()).await)
+}
+
+/// Function to ping an explicit list of hosts (e.g. a deduplicated target set)
+pub async fn ping_sweep_hosts(ips: Vec<IpAddr>, config: &ScanConfig) -> PingSweepResult {
     let mut result = PingSweepResult::new();
 
-    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_TASKS));
+    let semaphore = Arc::new(Semaphore::new(config.concurrency_or(MAX_CONCURRENT_TASKS)));
+    let timeout = config.timeout_or(Duration::from_secs(TIMEOUT_SECONDS));
+    let attempts = config.retries_or(0) + 1;
     let mut tasks = Vec::new();
+    config.report(ProgressEvent::Started { phase: Phase::PingSweep, total: ips.len() });
 
     for ip in ips {
         let permit = semaphore.clone().acquire_owned().await.unwrap();
+        let config = config.clone();
         let task = tokio::spawn(async move {
             let _permit = permit;
-            (ip, is_host_alive(ip))
+            let mut alive = Ok(None);
+            let mut tried = 0;
+            for attempt in 0..attempts {
+                config.wait_before_attempt(attempt).await;
+                tried += 1;
+                ratelimit::acquire(ratelimit::ICMP_ECHO_BYTES).await;
+                alive = is_host_alive(ip, timeout);
+                if !matches!(alive, Ok(None)) {
+                    break;
+                }
+            }
+            config.report(ProgressEvent::Probed { phase: Phase::PingSweep, ip, port: None });
+            (ip, alive, tried)
         });
         tasks.push(task);
     }
 
     for task in tasks {
         match task.await {
-            Ok((ip, Ok(true))) => result.add_live_host(ip),
-            Ok((ip, Ok(false))) => result.add_not_alive_host(ip),
-            Ok((ip, Err(e))) => result.add_error(ip, e),
-            Err(e) => result.add_error(Ipv4Addr::new(0, 0, 0, 0), format!("Task failed: {}", e)),
+            // Hosts that never answered were not retried into answering
+            Ok((ip, Ok(Some(reply)), tried)) => {
+                result.add_live_host(ip);
+                result.record_attempts(ip, tried);
+                result.record_rtt(ip, reply.rtt);
+                if let Some(ttl) = reply.ttl {
+                    result.record_ttl(ip, ttl);
+                }
+            }
+            Ok((ip, Ok(None), _)) => result.add_not_alive_host(ip),
+            Ok((ip, Err(e), _)) => result.add_error(ip, e),
+            Err(e) => result.add_error(IpAddr::V4(Ipv4Addr::UNSPECIFIED), format!("Task failed: {}", e)),
         }
     }
+    config.report(ProgressEvent::Finished { phase: Phase::PingSweep });
 
-    Ok(result)
+    result
 }
 
 /// Function to parse a subnet in CIDR notation and return a list of IP addresses
@@ -160,4 +327,30 @@ pub fn parse_subnet(subnet: &str) -> Result<Vec<Ipv4Addr>, String> {
     }
 
     Ok(ips)
+}
+
+/// Like `parse_subnet`, but also accepts IPv6 CIDR ("fd00::/120"). IPv6
+/// networks wider than `MIN_IPV6_PREFIX` are refused rather than enumerated.
+pub fn parse_network(cidr: &str) -> Result<Vec<IpAddr>, String> {
+    let Some((base, prefix)) = cidr.split_once('/').filter(|(base, _)| base.contains(':')) else {
+        return Ok(parse_subnet(cidr)?.into_iter().map(IpAddr::V4).collect());
+    };
+    let base_ip: Ipv6Addr = base.parse().map_err(|_| "Invalid IP address.".to_string())?;
+    let prefix: u32 = prefix.parse().map_err(|_| "Invalid prefix.".to_string())?;
+
+    if prefix > 128 {
+        return Err("Invalid prefix.".to_string());
+    }
+    if prefix < MIN_IPV6_PREFIX {
+        return Err(format!(
+            "IPv6 prefix /{} is too large to sweep; use /{} or longer.",
+            prefix, MIN_IPV6_PREFIX
+        ));
+    }
+
+    let host_bits = 128 - prefix;
+    let network = u128::from(base_ip) & !((1u128 << host_bits) - 1);
+    Ok((0..1u128 << host_bits)
+        .map(|i| IpAddr::V6(Ipv6Addr::from(network + i)))
+        .collect())
 }
\ No newline at end of file
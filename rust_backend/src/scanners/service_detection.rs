@@ -1,11 +1,30 @@
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::time::Duration;
+use crate::detect_legacy::LegacyService;
+use crate::detect_tls::CertificateInfo;
+use crate::utils::banner_parse;
+use crate::utils::evidence::{Attribute, Evidence, Source};
+use crate::utils::happy_eyeballs::{self, AddressFamily};
+use crate::utils::ratelimit;
+use crate::utils::progress::{Phase, ProgressEvent};
+use crate::utils::scan_config::ScanConfig;
+use crate::utils::sockets;
+use crate::utils::unknowns::{UnknownCorpus, UnknownSample};
+use futures::future::BoxFuture;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 // use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::io::{AsyncReadExt};
 use tokio::net::TcpStream;
+use tokio::sync::mpsc;
 // use tokio_native_tls::TlsConnector;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Serialized by its `--protocols` name, e.g. "http-proxy"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Protocol {
     Ssh,
     Ftp,
@@ -16,6 +35,252 @@ pub enum Protocol {
     Pop3,
     Imap,
     Telnet,
+    Smb,
+    Snmp,
+    Tftp,
+    Echo,
+    Daytime,
+    Chargen,
+    Finger,
+    Rexec,
+    Rlogin,
+    Rsh,
+    Sip,
+    Rtsp,
+    Onvif,
+    RemoteAdmin,
+    Socks,
+    HttpProxy,
+    Vpn,
+    Elasticsearch,
+    Mongodb,
+    Couchdb,
+    Mysql,
+    Postgres,
+    Redis,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 32] = [
+        Protocol::Ssh,
+        Protocol::Ftp,
+        Protocol::Smtp,
+        Protocol::Http,
+        Protocol::Https,
+        Protocol::Dns,
+        Protocol::Pop3,
+        Protocol::Imap,
+        Protocol::Telnet,
+        Protocol::Smb,
+        Protocol::Snmp,
+        Protocol::Tftp,
+        Protocol::Echo,
+        Protocol::Daytime,
+        Protocol::Chargen,
+        Protocol::Finger,
+        Protocol::Rexec,
+        Protocol::Rlogin,
+        Protocol::Rsh,
+        Protocol::Sip,
+        Protocol::Rtsp,
+        Protocol::Onvif,
+        Protocol::RemoteAdmin,
+        Protocol::Socks,
+        Protocol::HttpProxy,
+        Protocol::Vpn,
+        Protocol::Elasticsearch,
+        Protocol::Mongodb,
+        Protocol::Couchdb,
+        Protocol::Mysql,
+        Protocol::Postgres,
+        Protocol::Redis,
+    ];
+
+    /// Stable name, as given to `--protocols` and written to reports.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Protocol::Ssh => "ssh",
+            Protocol::Ftp => "ftp",
+            Protocol::Smtp => "smtp",
+            Protocol::Http => "http",
+            Protocol::Https => "https",
+            Protocol::Dns => "dns",
+            Protocol::Pop3 => "pop3",
+            Protocol::Imap => "imap",
+            Protocol::Telnet => "telnet",
+            Protocol::Smb => "smb",
+            Protocol::Snmp => "snmp",
+            Protocol::Tftp => "tftp",
+            Protocol::Echo => "echo",
+            Protocol::Daytime => "daytime",
+            Protocol::Chargen => "chargen",
+            Protocol::Finger => "finger",
+            Protocol::Rexec => "rexec",
+            Protocol::Rlogin => "rlogin",
+            Protocol::Rsh => "rsh",
+            Protocol::Sip => "sip",
+            Protocol::Rtsp => "rtsp",
+            Protocol::Onvif => "onvif",
+            Protocol::RemoteAdmin => "remote-admin",
+            Protocol::Socks => "socks",
+            Protocol::HttpProxy => "http-proxy",
+            Protocol::Vpn => "vpn",
+            Protocol::Elasticsearch => "elasticsearch",
+            Protocol::Mongodb => "mongodb",
+            Protocol::Couchdb => "couchdb",
+            Protocol::Mysql => "mysql",
+            Protocol::Postgres => "postgres",
+            Protocol::Redis => "redis",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Protocol> {
+        Protocol::ALL.into_iter().find(|protocol| protocol.label() == label)
+    }
+
+    /// Well-known ports of the protocol, most common first. The built-in
+    /// detectors claim these ports under `ProtocolSelection::Auto`.
+    pub fn default_ports(&self) -> &'static [u16] {
+        use crate::{
+            detect_couchdb, detect_db, detect_elasticsearch, detect_imap, detect_mongodb,
+            detect_pop3, detect_proxy, detect_rtsp, detect_sip, detect_vpn,
+        };
+        use crate::scanners::weak_protocols;
+        match self {
+            Protocol::Ssh => &[22],
+            Protocol::Ftp => &[21],
+            Protocol::Smtp => &[25, 587],
+            Protocol::Http => &[80, 8080],
+            Protocol::Https => &[443, 8443],
+            Protocol::Dns => &[53],
+            Protocol::Pop3 => &[detect_pop3::POP3_PORT],
+            Protocol::Imap => &[detect_imap::IMAP_PORT],
+            Protocol::Telnet => weak_protocols::TELNET_PORTS,
+            Protocol::Smb => &[445, 139],
+            Protocol::Snmp => &[161],
+            Protocol::Tftp => &[69],
+            Protocol::Echo => &[7],
+            Protocol::Daytime => &[13],
+            Protocol::Chargen => &[19],
+            Protocol::Finger => &[79],
+            Protocol::Rexec => &[512],
+            Protocol::Rlogin => &[513],
+            Protocol::Rsh => &[514],
+            Protocol::Sip => &[detect_sip::SIP_PORT, detect_sip::SIPS_PORT],
+            Protocol::Rtsp => &[detect_rtsp::RTSP_PORT],
+            Protocol::Onvif => &[80, 8000],
+            Protocol::RemoteAdmin => &REMOTE_ADMIN_PORTS,
+            Protocol::Socks => detect_proxy::SOCKS_PORTS,
+            Protocol::HttpProxy => detect_proxy::HTTP_PROXY_PORTS,
+            Protocol::Vpn => &[
+                detect_vpn::IKE_PORT,
+                detect_vpn::OPENVPN_PORT,
+                detect_vpn::WIREGUARD_PORT,
+            ],
+            Protocol::Elasticsearch => &[detect_elasticsearch::ELASTICSEARCH_PORT],
+            Protocol::Mongodb => &[detect_mongodb::MONGODB_PORT],
+            Protocol::Couchdb => &[detect_couchdb::COUCHDB_PORT],
+            Protocol::Mysql => &[detect_db::MYSQL_PORT],
+            Protocol::Postgres => &[detect_db::POSTGRES_PORT],
+            Protocol::Redis => &[detect_db::REDIS_PORT],
+        }
+    }
+
+    /// Protocols whose well-known ports include `port`, in `ALL` order.
+    pub fn for_port(port: u16) -> &'static [Protocol] {
+        PORT_INDEX.get(&port).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// TCP and UDP discovery ports of every remote admin tool the detector knows
+static REMOTE_ADMIN_PORTS: Lazy<Vec<u16>> = Lazy::new(|| {
+    crate::detect_remote_admin::RemoteAdminTool::ALL
+        .iter()
+        .flat_map(|tool| tool.tcp_ports().iter().chain(tool.discovery_ports()).copied())
+        .collect()
+});
+
+/// `Protocol::default_ports` turned around
+static PORT_INDEX: Lazy<HashMap<u16, Vec<Protocol>>> = Lazy::new(|| {
+    let mut index: HashMap<u16, Vec<Protocol>> = HashMap::new();
+    for protocol in Protocol::ALL {
+        for &port in protocol.default_ports() {
+            index.entry(port).or_default().push(protocol);
+        }
+    }
+    index
+});
+
+/// Why a detector did not identify the service on a port. Serialized as
+/// {"kind": "timeout", "message": "..."}.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "kebab-case")]
+pub enum DetectError {
+    /// No detector is registered for the protocol
+    Unsupported,
+    /// The connection was refused or the host could not be reached
+    Connect(String),
+    /// The port stayed silent until the detector gave up
+    Timeout(String),
+    /// The port answered, but not as the protocol
+    Mismatch(String),
+}
+
+impl DetectError {
+    /// Rebuilds an error from its `kind()` and `message()`.
+    pub fn from_parts(kind: &str, message: &str) -> Option<Self> {
+        match kind {
+            "unsupported" => Some(DetectError::Unsupported),
+            "connect" => Some(DetectError::Connect(message.to_string())),
+            "timeout" => Some(DetectError::Timeout(message.to_string())),
+            "mismatch" => Some(DetectError::Mismatch(message.to_string())),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DetectError::Unsupported => "unsupported",
+            DetectError::Connect(_) => "connect",
+            DetectError::Timeout(_) => "timeout",
+            DetectError::Mismatch(_) => "mismatch",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            DetectError::Unsupported => "No detector registered",
+            DetectError::Connect(message)
+            | DetectError::Timeout(message)
+            | DetectError::Mismatch(message) => message,
+        }
+    }
+
+    /// What a detector that recognized no reply reports: `Timeout` if it gave
+    /// up waiting, `Mismatch` if the port answered something else.
+    pub fn unrecognized(timed_out: bool, message: &str) -> Self {
+        if timed_out {
+            DetectError::Timeout(message.to_string())
+        } else {
+            DetectError::Mismatch(message.to_string())
+        }
+    }
+
+    /// The same kind of error, reworded.
+    pub fn with_message(&self, message: String) -> Self {
+        match self {
+            DetectError::Unsupported => DetectError::Unsupported,
+            DetectError::Connect(_) => DetectError::Connect(message),
+            DetectError::Timeout(_) => DetectError::Timeout(message),
+            DetectError::Mismatch(_) => DetectError::Mismatch(message),
+        }
+    }
+}
+
+impl std::fmt::Display for DetectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
 }
 
 const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
@@ -25,8 +290,51 @@ const _SSH_CONNECTION_TIMEOUT: Duration = Duration::from_secs(9);
 pub struct ServiceDetectionResult {
     pub port: u16,
     pub service: Option<String>,
+    /// Every detector failure, joined for display
     pub error: Option<String>,
-    pub protocol_failures: Vec<String>,
+    /// Detectors that ran without identifying the service, in order, and why
+    pub protocol_failures: Vec<(Protocol, DetectError)>,
+    /// Address family that answered, when the target was a dual-stack hostname
+    pub family: Option<AddressFamily>,
+    /// Server certificate, when the service was detected over TLS
+    pub certificate: Option<CertificateInfo>,
+    /// Application protocol negotiated over TLS (ALPN), e.g. "h2"
+    pub alpn: Option<String>,
+    /// What identified the service
+    pub evidence: Vec<Evidence>,
+    /// Every detector that ran on the port, in order
+    pub attempts: Vec<ProbeAttempt>,
+    /// Server software the banner names, e.g. "OpenSSH"
+    pub product: Option<String>,
+    /// Version of `product`, e.g. "8.9p1"
+    pub version: Option<String>,
+    /// OS the banner names, e.g. "Linux (Ubuntu)"
+    pub os_hint: Option<String>,
+    /// "tcp" or "udp": what the service answered over
+    pub transport: &'static str,
+}
+
+/// One detector run against a port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeAttempt {
+    /// Detector name, e.g. "SSH"
+    pub protocol: String,
+    pub detected: bool,
+    /// Whether the detector gave up waiting rather than being refused
+    pub timed_out: bool,
+    pub elapsed: Duration,
+}
+
+impl ProbeAttempt {
+    fn new(protocol: &str, detection: &Detection, elapsed: Duration) -> Self {
+        let timed_out = matches!(detection.error, Some(DetectError::Timeout(_)));
+        Self {
+            protocol: protocol.to_string(),
+            detected: detection.detected,
+            timed_out,
+            elapsed,
+        }
+    }
 }
 
 impl ServiceDetectionResult {
@@ -34,145 +342,648 @@ impl ServiceDetectionResult {
         port: u16,
         service: Option<String>,
         error: Option<String>,
-        protocol_failures: Vec<String>,
+        protocol_failures: Vec<(Protocol, DetectError)>,
     ) -> Self {
         Self {
             port,
             service,
             error,
             protocol_failures,
+            family: None,
+            certificate: None,
+            alpn: None,
+            evidence: Vec::new(),
+            attempts: Vec::new(),
+            product: None,
+            version: None,
+            os_hint: None,
+            transport: "tcp",
+        }
+    }
+
+    /// "OpenSSH 8.9p1", or just the product when the banner gave no version.
+    pub fn software(&self) -> Option<String> {
+        let product = self.product.as_deref()?;
+        Some(match &self.version {
+            Some(version) => format!("{} {}", product, version),
+            None => product.to_string(),
+        })
+    }
+
+    /// Fills in the product, version and OS hint `banner` gives away.
+    pub fn with_banner_info(mut self, banner: Option<&str>) -> Self {
+        if let Some(banner) = banner {
+            let info = banner_parse::parse(banner);
+            self.product = info.product;
+            self.version = info.version;
+            self.os_hint = info.os_hint;
+        }
+        self
+    }
+
+    /// Whether a detector or banner identified the service.
+    pub fn is_detected(&self) -> bool {
+        self.service
+            .as_deref()
+            .is_some_and(|service| service != "Unknown Service")
+    }
+
+    /// Whether the port answered: identified, or spoke some other protocol.
+    pub fn is_open(&self) -> bool {
+        self.is_detected()
+            || self
+                .protocol_failures
+                .iter()
+                .any(|(_, error)| matches!(error, DetectError::Mismatch(_)))
+    }
+
+    /// Time every detector spent on the port.
+    pub fn elapsed(&self) -> Duration {
+        self.attempts.iter().map(|attempt| attempt.elapsed).sum()
+    }
+
+    fn with_attempts(mut self, attempts: Vec<ProbeAttempt>) -> Self {
+        self.attempts = attempts;
+        self
+    }
+
+    /// Records that `source` identified the service on this port.
+    fn identified_by(mut self, source: Source) -> Self {
+        if let Some(service) = &self.service {
+            self.evidence
+                .push(Evidence::new(Attribute::Service, service, source).on_port(self.port));
+        }
+        self
+    }
+}
+
+/// Unified outcome of one protocol detector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    pub detected: bool,
+    /// What to report for the port when `detected`, e.g. "SIP over UDP (Asterisk)"
+    pub summary: String,
+    pub error: Option<DetectError>,
+    pub certificate: Option<CertificateInfo>,
+    pub alpn: Option<String>,
+    /// Greeting the service sent, for detectors that read one
+    pub banner: Option<String>,
+    /// "tcp" or "udp", for detectors that try both; otherwise the one the
+    /// detector speaks
+    pub transport: Option<&'static str>,
+}
+
+impl Detection {
+    pub fn new(detected: bool, summary: String, error: Option<DetectError>) -> Self {
+        Self {
+            detected,
+            summary,
+            error,
+            certificate: None,
+            alpn: None,
+            banner: None,
+            transport: None,
+        }
+    }
+
+    /// Records which transport the service answered over.
+    pub fn over(mut self, transport: &'static str) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Attaches what a TLS handshake revealed.
+    pub fn with_tls(mut self, certificate: Option<CertificateInfo>, alpn: Option<String>) -> Self {
+        self.certificate = certificate;
+        self.alpn = alpn;
+        self
+    }
+
+    pub fn with_banner(mut self, banner: Option<String>) -> Self {
+        self.banner = banner.map(|banner| banner.trim().to_string());
+        self
+    }
+
+    pub fn failed(error: DetectError) -> Self {
+        Self::new(false, String::new(), Some(error))
+    }
+}
+
+/// A protocol probe that `detect_service` can run against a port
+pub trait ServiceDetector: Send + Sync {
+    /// Label used in failure lists and messages, e.g. "SSH"
+    fn name(&self) -> &str;
+
+    /// Ports the protocol usually listens on
+    fn default_ports(&self) -> &[u16];
+
+    /// Whether the probe speaks UDP, so a TCP scan says nothing about its ports
+    fn over_udp(&self) -> bool {
+        false
+    }
+
+    fn detect(&self, ip: IpAddr, port: u16) -> BoxFuture<'static, Detection>;
+}
+
+type Probe = dyn Fn(IpAddr, u16) -> BoxFuture<'static, Detection> + Send + Sync;
+
+/// A detector made from a name, its usual ports and an async probe function
+pub struct FnDetector {
+    name: String,
+    ports: Vec<u16>,
+    udp: bool,
+    probe: Box<Probe>,
+}
+
+impl FnDetector {
+    pub fn new<F, Fut>(name: &str, ports: &[u16], probe: F) -> Self
+    where
+        F: Fn(IpAddr, u16) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Detection> + Send + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            ports: ports.to_vec(),
+            udp: false,
+            probe: Box::new(move |ip, port| Box::pin(probe(ip, port))),
         }
     }
+
+    /// Marks the probe as speaking UDP.
+    pub fn over_udp(mut self) -> Self {
+        self.udp = true;
+        self
+    }
+}
+
+impl ServiceDetector for FnDetector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn default_ports(&self) -> &[u16] {
+        &self.ports
+    }
+
+    fn over_udp(&self) -> bool {
+        self.udp
+    }
+
+    fn detect(&self, ip: IpAddr, port: u16) -> BoxFuture<'static, Detection> {
+        (self.probe)(ip, port)
+    }
+}
+
+/// Detectors by protocol; `detect_service` only dispatches through this
+#[derive(Clone, Default)]
+pub struct DetectorRegistry {
+    detectors: HashMap<Protocol, Arc<dyn ServiceDetector>>,
+}
+
+impl DetectorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry holding every detector that ships with netscan.
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        register_builtin(&mut registry);
+        registry
+    }
+
+    /// Adds or replaces the detector for `protocol`.
+    pub fn register(&mut self, protocol: Protocol, detector: impl ServiceDetector + 'static) {
+        self.detectors.insert(protocol, Arc::new(detector));
+    }
+
+    pub fn get(&self, protocol: Protocol) -> Option<Arc<dyn ServiceDetector>> {
+        self.detectors.get(&protocol).cloned()
+    }
+
+    pub fn contains(&self, protocol: Protocol) -> bool {
+        self.detectors.contains_key(&protocol)
+    }
+
+    /// Protocols whose detector usually listens on `port`, by detector name.
+    pub fn protocols_for_port(&self, port: u16) -> Vec<Protocol> {
+        let mut matches: Vec<(&str, Protocol)> = self
+            .detectors
+            .iter()
+            .filter(|(_, detector)| detector.default_ports().contains(&port))
+            .map(|(protocol, detector)| (detector.name(), *protocol))
+            .collect();
+        matches.sort_by(|a, b| a.0.cmp(b.0));
+        matches.into_iter().map(|(_, protocol)| protocol).collect()
+    }
+}
+
+/// Which detectors run on each port
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ProtocolSelection {
+    /// The detectors whose usual ports include the port; a port no detector
+    /// claims only gets the generic banner grab
+    #[default]
+    Auto,
+    /// These detectors on every port, in order (`--protocols`)
+    Only(Vec<Protocol>),
+}
+
+impl ProtocolSelection {
+    pub fn for_port(&self, registry: &DetectorRegistry, port: u16) -> Vec<Protocol> {
+        match self {
+            ProtocolSelection::Auto => registry.protocols_for_port(port),
+            ProtocolSelection::Only(protocols) => protocols.clone(),
+        }
+    }
+
+    /// `for_port` narrowed to the detectors that speak UDP.
+    pub fn udp_for_port(&self, registry: &DetectorRegistry, port: u16) -> Vec<Protocol> {
+        self.for_port(registry, port)
+            .into_iter()
+            .filter(|protocol| registry.get(*protocol).is_some_and(|d| d.over_udp()))
+            .collect()
+    }
+}
+
+static REGISTRY: Lazy<RwLock<DetectorRegistry>> =
+    Lazy::new(|| RwLock::new(DetectorRegistry::with_builtin()));
+
+/// Adds or replaces a detector in the registry `detect_service` uses.
+pub fn register_detector(protocol: Protocol, detector: impl ServiceDetector + 'static) {
+    REGISTRY.write().unwrap().register(protocol, detector);
+}
+
+/// Snapshot of the registry `detect_service` uses.
+pub fn registry() -> DetectorRegistry {
+    REGISTRY.read().unwrap().clone()
+}
+
+fn register_builtin(registry: &mut DetectorRegistry) {
+    use crate::{
+        detect_couchdb, detect_db, detect_dns, detect_elasticsearch, detect_ftp, detect_http,
+        detect_imap, detect_legacy, detect_mongodb, detect_onvif, detect_pop3, detect_proxy,
+        detect_remote_admin, detect_rtsp, detect_sip, detect_smb, detect_smtp, detect_snmp,
+        detect_ssh, detect_telnet, detect_tftp, detect_tls, detect_vpn,
+    };
+    use crate::detect_remote_admin::RemoteAdminTool;
+    use crate::detect_vpn::VpnKind;
+
+    registry.register(
+        Protocol::Ssh,
+        FnDetector::new("SSH", Protocol::Ssh.default_ports(), |ip, port| async move {
+            detect_ssh::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Ftp,
+        FnDetector::new("FTP", Protocol::Ftp.default_ports(), |ip, port| async move {
+            detect_ftp::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Smtp,
+        FnDetector::new("SMTP", Protocol::Smtp.default_ports(), |ip, port| async move {
+            detect_smtp::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Http,
+        FnDetector::new("HTTP", Protocol::Http.default_ports(), |ip, port| async move {
+            detect_http::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Https,
+        FnDetector::new("HTTPS", Protocol::Https.default_ports(), |ip, port| async move {
+            detect_tls::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Dns,
+        FnDetector::new("DNS", Protocol::Dns.default_ports(), |ip, port| async move {
+            detect_dns::detect(ip, port).await.into()
+        })
+        .over_udp(),
+    );
+    registry.register(
+        Protocol::Pop3,
+        FnDetector::new("POP3", Protocol::Pop3.default_ports(), |ip, port| async move {
+            detect_pop3::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Imap,
+        FnDetector::new("IMAP", Protocol::Imap.default_ports(), |ip, port| async move {
+            detect_imap::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Telnet,
+        FnDetector::new("Telnet", Protocol::Telnet.default_ports(), |ip, port| async move {
+            detect_telnet::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Smb,
+        FnDetector::new("SMB", Protocol::Smb.default_ports(), |ip, port| async move {
+            detect_smb::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Snmp,
+        FnDetector::new("SNMP", Protocol::Snmp.default_ports(), |ip, port| async move {
+            detect_snmp::detect(ip, port, detect_snmp::DEFAULT_COMMUNITIES)
+                .await
+                .into()
+        })
+        .over_udp(),
+    );
+    registry.register(
+        Protocol::Tftp,
+        FnDetector::new("TFTP", Protocol::Tftp.default_ports(), |ip, port| async move {
+            detect_tftp::detect(ip, port).await.into()
+        })
+        .over_udp(),
+    );
+    registry.register(
+        Protocol::Sip,
+        FnDetector::new(
+            "SIP",
+            Protocol::Sip.default_ports(),
+            |ip, port| async move { detect_sip::detect(ip, port).await.into() },
+        )
+        .over_udp(),
+    );
+    registry.register(
+        Protocol::Rtsp,
+        FnDetector::new("RTSP", Protocol::Rtsp.default_ports(), |ip, port| async move {
+            detect_rtsp::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Onvif,
+        FnDetector::new("ONVIF", Protocol::Onvif.default_ports(), |ip, port| async move {
+            detect_onvif::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::RemoteAdmin,
+        FnDetector::new("Remote admin", Protocol::RemoteAdmin.default_ports(), |ip, port| async move {
+            // TeamViewer's 5938 is both a TCP and a discovery port
+            let tcp = detect_remote_admin::detect(ip, port).await;
+            match (tcp, RemoteAdminTool::from_discovery_port(port)) {
+                (Some(remote), _) if remote.error.is_none() => Detection::from(remote).over("tcp"),
+                (_, Some(tool)) => {
+                    Detection::from(detect_remote_admin::discover(ip, tool, port).await).over("udp")
+                }
+                (Some(remote), None) => Detection::from(remote).over("tcp"),
+                (None, None) => Detection::failed(DetectError::Mismatch(
+                    "Not a known remote admin port".to_string(),
+                )),
+            }
+        })
+        .over_udp(),
+    );
+    registry.register(
+        Protocol::Socks,
+        FnDetector::new("SOCKS", Protocol::Socks.default_ports(), |ip, port| async move {
+            detect_proxy::detect_socks(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::HttpProxy,
+        FnDetector::new(
+            "HTTP proxy",
+            Protocol::HttpProxy.default_ports(),
+            |ip, port| async move { detect_proxy::detect_http_proxy(ip, port).await.into() },
+        ),
+    );
+    registry.register(
+        Protocol::Vpn,
+        FnDetector::new(
+            "VPN",
+            Protocol::Vpn.default_ports(),
+            |ip, port| async move {
+                // Off the well-known ports every VPN probe is tried in turn
+                let kinds = match VpnKind::from_port(port) {
+                    Some(kind) => vec![kind],
+                    None => vec![VpnKind::Ike, VpnKind::OpenVpn, VpnKind::WireGuard],
+                };
+                let mut errors = Vec::new();
+                for kind in kinds {
+                    let vpn = detect_vpn::detect(ip, port, kind).await;
+                    if vpn.detected {
+                        return vpn.into();
+                    }
+                    errors.push(vpn.error.unwrap_or_else(|| {
+                        DetectError::Mismatch("VPN detection failed".to_string())
+                    }));
+                }
+                // A probe that drew an answer says more than ones that timed out
+                let messages: Vec<&str> = errors.iter().map(DetectError::message).collect();
+                let error = errors
+                    .iter()
+                    .find(|error| matches!(error, DetectError::Mismatch(_)))
+                    .unwrap_or(&errors[0]);
+                Detection::failed(error.with_message(messages.join(" | ")))
+            },
+        )
+        .over_udp(),
+    );
+    registry.register(
+        Protocol::Elasticsearch,
+        FnDetector::new(
+            "Elasticsearch",
+            Protocol::Elasticsearch.default_ports(),
+            |ip, port| async move { detect_elasticsearch::detect(ip, port).await.into() },
+        ),
+    );
+    registry.register(
+        Protocol::Mongodb,
+        FnDetector::new("MongoDB", Protocol::Mongodb.default_ports(), |ip, port| async move {
+            detect_mongodb::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Couchdb,
+        FnDetector::new("CouchDB", Protocol::Couchdb.default_ports(), |ip, port| async move {
+            detect_couchdb::detect(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Mysql,
+        FnDetector::new("MySQL", Protocol::Mysql.default_ports(), |ip, port| async move {
+            detect_db::detect_mysql(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Postgres,
+        FnDetector::new("PostgreSQL", Protocol::Postgres.default_ports(), |ip, port| async move {
+            detect_db::detect_postgres(ip, port).await.into()
+        }),
+    );
+    registry.register(
+        Protocol::Redis,
+        FnDetector::new("Redis", Protocol::Redis.default_ports(), |ip, port| async move {
+            detect_db::detect_redis(ip, port).await.into()
+        }),
+    );
+    for (protocol, service) in [
+        (Protocol::Echo, LegacyService::Echo),
+        (Protocol::Daytime, LegacyService::Daytime),
+        (Protocol::Chargen, LegacyService::Chargen),
+        (Protocol::Finger, LegacyService::Finger),
+        (Protocol::Rexec, LegacyService::Rexec),
+        (Protocol::Rlogin, LegacyService::Rlogin),
+        (Protocol::Rsh, LegacyService::Rsh),
+    ] {
+        registry.register(
+            protocol,
+            FnDetector::new(
+                &service.label().to_uppercase(),
+                protocol.default_ports(),
+                move |ip, port| async move { detect_legacy::detect(ip, port, service).await.into() },
+            ),
+        );
+    }
 }
 
 pub async fn detect_service(
-    ip: Ipv4Addr,
+    ip: IpAddr,
     port: u16,
     protocols: &[Protocol],
 ) -> ServiceDetectionResult {
-    let addr = SocketAddr::new(IpAddr::V4(ip), port);
+    detect_service_with(&registry(), ip, port, protocols, &ScanConfig::default()).await
+}
 
+/// What the detectors learned about a port none of them identified
+struct Undetected {
+    errors: Vec<String>,
+    protocol_failures: Vec<(Protocol, DetectError)>,
+    attempts: Vec<ProbeAttempt>,
+}
+
+/// Runs the detectors of `protocols` in order until one identifies the service.
+async fn run_detectors(
+    registry: &DetectorRegistry,
+    ip: IpAddr,
+    port: u16,
+    protocols: &[Protocol],
+) -> Result<ServiceDetectionResult, Undetected> {
     let mut errors = Vec::new();
     let mut protocol_failures = Vec::new();
+    let mut attempts = Vec::new();
 
-    println!(
-        "DEBUG: detect_service called for port {} with protocols {:?}",
-        port, protocols
-    );
+    tracing::debug!(?protocols, "detecting service");
 
     for proto in protocols {
-        match proto {
-            Protocol::Ssh => {
-                let ssh = crate::detect_ssh::detect(ip, port).await;
-                if ssh.detected {
-                    return ServiceDetectionResult::new(
-                        port,
-                        Some("SSH".to_string()),
-                        None,
-                        protocol_failures,
-                    );
-                }
-                errors.push(
-                    ssh.error
-                        .unwrap_or_else(|| "SSH detection failed".to_string()),
-                );
-                protocol_failures.push("SSH".to_string());
-            }
-            Protocol::Http => {
-                let http = crate::detect_http::detect(ip, port).await;
-                if http.detected {
-                    return ServiceDetectionResult::new(
-                        port,
-                        Some("HTTP".to_string()),
-                        None,
-                        protocol_failures,
-                    );
-                }
-                errors.push(
-                    http.error
-                        .unwrap_or_else(|| "HTTP detection failed".to_string()),
-                );
-                protocol_failures.push("HTTP".to_string());
-            }
-            Protocol::Dns => {
-                let dns = crate::detect_dns::detect(ip, port).await;
-                if dns.detected {
-                    return ServiceDetectionResult::new(
-                        port,
-                        Some("DNS".to_string()),
-                        None,
-                        protocol_failures,
-                    );
-                }
-                errors.push(
-                    dns.error
-                        .unwrap_or_else(|| "DNS detection failed".to_string()),
-                );
-                protocol_failures.push("DNS".to_string());
-            }
-
-            Protocol::Smtp => {
-                let smtp = crate::detect_smtp::detect(ip, port).await;
-                if smtp.detected {
-                    return ServiceDetectionResult::new(
-                        port,
-                        Some("SMTP".to_string()),
-                        None,
-                        protocol_failures,
-                    );
-                }
-                errors.push(
-                    smtp.error
-                        .unwrap_or_else(|| "SMTP detection failed".to_string()),
-                );
-                protocol_failures.push("SMTP".to_string());
-            }
-            Protocol::Ftp => {
-                let ftp = crate::detect_ftp::detect(ip, port).await;
-                if ftp.detected {
-                    return ServiceDetectionResult::new(
-                        port,
-                        Some("FTP".to_string()),
-                        None,
-                        protocol_failures,
-                    );
-                }
-                errors.push(
-                    ftp.error
-                        .unwrap_or_else(|| "FTP detection failed".to_string()),
-                );
-                protocol_failures.push("FTP".to_string());
-            }
-
-            _ => {
-                protocol_failures.push(format!("{:?}", proto));
-            }
+        ratelimit::acquire(ratelimit::TCP_SYN_BYTES).await;
+        let Some(detector) = registry.get(*proto) else {
+            protocol_failures.push((*proto, DetectError::Unsupported));
+            continue;
+        };
+        let started = Instant::now();
+        let detection = detector.detect(ip, port).await;
+        tracing::debug!(
+            detector = detector.name(),
+            detected = detection.detected,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            error = detection.error.as_ref().map(DetectError::message),
+            "probe finished"
+        );
+        attempts.push(ProbeAttempt::new(detector.name(), &detection, started.elapsed()));
+        if detection.detected {
+            let mut result = ServiceDetectionResult::new(
+                port,
+                Some(detection.summary),
+                None,
+                protocol_failures,
+            )
+            .identified_by(Source::Probe(detector.name().to_string()))
+            .with_attempts(attempts)
+            .with_banner_info(detection.banner.as_deref());
+            result.certificate = detection.certificate;
+            result.alpn = detection.alpn;
+            result.transport = detection.transport.unwrap_or(if detector.over_udp() {
+                "udp"
+            } else {
+                "tcp"
+            });
+            return Ok(result);
         }
+        let error = detection.error.unwrap_or_else(|| {
+            DetectError::Mismatch(format!("{} detection failed", detector.name()))
+        });
+        errors.push(error.to_string());
+        protocol_failures.push((*proto, error));
     }
+    Err(Undetected {
+        errors,
+        protocol_failures,
+        attempts,
+    })
+}
+
+/// `detect_service` with the detectors of `registry`; `config.timeout` bounds
+/// the connect of the generic banner grab.
+#[tracing::instrument(name = "detect_service", skip_all, fields(%ip, port = port))]
+pub async fn detect_service_with(
+    registry: &DetectorRegistry,
+    ip: IpAddr,
+    port: u16,
+    protocols: &[Protocol],
+    config: &ScanConfig,
+) -> ServiceDetectionResult {
+    let addr = sockets::socket_addr(ip, port);
+    let Undetected {
+        errors,
+        protocol_failures,
+        attempts,
+    } = match run_detectors(registry, ip, port, protocols).await {
+        Ok(result) => return result,
+        Err(undetected) => undetected,
+    };
 
     // --- Generic Banner Detection (for unknown services) ---
+    ratelimit::acquire(ratelimit::TCP_SYN_BYTES).await;
     if let Ok(Ok(mut stream)) =
-        tokio::time::timeout(CONNECTION_TIMEOUT, TcpStream::connect(addr)).await
+        tokio::time::timeout(config.timeout_for(ip, CONNECTION_TIMEOUT), TcpStream::connect(addr)).await
     {
         let mut buf = vec![0u8; 256];
         if let Ok(Ok(n)) = tokio::time::timeout(Duration::from_secs(2), stream.read(&mut buf)).await
         {
             let banner = String::from_utf8_lossy(&buf[..n]);
+            tracing::trace!(bytes = n, banner = %banner.escape_debug(), "banner received");
             if banner.starts_with("SSH-") {
                 return ServiceDetectionResult::new(
                     port,
                     Some("SSH".to_string()),
                     None,
                     protocol_failures,
-                );
+                )
+                .identified_by(Source::Banner(
+                    banner.lines().next().unwrap_or_default().trim().to_string(),
+                ))
+                .with_attempts(attempts)
+                .with_banner_info(Some(&banner));
             }
             if !banner.trim().is_empty() {
+                if let Some(dir) = &config.collect_unknowns {
+                    let sample = UnknownSample::anonymized(port, &[], &buf[..n], ip, chrono::Utc::now());
+                    if let Err(e) = UnknownCorpus::new(dir).save(&sample) {
+                        tracing::warn!("{}", e);
+                    }
+                }
                 return ServiceDetectionResult::new(
                     port,
                     Some(format!("Banner: {}", banner.trim())),
                     None,
                     protocol_failures,
-                );
+                )
+                .identified_by(Source::Banner(banner.trim().to_string()))
+                .with_attempts(attempts)
+                .with_banner_info(Some(&banner));
             }
         }
     }
@@ -188,34 +999,303 @@ pub async fn detect_service(
         error,
         protocol_failures,
     )
+    .with_attempts(attempts)
+}
+
+/// Detect the service on a hostname target. A and AAAA records are raced
+/// (RFC 8305) and detection runs against whichever address answered first.
+pub async fn detect_service_host(
+    host: &str,
+    port: u16,
+    selection: &ProtocolSelection,
+    config: &ScanConfig,
+) -> ServiceDetectionResult {
+    ratelimit::acquire(ratelimit::TCP_SYN_BYTES).await;
+    let conn = match happy_eyeballs::connect_host(host, port, config.timeout_or(CONNECTION_TIMEOUT)).await {
+        Ok(conn) => conn,
+        Err(e) => {
+            return ServiceDetectionResult::new(
+                port,
+                Some("Unknown Service".to_string()),
+                Some(e),
+                Vec::new(),
+            );
+        }
+    };
+
+    drop(conn.stream);
+    let registry = registry();
+    let protocols = selection.for_port(&registry, port);
+    let mut result = detect_service_with(&registry, conn.addr.ip(), port, &protocols, config).await;
+    result.family = Some(conn.family);
+    result
+}
+
+/// `detect_service_host` on every port of `ports` under the
+/// `config.concurrency` budget (64 by default), in the order of `ports`.
+pub async fn service_scan_host(
+    host: &str,
+    ports: &[u16],
+    selection: &ProtocolSelection,
+    config: &ScanConfig,
+) -> Vec<ServiceDetectionResult> {
+    use futures::stream::{self, StreamExt};
+
+    stream::iter(ports)
+        .map(|&port| detect_service_host(host, port, selection, config))
+        .buffered(config.concurrency_or(64))
+        .collect()
+        .await
 }
 
 /// Scan only the user-supplied ports (no defaults, no merging).
 pub async fn service_scan(
-    ip: Ipv4Addr,
+    ip: IpAddr,
     user_ports: Option<Vec<u16>>,
-    protocols: &[Protocol],
+    selection: &ProtocolSelection,
+    config: &ScanConfig,
 ) -> Vec<ServiceDetectionResult> {
-    use futures::stream::{self, StreamExt};
-    use std::sync::Arc;
-    use tokio::sync::Semaphore;
-
-    let ports = user_ports.unwrap_or_default();
-    let semaphore = Arc::new(Semaphore::new(64)); // Limit to 64 concurrent scans
-
-    let results = stream::iter(ports.into_iter())
-        .map(|port| {
-            let ip = ip.clone();
-            let protocols = protocols.to_vec();
-            let semaphore = semaphore.clone();
-            async move {
-                let _permit = semaphore.acquire().await.unwrap();
-                detect_service(ip, port, &protocols).await
+    let endpoints: Vec<(IpAddr, u16)> = user_ports
+        .unwrap_or_default()
+        .into_iter()
+        .map(|port| (ip, port))
+        .collect();
+    service_scan_many(&endpoints, selection, config)
+        .await
+        .into_iter()
+        .next()
+        .map(|(_, results)| results)
+        .unwrap_or_default()
+}
+
+/// Detects the services on every `(ip, port)` of `endpoints` under one
+/// concurrency budget (`config.concurrency`, 64 by default), so a slow host
+/// does not hold up the others. Results are grouped by host, in the order
+/// hosts first appear in `endpoints`, each host's in the order of its ports.
+pub async fn service_scan_many(
+    endpoints: &[(IpAddr, u16)],
+    selection: &ProtocolSelection,
+    config: &ScanConfig,
+) -> Vec<(IpAddr, Vec<ServiceDetectionResult>)> {
+    let order: Vec<IpAddr> = endpoints.iter().map(|(ip, _)| *ip).collect();
+    collect_hosts(&order, service_scan_many_stream(endpoints, selection, config)).await
+}
+
+/// `service_scan_many`, sending each host's results as soon as its last port is done.
+pub fn service_scan_many_stream(
+    endpoints: &[(IpAddr, u16)],
+    selection: &ProtocolSelection,
+    config: &ScanConfig,
+) -> mpsc::Receiver<HostServices> {
+    let registry = registry();
+    let targets = endpoints
+        .iter()
+        .map(|&(ip, port)| Target {
+            ip,
+            port,
+            protocols: selection.for_port(&registry, port),
+            udp_only: false,
+        })
+        .collect();
+    scan_targets(registry, targets, config)
+}
+
+/// Service detection fed by a TCP scan (`--pipeline`). The selected detectors
+/// run on the ports `open` lists for each host; the UDP ones, whose ports a
+/// TCP scan says nothing about, also run on the other `ports` of every host,
+/// and those ports are only reported when a detector identified the service.
+pub async fn pipeline_scan(
+    hosts: &[IpAddr],
+    ports: &[u16],
+    open: &BTreeMap<IpAddr, Vec<u16>>,
+    selection: &ProtocolSelection,
+    config: &ScanConfig,
+) -> Vec<(IpAddr, Vec<ServiceDetectionResult>)> {
+    collect_hosts(hosts, pipeline_scan_stream(hosts, ports, open, selection, config)).await
+}
+
+/// `pipeline_scan`, sending each host's results as soon as its last port is done.
+pub fn pipeline_scan_stream(
+    hosts: &[IpAddr],
+    ports: &[u16],
+    open: &BTreeMap<IpAddr, Vec<u16>>,
+    selection: &ProtocolSelection,
+    config: &ScanConfig,
+) -> mpsc::Receiver<HostServices> {
+    let registry = registry();
+    let mut targets = Vec::new();
+    for &ip in hosts {
+        let host_open = open.get(&ip).map(Vec::as_slice).unwrap_or_default();
+        for &port in ports {
+            let (protocols, udp_only) = if host_open.contains(&port) {
+                (selection.for_port(&registry, port), false)
+            } else {
+                (selection.udp_for_port(&registry, port), true)
+            };
+            if !udp_only || !protocols.is_empty() {
+                targets.push(Target {
+                    ip,
+                    port,
+                    protocols,
+                    udp_only,
+                });
             }
+        }
+    }
+    scan_targets(registry, targets, config)
+}
+
+/// A port standing in for a well-known one, such as an emulated server's
+/// loopback listener
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StandIn {
+    pub port: u16,
+    /// The well-known port whose detectors run on `port`
+    pub as_port: u16,
+    /// Only the UDP detectors run, as on a port a TCP scan did not find open
+    pub udp: bool,
+}
+
+/// Service detection of `ip` through the same pipeline as a scan, on ports
+/// standing in for well-known ones: each gets what `selection` picks for the
+/// port it stands in for. Results are in the order of `stand_ins`, less the
+/// UDP ones no detector identified.
+pub async fn stand_in_scan(
+    registry: DetectorRegistry,
+    ip: IpAddr,
+    stand_ins: &[StandIn],
+    selection: &ProtocolSelection,
+    config: &ScanConfig,
+) -> Vec<ServiceDetectionResult> {
+    let targets = stand_ins
+        .iter()
+        .map(|stand_in| Target {
+            ip,
+            port: stand_in.port,
+            protocols: if stand_in.udp {
+                selection.udp_for_port(&registry, stand_in.as_port)
+            } else {
+                selection.for_port(&registry, stand_in.as_port)
+            },
+            udp_only: stand_in.udp,
         })
-        .buffer_unordered(64)
+        .collect();
+    collect_hosts(&[ip], scan_targets(registry, targets, config))
+        .await
+        .into_iter()
+        .flat_map(|(_, results)| results)
         .collect()
-        .await;
+}
 
-    results
+/// Detection results of one host, sent once its last port is done
+#[derive(Debug)]
+pub struct HostServices {
+    pub ip: IpAddr,
+    /// In the order of its ports
+    pub results: Vec<ServiceDetectionResult>,
+    /// From the host's first probe starting to its last one finishing
+    pub elapsed: Duration,
+}
+
+/// One port to detect the service on
+struct Target {
+    ip: IpAddr,
+    port: u16,
+    protocols: Vec<Protocol>,
+    /// Only UDP detectors run: no generic banner grab, and the port is
+    /// dropped from the results unless identified
+    udp_only: bool,
+}
+
+/// Progress of one host's ports
+struct HostProgress {
+    remaining: usize,
+    started: Option<Instant>,
+    results: Vec<(usize, ServiceDetectionResult)>,
+}
+
+/// Runs the detectors on every target under one concurrency budget and sends
+/// each host's results when its last target is done. Hosts none of whose
+/// ports are reported are not sent.
+fn scan_targets(
+    registry: DetectorRegistry,
+    targets: Vec<Target>,
+    config: &ScanConfig,
+) -> mpsc::Receiver<HostServices> {
+    use futures::stream::{self, StreamExt};
+
+    let (registry, config) = (Arc::new(registry), Arc::new(config.clone()));
+    let (tx, rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        let concurrency = config.concurrency_or(64);
+        config.report(ProgressEvent::Started { phase: Phase::ServiceScan, total: targets.len() });
+        let ips: Vec<IpAddr> = targets.iter().map(|target| target.ip).collect();
+        let mut hosts: HashMap<IpAddr, HostProgress> = HashMap::new();
+        for &ip in &ips {
+            hosts
+                .entry(ip)
+                .or_insert_with(|| HostProgress { remaining: 0, started: None, results: Vec::new() })
+                .remaining += 1;
+        }
+
+        let mut scans = stream::iter(targets.into_iter().enumerate())
+            .map(|(i, target)| {
+                let (registry, config) = (registry.clone(), config.clone());
+                async move {
+                    let started = Instant::now();
+                    let (ip, port) = (target.ip, target.port);
+                    let result = if target.udp_only {
+                        run_detectors(&registry, ip, port, &target.protocols).await.ok()
+                    } else {
+                        Some(detect_service_with(&registry, ip, port, &target.protocols, &config).await)
+                    };
+                    config.report(ProgressEvent::Probed { phase: Phase::ServiceScan, ip, port: Some(port) });
+                    (i, started, result)
+                }
+            })
+            .buffer_unordered(concurrency);
+        while let Some((i, started, result)) = scans.next().await {
+            let ip = ips[i];
+            let Some(host) = hosts.get_mut(&ip) else {
+                continue;
+            };
+            host.started = Some(host.started.map_or(started, |first| first.min(started)));
+            host.results.extend(result.map(|result| (i, result)));
+            host.remaining -= 1;
+            if host.remaining > 0 {
+                continue;
+            }
+            let Some(mut host) = hosts.remove(&ip).filter(|host| !host.results.is_empty()) else {
+                continue;
+            };
+            host.results.sort_by_key(|(i, _)| *i);
+            let results: Vec<ServiceDetectionResult> =
+                host.results.into_iter().map(|(_, result)| result).collect();
+            tracing::info!(
+                %ip,
+                ports = results.len(),
+                identified = results.iter().filter(|r| !r.evidence.is_empty()).count(),
+                "service detection of host finished"
+            );
+            let elapsed = host.started.map(|started| started.elapsed()).unwrap_or_default();
+            if tx.send(HostServices { ip, results, elapsed }).await.is_err() {
+                return; // nobody is listening any more
+            }
+        }
+        config.report(ProgressEvent::Finished { phase: Phase::ServiceScan });
+    });
+    rx
+}
+
+/// Every host a stream sends, in the order they first appear in `order`.
+async fn collect_hosts(
+    order: &[IpAddr],
+    mut hosts: mpsc::Receiver<HostServices>,
+) -> Vec<(IpAddr, Vec<ServiceDetectionResult>)> {
+    let mut grouped = Vec::new();
+    while let Some(host) = hosts.recv().await {
+        grouped.push(host);
+    }
+    grouped.sort_by_key(|host| order.iter().position(|ip| *ip == host.ip));
+    grouped.into_iter().map(|host| (host.ip, host.results)).collect()
 }
@@ -0,0 +1,359 @@
+use crate::scanners::tcpscan::{self, TcpScanResult};
+use crate::utils::errors::ErrorCategory;
+use crate::utils::progress::{Phase, ProgressEvent};
+use crate::utils::scan_config::ScanConfig;
+use crate::utils::sockets::local_bind_addr;
+use crate::utils::{ratelimit, routing};
+use futures::stream::{self, StreamExt};
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::tcp::{self, MutableTcpPacket, TcpFlags, TcpOption, TcpPacket};
+use pnet::transport::{
+    TransportChannelType, TransportProtocol, TransportReceiver, TransportSender, tcp_packet_iter,
+    transport_channel,
+};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, UdpSocket};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+const SYN_HEADER_LEN: usize = 24; // 20-byte header + MSS option
+const SYN_WINDOW: u16 = 1024;
+const SYN_MSS: u16 = 1460;
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2); // How long to wait after the last SYN of a round
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+const RETRIES: usize = 1; // Extra rounds for ports that have not answered yet
+const POLL_INTERVAL: Duration = Duration::from_millis(10); // How often a round checks for missing replies
+const MAX_CONCURRENT_HOSTS: usize = 64; // Each host holds a raw socket and two threads
+
+/// How a port answered a SYN
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SynReply {
+    /// SYN/ACK: something is listening
+    Open,
+    /// RST: reachable, nothing listening
+    Closed,
+}
+
+/// Builds a SYN segment from `src:src_port` to `dst:dst_port` with initial
+/// sequence number `seq`, checksummed for its pseudo-header.
+pub fn build_syn(
+    src: IpAddr,
+    dst: IpAddr,
+    src_port: u16,
+    dst_port: u16,
+    seq: u32,
+) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![0u8; SYN_HEADER_LEN];
+    let mut syn = MutableTcpPacket::new(&mut buffer).ok_or("Failed to create TCP packet")?;
+    syn.set_source(src_port);
+    syn.set_destination(dst_port);
+    syn.set_sequence(seq);
+    syn.set_data_offset((SYN_HEADER_LEN / 4) as u8);
+    syn.set_flags(TcpFlags::SYN);
+    syn.set_window(SYN_WINDOW);
+    syn.set_options(&[TcpOption::mss(SYN_MSS)]);
+    let checksum = match (src, dst) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) => tcp::ipv4_checksum(&syn.to_immutable(), &src, &dst),
+        (IpAddr::V6(src), IpAddr::V6(dst)) => tcp::ipv6_checksum(&syn.to_immutable(), &src, &dst),
+        _ => return Err(format!("Source {} cannot reach {}", src, dst)),
+    };
+    syn.set_checksum(checksum);
+    Ok(buffer)
+}
+
+/// Interprets a segment received in answer to a SYN sent from `src_port`
+/// with sequence number `seq`. Returns the probed port and its state, or
+/// `None` for unrelated traffic.
+pub fn classify_reply(reply: &TcpPacket, src_port: u16, seq: u32) -> Option<(u16, SynReply)> {
+    if reply.get_destination() != src_port || reply.get_acknowledgement() != seq.wrapping_add(1) {
+        return None;
+    }
+    let flags = reply.get_flags();
+    if flags & TcpFlags::RST != 0 {
+        Some((reply.get_source(), SynReply::Closed))
+    } else if flags & (TcpFlags::SYN | TcpFlags::ACK) == TcpFlags::SYN | TcpFlags::ACK {
+        Some((reply.get_source(), SynReply::Open))
+    } else {
+        None
+    }
+}
+
+/// Address the kernel would send from to reach `target`.
+pub fn route_source(target: IpAddr) -> Result<IpAddr, String> {
+    let socket = UdpSocket::bind(local_bind_addr(target))
+        .and_then(|socket| socket.connect((target, 9)).map(|_| socket))
+        .map_err(|e| format!("No route to {}: {}", target, e))?;
+    socket
+        .local_addr()
+        .map(|addr| addr.ip())
+        .map_err(|e| format!("No route to {}: {}", target, e))
+}
+
+fn open_channel(target: IpAddr) -> Result<(TransportSender, TransportReceiver), String> {
+    let protocol = match target {
+        IpAddr::V4(_) => TransportProtocol::Ipv4(IpNextHeaderProtocols::Tcp),
+        IpAddr::V6(_) => TransportProtocol::Ipv6(IpNextHeaderProtocols::Tcp),
+    };
+    transport_channel(4096, TransportChannelType::Layer4(protocol))
+        .map_err(|e| format!("Failed to open raw TCP socket (SYN scan needs root): {}", e))
+}
+
+/// SYN-scans the ports of one host and reports the ones that answered.
+/// Blocking: runs on its own thread.
+fn scan_host_blocking(
+    ip: IpAddr,
+    ports: Vec<u16>,
+    source: IpAddr,
+    config: ScanConfig,
+    runtime: tokio::runtime::Handle,
+) -> Result<BTreeMap<u16, SynReply>, String> {
+    let reply_timeout = config.timeout_or(REPLY_TIMEOUT);
+    let (mut tx, mut rx) = open_channel(ip)?;
+    // A fixed source port and ISN per host let replies be matched without
+    // keeping per-port state; the kernel answers SYN/ACKs with a RST for us
+    let src_port = 40_000 + (std::process::id() % 20_000) as u16;
+    let seq = (std::process::id() as u32).rotate_left(16) ^ u32::from(src_port);
+    let replies = Mutex::new(BTreeMap::new());
+    let done = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            let mut iter = tcp_packet_iter(&mut rx);
+            while !done.load(Ordering::Relaxed) {
+                let Ok(Some((reply, addr))) = iter.next_with_timeout(READ_TIMEOUT) else {
+                    continue; // read timeout
+                };
+                if addr != ip {
+                    continue;
+                }
+                if let Some((port, state)) = classify_reply(&reply, src_port, seq) {
+                    replies.lock().unwrap().entry(port).or_insert(state);
+                }
+            }
+        });
+
+        let sent: Result<(), String> = (0..=config.retries_or(RETRIES)).try_for_each(|_| {
+            let pending: Vec<u16> = {
+                let replies = replies.lock().unwrap();
+                ports
+                    .iter()
+                    .copied()
+                    .filter(|port| !replies.contains_key(port))
+                    .collect()
+            };
+            if pending.is_empty() {
+                return Ok(());
+            }
+            send_syns(&mut tx, source, ip, src_port, seq, &pending, &runtime)?;
+            await_replies(&replies, &pending, reply_timeout);
+            Ok(())
+        });
+        done.store(true, Ordering::Relaxed);
+        sent
+    })?;
+
+    Ok(replies.into_inner().unwrap())
+}
+
+/// Waits until every port in `ports` has answered, or `timeout` has passed
+/// without that, to let stragglers answer.
+fn await_replies(replies: &Mutex<BTreeMap<u16, SynReply>>, ports: &[u16], timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let answered = {
+            let replies = replies.lock().unwrap();
+            ports.iter().all(|port| replies.contains_key(port))
+        };
+        let now = Instant::now();
+        if answered || now >= deadline {
+            return;
+        }
+        std::thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// Sends one SYN per port in `ports`, paced by the rate limiter.
+fn send_syns(
+    tx: &mut TransportSender,
+    source: IpAddr,
+    ip: IpAddr,
+    src_port: u16,
+    seq: u32,
+    ports: &[u16],
+    runtime: &tokio::runtime::Handle,
+) -> Result<(), String> {
+    for &port in ports {
+        runtime.block_on(ratelimit::acquire(ratelimit::TCP_SYN_BYTES));
+        let syn = build_syn(source, ip, src_port, port, seq)?;
+        let packet = TcpPacket::new(&syn).ok_or("Failed to create TCP packet")?;
+        tx.send_to(packet, ip)
+            .map_err(|e| format!("Failed to send SYN to {}:{}: {}", ip, port, e))?;
+    }
+    Ok(())
+}
+
+/// SYN-scans one host. `None` means its replies would not reach us and it
+/// should get a connect scan instead.
+async fn scan_host(
+    ip: IpAddr,
+    ports: &[u16],
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+) -> Option<TcpScanResult> {
+    let mut result = TcpScanResult::new();
+    // a source of the other address family cannot reach this target
+    let source = match source_ip.filter(|source| source.is_ipv4() == ip.is_ipv4()) {
+        Some(source) => source,
+        None => match route_source(ip) {
+            Ok(source) => source,
+            Err(e) => {
+                result.add_error(ip, e);
+                return Some(result);
+            }
+        },
+    };
+    if let (IpAddr::V4(target), IpAddr::V4(source)) = (ip, source)
+        && let Some(mismatch) = routing::check_reverse_path(target, source)
+    {
+        tracing::warn!("{}; using a connect scan for {}.", mismatch, ip);
+        return None;
+    }
+    let runtime = tokio::runtime::Handle::current();
+    let replies = tokio::task::spawn_blocking({
+        let ports = ports.to_vec();
+        let config = config.clone();
+        move || scan_host_blocking(ip, ports, source, config, runtime)
+    })
+    .await
+    .map_err(|e| format!("SYN scan task failed: {}", e))
+    .and_then(|replies| replies);
+    let replies = match replies {
+        Ok(replies) => replies,
+        Err(e) => {
+            result.add_error(ip, e);
+            return Some(result);
+        }
+    };
+    if replies.is_empty() && !ports.is_empty() {
+        // A live host answers at least some SYNs with RSTs; total silence
+        // usually means the replies went to another interface
+        tracing::warn!(
+            "No SYN replies from {} via {}; replies may be taking another path, using a connect scan.",
+            ip, source
+        );
+        return None;
+    }
+    for &port in ports {
+        match replies.get(&port) {
+            Some(SynReply::Open) => result.add_open_port(ip, port),
+            Some(SynReply::Closed) => {
+                result.add_port_error(ip, port, ErrorCategory::Refused, "Reset (closed)")
+            }
+            None => result.add_port_error(
+                ip,
+                port,
+                ErrorCategory::Timeout,
+                "No response (filtered)",
+            ),
+        }
+    }
+    Some(result)
+}
+
+/// Half-open TCP scan: sends raw SYNs and reads SYN/ACK (open) or RST
+/// (closed); ports that never answer are reported as timeouts (filtered).
+/// Needs root; fails before sending anything if raw sockets are unavailable.
+/// Hosts behind an asymmetric route, or that answered no SYN at all, are
+/// rescanned with connect probes so dropped replies do not read as filtered.
+/// `config.timeout` is how long each round of SYNs waits for the ports that
+/// have not answered, and `config.retries` the number of extra rounds.
+///
+/// Returns at once; hosts are scanned concurrently and each one's result
+/// arrives on the receiver when it is done, the hosts rescanned with connect
+/// probes together at the end.
+pub fn syn_scan_stream(
+    live_hosts: &[IpAddr],
+    ports: impl IntoIterator<Item = u16>,
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+) -> Result<mpsc::Receiver<TcpScanResult>, String> {
+    // Probe for the privilege up front so the caller can fall back cleanly
+    for family in [IpAddr::from([0u8; 4]), IpAddr::from([0u16; 8])] {
+        if live_hosts.iter().any(|ip| ip.is_ipv4() == family.is_ipv4()) {
+            open_channel(family)?;
+        }
+    }
+
+    let ports: Vec<u16> = ports.into_iter().collect();
+    let live_hosts = live_hosts.to_vec();
+    let config = config.clone();
+    let (tx, rx) = mpsc::channel(live_hosts.len().max(1));
+    tokio::spawn(async move {
+        config.report(ProgressEvent::Started {
+            phase: Phase::TcpScan,
+            total: live_hosts.len() * ports.len(),
+        });
+        let mut connect_hosts = Vec::new();
+        let mut scans = stream::iter(live_hosts)
+            .map(|ip| {
+                let (ports, config) = (&ports, &config);
+                async move { (ip, scan_host(ip, ports, source_ip, config).await) }
+            })
+            .buffer_unordered(MAX_CONCURRENT_HOSTS);
+        while let Some((ip, scanned)) = scans.next().await {
+            // Every SYN went out, whether or not a connect scan follows
+            for &port in &ports {
+                config.report(ProgressEvent::Probed { phase: Phase::TcpScan, ip, port: Some(port) });
+            }
+            match scanned {
+                Some(result) => {
+                    if tx.send(result).await.is_err() {
+                        return; // nobody is listening any more
+                    }
+                }
+                None => connect_hosts.push(ip),
+            }
+        }
+        drop(scans);
+        config.report(ProgressEvent::Finished { phase: Phase::TcpScan });
+        if !connect_hosts.is_empty() {
+            let result = tcpscan::tcp_scan_from(&connect_hosts, ports, source_ip, &config).await;
+            let _ = tx.send(result).await;
+        }
+    });
+    Ok(rx)
+}
+
+/// `syn_scan_stream`, waiting for every host.
+pub async fn syn_scan(
+    live_hosts: &[IpAddr],
+    ports: impl IntoIterator<Item = u16>,
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+) -> Result<TcpScanResult, String> {
+    let mut hosts = syn_scan_stream(live_hosts, ports, source_ip, config)?;
+    let mut result = TcpScanResult::new();
+    while let Some(host) = hosts.recv().await {
+        result.merge(host);
+    }
+    Ok(result)
+}
+
+/// SYN scan when raw sockets are available, otherwise a connect scan.
+pub async fn syn_scan_or_connect(
+    live_hosts: &[IpAddr],
+    ports: impl IntoIterator<Item = u16>,
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+) -> TcpScanResult {
+    let ports: Vec<u16> = ports.into_iter().collect();
+    match syn_scan(live_hosts, ports.clone(), source_ip, config).await {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::warn!("{}; falling back to a connect scan.", e);
+            tcpscan::tcp_scan_from(live_hosts, ports, source_ip, config).await
+        }
+    }
+}
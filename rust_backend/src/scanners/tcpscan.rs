@@ -1,89 +1,437 @@
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use crate::utils::happy_eyeballs::{self, AddressFamily};
+use crate::utils::anomaly::{self, SuspectHost};
+use crate::utils::compact::{OpenPorts, PortState, PortStates, ProbeErrors, ProbeTable};
+use crate::utils::errors::{self, ErrorCategory};
+use crate::utils::progress::{Phase, ProgressEvent};
+use crate::utils::scan_config::ScanConfig;
+use crate::utils::sockets::{SocketFactory, SystemSockets};
+use crate::utils::{prettyprint, ratelimit, rdns, redact, warnings};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
-use tokio::net::TcpStream;
-use tokio::sync::Semaphore;
+use futures::stream::{self, StreamExt};
+use tokio::sync::{Semaphore, mpsc};
+use tracing::Instrument;
 use std::time::Duration;
 
-const MAX_CONCURRENT_TASKS: usize = 64; // Limit the number of concurrent tasks
+pub const MAX_CONCURRENT_TASKS: usize = 64; // Limit the number of concurrent tasks
 const CONNECTION_TIMEOUT: Duration = Duration::from_secs(3); // Timeout for TCP connections
+const RTT_PROBE_PORTS: usize = 3; // Ports tried per host when timing a handshake
 
 /// Struct to store the results of the TCP port scan
 pub struct TcpScanResult {
-    open_ports: Vec<(Ipv4Addr, u16)>, // (IP, Port)
-    errors: Vec<(Ipv4Addr, String)>,  // (IP, Error Message)
+    probes: ProbeTable, // Packed per-port states, interned error messages
+    retried: BTreeMap<(IpAddr, u16), usize>, // Attempts of the probes that took more than one
 }
 
 impl TcpScanResult {
     pub fn new() -> Self {
         Self {
-            open_ports: Vec::new(),
-            errors: Vec::new(),
+            probes: ProbeTable::new(),
+            retried: BTreeMap::new(),
         }
     }
 
-    pub fn add_open_port(&mut self, ip: Ipv4Addr, port: u16) {
-        self.open_ports.push((ip, port));
+    pub fn add_open_port(&mut self, ip: IpAddr, port: u16) {
+        self.probes.add_open_port(ip, port);
     }
 
-    pub fn add_error(&mut self, ip: Ipv4Addr, error: String) {
-        self.errors.push((ip, error));
+    pub fn add_error(&mut self, ip: IpAddr, error: String) {
+        self.probes.add_host_error(ip, ErrorCategory::Other, &error);
     }
 
-    pub fn get_open_ports(&self) -> &Vec<(Ipv4Addr, u16)> {
-        &self.open_ports
+    pub fn add_port_error(&mut self, ip: IpAddr, port: u16, category: ErrorCategory, message: &str) {
+        self.probes.add_port_error(ip, port, category, message);
     }
 
-    pub fn get_errors(&self) -> &Vec<(Ipv4Addr, String)> {
-        &self.errors
+    /// Records that the probe of `port` on `ip` took `attempts` attempts.
+    pub fn record_attempts(&mut self, ip: IpAddr, port: u16, attempts: usize) {
+        if attempts > 1 {
+            self.retried.insert((ip, port), attempts);
+        }
+    }
+
+    /// Attempts the probe of `port` on `ip` took (1 unless it was retried).
+    pub fn get_attempts(&self, ip: IpAddr, port: u16) -> usize {
+        self.retried.get(&(ip, port)).copied().unwrap_or(1)
+    }
+
+    /// Probes that took more than one attempt, with how many they took
+    pub fn get_retried(&self) -> &BTreeMap<(IpAddr, u16), usize> {
+        &self.retried
+    }
+
+    /// Records one streamed probe outcome.
+    pub fn add(&mut self, result: PortResult) {
+        self.record_attempts(result.ip, result.port, result.attempts);
+        match result.error {
+            None => self.add_open_port(result.ip, result.port),
+            Some((category, message)) => self.add_port_error(result.ip, result.port, category, &message),
+        }
+    }
+
+    /// Adds the probes of `other`, e.g. hosts rescanned another way.
+    pub fn merge(&mut self, other: TcpScanResult) {
+        self.probes.merge(other.probes);
+        self.retried.extend(other.retried);
+    }
+
+    /// Error counts per category for each host
+    pub fn get_error_counts(&self) -> &BTreeMap<IpAddr, BTreeMap<ErrorCategory, usize>> {
+        self.probes.error_counts()
+    }
+
+    pub fn get_open_ports(&self) -> OpenPorts<'_> {
+        self.probes.open_ports()
+    }
+
+    pub fn get_errors(&self) -> ProbeErrors<'_> {
+        self.probes.errors()
+    }
+
+    pub fn get_port_state(&self, ip: IpAddr, port: u16) -> PortState {
+        self.probes.state(ip, port)
+    }
+
+    /// States of every probed port, per host
+    pub fn get_port_states(&self) -> &BTreeMap<IpAddr, PortStates> {
+        self.probes.hosts()
+    }
+
+    /// Hosts where so many ports answered that the openings are not believable.
+    pub fn get_suspect_hosts(&self) -> Vec<SuspectHost> {
+        self.probes
+            .host_totals()
+            .filter_map(|(ip, open, probed)| anomaly::assess(ip, open, probed))
+            .collect()
+    }
+
+    pub fn is_suspect(&self, ip: IpAddr) -> bool {
+        self.get_suspect_hosts().iter().any(|host| host.ip == ip)
+    }
+
+    /// Open ports excluding those on suspect hosts.
+    pub fn get_trusted_open_ports(&self) -> Vec<(IpAddr, u16)> {
+        let suspects = self.get_suspect_hosts();
+        self.probes
+            .open_ports()
+            .iter()
+            .filter(|(ip, _)| !suspects.iter().any(|host| host.ip == *ip))
+            .collect()
+    }
+
+    /// Trusted open ports grouped by host, e.g. to feed service detection.
+    pub fn open_ports_by_host(&self) -> BTreeMap<IpAddr, Vec<u16>> {
+        let mut by_host: BTreeMap<IpAddr, Vec<u16>> = BTreeMap::new();
+        for (ip, port) in self.get_trusted_open_ports() {
+            by_host.entry(ip).or_default().push(port);
+        }
+        by_host
     }
 
     pub fn print_summary(&self) {
         println!("TCP scan completed.");
+        let suspects = self.get_suspect_hosts();
+        if suspects.is_empty() {
+            println!("Total open ports: {}", self.probes.open_ports().len());
+        } else {
+            let suspect_open: usize = suspects.iter().map(|host| host.open).sum();
+            println!(
+                "Total open ports: {} (plus {} suspect on {} host(s), not counted)",
+                self.probes.open_ports().len() - suspect_open,
+                suspect_open,
+                suspects.len()
+            );
+            for host in &suspects {
+                println!("{}", prettyprint::console(&format!("  ⚠️  {}", host)));
+            }
+        }
+        println!("Total errors: {}", self.probes.errors().len());
+        for (ip, counts) in self.probes.error_counts() {
+            println!("  {}: {}", rdns::label(*ip), errors::format_counts(counts));
+        }
+        if !self.retried.is_empty() {
+            println!("Probes retried: {}", self.retried.len());
+        }
+    }
+
+    /// Prints every raw error message (for --verbose).
+    pub fn print_error_details(&self) {
+        for error in self.probes.errors().iter() {
+            println!("  {}", error);
+        }
+    }
+}
+
+/// Outcome of one TCP probe, as `tcp_scan_stream` delivers it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortResult {
+    pub ip: IpAddr,
+    pub port: u16,
+    /// Why the port did not answer; `None` if it is open
+    pub error: Option<(ErrorCategory, String)>,
+    /// Connects tried, retries included
+    pub attempts: usize,
+}
+
+impl PortResult {
+    pub fn is_open(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Probes one port, retrying while it stays silent.
+async fn probe_port<S: SocketFactory>(
+    ip: IpAddr,
+    port: u16,
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+    sockets: &S,
+) -> PortResult {
+    let timeout = config.timeout_for(ip, CONNECTION_TIMEOUT);
+    let attempts = config.retries_or(0) + 1;
+    let addr = crate::utils::sockets::socket_addr(ip, port);
+    // a source of the other address family cannot reach this target
+    let source = source_ip.filter(|source| source.is_ipv4() == ip.is_ipv4());
+    let mut error = Some((ErrorCategory::Timeout, "Timed out".to_string()));
+    let mut tried = 0;
+    for attempt in 0..attempts {
+        config.wait_before_attempt(attempt).await;
+        tried += 1;
+        ratelimit::acquire(ratelimit::TCP_SYN_BYTES).await;
+        error = match tokio::time::timeout(timeout, sockets.tcp_connect(addr, source)).await {
+            Ok(Ok(_)) => None, // Port is open
+            Ok(Err(e)) => {
+                warnings::record_io_error(&e);
+                Some((ErrorCategory::from_io(&e), e.to_string()))
+            }
+            Err(_) => Some((ErrorCategory::Timeout, "Timed out".to_string())),
+        };
+        // Only silence is worth asking again about
+        if !matches!(error, Some((ErrorCategory::Timeout, _))) {
+            break;
+        }
+    }
+    tracing::debug!(open = error.is_none(), attempts = tried, "probe finished");
+    config.report(ProgressEvent::Probed { phase: Phase::TcpScan, ip, port: Some(port) });
+    PortResult { ip, port, error, attempts: tried }
+}
+
+/// Starts a TCP scan of `ports` on every host and returns at once; each
+/// probe's outcome arrives on the receiver as soon as it is known. The channel
+/// closes when every port has been probed.
+pub fn tcp_scan_stream(
+    live_hosts: &[IpAddr],
+    ports: impl IntoIterator<Item = u16>,
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+) -> mpsc::Receiver<PortResult> {
+    let sockets = SystemSockets::bound_to(config.interface.clone());
+    tcp_scan_stream_with(live_hosts, ports, source_ip, config, Arc::new(sockets))
+}
+
+/// `tcp_scan_stream` opening its connections through `sockets`.
+pub fn tcp_scan_stream_with<S: SocketFactory>(
+    live_hosts: &[IpAddr],
+    ports: impl IntoIterator<Item = u16>,
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+    sockets: Arc<S>,
+) -> mpsc::Receiver<PortResult> {
+    let concurrency = config.concurrency_or(MAX_CONCURRENT_TASKS);
+    let (tx, rx) = mpsc::channel(concurrency);
+    let live_hosts = live_hosts.to_vec();
+    let ports: Vec<u16> = ports.into_iter().collect();
+    let config = Arc::new(config.clone());
+    tokio::spawn(async move {
+        config.report(ProgressEvent::Started {
+            phase: Phase::TcpScan,
+            total: live_hosts.len() * ports.len(),
+        });
+        // At most `concurrency` probes exist at a time, host after host
+        let probes: Vec<(IpAddr, u16)> = live_hosts
+            .iter()
+            .flat_map(|&ip| ports.iter().map(move |&port| (ip, port)))
+            .collect();
+        let mut results = stream::iter(probes)
+            .map(|(ip, port)| {
+                let config = Arc::clone(&config);
+                let sockets = Arc::clone(&sockets);
+                async move { probe_port(ip, port, source_ip, &config, sockets.as_ref()).await }
+                    .instrument(tracing::debug_span!("tcp_probe", %ip, port))
+            })
+            .buffer_unordered(concurrency);
+        while let Some(result) = results.next().await {
+            if tx.send(result).await.is_err() {
+                return; // nobody is listening any more
+            }
+        }
+        config.report(ProgressEvent::Finished { phase: Phase::TcpScan });
+    });
+    rx
+}
+
+pub async fn tcp_scan(live_hosts: &[IpAddr], ports: impl IntoIterator<Item = u16>) -> TcpScanResult {
+    tcp_scan_from(live_hosts, ports, None, &ScanConfig::default()).await
+}
+
+/// TCP scan with every probe sent from `source_ip` (the `--source-ip` override).
+/// With `None` the kernel picks the source address per target route.
+pub async fn tcp_scan_from(
+    live_hosts: &[IpAddr],
+    ports: impl IntoIterator<Item = u16>,
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+) -> TcpScanResult {
+    let sockets = SystemSockets::bound_to(config.interface.clone());
+    tcp_scan_with(live_hosts, ports, source_ip, config, Arc::new(sockets)).await
+}
+
+/// TCP scan that opens its connections through `sockets`, e.g. [`MockSockets`]
+/// in tests.
+///
+/// [`MockSockets`]: crate::utils::sockets::MockSockets
+pub async fn tcp_scan_with<S: SocketFactory>(
+    live_hosts: &[IpAddr],
+    ports: impl IntoIterator<Item = u16>,
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+    sockets: Arc<S>,
+) -> TcpScanResult {
+    let mut results = tcp_scan_stream_with(live_hosts, ports, source_ip, config, sockets);
+    let mut final_result = TcpScanResult::new();
+    while let Some(result) = results.recv().await {
+        final_result.add(result);
+    }
+    for ip in live_hosts {
+        tracing::info!(
+            %ip,
+            open = final_result.get_open_ports().iter().filter(|(host, _)| host == ip).count(),
+            "TCP scan of host finished"
+        );
+    }
+    final_result
+}
+
+/// Round trips of a TCP handshake with each host, for hosts no ping measured.
+/// An accepted or refused connection both take one round trip; up to the
+/// first few `ports` are tried, and hosts silent on all of them are left out.
+pub async fn measure_rtts(
+    live_hosts: &[IpAddr],
+    ports: &[u16],
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+) -> BTreeMap<IpAddr, Duration> {
+    let sockets = SystemSockets::bound_to(config.interface.clone());
+    measure_rtts_with(live_hosts, ports, source_ip, config, Arc::new(sockets)).await
+}
+
+/// `measure_rtts` opening its connections through `sockets`.
+pub async fn measure_rtts_with<S: SocketFactory>(
+    live_hosts: &[IpAddr],
+    ports: &[u16],
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+    sockets: Arc<S>,
+) -> BTreeMap<IpAddr, Duration> {
+    let timeout = config.timeout_or(CONNECTION_TIMEOUT);
+    stream::iter(live_hosts.iter().copied())
+        .map(|ip| {
+            let sockets = Arc::clone(&sockets);
+            async move {
+                let source = source_ip.filter(|source| source.is_ipv4() == ip.is_ipv4());
+                for &port in ports.iter().take(RTT_PROBE_PORTS) {
+                    let addr = crate::utils::sockets::socket_addr(ip, port);
+                    ratelimit::acquire(ratelimit::TCP_SYN_BYTES).await;
+                    let sent = tokio::time::Instant::now();
+                    match tokio::time::timeout(timeout, sockets.tcp_connect(addr, source)).await {
+                        Ok(Ok(())) => return Some((ip, sent.elapsed())),
+                        Ok(Err(e)) if ErrorCategory::from_io(&e) == ErrorCategory::Refused => {
+                            return Some((ip, sent.elapsed()));
+                        }
+                        _ => {}
+                    }
+                }
+                None
+            }
+        })
+        .buffer_unordered(config.concurrency_or(MAX_CONCURRENT_TASKS))
+        .filter_map(std::future::ready)
+        .collect()
+        .await
+}
+
+/// An open port found on a hostname target, with the address family that answered
+#[derive(Debug, Clone)]
+pub struct DualStackOpenPort {
+    pub port: u16,
+    pub addr: SocketAddr,
+    pub family: AddressFamily,
+}
+
+/// Struct to store the results of a TCP scan against a hostname target
+#[derive(Debug)]
+pub struct HostTcpScanResult {
+    pub host: String,
+    pub open_ports: Vec<DualStackOpenPort>,
+    pub errors: Vec<String>,
+}
+
+impl HostTcpScanResult {
+    pub fn print_summary(&self) {
+        println!("TCP scan of {} completed.", redact::name(&self.host));
+        for open in &self.open_ports {
+            println!("  {} open via {} ({})", open.port, open.family, redact::ip(open.addr.ip()));
+        }
         println!("Total open ports: {}", self.open_ports.len());
         println!("Total errors: {}", self.errors.len());
     }
 }
 
-/// Function to perform a TCP port scan on a single IP
-async fn scan_ports(ip: Ipv4Addr, port_range: std::ops::Range<u16>, semaphore: Arc<Semaphore>) -> TcpScanResult {
-    let mut result = TcpScanResult::new();
+/// Connect-scan a hostname target, racing its A and AAAA records per port (RFC 8305).
+pub async fn tcp_scan_host(
+    host: &str,
+    ports: impl IntoIterator<Item = u16>,
+    config: &ScanConfig,
+) -> Result<HostTcpScanResult, String> {
+    let resolved = happy_eyeballs::resolve(host, 0).await?;
+    let semaphore = Arc::new(Semaphore::new(config.concurrency_or(MAX_CONCURRENT_TASKS)));
+    let timeout = config.timeout_or(CONNECTION_TIMEOUT);
 
     let mut tasks = Vec::new();
-    for port in port_range {
+    for port in ports {
         let permit = semaphore.clone().acquire_owned().await.unwrap();
-        let ip_clone = ip;
+        let addrs: Vec<SocketAddr> = resolved
+            .iter()
+            .map(|a| SocketAddr::new(a.ip(), port))
+            .collect();
         let task = tokio::spawn(async move {
-            let _permit = permit; // Hold the permit for the duration of the task
-            let addr = SocketAddr::new(IpAddr::V4(ip_clone), port);
-            match tokio::time::timeout(CONNECTION_TIMEOUT, TcpStream::connect(addr)).await {
-                Ok(Ok(_)) => Ok((ip_clone, port)), // Port is open
-                Ok(Err(e)) => Err(format!("Error connecting to {}:{} - {}", ip_clone, port, e)),
-                Err(_) => Err(format!("Timeout connecting to {}:{}", ip_clone, port)),
-            }
+            let _permit = permit;
+            ratelimit::acquire(ratelimit::TCP_SYN_BYTES).await;
+            happy_eyeballs::connect_addrs(&addrs, timeout)
+                .await
+                .map(|conn| DualStackOpenPort {
+                    port,
+                    addr: conn.addr,
+                    family: conn.family,
+                })
+                .map_err(|e| format!("Error connecting to port {}: {}", port, e))
         });
         tasks.push(task);
     }
 
+    let mut result = HostTcpScanResult {
+        host: host.to_string(),
+        open_ports: Vec::new(),
+        errors: Vec::new(),
+    };
     for task in tasks {
         match task.await {
-            Ok(Ok((ip, port))) => result.add_open_port(ip, port),
-            Ok(Err(e)) => result.add_error(ip, e),
-            Err(e) => result.add_error(ip, format!("Task failed: {}", e)),
+            Ok(Ok(open)) => result.open_ports.push(open),
+            Ok(Err(e)) => result.errors.push(e),
+            Err(e) => result.errors.push(format!("Task failed: {}", e)),
         }
     }
 
-    result
+    Ok(result)
 }
-
-pub async fn tcp_scan(live_hosts: &Vec<Ipv4Addr>, port_range: std::ops::Range<u16>) -> TcpScanResult {
-    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_TASKS));
-    let mut final_result = TcpScanResult::new();
-
-    for ip in live_hosts {
-        let result = scan_ports(*ip, port_range.clone(), semaphore.clone()).await;
-        final_result.open_ports.extend(result.get_open_ports().clone());
-        final_result.errors.extend(result.get_errors().clone());
-    }
-
-    final_result
-}
\ No newline at end of file
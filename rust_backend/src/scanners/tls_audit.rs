@@ -0,0 +1,315 @@
+use crate::detect_tls::{self, CertificateInfo, CipherStrength, TlsVersion};
+use crate::utils::findings::{self, Finding, Severity, code};
+use crate::utils::{prettyprint, redact};
+use chrono::{DateTime, Utc};
+use colored::*;
+use std::net::IpAddr;
+
+/// TLS configuration of one endpoint, graded A (best) to F
+#[derive(Debug, Clone)]
+pub struct TlsReport {
+    pub ip: IpAddr,
+    pub port: u16,
+    /// Protocol versions the server accepted, oldest first
+    pub versions: Vec<TlsVersion>,
+    /// Accepted cipher suites per version, in the server's preference order
+    pub ciphers: Vec<(TlsVersion, u16)>,
+    pub certificate: Option<CertificateInfo>,
+    pub error: Option<String>,
+    pub findings: Vec<Finding>,
+    pub grade: Option<char>,
+}
+
+fn grade_severity(grade: char) -> Severity {
+    match grade {
+        'A' => Severity::Info,
+        'B' => Severity::Low,
+        'C' => Severity::Medium,
+        _ => Severity::High,
+    }
+}
+
+fn cipher_names(ciphers: &[u16]) -> String {
+    ciphers
+        .iter()
+        .map(|c| detect_tls::cipher_info(*c).map_or("unknown", |(name, _)| name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl TlsReport {
+    pub fn new(ip: IpAddr, port: u16) -> Self {
+        Self {
+            ip,
+            port,
+            versions: Vec::new(),
+            ciphers: Vec::new(),
+            certificate: None,
+            error: None,
+            findings: Vec::new(),
+            grade: None,
+        }
+    }
+
+    /// Whether the endpoint completed at least one ClientHello exchange.
+    pub fn is_tls(&self) -> bool {
+        !self.versions.is_empty()
+    }
+
+    /// Accepted suites (any version) of the given strength, deduplicated.
+    pub fn ciphers_with_strength(&self, strength: CipherStrength) -> Vec<u16> {
+        let mut matching: Vec<u16> = Vec::new();
+        for (_, cipher) in &self.ciphers {
+            if detect_tls::cipher_info(*cipher).map(|(_, s)| s) == Some(strength)
+                && !matching.contains(cipher)
+            {
+                matching.push(*cipher);
+            }
+        }
+        matching
+    }
+
+    /// Derives findings and the letter grade; `now` is compared with the
+    /// certificate validity.
+    pub fn assess(&mut self, now: DateTime<Utc>) {
+        if !self.is_tls() {
+            self.findings.clear();
+            self.grade = None;
+            return;
+        }
+        let mut found = Vec::new();
+        let mut grade = 'A';
+        let port = Some(self.port);
+
+        if self.versions.contains(&TlsVersion::Ssl3) {
+            grade = grade.max('F');
+            found.push(
+                Finding::new(
+                    Severity::High,
+                    port,
+                    "SSLv3 accepted",
+                    "SSLv3 is broken (POODLE) and must be disabled".to_string(),
+                )
+                .with_code(code::TLS_SSL3),
+            );
+        }
+        let deprecated: Vec<&str> = self
+            .versions
+            .iter()
+            .filter(|v| matches!(v, TlsVersion::Tls10 | TlsVersion::Tls11))
+            .map(|v| v.label())
+            .collect();
+        if !deprecated.is_empty() {
+            grade = grade.max('B');
+            found.push(
+                Finding::new(
+                    Severity::Medium,
+                    port,
+                    "Deprecated TLS versions accepted",
+                    deprecated.join(", "),
+                )
+                .with_code(code::TLS_DEPRECATED_VERSIONS),
+            );
+        }
+        if !self.versions.iter().any(|v| *v >= TlsVersion::Tls12) {
+            grade = grade.max('C');
+            found.push(
+                Finding::new(
+                    Severity::Medium,
+                    port,
+                    "No TLS 1.2 or newer",
+                    "Modern clients will refuse to connect".to_string(),
+                )
+                .with_code(code::TLS_NO_MODERN_VERSION),
+            );
+        }
+
+        let insecure = self.ciphers_with_strength(CipherStrength::Insecure);
+        if !insecure.is_empty() {
+            grade = grade.max('F');
+            found.push(
+                Finding::new(
+                    Severity::High,
+                    port,
+                    "Insecure cipher suites accepted",
+                    cipher_names(&insecure),
+                )
+                .with_code(code::TLS_INSECURE_CIPHERS),
+            );
+        }
+        let weak = self.ciphers_with_strength(CipherStrength::Weak);
+        if !weak.is_empty() {
+            grade = grade.max('C');
+            found.push(
+                Finding::new(
+                    Severity::Medium,
+                    port,
+                    "Weak cipher suites accepted",
+                    cipher_names(&weak),
+                )
+                .with_code(code::TLS_WEAK_CIPHERS),
+            );
+        }
+
+        if let Some(cert) = &self.certificate {
+            if cert.is_expired(now) {
+                grade = grade.max('F');
+                found.push(
+                    Finding::new(
+                        Severity::High,
+                        port,
+                        "Certificate expired",
+                        format!(
+                            "{} expired on {}",
+                            cert.subject,
+                            cert.not_after.format("%Y-%m-%d")
+                        ),
+                    )
+                    .with_code(code::CERT_EXPIRED),
+                );
+            }
+            if cert.self_signed {
+                grade = grade.max('C');
+                found.push(
+                    Finding::new(
+                        Severity::Medium,
+                        port,
+                        "Self-signed certificate",
+                        format!("{} is not issued by a CA clients can verify", cert.subject),
+                    )
+                    .with_code(code::CERT_SELF_SIGNED),
+                );
+            }
+        }
+
+        let versions: Vec<&str> = self.versions.iter().map(|v| v.label()).collect();
+        found.push(Finding::new(
+            grade_severity(grade),
+            port,
+            &format!("TLS grade {}", grade),
+            format!(
+                "{}; {} cipher suite(s) accepted",
+                versions.join(", "),
+                self.ciphers.len()
+            ),
+        ));
+
+        findings::sort_by_severity(&mut found);
+        self.findings = found;
+        self.grade = Some(grade);
+    }
+
+    pub fn risk_score(&self) -> u32 {
+        findings::risk_score(&self.findings)
+    }
+
+    pub fn print_summary(&self) {
+        let Some(grade) = self.grade else {
+            return;
+        };
+        let grade_label = match grade {
+            'A' => grade.to_string().green().bold(),
+            'B' => grade.to_string().yellow().bold(),
+            _ => grade.to_string().red().bold(),
+        };
+        if let Some(cert) = &self.certificate {
+            redact::register(
+                std::iter::empty(),
+                std::iter::once(cert.subject.as_str()).chain(cert.sans.iter().map(String::as_str)),
+            );
+        }
+        println!(
+            "{}",
+            prettyprint::console(&format!(
+                "\n{} {}:{} — grade {} (risk {}/{})",
+                "🔐 TLS".bold().blue(),
+                redact::ip(self.ip),
+                self.port,
+                grade_label,
+                self.risk_score(),
+                findings::MAX_RISK_SCORE
+            ))
+        );
+        for version in &self.versions {
+            let suites: Vec<u16> = self
+                .ciphers
+                .iter()
+                .filter(|(v, _)| v == version)
+                .map(|(_, c)| *c)
+                .collect();
+            println!("  {}: {}", version.label().bold(), cipher_names(&suites));
+        }
+        if let Some(cert) = &self.certificate {
+            println!(
+                "  {}: {} (issuer {}, expires {})",
+                "Certificate".bold(),
+                redact::name(&cert.subject),
+                redact::text(&cert.issuer),
+                cert.not_after.format("%Y-%m-%d")
+            );
+        }
+        for finding in self.findings.iter().filter(|f| f.severity > Severity::Info) {
+            println!(
+                "  [{}] {}: {}",
+                finding.severity.colored(),
+                redact::text(&finding.title).bold(),
+                redact::text(&finding.detail)
+            );
+        }
+    }
+}
+
+/// Offers the probe set for `version` repeatedly, removing each suite the
+/// server picks, until it refuses. Returns the suites in server preference order.
+pub async fn enumerate_ciphers(
+    ip: IpAddr,
+    port: u16,
+    version: TlsVersion,
+) -> Result<Vec<u16>, String> {
+    let probe_set = if version == TlsVersion::Tls13 {
+        detect_tls::TLS13_CIPHER_SUITES
+    } else {
+        detect_tls::CIPHER_SUITES
+    };
+    let mut remaining: Vec<u16> = probe_set.iter().map(|(id, _, _)| *id).collect();
+    let mut accepted = Vec::new();
+    while !remaining.is_empty() {
+        match detect_tls::probe(ip, port, version, &remaining).await? {
+            Some(hello) if hello.version == version.wire() && remaining.contains(&hello.cipher) => {
+                remaining.retain(|c| *c != hello.cipher);
+                accepted.push(hello.cipher);
+            }
+            _ => break,
+        }
+    }
+    Ok(accepted)
+}
+
+/// Enumerates versions and cipher suites on `ip:port`, reads the certificate
+/// and grades the result. Non-TLS ports come back with no versions and no grade.
+pub async fn audit_tls_endpoint(ip: IpAddr, port: u16) -> TlsReport {
+    let mut report = TlsReport::new(ip, port);
+    for version in TlsVersion::ALL {
+        match enumerate_ciphers(ip, port, version).await {
+            Ok(ciphers) if !ciphers.is_empty() => {
+                report.versions.push(version);
+                report
+                    .ciphers
+                    .extend(ciphers.into_iter().map(|c| (version, c)));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                report.error = Some(e);
+                break;
+            }
+        }
+    }
+    if report.is_tls() {
+        match detect_tls::fetch_certificate(ip, port).await {
+            Ok(der) => report.certificate = detect_tls::parse_certificate(&der),
+            Err(e) => report.error = Some(e.to_string()),
+        }
+    }
+    report.assess(Utc::now());
+    report
+}
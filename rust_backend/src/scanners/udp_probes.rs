@@ -0,0 +1,146 @@
+use crate::detect_ntp;
+use crate::detect_sip::{self, SipTransport};
+use crate::detect_snmp::{self, SnmpVersion};
+use crate::detect_tftp;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// DNS query class IN
+const CLASS_IN: u16 = 1;
+/// mDNS "unicast response" bit (RFC 6762 5.4): the answer comes back to our
+/// port instead of the multicast group
+const CLASS_UNICAST_RESPONSE: u16 = 0x8000;
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+/// NetBIOS node status request
+const TYPE_NBSTAT: u16 = 0x21;
+const SSDP_SEARCH: &str = "M-SEARCH * HTTP/1.1\r\n\
+HOST: 239.255.255.250:1900\r\n\
+MAN: \"ssdp:discover\"\r\n\
+MX: 1\r\n\
+ST: ssdp:all\r\n\r\n";
+
+/// A payload a UDP service answers, where an empty datagram gets silence
+pub struct UdpProbe {
+    pub name: &'static str,
+    pub ports: &'static [u16],
+    build: fn(SocketAddr) -> Vec<u8>,
+}
+
+impl UdpProbe {
+    /// The datagram to send to `addr`.
+    pub fn payload(&self, addr: SocketAddr) -> Vec<u8> {
+        (self.build)(addr)
+    }
+}
+
+/// Well-known payloads, selected by port
+pub static PROBES: &[UdpProbe] = &[
+    UdpProbe {
+        name: "DNS",
+        ports: &[53],
+        build: |_| dns_query(0x1234, 0x0100, "www.example.com", TYPE_A, CLASS_IN),
+    },
+    UdpProbe {
+        name: "TFTP",
+        ports: &[69],
+        build: |_| detect_tftp::build_read_request(detect_tftp::PROBE_FILENAME),
+    },
+    UdpProbe {
+        name: "NTP",
+        ports: &[123],
+        build: |_| detect_ntp::build_request().to_vec(),
+    },
+    UdpProbe {
+        name: "NetBIOS-NS",
+        ports: &[137],
+        build: |_| netbios_status_query(),
+    },
+    UdpProbe {
+        name: "SNMP",
+        ports: &[161],
+        build: |_| detect_snmp::build_get_request(SnmpVersion::V2c, "public", 0x4e53),
+    },
+    UdpProbe {
+        name: "SSDP",
+        ports: &[1900],
+        build: |_| SSDP_SEARCH.as_bytes().to_vec(),
+    },
+    UdpProbe {
+        name: "mDNS",
+        ports: &[5353],
+        build: |_| {
+            dns_query(
+                0,
+                0,
+                "_services._dns-sd._udp.local",
+                TYPE_PTR,
+                CLASS_IN | CLASS_UNICAST_RESPONSE,
+            )
+        },
+    },
+    UdpProbe {
+        name: "SIP",
+        ports: &[5060],
+        build: sip_options,
+    },
+];
+
+/// The probe for `port`, if a service there has a known payload.
+pub fn probe_for(port: u16) -> Option<&'static UdpProbe> {
+    PROBES.iter().find(|probe| probe.ports.contains(&port))
+}
+
+/// What to send to `addr`: its service's payload, or a single zero byte.
+pub fn payload_for(addr: SocketAddr) -> Vec<u8> {
+    probe_for(addr.port()).map_or_else(|| vec![0u8], |probe| probe.payload(addr))
+}
+
+/// A one-question DNS message.
+pub fn dns_query(id: u16, flags: u16, name: &str, qtype: u16, qclass: u16) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(18 + name.len());
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&flags.to_be_bytes());
+    // one question, no answer, authority or additional records
+    packet.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    for label in name.split('.').filter(|label| !label.is_empty()) {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&qtype.to_be_bytes());
+    packet.extend_from_slice(&qclass.to_be_bytes());
+    packet
+}
+
+/// NBSTAT query for the wildcard name "*", which every NetBIOS node answers.
+fn netbios_status_query() -> Vec<u8> {
+    let mut packet = vec![
+        0x4e, 0x53, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    // "*" padded with NULs to 16 bytes, each nibble encoded as 'A' + nibble
+    packet.push(32);
+    for byte in std::iter::once(b'*').chain(std::iter::repeat_n(0u8, 15)) {
+        packet.push(b'A' + (byte >> 4));
+        packet.push(b'A' + (byte & 0x0f));
+    }
+    packet.push(0);
+    packet.extend_from_slice(&TYPE_NBSTAT.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    packet
+}
+
+/// OPTIONS request; the server routes the reply by the datagram's source
+/// (rport), so the advertised local address only needs the right family.
+fn sip_options(addr: SocketAddr) -> Vec<u8> {
+    let local_ip = match addr.ip() {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    detect_sip::build_options_request(
+        addr.ip(),
+        addr.port(),
+        SocketAddr::new(local_ip, addr.port()),
+        SipTransport::Udp,
+    )
+    .into_bytes()
+}
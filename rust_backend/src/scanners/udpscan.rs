@@ -1,123 +1,326 @@
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use crate::scanners::icmp_unreachable::{UnreachableListener, UnreachablePorts};
+use crate::scanners::udp_probes;
+use crate::utils::compact::{OpenPorts, PortState, PortStates, ProbeErrors, ProbeTable};
+use crate::utils::errors::{self, ErrorCategory};
+use crate::utils::progress::{Phase, ProgressEvent};
+use crate::utils::scan_config::ScanConfig;
+use crate::utils::sockets::{SocketFactory, SystemSockets};
+use crate::utils::{ratelimit, rdns, warnings};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::net::UdpSocket;
 use tokio::sync::Semaphore;
+use tracing::Instrument;
 
 const MAX_CONCURRENT_TASKS: usize = 64; // Limit the number of concurrent tasks
 const CONNECTION_TIMEOUT: Duration = Duration::from_secs(4); // Timeout for UDP responses
 
+/// State of a UDP port in nmap's terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UdpPortState {
+    /// A datagram came back
+    Open,
+    /// ICMP port unreachable came back
+    Closed,
+    /// Another ICMP unreachable came back, e.g. administratively prohibited
+    Filtered,
+    /// Nothing came back: an open port that ignored the probe, or a filter
+    OpenFiltered,
+    Unscanned,
+}
+
+impl UdpPortState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            UdpPortState::Open => "open",
+            UdpPortState::Closed => "closed",
+            UdpPortState::Filtered => "filtered",
+            UdpPortState::OpenFiltered => "open|filtered",
+            UdpPortState::Unscanned => "unscanned",
+        }
+    }
+}
+
+impl From<PortState> for UdpPortState {
+    fn from(state: PortState) -> Self {
+        match state {
+            PortState::Unscanned => UdpPortState::Unscanned,
+            PortState::Open => UdpPortState::Open,
+            PortState::Failed(ErrorCategory::Refused) => UdpPortState::Closed,
+            PortState::Failed(ErrorCategory::Timeout) => UdpPortState::OpenFiltered,
+            PortState::Failed(_) => UdpPortState::Filtered,
+        }
+    }
+}
+
+impl fmt::Display for UdpPortState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
 /// Struct to store the results of the UDP port scan
 pub struct UdpScanResult {
-    open_ports: Vec<(Ipv4Addr, u16)>, // (IP, Port)
-    errors: Vec<(Ipv4Addr, String)>,  // (IP, Error Message)
+    probes: ProbeTable, // Packed per-port states, interned error messages
+    retried: BTreeMap<(IpAddr, u16), usize>, // Attempts of the probes that took more than one
 }
 
 impl UdpScanResult {
     pub fn new() -> Self {
         Self {
-            open_ports: Vec::new(),
-            errors: Vec::new(),
+            probes: ProbeTable::new(),
+            retried: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_open_port(&mut self, ip: IpAddr, port: u16) {
+        self.probes.add_open_port(ip, port);
+    }
+
+    pub fn add_error(&mut self, ip: IpAddr, error: String) {
+        self.probes.add_host_error(ip, ErrorCategory::Other, &error);
+    }
+
+    pub fn add_port_error(&mut self, ip: IpAddr, port: u16, category: ErrorCategory, message: &str) {
+        self.probes.add_port_error(ip, port, category, message);
+    }
+
+    /// Records that the probe of `port` on `ip` took `attempts` attempts.
+    pub fn record_attempts(&mut self, ip: IpAddr, port: u16, attempts: usize) {
+        if attempts > 1 {
+            self.retried.insert((ip, port), attempts);
         }
     }
 
-    pub fn add_open_port(&mut self, ip: Ipv4Addr, port: u16) {
-        self.open_ports.push((ip, port));
+    /// Attempts the probe of `port` on `ip` took (1 unless it was retried).
+    pub fn get_attempts(&self, ip: IpAddr, port: u16) -> usize {
+        self.retried.get(&(ip, port)).copied().unwrap_or(1)
+    }
+
+    /// Probes that took more than one attempt, with how many they took
+    pub fn get_retried(&self) -> &BTreeMap<(IpAddr, u16), usize> {
+        &self.retried
+    }
+
+    /// Adds the probes of `other`, e.g. ports scanned in another batch.
+    pub fn merge(&mut self, other: UdpScanResult) {
+        self.probes.merge(other.probes);
+        self.retried.extend(other.retried);
+    }
+
+    /// Error counts per category for each host
+    pub fn get_error_counts(&self) -> &BTreeMap<IpAddr, BTreeMap<ErrorCategory, usize>> {
+        self.probes.error_counts()
+    }
+
+    pub fn get_open_ports(&self) -> OpenPorts<'_> {
+        self.probes.open_ports()
     }
 
-    pub fn add_error(&mut self, ip: Ipv4Addr, error: String) {
-        self.errors.push((ip, error));
+    pub fn get_errors(&self) -> ProbeErrors<'_> {
+        self.probes.errors()
     }
 
-    pub fn get_open_ports(&self) -> &Vec<(Ipv4Addr, u16)> {
-        &self.open_ports
+    pub fn get_port_state(&self, ip: IpAddr, port: u16) -> PortState {
+        self.probes.state(ip, port)
     }
 
-    pub fn get_errors(&self) -> &Vec<(Ipv4Addr, String)> {
-        &self.errors
+    /// States of every probed port, per host
+    pub fn get_port_states(&self) -> &BTreeMap<IpAddr, PortStates> {
+        self.probes.hosts()
+    }
+
+    pub fn get_udp_state(&self, ip: IpAddr, port: u16) -> UdpPortState {
+        self.probes.state(ip, port).into()
+    }
+
+    /// Number of probed ports in each state, across all hosts.
+    pub fn state_counts(&self) -> BTreeMap<UdpPortState, usize> {
+        let mut counts = BTreeMap::new();
+        for states in self.probes.hosts().values() {
+            for (_, state) in states.iter() {
+                *counts.entry(UdpPortState::from(state)).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// Reclassifies ports that timed out but drew an ICMP error, which the
+    /// probe socket itself may not have seen.
+    pub fn apply_unreachable(&mut self, unreachable: &UnreachablePorts) {
+        for (&(ip, port), &category) in unreachable {
+            if self.probes.state(ip, port) == PortState::Failed(ErrorCategory::Timeout) {
+                self.probes.add_port_error(ip, port, category, "ICMP destination unreachable");
+            }
+        }
     }
 
     pub fn print_summary(&self) {
         println!("UDP scan completed.");
-        println!("Total open ports: {}", self.open_ports.len());
-        println!("Total errors: {}", self.errors.len());
+        println!("Total open ports: {}", self.probes.open_ports().len());
+        let counts: Vec<String> = self
+            .state_counts()
+            .iter()
+            .map(|(state, count)| format!("{} {}", count, state))
+            .collect();
+        if !counts.is_empty() {
+            println!("Port states: {}", counts.join(", "));
+        }
+        println!("Total errors: {}", self.probes.errors().len());
+        for (ip, counts) in self.probes.error_counts() {
+            println!("  {}: {}", rdns::label(*ip), errors::format_counts(counts));
+        }
+        if !self.retried.is_empty() {
+            println!("Probes retried: {}", self.retried.len());
+        }
+    }
+
+    /// Prints every raw error message (for --verbose).
+    pub fn print_error_details(&self) {
+        for error in self.probes.errors().iter() {
+            println!("  {}", error);
+        }
     }
 }
 
 /// Function to perform a UDP port scan on a single IP (Version 2)
-async fn scan_udp_ports(
-    ip: Ipv4Addr,
-    port_range: std::ops::Range<u16>,
+async fn scan_udp_ports<S: SocketFactory>(
+    ip: IpAddr,
+    ports: &[u16],
     semaphore: Arc<Semaphore>,
+    source_ip: Option<IpAddr>,
+    config: ScanConfig,
+    sockets: Arc<S>,
 ) -> UdpScanResult {
     let mut result = UdpScanResult::new();
+    let timeout = config.timeout_for(ip, CONNECTION_TIMEOUT);
+    let attempts = config.retries_or(0) + 1;
 
     let mut tasks = Vec::new();
-    for port in port_range {
+    for &port in ports {
         let permit = semaphore.clone().acquire_owned().await.unwrap();
         let ip_clone = ip;
+        let sockets = sockets.clone();
+        let config = config.clone();
         let task = tokio::spawn(async move {
             let _permit = permit;
-            let addr = SocketAddr::new(IpAddr::V4(ip_clone), port);
+            let addr = crate::utils::sockets::socket_addr(ip_clone, port);
+            // Most services ignore a datagram they cannot parse, so speak their protocol
+            let payload = udp_probes::payload_for(addr);
 
-            match tokio::time::timeout(CONNECTION_TIMEOUT, async {
-                let socket = UdpSocket::bind("0.0.0.0:0")
+            // a source of the other address family cannot reach this target
+            let source = source_ip.filter(|source| source.is_ipv4() == ip_clone.is_ipv4());
+            let mut outcome = Err((ErrorCategory::Timeout, "Timed out".to_string()));
+            let mut tried = 0;
+            for attempt in 0..attempts {
+                config.wait_before_attempt(attempt).await;
+                tried += 1;
+                ratelimit::acquire(ratelimit::UDP_HEADER_BYTES + payload.len()).await;
+                outcome = match tokio::time::timeout(timeout, sockets.udp_exchange(addr, source, &payload))
                     .await
-                    .map_err(|e| e.to_string())?;
-                socket.connect(addr).await.map_err(|e| e.to_string())?;
-
-                if port == 53 {
-                    let dns_query = [
-                        0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                        0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e',
-                        0x03, b'c', b'o', b'm', 0x00, 0x00, 0x01, 0x00, 0x01,
-                    ];
-                    socket.send(&dns_query).await.map_err(|e| e.to_string())?;
-                } else {
-                    socket.send(&[0u8; 1]).await.map_err(|e| e.to_string())?;
-                }
-
-                let mut buf = [0u8; 1024];
-                match socket.recv(&mut buf).await {
-                    Ok(_) => Ok(()),
-                    Err(_) => Err("No response".to_string()),
+                {
+                    Ok(Ok(received)) => {
+                        tracing::trace!(sent = payload.len(), received, "datagram answered");
+                        Ok((ip_clone, port))
+                    }
+                    Ok(Err(e)) => {
+                        warnings::record_io_error(&e);
+                        Err((ErrorCategory::from_io(&e), e.to_string()))
+                    }
+                    Err(_) => Err((ErrorCategory::Timeout, "Timed out".to_string())),
+                };
+                // A lost datagram looks the same as a filtered port; ask again
+                if !matches!(outcome, Err((ErrorCategory::Timeout, _))) {
+                    break;
                 }
-            })
-            .await
-            {
-                Ok(Ok(_)) => Ok((ip_clone, port)),
-                Ok(Err(e)) => Err(format!("Error on {}:{} - {}", ip_clone, port, e)),
-                Err(_) => Err(format!("Timeout on {}:{}", ip_clone, port)),
             }
-        });
-        tasks.push(task);
+            tracing::debug!(open = outcome.is_ok(), attempts = tried, "probe finished");
+            config.report(ProgressEvent::Probed { phase: Phase::UdpScan, ip: ip_clone, port: Some(port) });
+            (outcome, tried)
+        }
+        .instrument(tracing::debug_span!("udp_probe", %ip, port)));
+        tasks.push((port, task));
     }
 
-    for task in tasks {
+    for (port, task) in tasks {
         match task.await {
-            Ok(Ok((ip, port))) => result.add_open_port(ip, port),
-            Ok(Err(e)) => result.add_error(ip, e),
-            Err(e) => result.add_error(ip, format!("Task failed: {}", e)),
+            Ok((outcome, tried)) => {
+                result.record_attempts(ip, port, tried);
+                match outcome {
+                    Ok((ip, port)) => result.add_open_port(ip, port),
+                    Err((category, e)) => result.add_port_error(ip, port, category, &e),
+                }
+            }
+            Err(e) => result.add_error(ip, format!("Task failed on port {}: {}", port, e)),
         }
     }
+    tracing::info!(%ip, open = result.get_open_ports().len(), "UDP scan of host finished");
 
     result
 }
 
 pub async fn udp_scan(
-    live_hosts: &Vec<Ipv4Addr>,
-    port_range: std::ops::Range<u16>,
+    live_hosts: &[IpAddr],
+    ports: impl IntoIterator<Item = u16>,
+) -> UdpScanResult {
+    udp_scan_from(live_hosts, ports, None, &ScanConfig::default()).await
+}
+
+/// UDP scan with every probe sent from `source_ip` (the `--source-ip` override).
+pub async fn udp_scan_from(
+    live_hosts: &[IpAddr],
+    ports: impl IntoIterator<Item = u16>,
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+) -> UdpScanResult {
+    // Raw sockets hear ICMP errors the probe sockets miss; without root the
+    // kernel's ECONNREFUSED on the probe socket is all there is
+    let listener = match UnreachableListener::start() {
+        Ok(listener) => Some(listener),
+        Err(e) => {
+            tracing::debug!("{}; relying on socket errors for closed ports", e);
+            None
+        }
+    };
+    let sockets = SystemSockets::bound_to(config.interface.clone());
+    let mut result = udp_scan_with(live_hosts, ports, source_ip, config, Arc::new(sockets)).await;
+    if let Some(listener) = listener {
+        result.apply_unreachable(&listener.stop());
+    }
+    result
+}
+
+/// UDP scan that sends its probes through `sockets`, e.g. a mock in tests.
+pub async fn udp_scan_with<S: SocketFactory>(
+    live_hosts: &[IpAddr],
+    ports: impl IntoIterator<Item = u16>,
+    source_ip: Option<IpAddr>,
+    config: &ScanConfig,
+    sockets: Arc<S>,
 ) -> UdpScanResult {
-    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_TASKS));
+    let semaphore = Arc::new(Semaphore::new(config.concurrency_or(MAX_CONCURRENT_TASKS)));
+    let ports: Vec<u16> = ports.into_iter().collect();
     let mut final_result = UdpScanResult::new();
+    config.report(ProgressEvent::Started {
+        phase: Phase::UdpScan,
+        total: live_hosts.len() * ports.len(),
+    });
 
     for ip in live_hosts {
-        let result = scan_udp_ports(*ip, port_range.clone(), semaphore.clone()).await;
-        final_result
-            .open_ports
-            .extend(result.get_open_ports().clone());
-        final_result.errors.extend(result.get_errors().clone());
+        let result = scan_udp_ports(
+            *ip,
+            &ports,
+            semaphore.clone(),
+            source_ip,
+            config.clone(),
+            sockets.clone(),
+        )
+        .await;
+        final_result.merge(result);
     }
+    config.report(ProgressEvent::Finished { phase: Phase::UdpScan });
 
     final_result
 }
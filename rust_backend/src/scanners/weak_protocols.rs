@@ -0,0 +1,456 @@
+use crate::detect_legacy::{self, LegacyService};
+use crate::utils::findings::{Finding, Severity, code};
+use crate::utils::{prettyprint, sockets};
+use crate::{
+    detect_couchdb, detect_elasticsearch, detect_ftp, detect_http, detect_mongodb, detect_proxy,
+    detect_rtsp, detect_smb, detect_snmp, detect_tftp, detect_vnc,
+};
+use colored::*;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+pub const TELNET_PORTS: &[u16] = &[23, 2323];
+pub const FTP_PORTS: &[u16] = &[21];
+pub const SMB_PORTS: &[u16] = &[445];
+/// Cleartext HTTP ports commonly used by device and application admin panels
+pub const HTTP_PORTS: &[u16] = &[80, 8000, 8008, 8080, 8081, 8888];
+pub const VNC_PORTS: &[u16] = &[5900, 5901, 5902, 5903];
+pub const RTSP_PORTS: &[u16] = &[554, 8554];
+pub const SNMP_PORT: u16 = 161;
+pub const TFTP_PORT: u16 = 69;
+
+/// Kinds of inherently weak service the summary reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum WeakKind {
+    Telnet,
+    FtpWithoutTls,
+    SnmpV1V2c,
+    Smb1,
+    HttpAdminPanel,
+    VncWithoutAuth,
+    Tftp,
+    LegacyService,
+    AnonymousCameraStream,
+    OpenProxy,
+    UnauthenticatedDataStore,
+}
+
+impl WeakKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            WeakKind::Telnet => "Telnet",
+            WeakKind::FtpWithoutTls => "FTP without TLS",
+            WeakKind::SnmpV1V2c => "SNMPv1/v2c",
+            WeakKind::Smb1 => "SMBv1",
+            WeakKind::HttpAdminPanel => "HTTP admin panel without TLS",
+            WeakKind::VncWithoutAuth => "VNC without authentication",
+            WeakKind::Tftp => "TFTP",
+            WeakKind::LegacyService => "Legacy service",
+            WeakKind::AnonymousCameraStream => "Camera stream without authentication",
+            WeakKind::OpenProxy => "Open proxy",
+            WeakKind::UnauthenticatedDataStore => "Data store without authentication",
+        }
+    }
+
+    /// Stable finding code, what compliance controls are mapped to
+    pub fn code(&self) -> &'static str {
+        match self {
+            WeakKind::Telnet => code::TELNET,
+            WeakKind::FtpWithoutTls => code::FTP_WITHOUT_TLS,
+            WeakKind::SnmpV1V2c => code::SNMP_V1_V2C,
+            WeakKind::Smb1 => code::SMB1,
+            WeakKind::HttpAdminPanel => code::HTTP_ADMIN_PANEL,
+            WeakKind::VncWithoutAuth => code::VNC_WITHOUT_AUTH,
+            WeakKind::Tftp => code::TFTP,
+            WeakKind::LegacyService => code::LEGACY_SERVICE,
+            WeakKind::AnonymousCameraStream => code::CAMERA_STREAM_WITHOUT_AUTH,
+            WeakKind::OpenProxy => code::OPEN_PROXY,
+            WeakKind::UnauthenticatedDataStore => code::DATA_STORE_WITHOUT_AUTH,
+        }
+    }
+
+    /// What to do about it, for the remediation list
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            WeakKind::Telnet => "Disable Telnet and use SSH",
+            WeakKind::FtpWithoutTls => "Require FTPS (AUTH TLS) or move to SFTP",
+            WeakKind::SnmpV1V2c => "Disable community-based SNMP; use SNMPv3 with authPriv",
+            WeakKind::Smb1 => "Disable the SMB1 protocol",
+            WeakKind::HttpAdminPanel => "Serve the interface over HTTPS only",
+            WeakKind::VncWithoutAuth => "Require VNC authentication or tunnel it over SSH",
+            WeakKind::Tftp => "Disable TFTP or restrict it to a provisioning network",
+            WeakKind::LegacyService => "Disable r-services, finger and the inetd small services",
+            WeakKind::AnonymousCameraStream => "Require RTSP authentication on the camera",
+            WeakKind::OpenProxy => "Require proxy authentication or restrict it to trusted clients",
+            WeakKind::UnauthenticatedDataStore => {
+                "Enable authentication and bind the data store to trusted networks"
+            }
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            WeakKind::VncWithoutAuth
+            | WeakKind::AnonymousCameraStream
+            | WeakKind::UnauthenticatedDataStore => Severity::Critical,
+            WeakKind::Telnet
+            | WeakKind::Smb1
+            | WeakKind::SnmpV1V2c
+            | WeakKind::Tftp
+            | WeakKind::LegacyService
+            | WeakKind::OpenProxy => Severity::High,
+            WeakKind::FtpWithoutTls | WeakKind::HttpAdminPanel => Severity::Medium,
+        }
+    }
+}
+
+/// One weak service instance on one endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeakService {
+    pub ip: IpAddr,
+    pub port: u16,
+    pub kind: WeakKind,
+    pub detail: String,
+}
+
+impl WeakService {
+    pub fn new(ip: IpAddr, port: u16, kind: WeakKind, detail: String) -> Self {
+        Self {
+            ip,
+            port,
+            kind,
+            detail,
+        }
+    }
+
+    pub fn to_finding(&self) -> Finding {
+        Finding::new(
+            self.kind.severity(),
+            Some(self.port),
+            self.kind.label(),
+            format!("{}: {}", self.ip, self.detail),
+        )
+        .with_code(self.kind.code())
+    }
+}
+
+/// Weak/legacy protocol exposure across all scanned hosts
+#[derive(Debug, Clone, Default)]
+pub struct WeakProtocolReport {
+    exposures: Vec<WeakService>,
+}
+
+impl WeakProtocolReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn extend(&mut self, exposures: Vec<WeakService>) {
+        self.exposures.extend(exposures);
+    }
+
+    pub fn len(&self) -> usize {
+        self.exposures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exposures.is_empty()
+    }
+
+    /// Exposures grouped by kind, then ordered by host and port.
+    pub fn exposures(&self) -> Vec<&WeakService> {
+        let mut sorted: Vec<&WeakService> = self.exposures.iter().collect();
+        sorted.sort_by_key(|e| (e.kind, e.ip, e.port));
+        sorted
+    }
+
+    pub fn findings(&self) -> Vec<Finding> {
+        self.exposures().iter().map(|e| e.to_finding()).collect()
+    }
+
+    /// Prints the remediation list: one block per kind, one line per host:port.
+    pub fn print_summary(&self) {
+        println!(
+            "\n{}",
+            prettyprint::console(&format!(
+                "🧯 Weak/legacy protocol exposure ({} instance(s))",
+                self.len()
+            ))
+            .bold()
+            .underline()
+            .blue()
+        );
+        if self.is_empty() {
+            println!("{}", "No weak or legacy services found.".green());
+            return;
+        }
+        let mut current = None;
+        for exposure in self.exposures() {
+            if current != Some(exposure.kind) {
+                current = Some(exposure.kind);
+                println!(
+                    "{}",
+                    prettyprint::console(&format!(
+                        "  [{}] {} — {}",
+                        exposure.kind.severity().colored(),
+                        exposure.kind.label().bold(),
+                        exposure.kind.remediation()
+                    ))
+                );
+            }
+            println!(
+                "      {}:{}  {}",
+                exposure.ip,
+                exposure.port,
+                exposure.detail.dimmed()
+            );
+        }
+    }
+}
+
+fn open_among<'a>(open_ports: &'a [u16], candidates: &'a [u16]) -> impl Iterator<Item = u16> + 'a {
+    open_ports
+        .iter()
+        .copied()
+        .filter(move |port| candidates.contains(port))
+}
+
+/// Reads whatever a Telnet server sends first; option negotiation (IAC,
+/// 0xff) or a login prompt both confirm it.
+async fn telnet_greeting(ip: IpAddr, port: u16) -> Option<String> {
+    let mut stream = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    let mut buf = [0u8; 256];
+    let n = match tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await {
+        Ok(Ok(n)) => n,
+        _ => 0,
+    };
+    if buf[..n].first() == Some(&0xff) {
+        return Some("option negotiation".to_string());
+    }
+    let text = String::from_utf8_lossy(&buf[..n]).trim().to_string();
+    Some(if text.is_empty() {
+        "port open, no greeting".to_string()
+    } else {
+        format!("greeting \"{}\"", text.lines().next().unwrap_or_default())
+    })
+}
+
+async fn check_telnet(ip: IpAddr, open_ports: &[u16]) -> Vec<WeakService> {
+    let mut found = Vec::new();
+    for port in open_among(open_ports, TELNET_PORTS) {
+        if let Some(detail) = telnet_greeting(ip, port).await {
+            found.push(WeakService::new(ip, port, WeakKind::Telnet, detail));
+        }
+    }
+    found
+}
+
+async fn check_ftp(ip: IpAddr, open_ports: &[u16]) -> Vec<WeakService> {
+    let mut found = Vec::new();
+    for port in open_among(open_ports, FTP_PORTS) {
+        if let Ok(false) = detect_ftp::supports_auth_tls(ip, port).await {
+            found.push(WeakService::new(
+                ip,
+                port,
+                WeakKind::FtpWithoutTls,
+                "AUTH TLS refused; credentials travel in cleartext".to_string(),
+            ));
+        }
+    }
+    found
+}
+
+async fn check_snmp(ip: IpAddr) -> Vec<WeakService> {
+    let snmp = detect_snmp::detect(ip, SNMP_PORT, detect_snmp::DEFAULT_COMMUNITIES).await;
+    if !snmp.community_enabled() {
+        return Vec::new();
+    }
+    let versions: Vec<&str> = snmp.versions.iter().map(|v| v.label()).collect();
+    let mut detail = format!(
+        "{} answer community \"{}\"",
+        versions.join(" and "),
+        snmp.community.as_deref().unwrap_or_default()
+    );
+    if snmp.v3.is_some() {
+        detail.push_str(" alongside SNMPv3");
+    }
+    vec![WeakService::new(ip, SNMP_PORT, WeakKind::SnmpV1V2c, detail)]
+}
+
+async fn check_tftp(ip: IpAddr) -> Vec<WeakService> {
+    let tftp = detect_tftp::detect(ip, TFTP_PORT).await;
+    if !tftp.detected {
+        return Vec::new();
+    }
+    vec![WeakService::new(
+        ip,
+        TFTP_PORT,
+        WeakKind::Tftp,
+        tftp.summary(),
+    )]
+}
+
+async fn check_legacy(ip: IpAddr, open_ports: &[u16]) -> Vec<WeakService> {
+    let mut found = Vec::new();
+    for port in open_ports {
+        let Some(service) = LegacyService::from_port(*port) else {
+            continue;
+        };
+        let legacy = detect_legacy::detect(ip, *port, service).await;
+        if legacy.detected {
+            found.push(WeakService::new(
+                ip,
+                *port,
+                WeakKind::LegacyService,
+                legacy.summary(),
+            ));
+        }
+    }
+    found
+}
+
+async fn check_rtsp(ip: IpAddr, open_ports: &[u16]) -> Vec<WeakService> {
+    let mut found = Vec::new();
+    for port in open_among(open_ports, RTSP_PORTS) {
+        let rtsp = detect_rtsp::detect(ip, port).await;
+        if rtsp.anonymous_stream.is_some() {
+            found.push(WeakService::new(
+                ip,
+                port,
+                WeakKind::AnonymousCameraStream,
+                rtsp.summary(),
+            ));
+        }
+    }
+    found
+}
+
+async fn check_smb(ip: IpAddr, open_ports: &[u16]) -> Vec<WeakService> {
+    let mut found = Vec::new();
+    for port in open_among(open_ports, SMB_PORTS) {
+        let smb = detect_smb::detect(ip, port).await;
+        if smb.smb1_accepted {
+            found.push(WeakService::new(ip, port, WeakKind::Smb1, smb.summary()));
+        }
+    }
+    found
+}
+
+async fn check_http_admin(ip: IpAddr, open_ports: &[u16]) -> Vec<WeakService> {
+    let mut found = Vec::new();
+    for port in open_among(open_ports, HTTP_PORTS) {
+        let Ok(response) = detect_http::fetch(ip, port, "/").await else {
+            continue;
+        };
+        if !response.starts_with("HTTP/") {
+            continue;
+        }
+        if let Some(hint) = detect_http::admin_panel_hint(&response) {
+            found.push(WeakService::new(ip, port, WeakKind::HttpAdminPanel, hint));
+        }
+    }
+    found
+}
+
+async fn check_vnc(ip: IpAddr, open_ports: &[u16]) -> Vec<WeakService> {
+    let mut found = Vec::new();
+    for port in open_among(open_ports, VNC_PORTS) {
+        let vnc = detect_vnc::detect(ip, port).await;
+        if vnc.no_auth() {
+            found.push(WeakService::new(
+                ip,
+                port,
+                WeakKind::VncWithoutAuth,
+                format!(
+                    "RFB {} offers security type None",
+                    vnc.version.as_deref().unwrap_or("?")
+                ),
+            ));
+        }
+    }
+    found
+}
+
+async fn check_proxy(ip: IpAddr, open_ports: &[u16]) -> Vec<WeakService> {
+    let mut found = Vec::new();
+    for port in open_among(open_ports, detect_proxy::SOCKS_PORTS) {
+        let socks = detect_proxy::detect_socks(ip, port).await;
+        if socks.open() {
+            found.push(WeakService::new(
+                ip,
+                port,
+                WeakKind::OpenProxy,
+                socks.summary(),
+            ));
+        }
+    }
+    for port in open_among(open_ports, detect_proxy::HTTP_PROXY_PORTS) {
+        let proxy = detect_proxy::detect_http_proxy(ip, port).await;
+        if proxy.open() {
+            found.push(WeakService::new(
+                ip,
+                port,
+                WeakKind::OpenProxy,
+                proxy.summary(),
+            ));
+        }
+    }
+    found
+}
+
+async fn check_datastores(ip: IpAddr, open_ports: &[u16]) -> Vec<WeakService> {
+    let mut found = Vec::new();
+    let kind = WeakKind::UnauthenticatedDataStore;
+    if open_ports.contains(&detect_elasticsearch::ELASTICSEARCH_PORT) {
+        let port = detect_elasticsearch::ELASTICSEARCH_PORT;
+        let es = detect_elasticsearch::detect(ip, port).await;
+        if es.anonymous_read() {
+            found.push(WeakService::new(ip, port, kind, es.summary()));
+        }
+    }
+    if open_ports.contains(&detect_mongodb::MONGODB_PORT) {
+        let port = detect_mongodb::MONGODB_PORT;
+        let mongo = detect_mongodb::detect(ip, port).await;
+        if mongo.anonymous_read() {
+            found.push(WeakService::new(ip, port, kind, mongo.summary()));
+        }
+    }
+    if open_ports.contains(&detect_couchdb::COUCHDB_PORT) {
+        let port = detect_couchdb::COUCHDB_PORT;
+        let couch = detect_couchdb::detect(ip, port).await;
+        if couch.anonymous_read() {
+            found.push(WeakService::new(ip, port, kind, couch.summary()));
+        }
+    }
+    found
+}
+
+/// Runs the weak-protocol checks relevant to `open_ports` on one host. SNMP
+/// and TFTP are UDP, so they are probed regardless of the TCP results.
+pub async fn check_host(ip: IpAddr, open_ports: &[u16]) -> Vec<WeakService> {
+    let (telnet, ftp, snmp, smb, http, vnc, tftp, legacy, rtsp, proxy, datastores) = tokio::join!(
+        check_telnet(ip, open_ports),
+        check_ftp(ip, open_ports),
+        check_snmp(ip),
+        check_smb(ip, open_ports),
+        check_http_admin(ip, open_ports),
+        check_vnc(ip, open_ports),
+        check_tftp(ip),
+        check_legacy(ip, open_ports),
+        check_rtsp(ip, open_ports),
+        check_proxy(ip, open_ports),
+        check_datastores(ip, open_ports)
+    );
+    [
+        telnet, ftp, snmp, smb, http, vnc, tftp, legacy, rtsp, proxy, datastores,
+    ]
+    .concat()
+}
@@ -0,0 +1,433 @@
+use crate::utils::findings::{self, Finding};
+use crate::utils::{prettyprint, rdns, redact};
+use chrono::{DateTime, Utc};
+use colored::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+/// Annotations, one row per annotated finding
+pub const ANNOTATION_FILE: &str = "annotations.csv";
+/// Findings of the most recent scan, so IDs can be looked up when annotating
+pub const FINDINGS_FILE: &str = "findings.csv";
+
+const ANNOTATION_HEADER: [&str; 7] = [
+    "id",
+    "verdict",
+    "comment",
+    "annotated_at",
+    "finding",
+    "mac",
+    "subject",
+];
+const FINDINGS_HEADER: [&str; 5] = ["id", "host", "finding", "mac", "subject"];
+
+/// Why a finding should no longer be reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    FalsePositive,
+    AcceptedRisk,
+}
+
+impl Verdict {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Verdict::FalsePositive => "false-positive",
+            Verdict::AcceptedRisk => "accepted-risk",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "false-positive" => Some(Verdict::FalsePositive),
+            "accepted-risk" => Some(Verdict::AcceptedRisk),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// A user's verdict on one finding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub id: String,
+    pub verdict: Verdict,
+    pub comment: String,
+    pub at: DateTime<Utc>,
+    /// The finding as recorded when it was annotated
+    pub finding: String,
+    /// MAC of the host the finding was about, if the scan learned it
+    pub mac: Option<String>,
+    /// The finding's `findings::subject_id`, which with `mac` follows the
+    /// host to a new address
+    pub subject: Option<String>,
+}
+
+/// Annotations kept in the state directory
+#[derive(Debug, Clone)]
+pub struct AnnotationStore {
+    dir: PathBuf,
+    annotations: BTreeMap<String, Annotation>,
+    /// MACs of this run's hosts, see `follow`
+    macs: BTreeMap<IpAddr, String>,
+}
+
+impl AnnotationStore {
+    /// A store in `dir` without annotations.
+    pub fn empty(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            annotations: BTreeMap::new(),
+            macs: BTreeMap::new(),
+        }
+    }
+
+    /// Reads the annotations in `dir`; none if it has no annotation file.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let mut store = Self::empty(dir);
+        let path = dir.join(ANNOTATION_FILE);
+        if !path.exists() {
+            return Ok(store);
+        }
+        let mut reader = csv::Reader::from_path(&path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        for (line, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| e.to_string())?;
+            let bad_row = || format!("{}: malformed row {}", path.display(), line + 2);
+            let field = |i: usize| record.get(i).ok_or_else(bad_row);
+            // Files written before hosts were followed by MAC end at "finding"
+            let optional = |i: usize| record.get(i).filter(|f| !f.is_empty()).map(String::from);
+            let annotation = Annotation {
+                id: field(0)?.to_string(),
+                verdict: Verdict::parse(field(1)?).ok_or_else(bad_row)?,
+                comment: field(2)?.to_string(),
+                at: DateTime::parse_from_rfc3339(field(3)?)
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|_| bad_row())?,
+                finding: field(4)?.to_string(),
+                mac: optional(5),
+                subject: optional(6),
+            };
+            store.annotations.insert(annotation.id.clone(), annotation);
+        }
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).map_err(|e| {
+            format!(
+                "Could not create state directory {}: {}",
+                self.dir.display(),
+                e
+            )
+        })?;
+        let path = self.dir.join(ANNOTATION_FILE);
+        let mut writer = csv::Writer::from_path(&path)
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+        writer
+            .write_record(ANNOTATION_HEADER)
+            .map_err(|e| e.to_string())?;
+        for annotation in self.annotations.values() {
+            writer
+                .write_record([
+                    annotation.id.as_str(),
+                    annotation.verdict.label(),
+                    &annotation.comment,
+                    &annotation.at.to_rfc3339(),
+                    &annotation.finding,
+                    annotation.mac.as_deref().unwrap_or_default(),
+                    annotation.subject.as_deref().unwrap_or_default(),
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+        writer.flush().map_err(|e| e.to_string())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Annotation> {
+        self.annotations.get(id)
+    }
+
+    pub fn annotations(&self) -> impl Iterator<Item = &Annotation> {
+        self.annotations.values()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.annotations.is_empty()
+    }
+
+    /// Annotates the finding `id` recorded by the last scan, replacing any
+    /// earlier annotation of it. Does not save.
+    pub fn annotate(
+        &mut self,
+        id: &str,
+        verdict: Verdict,
+        comment: &str,
+        now: DateTime<Utc>,
+    ) -> Result<&Annotation, String> {
+        let recorded = recorded_finding(&self.dir, id)?.ok_or_else(|| {
+            format!(
+                "No finding with ID {} in the last scan ({})",
+                id, FINDINGS_FILE
+            )
+        })?;
+        let annotation = Annotation {
+            id: id.to_string(),
+            verdict,
+            comment: comment.to_string(),
+            at: now,
+            finding: recorded.finding,
+            mac: recorded.mac,
+            subject: recorded.subject,
+        };
+        self.annotations.insert(id.to_string(), annotation);
+        Ok(&self.annotations[id])
+    }
+
+    /// Removes the annotation of `id` so the finding is reported again. Does not save.
+    pub fn remove(&mut self, id: &str) -> Option<Annotation> {
+        self.annotations.remove(id)
+    }
+
+    /// Follows the host at `ip` with `mac` (if this run learned it), so
+    /// annotations made while it had another address still apply.
+    pub fn follow(&mut self, ip: IpAddr, mac: Option<&str>) {
+        if let Some(mac) = mac {
+            self.macs.insert(ip, mac.to_ascii_lowercase());
+        }
+    }
+
+    /// The annotation of `finding` about `ip`: the one with its ID, else one
+    /// of the same finding on the same host, by MAC, at an earlier address.
+    /// An ID match made on another host that now has the address is ignored.
+    fn annotation_of(&self, ip: IpAddr, finding: &Finding) -> Option<&Annotation> {
+        let mac = self.macs.get(&ip);
+        self.annotations
+            .get(&findings::finding_id(ip, finding))
+            .filter(|annotation| match (&annotation.mac, mac) {
+                (Some(noted), Some(mac)) => noted == mac,
+                _ => true,
+            })
+            .or_else(|| {
+                let mac = mac?;
+                let subject = findings::subject_id(finding);
+                self.annotations.values().find(|annotation| {
+                    annotation.mac.as_ref() == Some(mac)
+                        && annotation.subject.as_ref() == Some(&subject)
+                })
+            })
+    }
+
+    /// Whether an annotation suppresses `finding` about `ip`.
+    pub fn suppresses(&self, ip: IpAddr, finding: &Finding) -> bool {
+        self.annotation_of(ip, finding).is_some()
+    }
+
+    /// Annotations of findings on the host with `mac`.
+    pub fn for_host(&self, mac: &str) -> Vec<&Annotation> {
+        self.annotations
+            .values()
+            .filter(|annotation| {
+                annotation
+                    .mac
+                    .as_deref()
+                    .is_some_and(|noted| noted.eq_ignore_ascii_case(mac))
+            })
+            .collect()
+    }
+
+    /// Moves annotations that matched a finding by MAC to the finding's ID at
+    /// its host's current address, so `netscan annotate --remove` takes the
+    /// ID this run printed. Returns whether any moved. Does not save.
+    pub fn carry_forward(&mut self, host_findings: &BTreeMap<IpAddr, Vec<Finding>>) -> bool {
+        let mut changed = false;
+        for (ip, list) in host_findings {
+            for finding in list {
+                let id = findings::finding_id(*ip, finding);
+                let Some(old) = self.annotation_of(*ip, finding).map(|a| a.id.clone()) else {
+                    continue;
+                };
+                if old != id
+                    && let Some(mut annotation) = self.annotations.remove(&old)
+                {
+                    annotation.id = id.clone();
+                    self.annotations.insert(id, annotation);
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Splits per-host findings into those still reported and those an
+    /// annotation suppresses.
+    pub fn apply(&self, host_findings: &BTreeMap<IpAddr, Vec<Finding>>) -> AnnotatedFindings {
+        let mut result = AnnotatedFindings::default();
+        for (ip, list) in host_findings {
+            let kept = result.reported.entry(*ip).or_default();
+            for finding in list {
+                match self.annotation_of(*ip, finding) {
+                    Some(annotation) => result.suppressed.push(SuppressedFinding {
+                        ip: *ip,
+                        finding: finding.clone(),
+                        annotation: annotation.clone(),
+                    }),
+                    None => kept.push(finding.clone()),
+                }
+            }
+        }
+        result
+    }
+}
+
+/// A finding left out of reports because of its annotation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuppressedFinding {
+    pub ip: IpAddr,
+    pub finding: Finding,
+    pub annotation: Annotation,
+}
+
+/// Findings after annotations are applied
+#[derive(Debug, Clone, Default)]
+pub struct AnnotatedFindings {
+    pub reported: BTreeMap<IpAddr, Vec<Finding>>,
+    pub suppressed: Vec<SuppressedFinding>,
+}
+
+impl AnnotatedFindings {
+    pub fn reported_for(&self, ip: IpAddr) -> &[Finding] {
+        self.reported.get(&ip).map_or(&[], |f| f.as_slice())
+    }
+
+    /// Prints the reported findings with their IDs, and the suppressed ones as footnotes.
+    pub fn print_report(&self) {
+        println!(
+            "\n{}",
+            prettyprint::console("🧾 Findings")
+                .bold()
+                .underline()
+                .blue()
+        );
+        for (ip, list) in &self.reported {
+            let mut list = list.clone();
+            findings::sort_by_severity(&mut list);
+            for finding in &list {
+                println!(
+                    "  {} [{}] {}: {} {}",
+                    rdns::label(*ip).bold(),
+                    finding.severity.colored(),
+                    redact::text(&finding.title),
+                    redact::text(&finding.detail),
+                    format!("({})", findings::finding_id(*ip, finding)).dimmed()
+                );
+            }
+        }
+        if self.reported.values().all(|list| list.is_empty()) {
+            println!("{}", "  No findings to report.".green());
+        }
+        if self.suppressed.is_empty() {
+            return;
+        }
+        println!(
+            "{}",
+            format!(
+                "  {} finding(s) suppressed by annotations:",
+                self.suppressed.len()
+            )
+            .dimmed()
+        );
+        for suppressed in &self.suppressed {
+            let annotation = &suppressed.annotation;
+            let comment = if annotation.comment.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", annotation.comment)
+            };
+            println!(
+                "{}",
+                prettyprint::console(&format!(
+                    "    † {} {} ({}{})",
+                    redact::ip(suppressed.ip),
+                    redact::text(&suppressed.finding.title),
+                    annotation.verdict,
+                    redact::text(&comment)
+                ))
+                .dimmed()
+            );
+        }
+    }
+}
+
+/// Records this scan's findings so `netscan annotate` can look their IDs up;
+/// `mac_of` gives a host's MAC when it is known. The IDs and MACs stay those
+/// of the real hosts; the rest is redacted under `--redact`.
+pub fn save_findings(
+    dir: &Path,
+    host_findings: &BTreeMap<IpAddr, Vec<Finding>>,
+    mac_of: impl Fn(IpAddr) -> Option<String>,
+) -> Result<(), String> {
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Could not create state directory {}: {}", dir.display(), e))?;
+    let path = dir.join(FINDINGS_FILE);
+    let mut writer = csv::Writer::from_path(&path)
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    writer
+        .write_record(FINDINGS_HEADER)
+        .map_err(|e| e.to_string())?;
+    for (ip, list) in host_findings {
+        let mac = mac_of(*ip).map(|mac| mac.to_ascii_lowercase());
+        for finding in list {
+            writer
+                .write_record([
+                    findings::finding_id(*ip, finding),
+                    redact::ip(*ip).to_string(),
+                    redact::text(&finding.to_string()),
+                    mac.clone().unwrap_or_default(),
+                    findings::subject_id(finding),
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+/// A row of the findings file
+struct RecordedFinding {
+    /// "host: finding"
+    finding: String,
+    mac: Option<String>,
+    subject: Option<String>,
+}
+
+/// Finding `id` as the last scan recorded it.
+fn recorded_finding(dir: &Path, id: &str) -> Result<Option<RecordedFinding>, String> {
+    let path = dir.join(FINDINGS_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut reader = csv::Reader::from_path(&path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        if record.get(0) == Some(id) {
+            let optional = |i: usize| record.get(i).filter(|f| !f.is_empty()).map(String::from);
+            return Ok(Some(RecordedFinding {
+                finding: format!(
+                    "{}: {}",
+                    record.get(1).unwrap_or_default(),
+                    record.get(2).unwrap_or_default()
+                ),
+                mac: optional(3),
+                subject: optional(4),
+            }));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,44 @@
+use std::fmt;
+use std::net::IpAddr;
+
+/// Fewer probes than this never mark a host suspect; small scans of busy hosts are normal
+pub const MIN_PROBED_PORTS: usize = 30;
+/// Fraction of probed ports reported open above which results are implausible
+pub const SUSPECT_OPEN_RATIO: f64 = 0.6;
+
+/// A host whose open-port count looks like an inline IPS, tarpit or
+/// honeypot answering every SYN rather than real listening services
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuspectHost {
+    pub ip: IpAddr,
+    pub open: usize,
+    pub probed: usize,
+}
+
+impl SuspectHost {
+    pub fn open_ratio(&self) -> f64 {
+        self.open as f64 / self.probed as f64
+    }
+}
+
+impl fmt::Display for SuspectHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} of {} probed ports ({:.0}%) answered as open; likely a tarpit or IPS accepting every connection",
+            self.ip,
+            self.open,
+            self.probed,
+            self.open_ratio() * 100.0
+        )
+    }
+}
+
+/// Returns a `SuspectHost` if `open` of `probed` ports is implausibly many.
+pub fn assess(ip: IpAddr, open: usize, probed: usize) -> Option<SuspectHost> {
+    if probed < MIN_PROBED_PORTS {
+        return None;
+    }
+    let host = SuspectHost { ip, open, probed };
+    (host.open_ratio() >= SUSPECT_OPEN_RATIO).then_some(host)
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+use std::fs;
+
+/// Concurrency when no limit could be read
+pub const DEFAULT_CONCURRENCY: usize = 64;
+/// Fewest probes in flight the auto-tuning settles on
+pub const MIN_CONCURRENCY: usize = 16;
+/// Most probes in flight the auto-tuning settles on
+pub const MAX_CONCURRENCY: usize = 4096;
+/// File descriptors kept free for stdio, reports, the history database and raw sockets
+const RESERVED_FDS: u64 = 128;
+/// Memory budgeted per probe in flight: its task, buffers and kernel socket
+const BYTES_PER_PROBE: u64 = 256 * 1024;
+
+/// Limits of this machine that cap how many probes can be in flight
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemLimits {
+    /// Soft limit on open files (`ulimit -n`)
+    pub open_files: Option<u64>,
+    /// MemAvailable, in bytes
+    pub available_memory: Option<u64>,
+    /// Connections the netfilter connection tracking table holds
+    pub conntrack_max: Option<u64>,
+    /// Connections it tracks now
+    pub conntrack_count: Option<u64>,
+}
+
+impl SystemLimits {
+    /// Reads the limits from /proc; the ones it cannot read stay unknown.
+    pub fn read() -> Self {
+        let read = |path: &str| fs::read_to_string(path).ok();
+        let number = |path: &str| read(path).and_then(|text| text.trim().parse().ok());
+        Self {
+            open_files: read("/proc/self/limits").and_then(|text| parse_open_files(&text)),
+            available_memory: read("/proc/meminfo").and_then(|text| parse_mem_available(&text)),
+            conntrack_max: number("/proc/sys/net/netfilter/nf_conntrack_max"),
+            conntrack_count: number("/proc/sys/net/netfilter/nf_conntrack_count"),
+        }
+    }
+
+    /// Probes that can safely be in flight at once: each holds a socket, some
+    /// memory and, behind a stateful firewall, a conntrack entry. Half of the
+    /// free conntrack entries and a quarter of the available memory are used.
+    pub fn concurrency(&self) -> usize {
+        let budgets = [
+            self.open_files
+                .map(|limit| limit.saturating_sub(RESERVED_FDS)),
+            self.available_memory
+                .map(|bytes| bytes / 4 / BYTES_PER_PROBE),
+            self.conntrack_max
+                .map(|max| max.saturating_sub(self.conntrack_count.unwrap_or(0)) / 2),
+        ];
+        match budgets.into_iter().flatten().min() {
+            Some(budget) => (budget.min(MAX_CONCURRENCY as u64) as usize).max(MIN_CONCURRENCY),
+            None => DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+impl fmt::Display for SystemLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(limit) = self.open_files {
+            parts.push(format!("{} open files", limit));
+        }
+        if let Some(bytes) = self.available_memory {
+            parts.push(format!("{} MiB free", bytes / (1024 * 1024)));
+        }
+        if let Some(max) = self.conntrack_max {
+            parts.push(format!(
+                "conntrack {}/{}",
+                self.conntrack_count.unwrap_or(0),
+                max
+            ));
+        }
+        if parts.is_empty() {
+            write!(f, "no limits readable")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// Soft "Max open files" of a /proc/<pid>/limits listing; `None` if unlimited.
+pub fn parse_open_files(limits: &str) -> Option<u64> {
+    let line = limits
+        .lines()
+        .find(|line| line.starts_with("Max open files"))?;
+    line["Max open files".len()..]
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// MemAvailable of a /proc/meminfo listing, in bytes.
+pub fn parse_mem_available(meminfo: &str) -> Option<u64> {
+    let line = meminfo
+        .lines()
+        .find(|line| line.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
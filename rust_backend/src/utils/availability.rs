@@ -0,0 +1,230 @@
+use crate::utils::prettyprint;
+use chrono::{DateTime, Utc};
+use colored::*;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::Path;
+
+const STATE_HEADER: [&str; 8] = [
+    "ip",
+    "port",
+    "checks",
+    "up_checks",
+    "flaps",
+    "up",
+    "first_seen",
+    "last_change",
+];
+
+/// Reachability history of one TCP service across monitor runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceAvailability {
+    pub ip: IpAddr,
+    pub port: u16,
+    /// Runs that probed the service since it was first seen open
+    pub checks: u32,
+    /// Runs in which it was open
+    pub up_checks: u32,
+    /// Times it went from up to down or back
+    pub flaps: u32,
+    /// State at the latest check
+    pub up: bool,
+    pub first_seen: DateTime<Utc>,
+    pub last_change: DateTime<Utc>,
+}
+
+impl ServiceAvailability {
+    /// Share of checks the service was up in, as a percentage.
+    pub fn availability(&self) -> f64 {
+        if self.checks == 0 {
+            return 0.0;
+        }
+        f64::from(self.up_checks) * 100.0 / f64::from(self.checks)
+    }
+}
+
+/// A service that changed state in the latest run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateChange {
+    pub ip: IpAddr,
+    pub port: u16,
+    pub up: bool,
+}
+
+/// Availability of every service monitor mode has seen open
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AvailabilityTracker {
+    services: BTreeMap<(IpAddr, u16), ServiceAvailability>,
+}
+
+impl AvailabilityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.services.is_empty()
+    }
+
+    pub fn get(&self, ip: IpAddr, port: u16) -> Option<&ServiceAvailability> {
+        self.services.get(&(ip, port))
+    }
+
+    pub fn services(&self) -> impl Iterator<Item = &ServiceAvailability> {
+        self.services.values()
+    }
+
+    /// Records one scan of `ports` on `targets` that found `open` open.
+    /// Open ports not seen before start being tracked; tracked services the
+    /// scan covered but did not find open count as down, including those of
+    /// hosts that did not answer discovery. Returns the services that went
+    /// down or came back.
+    pub fn record(
+        &mut self,
+        targets: &[IpAddr],
+        ports: &[u16],
+        open: &[(IpAddr, u16)],
+        now: DateTime<Utc>,
+    ) -> Vec<StateChange> {
+        for &(ip, port) in open {
+            self.services
+                .entry((ip, port))
+                .or_insert_with(|| ServiceAvailability {
+                    ip,
+                    port,
+                    checks: 0,
+                    up_checks: 0,
+                    flaps: 0,
+                    up: true,
+                    first_seen: now,
+                    last_change: now,
+                });
+        }
+        let mut changes = Vec::new();
+        for service in self.services.values_mut() {
+            if !targets.contains(&service.ip) || !ports.contains(&service.port) {
+                continue; // not part of this scan
+            }
+            let up = open.contains(&(service.ip, service.port));
+            service.checks += 1;
+            if up {
+                service.up_checks += 1;
+            }
+            if up != service.up {
+                service.up = up;
+                service.flaps += 1;
+                service.last_change = now;
+                changes.push(StateChange {
+                    ip: service.ip,
+                    port: service.port,
+                    up,
+                });
+            }
+        }
+        changes
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut writer = csv::Writer::from_path(path)
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+        writer
+            .write_record(STATE_HEADER)
+            .map_err(|e| e.to_string())?;
+        for service in self.services.values() {
+            writer
+                .write_record([
+                    service.ip.to_string(),
+                    service.port.to_string(),
+                    service.checks.to_string(),
+                    service.up_checks.to_string(),
+                    service.flaps.to_string(),
+                    service.up.to_string(),
+                    service.first_seen.to_rfc3339(),
+                    service.last_change.to_rfc3339(),
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+        writer.flush().map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut reader = csv::Reader::from_path(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let mut tracker = Self::new();
+        for (line, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| e.to_string())?;
+            let bad_row = || format!("{}: malformed row {}", path.display(), line + 2);
+            let field = |i: usize| record.get(i).ok_or_else(bad_row);
+            let time = |i: usize| {
+                DateTime::parse_from_rfc3339(field(i)?)
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|_| bad_row())
+            };
+            let count = |i: usize| field(i)?.parse::<u32>().map_err(|_| bad_row());
+            let service = ServiceAvailability {
+                ip: field(0)?.parse().map_err(|_| bad_row())?,
+                port: field(1)?.parse().map_err(|_| bad_row())?,
+                checks: count(2)?,
+                up_checks: count(3)?,
+                flaps: count(4)?,
+                up: field(5)? == "true",
+                first_seen: time(6)?,
+                last_change: time(7)?,
+            };
+            tracker.services.insert((service.ip, service.port), service);
+        }
+        Ok(tracker)
+    }
+
+    pub fn print_report(&self) {
+        println!(
+            "\n{}",
+            prettyprint::console("📈 Service Availability")
+                .bold()
+                .underline()
+                .blue()
+        );
+        if self.services.is_empty() {
+            println!("{}", "No services tracked yet.".yellow());
+            return;
+        }
+        println!(
+            "{:<28} {:<6} {:>12} {:>7} {:>6}",
+            "Service".bold().cyan(),
+            "State".bold().cyan(),
+            "Availability".bold().cyan(),
+            "Checks".bold().cyan(),
+            "Flaps".bold().cyan()
+        );
+        println!("{}", "-".repeat(63).dimmed());
+        for service in self.services.values() {
+            let state = if service.up {
+                "up".green()
+            } else {
+                "down".red().bold()
+            };
+            let availability = format!("{:.1}%", service.availability());
+            let availability = if service.availability() >= 99.0 {
+                availability.green()
+            } else if service.availability() >= 90.0 {
+                availability.yellow()
+            } else {
+                availability.red()
+            };
+            let flaps = if service.flaps > 0 {
+                service.flaps.to_string().yellow()
+            } else {
+                service.flaps.to_string().normal()
+            };
+            println!(
+                "{:<28} {:<6} {:>12} {:>7} {:>6}",
+                format!("{}", std::net::SocketAddr::new(service.ip, service.port)),
+                state,
+                availability,
+                service.checks,
+                flaps
+            );
+        }
+        println!("{}", "-".repeat(63).dimmed());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::utils::evidence;
+
+/// Product, version and OS hint read from a service banner
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BannerInfo {
+    /// Server software, e.g. "OpenSSH" or "Postfix"
+    pub product: Option<String>,
+    pub version: Option<String>,
+    /// OS the banner names, e.g. "Linux (Ubuntu)"
+    pub os_hint: Option<String>,
+}
+
+/// Server software that names itself in FTP and SMTP greetings, as it is
+/// matched (case-insensitively) and as it is reported
+const PRODUCTS: &[(&str, &str)] = &[
+    ("vsftpd", "vsftpd"),
+    ("proftpd", "ProFTPD"),
+    ("pure-ftpd", "Pure-FTPd"),
+    ("filezilla server", "FileZilla Server"),
+    ("microsoft ftp service", "Microsoft FTP Service"),
+    ("wu-ftpd", "wu-ftpd"),
+    ("postfix", "Postfix"),
+    ("exim", "Exim"),
+    ("sendmail", "Sendmail"),
+    ("opensmtpd", "OpenSMTPD"),
+    (
+        "microsoft esmtp mail service",
+        "Microsoft ESMTP MAIL Service",
+    ),
+    ("qmail", "qmail"),
+    ("haraka", "Haraka"),
+    ("dovecot", "Dovecot"),
+];
+
+/// Reads whatever a banner says about the software behind it.
+pub fn parse(banner: &str) -> BannerInfo {
+    let line = banner.lines().next().unwrap_or_default().trim();
+    let mut info = match line.strip_prefix("SSH-") {
+        Some(ident) => parse_ssh(ident),
+        None => parse_greeting(line),
+    };
+    info.os_hint = evidence::os_named_in(banner).map(str::to_string);
+    info
+}
+
+/// "2.0-OpenSSH_8.9p1 Ubuntu-3": the software field is "name_version",
+/// or "name-version" for some embedded servers.
+fn parse_ssh(ident: &str) -> BannerInfo {
+    let Some((_protocol, rest)) = ident.split_once('-') else {
+        return BannerInfo::default();
+    };
+    let software = rest.split_whitespace().next().unwrap_or_default();
+    let split = software.split_once('_').or_else(|| {
+        software
+            .rsplit_once('-')
+            .filter(|(_, version)| version.starts_with(|c: char| c.is_ascii_digit()))
+    });
+    let (product, version) = match split {
+        Some((product, version)) => (product, Some(version)),
+        None => (software, None),
+    };
+    BannerInfo {
+        product: (!product.is_empty()).then(|| product.to_string()),
+        version: version.filter(|v| !v.is_empty()).map(str::to_string),
+        os_hint: None,
+    }
+}
+
+/// FTP/SMTP greetings name the product somewhere in free text, usually
+/// followed by its version: "220 (vsFTPd 3.0.3)", "220 mx ESMTP Exim 4.96".
+fn parse_greeting(line: &str) -> BannerInfo {
+    let lower = line.to_ascii_lowercase();
+    let Some((start, needle, product)) = PRODUCTS
+        .iter()
+        .filter_map(|&(needle, product)| lower.find(needle).map(|start| (start, needle, product)))
+        .min_by_key(|(start, _, _)| *start)
+    else {
+        return BannerInfo::default();
+    };
+    let version = line[start + needle.len()..]
+        .split_whitespace()
+        .next()
+        .map(|token| {
+            // "8.15.2/8.15.2;" and "3.0.3)" carry punctuation around the version
+            token
+                .split(['/', ';', ',', ')', ']'])
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches(['v', 'V'])
+        })
+        .filter(|token| token.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string);
+    BannerInfo {
+        product: Some(product.to_string()),
+        version,
+        os_hint: None,
+    }
+}
@@ -0,0 +1,416 @@
+use crate::utils::findings::{Finding, Severity, code};
+use crate::utils::{prettyprint, redact, sockets};
+use colored::*;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const MISSING_LEGAL_BANNER: &str = "Missing legal warning banner";
+
+/// Words a legal warning banner is expected to contain at least one of
+pub const LEGAL_KEYWORDS: &[&str] = &[
+    "authorized",
+    "unauthorized",
+    "prohibited",
+    "monitored",
+    "consent",
+    "prosecut",
+    "legal",
+    "warning",
+];
+
+const TELNET_IAC: u8 = 0xff;
+const TELNET_DONT: u8 = 0xfe;
+const TELNET_DO: u8 = 0xfd;
+const TELNET_WONT: u8 = 0xfc;
+const TELNET_WILL: u8 = 0xfb;
+const TELNET_SB: u8 = 0xfa;
+const TELNET_SE: u8 = 0xf0;
+
+/// Service a pre-authentication banner was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BannerSource {
+    Ssh,
+    Telnet,
+    Ftp,
+}
+
+impl BannerSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BannerSource::Ssh => "SSH",
+            BannerSource::Telnet => "Telnet",
+            BannerSource::Ftp => "FTP",
+        }
+    }
+
+    pub fn from_port(port: u16) -> Option<Self> {
+        match port {
+            22 | 2222 => Some(BannerSource::Ssh),
+            23 | 2323 => Some(BannerSource::Telnet),
+            21 => Some(BannerSource::Ftp),
+            _ => None,
+        }
+    }
+}
+
+/// Verbatim pre-authentication text one service sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginBanner {
+    pub ip: IpAddr,
+    pub port: u16,
+    pub source: BannerSource,
+    pub text: String,
+    /// Whether an SSH server got as far as user authentication, where it
+    /// sends its configured `Banner`; appended to `text` when present.
+    pub reached_userauth: bool,
+}
+
+impl LoginBanner {
+    pub fn has_legal_warning(&self) -> bool {
+        has_legal_warning(&self.text)
+    }
+
+    /// Finding for a judged banner without legal wording.
+    pub fn finding(&self) -> Option<Finding> {
+        if self.has_legal_warning() || !self.is_judged() {
+            return None;
+        }
+        Some(
+            Finding::new(
+                Severity::Low,
+                Some(self.port),
+                MISSING_LEGAL_BANNER,
+                format!(
+                    "{}: {} banner carries no authorized-use warning",
+                    self.ip,
+                    self.source.label()
+                ),
+            )
+            .with_code(code::MISSING_LEGAL_BANNER),
+        )
+    }
+
+    /// Whether the banner shows if a legal warning is displayed. SSH is only
+    /// judged once user authentication was reached or the server sent lines
+    /// before its identification string.
+    pub fn is_judged(&self) -> bool {
+        self.source != BannerSource::Ssh
+            || self.reached_userauth
+            || !ssh_pre_identification(&self.text).is_empty()
+    }
+}
+
+pub fn has_legal_warning(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    LEGAL_KEYWORDS.iter().any(|word| lower.contains(word))
+}
+
+/// Lines an SSH server sent before its `SSH-` identification string.
+pub fn ssh_pre_identification(text: &str) -> Vec<&str> {
+    text.lines()
+        .take_while(|line| !line.starts_with("SSH-"))
+        .filter(|line| !line.trim().is_empty())
+        .collect()
+}
+
+/// Userauth banner in the stderr of an `ssh` client that offered only the
+/// `none` method; `None` when the client never got to user authentication.
+pub fn parse_ssh_client_output(stderr: &str) -> Option<String> {
+    let lines: Vec<&str> = stderr.lines().collect();
+    if !lines.iter().any(|line| line.contains("Permission denied")) {
+        return None;
+    }
+    let banner: Vec<&str> = lines
+        .into_iter()
+        .filter(|line| !line.starts_with("Warning: Permanently added"))
+        .filter(|line| !line.contains("Permission denied"))
+        .collect();
+    Some(banner.join("\n"))
+}
+
+/// Splits Telnet input into printable text and the refusals to send back:
+/// every DO is answered WONT and every WILL is answered DONT, so the server
+/// moves on to its login prompt.
+pub fn strip_telnet_negotiation(input: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut text = Vec::new();
+    let mut replies = Vec::new();
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] != TELNET_IAC {
+            text.push(input[i]);
+            i += 1;
+            continue;
+        }
+        match input.get(i + 1) {
+            Some(&TELNET_DO) | Some(&TELNET_DONT) | Some(&TELNET_WILL) | Some(&TELNET_WONT) => {
+                let command = input[i + 1];
+                if let Some(&option) = input.get(i + 2) {
+                    match command {
+                        TELNET_DO => replies.extend_from_slice(&[TELNET_IAC, TELNET_WONT, option]),
+                        TELNET_WILL => {
+                            replies.extend_from_slice(&[TELNET_IAC, TELNET_DONT, option])
+                        }
+                        _ => {}
+                    }
+                }
+                i += 3;
+            }
+            Some(&TELNET_SB) => {
+                let end = input[i..]
+                    .windows(2)
+                    .position(|w| w == [TELNET_IAC, TELNET_SE])
+                    .map_or(input.len(), |pos| i + pos + 2);
+                i = end;
+            }
+            Some(&TELNET_IAC) => {
+                text.push(TELNET_IAC);
+                i += 2;
+            }
+            _ => i += 2,
+        }
+    }
+    (text, replies)
+}
+
+/// Reads until the server goes quiet for `idle` or `limit` bytes arrive.
+async fn read_until_idle(stream: &mut TcpStream, idle: Duration, limit: usize) -> Vec<u8> {
+    let mut collected = Vec::new();
+    let mut buf = [0u8; 1024];
+    while collected.len() < limit {
+        match tokio::time::timeout(idle, stream.read(&mut buf)).await {
+            Ok(Ok(n)) if n > 0 => collected.extend_from_slice(&buf[..n]),
+            _ => break,
+        }
+    }
+    collected
+}
+
+async fn connect(ip: IpAddr, port: u16) -> Result<TcpStream, String> {
+    match tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect(sockets::socket_addr(ip, port)),
+    )
+    .await
+    {
+        Ok(Ok(stream)) => Ok(stream),
+        _ => Err("Connection failed".to_string()),
+    }
+}
+
+/// Everything the server sends up to and including its identification line.
+async fn read_ssh(ip: IpAddr, port: u16) -> Result<String, String> {
+    let mut stream = connect(ip, port).await?;
+    let mut collected = Vec::new();
+    let mut buf = [0u8; 1024];
+    while collected.len() < 8192 {
+        match tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await {
+            Ok(Ok(n)) if n > 0 => collected.extend_from_slice(&buf[..n]),
+            _ => break,
+        }
+        let text = String::from_utf8_lossy(&collected);
+        if text.lines().any(|line| line.starts_with("SSH-")) {
+            break;
+        }
+    }
+    let text = String::from_utf8_lossy(&collected).to_string();
+    if !text.contains("SSH-") {
+        return Err("No SSH identification".to_string());
+    }
+    let end = text.find("SSH-").unwrap_or(0);
+    let ident_end = text[end..]
+        .find('\n')
+        .map_or(text.len(), |pos| end + pos + 1);
+    Ok(text[..ident_end].to_string())
+}
+
+/// The banner an SSH server sends at user authentication. Reading it needs a
+/// full key exchange, so the system `ssh` client does it, offering only the
+/// `none` method so it never authenticates.
+async fn read_ssh_userauth(ip: IpAddr, port: u16) -> Option<String> {
+    let output = tokio::process::Command::new("ssh")
+        .args([
+            "-T",
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            "PreferredAuthentications=none",
+            "-o",
+            "StrictHostKeyChecking=no",
+            "-o",
+            "UserKnownHostsFile=/dev/null",
+            "-o",
+            "ConnectTimeout=5",
+            "-p",
+            &port.to_string(),
+            &format!("netscan@{}", ip),
+            "true",
+        ])
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(Duration::from_secs(15), output)
+        .await
+        .ok()?
+        .ok()?;
+    parse_ssh_client_output(&String::from_utf8_lossy(&output.stderr))
+}
+
+async fn read_telnet(ip: IpAddr, port: u16) -> Result<String, String> {
+    let mut stream = connect(ip, port).await?;
+    let mut text = Vec::new();
+    // negotiation can take a few round trips before the banner arrives
+    for _ in 0..4 {
+        let chunk = read_until_idle(&mut stream, Duration::from_secs(2), 8192).await;
+        if chunk.is_empty() {
+            break;
+        }
+        let (printable, replies) = strip_telnet_negotiation(&chunk);
+        text.extend_from_slice(&printable);
+        if replies.is_empty() {
+            break;
+        }
+        if stream.write_all(&replies).await.is_err() {
+            break;
+        }
+    }
+    Ok(String::from_utf8_lossy(&text).to_string())
+}
+
+/// The 220 greeting, including every line of a multi-line reply.
+async fn read_ftp(ip: IpAddr, port: u16) -> Result<String, String> {
+    let mut stream = connect(ip, port).await?;
+    let mut collected = Vec::new();
+    let mut buf = [0u8; 1024];
+    while collected.len() < 8192 {
+        match tokio::time::timeout(Duration::from_secs(3), stream.read(&mut buf)).await {
+            Ok(Ok(n)) if n > 0 => collected.extend_from_slice(&buf[..n]),
+            _ => break,
+        }
+        let text = String::from_utf8_lossy(&collected);
+        if text
+            .lines()
+            .any(|line| line.starts_with("220 ") || line == "220")
+        {
+            break;
+        }
+    }
+    let text = String::from_utf8_lossy(&collected).to_string();
+    if !text.starts_with("220") {
+        return Err("No FTP greeting".to_string());
+    }
+    Ok(text)
+}
+
+/// Reads the pre-authentication banner `source` sends on `port`.
+pub async fn capture(ip: IpAddr, port: u16, source: BannerSource) -> Result<LoginBanner, String> {
+    let mut reached_userauth = false;
+    let text = match source {
+        BannerSource::Ssh => {
+            let mut text = read_ssh(ip, port).await?;
+            if let Some(banner) = read_ssh_userauth(ip, port).await {
+                reached_userauth = true;
+                if !banner.trim().is_empty() {
+                    text.push_str(&banner);
+                    text.push('\n');
+                }
+            }
+            text
+        }
+        BannerSource::Telnet => read_telnet(ip, port).await?,
+        BannerSource::Ftp => read_ftp(ip, port).await?,
+    };
+    Ok(LoginBanner {
+        ip,
+        port,
+        source,
+        text,
+        reached_userauth,
+    })
+}
+
+/// Captures banners from every open SSH, Telnet and FTP port on a host.
+pub async fn capture_host(ip: IpAddr, open_ports: &[u16]) -> Vec<LoginBanner> {
+    let mut banners = Vec::new();
+    for port in open_ports {
+        let Some(source) = BannerSource::from_port(*port) else {
+            continue;
+        };
+        if let Ok(banner) = capture(ip, *port, source).await {
+            banners.push(banner);
+        }
+    }
+    banners
+}
+
+/// Banners collected across a scan
+#[derive(Debug, Clone, Default)]
+pub struct BannerInventory {
+    banners: Vec<LoginBanner>,
+}
+
+impl BannerInventory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn extend(&mut self, banners: Vec<LoginBanner>) {
+        self.banners.extend(banners);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.banners.is_empty()
+    }
+
+    /// Banners ordered by host and port.
+    pub fn banners(&self) -> Vec<&LoginBanner> {
+        let mut sorted: Vec<&LoginBanner> = self.banners.iter().collect();
+        sorted.sort_by_key(|b| (b.ip, b.port));
+        sorted
+    }
+
+    pub fn findings(&self) -> Vec<Finding> {
+        self.banners().iter().filter_map(|b| b.finding()).collect()
+    }
+
+    pub fn print_summary(&self) {
+        println!(
+            "\n{}",
+            prettyprint::console(&format!(
+                "🪧 Login banners ({} captured)",
+                self.banners.len()
+            ))
+            .bold()
+            .underline()
+            .blue()
+        );
+        if self.is_empty() {
+            println!("{}", "No SSH, Telnet or FTP banners captured.".yellow());
+            return;
+        }
+        for banner in self.banners() {
+            let verdict = if banner.has_legal_warning() {
+                "legal warning present".green()
+            } else if banner.finding().is_some() {
+                "no legal warning".red()
+            } else {
+                "identification only".dimmed()
+            };
+            println!(
+                "{}",
+                prettyprint::console(&format!(
+                    "  {}:{} {} — {}",
+                    redact::ip(banner.ip),
+                    banner.port,
+                    banner.source.label().bold(),
+                    verdict
+                ))
+            );
+            for line in banner.text.lines().filter(|line| !line.trim().is_empty()) {
+                let line = redact::text(line.trim_end());
+                println!("      {}", prettyprint::console(&line).dimmed());
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OBJECT_IDENTIFIER: u8 = 0x06;
+pub const TAG_ENUMERATED: u8 = 0x0a;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+pub const TAG_UTC_TIME: u8 = 0x17;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_GENERAL_STRING: u8 = 0x1b;
+
+/// Tag of a constructed context-specific field, e.g. `[1]` is 0xa1.
+pub fn context(n: u8) -> u8 {
+    0xa0 | n
+}
+
+/// Tag of a constructed application type, e.g. `[APPLICATION 10]` is 0x6a.
+pub fn application(n: u8) -> u8 {
+    0x60 | n
+}
+
+fn encode_length(len: usize, out: &mut Vec<u8>) {
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .iter()
+            .copied()
+            .skip_while(|b| *b == 0)
+            .collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend(bytes);
+    }
+}
+
+/// Encodes one tag-length-value element.
+pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    encode_length(content.len(), &mut out);
+    out.extend_from_slice(content);
+    out
+}
+
+/// Encodes a constructed element from already-encoded children.
+pub fn constructed(tag: u8, children: &[Vec<u8>]) -> Vec<u8> {
+    tlv(tag, &children.concat())
+}
+
+pub fn sequence(children: &[Vec<u8>]) -> Vec<u8> {
+    constructed(TAG_SEQUENCE, children)
+}
+
+/// Minimal two's-complement encoding of `value` with the given tag.
+pub fn integer_with_tag(tag: u8, value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < 7
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    tlv(tag, &bytes[start..])
+}
+
+pub fn integer(value: i64) -> Vec<u8> {
+    integer_with_tag(TAG_INTEGER, value)
+}
+
+pub fn octet_string(value: &[u8]) -> Vec<u8> {
+    tlv(TAG_OCTET_STRING, value)
+}
+
+/// A decoded element borrowing its content from the input buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tlv<'a> {
+    pub tag: u8,
+    pub value: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    /// Decodes the children of a constructed element.
+    pub fn children(&self) -> Option<Vec<Tlv<'a>>> {
+        read_all(self.value)
+    }
+
+    /// The child with the given tag, if present.
+    pub fn child(&self, tag: u8) -> Option<Tlv<'a>> {
+        self.children()?.into_iter().find(|c| c.tag == tag)
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        parse_integer(self.value)
+    }
+
+    pub fn as_str(&self) -> String {
+        String::from_utf8_lossy(self.value).to_string()
+    }
+}
+
+/// Total encoded size of the element at the start of `input`, once enough of
+/// it has arrived to know. Used to tell when a streamed message is complete.
+pub fn encoded_len(input: &[u8]) -> Option<usize> {
+    let first = *input.get(1)?;
+    if first < 0x80 {
+        return Some(2 + first as usize);
+    }
+    let count = (first & 0x7f) as usize;
+    if count == 0 || count > 4 {
+        return None;
+    }
+    let len_bytes = input.get(2..2 + count)?;
+    let len = len_bytes
+        .iter()
+        .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+    Some(2 + count + len)
+}
+
+/// Reads one element, returning it and the remaining input.
+pub fn read_tlv(input: &[u8]) -> Option<(Tlv<'_>, &[u8])> {
+    let total = encoded_len(input)?;
+    if total > input.len() {
+        return None;
+    }
+    let header = if input[1] < 0x80 {
+        2
+    } else {
+        2 + (input[1] & 0x7f) as usize
+    };
+    Some((
+        Tlv {
+            tag: input[0],
+            value: &input[header..total],
+        },
+        &input[total..],
+    ))
+}
+
+/// Reads consecutive elements until the input is exhausted.
+pub fn read_all(mut input: &[u8]) -> Option<Vec<Tlv<'_>>> {
+    let mut items = Vec::new();
+    while !input.is_empty() {
+        let (item, rest) = read_tlv(input)?;
+        items.push(item);
+        input = rest;
+    }
+    Some(items)
+}
+
+pub fn parse_integer(bytes: &[u8]) -> Option<i64> {
+    if bytes.is_empty() || bytes.len() > 8 {
+        return None;
+    }
+    let negative = bytes[0] & 0x80 != 0;
+    let init: i64 = if negative { -1 } else { 0 };
+    Some(bytes.iter().fold(init, |acc, b| (acc << 8) | *b as i64))
+}
@@ -0,0 +1,301 @@
+use crate::detect_tls::CertificateInfo;
+use crate::utils::{prettyprint, redact};
+use chrono::{DateTime, Utc};
+use colored::*;
+use std::fmt;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Columns of the persisted inventory, one row per endpoint
+const STATE_HEADER: [&str; 12] = [
+    "ip",
+    "port",
+    "serial",
+    "subject",
+    "issuer",
+    "sans",
+    "key_algorithm",
+    "key_bits",
+    "key_fingerprint",
+    "not_before",
+    "not_after",
+    "self_signed",
+];
+
+/// One distinct certificate and every endpoint that served it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateEntry {
+    pub certificate: CertificateInfo,
+    pub endpoints: Vec<(IpAddr, u16)>,
+}
+
+/// How an endpoint's certificate differs from the previous run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertificateChange {
+    IssuerChanged {
+        endpoint: (IpAddr, u16),
+        before: String,
+        after: String,
+    },
+    KeyChanged {
+        endpoint: (IpAddr, u16),
+        before: String,
+        after: String,
+    },
+}
+
+impl fmt::Display for CertificateChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CertificateChange::IssuerChanged {
+                endpoint: (ip, port),
+                before,
+                after,
+            } => write!(
+                f,
+                "{}:{} issuer changed from {} to {}",
+                ip, port, before, after
+            ),
+            CertificateChange::KeyChanged {
+                endpoint: (ip, port),
+                before,
+                after,
+            } => write!(
+                f,
+                "{}:{} key changed from {} to {}",
+                ip, port, before, after
+            ),
+        }
+    }
+}
+
+/// All TLS certificates observed during a scan, deduplicated by issuer and serial
+#[derive(Debug, Clone, Default)]
+pub struct CertificateInventory {
+    entries: Vec<CertificateEntry>,
+}
+
+impl CertificateInventory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `ip:port` served `certificate`. Returns true when the
+    /// certificate had not been seen before.
+    pub fn record(&mut self, ip: IpAddr, port: u16, certificate: &CertificateInfo) -> bool {
+        let existing = self.entries.iter_mut().find(|entry| {
+            entry.certificate.serial == certificate.serial
+                && entry.certificate.issuer == certificate.issuer
+        });
+        match existing {
+            Some(entry) => {
+                if !entry.endpoints.contains(&(ip, port)) {
+                    entry.endpoints.push((ip, port));
+                    entry.endpoints.sort_unstable();
+                }
+                false
+            }
+            None => {
+                self.entries.push(CertificateEntry {
+                    certificate: certificate.clone(),
+                    endpoints: vec![(ip, port)],
+                });
+                true
+            }
+        }
+    }
+
+    /// Records every endpoint of `other`, e.g. to combine the inventories of
+    /// several network segments.
+    pub fn merge(&mut self, other: &CertificateInventory) {
+        for entry in &other.entries {
+            for &(ip, port) in &entry.endpoints {
+                self.record(ip, port, &entry.certificate);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Subjects and SANs of every certificate, e.g. to register for `--redact`.
+    pub fn names(&self) -> Vec<&str> {
+        self.entries()
+            .into_iter()
+            .flat_map(|entry| {
+                std::iter::once(entry.certificate.subject.as_str())
+                    .chain(entry.certificate.sans.iter().map(String::as_str))
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries sorted by expiry, soonest first; certificates expiring at the
+    /// same time by subject and serial, so the order does not depend on
+    /// which host was scanned first.
+    pub fn entries(&self) -> Vec<&CertificateEntry> {
+        let mut entries: Vec<&CertificateEntry> = self.entries.iter().collect();
+        entries.sort_by_key(|&entry| {
+            let cert = &entry.certificate;
+            (cert.not_after, &cert.subject, &cert.serial)
+        });
+        entries
+    }
+
+    /// The certificate `ip:port` served, if any.
+    pub fn certificate_at(&self, ip: IpAddr, port: u16) -> Option<&CertificateInfo> {
+        self.entries
+            .iter()
+            .find(|entry| entry.endpoints.contains(&(ip, port)))
+            .map(|entry| &entry.certificate)
+    }
+
+    /// Certificates expiring within `days` of `now` (expired ones included),
+    /// soonest first.
+    pub fn expiring_within(&self, now: DateTime<Utc>, days: i64) -> Vec<&CertificateEntry> {
+        self.entries()
+            .into_iter()
+            .filter(|entry| entry.certificate.days_until_expiry(now) <= days)
+            .collect()
+    }
+
+    /// Issuer and key changes on endpoints that served a certificate in both
+    /// inventories. Endpoints that appeared or disappeared are not changes.
+    pub fn changes_since(&self, previous: &CertificateInventory) -> Vec<CertificateChange> {
+        let mut changes = Vec::new();
+        for entry in self.entries() {
+            for &(ip, port) in &entry.endpoints {
+                let Some(before) = previous.certificate_at(ip, port) else {
+                    continue;
+                };
+                let after = &entry.certificate;
+                if before.issuer != after.issuer {
+                    changes.push(CertificateChange::IssuerChanged {
+                        endpoint: (ip, port),
+                        before: before.issuer.clone(),
+                        after: after.issuer.clone(),
+                    });
+                }
+                if before.key_fingerprint != after.key_fingerprint {
+                    changes.push(CertificateChange::KeyChanged {
+                        endpoint: (ip, port),
+                        before: format!("{} {}", before.key_description(), before.key_fingerprint),
+                        after: format!("{} {}", after.key_description(), after.key_fingerprint),
+                    });
+                }
+            }
+        }
+        changes
+    }
+
+    /// Persists the inventory so the next run can compare against it.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut writer = csv::Writer::from_path(path)
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+        writer
+            .write_record(STATE_HEADER)
+            .map_err(|e| e.to_string())?;
+        for entry in &self.entries {
+            let cert = &entry.certificate;
+            for (ip, port) in &entry.endpoints {
+                writer
+                    .write_record([
+                        ip.to_string(),
+                        port.to_string(),
+                        cert.serial.clone(),
+                        cert.subject.clone(),
+                        cert.issuer.clone(),
+                        cert.sans.join(" "),
+                        cert.key_algorithm.clone(),
+                        cert.key_bits.map(|b| b.to_string()).unwrap_or_default(),
+                        cert.key_fingerprint.clone(),
+                        cert.not_before.to_rfc3339(),
+                        cert.not_after.to_rfc3339(),
+                        cert.self_signed.to_string(),
+                    ])
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        writer.flush().map_err(|e| e.to_string())
+    }
+
+    /// Reads an inventory written by [`CertificateInventory::save`].
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut reader = csv::Reader::from_path(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let mut inventory = Self::new();
+        for (line, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| e.to_string())?;
+            let bad_row = || format!("{}: malformed row {}", path.display(), line + 2);
+            let field = |i: usize| record.get(i).ok_or_else(bad_row);
+            let time = |i: usize| {
+                DateTime::parse_from_rfc3339(field(i)?)
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|_| bad_row())
+            };
+            let ip: IpAddr = field(0)?.parse().map_err(|_| bad_row())?;
+            let port: u16 = field(1)?.parse().map_err(|_| bad_row())?;
+            let certificate = CertificateInfo {
+                serial: field(2)?.to_string(),
+                subject: field(3)?.to_string(),
+                issuer: field(4)?.to_string(),
+                sans: field(5)?.split_whitespace().map(String::from).collect(),
+                key_algorithm: field(6)?.to_string(),
+                key_bits: field(7)?.parse().ok(),
+                key_fingerprint: field(8)?.to_string(),
+                not_before: time(9)?,
+                not_after: time(10)?,
+                self_signed: field(11)? == "true",
+            };
+            inventory.record(ip, port, &certificate);
+        }
+        Ok(inventory)
+    }
+
+    pub fn print_report(&self, now: DateTime<Utc>) {
+        println!(
+            "\n{}",
+            prettyprint::console(&format!(
+                "📜 Certificate inventory ({} certificate(s))",
+                self.len()
+            ))
+            .bold()
+            .underline()
+            .blue()
+        );
+        if self.is_empty() {
+            println!("{}", "No TLS certificates observed.".yellow());
+            return;
+        }
+        for entry in self.entries() {
+            let cert = &entry.certificate;
+            let days = cert.days_until_expiry(now);
+            let expiry = format!("{} ({} days)", cert.not_after.format("%Y-%m-%d"), days);
+            let expiry = if days < 0 {
+                expiry.red().bold()
+            } else if days <= 30 {
+                expiry.yellow()
+            } else {
+                expiry.green()
+            };
+            println!("  {} {}", "CN:".bold(), redact::name(&cert.subject));
+            if !cert.sans.is_empty() {
+                let sans: Vec<String> = cert.sans.iter().map(|san| redact::name(san)).collect();
+                println!("    SANs:    {}", sans.join(", "));
+            }
+            println!("    Issuer:  {}", redact::text(&cert.issuer));
+            println!("    Expires: {}", expiry);
+            println!("    Key:     {}", cert.key_description());
+            let served_by: Vec<String> = entry
+                .endpoints
+                .iter()
+                .map(|(ip, port)| format!("{}:{}", redact::ip(*ip), port))
+                .collect();
+            println!("    Served:  {}", served_by.join(", "));
+        }
+    }
+}
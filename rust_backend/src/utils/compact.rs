@@ -0,0 +1,411 @@
+use crate::utils::errors::ErrorCategory;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Outcome of a single port probe, stored in 4 bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortState {
+    Unscanned,
+    Open,
+    Failed(ErrorCategory),
+}
+
+impl PortState {
+    fn to_nibble(self) -> u8 {
+        match self {
+            PortState::Unscanned => 0,
+            PortState::Open => 1,
+            PortState::Failed(ErrorCategory::Refused) => 2,
+            PortState::Failed(ErrorCategory::Timeout) => 3,
+            PortState::Failed(ErrorCategory::Unreachable) => 4,
+            PortState::Failed(ErrorCategory::Permission) => 5,
+            PortState::Failed(ErrorCategory::Other) => 6,
+        }
+    }
+
+    fn from_nibble(nibble: u8) -> Self {
+        match nibble {
+            1 => PortState::Open,
+            2 => PortState::Failed(ErrorCategory::Refused),
+            3 => PortState::Failed(ErrorCategory::Timeout),
+            4 => PortState::Failed(ErrorCategory::Unreachable),
+            5 => PortState::Failed(ErrorCategory::Permission),
+            6 => PortState::Failed(ErrorCategory::Other),
+            _ => PortState::Unscanned,
+        }
+    }
+}
+
+/// Per-host port states packed two to a byte. Storage covers only the span
+/// between the lowest and highest port recorded, so a 1-1024 scan costs 512 bytes.
+#[derive(Debug, Clone, Default)]
+pub struct PortStates {
+    first_port: u16,
+    nibbles: Vec<u8>,
+}
+
+impl PortStates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn span(&self) -> usize {
+        self.nibbles.len() * 2
+    }
+
+    pub fn set(&mut self, port: u16, state: PortState) {
+        if self.nibbles.is_empty() {
+            self.first_port = port;
+        } else if port < self.first_port {
+            // Re-base so the new lowest port sits at index 0
+            let shift = usize::from(self.first_port - port);
+            let old = std::mem::take(&mut self.nibbles);
+            let old_first = self.first_port;
+            let old_span = old.len() * 2;
+            self.first_port = port;
+            self.nibbles = vec![0; (shift + old_span).div_ceil(2)];
+            for index in 0..old_span {
+                let nibble = (old[index / 2] >> ((index % 2) * 4)) & 0x0f;
+                if nibble != 0 {
+                    self.write(old_first - self.first_port + index as u16, nibble);
+                }
+            }
+        }
+        self.write(port - self.first_port, state.to_nibble());
+    }
+
+    fn write(&mut self, offset: u16, nibble: u8) {
+        let index = usize::from(offset);
+        if index >= self.span() {
+            self.nibbles.resize(index / 2 + 1, 0);
+        }
+        let shift = (index % 2) * 4;
+        let byte = &mut self.nibbles[index / 2];
+        *byte = (*byte & !(0x0f << shift)) | (nibble << shift);
+    }
+
+    pub fn get(&self, port: u16) -> PortState {
+        if self.nibbles.is_empty() || port < self.first_port {
+            return PortState::Unscanned;
+        }
+        let index = usize::from(port - self.first_port);
+        if index >= self.span() {
+            return PortState::Unscanned;
+        }
+        PortState::from_nibble((self.nibbles[index / 2] >> ((index % 2) * 4)) & 0x0f)
+    }
+
+    /// Every scanned port with its state, in ascending port order.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, PortState)> + '_ {
+        (0..self.span()).filter_map(move |index| {
+            let nibble = (self.nibbles[index / 2] >> ((index % 2) * 4)) & 0x0f;
+            (nibble != 0).then(|| {
+                (
+                    self.first_port + index as u16,
+                    PortState::from_nibble(nibble),
+                )
+            })
+        })
+    }
+
+    /// Heap bytes used by the packed states.
+    pub fn heap_bytes(&self) -> usize {
+        self.nibbles.capacity()
+    }
+}
+
+/// Stores each distinct string once and hands out small ids for it
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    strings: Vec<Arc<str>>,
+    ids: HashMap<Arc<str>, u32>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, s: &str) -> u32 {
+        if let Some(id) = self.ids.get(s) {
+            return *id;
+        }
+        let id = self.strings.len() as u32;
+        let shared: Arc<str> = Arc::from(s);
+        self.strings.push(shared.clone());
+        self.ids.insert(shared, id);
+        id
+    }
+
+    pub fn resolve(&self, id: u32) -> &str {
+        &self.strings[id as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+/// A failed probe as seen through [`ProbeTable::errors`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeError<'a> {
+    pub ip: IpAddr,
+    pub port: Option<u16>,
+    pub category: ErrorCategory,
+    pub message: &'a str,
+}
+
+impl fmt::Display for ProbeError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.port {
+            Some(port) => write!(f, "{}:{} - {}", self.ip, port, self.message),
+            None => write!(f, "{} - {}", self.ip, self.message),
+        }
+    }
+}
+
+fn default_message(category: ErrorCategory) -> &'static str {
+    match category {
+        ErrorCategory::Refused => "Connection refused",
+        ErrorCategory::Timeout => "Timed out",
+        ErrorCategory::Unreachable => "Host unreachable",
+        ErrorCategory::Permission => "Permission denied",
+        ErrorCategory::Other => "Error",
+    }
+}
+
+/// Compact storage for port-scan outcomes shared by the TCP and UDP scanners.
+///
+/// Refused and timed-out probes, the bulk of any large scan, cost 4 bits each.
+/// Rarer errors also keep their message, interned so repeats are stored once.
+#[derive(Debug, Clone, Default)]
+pub struct ProbeTable {
+    hosts: BTreeMap<IpAddr, PortStates>,
+    details: HashMap<(IpAddr, u16), u32>,
+    host_errors: Vec<(IpAddr, ErrorCategory, u32)>,
+    messages: Interner,
+    error_counts: BTreeMap<IpAddr, BTreeMap<ErrorCategory, usize>>,
+    open_count: usize,
+    error_count: usize,
+}
+
+impl ProbeTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_open_port(&mut self, ip: IpAddr, port: u16) {
+        let states = self.hosts.entry(ip).or_default();
+        if states.get(port) != PortState::Open {
+            self.open_count += 1;
+        }
+        states.set(port, PortState::Open);
+    }
+
+    /// Records a failed probe of `port`. Refused and timeout messages are
+    /// implied by the category and not stored.
+    pub fn add_port_error(
+        &mut self,
+        ip: IpAddr,
+        port: u16,
+        category: ErrorCategory,
+        message: &str,
+    ) {
+        let states = self.hosts.entry(ip).or_default();
+        let previous = states.get(port);
+        states.set(port, PortState::Failed(category));
+        match previous {
+            PortState::Open => self.open_count -= 1,
+            // A reclassified probe is counted once, under its new category
+            PortState::Failed(previous) => self.uncount_error(ip, previous),
+            PortState::Unscanned => {}
+        }
+        if matches!(category, ErrorCategory::Refused | ErrorCategory::Timeout) {
+            self.details.remove(&(ip, port));
+        } else {
+            let id = self.messages.intern(message);
+            self.details.insert((ip, port), id);
+        }
+        self.count_error(ip, category);
+    }
+
+    /// Records an error not tied to a single port.
+    pub fn add_host_error(&mut self, ip: IpAddr, category: ErrorCategory, message: &str) {
+        let id = self.messages.intern(message);
+        self.host_errors.push((ip, category, id));
+        self.count_error(ip, category);
+    }
+
+    fn uncount_error(&mut self, ip: IpAddr, category: ErrorCategory) {
+        if let Some(count) = self
+            .error_counts
+            .get_mut(&ip)
+            .and_then(|counts| counts.get_mut(&category))
+        {
+            *count -= 1;
+            self.error_count -= 1;
+        }
+    }
+
+    fn count_error(&mut self, ip: IpAddr, category: ErrorCategory) {
+        *self
+            .error_counts
+            .entry(ip)
+            .or_default()
+            .entry(category)
+            .or_insert(0) += 1;
+        self.error_count += 1;
+    }
+
+    pub fn merge(&mut self, other: ProbeTable) {
+        for (ip, states) in &other.hosts {
+            for (port, state) in states.iter() {
+                match state {
+                    PortState::Open => self.add_open_port(*ip, port),
+                    PortState::Failed(category) => {
+                        let message = other
+                            .details
+                            .get(&(*ip, port))
+                            .map_or(default_message(category), |id| other.messages.resolve(*id));
+                        self.add_port_error(*ip, port, category, message);
+                    }
+                    PortState::Unscanned => {}
+                }
+            }
+        }
+        for (ip, category, id) in &other.host_errors {
+            self.add_host_error(*ip, *category, other.messages.resolve(*id));
+        }
+    }
+
+    pub fn state(&self, ip: IpAddr, port: u16) -> PortState {
+        self.hosts
+            .get(&ip)
+            .map_or(PortState::Unscanned, |states| states.get(port))
+    }
+
+    /// (open, probed) port counts per host.
+    pub fn host_totals(&self) -> impl Iterator<Item = (IpAddr, usize, usize)> + '_ {
+        self.hosts.iter().map(|(ip, states)| {
+            let (open, probed) = states.iter().fold((0, 0), |(open, probed), (_, state)| {
+                (open + usize::from(state == PortState::Open), probed + 1)
+            });
+            (*ip, open, probed)
+        })
+    }
+
+    pub fn hosts(&self) -> &BTreeMap<IpAddr, PortStates> {
+        &self.hosts
+    }
+
+    pub fn error_counts(&self) -> &BTreeMap<IpAddr, BTreeMap<ErrorCategory, usize>> {
+        &self.error_counts
+    }
+
+    pub fn open_ports(&self) -> OpenPorts<'_> {
+        OpenPorts { table: self }
+    }
+
+    pub fn errors(&self) -> ProbeErrors<'_> {
+        ProbeErrors { table: self }
+    }
+
+    /// Approximate heap bytes held, for comparing against the old per-port Strings.
+    pub fn heap_bytes(&self) -> usize {
+        let states: usize = self.hosts.values().map(PortStates::heap_bytes).sum();
+        let messages: usize = (0..self.messages.len())
+            .map(|id| self.messages.resolve(id as u32).len())
+            .sum();
+        states
+            + messages
+            + self.details.capacity() * std::mem::size_of::<((IpAddr, u16), u32)>()
+            + self.host_errors.capacity() * std::mem::size_of::<(IpAddr, ErrorCategory, u32)>()
+    }
+}
+
+/// Borrowed view over the open ports of a [`ProbeTable`], in (IP, port) order
+#[derive(Clone, Copy)]
+pub struct OpenPorts<'a> {
+    table: &'a ProbeTable,
+}
+
+impl<'a> OpenPorts<'a> {
+    pub fn len(&self) -> usize {
+        self.table.open_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.open_count == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (IpAddr, u16)> + 'a {
+        self.table.hosts.iter().flat_map(|(ip, states)| {
+            states
+                .iter()
+                .filter(|(_, state)| *state == PortState::Open)
+                .map(move |(port, _)| (*ip, port))
+        })
+    }
+}
+
+impl fmt::Debug for OpenPorts<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Borrowed view over the failed probes of a [`ProbeTable`]
+#[derive(Clone, Copy)]
+pub struct ProbeErrors<'a> {
+    table: &'a ProbeTable,
+}
+
+impl<'a> ProbeErrors<'a> {
+    pub fn len(&self) -> usize {
+        self.table.error_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.error_count == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ProbeError<'a>> + 'a {
+        let table = self.table;
+        let port_errors = table.hosts.iter().flat_map(move |(ip, states)| {
+            states.iter().filter_map(move |(port, state)| match state {
+                PortState::Failed(category) => Some(ProbeError {
+                    ip: *ip,
+                    port: Some(port),
+                    category,
+                    message: table
+                        .details
+                        .get(&(*ip, port))
+                        .map_or(default_message(category), |id| table.messages.resolve(*id)),
+                }),
+                _ => None,
+            })
+        });
+        let host_errors = table
+            .host_errors
+            .iter()
+            .map(move |(ip, category, id)| ProbeError {
+                ip: *ip,
+                port: None,
+                category: *category,
+                message: table.messages.resolve(*id),
+            });
+        port_errors.chain(host_errors)
+    }
+}
+
+impl fmt::Debug for ProbeErrors<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
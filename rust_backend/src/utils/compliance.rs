@@ -0,0 +1,416 @@
+use crate::utils::findings::{self, Finding, code};
+use crate::utils::prettyprint;
+use colored::*;
+use std::net::IpAddr;
+
+/// A check that must have examined a host before a control can be judged on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Check {
+    WeakProtocols,
+    TlsGrade,
+    LoginBanners,
+    SmbSigning,
+}
+
+impl Check {
+    /// Why a control relying on this check could not be judged
+    pub fn missing_reason(&self) -> &'static str {
+        match self {
+            Check::WeakProtocols => "weak-protocol checks did not run",
+            Check::TlsGrade => "no TLS endpoint was graded",
+            Check::LoginBanners => "no login banner could be judged",
+            Check::SmbSigning => "SMB did not answer",
+        }
+    }
+}
+
+/// A control statement and the finding codes that violate it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Control {
+    pub id: &'static str,
+    pub statement: &'static str,
+    /// Codes (see [`findings::code`]) of findings that make a host fail this control
+    pub violated_by: &'static [&'static str],
+    /// Fail the control when the host risk score reaches this value
+    pub max_risk: Option<u32>,
+    /// Check whose evidence the control is judged on; `None` for every host
+    pub assessed_by: Option<Check>,
+}
+
+const INSECURE_ADMIN: &[&str] = &[
+    code::TELNET,
+    code::HTTP_ADMIN_PANEL,
+    code::VNC_WITHOUT_AUTH,
+    code::FTP_WITHOUT_TLS,
+];
+const LEGACY_PROTOCOLS: &[&str] = &[
+    code::SMB1,
+    code::SMB1_ACCEPTED,
+    code::SNMP_V1_V2C,
+    code::TFTP,
+    code::LEGACY_SERVICE,
+];
+const WEAK_TLS: &[&str] = &[
+    code::TLS_SSL3,
+    code::TLS_DEPRECATED_VERSIONS,
+    code::TLS_NO_MODERN_VERSION,
+    code::TLS_INSECURE_CIPHERS,
+    code::TLS_WEAK_CIPHERS,
+];
+const BAD_CERTIFICATES: &[&str] = &[code::CERT_EXPIRED, code::CERT_SELF_SIGNED];
+const UNAUTHENTICATED: &[&str] = &[
+    code::VNC_WITHOUT_AUTH,
+    code::CAMERA_STREAM_WITHOUT_AUTH,
+    code::OPEN_PROXY,
+    code::DATA_STORE_WITHOUT_AUTH,
+    code::ANONYMOUS_ROOT_DSE,
+    code::AS_REP_WITHOUT_PREAUTH,
+];
+
+const PCI_CONTROLS: &[Control] = &[
+    Control {
+        id: "2.2.4",
+        statement: "Only necessary and secure services and protocols are enabled",
+        violated_by: LEGACY_PROTOCOLS,
+        max_risk: None,
+        assessed_by: Some(Check::WeakProtocols),
+    },
+    Control {
+        id: "2.2.7",
+        statement: "All non-console administrative access is encrypted",
+        violated_by: INSECURE_ADMIN,
+        max_risk: None,
+        assessed_by: Some(Check::WeakProtocols),
+    },
+    Control {
+        id: "4.2.1",
+        statement: "Strong cryptography protects data in transit",
+        violated_by: WEAK_TLS,
+        max_risk: None,
+        assessed_by: Some(Check::TlsGrade),
+    },
+    Control {
+        id: "4.2.1.1",
+        statement: "Certificates protecting data in transit are valid and trusted",
+        violated_by: BAD_CERTIFICATES,
+        max_risk: None,
+        assessed_by: Some(Check::TlsGrade),
+    },
+    Control {
+        id: "8.3.1",
+        statement: "All access to system components requires authentication",
+        violated_by: UNAUTHENTICATED,
+        max_risk: None,
+        assessed_by: Some(Check::WeakProtocols),
+    },
+    Control {
+        id: "6.3.1",
+        statement: "Identified vulnerabilities keep the host risk score below 25",
+        violated_by: &[],
+        max_risk: Some(25),
+        assessed_by: None,
+    },
+];
+
+const CIS_BASIC_CONTROLS: &[Control] = &[
+    Control {
+        id: "CIS-1",
+        statement: "Insecure management protocols must be disabled",
+        violated_by: INSECURE_ADMIN,
+        max_risk: None,
+        assessed_by: Some(Check::WeakProtocols),
+    },
+    Control {
+        id: "CIS-2",
+        statement: "Legacy SMB, SNMP, TFTP and inetd services must be disabled",
+        violated_by: LEGACY_PROTOCOLS,
+        max_risk: None,
+        assessed_by: Some(Check::WeakProtocols),
+    },
+    Control {
+        id: "CIS-3",
+        statement: "Only TLS 1.2 or newer with strong cipher suites may be offered",
+        violated_by: WEAK_TLS,
+        max_risk: None,
+        assessed_by: Some(Check::TlsGrade),
+    },
+    Control {
+        id: "CIS-4",
+        statement: "Certificates must be current and issued by a trusted CA",
+        violated_by: BAD_CERTIFICATES,
+        max_risk: None,
+        assessed_by: Some(Check::TlsGrade),
+    },
+    Control {
+        id: "CIS-5",
+        statement: "SMB signing must be required",
+        violated_by: &[code::SMB_SIGNING_NOT_REQUIRED],
+        max_risk: None,
+        assessed_by: Some(Check::SmbSigning),
+    },
+    Control {
+        id: "CIS-6",
+        statement: "Services must not allow anonymous access",
+        violated_by: UNAUTHENTICATED,
+        max_risk: None,
+        assessed_by: Some(Check::WeakProtocols),
+    },
+    Control {
+        id: "CIS-7",
+        statement: "Host risk score must stay below 40",
+        violated_by: &[],
+        max_risk: Some(40),
+        assessed_by: None,
+    },
+    Control {
+        id: "CIS-8",
+        statement: "Login services must display a legal warning banner",
+        violated_by: &[code::MISSING_LEGAL_BANNER],
+        max_risk: None,
+        assessed_by: Some(Check::LoginBanners),
+    },
+];
+
+/// Set of controls a scan is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComplianceProfile {
+    Pci,
+    CisBasic,
+}
+
+impl ComplianceProfile {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ComplianceProfile::Pci => "PCI DSS",
+            ComplianceProfile::CisBasic => "CIS basic",
+        }
+    }
+
+    pub fn controls(&self) -> &'static [Control] {
+        match self {
+            ComplianceProfile::Pci => PCI_CONTROLS,
+            ComplianceProfile::CisBasic => CIS_BASIC_CONTROLS,
+        }
+    }
+
+    /// Whether any control of the profile is judged on `check`
+    pub fn uses(&self, check: Check) -> bool {
+        self.controls()
+            .iter()
+            .any(|control| control.assessed_by == Some(check))
+    }
+}
+
+/// Outcome of one control on one host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlStatus {
+    Pass,
+    Fail,
+    /// Nothing examined the host for this control
+    NotAssessed,
+}
+
+impl ControlStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ControlStatus::Pass => "PASS",
+            ControlStatus::Fail => "FAIL",
+            ControlStatus::NotAssessed => "NOT ASSESSED",
+        }
+    }
+
+    /// Label for a cell of the matrix
+    fn cell(&self) -> ColoredString {
+        match self {
+            ControlStatus::Pass => "PASS".green(),
+            ControlStatus::Fail => "FAIL".red().bold(),
+            ControlStatus::NotAssessed => "N/A".dimmed(),
+        }
+    }
+}
+
+/// Judgement of one control on one host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlResult {
+    pub control: Control,
+    pub status: ControlStatus,
+    /// Findings (or the risk score) that caused a failure, or why the
+    /// control was not assessed
+    pub evidence: Vec<String>,
+}
+
+/// Results of every control of a profile for one host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostCompliance {
+    pub ip: IpAddr,
+    pub risk_score: u32,
+    pub results: Vec<ControlResult>,
+}
+
+impl HostCompliance {
+    /// Whether no assessed control failed
+    pub fn passed(&self) -> bool {
+        self.failed_controls().is_empty()
+    }
+
+    pub fn failed_controls(&self) -> Vec<&ControlResult> {
+        self.results
+            .iter()
+            .filter(|result| result.status == ControlStatus::Fail)
+            .collect()
+    }
+}
+
+/// Checks one host's findings against every control of `profile`. Controls
+/// relying on a check missing from `assessed`, the checks that examined the
+/// host, are not assessed rather than passed.
+pub fn evaluate(
+    profile: ComplianceProfile,
+    ip: IpAddr,
+    found: &[Finding],
+    assessed: &[Check],
+) -> HostCompliance {
+    let risk_score = findings::risk_score(found);
+    let results = profile
+        .controls()
+        .iter()
+        .map(|control| {
+            if let Some(check) = control.assessed_by
+                && !assessed.contains(&check)
+            {
+                return ControlResult {
+                    control: *control,
+                    status: ControlStatus::NotAssessed,
+                    evidence: vec![check.missing_reason().to_string()],
+                };
+            }
+            let mut evidence: Vec<String> = found
+                .iter()
+                .filter(|finding| {
+                    finding
+                        .code
+                        .is_some_and(|code| control.violated_by.contains(&code))
+                })
+                .map(|finding| finding.to_string())
+                .collect();
+            if let Some(max_risk) = control.max_risk
+                && risk_score >= max_risk
+            {
+                evidence.push(format!(
+                    "risk score {}/{}",
+                    risk_score,
+                    findings::MAX_RISK_SCORE
+                ));
+            }
+            ControlResult {
+                control: *control,
+                status: if evidence.is_empty() {
+                    ControlStatus::Pass
+                } else {
+                    ControlStatus::Fail
+                },
+                evidence,
+            }
+        })
+        .collect();
+    HostCompliance {
+        ip,
+        risk_score,
+        results,
+    }
+}
+
+/// Per-host pass/fail/not-assessed matrix for one compliance profile
+#[derive(Debug, Clone)]
+pub struct ComplianceMatrix {
+    pub profile: ComplianceProfile,
+    hosts: Vec<HostCompliance>,
+}
+
+impl ComplianceMatrix {
+    pub fn new(profile: ComplianceProfile) -> Self {
+        Self {
+            profile,
+            hosts: Vec::new(),
+        }
+    }
+
+    /// Evaluates a host's findings and adds its row to the matrix; `assessed`
+    /// are the checks that examined the host.
+    pub fn add_host(&mut self, ip: IpAddr, found: &[Finding], assessed: &[Check]) {
+        self.hosts.push(evaluate(self.profile, ip, found, assessed));
+        self.hosts.sort_by_key(|host| host.ip);
+    }
+
+    pub fn hosts(&self) -> &[HostCompliance] {
+        &self.hosts
+    }
+
+    pub fn print_matrix(&self) {
+        println!(
+            "\n{}",
+            prettyprint::console(&format!("🧾 Compliance matrix: {}", self.profile.label()))
+                .bold()
+                .underline()
+                .blue()
+        );
+        let controls = self.profile.controls();
+        for control in controls {
+            println!("  {:<8} {}", control.id.bold(), control.statement);
+        }
+        let width = controls
+            .iter()
+            .map(|control| control.id.len())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+        let mut header = format!("\n  {:<16}", "Host");
+        for control in controls {
+            header.push_str(&format!(" {:<width$}", control.id));
+        }
+        println!("{}  Risk", header.bold());
+        for host in &self.hosts {
+            let mut row = format!("  {:<16}", host.ip.to_string());
+            for result in &host.results {
+                let cell = result.status.cell();
+                let padding = " ".repeat(width.saturating_sub(cell.chars().count()));
+                row.push_str(&format!(" {}{}", cell, padding));
+            }
+            println!("{}  {}/{}", row, host.risk_score, findings::MAX_RISK_SCORE);
+        }
+        for host in &self.hosts {
+            for result in host.failed_controls() {
+                println!(
+                    "  {} {} {}:",
+                    prettyprint::console("✗").red(),
+                    host.ip,
+                    result.control.id.bold()
+                );
+                for evidence in &result.evidence {
+                    println!("      {}", evidence.dimmed());
+                }
+            }
+        }
+        if self
+            .hosts
+            .iter()
+            .flat_map(|host| &host.results)
+            .any(|result| result.status == ControlStatus::NotAssessed)
+        {
+            println!(
+                "  {}",
+                "N/A: not assessed, no check examined the host for the control".dimmed()
+            );
+        }
+        let passing = self.hosts.iter().filter(|host| host.passed()).count();
+        println!(
+            "{}",
+            format!(
+                "{}/{} host(s) pass every assessed control",
+                passing,
+                self.hosts.len()
+            )
+            .cyan()
+        );
+    }
+}
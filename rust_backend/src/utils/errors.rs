@@ -0,0 +1,156 @@
+use std::fmt;
+use std::io;
+
+/// Broad cause of a failed probe, used to summarize thousands of per-port errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorCategory {
+    Refused,
+    Timeout,
+    Unreachable,
+    Permission,
+    Other,
+}
+
+impl ErrorCategory {
+    pub const ALL: [ErrorCategory; 5] = [
+        ErrorCategory::Refused,
+        ErrorCategory::Timeout,
+        ErrorCategory::Unreachable,
+        ErrorCategory::Permission,
+        ErrorCategory::Other,
+    ];
+
+    pub fn from_io(e: &io::Error) -> Self {
+        match e.raw_os_error() {
+            Some(111) => return ErrorCategory::Refused, // ECONNREFUSED
+            Some(110) => return ErrorCategory::Timeout, // ETIMEDOUT
+            Some(101) | Some(113) => return ErrorCategory::Unreachable, // ENETUNREACH, EHOSTUNREACH
+            Some(1) | Some(13) => return ErrorCategory::Permission, // EPERM, EACCES
+            _ => {}
+        }
+        match e.kind() {
+            io::ErrorKind::ConnectionRefused => ErrorCategory::Refused,
+            io::ErrorKind::TimedOut => ErrorCategory::Timeout,
+            io::ErrorKind::HostUnreachable | io::ErrorKind::NetworkUnreachable => {
+                ErrorCategory::Unreachable
+            }
+            io::ErrorKind::PermissionDenied => ErrorCategory::Permission,
+            _ => ErrorCategory::Other,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorCategory::Refused => "refused",
+            ErrorCategory::Timeout => "timeout",
+            ErrorCategory::Unreachable => "unreachable",
+            ErrorCategory::Permission => "permission",
+            ErrorCategory::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Formats per-category counts as "1020 refused, 3 timeout".
+pub fn format_counts<'a>(
+    counts: impl IntoIterator<Item = (&'a ErrorCategory, &'a usize)>,
+) -> String {
+    counts
+        .into_iter()
+        .filter(|(_, count)| **count > 0)
+        .map(|(category, count)| format!("{} {}", count, category))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Why a run aborted, for automation to branch on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbortCode {
+    InvalidArguments,
+    InvalidTarget,
+    NothingToScan,
+    PermissionDenied,
+    NoInterface,
+    StateError,
+    DiscoveryFailed,
+    CheckFailed,
+}
+
+impl AbortCode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AbortCode::InvalidArguments => "invalid_arguments",
+            AbortCode::InvalidTarget => "invalid_target",
+            AbortCode::NothingToScan => "nothing_to_scan",
+            AbortCode::PermissionDenied => "permission_denied",
+            AbortCode::NoInterface => "no_interface",
+            AbortCode::StateError => "state_error",
+            AbortCode::DiscoveryFailed => "discovery_failed",
+            AbortCode::CheckFailed => "check_failed",
+        }
+    }
+
+    /// What the user can do about it.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            AbortCode::InvalidArguments => "Check the command line against netscan --help",
+            AbortCode::InvalidTarget => {
+                "Give addresses, ranges (10.0.0.1-50), CIDR networks or resolvable hostnames"
+            }
+            AbortCode::NothingToScan => "Loosen --exclude/--exclude-file or widen the targets",
+            AbortCode::PermissionDenied => {
+                "Run as root or grant CAP_NET_RAW, or drop the raw-socket options (--arp, --syn, --link-local)"
+            }
+            AbortCode::NoInterface => {
+                "Pick an existing, up interface with --interface (see `ip link`)"
+            }
+            AbortCode::StateError => "Check that the --state-dir or --history path is writable",
+            AbortCode::DiscoveryFailed => "Retry without --arp, or check the interface and routes",
+            AbortCode::CheckFailed => "See the message for the failing item",
+        }
+    }
+}
+
+impl fmt::Display for AbortCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// An error that ends a run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunError {
+    pub code: AbortCode,
+    pub message: String,
+}
+
+impl RunError {
+    pub fn new(code: AbortCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// `{"error": {"code": ..., "message": ..., "hint": ...}}`
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "error": {
+                "code": self.code.label(),
+                "message": self.message,
+                "hint": self.code.hint(),
+            }
+        })
+    }
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+/// What a conclusion is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Attribute {
+    Os,
+    Vendor,
+    Model,
+    Serial,
+    Service,
+}
+
+impl fmt::Display for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Attribute::Os => "OS",
+            Attribute::Vendor => "Vendor",
+            Attribute::Model => "Model",
+            Attribute::Serial => "Serial",
+            Attribute::Service => "Service",
+        })
+    }
+}
+
+/// The observation a conclusion rests on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Source {
+    /// IP TTL of a reply
+    Ttl(u8),
+    /// MAC prefix found in the OUI registry
+    Oui(String),
+    /// Text a service sent (banner line or header) that matched
+    Banner(String),
+    /// A protocol probe, by name, that got a valid answer
+    Probe(String),
+    /// TTL, window and TCP options of a SYN/ACK
+    Stack(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Ttl(ttl) => write!(f, "TTL {}", ttl),
+            Source::Oui(prefix) => write!(f, "OUI {}", prefix),
+            Source::Banner(text) => write!(f, "banner \"{}\"", text),
+            Source::Probe(name) => write!(f, "{} probe", name),
+            Source::Stack(signature) => write!(f, "TCP/IP stack ({})", signature),
+        }
+    }
+}
+
+/// One piece of provenance: `attribute` was concluded to be `value` because of `source`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Evidence {
+    pub attribute: Attribute,
+    pub value: String,
+    pub source: Source,
+    /// Port the observation was made on, if any
+    pub port: Option<u16>,
+}
+
+impl Evidence {
+    pub fn new(attribute: Attribute, value: &str, source: Source) -> Self {
+        Self {
+            attribute,
+            value: value.to_string(),
+            source,
+            port: None,
+        }
+    }
+
+    pub fn on_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+}
+
+impl fmt::Display for Evidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} <- {}", self.attribute, self.value, self.source)?;
+        if let Some(port) = self.port {
+            write!(f, " on port {}", port)?;
+        }
+        Ok(())
+    }
+}
+
+/// Banner substrings that name the operating system, e.g. in "SSH-2.0-OpenSSH_8.9p1 Ubuntu-3"
+const OS_MARKERS: &[(&str, &str)] = &[
+    ("Ubuntu", "Linux (Ubuntu)"),
+    ("Debian", "Linux (Debian)"),
+    ("Raspbian", "Linux (Raspbian)"),
+    ("CentOS", "Linux (CentOS)"),
+    ("Red Hat", "Linux (Red Hat)"),
+    ("Fedora", "Linux (Fedora)"),
+    ("FreeBSD", "FreeBSD"),
+    ("OpenBSD", "OpenBSD"),
+    ("NetBSD", "NetBSD"),
+    ("Windows", "Windows"),
+    ("Microsoft", "Windows"),
+    ("Cisco", "Cisco IOS"),
+    ("RouterOS", "MikroTik RouterOS"),
+];
+
+/// NIC makers whose devices run one known OS family
+const VENDOR_OS: &[(&str, &str)] = &[
+    ("Apple", "macOS/iOS"),
+    ("Raspberry Pi", "Linux"),
+    ("Routerboard", "MikroTik RouterOS"),
+    ("Cisco", "Cisco IOS"),
+    ("Ubiquiti", "Linux"),
+    ("Synology", "Linux"),
+    ("QNAP", "Linux"),
+];
+
+/// The OS family implied by a NIC vendor, with the OUI `prefix` as evidence.
+pub fn os_from_vendor(vendor: &str, prefix: &str) -> Option<Evidence> {
+    VENDOR_OS
+        .iter()
+        .find(|(maker, _)| vendor.contains(maker))
+        .map(|(_, os)| Evidence::new(Attribute::Os, os, Source::Oui(prefix.to_string())))
+}
+
+/// The OS family implied by the IP TTL a reply arrived with: hosts start at
+/// 64 (Linux and most Unix) or 128 (Windows), and each hop takes one off.
+pub fn os_from_ttl(ttl: u8) -> Option<Evidence> {
+    let os = match ttl {
+        33..=64 => "Linux",
+        65..=128 => "Windows",
+        _ => return None,
+    };
+    Some(Evidence::new(Attribute::Os, os, Source::Ttl(ttl)))
+}
+
+/// The OS a banner names, e.g. "Linux (Ubuntu)".
+pub fn os_named_in(banner: &str) -> Option<&'static str> {
+    OS_MARKERS
+        .iter()
+        .find(|(marker, _)| banner.contains(marker))
+        .map(|(_, os)| *os)
+}
+
+/// The OS a banner names, with evidence pointing at the matching substring.
+pub fn os_from_banner(banner: &str, port: u16) -> Option<Evidence> {
+    OS_MARKERS
+        .iter()
+        .find(|(marker, _)| banner.contains(marker))
+        .map(|(marker, os)| {
+            Evidence::new(Attribute::Os, os, Source::Banner(marker.to_string())).on_port(port)
+        })
+}
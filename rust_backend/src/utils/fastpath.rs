@@ -0,0 +1,74 @@
+use crate::utils::scan_config::ScanConfig;
+use crate::utils::targets::TargetSpec;
+use pnet::datalink;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Probe timeout for this machine: its closed ports refuse at once and its
+/// open ones answer within milliseconds
+pub const LOCAL_TIMEOUT: Duration = Duration::from_millis(300);
+/// Probes in flight at once against this machine
+pub const LOCAL_CONCURRENCY: usize = 1024;
+
+/// Addresses assigned to this machine's interfaces.
+pub fn local_addresses() -> Vec<IpAddr> {
+    datalink::interfaces()
+        .into_iter()
+        .flat_map(|iface| iface.ips.into_iter().map(|net| net.ip()))
+        .collect()
+}
+
+/// Whether `ip` is this machine: a loopback address or one of `local`.
+pub fn is_local(ip: IpAddr, local: &[IpAddr]) -> bool {
+    ip.is_loopback() || local.contains(&ip)
+}
+
+/// Whether every target of `specs` is this machine. Networks and ranges
+/// count only inside the loopback range, hostnames only as "localhost".
+pub fn all_local(specs: &[TargetSpec], local: &[IpAddr]) -> bool {
+    !specs.is_empty()
+        && specs.iter().all(|spec| match spec {
+            TargetSpec::Address(ip) => is_local(*ip, local),
+            TargetSpec::Range(first, last) => {
+                (first.is_loopback() && last.is_loopback())
+                    || (first == last && is_local(*first, local))
+            }
+            TargetSpec::Network(cidr) => {
+                cidr.split_once('/').is_some_and(|(base, prefix)| {
+                    match (base.parse::<IpAddr>(), prefix.parse::<u8>()) {
+                        (Ok(IpAddr::V4(base)), Ok(prefix)) => base.is_loopback() && prefix >= 8,
+                        (Ok(IpAddr::V6(base)), Ok(prefix)) => base.is_loopback() && prefix == 128,
+                        _ => false,
+                    }
+                })
+            }
+            TargetSpec::Hostname(name) => {
+                let name = name.trim_end_matches('.').to_ascii_lowercase();
+                name == "localhost" || name.ends_with(".localhost")
+            }
+        })
+}
+
+/// `live_hosts` to scan. A network scan leaves out `own`, the address of
+/// this machine, unless this machine is what was asked for (`local_fast`).
+pub fn hosts_to_scan(
+    live_hosts: Vec<IpAddr>,
+    own: Option<IpAddr>,
+    local_fast: bool,
+) -> Vec<IpAddr> {
+    match own {
+        Some(own) if !local_fast => live_hosts.into_iter().filter(|ip| *ip != own).collect(),
+        _ => live_hosts,
+    }
+}
+
+/// `config` for scanning this machine: the timeout, concurrency and retries
+/// left unset get fast defaults, the ones given are kept.
+pub fn tune(config: ScanConfig) -> ScanConfig {
+    ScanConfig {
+        timeout: config.timeout.or(Some(LOCAL_TIMEOUT)),
+        concurrency: config.concurrency.or(Some(LOCAL_CONCURRENCY)),
+        retries: config.retries.or(Some(0)),
+        ..config
+    }
+}
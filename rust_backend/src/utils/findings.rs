@@ -0,0 +1,185 @@
+use colored::*;
+use std::fmt;
+use std::net::IpAddr;
+
+/// How serious a finding is, ordered from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Low => "LOW",
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+
+    /// Contribution of one finding to a risk score
+    pub fn weight(&self) -> u32 {
+        match self {
+            Severity::Info => 0,
+            Severity::Low => 1,
+            Severity::Medium => 4,
+            Severity::High => 10,
+            Severity::Critical => 25,
+        }
+    }
+
+    pub fn colored(&self) -> ColoredString {
+        match self {
+            Severity::Info => self.label().normal(),
+            Severity::Low => self.label().blue(),
+            Severity::Medium => self.label().yellow(),
+            Severity::High => self.label().red(),
+            Severity::Critical => self.label().red().bold(),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Something noteworthy about a host or service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub port: Option<u16>,
+    pub title: String,
+    pub detail: String,
+    /// Hosts the finding is about, when it is not already filed under one
+    pub hosts: Vec<IpAddr>,
+    /// Stable identifier of a recurring alert, used to acknowledge it
+    pub id: Option<String>,
+    /// Stable kind of the finding (see [`code`]), unaffected by rewording the title
+    pub code: Option<&'static str>,
+}
+
+impl Finding {
+    pub fn new(severity: Severity, port: Option<u16>, title: &str, detail: String) -> Self {
+        Self {
+            severity,
+            port,
+            title: title.to_string(),
+            detail,
+            hosts: Vec::new(),
+            id: None,
+            code: None,
+        }
+    }
+
+    pub fn with_hosts(mut self, hosts: Vec<IpAddr>) -> Self {
+        self.hosts = hosts;
+        self
+    }
+
+    pub fn with_id(mut self, id: String) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.port {
+            Some(port) => write!(
+                f,
+                "[{}] {} (port {}): {}",
+                self.severity, self.title, port, self.detail
+            ),
+            None => write!(f, "[{}] {}: {}", self.severity, self.title, self.detail),
+        }
+    }
+}
+
+/// Codes of the findings compliance controls are mapped to. Titles are for
+/// people and may be reworded; these may not change.
+pub mod code {
+    pub const TELNET: &str = "weak-telnet";
+    pub const FTP_WITHOUT_TLS: &str = "weak-ftp-cleartext";
+    pub const SNMP_V1_V2C: &str = "weak-snmp-community";
+    pub const SMB1: &str = "weak-smb1";
+    pub const HTTP_ADMIN_PANEL: &str = "weak-http-admin";
+    pub const VNC_WITHOUT_AUTH: &str = "weak-vnc-noauth";
+    pub const TFTP: &str = "weak-tftp";
+    pub const LEGACY_SERVICE: &str = "weak-legacy-service";
+    pub const CAMERA_STREAM_WITHOUT_AUTH: &str = "weak-rtsp-noauth";
+    pub const OPEN_PROXY: &str = "weak-open-proxy";
+    pub const DATA_STORE_WITHOUT_AUTH: &str = "weak-datastore-noauth";
+    pub const TLS_SSL3: &str = "tls-ssl3";
+    pub const TLS_DEPRECATED_VERSIONS: &str = "tls-deprecated-versions";
+    pub const TLS_NO_MODERN_VERSION: &str = "tls-no-tls12";
+    pub const TLS_INSECURE_CIPHERS: &str = "tls-insecure-ciphers";
+    pub const TLS_WEAK_CIPHERS: &str = "tls-weak-ciphers";
+    pub const CERT_EXPIRED: &str = "cert-expired";
+    pub const CERT_SELF_SIGNED: &str = "cert-self-signed";
+    pub const SMB1_ACCEPTED: &str = "dc-smb1";
+    pub const SMB_SIGNING_NOT_REQUIRED: &str = "smb-signing-optional";
+    pub const ANONYMOUS_ROOT_DSE: &str = "dc-anonymous-rootdse";
+    pub const AS_REP_WITHOUT_PREAUTH: &str = "dc-asrep-no-preauth";
+    pub const MISSING_LEGAL_BANNER: &str = "banner-no-legal-warning";
+    pub const KNOWN_CVE: &str = "cve-known";
+}
+
+/// Short FNV-1a digest of `parts`, stable across runs and builds; used for
+/// the IDs users acknowledge or annotate findings by.
+pub fn digest(parts: &[&str]) -> String {
+    let hash = parts
+        .iter()
+        .flat_map(|part| part.bytes().chain([0]))
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{:012x}", hash >> 16)
+}
+
+/// Stable ID of a finding about `ip`: its own if set, else derived from the
+/// host, port and title so it survives changing details.
+pub fn finding_id(ip: IpAddr, finding: &Finding) -> String {
+    finding.id.clone().unwrap_or_else(|| {
+        let port = finding.port.map(|p| p.to_string()).unwrap_or_default();
+        digest(&[&ip.to_string(), &port, &finding.title])
+    })
+}
+
+/// What `finding_id` derives from besides the host, so the same finding is
+/// recognised after its host moves to another address.
+pub fn subject_id(finding: &Finding) -> String {
+    finding.id.clone().unwrap_or_else(|| {
+        let port = finding.port.map(|p| p.to_string()).unwrap_or_default();
+        digest(&[&port, &finding.title])
+    })
+}
+
+/// Sorts findings most severe first, keeping the original order within a severity.
+pub fn sort_by_severity(findings: &mut [Finding]) {
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+}
+
+/// Highest possible risk score
+pub const MAX_RISK_SCORE: u32 = 100;
+
+/// Sums finding weights into a 0-100 risk score.
+pub fn risk_score(findings: &[Finding]) -> u32 {
+    findings
+        .iter()
+        .map(|finding| finding.severity.weight())
+        .sum::<u32>()
+        .min(MAX_RISK_SCORE)
+}
@@ -1,44 +1,141 @@
 use crate::detect_dns;
 use crate::detect_ftp;
 use crate::detect_http;
+use crate::detect_onvif;
+use crate::detect_rtsp;
+use crate::detect_sip;
+use crate::detect_smb;
 use crate::detect_smtp;
 use crate::detect_ssh;
 use crate::fingerprint_mac;
-use std::net::Ipv4Addr;
+use crate::scanners::os_fingerprint;
+use crate::scanners::{igmp, linklocal};
+use crate::utils::evidence::{self, Attribute, Evidence, Source};
+use crate::utils::os_fusion::{self, OsGuess};
+use crate::utils::oui;
+use crate::utils::routing;
+use std::net::IpAddr;
 
 #[derive(Debug, Clone)]
 pub struct HostFingerprintResult {
-    pub ip: Ipv4Addr,
+    pub ip: IpAddr,
     pub details: Option<String>,
     pub os: Option<String>,
     pub vendor: Option<String>,
+    pub model: Option<String>,
     pub serial: Option<String>,
+    /// Interface (and source address) the host is reached through
+    pub egress: Option<String>,
+    /// What each OS, vendor, model, serial and service conclusion is based on
+    pub evidence: Vec<Evidence>,
+    /// Candidate operating systems, most likely first; `os` is the first
+    pub os_guesses: Vec<OsGuess>,
 }
 
 impl HostFingerprintResult {
-    pub fn new(ip: Ipv4Addr) -> Self {
+    pub fn new(ip: IpAddr) -> Self {
         Self {
             ip,
             details: None,
             os: None,
             vendor: None,
+            model: None,
             serial: None,
+            egress: None,
+            evidence: Vec::new(),
+            os_guesses: Vec::new(),
+        }
+    }
+
+    /// Records `evidence`, skipping observations already recorded.
+    pub fn add_evidence(&mut self, evidence: Evidence) {
+        if !self.evidence.contains(&evidence) {
+            self.evidence.push(evidence);
+        }
+    }
+
+    /// The evidence behind conclusions about `attribute`.
+    pub fn evidence_for(&self, attribute: Attribute) -> impl Iterator<Item = &Evidence> {
+        self.evidence
+            .iter()
+            .filter(move |evidence| evidence.attribute == attribute)
+    }
+
+    /// Folds a duplicate result for the same host into this one, keeping
+    /// known values and filling in the ones this result lacks.
+    pub fn merge(&mut self, other: HostFingerprintResult) {
+        self.os = self.os.take().or(other.os);
+        self.vendor = self.vendor.take().or(other.vendor);
+        self.model = self.model.take().or(other.model);
+        self.serial = self.serial.take().or(other.serial);
+        self.egress = self.egress.take().or(other.egress);
+        self.details = match (self.details.take(), other.details) {
+            (Some(a), Some(b)) if a != b => Some(format!("{}\n{}", a, b)),
+            (a, b) => a.or(b),
+        };
+        for evidence in other.evidence {
+            self.add_evidence(evidence);
+        }
+        self.fuse_os();
+    }
+
+    /// Ranks the OS evidence and reports the most likely OS.
+    pub fn fuse_os(&mut self) {
+        self.os_guesses = os_fusion::fuse(&self.evidence);
+        if let Some(best) = self.os_guesses.first() {
+            self.os = Some(best.os.clone());
         }
     }
 }
 
-pub async fn fingerprint_host(ip: Ipv4Addr, ports: &[u16]) -> HostFingerprintResult {
+/// Fingerprints `ip`; `interface` (`--interface`) is where its MAC is looked
+/// up and `ttl` is the IP TTL of its echo reply, if discovery saw one.
+pub async fn fingerprint_host(
+    ip: IpAddr,
+    ports: &[u16],
+    interface: Option<&str>,
+    ttl: Option<u8>,
+) -> HostFingerprintResult {
     let mut result = HostFingerprintResult::new(ip);
-
-    // MAC fingerprinting
-    let mac = fingerprint_mac::fingerprint(ip).await;
+    if let Some(os) = ttl.and_then(evidence::os_from_ttl) {
+        result.add_evidence(os);
+    }
+    // The routing table and ARP are IPv4-only
+    let mac = match ip {
+        IpAddr::V4(ipv4) => {
+            result.egress = routing::select_source(ipv4, None)
+                .ok()
+                .map(|route| route.to_string());
+            fingerprint_mac::fingerprint_on(ipv4, interface).await
+        }
+        // SLAAC addresses with an EUI-64 identifier carry the MAC in them
+        IpAddr::V6(ipv6) => {
+            let mac = linklocal::eui64_mac(ipv6).map(|mac| mac.to_string());
+            fingerprint_mac::MacFingerprint {
+                vendor: mac.as_deref().and_then(oui::lookup),
+                mac,
+                error: None,
+            }
+        }
+    };
+    if let (Some(mac_addr), Some(vendor)) = (&mac.mac, &mac.vendor) {
+        let prefix = mac_addr.get(..8).unwrap_or(mac_addr);
+        result.add_evidence(Evidence::new(
+            Attribute::Vendor,
+            vendor,
+            Source::Oui(prefix.to_string()),
+        ));
+        if let Some(os) = evidence::os_from_vendor(vendor, prefix) {
+            result.add_evidence(os);
+        }
+    }
     if let Some(mac_addr) = mac.mac {
         result
             .details
             .get_or_insert_with(String::new)
             .push_str(&format!("\nMAC: {}", mac_addr));
     }
-    if let Some(vendor) = mac.vendor {
+    if let Some(vendor) = &mac.vendor {
         result
             .details
             .get_or_insert_with(String::new)
@@ -48,13 +145,14 @@ pub async fn fingerprint_host(ip: Ipv4Addr, ports: &[u16]) -> HostFingerprintRes
         result
             .details
             .get_or_insert_with(String::new)
-            .push_str(&format!("\nMAC error: {}", mac_err));
+            .push_str(&format!("\nMAC: unavailable ({})", mac_err));
     }
 
     // SSH detection on all user-supplied ports
     for &port in ports {
         let ssh = detect_ssh::detect(ip, port).await;
         if ssh.detected {
+            record_service(&mut result, "SSH", port, ssh.banner.as_deref());
             result
                 .details
                 .get_or_insert_with(String::new)
@@ -70,6 +168,7 @@ pub async fn fingerprint_host(ip: Ipv4Addr, ports: &[u16]) -> HostFingerprintRes
     for &port in ports {
         let dns = detect_dns::detect(ip, port).await;
         if dns.detected {
+            record_service(&mut result, "DNS", port, None);
             result
                 .details
                 .get_or_insert_with(String::new)
@@ -81,6 +180,7 @@ pub async fn fingerprint_host(ip: Ipv4Addr, ports: &[u16]) -> HostFingerprintRes
     for &port in ports {
         let http = detect_http::detect(ip, port).await;
         if http.detected {
+            record_service(&mut result, "HTTP", port, http.banner.as_deref());
             result
                 .details
                 .get_or_insert_with(String::new)
@@ -96,6 +196,7 @@ pub async fn fingerprint_host(ip: Ipv4Addr, ports: &[u16]) -> HostFingerprintRes
     for &port in ports {
         let smtp = detect_smtp::detect(ip, port).await;
         if smtp.detected {
+            record_service(&mut result, "SMTP", port, smtp.banner.as_deref());
             result
                 .details
                 .get_or_insert_with(String::new)
@@ -111,6 +212,7 @@ pub async fn fingerprint_host(ip: Ipv4Addr, ports: &[u16]) -> HostFingerprintRes
     for &port in ports {
         let ftp = detect_ftp::detect(ip, port).await;
         if ftp.detected {
+            record_service(&mut result, "FTP", port, ftp.banner.as_deref());
             result
                 .details
                 .get_or_insert_with(String::new)
@@ -122,5 +224,157 @@ pub async fn fingerprint_host(ip: Ipv4Addr, ports: &[u16]) -> HostFingerprintRes
         }
     }
 
+    // SMB answers on 445 point to Windows (or a Samba server imitating it)
+    if ports.contains(&445) {
+        let smb = detect_smb::detect(ip, 445).await;
+        if smb.detected {
+            record_service(&mut result, "SMB", 445, None);
+            // The NTLM challenge's version pins down the release
+            let os = smb
+                .host_info
+                .as_ref()
+                .and_then(|info| info.windows_release())
+                .unwrap_or("Windows");
+            result.add_evidence(
+                Evidence::new(Attribute::Os, os, Source::Probe("SMB".to_string())).on_port(445),
+            );
+            result
+                .details
+                .get_or_insert_with(String::new)
+                .push_str(&format!("\nSMB detected on port 445: {}", smb.summary()));
+        }
+    }
+
+    // SIP detection on the SIP ports among those supplied; phones and PBXes
+    // name their make and model in the Server/User-Agent header
+    for &port in ports
+        .iter()
+        .filter(|port| [detect_sip::SIP_PORT, detect_sip::SIPS_PORT].contains(port))
+    {
+        let sip = detect_sip::detect(ip, port).await;
+        if sip.detected {
+            result
+                .details
+                .get_or_insert_with(String::new)
+                .push_str(&format!(
+                    "\nSIP detected on port {}: {}",
+                    port,
+                    sip.summary()
+                ));
+            record_service(&mut result, "SIP", port, None);
+            if let Some(agent) = &sip.agent {
+                for (attribute, value) in [
+                    (Attribute::Vendor, &sip.vendor),
+                    (Attribute::Model, &sip.model),
+                ] {
+                    if let Some(value) = value {
+                        result.add_evidence(
+                            Evidence::new(attribute, value, Source::Banner(agent.clone()))
+                                .on_port(port),
+                        );
+                    }
+                }
+            }
+            result.vendor = result.vendor.take().or(sip.vendor);
+            result.model = result.model.take().or(sip.model);
+        }
+    }
+
+    // ONVIF device information when an RTSP port suggests a camera; the
+    // device service is found through WS-Discovery, else on port 80
+    if ports.contains(&detect_rtsp::RTSP_PORT) {
+        let onvif = detect_onvif::detect(ip, 80).await;
+        if onvif.detected {
+            result
+                .details
+                .get_or_insert_with(String::new)
+                .push_str(&format!("\nONVIF detected: {}", onvif.summary()));
+            let serial = onvif
+                .device
+                .as_ref()
+                .and_then(|device| device.serial.clone());
+            for (attribute, value) in [
+                (Attribute::Vendor, onvif.vendor()),
+                (Attribute::Model, onvif.model()),
+                (Attribute::Serial, serial),
+            ] {
+                if let Some(value) = value {
+                    result.add_evidence(
+                        Evidence::new(
+                            attribute,
+                            &value,
+                            Source::Probe("ONVIF GetDeviceInformation".to_string()),
+                        )
+                        .on_port(80),
+                    );
+                }
+            }
+            result.vendor = result.vendor.take().or(onvif.vendor());
+            result.model = result.model.take().or(onvif.model());
+            result.serial = result
+                .serial
+                .take()
+                .or(onvif.device.and_then(|device| device.serial));
+        }
+    }
+
+    // TTL, window and option order of a SYN/ACK narrow down the OS family;
+    // the raw sockets this takes need root, so without it the step is skipped
+    if let IpAddr::V4(ipv4) = ip {
+        match os_fingerprint::fingerprint(ipv4, ports).await {
+            Ok(Some(stack)) => {
+                if let Some(os) = stack.evidence() {
+                    result.add_evidence(os);
+                }
+                result
+                    .details
+                    .get_or_insert_with(String::new)
+                    .push_str(&format!("\nTCP/IP stack on {}", stack));
+            }
+            Ok(None) => {}
+            Err(e) => tracing::debug!(%ip, "stack fingerprint skipped: {}", e),
+        }
+    }
+
+    // Multicast groups the host reported to an IGMP query: streaming,
+    // discovery and clustering protocols that no port scan shows
+    if let IpAddr::V4(ipv4) = ip {
+        match igmp::groups_of(ipv4, interface).await {
+            Ok(groups) if !groups.is_empty() => {
+                let groups: Vec<String> = groups.into_iter().map(igmp::describe_group).collect();
+                result
+                    .details
+                    .get_or_insert_with(String::new)
+                    .push_str(&format!("\nMulticast groups: {}", groups.join(", ")));
+            }
+            Ok(_) => {}
+            Err(e) => tracing::debug!(%ip, "IGMP query skipped: {}", e),
+        }
+    }
+
+    // The NIC maker is the fallback when no service named the device vendor
+    result.vendor = result.vendor.take().or(mac.vendor);
+    // Banners, probes and the NIC vendor may disagree about the OS
+    result.fuse_os();
     result
 }
+
+/// Records that `service` answered on `port`, and the OS its banner names.
+fn record_service(
+    result: &mut HostFingerprintResult,
+    service: &str,
+    port: u16,
+    banner: Option<&str>,
+) {
+    result.add_evidence(
+        Evidence::new(
+            Attribute::Service,
+            service,
+            Source::Probe(service.to_string()),
+        )
+        .on_port(port),
+    );
+    if let Some(os) = banner.and_then(|banner| evidence::os_from_banner(banner, port)) {
+        result.add_evidence(os);
+    }
+}
@@ -0,0 +1,146 @@
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+/// Delay before starting the next connection attempt (RFC 8305 "Connection Attempt Delay")
+pub const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Address family that answered a dual-stack connection race
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    pub fn of(addr: &SocketAddr) -> Self {
+        if addr.is_ipv6() {
+            AddressFamily::Ipv6
+        } else {
+            AddressFamily::Ipv4
+        }
+    }
+}
+
+impl fmt::Display for AddressFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressFamily::Ipv4 => write!(f, "IPv4"),
+            AddressFamily::Ipv6 => write!(f, "IPv6"),
+        }
+    }
+}
+
+/// The winning connection of a race, along with the address that answered
+#[derive(Debug)]
+pub struct RacedConnection {
+    pub stream: TcpStream,
+    pub addr: SocketAddr,
+    pub family: AddressFamily,
+}
+
+/// Resolves a hostname (or IP literal) and orders the addresses per RFC 8305.
+pub async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("Failed to resolve {host}: {e}"))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("No addresses found for {host}"));
+    }
+    Ok(interleave_families(addrs))
+}
+
+/// Interleaves IPv6 and IPv4 addresses, starting with IPv6, keeping resolver order
+/// within each family. Duplicate addresses are dropped.
+pub fn interleave_families(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let mut v6 = Vec::new();
+    let mut v4 = Vec::new();
+    for addr in addrs {
+        let bucket = if addr.is_ipv6() { &mut v6 } else { &mut v4 };
+        if !bucket.contains(&addr) {
+            bucket.push(addr);
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(v6.len() + v4.len());
+    let mut v6 = v6.into_iter();
+    let mut v4 = v4.into_iter();
+    loop {
+        match (v6.next(), v4.next()) {
+            (None, None) => break,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+    ordered
+}
+
+/// Resolves `host` and races connections to all of its addresses.
+pub async fn connect_host(
+    host: &str,
+    port: u16,
+    timeout: Duration,
+) -> Result<RacedConnection, String> {
+    let addrs = resolve(host, port).await?;
+    connect_addrs(&addrs, timeout).await
+}
+
+/// Races TCP connections to `addrs` in order, starting a new attempt every
+/// `CONNECTION_ATTEMPT_DELAY` (or immediately when an attempt fails).
+/// The first successful connection wins and the others are dropped.
+pub async fn connect_addrs(
+    addrs: &[SocketAddr],
+    timeout: Duration,
+) -> Result<RacedConnection, String> {
+    if addrs.is_empty() {
+        return Err("No addresses to connect to".to_string());
+    }
+    match tokio::time::timeout(timeout, race(addrs)).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("Timeout connecting to {}", addrs[0])),
+    }
+}
+
+async fn attempt(addr: SocketAddr) -> (SocketAddr, io::Result<TcpStream>) {
+    (addr, TcpStream::connect(addr).await)
+}
+
+async fn race(addrs: &[SocketAddr]) -> Result<RacedConnection, String> {
+    let mut pending = addrs.iter().copied();
+    let mut attempts = FuturesUnordered::new();
+    let mut errors = Vec::new();
+
+    if let Some(addr) = pending.next() {
+        attempts.push(attempt(addr));
+    }
+
+    loop {
+        let delay = tokio::time::sleep(CONNECTION_ATTEMPT_DELAY);
+        tokio::select! {
+            Some((addr, result)) = attempts.next() => match result {
+                Ok(stream) => {
+                    return Ok(RacedConnection {
+                        stream,
+                        addr,
+                        family: AddressFamily::of(&addr),
+                    });
+                }
+                Err(e) => {
+                    errors.push(format!("{addr}: {e}"));
+                    if let Some(next) = pending.next() {
+                        attempts.push(attempt(next));
+                    }
+                }
+            },
+            _ = delay, if pending.len() > 0 => {
+                if let Some(next) = pending.next() {
+                    attempts.push(attempt(next));
+                }
+            }
+            else => return Err(errors.join(" | ")),
+        }
+    }
+}
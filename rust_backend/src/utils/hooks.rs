@@ -0,0 +1,239 @@
+use crate::report::ScanReport;
+use futures::StreamExt;
+use std::net::IpAddr;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Commands run at once when --on-finding-concurrency is not given
+pub const DEFAULT_HOOK_CONCURRENCY: usize = 4;
+/// A hook still running after this long is killed
+pub const HOOK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// An open port a hook runs for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookEvent {
+    pub ip: IpAddr,
+    pub port: u16,
+    /// "tcp" or "udp"
+    pub transport: &'static str,
+    /// What service detection identified, if it ran
+    pub service: Option<String>,
+}
+
+/// How one hook invocation ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookOutcome {
+    pub event: HookEvent,
+    pub result: Result<(), String>,
+}
+
+/// Every open port in `report`, TCP then UDP per host, with the service
+/// detected on it. Identified services on ports no scan covered count too.
+pub fn events(report: &ScanReport) -> Vec<HookEvent> {
+    let mut events = Vec::new();
+    for host in &report.hosts {
+        let service_on = |port: u16| {
+            host.services
+                .iter()
+                .find(|service| service.port == port)
+                .and_then(|service| service.service.clone())
+        };
+        let tcp_open = host.tcp.as_ref().map_or(&[][..], |tcp| &tcp.open[..]);
+        let mut tcp_ports: Vec<u16> = tcp_open.to_vec();
+        for service in host.services.iter().filter(|s| s.service.is_some()) {
+            if !tcp_ports.contains(&service.port) {
+                tcp_ports.push(service.port);
+            }
+        }
+        tcp_ports.sort_unstable();
+        for port in tcp_ports {
+            events.push(HookEvent {
+                ip: host.ip,
+                port,
+                transport: "tcp",
+                service: service_on(port),
+            });
+        }
+        for &port in host.udp.as_ref().map_or(&[][..], |udp| &udp.open[..]) {
+            events.push(HookEvent {
+                ip: host.ip,
+                port,
+                transport: "udp",
+                service: None,
+            });
+        }
+    }
+    events
+}
+
+/// Which open ports qualify for the hook (`--on-finding-filter`). A port
+/// qualifies when it matches one of the values given for each kind; kinds
+/// left out match everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookFilter {
+    /// Detected service names, lowercase
+    services: Vec<String>,
+    ports: Vec<u16>,
+    transports: Vec<String>,
+}
+
+impl HookFilter {
+    /// A filter every port passes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses comma-separated `service:NAME`, `port:N` and `transport:tcp|udp`
+    /// terms, e.g. "service:http,service:https".
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut filter = Self::new();
+        for term in spec.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            let (kind, value) = term
+                .split_once(':')
+                .map(|(kind, value)| (kind.trim(), value.trim().to_ascii_lowercase()))
+                .filter(|(_, value)| !value.is_empty())
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid filter \"{}\"; expected service:NAME, port:N or transport:tcp|udp",
+                        term
+                    )
+                })?;
+            match kind {
+                "service" => filter.services.push(value),
+                "port" => filter.ports.push(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid port \"{}\" in filter", value))?,
+                ),
+                "transport" if value == "tcp" || value == "udp" => filter.transports.push(value),
+                "transport" => {
+                    return Err(format!(
+                        "Unknown transport \"{}\"; expected tcp or udp",
+                        value
+                    ));
+                }
+                _ => {
+                    return Err(format!(
+                        "Unknown filter \"{}\"; expected service, port or transport",
+                        kind
+                    ));
+                }
+            }
+        }
+        if filter == Self::new() {
+            return Err("--on-finding-filter needs at least one term".to_string());
+        }
+        Ok(filter)
+    }
+
+    /// Whether the hook runs for `event`. Service names compare without case;
+    /// ports with no identified service never match a service term.
+    pub fn matches(&self, event: &HookEvent) -> bool {
+        let service = event.service.as_deref().map(str::to_ascii_lowercase);
+        (self.services.is_empty() || service.is_some_and(|s| self.services.contains(&s)))
+            && (self.ports.is_empty() || self.ports.contains(&event.port))
+            && (self.transports.is_empty() || self.transports.iter().any(|t| t == event.transport))
+    }
+}
+
+/// Splits a command line into words; single or double quotes keep spaces
+/// inside a word. No shell is involved.
+pub fn split_command(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(format!("Unbalanced quote in \"{}\"", command));
+    }
+    if in_word {
+        words.push(word);
+    }
+    if words.is_empty() {
+        return Err("--on-finding needs a command".to_string());
+    }
+    Ok(words)
+}
+
+/// Command run for every open port (`--on-finding`), e.g.
+/// `screenshot.sh {ip} {port} {service}`
+#[derive(Debug, Clone)]
+pub struct FindingHook {
+    argv: Vec<String>,
+    concurrency: usize,
+}
+
+impl FindingHook {
+    /// Parses the command template; `concurrency` caps how many run at once.
+    pub fn new(command: &str, concurrency: usize) -> Result<Self, String> {
+        Ok(Self {
+            argv: split_command(command)?,
+            concurrency: concurrency.max(1),
+        })
+    }
+
+    /// Program and arguments for `event`, with {ip}, {port}, {transport} and
+    /// {service} filled in. Values are passed as arguments, never to a shell,
+    /// so whatever a service reports cannot inject commands.
+    pub fn command_for(&self, event: &HookEvent) -> Vec<String> {
+        self.argv
+            .iter()
+            .map(|arg| {
+                arg.replace("{ip}", &event.ip.to_string())
+                    .replace("{port}", &event.port.to_string())
+                    .replace("{transport}", event.transport)
+                    .replace("{service}", event.service.as_deref().unwrap_or(""))
+            })
+            .collect()
+    }
+
+    async fn run_one(&self, event: HookEvent) -> HookOutcome {
+        let argv = self.command_for(&event);
+        let child = Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .status();
+        let result = match tokio::time::timeout(HOOK_TIMEOUT, child).await {
+            Ok(Ok(status)) if status.success() => Ok(()),
+            Ok(Ok(status)) => Err(format!("{} exited with {}", argv[0], status)),
+            Ok(Err(e)) => Err(format!("Failed to run {}: {}", argv[0], e)),
+            Err(_) => Err(format!(
+                "{} killed after {}s",
+                argv[0],
+                HOOK_TIMEOUT.as_secs()
+            )),
+        };
+        HookOutcome { event, result }
+    }
+
+    /// Runs the command for every event, at most `concurrency` at a time.
+    pub async fn run(&self, events: Vec<HookEvent>) -> Vec<HookOutcome> {
+        futures::stream::iter(events)
+            .map(|event| self.run_one(event))
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await
+    }
+}
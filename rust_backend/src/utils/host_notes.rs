@@ -0,0 +1,198 @@
+use crate::utils::sightings::device_key;
+use crate::utils::{prettyprint, rdns};
+use chrono::{DateTime, Utc};
+use colored::*;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+/// Notes attached to hosts with `netscan note`, one row per note
+pub const HOST_NOTES_FILE: &str = "host_notes.csv";
+
+const HOST_NOTES_HEADER: [&str; 4] = ["ip", "mac", "noted_at", "note"];
+
+/// Something a user wants remembered about a host, e.g. "lab printer, ignore telnet"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostNote {
+    /// Address the host was last seen on
+    pub ip: IpAddr,
+    /// Lowercase and colon-separated, once a scan learned it
+    pub mac: Option<String>,
+    pub note: String,
+    pub at: DateTime<Utc>,
+}
+
+impl HostNote {
+    /// What identifies the host across runs: its MAC, else its address.
+    pub fn key(&self) -> String {
+        device_key(self.ip, self.mac.as_deref())
+    }
+
+    /// Whether this note belongs to the host at `ip` with `mac`. Hosts are
+    /// told apart by MAC when both sides know one, else by address.
+    pub fn matches(&self, ip: IpAddr, mac: Option<&str>) -> bool {
+        match (&self.mac, mac) {
+            (Some(noted), Some(mac)) => noted.eq_ignore_ascii_case(mac),
+            _ => self.ip == ip,
+        }
+    }
+}
+
+/// Host notes kept in the state directory, carried from run to run
+#[derive(Debug, Clone)]
+pub struct HostNotes {
+    dir: PathBuf,
+    notes: Vec<HostNote>,
+}
+
+impl HostNotes {
+    /// A store in `dir` without notes.
+    pub fn empty(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            notes: Vec::new(),
+        }
+    }
+
+    /// Reads the notes in `dir`; none if it has no notes file.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let mut store = Self::empty(dir);
+        let path = dir.join(HOST_NOTES_FILE);
+        if !path.exists() {
+            return Ok(store);
+        }
+        let mut reader = csv::Reader::from_path(&path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        for (line, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| e.to_string())?;
+            let bad_row = || format!("{}: malformed row {}", path.display(), line + 2);
+            let field = |i: usize| record.get(i).ok_or_else(bad_row);
+            store.notes.push(HostNote {
+                ip: field(0)?.parse().map_err(|_| bad_row())?,
+                mac: Some(field(1)?.to_string()).filter(|mac| !mac.is_empty()),
+                at: DateTime::parse_from_rfc3339(field(2)?)
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|_| bad_row())?,
+                note: field(3)?.to_string(),
+            });
+        }
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).map_err(|e| {
+            format!(
+                "Could not create state directory {}: {}",
+                self.dir.display(),
+                e
+            )
+        })?;
+        let path = self.dir.join(HOST_NOTES_FILE);
+        let mut writer = csv::Writer::from_path(&path)
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+        writer
+            .write_record(HOST_NOTES_HEADER)
+            .map_err(|e| e.to_string())?;
+        for note in &self.notes {
+            writer
+                .write_record([
+                    note.ip.to_string(),
+                    note.mac.clone().unwrap_or_default(),
+                    note.at.to_rfc3339(),
+                    note.note.clone(),
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+        writer.flush().map_err(|e| e.to_string())
+    }
+
+    pub fn notes(&self) -> impl Iterator<Item = &HostNote> {
+        self.notes.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Attaches `note` to the host at `ip` (with `mac`, if known). Does not save.
+    pub fn add(
+        &mut self,
+        ip: IpAddr,
+        mac: Option<&str>,
+        note: &str,
+        now: DateTime<Utc>,
+    ) -> &HostNote {
+        self.notes.push(HostNote {
+            ip,
+            mac: mac.map(str::to_ascii_lowercase),
+            note: note.to_string(),
+            at: now,
+        });
+        &self.notes[self.notes.len() - 1]
+    }
+
+    /// Removes every note of the host at `ip` and returns how many there
+    /// were. Does not save.
+    pub fn clear(&mut self, ip: IpAddr, mac: Option<&str>) -> usize {
+        let before = self.notes.len();
+        self.notes.retain(|note| !note.matches(ip, mac));
+        before - self.notes.len()
+    }
+
+    /// Notes of the host at `ip`, oldest first.
+    pub fn for_host(&self, ip: IpAddr, mac: Option<&str>) -> Vec<&HostNote> {
+        self.notes
+            .iter()
+            .filter(|note| note.matches(ip, mac))
+            .collect()
+    }
+
+    /// Follows the host at `ip` (with `mac`, if this run learned it): its
+    /// notes move to its new address, and notes taken before its MAC was
+    /// known adopt it. Returns whether any note changed. Does not save.
+    pub fn carry_forward(&mut self, ip: IpAddr, mac: Option<&str>) -> bool {
+        let mut changed = false;
+        for note in self.notes.iter_mut().filter(|note| note.matches(ip, mac)) {
+            if note.ip != ip {
+                note.ip = ip;
+                changed = true;
+            }
+            if note.mac.is_none()
+                && let Some(mac) = mac
+            {
+                note.mac = Some(mac.to_ascii_lowercase());
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Prints the notes of each host in `hosts` that has any.
+pub fn print_notes(notes: &HostNotes, hosts: &[(IpAddr, Option<String>)]) {
+    let noted: Vec<(IpAddr, Vec<&HostNote>)> = hosts
+        .iter()
+        .map(|(ip, mac)| (*ip, notes.for_host(*ip, mac.as_deref())))
+        .filter(|(_, list)| !list.is_empty())
+        .collect();
+    if noted.is_empty() {
+        return;
+    }
+    println!(
+        "\n{}",
+        prettyprint::console("📝 Host notes")
+            .bold()
+            .underline()
+            .blue()
+    );
+    for (ip, list) in noted {
+        for note in list {
+            println!(
+                "  {} {} {}",
+                rdns::label(ip).bold(),
+                note.note,
+                format!("({})", note.at.format("%Y-%m-%d")).dimmed()
+            );
+        }
+    }
+}
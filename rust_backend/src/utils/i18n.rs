@@ -0,0 +1,473 @@
+use crate::utils::prettyprint;
+use once_cell::sync::OnceCell;
+use std::borrow::Cow;
+use std::env;
+use std::fmt;
+
+static LOCALE: OnceCell<Locale> = OnceCell::new();
+
+/// Language of netscan's status messages, the ones in `Msg`. Result tables,
+/// the scanners' own output, reports, CSV and JSON are English whatever it
+/// is, so scripts parsing them keep working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::De];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+        }
+    }
+
+    /// The locale of a language tag like "de", "de-AT" or "de_DE.UTF-8".
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            _ => None,
+        }
+    }
+
+    /// The locale the first set of LC_ALL, LC_MESSAGES and LANG names, in
+    /// that order as for any POSIX program; English if it is not translated.
+    pub fn from_env_vars(
+        lc_all: Option<&str>,
+        lc_messages: Option<&str>,
+        lang: Option<&str>,
+    ) -> Self {
+        [lc_all, lc_messages, lang]
+            .into_iter()
+            .flatten()
+            .find(|value| !value.is_empty())
+            .and_then(Self::from_tag)
+            .unwrap_or_default()
+    }
+
+    pub fn from_env() -> Self {
+        let var = |name| env::var(name).ok();
+        Self::from_env_vars(
+            var("LC_ALL").as_deref(),
+            var("LC_MESSAGES").as_deref(),
+            var("LANG").as_deref(),
+        )
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Parses `--lang`.
+pub fn parse_locale(tag: &str) -> Result<Locale, String> {
+    Locale::from_tag(tag).ok_or_else(|| {
+        let codes: Vec<&str> = Locale::ALL.iter().map(|l| l.code()).collect();
+        format!(
+            "Unsupported language {:?}, expected one of {}",
+            tag,
+            codes.join(", ")
+        )
+    })
+}
+
+/// Sets the locale of this run; the first call wins.
+pub fn install(locale: Locale) {
+    let _ = LOCALE.set(locale);
+}
+
+/// The installed locale, English if none was.
+pub fn locale() -> Locale {
+    LOCALE.get().copied().unwrap_or_default()
+}
+
+/// A console status message; `{name}` in its text stands for the argument
+/// of that name, so a translation may put the arguments in any order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    Banner,
+    RedactedReports,
+    Examining,
+    NoAcks,
+    Acknowledged,
+    NoAnnotations,
+    ClearedAnnotation,
+    Marked,
+    NoHostNotes,
+    RemovedNotes,
+    Noted,
+    Replayed,
+    NotEmulated,
+    AllEmulatedDetected,
+    NothingListening,
+    OwnersRootOnly,
+    AllowedNoListener,
+    NotAllowed,
+    OnlyAllowedReachable,
+    Expanding,
+    ExpansionCapped,
+    TimingTemplate,
+    ProbesInFlight,
+    RateCapsLifted,
+    TargetsExcluded,
+    BandwidthCapped,
+    ProbeRateCapped,
+    ProbesSpaced,
+    ResolvingPtr,
+    NeighborSweep,
+    ArpSweep,
+    PingSweep,
+    NoLiveHosts,
+    Fingerprinting,
+    TcpSynScan,
+    TcpScan,
+    DualStackScan,
+    UdpScan,
+    GradingTls,
+    NoTlsEndpoints,
+    NoLikelyDcs,
+    NextScan,
+    TrafficSent,
+    EnvironmentWarnings,
+    LocalTargets,
+    ReportWritten,
+    WriteFailed,
+    ProtocolStatistics,
+    RunRecorded,
+    LoginBanners,
+    CertificateInventory,
+    ComplianceMatrix,
+    RetriedProbes,
+    MsfSuggestions,
+    DetectedServices,
+    PortOpen,
+    AnsweredOver,
+    Inventory,
+    OnLinkHint,
+    DcAuditProfile,
+    MonitorMode,
+    AvailabilityNeedsTcpScan,
+    CertAlertsNeedTlsGrade,
+    DeviceAlertsNeedInventory,
+    MutingAlerts,
+    ScanWindow,
+    OutsideWindows,
+    PassStopped,
+    WindowOpen,
+    WindowClosed,
+    PausedUntil,
+    NoWindowThisWeek,
+    Segments,
+    SegmentScan,
+    NoFindingPassed,
+    RunningHooks,
+    HooksDone,
+    EnumeratingIpv6,
+    DuplicatesMerged,
+    PtrRecords,
+    LiveHostsFound,
+    PtrOnlyHosts,
+    UnknownDevices,
+    AdaptiveTimeouts,
+    OuiRegistry,
+    PipelineScan,
+    CveLookup,
+}
+
+impl Msg {
+    fn en(&self) -> &'static str {
+        match self {
+            Msg::Banner => "🛰️  NetScan - Network Service Scanner",
+            Msg::RedactedReports => {
+                "🔒 Output is redacted: addresses and hostnames are pseudonymized"
+            }
+            Msg::Examining => "🔎 Examining {target} (budget {budget}s)...",
+            Msg::NoAcks => "No acknowledged alerts.",
+            Msg::Acknowledged => "✅ Acknowledged {id}:",
+            Msg::NoAnnotations => "No annotated findings.",
+            Msg::ClearedAnnotation => "✅ Cleared the annotation of {id}",
+            Msg::Marked => "✅ Marked {id} as {verdict}:",
+            Msg::NoHostNotes => "No host notes.",
+            Msg::RemovedNotes => "✅ Removed {count} note(s) of {host}",
+            Msg::Noted => "✅ Noted on {host}:",
+            Msg::Replayed => "✅ Replayed {count} samples",
+            Msg::NotEmulated => "Not emulated: {protocols}",
+            Msg::AllEmulatedDetected => "✅ All {count} emulated protocols detected",
+            Msg::NothingListening => "Nothing is listening.",
+            Msg::OwnersRootOnly => "Owners of other users' sockets are only shown to root.",
+            Msg::AllowedNoListener => "ℹ️  Allowed {port} has no listener",
+            Msg::NotAllowed => "❌ {socket} is not allowed ({owner})",
+            Msg::OnlyAllowedReachable => "✅ Only allowed ports are reachable from other machines",
+            Msg::Expanding => "🔭 Expanding {target} to {subnet}",
+            Msg::ExpansionCapped => {
+                "✂️  {subnet} has more than {max} hosts; pass --ip {cidr} to scan all of it"
+            }
+            Msg::TimingTemplate => "⏱️  Timing template {template}",
+            Msg::ProbesInFlight => "⚙️  {count} probes in flight ({limits}); --concurrency to override",
+            Msg::RateCapsLifted => "⚡ Rate caps lifted for local targets",
+            Msg::TargetsExcluded => "🚫 {excluded} target(s) excluded; {left} left",
+            Msg::BandwidthCapped => "📶 Bandwidth capped at {bandwidth}",
+            Msg::ProbeRateCapped => "📶 Probe rate capped at {rate} packets/s",
+            Msg::ProbesSpaced => "📶 Probes spaced {delay} apart",
+            Msg::ResolvingPtr => "🔤 Resolving PTR records for {count} targets...",
+            Msg::NeighborSweep => "🔎 Performing neighbor discovery sweep on {target}...",
+            Msg::ArpSweep => "🔎 Performing ARP sweep on {target}...",
+            Msg::PingSweep => "🔎 Performing ping sweep on {target}...",
+            Msg::NoLiveHosts => "No live hosts found. Exiting.",
+            Msg::Fingerprinting => "🕵️  Fingerprinting live hosts...",
+            Msg::TcpSynScan => "🔗 Performing TCP SYN scan...",
+            Msg::TcpScan => "🔗 Performing TCP scan...",
+            Msg::DualStackScan => "🔗 Performing dual-stack TCP scan of {host}...",
+            Msg::UdpScan => "🔗 Performing UDP scan...",
+            Msg::GradingTls => "🔐 Grading TLS endpoints...",
+            Msg::NoTlsEndpoints => "No TLS endpoints found.",
+            Msg::NoLikelyDcs => "No likely domain controllers found.",
+            Msg::NextScan => "💤 Next scan in {seconds}s",
+            Msg::TrafficSent => "📶 Traffic sent: {traffic}",
+            Msg::EnvironmentWarnings => "Environment warnings during this run:",
+            Msg::LocalTargets => {
+                "⚡ Local targets: skipping discovery and using short timeouts (--no-fast-path to disable)"
+            }
+            Msg::ReportWritten => "📄 {what} written to {path}",
+            Msg::WriteFailed => "Failed to write {path}: {error}",
+            Msg::ProtocolStatistics => "Per-protocol statistics",
+            Msg::RunRecorded => "🗄️  Run #{id} of {targets} recorded in {path}",
+            Msg::LoginBanners => "Login banners",
+            Msg::CertificateInventory => "Certificate inventory",
+            Msg::ComplianceMatrix => "Compliance matrix",
+            Msg::RetriedProbes => "Retried probes",
+            Msg::MsfSuggestions => "Metasploit suggestions",
+            Msg::DetectedServices => "Detected Services for {host}",
+            Msg::PortOpen => "{port}/tcp open",
+            Msg::AnsweredOver => "Port {port} answered over {family}",
+            Msg::Inventory => "📒 Inventory: {count} known devices",
+            Msg::OnLinkHint => {
+                "💡 {target} is on-link in {subnet}; use --ip {cidr} or --auto-expand to scan it all"
+            }
+            Msg::DcAuditProfile => {
+                "🏛️  Profile dc-audit: likely domain controllers will be deep-probed"
+            }
+            Msg::MonitorMode => "🔁 Monitor mode: rescanning every {seconds}s, state in {path}",
+            Msg::AvailabilityNeedsTcpScan => {
+                "Service availability tracking needs --tcpscan; only scan output will be produced."
+            }
+            Msg::CertAlertsNeedTlsGrade => "Certificate alerts need --tls-grade.",
+            Msg::DeviceAlertsNeedInventory => "Unapproved device alerts need --inventory.",
+            Msg::MutingAlerts => "🔕 Muting alerts: {rule}",
+            Msg::ScanWindow => "🕒 Scan window: {window}",
+            Msg::OutsideWindows => "⏸️  Outside scan windows; {next}",
+            Msg::PassStopped => {
+                "⏹️  Scan window closed; stopped this pass, the next starts when a window opens"
+            }
+            Msg::WindowOpen => "▶️  Scan window open; resuming",
+            Msg::WindowClosed => "⏸️  Scan window closed; {next}",
+            Msg::PausedUntil => "paused until {time}",
+            Msg::NoWindowThisWeek => "no window opens in the coming week",
+            Msg::Segments => "🧭 Segments",
+            Msg::SegmentScan => "🧭 Segment {interface}: scanning {targets}",
+            Msg::NoFindingPassed => "🪝 None of {count} open port(s) passed --on-finding-filter",
+            Msg::RunningHooks => "🪝 Running --on-finding for {count} open port(s)...",
+            Msg::HooksDone => "🪝 {succeeded} hook(s) succeeded, {failed} failed",
+            Msg::EnumeratingIpv6 => {
+                "🔎 Enumerating on-link IPv6 hosts on {interface} (ff02::1 and neighbor cache)..."
+            }
+            Msg::DuplicatesMerged => {
+                "🧹 {duplicates} duplicate target(s) merged; {unique} unique addresses"
+            }
+            Msg::PtrRecords => "{count} targets have PTR records.",
+            Msg::LiveHostsFound => "{count} live hosts found.",
+            Msg::PtrOnlyHosts => "🔤 {count} more host(s) known only from PTR records",
+            Msg::UnknownDevices => "⚠️  {count} unknown device(s), not in the inventory:",
+            Msg::AdaptiveTimeouts => {
+                "⏱️  Adaptive timeouts for {count} hosts: {fastest} to {slowest}"
+            }
+            Msg::OuiRegistry => "📚 OUI registry: {count} vendors ({path})",
+            Msg::PipelineScan => {
+                "🔗 Pipeline: detecting services on {ports} open TCP ports across {hosts} hosts; UDP detectors still try the other ports"
+            }
+            Msg::CveLookup => "🐞 Looking up known CVEs of the detected versions...",
+        }
+    }
+
+    fn de(&self) -> &'static str {
+        match self {
+            Msg::Banner => "🛰️  NetScan - Netzwerk-Dienstscanner",
+            Msg::RedactedReports => {
+                "🔒 Ausgabe ist geschwärzt: Adressen und Hostnamen sind pseudonymisiert"
+            }
+            Msg::Examining => "🔎 Untersuche {target} (Budget {budget}s)...",
+            Msg::NoAcks => "Keine bestätigten Alarme.",
+            Msg::Acknowledged => "✅ {id} bestätigt:",
+            Msg::NoAnnotations => "Keine annotierten Befunde.",
+            Msg::ClearedAnnotation => "✅ Annotation von {id} entfernt",
+            Msg::Marked => "✅ {id} als {verdict} markiert:",
+            Msg::NoHostNotes => "Keine Host-Notizen.",
+            Msg::RemovedNotes => "✅ {count} Notiz(en) von {host} entfernt",
+            Msg::Noted => "✅ Notiz zu {host}:",
+            Msg::Replayed => "✅ {count} Proben abgespielt",
+            Msg::NotEmulated => "Nicht emuliert: {protocols}",
+            Msg::AllEmulatedDetected => "✅ Alle {count} emulierten Protokolle erkannt",
+            Msg::NothingListening => "Nichts lauscht.",
+            Msg::OwnersRootOnly => "Besitzer der Sockets anderer Benutzer sieht nur root.",
+            Msg::AllowedNoListener => "ℹ️  Auf dem erlaubten Port {port} lauscht nichts",
+            Msg::NotAllowed => "❌ {socket} ist nicht erlaubt ({owner})",
+            Msg::OnlyAllowedReachable => {
+                "✅ Von anderen Rechnern sind nur erlaubte Ports erreichbar"
+            }
+            Msg::Expanding => "🔭 Erweitere {target} auf {subnet}",
+            Msg::ExpansionCapped => {
+                "✂️  {subnet} hat mehr als {max} Hosts; --ip {cidr} scannt es ganz"
+            }
+            Msg::TimingTemplate => "⏱️  Timing-Vorlage {template}",
+            Msg::ProbesInFlight => {
+                "⚙️  {count} gleichzeitige Proben ({limits}); mit --concurrency überschreibbar"
+            }
+            Msg::RateCapsLifted => "⚡ Ratenbegrenzung für lokale Ziele aufgehoben",
+            Msg::TargetsExcluded => "🚫 {excluded} Ziel(e) ausgeschlossen; {left} verbleiben",
+            Msg::BandwidthCapped => "📶 Bandbreite auf {bandwidth} begrenzt",
+            Msg::ProbeRateCapped => "📶 Probenrate auf {rate} Pakete/s begrenzt",
+            Msg::ProbesSpaced => "📶 Proben im Abstand von {delay}",
+            Msg::ResolvingPtr => "🔤 Löse PTR-Einträge für {count} Ziele auf...",
+            Msg::NeighborSweep => "🔎 Neighbor-Discovery-Suche in {target}...",
+            Msg::ArpSweep => "🔎 ARP-Suche in {target}...",
+            Msg::PingSweep => "🔎 Ping-Suche in {target}...",
+            Msg::NoLiveHosts => "Keine aktiven Hosts gefunden. Beende.",
+            Msg::Fingerprinting => "🕵️  Bestimme Fingerabdrücke der aktiven Hosts...",
+            Msg::TcpSynScan => "🔗 Führe TCP-SYN-Scan durch...",
+            Msg::TcpScan => "🔗 Führe TCP-Scan durch...",
+            Msg::DualStackScan => "🔗 Führe Dual-Stack-TCP-Scan von {host} durch...",
+            Msg::UdpScan => "🔗 Führe UDP-Scan durch...",
+            Msg::GradingTls => "🔐 Bewerte TLS-Endpunkte...",
+            Msg::NoTlsEndpoints => "Keine TLS-Endpunkte gefunden.",
+            Msg::NoLikelyDcs => "Keine wahrscheinlichen Domänencontroller gefunden.",
+            Msg::NextScan => "💤 Nächster Scan in {seconds}s",
+            Msg::TrafficSent => "📶 Gesendeter Verkehr: {traffic}",
+            Msg::EnvironmentWarnings => "Umgebungswarnungen während dieses Laufs:",
+            Msg::LocalTargets => {
+                "⚡ Lokale Ziele: keine Host-Erkennung, kurze Timeouts (abschaltbar mit --no-fast-path)"
+            }
+            Msg::ReportWritten => "📄 {what} nach {path} geschrieben",
+            Msg::WriteFailed => "Konnte {path} nicht schreiben: {error}",
+            Msg::ProtocolStatistics => "Protokollstatistik",
+            Msg::RunRecorded => "🗄️  Lauf #{id} über {targets} in {path} gespeichert",
+            Msg::LoginBanners => "Login-Banner",
+            Msg::CertificateInventory => "Zertifikatsinventar",
+            Msg::ComplianceMatrix => "Compliance-Matrix",
+            Msg::RetriedProbes => "Wiederholte Proben",
+            Msg::MsfSuggestions => "Metasploit-Vorschläge",
+            Msg::DetectedServices => "Erkannte Dienste auf {host}",
+            Msg::PortOpen => "{port}/tcp offen",
+            Msg::AnsweredOver => "Port {port} antwortete über {family}",
+            Msg::Inventory => "📒 Inventar: {count} bekannte Geräte",
+            Msg::OnLinkHint => {
+                "💡 {target} liegt im lokalen Netz {subnet}; --ip {cidr} oder --auto-expand scannt es ganz"
+            }
+            Msg::DcAuditProfile => {
+                "🏛️  Profil dc-audit: wahrscheinliche Domänencontroller werden gründlich geprüft"
+            }
+            Msg::MonitorMode => {
+                "🔁 Überwachungsmodus: erneuter Scan alle {seconds}s, Zustand in {path}"
+            }
+            Msg::AvailabilityNeedsTcpScan => {
+                "Die Verfügbarkeitsüberwachung braucht --tcpscan; es wird nur die Scan-Ausgabe erzeugt."
+            }
+            Msg::CertAlertsNeedTlsGrade => "Zertifikatsalarme brauchen --tls-grade.",
+            Msg::DeviceAlertsNeedInventory => {
+                "Alarme zu nicht freigegebenen Geräten brauchen --inventory."
+            }
+            Msg::MutingAlerts => "🔕 Stummgeschaltete Alarme: {rule}",
+            Msg::ScanWindow => "🕒 Scan-Fenster: {window}",
+            Msg::OutsideWindows => "⏸️  Außerhalb der Scan-Fenster; {next}",
+            Msg::PassStopped => {
+                "⏹️  Scan-Fenster geschlossen; dieser Durchlauf wurde angehalten, der nächste beginnt, wenn ein Fenster öffnet"
+            }
+            Msg::WindowOpen => "▶️  Scan-Fenster offen; es geht weiter",
+            Msg::WindowClosed => "⏸️  Scan-Fenster geschlossen; {next}",
+            Msg::PausedUntil => "pausiert bis {time}",
+            Msg::NoWindowThisWeek => "in der kommenden Woche öffnet kein Fenster",
+            Msg::Segments => "🧭 Segmente",
+            Msg::SegmentScan => "🧭 Segment {interface}: scanne {targets}",
+            Msg::NoFindingPassed => {
+                "🪝 Keiner von {count} offenen Port(s) hat --on-finding-filter passiert"
+            }
+            Msg::RunningHooks => "🪝 Führe --on-finding für {count} offene(n) Port(s) aus...",
+            Msg::HooksDone => "🪝 {succeeded} Hook(s) erfolgreich, {failed} fehlgeschlagen",
+            Msg::EnumeratingIpv6 => {
+                "🔎 Ermittle IPv6-Hosts im lokalen Netz auf {interface} (ff02::1 und Nachbar-Cache)..."
+            }
+            Msg::DuplicatesMerged => {
+                "🧹 {duplicates} doppelte(s) Ziel(e) zusammengeführt; {unique} eindeutige Adressen"
+            }
+            Msg::PtrRecords => "{count} Ziele haben PTR-Einträge.",
+            Msg::LiveHostsFound => "{count} aktive Hosts gefunden.",
+            Msg::PtrOnlyHosts => "🔤 {count} weitere(r) Host(s) nur aus PTR-Einträgen bekannt",
+            Msg::UnknownDevices => "⚠️  {count} unbekannte(s) Gerät(e), nicht im Inventar:",
+            Msg::AdaptiveTimeouts => {
+                "⏱️  Adaptive Timeouts für {count} Hosts: {fastest} bis {slowest}"
+            }
+            Msg::OuiRegistry => "📚 OUI-Register: {count} Hersteller ({path})",
+            Msg::PipelineScan => {
+                "🔗 Pipeline: Diensterkennung auf {ports} offenen TCP-Ports von {hosts} Hosts; UDP-Detektoren versuchen weiterhin die übrigen Ports"
+            }
+            Msg::CveLookup => "🐞 Suche bekannte CVEs der erkannten Versionen...",
+        }
+    }
+
+    /// The text of the message in `locale`, with its `{name}` placeholders.
+    pub fn text_in(&self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::En => self.en(),
+            Locale::De => self.de(),
+        }
+    }
+
+    /// The text of the message in the installed locale, as the console prints it.
+    pub fn text(&self) -> Cow<'static, str> {
+        prettyprint::console(self.text_in(locale()))
+    }
+
+    /// The message in the installed locale with `args`, as `(name, value)`, filled in.
+    pub fn with(&self, args: &[(&str, &dyn fmt::Display)]) -> String {
+        prettyprint::console(&fill(self.text_in(locale()), args)).into_owned()
+    }
+}
+
+impl fmt::Display for Msg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text())
+    }
+}
+
+/// Replaces each `{name}` placeholder of `template` with the argument of
+/// that name. Placeholders without an argument are left as they are.
+pub fn fill(template: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let Some(close) = rest.find('}') else {
+            break;
+        };
+        let name = &rest[1..close];
+        match args.iter().find(|(arg, _)| *arg == name) {
+            Some((_, value)) => out.push_str(&value.to_string()),
+            None => out.push_str(&rest[..=close]),
+        }
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
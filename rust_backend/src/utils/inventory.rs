@@ -0,0 +1,185 @@
+use crate::scanners::arpcache;
+use once_cell::sync::OnceCell;
+use std::fmt;
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// One known device from the user's inventory (`--inventory`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InventoryEntry {
+    pub ip: Option<IpAddr>,
+    /// Lowercase and colon-separated, e.g. "b8:27:eb:01:02:03"
+    pub mac: Option<String>,
+    pub hostname: Option<String>,
+    pub owner: Option<String>,
+    pub role: Option<String>,
+}
+
+impl fmt::Display for InventoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(hostname) = &self.hostname {
+            parts.push(hostname.clone());
+        }
+        if let Some(owner) = &self.owner {
+            parts.push(format!("owner {}", owner));
+        }
+        if let Some(role) = &self.role {
+            parts.push(format!("role {}", role));
+        }
+        if parts.is_empty() {
+            return f.write_str("in inventory");
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// The devices the user expects on the network
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inventory {
+    entries: Vec<InventoryEntry>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses CSV with a header naming any of the columns ip, mac, hostname,
+    /// owner and role, in any order; empty cells are allowed.
+    pub fn parse_csv(text: &str) -> Result<Self, String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes());
+        let headers = reader
+            .headers()
+            .map_err(|e| format!("Invalid inventory: {}", e))?
+            .clone();
+        let column = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header.eq_ignore_ascii_case(name))
+        };
+        let (ip_col, mac_col) = (column("ip"), column("mac"));
+        if ip_col.is_none() && mac_col.is_none() {
+            return Err("Inventory needs an ip or mac column".to_string());
+        }
+        let (hostname_col, owner_col, role_col) =
+            (column("hostname"), column("owner"), column("role"));
+
+        let mut inventory = Self::new();
+        for (line, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| format!("Invalid inventory: {}", e))?;
+            let cell = |col: Option<usize>| {
+                col.and_then(|col| record.get(col))
+                    .filter(|value| !value.is_empty())
+                    .map(String::from)
+            };
+            let ip = match cell(ip_col) {
+                Some(ip) => Some(ip.parse::<IpAddr>().map_err(|_| {
+                    format!("Invalid IP \"{}\" on inventory line {}", ip, line + 2)
+                })?),
+                None => None,
+            };
+            let mac = match cell(mac_col) {
+                Some(mac) => Some(normalize_mac(&mac).ok_or_else(|| {
+                    format!("Invalid MAC \"{}\" on inventory line {}", mac, line + 2)
+                })?),
+                None => None,
+            };
+            if ip.is_none() && mac.is_none() {
+                continue;
+            }
+            inventory.entries.push(InventoryEntry {
+                ip,
+                mac,
+                hostname: cell(hostname_col),
+                owner: cell(owner_col),
+                role: cell(role_col),
+            });
+        }
+        Ok(inventory)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        Self::parse_csv(&text)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry for the host at `ip`, matched by address, else by its MAC.
+    pub fn lookup(&self, ip: IpAddr, mac: Option<&str>) -> Option<&InventoryEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.ip == Some(ip))
+            .or_else(|| {
+                let mac = normalize_mac(mac?)?;
+                self.entries
+                    .iter()
+                    .find(|entry| entry.mac.as_deref() == Some(mac.as_str()))
+            })
+    }
+
+    /// The `hosts` that match no entry ("unknown devices"); `mac_of` gives
+    /// a host's MAC when it is known.
+    pub fn unknown_hosts(
+        &self,
+        hosts: &[IpAddr],
+        mac_of: impl Fn(IpAddr) -> Option<String>,
+    ) -> Vec<IpAddr> {
+        hosts
+            .iter()
+            .copied()
+            .filter(|&ip| self.lookup(ip, mac_of(ip).as_deref()).is_none())
+            .collect()
+    }
+}
+
+/// "AA-BB-CC-DD-EE-FF" or "aa:bb:cc:dd:ee:ff" as the latter.
+pub fn normalize_mac(text: &str) -> Option<String> {
+    let octets: Vec<&str> = text.split([':', '-']).collect();
+    let valid = octets.len() == 6
+        && octets
+            .iter()
+            .all(|octet| octet.len() == 2 && u8::from_str_radix(octet, 16).is_ok());
+    valid.then(|| octets.join(":").to_ascii_lowercase())
+}
+
+/// The inventory given with --inventory, once loaded.
+static INVENTORY: OnceCell<Inventory> = OnceCell::new();
+
+/// Makes `inventory` the one reports label hosts from; only the first call counts.
+pub fn install(inventory: Inventory) {
+    let _ = INVENTORY.set(inventory);
+}
+
+/// MAC of `ip` if discovery saw it (ARP is IPv4-only).
+pub fn discovered_mac(ip: IpAddr) -> Option<String> {
+    match ip {
+        IpAddr::V4(ip) => arpcache::cached(ip).map(|mac| mac.to_string()),
+        IpAddr::V6(_) => None,
+    }
+}
+
+/// How the installed inventory describes `ip`, if it lists the host.
+pub fn describe(ip: IpAddr) -> Option<String> {
+    INVENTORY
+        .get()?
+        .lookup(ip, discovered_mac(ip).as_deref())
+        .map(InventoryEntry::to_string)
+}
+
+/// The installed inventory, if --inventory was given.
+pub fn installed() -> Option<&'static Inventory> {
+    INVENTORY.get()
+}
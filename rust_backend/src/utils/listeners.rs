@@ -0,0 +1,250 @@
+use crate::utils::warnings;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Socket tables of the kernel, as `(path, transport)`
+const SOCKET_TABLES: [(&str, &str); 4] = [
+    ("/proc/net/tcp", "tcp"),
+    ("/proc/net/tcp6", "tcp"),
+    ("/proc/net/udp", "udp"),
+    ("/proc/net/udp6", "udp"),
+];
+/// State column of a listening TCP socket
+const TCP_LISTEN: &str = "0A";
+/// State column of an unconnected UDP socket, i.e. one bound to receive
+const UDP_UNCONNECTED: &str = "07";
+/// Linux's default ip_local_port_range
+const DEFAULT_EPHEMERAL_PORTS: (u16, u16) = (32768, 60999);
+
+/// The process that holds a socket open
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketOwner {
+    pub pid: u32,
+    /// Command name, from /proc/<pid>/comm
+    pub name: String,
+}
+
+/// A socket of this machine waiting for connections or datagrams
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listener {
+    /// "tcp" or "udp"
+    pub transport: &'static str,
+    pub address: IpAddr,
+    pub port: u16,
+    pub inode: u64,
+    /// Unknown when the socket belongs to a process of another user and we are not root
+    pub owner: Option<SocketOwner>,
+}
+
+impl Listener {
+    /// Whether the socket accepts traffic from other machines, not only this
+    /// one. An IPv4-mapped loopback address (::ffff:127.0.0.1) is loopback too.
+    pub fn is_exposed(&self) -> bool {
+        !self.address.to_canonical().is_loopback()
+    }
+
+    /// Whether this is most likely a client's UDP socket rather than a
+    /// service: a datagram socket the kernel gave an ephemeral port when it
+    /// first sent, which /proc lists as unconnected just like a bound one.
+    pub fn is_udp_client(&self, ephemeral: (u16, u16)) -> bool {
+        self.transport == "udp" && (ephemeral.0..=ephemeral.1).contains(&self.port)
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.address {
+            IpAddr::V6(address) => write!(f, "{}/[{}]:{}", self.transport, address, self.port),
+            IpAddr::V4(address) => write!(f, "{}/{}:{}", self.transport, address, self.port),
+        }
+    }
+}
+
+/// Parses an address of the kernel socket tables: "0100007F:0016", hex with
+/// each 32-bit word in host byte order.
+fn parse_endpoint(text: &str) -> Option<(IpAddr, u16)> {
+    let (address, port) = text.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let word = |hex: &str| u32::from_str_radix(hex, 16).ok().map(u32::to_ne_bytes);
+    let address = match address.len() {
+        8 => IpAddr::V4(Ipv4Addr::from(word(address)?)),
+        32 => {
+            let mut octets = [0u8; 16];
+            for (i, chunk) in octets.chunks_mut(4).enumerate() {
+                chunk.copy_from_slice(&word(address.get(i * 8..i * 8 + 8)?)?);
+            }
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+    Some((address, port))
+}
+
+/// Parses /proc/net/{tcp,tcp6,udp,udp6}, keeping the listening TCP sockets
+/// and the bound UDP ones. Owners are left unknown.
+pub fn parse_socket_table(contents: &str, transport: &'static str) -> Vec<Listener> {
+    let listening = if transport == "tcp" {
+        TCP_LISTEN
+    } else {
+        UDP_UNCONNECTED
+    };
+    contents
+        .lines()
+        .skip(1) // header
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || fields[3] != listening {
+                return None;
+            }
+            let (address, port) = parse_endpoint(fields[1])?;
+            Some(Listener {
+                transport,
+                address,
+                port,
+                inode: fields[9].parse().ok()?,
+                owner: None,
+            })
+        })
+        .collect()
+}
+
+/// Inode of the socket a /proc/<pid>/fd link points to, e.g. "socket:[4242]".
+pub fn parse_socket_link(link: &str) -> Option<u64> {
+    link.strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// The process holding each socket inode open, from the fd links in /proc.
+/// Processes we may not look into are skipped.
+fn socket_owners() -> HashMap<u64, SocketOwner> {
+    let mut owners = HashMap::new();
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return owners;
+    };
+    for process in processes.flatten() {
+        let Some(pid) = process
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse().ok())
+        else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(process.path().join("fd")) else {
+            continue;
+        };
+        let name = fs::read_to_string(process.path().join("comm"))
+            .map(|comm| comm.trim().to_string())
+            .unwrap_or_default();
+        for fd in fds.flatten() {
+            if let Some(inode) = fs::read_link(fd.path())
+                .ok()
+                .and_then(|link| parse_socket_link(&link.to_string_lossy()))
+            {
+                owners.entry(inode).or_insert_with(|| SocketOwner {
+                    pid,
+                    name: name.clone(),
+                });
+            }
+        }
+    }
+    owners
+}
+
+/// The kernel's ephemeral port range, or Linux's default if unreadable.
+pub fn ephemeral_ports() -> (u16, u16) {
+    warnings::ephemeral_port_range().unwrap_or(DEFAULT_EPHEMERAL_PORTS)
+}
+
+/// Listening sockets of this machine with their owning processes, ordered
+/// by transport, port and address. UDP client sockets are left out.
+pub fn read_listeners() -> Result<Vec<Listener>, String> {
+    let ephemeral = ephemeral_ports();
+    let mut listeners = Vec::new();
+    for (path, transport) in SOCKET_TABLES {
+        match fs::read_to_string(path) {
+            Ok(contents) => listeners.extend(
+                parse_socket_table(&contents, transport)
+                    .into_iter()
+                    .filter(|listener| !listener.is_udp_client(ephemeral)),
+            ),
+            // No IPv6 support in this kernel
+            Err(_) if path.ends_with('6') => {}
+            Err(e) => return Err(format!("Failed to read {}: {}", path, e)),
+        }
+    }
+    let owners = socket_owners();
+    for listener in &mut listeners {
+        listener.owner = owners.get(&listener.inode).cloned();
+    }
+    listeners.sort_by_key(|l| (l.transport, l.port, l.address));
+    listeners.dedup_by_key(|l| (l.transport, l.port, l.address));
+    Ok(listeners)
+}
+
+/// Parses an expected-ports allowlist: "22,80,443,udp/53"; bare ports are TCP.
+pub fn parse_expected(spec: &str) -> Result<BTreeSet<(&'static str, u16)>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (transport, port) = match part.split_once('/') {
+                Some((transport, port)) => match transport.to_ascii_lowercase().as_str() {
+                    "tcp" => ("tcp", port),
+                    "udp" => ("udp", port),
+                    _ => {
+                        return Err(format!(
+                            "Unknown transport in {:?}, expected tcp or udp",
+                            part
+                        ));
+                    }
+                },
+                None => ("tcp", part),
+            };
+            port.parse()
+                .map(|port| (transport, port))
+                .map_err(|_| format!("Invalid port in {:?}", part))
+        })
+        .collect()
+}
+
+/// How the listeners of this machine compare with an allowlist
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListenerAudit {
+    /// Sockets reachable from other machines on a port not allowed
+    pub unexpected: Vec<Listener>,
+    /// Allowed ports nothing listens on
+    pub missing: Vec<(&'static str, u16)>,
+}
+
+impl ListenerAudit {
+    /// Compares `listeners` with `expected`. Loopback-only sockets are never
+    /// unexpected: other machines cannot reach them.
+    pub fn new(listeners: &[Listener], expected: &BTreeSet<(&'static str, u16)>) -> Self {
+        let unexpected = listeners
+            .iter()
+            .filter(|l| l.is_exposed() && !expected.contains(&(l.transport, l.port)))
+            .cloned()
+            .collect();
+        let missing = expected
+            .iter()
+            .filter(|(transport, port)| {
+                !listeners
+                    .iter()
+                    .any(|l| l.transport == *transport && l.port == *port)
+            })
+            .copied()
+            .collect();
+        Self {
+            unexpected,
+            missing,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.unexpected.is_empty()
+    }
+}
@@ -0,0 +1,47 @@
+use crate::utils::prettyprint;
+use std::io::Write;
+use tracing::level_filters::LevelFilter;
+
+/// Level that `-v` repeated `verbose` times asks for: per-host progress (info),
+/// per-port probe results (debug), then raw protocol traces (trace).
+pub fn verbosity_level(verbose: u8) -> LevelFilter {
+    match verbose {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Sends diagnostics (`tracing` events) to stderr, so stdout keeps only the
+/// report tables. `json` writes one JSON object per event, with its spans.
+pub fn init(level: LevelFilter, json: bool) -> Result<(), String> {
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(|| ConsoleStderr);
+    let result = if json {
+        builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .try_init()
+    } else {
+        builder.without_time().with_target(false).try_init()
+    };
+    result.map_err(|e| format!("Failed to set up logging: {}", e))
+}
+
+/// Stderr, with each event passed through `prettyprint::console`.
+struct ConsoleStderr;
+
+impl Write for ConsoleStderr {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        std::io::stderr().write_all(prettyprint::console(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()
+    }
+}
@@ -1,3 +1,55 @@
 pub mod fingerprinting;
 pub mod prettyprint;
-pub mod reports;
\ No newline at end of file
+pub mod reports;
+pub mod happy_eyeballs;
+pub mod routing;
+pub mod vlan;
+pub mod ratelimit;
+pub mod warnings;
+pub mod errors;
+pub mod compact;
+pub mod clock;
+pub mod sockets;
+pub mod targets;
+pub mod anomaly;
+pub mod roles;
+pub mod ber;
+pub mod findings;
+pub mod cert_inventory;
+pub mod monitor;
+pub mod compliance;
+pub mod banners;
+pub mod timezone;
+pub mod schedule;
+pub mod mutes;
+pub mod annotations;
+pub mod availability;
+pub mod segments;
+pub mod oui;
+pub mod netutil;
+pub mod scan_config;
+pub mod progress;
+pub mod evidence;
+pub mod ports_presets;
+pub mod os_fusion;
+pub mod rdns;
+pub mod inventory;
+pub mod sightings;
+pub mod unknowns;
+pub mod replay;
+pub mod quicklook;
+pub mod storage;
+pub mod hooks;
+pub mod scan_diff;
+pub mod timing;
+pub mod logging;
+pub mod banner_parse;
+pub mod redact;
+pub mod host_notes;
+pub mod selftest;
+pub mod msf;
+pub mod fastpath;
+pub mod autotune;
+pub mod timing_template;
+pub mod listeners;
+pub mod i18n;
\ No newline at end of file
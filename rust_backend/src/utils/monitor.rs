@@ -0,0 +1,559 @@
+use crate::utils::availability::AvailabilityTracker;
+use crate::utils::cert_inventory::{CertificateChange, CertificateInventory};
+use crate::utils::findings::{self, Finding, Severity, digest};
+use crate::utils::inventory::{self, Inventory};
+use crate::utils::mutes::MuteList;
+use crate::utils::oui;
+use crate::utils::prettyprint;
+use crate::utils::sightings::{self, DeviceSighting, DeviceSightings};
+use chrono::{DateTime, Utc};
+use colored::*;
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+/// Where monitor mode keeps state between runs unless --state-dir is given
+pub const DEFAULT_STATE_DIR: &str = ".netscan";
+/// Alert when a certificate expires within this many days unless overridden
+pub const DEFAULT_CERT_EXPIRY_DAYS: i64 = 30;
+
+const CERTIFICATE_STATE_FILE: &str = "certificates.csv";
+const AVAILABILITY_STATE_FILE: &str = "availability.csv";
+const DEVICE_STATE_FILE: &str = "devices.csv";
+const ALERT_LOG_FILE: &str = "alerts.log";
+/// Mute rules, re-read on every run so they can be edited while the daemon runs
+pub const MUTE_FILE: &str = "mutes.conf";
+/// Every acknowledgement ever made, oldest first
+const ACK_FILE: &str = "acknowledgements.csv";
+const ACK_HEADER: [&str; 4] = ["id", "acknowledged_at", "note", "alert"];
+
+/// A user's sign-off that an alert describes an expected change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Acknowledgement {
+    pub id: String,
+    pub at: DateTime<Utc>,
+    pub note: String,
+    /// The alert as it was logged when acknowledged
+    pub alert: String,
+}
+
+/// Persistent state of a monitor-mode daemon
+#[derive(Debug, Clone)]
+pub struct MonitorState {
+    dir: PathBuf,
+}
+
+impl MonitorState {
+    /// Uses `dir`, creating it if needed.
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Could not create state directory {}: {}", dir.display(), e))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Certificate inventory saved by the previous run, if there was one.
+    pub fn previous_certificates(&self) -> Result<Option<CertificateInventory>, String> {
+        let path = self.dir.join(CERTIFICATE_STATE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        CertificateInventory::load(&path).map(Some)
+    }
+
+    pub fn save_certificates(&self, inventory: &CertificateInventory) -> Result<(), String> {
+        inventory.save(&self.dir.join(CERTIFICATE_STATE_FILE))
+    }
+
+    /// Availability history of the services seen so far; empty on the first run.
+    pub fn availability(&self) -> Result<AvailabilityTracker, String> {
+        let path = self.dir.join(AVAILABILITY_STATE_FILE);
+        if !path.exists() {
+            return Ok(AvailabilityTracker::new());
+        }
+        AvailabilityTracker::load(&path)
+    }
+
+    pub fn save_availability(&self, tracker: &AvailabilityTracker) -> Result<(), String> {
+        tracker.save(&self.dir.join(AVAILABILITY_STATE_FILE))
+    }
+
+    /// Devices seen by earlier runs; none on the first run.
+    pub fn sightings(&self) -> Result<DeviceSightings, String> {
+        let path = self.dir.join(DEVICE_STATE_FILE);
+        if !path.exists() {
+            return Ok(DeviceSightings::new());
+        }
+        DeviceSightings::load(&path)
+    }
+
+    pub fn save_sightings(&self, sightings: &DeviceSightings) -> Result<(), String> {
+        sightings.save(&self.dir.join(DEVICE_STATE_FILE))
+    }
+
+    /// Mute rules from the state directory; none if the file does not exist.
+    pub fn mutes(&self) -> Result<MuteList, String> {
+        MuteList::load(&self.dir.join(MUTE_FILE))
+    }
+
+    /// Acknowledgement history, oldest first; empty if nothing was acknowledged.
+    pub fn acknowledgements(&self) -> Result<Vec<Acknowledgement>, String> {
+        let path = self.dir.join(ACK_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut reader = csv::Reader::from_path(&path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let mut acks = Vec::new();
+        for (line, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| e.to_string())?;
+            let bad_row = || format!("{}: malformed row {}", path.display(), line + 2);
+            let field = |i: usize| record.get(i).ok_or_else(bad_row);
+            acks.push(Acknowledgement {
+                id: field(0)?.to_string(),
+                at: DateTime::parse_from_rfc3339(field(1)?)
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|_| bad_row())?,
+                note: field(2)?.to_string(),
+                alert: field(3)?.to_string(),
+            });
+        }
+        Ok(acks)
+    }
+
+    /// IDs of every acknowledged alert.
+    pub fn acknowledged_ids(&self) -> Result<BTreeSet<String>, String> {
+        Ok(self
+            .acknowledgements()?
+            .into_iter()
+            .map(|ack| ack.id)
+            .collect())
+    }
+
+    /// Acknowledges the logged alert `id` so it stops notifying, and records
+    /// the acknowledgement in the history.
+    pub fn acknowledge(
+        &self,
+        id: &str,
+        note: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Acknowledgement, String> {
+        if let Some(ack) = self.acknowledgements()?.into_iter().find(|ack| ack.id == id) {
+            return Err(format!(
+                "Alert {} was already acknowledged on {}",
+                id,
+                ack.at.format("%Y-%m-%d %H:%M")
+            ));
+        }
+        let alert = self
+            .logged_alert(id)?
+            .ok_or_else(|| format!("No alert with ID {} in {}", id, ALERT_LOG_FILE))?;
+        let ack = Acknowledgement {
+            id: id.to_string(),
+            at: now,
+            note: note.to_string(),
+            alert,
+        };
+
+        let path = self.dir.join(ACK_FILE);
+        let is_new = !path.exists();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+        let mut writer = csv::Writer::from_writer(file);
+        if is_new {
+            writer
+                .write_record(ACK_HEADER)
+                .map_err(|e| e.to_string())?;
+        }
+        writer
+            .write_record([&ack.id, &ack.at.to_rfc3339(), &ack.note, &ack.alert])
+            .map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())?;
+        Ok(ack)
+    }
+
+    /// IDs of the alerts in the alert log that were not muted when raised.
+    fn notified_ids(&self) -> Result<BTreeSet<String>, String> {
+        let path = self.dir.join(ALERT_LOG_FILE);
+        if !path.exists() {
+            return Ok(BTreeSet::new());
+        }
+        let log = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        Ok(log
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(' ').skip(1);
+                let id = fields.next()?;
+                (fields.next() != Some("[muted]")).then(|| id.to_string())
+            })
+            .collect())
+    }
+
+    /// The most recent alert-log entry for `id`, without its timestamp and markers.
+    fn logged_alert(&self, id: &str) -> Result<Option<String>, String> {
+        let path = self.dir.join(ALERT_LOG_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let log = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        Ok(log.lines().rev().find_map(|line| {
+            let (_, rest) = line.split_once(' ')?;
+            let rest = rest.strip_prefix(&format!("{} ", id))?;
+            let rest = rest
+                .trim_start_matches("[muted] ")
+                .trim_start_matches("[acknowledged] ");
+            Some(rest.to_string())
+        }))
+    }
+
+    /// Prints alerts that are neither muted nor acknowledged and appends all
+    /// of them to the alert log, marking the silenced ones. An unreadable mute
+    /// or acknowledgement file silences nothing.
+    pub fn raise(&self, alerts: &[Finding], now: DateTime<Utc>) -> Result<(), String> {
+        let mutes = self.mutes().unwrap_or_else(|e| {
+            tracing::warn!("{}; notifying on every alert", e);
+            MuteList::default()
+        });
+        let acknowledged = self.acknowledged_ids().unwrap_or_else(|e| {
+            tracing::warn!("{}; notifying on every alert", e);
+            BTreeSet::new()
+        });
+        let mut markers = Vec::with_capacity(alerts.len());
+        for alert in alerts {
+            let id = alert_id(alert);
+            let marker = if acknowledged.contains(&id) {
+                " [acknowledged]"
+            } else if mutes.is_muted(alert, now) {
+                " [muted]"
+            } else {
+                let line = format!(
+                    "🔔 [{}] {}: {} {}",
+                    alert.severity.colored(),
+                    alert.title.bold(),
+                    alert.detail,
+                    format!("(netscan ack {})", id).dimmed()
+                );
+                eprintln!("{}", prettyprint::console(&line));
+                ""
+            };
+            markers.push((id, marker));
+        }
+        let silenced = markers.iter().filter(|(_, m)| !m.is_empty()).count();
+        if silenced > 0 {
+            eprintln!(
+                "{}",
+                prettyprint::console(&format!(
+                    "🔕 {} muted or acknowledged alert(s) recorded in {}",
+                    silenced, ALERT_LOG_FILE
+                ))
+                .dimmed()
+            );
+        }
+        if alerts.is_empty() {
+            return Ok(());
+        }
+        let path = self.dir.join(ALERT_LOG_FILE);
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+        for (alert, (id, marker)) in alerts.iter().zip(markers) {
+            writeln!(log, "{} {}{} {}", now.to_rfc3339(), id, marker, alert)
+                .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// The ID `netscan ack` takes for `alert`: its own if it has one, else a
+/// digest of its text.
+pub fn alert_id(alert: &Finding) -> String {
+    alert
+        .id
+        .clone()
+        .unwrap_or_else(|| digest(&[&alert.to_string()]))
+}
+
+fn change_id(change: &CertificateChange) -> String {
+    digest(&["certificate-change", &change.to_string()])
+}
+
+/// Alerts for certificates expiring within `expiry_days` and for endpoints
+/// whose certificate issuer or key changed since `previous`.
+pub fn certificate_alerts(
+    previous: Option<&CertificateInventory>,
+    current: &CertificateInventory,
+    now: DateTime<Utc>,
+    expiry_days: i64,
+) -> Vec<Finding> {
+    let mut alerts = expiry_alerts(current, now, expiry_days);
+    if let Some(previous) = previous {
+        alerts.extend(change_alerts(previous, current));
+    }
+    findings::sort_by_severity(&mut alerts);
+    alerts
+}
+
+/// One alert per certificate expiring within `expiry_days` or expired.
+pub fn expiry_alerts(
+    current: &CertificateInventory,
+    now: DateTime<Utc>,
+    expiry_days: i64,
+) -> Vec<Finding> {
+    let mut alerts = Vec::new();
+    for entry in current.expiring_within(now, expiry_days) {
+        let cert = &entry.certificate;
+        let days = cert.days_until_expiry(now);
+        let served_by: Vec<String> = entry
+            .endpoints
+            .iter()
+            .map(|(ip, port)| format!("{}:{}", ip, port))
+            .collect();
+        let (severity, title) = if days < 0 {
+            (Severity::High, "Certificate expired")
+        } else {
+            (Severity::Medium, "Certificate expiring soon")
+        };
+        let mut hosts: Vec<_> = entry.endpoints.iter().map(|(ip, _)| *ip).collect();
+        hosts.dedup();
+        alerts.push(
+            Finding::new(
+                severity,
+                entry.endpoints.first().map(|(_, port)| *port),
+                title,
+                format!(
+                    "{} expires {} ({} days) on {}",
+                    cert.subject,
+                    cert.not_after.format("%Y-%m-%d"),
+                    days,
+                    served_by.join(", ")
+                ),
+            )
+            .with_hosts(hosts)
+            // Stays the same as the days count down, so one acknowledgement covers it
+            .with_id(digest(&[title, &cert.issuer, &cert.serial, &cert.key_fingerprint])),
+        );
+    }
+    alerts
+}
+
+/// One alert per endpoint whose certificate issuer or key changed since `previous`.
+pub fn change_alerts(previous: &CertificateInventory, current: &CertificateInventory) -> Vec<Finding> {
+    current
+        .changes_since(previous)
+        .into_iter()
+        .map(|change| {
+            let (endpoint, title) = match &change {
+                CertificateChange::IssuerChanged { endpoint, .. } => {
+                    (*endpoint, "Certificate issuer changed")
+                }
+                CertificateChange::KeyChanged { endpoint, .. } => {
+                    (*endpoint, "Certificate key changed")
+                }
+            };
+            Finding::new(Severity::High, Some(endpoint.1), title, change.to_string())
+                .with_hosts(vec![endpoint.0])
+                .with_id(change_id(&change))
+        })
+        .collect()
+}
+
+/// The inventory to compare the next run against: `current`, except that
+/// endpoints in `pending` keep their `previous` certificate so an
+/// unacknowledged change keeps alerting.
+pub fn approved_baseline(
+    previous: Option<&CertificateInventory>,
+    current: &CertificateInventory,
+    pending: &[(IpAddr, u16)],
+) -> CertificateInventory {
+    let mut baseline = CertificateInventory::new();
+    for entry in current.entries() {
+        for &(ip, port) in &entry.endpoints {
+            let held = previous
+                .filter(|_| pending.contains(&(ip, port)))
+                .and_then(|previous| previous.certificate_at(ip, port));
+            baseline.record(ip, port, held.unwrap_or(&entry.certificate));
+        }
+    }
+    baseline
+}
+
+/// Compares this run's certificates with the last approved state, raises the
+/// resulting alerts and stores the new baseline. An expiring certificate is
+/// alerted on once; a certificate change keeps alerting until it is
+/// acknowledged and then becomes part of the baseline. A run that saw no
+/// certificates at all, e.g. because the TLS endpoints were unreachable,
+/// leaves the baseline as it was.
+pub fn check_certificates(
+    state: &MonitorState,
+    current: &CertificateInventory,
+    now: DateTime<Utc>,
+    expiry_days: i64,
+) -> Result<Vec<Finding>, String> {
+    let previous = state.previous_certificates()?;
+    let mut raised = state.notified_ids()?;
+    let mut alerts: Vec<Finding> = expiry_alerts(current, now, expiry_days)
+        .into_iter()
+        .filter(|alert| raised.insert(alert_id(alert)))
+        .collect();
+    if let Some(previous) = &previous {
+        alerts.extend(change_alerts(previous, current));
+    }
+    findings::sort_by_severity(&mut alerts);
+    state.raise(&alerts, now)?;
+    if current.is_empty() {
+        return Ok(alerts);
+    }
+    let acknowledged = state.acknowledged_ids()?;
+    let pending: Vec<(IpAddr, u16)> = previous
+        .as_ref()
+        .map(|previous| current.changes_since(previous))
+        .unwrap_or_default()
+        .iter()
+        .filter(|change| !acknowledged.contains(&change_id(change)))
+        .map(|change| match change {
+            CertificateChange::IssuerChanged { endpoint, .. }
+            | CertificateChange::KeyChanged { endpoint, .. } => *endpoint,
+        })
+        .collect();
+    state.save_certificates(&approved_baseline(previous.as_ref(), current, &pending))?;
+    Ok(alerts)
+}
+
+/// Adds this run's TCP scan of `ports` on `targets` to the availability
+/// history, prints the services that went down or came back and the running
+/// totals, and stores the history.
+pub fn check_availability(
+    state: &MonitorState,
+    targets: &[IpAddr],
+    ports: &[u16],
+    open: &[(IpAddr, u16)],
+    now: DateTime<Utc>,
+) -> Result<AvailabilityTracker, String> {
+    let mut tracker = state.availability()?;
+    for change in tracker.record(targets, ports, open, now) {
+        let endpoint = std::net::SocketAddr::new(change.ip, change.port);
+        if change.up {
+            println!("{}", prettyprint::console(&format!("⬆️  {} is back up", endpoint)).green());
+        } else {
+            eprintln!("{}", prettyprint::console(&format!("⬇️  {} went down", endpoint)).red().bold());
+        }
+    }
+    tracker.print_report();
+    state.save_availability(&tracker)?;
+    Ok(tracker)
+}
+
+/// Alert for a device that is not in the approved inventory. Its ID follows
+/// the device rather than its address, so one acknowledgement approves it.
+pub fn rogue_device_alert(device: &DeviceSighting) -> Finding {
+    let mut identity = vec![device.mac.clone().unwrap_or_else(|| "MAC unknown".to_string())];
+    if let Some(vendor) = &device.vendor {
+        identity.push(vendor.clone());
+    }
+    Finding::new(
+        Severity::Medium,
+        None,
+        "Unapproved device",
+        format!(
+            "{} ({}) is not in the inventory; first seen {}",
+            device.ip,
+            identity.join(", "),
+            device.first_seen.format("%Y-%m-%d %H:%M UTC")
+        ),
+    )
+    .with_hosts(vec![device.ip])
+    .with_id(device_alert_id(&device.key()))
+}
+
+/// ID of the unapproved-device alert for the device with `key` (see
+/// `sightings::device_key`).
+pub fn device_alert_id(key: &str) -> String {
+    digest(&["rogue-device", key])
+}
+
+/// The `hosts` that `inventory` does not list, less those whose
+/// unapproved-device alert was acknowledged in `dir`; `mac_of` gives a host's
+/// MAC when it is known. A missing state directory is not created.
+pub fn unapproved_hosts(
+    dir: &Path,
+    inventory: &Inventory,
+    hosts: &[IpAddr],
+    mac_of: impl Fn(IpAddr) -> Option<String>,
+) -> Vec<IpAddr> {
+    let acknowledged = if dir.exists() {
+        let state = MonitorState {
+            dir: dir.to_path_buf(),
+        };
+        state.acknowledged_ids().unwrap_or_else(|e| {
+            tracing::warn!("{}; listing every unknown device", e);
+            BTreeSet::new()
+        })
+    } else {
+        BTreeSet::new()
+    };
+    inventory
+        .unknown_hosts(hosts, &mac_of)
+        .into_iter()
+        .filter(|&ip| {
+            let key = sightings::device_key(ip, mac_of(ip).as_deref());
+            !acknowledged.contains(&device_alert_id(&key))
+        })
+        .collect()
+}
+
+/// Records the devices that answered this run and alerts on those the
+/// `inventory` does not list; `mac_of` gives a host's MAC when discovery
+/// learned it. Unapproved devices keep alerting each run until acknowledged
+/// or added to the inventory.
+pub fn check_devices(
+    state: &MonitorState,
+    inventory: &Inventory,
+    live_hosts: &[IpAddr],
+    mac_of: impl Fn(IpAddr) -> Option<String>,
+    now: DateTime<Utc>,
+) -> Result<Vec<Finding>, String> {
+    let mut sightings = state.sightings()?;
+    let mut alerts = Vec::new();
+    for &ip in live_hosts {
+        let mac = mac_of(ip);
+        let vendor = mac.as_deref().and_then(oui::lookup);
+        let device = sightings.record(ip, mac.as_deref(), vendor.as_deref(), now);
+        if inventory.lookup(ip, mac.as_deref()).is_none() {
+            alerts.push(rogue_device_alert(device));
+        }
+    }
+    state.raise(&alerts, now)?;
+    state.save_sightings(&sightings)?;
+    Ok(alerts)
+}
+
+/// `check_devices` against the --inventory list with the MACs discovery saw,
+/// if an inventory was given.
+pub fn check_installed_inventory(
+    state: &MonitorState,
+    live_hosts: &[IpAddr],
+    now: DateTime<Utc>,
+) -> Option<Result<Vec<Finding>, String>> {
+    let inventory = inventory::installed()?;
+    Some(check_devices(
+        state,
+        inventory,
+        live_hosts,
+        inventory::discovered_mac,
+        now,
+    ))
+}
@@ -0,0 +1,264 @@
+use crate::scanners::service_detection::{DetectorRegistry, Protocol, ServiceDetectionResult};
+use crate::utils::evidence::{Attribute, Source};
+use crate::utils::findings::{self, Finding, Severity};
+use crate::utils::{netutil, prettyprint, rdns};
+use colored::*;
+use metasploit_tools::ServiceInfo;
+use metasploit_tools::suggest::{self, ModuleSuggestion};
+use metasploit_tools::vuln::{self, CveFeed, CveMatch};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+/// Days a downloaded NVD feed is reused before it is fetched again
+pub const CVE_FEED_MAX_AGE_DAYS: u64 = 7;
+
+/// What metasploit_tools needs to know about a service detection found on
+/// `ip`; `None` if nothing was identified on the port.
+pub fn service_info(
+    registry: &DetectorRegistry,
+    ip: IpAddr,
+    result: &ServiceDetectionResult,
+) -> Option<ServiceInfo> {
+    if !result.is_detected() {
+        return None;
+    }
+    let mut name = None;
+    let mut banner = None;
+    // Banner evidence about the OS quotes only the word that named it
+    for evidence in result.evidence.iter().filter(|e| e.attribute == Attribute::Service) {
+        match &evidence.source {
+            Source::Probe(detector) => name = protocol_label(registry, detector),
+            Source::Banner(text) => banner = Some(text.clone()),
+            _ => {}
+        }
+    }
+    // The banner grab only names SSH itself; anything else it saw is unknown
+    let name = name.unwrap_or_else(|| match result.service.as_deref() {
+        Some("SSH") => Protocol::Ssh.label(),
+        _ => "unknown",
+    });
+    Some(ServiceInfo {
+        host: ip.to_string(),
+        port: result.port,
+        name: name.to_string(),
+        product: result.product.clone(),
+        version: result.version.clone(),
+        banner,
+        transport: result.transport,
+    })
+}
+
+/// Label of the protocol whose detector is named `detector`, e.g. "postgres"
+/// for "PostgreSQL", as the suggestion rules key services.
+fn protocol_label(registry: &DetectorRegistry, detector: &str) -> Option<&'static str> {
+    Protocol::ALL
+        .into_iter()
+        .find(|protocol| {
+            registry
+                .get(*protocol)
+                .is_some_and(|d| d.name() == detector)
+        })
+        .map(|protocol| protocol.label())
+}
+
+/// Metasploit modules suggested for every detected service of a run, by
+/// host and port
+#[derive(Debug, Clone, Default)]
+pub struct MsfSuggestions {
+    entries: Vec<(ServiceInfo, Vec<ModuleSuggestion>)>,
+}
+
+impl MsfSuggestions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the services detected on `ip` that at least one module is suggested
+    /// for; fails if the user's rules file is broken.
+    pub fn add_results(
+        &mut self,
+        registry: &DetectorRegistry,
+        ip: IpAddr,
+        results: &[ServiceDetectionResult],
+    ) -> Result<(), String> {
+        for info in results
+            .iter()
+            .filter_map(|result| service_info(registry, ip, result))
+        {
+            let suggestions = suggest::suggest_modules(&info)?;
+            if !suggestions.is_empty() {
+                self.entries.push((info, suggestions));
+            }
+        }
+        self.entries
+            .sort_by_key(|(info, _)| (info.host.parse::<IpAddr>().ok(), info.port));
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[(ServiceInfo, Vec<ModuleSuggestion>)] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The services alone, e.g. to import into an msfrpcd workspace.
+    pub fn services(&self) -> Vec<ServiceInfo> {
+        self.entries.iter().map(|(info, _)| info.clone()).collect()
+    }
+
+    pub fn print_report(&self) {
+        println!(
+            "\n{}",
+            prettyprint::console("🎯 Metasploit suggestions").bold().underline().blue()
+        );
+        if self.entries.is_empty() {
+            println!(
+                "{}",
+                "  No modules suggested for the detected services.".green()
+            );
+            return;
+        }
+        for (info, suggestions) in &self.entries {
+            let ip: Option<IpAddr> = info.host.parse().ok();
+            let host = ip.map_or_else(|| info.host.clone(), rdns::label);
+            println!("  {}:{} {}", host.bold(), info.port, info.name.dimmed());
+            for suggestion in suggestions {
+                let module = if suggestion.is_exploit() {
+                    suggestion.module.red()
+                } else {
+                    suggestion.module.normal()
+                };
+                println!(
+                    "    {} {}",
+                    module,
+                    format!("({})", suggestion.reason).dimmed()
+                );
+            }
+        }
+    }
+}
+
+/// NVD feeds of the products detected in a run, one per product, cached
+/// under a directory so later runs reuse them
+#[derive(Debug, Clone)]
+pub struct CveLookup {
+    dir: PathBuf,
+    /// Lowercase product name to its feed
+    feeds: BTreeMap<String, CveFeed>,
+}
+
+impl CveLookup {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            feeds: BTreeMap::new(),
+        }
+    }
+
+    /// Where the feed for `product` is cached, e.g. "<dir>/pure_ftpd.json".
+    pub fn feed_path(&self, product: &str) -> PathBuf {
+        let name: String = product
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", name))
+    }
+
+    /// Loads the feeds of the products in `services` that have a version,
+    /// downloading the ones not cached within `CVE_FEED_MAX_AGE_DAYS`.
+    /// Blocking; returns why each feed that could not be loaded failed.
+    pub fn fetch(&mut self, services: &[ServiceInfo]) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Err(e) = std::fs::create_dir_all(&self.dir) {
+            errors.push(format!("Could not create {}: {}", self.dir.display(), e));
+            return errors;
+        }
+        for service in services {
+            let (Some(product), Some(_)) = (&service.product, &service.version) else {
+                continue;
+            };
+            let key = product.to_ascii_lowercase();
+            if self.feeds.contains_key(&key) {
+                continue;
+            }
+            let path = self.feed_path(product);
+            let feed = netutil::fetch_and_cache(
+                &vuln::nvd_url(product),
+                &path.to_string_lossy(),
+                CVE_FEED_MAX_AGE_DAYS,
+            )
+            .and_then(|_| CveFeed::load(&path));
+            match feed {
+                Ok(feed) => {
+                    self.feeds.insert(key, feed);
+                }
+                Err(e) => {
+                    errors.push(format!("No CVE feed for {}: {}", product, e));
+                    // Tried once per run, not once per host running it
+                    self.feeds.insert(key, CveFeed::default());
+                }
+            }
+        }
+        errors
+    }
+
+    /// A finding for each CVE affecting the product and version of `service`.
+    pub fn findings(&self, service: &ServiceInfo) -> Vec<Finding> {
+        let Some(feed) = service
+            .product
+            .as_ref()
+            .and_then(|product| self.feeds.get(&product.to_ascii_lowercase()))
+        else {
+            return Vec::new();
+        };
+        let software = [service.product.as_deref(), service.version.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+        feed.lookup(service)
+            .into_iter()
+            .map(|cve| {
+                let score = cve
+                    .cvss
+                    .map(|score| format!(" (CVSS {:.1})", score))
+                    .unwrap_or_default();
+                Finding::new(
+                    cve_severity(&cve),
+                    Some(service.port),
+                    &cve.id,
+                    format!("{} is affected{}: {}", software, score, cve.summary),
+                )
+                .with_code(findings::code::KNOWN_CVE)
+            })
+            .collect()
+    }
+}
+
+/// Severity of a CVE by the CVSS rating bands, or by the rating the feed
+/// gives when it has no score.
+pub fn cve_severity(cve: &CveMatch) -> Severity {
+    match cve.cvss {
+        Some(score) if score >= 9.0 => Severity::Critical,
+        Some(score) if score >= 7.0 => Severity::High,
+        Some(score) if score >= 4.0 => Severity::Medium,
+        Some(score) if score > 0.0 => Severity::Low,
+        Some(_) => Severity::Info,
+        None => match cve.severity.as_deref() {
+            Some("CRITICAL") => Severity::Critical,
+            Some("HIGH") => Severity::High,
+            Some("MEDIUM") => Severity::Medium,
+            Some("LOW") => Severity::Low,
+            _ => Severity::Info,
+        },
+    }
+}
@@ -0,0 +1,189 @@
+use crate::utils::findings::Finding;
+use crate::utils::schedule::ScanWindow;
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Which hosts a mute rule silences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MuteScope {
+    All,
+    Network { base: IpAddr, prefix: u8 },
+}
+
+impl MuteScope {
+    /// Parses "*", an address or a CIDR network.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if text == "*" || text.eq_ignore_ascii_case("all") {
+            return Ok(MuteScope::All);
+        }
+        let (addr, prefix) = match text.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (text, None),
+        };
+        let base: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("Invalid host or network \"{}\"", text))?;
+        let max = if base.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|&p| p <= max)
+                .ok_or_else(|| format!("Invalid prefix length in \"{}\"", text))?,
+            None => max,
+        };
+        Ok(MuteScope::Network { base, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let (base, prefix) = match *self {
+            MuteScope::All => return true,
+            MuteScope::Network { base, prefix } => (base, prefix),
+        };
+        match (base, ip.to_canonical()) {
+            (IpAddr::V4(base), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+                u32::from(base) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(base), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+                u128::from(base) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// One line of a mute file: "HOSTS [until TIME] [during WINDOW]"
+#[derive(Debug, Clone, PartialEq)]
+pub struct MuteRule {
+    pub scope: MuteScope,
+    /// The rule lapses at this instant
+    pub until: Option<DateTime<Utc>>,
+    /// The rule only applies while this window is open
+    pub during: Option<ScanWindow>,
+    spec: String,
+}
+
+impl MuteRule {
+    /// Parses e.g. "10.0.0.0/24 until 2026-11-01T06:00:00Z" or
+    /// "* during Sat 02:00-06:00 Europe/Berlin".
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut tokens = line.split_whitespace();
+        let scope = MuteScope::parse(tokens.next().ok_or("Empty mute rule")?)?;
+        let mut until = None;
+        let mut during = None;
+        while let Some(keyword) = tokens.next() {
+            match keyword.to_ascii_lowercase().as_str() {
+                "until" => {
+                    let time = tokens.next().ok_or("Missing time after \"until\"")?;
+                    let time = DateTime::parse_from_rfc3339(time).map_err(|_| {
+                        format!(
+                            "Invalid time \"{}\"; expected e.g. 2026-11-01T06:00:00Z",
+                            time
+                        )
+                    })?;
+                    until = Some(time.with_timezone(&Utc));
+                }
+                "during" => {
+                    // The window takes the rest of the line
+                    let window: Vec<&str> = tokens.by_ref().collect();
+                    during = Some(ScanWindow::parse(&window.join(" "))?);
+                }
+                other => {
+                    return Err(format!(
+                        "Unexpected \"{}\"; expected \"until\" or \"during\"",
+                        other
+                    ));
+                }
+            }
+        }
+        Ok(Self {
+            scope,
+            until,
+            during,
+            spec: line.trim().to_string(),
+        })
+    }
+
+    /// Whether the rule is in force at `now`.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.until.is_none_or(|until| now < until)
+            && self
+                .during
+                .as_ref()
+                .is_none_or(|window| window.is_open(now))
+    }
+
+    /// Whether the rule silences `alert` at `now`. Alerts about no particular
+    /// host are only silenced by "*" rules.
+    pub fn mutes(&self, alert: &Finding, now: DateTime<Utc>) -> bool {
+        if !self.is_active(now) {
+            return false;
+        }
+        match self.scope {
+            MuteScope::All => true,
+            MuteScope::Network { .. } => {
+                !alert.hosts.is_empty() && alert.hosts.iter().all(|&ip| self.scope.contains(ip))
+            }
+        }
+    }
+}
+
+impl fmt::Display for MuteRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.spec)
+    }
+}
+
+/// Rules silencing alert notifications; muted alerts are still recorded
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MuteList {
+    rules: Vec<MuteRule>,
+}
+
+impl MuteList {
+    pub fn new(rules: Vec<MuteRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Parses one rule per line; blank lines and "#" comments are skipped.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let rules = text
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let line = line.split('#').next().unwrap_or("").trim();
+                (!line.is_empty()).then(|| {
+                    MuteRule::parse(line).map_err(|e| format!("line {}: {}", index + 1, e))
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Reads `path`; a missing file means no rules.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("Invalid mute rule in {}, {}", path.display(), e))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn rules(&self) -> &[MuteRule] {
+        &self.rules
+    }
+
+    pub fn is_muted(&self, alert: &Finding, now: DateTime<Utc>) -> bool {
+        self.rules.iter().any(|rule| rule.mutes(alert, now))
+    }
+}
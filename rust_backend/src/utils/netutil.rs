@@ -1,7 +1,6 @@
 use std::fs;
-use std::io::{self, Write};
 use std::path::Path;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 
 /// Checks if the system has internet access by connecting to a well-known site.
 pub fn has_internet() -> Result<bool, String> {
@@ -26,8 +25,8 @@ pub fn has_internet() -> Result<bool, String> {
 /// If the cache is fresh (default 7 days), uses the cached file.
 pub fn fetch_and_cache(url: &str, cache_path: &str, max_age_days: u64) -> Result<(), String> {
     let path = Path::new(cache_path);
-    let cache_fresh = path.exists() && path.metadata().and_then(|m| m.modified()).map_or(false, |mtime| {
-        mtime.elapsed().map_or(false, |elapsed| elapsed.as_secs() < max_age_days * 86400)
+    let cache_fresh = path.exists() && path.metadata().and_then(|m| m.modified()).is_ok_and(|mtime| {
+        mtime.elapsed().is_ok_and(|elapsed| elapsed.as_secs() < max_age_days * 86400)
     });
 
     if cache_fresh {
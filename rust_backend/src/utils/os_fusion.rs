@@ -0,0 +1,97 @@
+use crate::utils::evidence::{Attribute, Evidence, Source};
+use std::fmt;
+
+/// How much one observation of each kind counts towards an OS guess. Banners
+/// usually name the distribution outright; a TCP/IP stack signature or a TTL
+/// only narrows the family and a NIC vendor says little about what runs on
+/// the box.
+pub fn weight(source: &Source) -> f64 {
+    match source {
+        Source::Banner(_) => 0.6,
+        Source::Probe(_) => 0.5,
+        Source::Stack(_) => 0.4,
+        Source::Ttl(_) => 0.3,
+        Source::Oui(_) => 0.2,
+    }
+}
+
+/// One candidate operating system and how sure the evidence makes us
+#[derive(Debug, Clone, PartialEq)]
+pub struct OsGuess {
+    pub os: String,
+    /// Share of the OS evidence weight supporting this guess, 0 to 1
+    pub confidence: f64,
+}
+
+impl fmt::Display for OsGuess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:.0}%)", self.os, self.confidence * 100.0)
+    }
+}
+
+/// Whether evidence for `claim` supports `guess`: the same OS, or the family a
+/// more specific guess belongs to ("Linux" supports "Linux (Ubuntu)").
+fn supports(claim: &str, guess: &str) -> bool {
+    guess == claim || guess.starts_with(&format!("{} ", claim))
+}
+
+/// Weighs the OS evidence and ranks the most specific OS names it points
+/// to, most likely first.
+pub fn fuse(evidence: &[Evidence]) -> Vec<OsGuess> {
+    let os_evidence: Vec<&Evidence> = evidence
+        .iter()
+        .filter(|evidence| evidence.attribute == Attribute::Os)
+        .collect();
+    let total: f64 = os_evidence.iter().map(|e| weight(&e.source)).sum();
+    if total == 0.0 {
+        return Vec::new();
+    }
+
+    let mut candidates: Vec<&str> = Vec::new();
+    for evidence in &os_evidence {
+        if !candidates.contains(&evidence.value.as_str()) {
+            candidates.push(&evidence.value);
+        }
+    }
+    // A family is only listed when nothing names a member of it
+    let specific: Vec<&str> = candidates
+        .iter()
+        .copied()
+        .filter(|&os| {
+            !candidates
+                .iter()
+                .any(|&other| other != os && supports(os, other))
+        })
+        .collect();
+
+    let mut guesses: Vec<OsGuess> = specific
+        .into_iter()
+        .map(|os| {
+            let support: f64 = os_evidence
+                .iter()
+                .filter(|evidence| supports(&evidence.value, os))
+                .map(|evidence| weight(&evidence.source))
+                .sum();
+            OsGuess {
+                os: os.to_string(),
+                confidence: (support / total).min(1.0),
+            }
+        })
+        .collect();
+    // Equally likely guesses by name, whatever order the evidence came in
+    guesses.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then_with(|| a.os.cmp(&b.os))
+    });
+    guesses
+}
+
+/// The ranked guesses as one line, e.g. "Linux (Ubuntu) (64%), Windows (36%)".
+pub fn format_guesses(guesses: &[OsGuess]) -> String {
+    guesses
+        .iter()
+        .map(OsGuess::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,222 @@
+use crate::utils::netutil;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The IEEE MA-L registry, downloaded by `update_cache`
+pub const IEEE_OUI_URL: &str = "https://standards-oui.ieee.org/oui/oui.csv";
+/// Where the downloaded registry is kept; it takes precedence over the embedded table
+pub const CACHE_PATH: &str = ".netscan/oui.csv";
+const CACHE_MAX_AGE_DAYS: u64 = 30;
+
+/// Common vendors, so lookups work without a download
+const EMBEDDED: &[([u8; 3], &str)] = &[
+    ([0x00, 0x00, 0x0c], "Cisco Systems, Inc"),
+    ([0x00, 0x07, 0x0e], "Cisco Systems, Inc"),
+    ([0x00, 0x1a, 0x2f], "Cisco Systems, Inc"),
+    ([0x00, 0x23, 0x04], "Cisco Systems, Inc"),
+    ([0x00, 0x40, 0x96], "Cisco Systems, Inc"),
+    ([0x00, 0x60, 0x2f], "Cisco Systems, Inc"),
+    ([0x58, 0xac, 0x78], "Cisco Systems, Inc"),
+    ([0x00, 0x18, 0x0a], "Cisco Meraki"),
+    ([0xe0, 0x55, 0x3d], "Cisco Meraki"),
+    ([0x00, 0x05, 0x85], "Juniper Networks"),
+    ([0x00, 0x0b, 0x86], "Aruba Networks"),
+    ([0x00, 0x1a, 0x1e], "Aruba Networks"),
+    ([0x00, 0x09, 0x0f], "Fortinet, Inc."),
+    ([0x00, 0x1b, 0x17], "Palo Alto Networks"),
+    ([0x00, 0x0c, 0x42], "Routerboard.com"),
+    ([0x4c, 0x5e, 0x0c], "Routerboard.com"),
+    ([0x6c, 0x3b, 0x6b], "Routerboard.com"),
+    ([0x24, 0xa4, 0x3c], "Ubiquiti Inc"),
+    ([0x44, 0xd9, 0xe7], "Ubiquiti Inc"),
+    ([0x78, 0x8a, 0x20], "Ubiquiti Inc"),
+    ([0xfc, 0xec, 0xda], "Ubiquiti Inc"),
+    ([0x00, 0xe0, 0xfc], "Huawei Technologies Co.,Ltd"),
+    ([0x00, 0x25, 0x9e], "Huawei Technologies Co.,Ltd"),
+    ([0x00, 0x09, 0x5b], "Netgear"),
+    ([0x00, 0x14, 0x6c], "Netgear"),
+    ([0x20, 0x4e, 0x7f], "Netgear"),
+    ([0x50, 0xc7, 0xbf], "TP-Link Technologies Co.,Ltd."),
+    ([0x14, 0xcc, 0x20], "TP-Link Technologies Co.,Ltd."),
+    ([0x00, 0x05, 0x5d], "D-Link Corporation"),
+    ([0x00, 0x26, 0x5a], "D-Link Corporation"),
+    ([0x00, 0x0c, 0x6e], "ASUSTek Computer Inc."),
+    ([0x00, 0x1d, 0x60], "ASUSTek Computer Inc."),
+    ([0x00, 0x0d, 0xb9], "PC Engines GmbH"),
+    ([0x00, 0x07, 0xe9], "Intel Corporation"),
+    ([0x00, 0xa0, 0xc9], "Intel Corporation"),
+    ([0x00, 0x1b, 0x21], "Intel Corporate"),
+    ([0x3c, 0xfd, 0xfe], "Intel Corporate"),
+    ([0x00, 0xe0, 0x4c], "Realtek Semiconductor Corp."),
+    ([0x00, 0x10, 0x18], "Broadcom"),
+    ([0x00, 0x04, 0x4b], "NVIDIA"),
+    ([0x00, 0x14, 0x22], "Dell Inc."),
+    ([0x00, 0x1a, 0xa0], "Dell Inc."),
+    ([0xf8, 0xbc, 0x12], "Dell Inc."),
+    ([0x3c, 0xd9, 0x2b], "Hewlett Packard"),
+    ([0x00, 0x25, 0x90], "Super Micro Computer, Inc."),
+    ([0x00, 0x30, 0x48], "Super Micro Computer, Inc."),
+    ([0xac, 0x1f, 0x6b], "Super Micro Computer, Inc."),
+    ([0x00, 0x03, 0x93], "Apple, Inc."),
+    ([0x00, 0x0a, 0x95], "Apple, Inc."),
+    ([0x00, 0x25, 0x00], "Apple, Inc."),
+    ([0x3c, 0x07, 0x54], "Apple, Inc."),
+    ([0xb8, 0xe8, 0x56], "Apple, Inc."),
+    ([0x00, 0x00, 0xf0], "Samsung Electronics Co.,Ltd"),
+    ([0x00, 0x12, 0xfb], "Samsung Electronics Co.,Ltd"),
+    ([0x00, 0x1a, 0x11], "Google, Inc."),
+    ([0xf4, 0xf5, 0xd8], "Google, Inc."),
+    ([0x18, 0xb4, 0x30], "Nest Labs Inc."),
+    ([0x44, 0x65, 0x0d], "Amazon Technologies Inc."),
+    ([0x00, 0x0e, 0x58], "Sonos, Inc."),
+    ([0x00, 0x17, 0x88], "Philips Lighting BV"),
+    ([0x00, 0x0d, 0x3a], "Microsoft Corporation"),
+    ([0x00, 0x15, 0x5d], "Microsoft Corporation"),
+    ([0x00, 0x50, 0x56], "VMware, Inc."),
+    ([0x00, 0x0c, 0x29], "VMware, Inc."),
+    ([0x00, 0x05, 0x69], "VMware, Inc."),
+    ([0x00, 0x1c, 0x14], "VMware, Inc."),
+    ([0x08, 0x00, 0x27], "PCS Systemtechnik GmbH"),
+    ([0x00, 0x16, 0x3e], "Xensource, Inc."),
+    ([0x52, 0x54, 0x00], "QEMU virtual NIC"),
+    ([0xb8, 0x27, 0xeb], "Raspberry Pi Foundation"),
+    ([0xdc, 0xa6, 0x32], "Raspberry Pi Trading Ltd"),
+    ([0xe4, 0x5f, 0x01], "Raspberry Pi Trading Ltd"),
+    ([0x28, 0xcd, 0xc1], "Raspberry Pi Trading Ltd"),
+    ([0xd8, 0x3a, 0xdd], "Raspberry Pi Trading Ltd"),
+    ([0x00, 0x11, 0x32], "Synology Incorporated"),
+    ([0x24, 0x5e, 0xbe], "QNAP Systems, Inc."),
+    ([0x00, 0x90, 0xa9], "Western Digital"),
+    ([0x00, 0x40, 0x8c], "Axis Communications AB"),
+    ([0xac, 0xcc, 0x8e], "Axis Communications AB"),
+    (
+        [0x44, 0x19, 0xb6],
+        "Hangzhou Hikvision Digital Technology Co.,Ltd.",
+    ),
+    (
+        [0xc0, 0x56, 0xe3],
+        "Hangzhou Hikvision Digital Technology Co.,Ltd.",
+    ),
+    ([0x00, 0x04, 0xf2], "Polycom"),
+    ([0x00, 0x04, 0x13], "snom technology GmbH"),
+    ([0x00, 0x0b, 0x82], "Grandstream Networks, Inc."),
+    (
+        [0x00, 0x15, 0x65],
+        "Xiamen Yealink Network Technology Co.,Ltd",
+    ),
+    ([0x00, 0x80, 0x77], "Brother Industries, Ltd."),
+    ([0x00, 0x00, 0x48], "Seiko Epson Corporation"),
+    ([0x00, 0x00, 0xaa], "Xerox Corporation"),
+    ([0x00, 0x00, 0x85], "Canon Inc."),
+    ([0x00, 0xc0, 0xb7], "American Power Conversion Corp"),
+    ([0x00, 0x20, 0x85], "Eaton Corporation"),
+    ([0x00, 0x0e, 0x8c], "Siemens AG"),
+    ([0x00, 0x1b, 0x1b], "Siemens AG"),
+    ([0x00, 0x30, 0xde], "WAGO Kontakttechnik GmbH"),
+    ([0x00, 0xa0, 0x45], "Phoenix Contact GmbH & Co. KG"),
+    ([0x00, 0x00, 0xbc], "Rockwell Automation"),
+    ([0x00, 0x1d, 0x9c], "Rockwell Automation"),
+];
+
+/// OUI (first three octets of a MAC) to vendor name
+#[derive(Debug, Clone, Default)]
+pub struct OuiDatabase {
+    vendors: HashMap<[u8; 3], String>,
+}
+
+impl OuiDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The table compiled into netscan.
+    pub fn embedded() -> Self {
+        let mut db = Self::new();
+        for (oui, vendor) in EMBEDDED {
+            db.vendors.insert(*oui, vendor.to_string());
+        }
+        db
+    }
+
+    /// Parses the IEEE registry CSV (Registry,Assignment,Organization Name,...).
+    pub fn parse_ieee_csv(text: &str) -> Result<Self, String> {
+        let mut reader = csv::Reader::from_reader(text.as_bytes());
+        let mut db = Self::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| format!("Invalid OUI registry: {}", e))?;
+            let (Some(assignment), Some(name)) = (record.get(1), record.get(2)) else {
+                continue;
+            };
+            if let Some(oui) = parse_oui(assignment) {
+                db.vendors.insert(oui, name.trim().to_string());
+            }
+        }
+        Ok(db)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        Self::parse_ieee_csv(&text)
+    }
+
+    /// Adds the entries of `other`, replacing vendors already known.
+    pub fn extend(&mut self, other: OuiDatabase) {
+        self.vendors.extend(other.vendors);
+    }
+
+    pub fn len(&self) -> usize {
+        self.vendors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vendors.is_empty()
+    }
+
+    /// Vendor of the NIC with address `mac` ("b8:27:eb:..", "B8-27-EB-.."
+    /// or "b827.eb..").
+    pub fn lookup(&self, mac: &str) -> Option<&str> {
+        self.vendors.get(&parse_oui(mac)?).map(String::as_str)
+    }
+}
+
+/// The first three octets of a MAC address or bare OUI in any common notation.
+fn parse_oui(text: &str) -> Option<[u8; 3]> {
+    let hex: String = text
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .take(6)
+        .collect();
+    if hex.len() != 6 {
+        return None;
+    }
+    let octet = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([octet(0)?, octet(2)?, octet(4)?])
+}
+
+/// The embedded table, overlaid with the downloaded registry when one is cached.
+static DATABASE: Lazy<OuiDatabase> = Lazy::new(|| {
+    let mut db = OuiDatabase::embedded();
+    if let Ok(cached) = OuiDatabase::load(Path::new(CACHE_PATH)) {
+        db.extend(cached);
+    }
+    db
+});
+
+/// Vendor of the NIC with address `mac`, if its OUI is known.
+pub fn lookup(mac: &str) -> Option<String> {
+    DATABASE.lookup(mac).map(String::from)
+}
+
+/// Downloads the IEEE registry into `CACHE_PATH` unless the cached copy is
+/// recent. Blocking; lookups use the registry if this runs before the first one.
+pub fn update_cache() -> Result<usize, String> {
+    if let Some(dir) = Path::new(CACHE_PATH).parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    }
+    netutil::fetch_and_cache(IEEE_OUI_URL, CACHE_PATH, CACHE_MAX_AGE_DAYS)?;
+    OuiDatabase::load(Path::new(CACHE_PATH)).map(|db| db.len())
+}
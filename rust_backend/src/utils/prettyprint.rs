@@ -1,48 +1,312 @@
 use colored::*;
+use once_cell::sync::OnceCell;
+use std::borrow::Cow;
 use crate::scanners::service_detection;
+use crate::detect_remote_admin::RemoteAdminDetection;
+use crate::scanners::service_detection::ServiceDetectionResult;
+use crate::utils::evidence::Source;
+use crate::utils::{rdns, redact, roles};
+use std::net::IpAddr;
+use crate::utils::timing::format_duration;
+
+/// A column of the service table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Port,
+    /// What was identified, e.g. "SSH"; includes the version unless a version column is shown
+    Service,
+    /// Product and version read from the banner, else what the identification
+    /// has in parentheses, e.g. "OpenSSH 8.9p1"
+    Version,
+    Status,
+    /// Time all detectors spent on the port
+    Latency,
+    /// Text the service sent, cut to the layout's banner width
+    Banner,
+    Error,
+}
+
+impl Column {
+    fn title(&self) -> &'static str {
+        match self {
+            Column::Port => "Port",
+            Column::Service => "Service",
+            Column::Version => "Version",
+            Column::Status => "Status",
+            Column::Latency => "Latency",
+            Column::Banner => "Banner",
+            Column::Error => "Error",
+        }
+    }
+
+    fn width(&self, layout: &TableLayout) -> usize {
+        match self {
+            Column::Port => 8,
+            Column::Service | Column::Version => 20,
+            Column::Status | Column::Latency => 10,
+            Column::Banner => layout.banner_width + 1,
+            Column::Error => 0,
+        }
+    }
+
+    fn cell(&self, res: &ServiceDetectionResult, layout: &TableLayout) -> ColoredString {
+        let split = layout.columns.contains(&Column::Version);
+        match self {
+            Column::Port => res.port.to_string().bold(),
+            Column::Service => match &res.service {
+                Some(s) if s == "Unknown Service" => s.red().bold(),
+                Some(s) => {
+                    let name = if split { split_version(s).0 } else { s.as_str() };
+                    if s == "HTTP" || s == "HTTPS" {
+                        name.green().bold()
+                    } else {
+                        name.yellow().bold()
+                    }
+                }
+                None => "-".normal(),
+            },
+            Column::Version => {
+                res.software()
+                    .or_else(|| res.service.as_deref().and_then(|s| split_version(s).1).map(str::to_string))
+                    .unwrap_or_else(|| "-".to_string())
+                    .normal()
+            }
+            Column::Status => {
+                if res.error.is_none() {
+                    "OK".green()
+                } else {
+                    "FAIL".red()
+                }
+            }
+            Column::Latency => {
+                if res.attempts.is_empty() {
+                    "-".normal()
+                } else {
+                    format_duration(res.elapsed()).normal()
+                }
+            }
+            Column::Banner => banner_of(res)
+                .map_or_else(|| "-".to_string(), |banner| truncate(banner, layout.banner_width))
+                .dimmed(),
+            Column::Error => match &res.error {
+                Some(e) if e != "-" => e.bright_red(),
+                _ => "-".normal(),
+            },
+        }
+    }
+}
+
+/// "SSH (OpenSSH_8.9)" -> ("SSH", Some("OpenSSH_8.9"))
+fn split_version(service: &str) -> (&str, Option<&str>) {
+    match service.strip_suffix(')').and_then(|s| s.rsplit_once(" (")) {
+        Some((name, version)) => (name, Some(version)),
+        None => (service, None),
+    }
+}
+
+fn banner_of(res: &ServiceDetectionResult) -> Option<&str> {
+    res.evidence
+        .iter()
+        .find_map(|evidence| match &evidence.source {
+            Source::Banner(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .or_else(|| res.service.as_deref()?.strip_prefix("Banner: "))
+}
+
+/// First line of `text`, cut to `width` characters with "..." marking the cut
+fn truncate(text: &str, width: usize) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    if line.chars().count() <= width {
+        return line.to_string();
+    }
+    let kept: String = line.chars().take(width.saturating_sub(3)).collect();
+    format!("{}...", kept)
+}
+
+/// Order of the rows of the service table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Port,
+    /// By service name; unidentified ports last
+    Service,
+    /// Slowest first
+    Latency,
+}
+
+/// Rows of the service table to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowFilter {
+    /// Ports that answered, whether or not the service was identified
+    Open,
+    /// Ports whose service was identified
+    Detected,
+}
+
+impl RowFilter {
+    pub fn keeps(&self, res: &ServiceDetectionResult) -> bool {
+        match self {
+            RowFilter::Open => res.is_open(),
+            RowFilter::Detected => res.is_detected(),
+        }
+    }
+}
+
+/// Which columns the service table shows, in order, how wide banners may
+/// get, and which rows are shown in what order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableLayout {
+    pub columns: Vec<Column>,
+    pub banner_width: usize,
+    /// `None` keeps the order the ports were scanned in
+    pub sort_by: Option<SortKey>,
+    pub only: Option<RowFilter>,
+}
+
+/// Banner width when `--banner-width` is not given
+pub const DEFAULT_BANNER_WIDTH: usize = 40;
+
+impl Default for TableLayout {
+    fn default() -> Self {
+        Self {
+            columns: vec![Column::Port, Column::Service, Column::Status, Column::Error],
+            banner_width: DEFAULT_BANNER_WIDTH,
+            sort_by: None,
+            only: None,
+        }
+    }
+}
+
+impl TableLayout {
+    /// The rows to print: filtered, then sorted.
+    pub fn arrange<'a>(&self, results: &'a [ServiceDetectionResult]) -> Vec<&'a ServiceDetectionResult> {
+        let mut rows: Vec<&ServiceDetectionResult> = results
+            .iter()
+            .filter(|res| self.only.is_none_or(|only| only.keeps(res)))
+            .collect();
+        match self.sort_by {
+            Some(SortKey::Port) => rows.sort_by_key(|res| res.port),
+            // Identified services first, alphabetically
+            Some(SortKey::Service) => rows.sort_by_cached_key(|res| {
+                let name = res.service.as_deref().unwrap_or_default().to_lowercase();
+                (!res.is_detected(), name, res.port)
+            }),
+            Some(SortKey::Latency) => {
+                rows.sort_by_key(|res| (std::cmp::Reverse(res.elapsed()), res.port))
+            }
+            None => {}
+        }
+        rows
+    }
+
+    pub fn header(&self) -> String {
+        self.row(self.columns.iter().map(|c| c.title().bold().cyan()).collect())
+    }
+
+    /// The table line for one port.
+    pub fn format_row(&self, res: &ServiceDetectionResult) -> String {
+        self.row(self.columns.iter().map(|c| c.cell(res, self)).collect())
+    }
+
+    /// Pads every cell but the last to its column's width.
+    fn row(&self, cells: Vec<ColoredString>) -> String {
+        let last = cells.len().saturating_sub(1);
+        cells
+            .into_iter()
+            .zip(&self.columns)
+            .enumerate()
+            .map(|(i, (cell, column))| {
+                let cell = console(&cell.to_string()).into_owned();
+                if i == last {
+                    cell
+                } else {
+                    let padding = column.width(self).saturating_sub(display_width(&cell));
+                    format!("{}{}", cell, " ".repeat(padding))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
 
 pub fn pretty_print_service_results(
     title: &str,
     results: &[service_detection::ServiceDetectionResult],
 ) {
-    println!("\n{}", title.bold().underline().blue());
-    println!(
-        "{:<8} {:<20} {:<10} {}",
-        "Port".bold().cyan(),
-        "Service".bold().cyan(),
-        "Status".bold().cyan(),
-        "Error".bold().cyan()
-    );
+    print_service_table(title, results, &TableLayout::default());
+}
+
+/// Prints service detection results with the columns of `layout`.
+pub fn print_service_table(title: &str, results: &[ServiceDetectionResult], layout: &TableLayout) {
+    println!("\n{}", console(title).bold().underline().blue());
+    println!("{}", layout.header());
     println!("{}", "-".repeat(70).dimmed());
 
-    for res in results {
-        let service_str = match &res.service {
-            Some(s) if s == "Unknown Service" => s.red().bold(),
-            Some(s) if s == "HTTP" || s == "HTTPS" => s.green().bold(),
-            Some(s) => s.yellow().bold(),
-            None => "-".normal(),
-        };
-        let status_str = if res.error.is_none() {
-            "OK".green()
-        } else {
-            "FAIL".red()
-        };
-        let error_str = match &res.error {
-            Some(e) if e != "-" => e.bright_red(),
-            _ => "-".normal(),
-        };
-        println!(
-            "{:<8} {:<20} {:<10} {}",
-            res.port.to_string().bold(),
-            service_str,
-            status_str,
-            error_str
-        );
+    let rows = layout.arrange(results);
+    for res in &rows {
+        println!("{}", layout.format_row(res));
+        if let Some(cert) = &res.certificate {
+            let sans = if cert.sans.is_empty() {
+                "-".to_string()
+            } else {
+                cert.sans.join(", ")
+            };
+            let line = format!(
+                "{:<8} CN {} | SAN {} | issuer {} | expires {}",
+                "",
+                cert.subject,
+                sans,
+                cert.issuer,
+                cert.not_after.format("%Y-%m-%d")
+            );
+            println!("{}", console(&line));
+        }
+        if let Some(alpn) = &res.alpn {
+            println!("{:<8} ALPN {}", "", alpn);
+        }
+        for evidence in &res.evidence {
+            let line = format!("evidence: {}", evidence.source);
+            println!("{:<8} {}", "", console(&line).dimmed());
+        }
     }
     println!("{}", "-".repeat(70).dimmed());
+    if rows.len() < results.len() {
+        println!("{}", format!("{} of {} port(s) shown", rows.len(), results.len()).dimmed());
+    }
     println!();
 }
 
+/// Prints a "likely roles" line for a host, inferred from its open ports
+pub fn print_likely_roles(ip: IpAddr, open_ports: &[u16]) {
+    let inferred = roles::infer_roles(open_ports);
+    if inferred.is_empty() {
+        return;
+    }
+    println!(
+        "  {} {}: {}",
+        rdns::label(ip).bold(),
+        "likely roles".bold().blue(),
+        roles::format_roles(&inferred).green()
+    );
+}
+
+/// Prints the remote-access tools found listening on a host
+pub fn print_remote_admin_tools(ip: IpAddr, tools: &[RemoteAdminDetection]) {
+    if tools.is_empty() {
+        return;
+    }
+    let names: Vec<String> = tools
+        .iter()
+        .map(|tool| format!("{}/tcp {}", tool.port, redact::text(&tool.summary())))
+        .collect();
+    println!(
+        "  {} {}: {}",
+        rdns::label(ip).bold(),
+        "remote access".bold().blue(),
+        names.join(", ").yellow()
+    );
+}
+
 
 
 /// Converts a sorted Vec<u16> into a compact range string, e.g. "1-5,7,9-11"
@@ -74,4 +338,130 @@ pub fn format_port_ranges(ports: &[u16]) -> String {
         ranges.push(format!("{}-{}", start, end));
     }
     ranges.join(",")
-}
\ No newline at end of file
+}
+/// One-column ASCII stand-ins for glyphs printed mid-line, so tables keep their alignment
+fn ascii_glyph(c: char) -> Option<char> {
+    match c {
+        '—' | '–' | '─' => Some('-'),
+        '†' => Some('+'),
+        '✗' | '❌' => Some('x'),
+        '✓' | '✔' | '✅' => Some('+'),
+        '⚠' => Some('!'),
+        '•' | '·' => Some('*'),
+        '§' => Some('S'),
+        '“' | '”' => Some('"'),
+        '‘' | '’' => Some('\''),
+        _ => None,
+    }
+}
+
+/// Emoji and other decorations with no ASCII meaning; they are dropped with the padding after them
+fn is_decoration(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF | 0x2190..=0x21FF | 0x2300..=0x23FF | 0x25A0..=0x27BF | 0x2B00..=0x2BFF)
+}
+
+/// Rewrites console output as ASCII for `--ascii`; bytes may arrive split
+/// anywhere, e.g. in the middle of a multi-byte character.
+#[derive(Debug, Default)]
+pub struct AsciiFilter {
+    pending: Vec<u8>,
+    after_decoration: bool,
+}
+
+impl AsciiFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            // An incomplete character at the end waits for the next chunk
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        let chunk: Vec<u8> = self.pending.drain(..valid).collect();
+        let mut out = String::with_capacity(chunk.len());
+        for c in String::from_utf8_lossy(&chunk).chars() {
+            if c == ' ' && self.after_decoration {
+                continue;
+            }
+            // Emoji presentation selector and joiner belong to the glyph before them
+            if c == '\u{FE0F}' || c == '\u{200D}' {
+                continue;
+            }
+            self.after_decoration = false;
+            if c.is_ascii() {
+                out.push(c);
+            } else if let Some(ascii) = ascii_glyph(c) {
+                out.push(ascii);
+            } else if is_decoration(c) {
+                self.after_decoration = true;
+            } else {
+                out.push('?');
+            }
+        }
+        out
+    }
+}
+
+/// Text as `--ascii` prints it.
+pub fn to_ascii(text: &str) -> String {
+    AsciiFilter::new().push(text.as_bytes())
+}
+
+static ASCII_CONSOLE: OnceCell<bool> = OnceCell::new();
+
+/// Makes `console` rewrite everything netscan prints as ASCII (`--ascii`).
+pub fn install_ascii_console() {
+    let _ = ASCII_CONSOLE.set(true);
+}
+
+/// `text` the way the console gets it: as is, or as ASCII under `--ascii`.
+pub fn console(text: &str) -> Cow<'_, str> {
+    if ASCII_CONSOLE.get().copied().unwrap_or(false) {
+        Cow::Owned(to_ascii(text))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Terminal columns `c` takes: none for joiners, selectors and combining
+/// marks, two for East Asian wide characters and emoji.
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Terminal columns `text` takes, not counting ANSI color codes.
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip to the letter that ends the escape sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        width += char_width(c);
+    }
+    width
+}
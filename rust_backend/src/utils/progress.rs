@@ -0,0 +1,205 @@
+use crate::utils::ratelimit::{self, format_bandwidth};
+use crate::utils::timing::format_duration;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::Write;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+const BAR_WIDTH: usize = 20;
+
+/// The scanner an event comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    PingSweep,
+    TcpScan,
+    UdpScan,
+    ServiceScan,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::PingSweep => "Ping sweep",
+            Phase::TcpScan => "TCP scan",
+            Phase::UdpScan => "UDP scan",
+            Phase::ServiceScan => "Service detection",
+        })
+    }
+}
+
+/// What a scanner reports while it runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// `total` more probes are about to run
+    Started { phase: Phase, total: usize },
+    /// One probe finished; `port` is `None` for host probes (ping)
+    Probed {
+        phase: Phase,
+        ip: IpAddr,
+        port: Option<u16>,
+    },
+    /// Every probe announced by the matching `Started` has finished
+    Finished { phase: Phase },
+}
+
+/// Where scanners send their progress (`ScanConfig::progress`). Cheap to
+/// clone; the callback runs on the probing task, so it should not block.
+#[derive(Clone)]
+pub struct ScanProgress {
+    callback: Arc<dyn Fn(ProgressEvent) + Send + Sync>,
+}
+
+impl ScanProgress {
+    pub fn new(callback: impl Fn(ProgressEvent) + Send + Sync + 'static) -> Self {
+        Self {
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// A handle whose events arrive on the returned receiver.
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<ProgressEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let progress = Self::new(move |event| {
+            let _ = tx.send(event); // the receiver may have stopped listening
+        });
+        (progress, rx)
+    }
+
+    pub fn report(&self, event: ProgressEvent) {
+        (self.callback)(event)
+    }
+}
+
+impl fmt::Debug for ScanProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ScanProgress")
+    }
+}
+
+/// Two handles are equal when they report to the same callback.
+impl PartialEq for ScanProgress {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.callback, &other.callback)
+    }
+}
+
+impl Eq for ScanProgress {}
+
+/// Running totals built from progress events, e.g. for a status line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgressState {
+    pub total: usize,
+    pub done: usize,
+    /// Hosts probed at least once
+    pub hosts: BTreeSet<IpAddr>,
+    /// The most recent probe
+    pub last: Option<(IpAddr, Option<u16>)>,
+}
+
+impl ProgressState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, event: &ProgressEvent) {
+        match *event {
+            ProgressEvent::Started { total, .. } => self.total += total,
+            ProgressEvent::Probed { ip, port, .. } => {
+                self.done += 1;
+                self.hosts.insert(ip);
+                self.last = Some((ip, port));
+            }
+            ProgressEvent::Finished { .. } => {}
+        }
+    }
+
+    /// Share of the announced probes that finished, 0 to 100.
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.done.min(self.total) as f64 / self.total as f64) * 100.0
+    }
+
+    /// Time left if the remaining probes go as fast as the first ones did
+    /// in `elapsed`; `None` before any probe finished.
+    pub fn eta(&self, elapsed: Duration) -> Option<Duration> {
+        if self.done == 0 {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.done) as f64;
+        Some(elapsed.mul_f64(remaining / self.done as f64))
+    }
+
+    /// `[#####---------------]`, filled by the share of finished probes.
+    pub fn bar(&self) -> String {
+        let filled = (self.percent() / 100.0 * BAR_WIDTH as f64).round() as usize;
+        format!("[{}{}]", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled))
+    }
+}
+
+impl fmt::Display for ProgressState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}/{} probes ({:.0}%), {} host{}",
+            self.bar(),
+            self.done,
+            self.total,
+            self.percent(),
+            self.hosts.len(),
+            if self.hosts.len() == 1 { "" } else { "s" }
+        )?;
+        match self.last {
+            Some((ip, Some(port))) => write!(f, ", last {}", std::net::SocketAddr::new(ip, port)),
+            Some((ip, None)) => write!(f, ", last {}", ip),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Prints a live progress and throughput line to stderr every second until
+/// the handle is aborted or every copy of the returned `ScanProgress`, which
+/// goes to the scanner, is dropped.
+pub fn spawn_reporter(label: &'static str) -> (ScanProgress, tokio::task::JoinHandle<()>) {
+    let (progress, mut events) = ScanProgress::channel();
+    let handle = tokio::spawn(async move {
+        let limiter = ratelimit::global();
+        let mut previous = limiter.snapshot();
+        let mut state = ProgressState::new();
+        let started = Instant::now();
+        let mut tick = tokio::time::interval(REPORT_INTERVAL);
+        tick.tick().await; // the first tick is immediate
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Some(event) => state.update(&event),
+                    // The scan is over, or was given up
+                    None => break,
+                },
+                _ = tick.tick() => {
+                    let current = limiter.snapshot();
+                    let window = current.since(&previous);
+                    let eta = state
+                        .eta(started.elapsed())
+                        .map(|eta| format!(", ETA {}", format_duration(eta)))
+                        .unwrap_or_default();
+                    eprint!(
+                        "\r{label}: {}, {:.0} pkt/s, {}{}    ",
+                        state,
+                        window.packets_per_sec(),
+                        format_bandwidth(window.bits_per_sec() as u64),
+                        eta
+                    );
+                    let _ = std::io::stderr().flush();
+                    previous = current;
+                }
+            }
+        }
+    });
+    (progress, handle)
+}
@@ -0,0 +1,320 @@
+use crate::report::ScanReport;
+use crate::scanners::arpsweep;
+use crate::scanners::pingsweep::{self, LiveHost};
+use crate::scanners::service_detection;
+use crate::scanners::tcpscan::{self, TcpScanResult};
+use crate::scanners::tls_audit::{self, TlsReport};
+use crate::scanners::udpscan::{self, UdpScanResult};
+use crate::utils::scan_config::ScanConfig;
+use crate::utils::{fingerprinting, ports_presets, prettyprint, rdns, routing};
+use colored::*;
+use futures::stream::{self, StreamExt};
+use std::future::Future;
+use std::net::IpAddr;
+use std::ops::Range;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// TCP ports scanned in full by default; the common ports above it are added
+pub const WELL_KNOWN_TCP: Range<u16> = 1..1025;
+/// Most ports scanned as one batch, so a budget cut loses at most this many
+const CHUNK: usize = 64;
+/// TCP batches scanned at once; the earliest finish first
+const BATCHES_IN_FLIGHT: usize = 4;
+/// UDP services worth a probe on almost any host
+pub const COMMON_UDP_PORTS: &[u16] = &[53, 67, 69, 123, 137, 161, 500, 514, 1900, 5353];
+/// Ports graded for TLS even when detection did not see a certificate
+const TLS_PORTS: &[u16] = &[443, 465, 636, 993, 995, 8443];
+/// Cap on discovery, so a silent host leaves time for the scans
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Everything `netscan host` found on one address
+#[derive(Debug, Clone)]
+pub struct QuickLook {
+    pub ip: IpAddr,
+    pub report: ScanReport,
+    /// Graded TLS endpoints
+    pub tls: Vec<TlsReport>,
+    /// Phases the budget cut short, e.g. "UDP scan"
+    pub cut_short: Vec<&'static str>,
+    pub elapsed: Duration,
+}
+
+/// TCP ports a quick look scans unless given others: the well-known ports
+/// plus every common port above them, ascending.
+pub fn default_tcp_ports() -> Vec<u16> {
+    let mut ports: Vec<u16> = WELL_KNOWN_TCP.collect();
+    ports.extend(
+        ports_presets::TOP_TCP_PORTS
+            .iter()
+            .filter(|port| !WELL_KNOWN_TCP.contains(port)),
+    );
+    ports.sort_unstable();
+    ports
+}
+
+/// `ports` ascending, in batches of at most `CHUNK` ports.
+pub fn tcp_batches(ports: &[u16]) -> Vec<Vec<u16>> {
+    let mut sorted = ports.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    sorted.chunks(CHUNK).map(<[u16]>::to_vec).collect()
+}
+
+/// Runs `phase` with whatever is left before `deadline`; `None` if it ran out.
+async fn within<T>(
+    deadline: Instant,
+    phase: &'static str,
+    cut_short: &mut Vec<&'static str>,
+    future: impl Future<Output = T>,
+) -> Option<T> {
+    match tokio::time::timeout_at(deadline, future).await {
+        Ok(output) => Some(output),
+        Err(_) => {
+            cut_short.push(phase);
+            None
+        }
+    }
+}
+
+/// Runs `tasks`, `limit` at a time, passing each output to `keep` as it
+/// finishes; false if `deadline` came first, with the outputs kept so far.
+async fn gather<T>(
+    deadline: Instant,
+    limit: usize,
+    tasks: impl IntoIterator<Item = impl Future<Output = T>>,
+    mut keep: impl FnMut(T),
+) -> bool {
+    let mut pending = stream::iter(tasks).buffer_unordered(limit);
+    loop {
+        match tokio::time::timeout_at(deadline, pending.next()).await {
+            Ok(Some(output)) => keep(output),
+            Ok(None) => return true,
+            Err(_) => return false,
+        }
+    }
+}
+
+/// Discovery, a TCP scan of `tcp_ports`, common UDP ports, service
+/// detection, fingerprinting and TLS grading of `ip`, all finished within
+/// `budget`. A phase that runs out of time keeps what it finished, e.g. the
+/// TCP batches scanned so far, and the report names it.
+pub async fn quick_look(
+    ip: IpAddr,
+    interface: Option<&str>,
+    tcp_ports: &[u16],
+    budget: Duration,
+    config: &ScanConfig,
+) -> QuickLook {
+    let started = Instant::now();
+    let deadline = started + budget;
+    let mut report = ScanReport::new();
+    let mut cut_short = Vec::new();
+    let mut tls = Vec::new();
+
+    // 1. Discovery: ARP on the local segment, ICMP otherwise; a silent host is scanned anyway
+    let discovery = async {
+        if let IpAddr::V4(v4) = ip
+            && routing::local_subnet(v4).is_some()
+            && let Ok(arp) = arpsweep::arp_sweep(&format!("{}/32", v4), interface, None, None).await
+            && !arp.get_live_hosts().is_empty()
+        {
+            return (true, None);
+        }
+        let ping = pingsweep::ping_sweep_hosts(vec![ip], config).await;
+        (
+            ping.get_live_hosts().contains(&ip),
+            ping.get_ttls().get(&ip).copied(),
+        )
+    };
+    let discovery_deadline = deadline.min(Instant::now() + DISCOVERY_TIMEOUT);
+    let discovered = within(discovery_deadline, "discovery", &mut cut_short, discovery).await;
+    let (alive, ttl) = (
+        discovered.map(|(alive, _)| alive),
+        discovered.and_then(|(_, ttl)| ttl),
+    );
+    let hostname = within(
+        deadline,
+        "reverse DNS",
+        &mut cut_short,
+        rdns::lookup(ip, Duration::from_secs(2)),
+    )
+    .await
+    .flatten();
+    if alive == Some(true) {
+        report.add_discovery(&[LiveHost { ip, hostname }]);
+    }
+
+    // 2. TCP and UDP port scans side by side, keeping each batch as it finishes
+    let target = [ip];
+    let mut tcp = TcpScanResult::new();
+    let mut udp = UdpScanResult::new();
+    let (tcp_done, udp_done) = tokio::join!(
+        gather(
+            deadline,
+            BATCHES_IN_FLIGHT,
+            tcp_batches(tcp_ports)
+                .into_iter()
+                .map(|batch| tcpscan::tcp_scan_from(&target, batch, None, config)),
+            |scan| tcp.merge(scan),
+        ),
+        gather(
+            deadline,
+            COMMON_UDP_PORTS.len(),
+            COMMON_UDP_PORTS.iter().map(|&port| udpscan::udp_scan_from(
+                &target,
+                port..port + 1,
+                None,
+                config
+            )),
+            |scan| udp.merge(scan),
+        )
+    );
+    if !tcp_done {
+        cut_short.push("TCP scan");
+    }
+    if !udp_done {
+        cut_short.push("UDP scan");
+    }
+    report.add_tcp(&tcp);
+    report.add_udp(&udp);
+    let open: Vec<u16> = tcp.open_ports_by_host().remove(&ip).unwrap_or_default();
+
+    // 3. Service detection with the detectors usual for each open port
+    let registry = service_detection::registry();
+    let detections = open.iter().map(|&port| {
+        let protocols = registry.protocols_for_port(port);
+        let registry = &registry;
+        async move {
+            service_detection::detect_service_with(registry, ip, port, &protocols, config).await
+        }
+    });
+    let mut services = Vec::new();
+    if !gather(deadline, open.len().max(1), detections, |result| {
+        services.push(result)
+    })
+    .await
+    {
+        cut_short.push("service detection");
+    }
+    report.add_services(ip, &services);
+
+    // 4. Fingerprinting from what the scans found
+    if let Some(fingerprint) = within(
+        deadline,
+        "fingerprinting",
+        &mut cut_short,
+        fingerprinting::fingerprint_host(ip, &open, interface, ttl),
+    )
+    .await
+    {
+        report.add_fingerprint(&fingerprint);
+    }
+
+    // 5. TLS grading of ports that presented a certificate or usually speak TLS
+    let tls_ports: Vec<u16> = open
+        .iter()
+        .copied()
+        .filter(|port| {
+            TLS_PORTS.contains(port)
+                || services
+                    .iter()
+                    .any(|res| res.port == *port && res.certificate.is_some())
+        })
+        .collect();
+    let audits = tls_ports
+        .iter()
+        .map(|&port| tls_audit::audit_tls_endpoint(ip, port));
+    let graded = gather(deadline, tls_ports.len().max(1), audits, |report| {
+        if report.is_tls() {
+            tls.push(report);
+        }
+    })
+    .await;
+    if !graded {
+        cut_short.push("TLS grading");
+    }
+    tls.sort_by_key(|report| report.port);
+
+    QuickLook {
+        ip,
+        report,
+        tls,
+        cut_short,
+        elapsed: started.elapsed(),
+    }
+}
+
+impl QuickLook {
+    /// Prints the consolidated report for the host.
+    pub fn print(&self) {
+        println!(
+            "\n{} {} {}",
+            prettyprint::console("🔎 Quick look at").bold().blue(),
+            rdns::label(self.ip).bold(),
+            format!("({:.1}s)", self.elapsed.as_secs_f64()).dimmed()
+        );
+        let host = self.report.host(self.ip);
+        let alive = host.is_some_and(|host| host.alive);
+        println!(
+            "  {:<12} {}",
+            "Discovery".bold(),
+            if alive {
+                "answered".green()
+            } else {
+                "no answer to ARP/ICMP (scanned anyway)".yellow()
+            }
+        );
+        if let Some(fingerprint) = host.and_then(|host| host.fingerprint.as_ref()) {
+            let identity: Vec<&str> = [&fingerprint.os, &fingerprint.vendor, &fingerprint.model]
+                .into_iter()
+                .filter_map(|value| value.as_deref())
+                .collect();
+            if !identity.is_empty() {
+                println!("  {:<12} {}", "Identity".bold(), identity.join(", "));
+            }
+        }
+        for (label, ports) in [
+            ("TCP open", host.and_then(|host| host.tcp.as_ref())),
+            ("UDP open", host.and_then(|host| host.udp.as_ref())),
+        ] {
+            let Some(ports) = ports else {
+                continue;
+            };
+            let open: Vec<String> = ports.open.iter().map(u16::to_string).collect();
+            println!(
+                "  {:<12} {}",
+                label.bold(),
+                if open.is_empty() {
+                    "none".dimmed().to_string()
+                } else {
+                    open.join(", ")
+                }
+            );
+        }
+        let services = host.map_or(&[][..], |host| &host.services[..]);
+        if !services.is_empty() {
+            println!("  {}", "Services".bold());
+            for service in services {
+                println!(
+                    "    {:>5}  {}",
+                    service.port,
+                    service.service.as_deref().unwrap_or("Unknown Service")
+                );
+            }
+        }
+        for report in &self.tls {
+            report.print_summary();
+        }
+        if !self.cut_short.is_empty() {
+            println!(
+                "{}",
+                prettyprint::console(&format!(
+                    "⏱ The time budget ran out during: {}",
+                    self.cut_short.join(", ")
+                ))
+                .yellow()
+            );
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use crate::utils::clock::{Clock, TokioClock};
+use once_cell::sync::Lazy;
+use std::fmt;
+use std::io::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Approximate on-wire size of a TCP SYN (IPv4 + TCP headers with options)
+pub const TCP_SYN_BYTES: usize = 60;
+/// IPv4 + UDP header overhead added to every datagram payload
+pub const UDP_HEADER_BYTES: usize = 28;
+/// IPv4 header + ICMP echo request as sent by the ping sweep
+pub const ICMP_ECHO_BYTES: usize = 84;
+
+const BURST: Duration = Duration::from_millis(100); // Bucket depth, as time at the configured rate
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+static LIMITER: Lazy<RateLimiter> = Lazy::new(RateLimiter::new);
+
+/// Global limiter shared by every scanner in the process.
+pub fn global() -> &'static RateLimiter {
+    &LIMITER
+}
+
+/// Account for (and, if capped, wait for) `bytes` about to be sent as one packet.
+pub async fn acquire(bytes: usize) {
+    LIMITER.acquire(bytes).await
+}
+
+struct Bucket {
+    /// Units (bytes or packets) per second; `None` when uncapped
+    rate: Option<f64>,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn new(now: Instant) -> Self {
+        Self {
+            rate: None,
+            tokens: 0.0,
+            last_refill: now,
+        }
+    }
+
+    fn set_rate(&mut self, rate: Option<f64>, now: Instant) {
+        self.rate = rate;
+        self.tokens = rate.map_or(0.0, |rate| rate * BURST.as_secs_f64());
+        self.last_refill = now;
+    }
+
+    /// Takes `amount` tokens and returns how long to wait until they are covered.
+    fn reserve(&mut self, amount: f64, now: Instant) -> Duration {
+        let Some(rate) = self.rate else {
+            return Duration::ZERO;
+        };
+        let refill = now.duration_since(self.last_refill).as_secs_f64() * rate;
+        // A full burst is always allowed, even when it is under one packet
+        let depth = (rate * BURST.as_secs_f64()).max(1.0);
+        self.tokens = (self.tokens + refill).min(depth);
+        self.last_refill = now;
+        // Callers reserve ahead, so concurrent tasks queue up behind each other
+        self.tokens -= amount;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+}
+
+/// Token-bucket limiter on bandwidth and packet rate that also keeps
+/// byte/packet counters for throughput reporting
+pub struct RateLimiter<C: Clock = TokioClock> {
+    clock: C,
+    bucket: Mutex<Bucket>,
+    packets: Mutex<Bucket>,
+    bytes_sent: AtomicU64,
+    packets_sent: AtomicU64,
+    started: Mutex<Instant>,
+    paused: watch::Sender<bool>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::with_clock(TokioClock)
+    }
+}
+
+impl<C: Clock> RateLimiter<C> {
+    /// A limiter driven by `clock`, e.g. a `ManualClock` in tests.
+    pub fn with_clock(clock: C) -> Self {
+        let now = clock.now();
+        Self {
+            clock,
+            bucket: Mutex::new(Bucket::new(now)),
+            packets: Mutex::new(Bucket::new(now)),
+            bytes_sent: AtomicU64::new(0),
+            packets_sent: AtomicU64::new(0),
+            started: Mutex::new(now),
+            paused: watch::Sender::new(false),
+        }
+    }
+
+    /// Caps outgoing traffic to `bits_per_sec`, or removes the cap with `None`.
+    pub fn set_max_bandwidth(&self, bits_per_sec: Option<u64>) {
+        self.bucket
+            .lock()
+            .unwrap()
+            .set_rate(bits_per_sec.map(|bps| bps as f64 / 8.0), self.clock.now());
+    }
+
+    pub fn max_bandwidth(&self) -> Option<u64> {
+        self.bucket
+            .lock()
+            .unwrap()
+            .rate
+            .map(|rate| (rate * 8.0) as u64)
+    }
+
+    /// Caps outgoing packets (probes) to `packets_per_sec`, or removes the cap with `None`.
+    pub fn set_max_rate(&self, packets_per_sec: Option<u64>) {
+        self.packets
+            .lock()
+            .unwrap()
+            .set_rate(packets_per_sec.map(|pps| pps as f64), self.clock.now());
+    }
+
+    /// Spaces outgoing packets at least `interval` apart, which allows rates
+    /// under one packet per second, or removes the cap with `None`.
+    pub fn set_probe_interval(&self, interval: Option<Duration>) {
+        self.packets.lock().unwrap().set_rate(
+            interval
+                .filter(|interval| !interval.is_zero())
+                .map(|interval| 1.0 / interval.as_secs_f64()),
+            self.clock.now(),
+        );
+    }
+
+    pub fn max_rate(&self) -> Option<u64> {
+        self.packets.lock().unwrap().rate.map(|rate| rate as u64)
+    }
+
+    /// Holds every `acquire` until `set_paused(false)`, e.g. outside a scan window.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.send_replace(paused);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Returns once the limiter is not paused.
+    pub async fn wait_while_paused(&self) {
+        if !self.is_paused() {
+            return;
+        }
+        let mut paused = self.paused.subscribe();
+        // the sender lives in `self`, so the channel cannot close
+        let _ = paused.wait_for(|paused| !paused).await;
+    }
+
+    /// Returns once the limiter is paused.
+    pub async fn wait_until_paused(&self) {
+        let mut paused = self.paused.subscribe();
+        let _ = paused.wait_for(|paused| *paused).await;
+    }
+
+    /// Records one packet of `bytes` and sleeps long enough to stay under the
+    /// bandwidth and packet-rate caps, first waiting out any pause.
+    pub async fn acquire(&self, bytes: usize) {
+        self.wait_while_paused().await;
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.packets_sent.fetch_add(1, Ordering::Relaxed);
+
+        let now = self.clock.now();
+        let bandwidth_wait = self.bucket.lock().unwrap().reserve(bytes as f64, now);
+        let rate_wait = self.packets.lock().unwrap().reserve(1.0, now);
+        let wait = bandwidth_wait.max(rate_wait);
+        if !wait.is_zero() {
+            self.clock.sleep(wait).await;
+        }
+    }
+
+    /// Resets the counters (not the cap), e.g. at the start of a scan phase.
+    pub fn reset_counters(&self) {
+        self.bytes_sent.store(0, Ordering::Relaxed);
+        self.packets_sent.store(0, Ordering::Relaxed);
+        *self.started.lock().unwrap() = self.clock.now();
+    }
+
+    pub fn snapshot(&self) -> Throughput {
+        Throughput {
+            bytes: self.bytes_sent.load(Ordering::Relaxed),
+            packets: self.packets_sent.load(Ordering::Relaxed),
+            elapsed: self.clock.now().duration_since(*self.started.lock().unwrap()),
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Traffic sent over a period of time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Throughput {
+    pub bytes: u64,
+    pub packets: u64,
+    pub elapsed: Duration,
+}
+
+impl Throughput {
+    pub fn bits_per_sec(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 { 0.0 } else { self.bytes as f64 * 8.0 / secs }
+    }
+
+    pub fn packets_per_sec(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 { 0.0 } else { self.packets as f64 / secs }
+    }
+
+    /// Traffic sent between an earlier snapshot and this one.
+    pub fn since(&self, earlier: &Throughput) -> Throughput {
+        Throughput {
+            bytes: self.bytes.saturating_sub(earlier.bytes),
+            packets: self.packets.saturating_sub(earlier.packets),
+            elapsed: self.elapsed.saturating_sub(earlier.elapsed),
+        }
+    }
+}
+
+impl fmt::Display for Throughput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} packets, {} bytes ({:.0} pkt/s, {})",
+            self.packets,
+            self.bytes,
+            self.packets_per_sec(),
+            format_bandwidth(self.bits_per_sec() as u64)
+        )
+    }
+}
+
+/// Parses a bandwidth like "5mbps", "512kbps", "1.5M" or "100000" (bits per second).
+pub fn parse_bandwidth(input: &str) -> Result<u64, String> {
+    let lower = input.trim().to_ascii_lowercase();
+    let number_end = lower
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(lower.len());
+    let (number, unit) = lower.split_at(number_end);
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid bandwidth '{input}'. Use e.g. 5mbps or 512kbps."))?;
+    let multiplier = match unit.trim().trim_end_matches("bps").trim_end_matches("bit/s") {
+        "" | "b" => 1.0,
+        "k" => 1_000.0,
+        "m" => 1_000_000.0,
+        "g" => 1_000_000_000.0,
+        _ => return Err(format!("Invalid bandwidth unit in '{input}'. Use bps, kbps, mbps or gbps.")),
+    };
+    let bits = value * multiplier;
+    if bits < 1.0 {
+        return Err(format!("Bandwidth '{input}' must be at least 1bps."));
+    }
+    Ok(bits as u64)
+}
+
+/// Formats bits per second with a human-friendly unit, e.g. "4.98 Mbps".
+pub fn format_bandwidth(bits_per_sec: u64) -> String {
+    let bps = bits_per_sec as f64;
+    if bps >= 1_000_000_000.0 {
+        format!("{:.2} Gbps", bps / 1_000_000_000.0)
+    } else if bps >= 1_000_000.0 {
+        format!("{:.2} Mbps", bps / 1_000_000.0)
+    } else if bps >= 1_000.0 {
+        format!("{:.1} kbps", bps / 1_000.0)
+    } else {
+        format!("{:.0} bps", bps)
+    }
+}
+
+/// Prints a live throughput line to stderr every second until the handle is aborted.
+pub fn spawn_throughput_reporter(label: &'static str) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let limiter = global();
+        let mut previous = limiter.snapshot();
+        loop {
+            tokio::time::sleep(REPORT_INTERVAL).await;
+            let current = limiter.snapshot();
+            let window = current.since(&previous);
+            eprint!(
+                "\r{label}: {} packets sent, {:.0} pkt/s, {}    ",
+                current.packets,
+                window.packets_per_sec(),
+                format_bandwidth(window.bits_per_sec() as u64)
+            );
+            let _ = std::io::stderr().flush();
+            previous = current;
+        }
+    })
+}
@@ -0,0 +1,129 @@
+use crate::scanners::pingsweep::LiveHost;
+use crate::utils::inventory;
+use crate::utils::redact;
+use crate::utils::scan_config::ScanConfig;
+use futures::stream::{self, StreamExt};
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::ffi::CStr;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_CONCURRENT_LOOKUPS: usize = 32;
+const MAX_HOSTNAME: usize = 1025; // NI_MAXHOST
+
+/// PTR names found so far, so every report can show them without passing them around.
+static NAMES: Lazy<Mutex<HashMap<IpAddr, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The name `ip` resolved to earlier in this run, if any.
+pub fn cached(ip: IpAddr) -> Option<String> {
+    NAMES.lock().unwrap().get(&ip).cloned()
+}
+
+/// `ip` followed by its PTR name and inventory entry when known, e.g.
+/// "10.0.0.1 (gw.lan) [core-rtr, owner netops]". Pseudonymized under `--redact`.
+pub fn label(ip: IpAddr) -> String {
+    let mut label = redact::ip(ip).to_string();
+    if let Some(name) = cached(ip) {
+        label.push_str(&format!(" ({})", redact::hostname(&name)));
+    }
+    if let Some(entry) = inventory::describe(ip) {
+        label.push_str(&format!(" [{}]", redact::text(&entry)));
+    }
+    label
+}
+
+/// Reverse-resolves `ip` through the system resolver (hosts file, DNS, ...).
+/// Blocking.
+pub fn lookup_blocking(ip: IpAddr) -> Option<String> {
+    // SAFETY: the sockaddr is fully initialized for its family and the
+    // buffer length passed matches the buffer
+    unsafe {
+        let mut storage: libc::sockaddr_storage = std::mem::zeroed();
+        let len = match ip {
+            IpAddr::V4(v4) => {
+                let sin = &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in);
+                sin.sin_family = libc::AF_INET as libc::sa_family_t;
+                sin.sin_addr.s_addr = u32::from_ne_bytes(v4.octets());
+                std::mem::size_of::<libc::sockaddr_in>()
+            }
+            IpAddr::V6(v6) => {
+                let sin6 = &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6);
+                sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                sin6.sin6_addr.s6_addr = v6.octets();
+                std::mem::size_of::<libc::sockaddr_in6>()
+            }
+        };
+        let mut host = [0 as libc::c_char; MAX_HOSTNAME];
+        let status = libc::getnameinfo(
+            &storage as *const _ as *const libc::sockaddr,
+            len as libc::socklen_t,
+            host.as_mut_ptr(),
+            host.len() as libc::socklen_t,
+            std::ptr::null_mut(),
+            0,
+            libc::NI_NAMEREQD,
+        );
+        if status != 0 {
+            return None;
+        }
+        let name = CStr::from_ptr(host.as_ptr()).to_string_lossy().into_owned();
+        (!name.is_empty()).then_some(name)
+    }
+}
+
+/// `lookup_blocking` on the blocking pool, giving up after `timeout`.
+pub async fn lookup(ip: IpAddr, timeout: Duration) -> Option<String> {
+    let task = tokio::task::spawn_blocking(move || lookup_blocking(ip));
+    tokio::time::timeout(timeout, task).await.ok()?.ok()?
+}
+
+/// Reverse-resolves every host, remembering the names for `label`. Hosts
+/// keep their order; those without a PTR record get no name. Names found
+/// earlier in the run are not looked up again.
+pub async fn resolve_hosts(hosts: &[IpAddr], config: &ScanConfig) -> Vec<LiveHost> {
+    let timeout = config.timeout_or(LOOKUP_TIMEOUT);
+    let resolved: Vec<LiveHost> = stream::iter(hosts.iter().copied())
+        .map(|ip| async move {
+            LiveHost {
+                ip,
+                hostname: match cached(ip) {
+                    Some(name) => Some(name),
+                    None => lookup(ip, timeout).await,
+                },
+            }
+        })
+        .buffered(config.concurrency_or(MAX_CONCURRENT_LOOKUPS))
+        .collect()
+        .await;
+
+    let mut names = NAMES.lock().unwrap();
+    for host in &resolved {
+        if let Some(name) = &host.hostname {
+            names.insert(host.ip, name.clone());
+        }
+    }
+    resolved
+}
+
+/// Resolves the PTR record of every target before probing and returns the
+/// ones that have a name. A name suggests the address is in use even when
+/// the host does not answer discovery.
+pub async fn ptr_sweep(targets: &[IpAddr], config: &ScanConfig) -> Vec<LiveHost> {
+    resolve_hosts(targets, config)
+        .await
+        .into_iter()
+        .filter(|host| host.hostname.is_some())
+        .collect()
+}
+
+/// The `discovered` hosts plus the `named` ones discovery missed, named
+/// hosts first so scans reach them early.
+pub fn prioritize(discovered: &[IpAddr], named: &[IpAddr]) -> Vec<IpAddr> {
+    let first: HashSet<IpAddr> = named.iter().copied().collect();
+    let mut hosts: Vec<IpAddr> = named.to_vec();
+    hosts.extend(discovered.iter().filter(|ip| !first.contains(ip)));
+    hosts
+}
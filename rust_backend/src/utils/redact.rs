@@ -0,0 +1,224 @@
+use hmac::{Hmac, Mac};
+use once_cell::sync::OnceCell;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Mutex;
+
+/// Redactor for reports, the history and console output, set once by `--redact`
+static REDACTOR: OnceCell<Redactor> = OnceCell::new();
+
+const KEY_LEN: usize = 32;
+
+/// Consistent pseudonyms for addresses and names: the same input always
+/// maps to the same output under one key, so correlations between hosts,
+/// ports and findings survive, but the real values cannot be recovered
+/// without the key
+#[derive(Debug)]
+pub struct Redactor {
+    key: Vec<u8>,
+    ips: Mutex<Pseudonyms<IpAddr>>,
+    hostnames: Mutex<BTreeMap<String, String>>,
+}
+
+/// Pseudonyms handed out so far, so two addresses never share one
+#[derive(Debug)]
+struct Pseudonyms<T> {
+    assigned: BTreeMap<T, T>,
+    taken: BTreeSet<T>,
+}
+
+impl Redactor {
+    pub fn new(key: &[u8]) -> Self {
+        Self {
+            key: key.to_vec(),
+            ips: Mutex::new(Pseudonyms {
+                assigned: BTreeMap::new(),
+                taken: BTreeSet::new(),
+            }),
+            hostnames: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// A redactor with a random key: pseudonyms are only stable within the run.
+    pub fn random() -> Self {
+        Self::new(&random_key())
+    }
+
+    fn digest(&self, kind: &str, value: &str) -> [u8; 32] {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC takes keys of any length");
+        mac.update(format!("{}:{}", kind, value).as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    /// IPv4 addresses become addresses in 10.0.0.0/8, IPv6 ones in fd00::/8.
+    pub fn ip(&self, ip: IpAddr) -> IpAddr {
+        let mut ips = self.ips.lock().unwrap();
+        if let Some(pseudonym) = ips.assigned.get(&ip) {
+            return *pseudonym;
+        }
+        let pseudonym = (0u32..)
+            .map(|attempt| {
+                let hash = self.digest("ip", &format!("{}#{}", ip, attempt));
+                match ip {
+                    IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::new(10, hash[0], hash[1], hash[2])),
+                    IpAddr::V6(_) => {
+                        let mut octets = [0u8; 16];
+                        octets[0] = 0xfd;
+                        octets[1..].copy_from_slice(&hash[..15]);
+                        IpAddr::V6(Ipv6Addr::from(octets))
+                    }
+                }
+            })
+            .find(|candidate| !ips.taken.contains(candidate))
+            .expect("pseudonym space exhausted");
+        ips.taken.insert(pseudonym);
+        ips.assigned.insert(ip, pseudonym);
+        pseudonym
+    }
+
+    /// The vendor prefix (OUI) is kept; the NIC-specific half is replaced.
+    pub fn mac(&self, mac: &str) -> String {
+        let normalized = mac.to_ascii_lowercase().replace('-', ":");
+        let hash = self.digest("mac", &normalized);
+        let oui = normalized.get(..8).unwrap_or("00:00:00");
+        format!("{}:{:02x}:{:02x}:{:02x}", oui, hash[0], hash[1], hash[2])
+    }
+
+    /// "host-1a2b3c4d"; a leading "*." of wildcard certificate names is kept.
+    pub fn hostname(&self, name: &str) -> String {
+        let lower = name.trim_end_matches('.').to_ascii_lowercase();
+        let mut hostnames = self.hostnames.lock().unwrap();
+        if let Some(pseudonym) = hostnames.get(&lower) {
+            return pseudonym.clone();
+        }
+        let (wildcard, bare) = match lower.strip_prefix("*.") {
+            Some(bare) => ("*.", bare),
+            None => ("", lower.as_str()),
+        };
+        let hash = self.digest("host", bare);
+        let pseudonym = format!(
+            "{}host-{:02x}{:02x}{:02x}{:02x}",
+            wildcard, hash[0], hash[1], hash[2], hash[3]
+        );
+        hostnames.insert(lower, pseudonym.clone());
+        pseudonym
+    }
+
+    /// Free text with every IP address, MAC address and already redacted
+    /// hostname in it replaced by its pseudonym.
+    pub fn text(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut token = String::new();
+        for c in text.chars() {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '-' | '_' | '*') {
+                token.push(c);
+            } else {
+                out.push_str(&self.token(&token));
+                token.clear();
+                out.push(c);
+            }
+        }
+        out.push_str(&self.token(&token));
+        out
+    }
+
+    fn token(&self, token: &str) -> String {
+        // Sentence punctuation is not part of the address
+        let trimmed = token.trim_end_matches(['.', ':']);
+        let rest = &token[trimmed.len()..];
+        if let Ok(ip) = trimmed.parse::<IpAddr>() {
+            return format!("{}{}", self.ip(ip), rest);
+        }
+        if is_mac(trimmed) {
+            return format!("{}{}", self.mac(trimmed), rest);
+        }
+        let known = self
+            .hostnames
+            .lock()
+            .unwrap()
+            .get(&trimmed.to_ascii_lowercase())
+            .cloned();
+        match known {
+            Some(pseudonym) => format!("{}{}", pseudonym, rest),
+            None => token.to_string(),
+        }
+    }
+}
+
+fn is_mac(token: &str) -> bool {
+    let parts: Vec<&str> = token.split([':', '-']).collect();
+    parts.len() == 6
+        && parts
+            .iter()
+            .all(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn random_key() -> Vec<u8> {
+    use std::io::Read;
+    let mut key = vec![0u8; KEY_LEN];
+    if std::fs::File::open("/dev/urandom")
+        .and_then(|mut urandom| urandom.read_exact(&mut key))
+        .is_err()
+    {
+        let seed = format!("{}:{:?}", std::process::id(), std::time::SystemTime::now());
+        key = Sha256::digest(seed.as_bytes()).to_vec();
+    }
+    key
+}
+
+/// Makes `redactor` the one every output goes through; only the first call counts.
+pub fn install(redactor: Redactor) {
+    let _ = REDACTOR.set(redactor);
+}
+
+/// Hands out the pseudonyms of `ips`, in address order, and of every name in
+/// `names`, so `text` scrubs them wherever they turn up later. Call it with
+/// everything a scan learned before writing its results.
+pub fn register<'a>(ips: impl IntoIterator<Item = IpAddr>, names: impl IntoIterator<Item = &'a str>) {
+    let Some(redactor) = REDACTOR.get() else {
+        return;
+    };
+    let ips: BTreeSet<IpAddr> = ips.into_iter().collect();
+    for ip in ips {
+        redactor.ip(ip);
+    }
+    for name in names {
+        if name.parse::<IpAddr>().is_err() && !name.is_empty() {
+            redactor.hostname(name);
+        }
+    }
+}
+
+/// Whether `--redact` is on.
+pub fn enabled() -> bool {
+    REDACTOR.get().is_some()
+}
+
+/// `ip`, pseudonymized when redaction is on.
+pub fn ip(ip: IpAddr) -> IpAddr {
+    REDACTOR.get().map_or(ip, |redactor| redactor.ip(ip))
+}
+
+/// `name`, pseudonymized when redaction is on.
+pub fn hostname(name: &str) -> String {
+    REDACTOR
+        .get()
+        .map_or_else(|| name.to_string(), |redactor| redactor.hostname(name))
+}
+
+/// A certificate name or SAN: an IP address or a hostname, pseudonymized
+/// when redaction is on.
+pub fn name(name: &str) -> String {
+    match name.parse::<IpAddr>() {
+        Ok(addr) => ip(addr).to_string(),
+        Err(_) => hostname(name),
+    }
+}
+
+/// `text` with addresses and known names pseudonymized when redaction is on.
+pub fn text(text: &str) -> String {
+    REDACTOR
+        .get()
+        .map_or_else(|| text.to_string(), |redactor| redactor.text(text))
+}
@@ -0,0 +1,384 @@
+use crate::scanners::service_detection::{self, DetectorRegistry, Protocol, ServiceDetectionResult};
+use crate::utils::evidence::Source;
+use crate::utils::scan_config::ScanConfig;
+use crate::utils::unknowns::UnknownSample;
+use chrono::{TimeZone, Utc};
+use pnet::packet::Packet;
+use pnet::packet::ethernet::{EtherTypes, EthernetPacket};
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv4::Ipv4Packet;
+use pnet::packet::ipv6::Ipv6Packet;
+use pnet::packet::tcp::{TcpFlags, TcpPacket};
+use pnet::packet::vlan::VlanPacket;
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// Link type of Ethernet captures, the only kind read
+const LINKTYPE_ETHERNET: u32 = 1;
+
+/// A saved exchange to run the detectors against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplaySample {
+    /// File name, with "#N" for the Nth TCP conversation of a pcap
+    pub name: String,
+    pub sample: UnknownSample,
+    /// What detection should report, from an "expect:" line
+    pub expected: Option<String>,
+}
+
+/// What detection made of one sample
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayOutcome {
+    pub name: String,
+    pub port: u16,
+    pub service: Option<String>,
+    pub expected: Option<String>,
+    /// Detector whose probe identified the service; `None` when only the
+    /// generic banner grab did
+    pub detector: Option<String>,
+}
+
+impl ReplayOutcome {
+    /// What `result`, from serving `sample`, made of it.
+    pub fn new(sample: &ReplaySample, result: ServiceDetectionResult) -> Self {
+        let detector = result.evidence.iter().find_map(|evidence| match &evidence.source {
+            Source::Probe(name) => Some(name.clone()),
+            _ => None,
+        });
+        Self {
+            name: sample.name.clone(),
+            port: sample.sample.port,
+            service: result.service,
+            expected: sample.expected.clone(),
+            detector,
+        }
+    }
+
+    /// Whether the service matched the expectation, case-insensitively;
+    /// `None` for samples without one.
+    pub fn passed(&self) -> Option<bool> {
+        let expected = self.expected.as_ref()?.to_lowercase();
+        Some(
+            self.service
+                .as_ref()
+                .is_some_and(|service| service.to_lowercase().contains(&expected)),
+        )
+    }
+}
+
+/// Parses a sample file as written by `--collect-unknowns`, plus an optional
+/// "expect: SERVICE" line.
+pub fn parse_sample(name: &str, text: &str) -> Result<ReplaySample, String> {
+    let sample = UnknownSample::parse(text).map_err(|e| format!("{}: {}", name, e))?;
+    let expected = text
+        .lines()
+        .find_map(|line| line.strip_prefix("expect: "))
+        .map(|value| value.trim().to_string());
+    Ok(ReplaySample {
+        name: name.to_string(),
+        sample,
+        expected,
+    })
+}
+
+/// The first exchange of every TCP conversation in a classic libpcap file:
+/// what the client sent, then what the server answered.
+pub fn read_pcap(bytes: &[u8]) -> Result<Vec<UnknownSample>, String> {
+    let header = bytes.get(..24).ok_or("File too short for a pcap header")?;
+    let magic = u32::from_le_bytes(header[..4].try_into().unwrap());
+    let (big_endian, nanos) = match magic {
+        0xa1b2_c3d4 => (false, false),
+        0xa1b2_3c4d => (false, true),
+        0xd4c3_b2a1 => (true, false),
+        0x4d3c_b2a1 => (true, true),
+        _ => return Err("Not a pcap file (pcapng is not supported)".to_string()),
+    };
+    let read_u32 = |b: &[u8]| {
+        let b: [u8; 4] = b.try_into().unwrap();
+        if big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    };
+    let link_type = read_u32(&header[20..24]);
+    if link_type != LINKTYPE_ETHERNET {
+        return Err(format!("Unsupported pcap link type {}", link_type));
+    }
+
+    let mut conversations: Vec<Conversation> = Vec::new();
+    let mut seen: HashSet<(SocketAddr, u32, usize)> = HashSet::new();
+    let mut offset = 24;
+    while let Some(record) = bytes.get(offset..offset + 16) {
+        let seconds = read_u32(&record[..4]);
+        let fraction = read_u32(&record[4..8]);
+        let length = read_u32(&record[8..12]) as usize;
+        let data = bytes
+            .get(offset + 16..offset + 16 + length)
+            .ok_or("Truncated pcap record")?;
+        offset += 16 + length;
+        let Some(segment) = tcp_segment(data) else {
+            continue;
+        };
+        // Retransmissions repeat a sequence number already seen
+        if !segment.payload.is_empty()
+            && !seen.insert((segment.src, segment.seq, segment.payload.len()))
+        {
+            continue;
+        }
+        let nanoseconds = if nanos {
+            fraction
+        } else {
+            fraction.saturating_mul(1000)
+        };
+        let time = Utc
+            .timestamp_opt(seconds as i64, nanoseconds)
+            .single()
+            .unwrap_or_else(Utc::now);
+        let index = match conversations
+            .iter()
+            .position(|c| c.involves(segment.src, segment.dst))
+        {
+            Some(index) => index,
+            None => {
+                conversations.push(Conversation::new(segment.src, segment.dst, time));
+                conversations.len() - 1
+            }
+        };
+        conversations[index].add(segment);
+    }
+    Ok(conversations
+        .into_iter()
+        .filter_map(Conversation::first_exchange)
+        .collect())
+}
+
+struct TcpSegment {
+    src: SocketAddr,
+    dst: SocketAddr,
+    flags: u8,
+    seq: u32,
+    payload: Vec<u8>,
+}
+
+fn tcp_segment(frame: &[u8]) -> Option<TcpSegment> {
+    let ethernet = EthernetPacket::new(frame)?;
+    let (ethertype, mut payload) = (ethernet.get_ethertype(), ethernet.payload().to_vec());
+    let ethertype = if ethertype == EtherTypes::Vlan {
+        let vlan = VlanPacket::new(&payload)?;
+        let inner = vlan.get_ethertype();
+        payload = vlan.payload().to_vec();
+        inner
+    } else {
+        ethertype
+    };
+    let (src_ip, dst_ip, tcp): (IpAddr, IpAddr, Vec<u8>) = if ethertype == EtherTypes::Ipv4 {
+        let ip = Ipv4Packet::new(&payload)?;
+        if ip.get_next_level_protocol() != IpNextHeaderProtocols::Tcp {
+            return None;
+        }
+        // The capture may include Ethernet padding past the IP total length
+        let end = (ip.get_total_length() as usize).min(payload.len());
+        let start = ip.get_header_length() as usize * 4;
+        (
+            ip.get_source().into(),
+            ip.get_destination().into(),
+            payload.get(start..end)?.to_vec(),
+        )
+    } else if ethertype == EtherTypes::Ipv6 {
+        let ip = Ipv6Packet::new(&payload)?;
+        if ip.get_next_header() != IpNextHeaderProtocols::Tcp {
+            return None;
+        }
+        (
+            ip.get_source().into(),
+            ip.get_destination().into(),
+            ip.payload().to_vec(),
+        )
+    } else {
+        return None;
+    };
+    let segment = TcpPacket::new(&tcp)?;
+    Some(TcpSegment {
+        src: SocketAddr::new(src_ip, segment.get_source()),
+        dst: SocketAddr::new(dst_ip, segment.get_destination()),
+        flags: segment.get_flags(),
+        seq: segment.get_sequence(),
+        payload: segment.payload().to_vec(),
+    })
+}
+
+/// One TCP connection of a capture
+struct Conversation {
+    ends: (SocketAddr, SocketAddr),
+    /// Learned from the handshake; otherwise the end with the lower port
+    server: Option<SocketAddr>,
+    segments: Vec<(SocketAddr, Vec<u8>)>,
+    started: chrono::DateTime<Utc>,
+}
+
+impl Conversation {
+    fn new(a: SocketAddr, b: SocketAddr, started: chrono::DateTime<Utc>) -> Self {
+        Self {
+            ends: (a, b),
+            server: None,
+            segments: Vec::new(),
+            started,
+        }
+    }
+
+    fn involves(&self, src: SocketAddr, dst: SocketAddr) -> bool {
+        self.ends == (src, dst) || self.ends == (dst, src)
+    }
+
+    fn add(&mut self, segment: TcpSegment) {
+        if segment.flags & TcpFlags::SYN != 0 {
+            let server = if segment.flags & TcpFlags::ACK != 0 {
+                segment.src
+            } else {
+                segment.dst
+            };
+            self.server.get_or_insert(server);
+        }
+        if !segment.payload.is_empty() {
+            self.segments.push((segment.src, segment.payload));
+        }
+    }
+
+    fn first_exchange(self) -> Option<UnknownSample> {
+        let (a, b) = self.ends;
+        let server = self
+            .server
+            .unwrap_or(if a.port() <= b.port() { a } else { b });
+        let mut probe = Vec::new();
+        let mut response = Vec::new();
+        for (from, payload) in &self.segments {
+            if *from == server {
+                response.extend_from_slice(payload);
+            } else if response.is_empty() {
+                probe.extend_from_slice(payload);
+            } else {
+                break; // the client's next request
+            }
+        }
+        if response.is_empty() {
+            return None;
+        }
+        Some(UnknownSample {
+            port: server.port(),
+            probe,
+            response,
+            captured: self.started,
+        })
+    }
+}
+
+/// Every sample in `dir`: "*.txt" sample files and the TCP conversations of
+/// "*.pcap" captures, in file name order.
+pub fn load_corpus(dir: &Path) -> Result<Vec<ReplaySample>, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    paths.sort();
+    let mut samples = Vec::new();
+    for path in paths {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("txt") => {
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                samples.push(parse_sample(&name, &text)?);
+            }
+            Some("pcap") => {
+                let bytes = std::fs::read(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                let exchanges = read_pcap(&bytes).map_err(|e| format!("{}: {}", name, e))?;
+                for (n, sample) in exchanges.into_iter().enumerate() {
+                    samples.push(ReplaySample {
+                        name: format!("{}#{}", name, n + 1),
+                        sample,
+                        expected: None,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(samples)
+}
+
+/// Serves `sample` from a loopback listener until the returned task is
+/// aborted: every connection gets the saved response after sending its
+/// probe. Returns the listener's port.
+pub async fn serve(sample: &UnknownSample) -> Result<(u16, JoinHandle<()>), String> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .map_err(|e| format!("Failed to open a replay listener: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to open a replay listener: {}", e))?
+        .port();
+    let saved = sample.clone();
+    let server = tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let saved = saved.clone();
+            tokio::spawn(async move {
+                let mut buf = vec![0u8; 4096];
+                let mut received = 0;
+                while received < saved.probe.len() {
+                    match tokio::time::timeout(Duration::from_secs(1), socket.read(&mut buf)).await
+                    {
+                        Ok(Ok(n)) if n > 0 => received += n,
+                        _ => break,
+                    }
+                }
+                let _ = socket.write_all(&saved.response).await;
+                let _ = socket.shutdown().await;
+                // Drain whatever else the detector sends until it hangs up
+                while let Ok(Ok(n)) =
+                    tokio::time::timeout(Duration::from_secs(2), socket.read(&mut buf)).await
+                {
+                    if n == 0 {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    Ok((port, server))
+}
+
+/// Serves `sample` (see `serve`) and runs detection against it. `protocols`
+/// defaults to the detectors registered for the sample's port.
+pub async fn replay(
+    registry: &DetectorRegistry,
+    sample: &ReplaySample,
+    protocols: &[Protocol],
+) -> Result<ReplayOutcome, String> {
+    let (port, server) = serve(&sample.sample).await?;
+    let protocols = if protocols.is_empty() {
+        registry.protocols_for_port(sample.sample.port)
+    } else {
+        protocols.to_vec()
+    };
+    let result = service_detection::detect_service_with(
+        registry,
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+        port,
+        &protocols,
+        &ScanConfig::default(),
+    )
+    .await;
+    server.abort();
+    Ok(ReplayOutcome::new(sample, result))
+}
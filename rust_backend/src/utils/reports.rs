@@ -1,38 +1,245 @@
-use std::collections::HashMap;
 use std::fs::OpenOptions;
-use std::io::Write;
+use std::time::Duration;
 use chrono::Utc;
+use crate::report::ScanReport;
 use crate::scanners::service_detection; // <-- Use the crate name
+use crate::utils::banners::BannerInventory;
+use crate::utils::cert_inventory::CertificateInventory;
+use crate::utils::compliance::ComplianceMatrix;
+use crate::utils::msf::MsfSuggestions;
+use crate::utils::redact;
 
+/// Success, failure and timing counts of one detector on one host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolStats {
+    pub protocol: String,
+    pub successes: usize,
+    pub failures: usize,
+    /// Failures where the detector gave up waiting; counted in `failures` too
+    pub timeouts: usize,
+    pub total_latency: Duration,
+    /// Ports the detector ran on without identifying the service
+    pub failed_ports: Vec<u16>,
+}
+
+impl ProtocolStats {
+    fn new(protocol: &str) -> Self {
+        Self {
+            protocol: protocol.to_string(),
+            successes: 0,
+            failures: 0,
+            timeouts: 0,
+            total_latency: Duration::ZERO,
+            failed_ports: Vec::new(),
+        }
+    }
+
+    pub fn attempts(&self) -> usize {
+        self.successes + self.failures
+    }
+
+    pub fn average_latency(&self) -> Duration {
+        match self.attempts() {
+            0 => Duration::ZERO,
+            n => self.total_latency / n as u32,
+        }
+    }
+
+    /// Share of attempts that timed out, from 0.0 to 1.0.
+    pub fn timeout_rate(&self) -> f64 {
+        match self.attempts() {
+            0 => 0.0,
+            n => self.timeouts as f64 / n as f64,
+        }
+    }
+}
+
+/// Per-detector statistics over one host's results, by protocol name.
+pub fn protocol_stats(results: &[service_detection::ServiceDetectionResult]) -> Vec<ProtocolStats> {
+    let mut stats: Vec<ProtocolStats> = Vec::new();
+    for res in results {
+        for attempt in &res.attempts {
+            let index = match stats.iter().position(|s| s.protocol == attempt.protocol) {
+                Some(index) => index,
+                None => {
+                    stats.push(ProtocolStats::new(&attempt.protocol));
+                    stats.len() - 1
+                }
+            };
+            let entry = &mut stats[index];
+            entry.total_latency += attempt.elapsed;
+            if attempt.detected {
+                entry.successes += 1;
+            } else {
+                entry.failures += 1;
+                entry.failed_ports.push(res.port);
+                if attempt.timed_out {
+                    entry.timeouts += 1;
+                }
+            }
+        }
+    }
+    stats.sort_by(|a, b| a.protocol.cmp(&b.protocol));
+    // Results arrive in the order the ports were given on the command line
+    for entry in &mut stats {
+        entry.failed_ports.sort_unstable();
+    }
+    stats
+}
+
+/// Appends one row per detector that ran on `ip`, writing the header first
+/// when the file is new. Like every export, addresses are pseudonymized
+/// under `--redact`.
 pub fn append_summary_to_csv(
     filename: &str,
     ip: &str,
     results: &[service_detection::ServiceDetectionResult], // <-- Use the module path
 ) -> std::io::Result<()> {
-    let mut file = OpenOptions::new()
+    let is_new = std::fs::metadata(filename).map_or(true, |meta| meta.len() == 0);
+    let file = OpenOptions::new()
         .create(true)
         .append(true)
         .open(filename)?;
+    let mut writer = csv::Writer::from_writer(file);
 
-    // Aggregate protocol failures
-    let mut protocol_counts: HashMap<String, Vec<u16>> = HashMap::new();
-    for res in results {
-        for proto in &res.protocol_failures {
-            protocol_counts.entry(proto.clone()).or_default().push(res.port);
+    if is_new {
+        writer.write_record([
+            "Timestamp",
+            "Target",
+            "Protocol",
+            "Successes",
+            "Failures",
+            "Timeouts",
+            "TimeoutRate",
+            "AvgLatencyMs",
+            "FailedPorts",
+        ])?;
+    }
+    let timestamp = Utc::now().to_rfc3339();
+    let ip = redact::text(ip);
+    for stats in protocol_stats(results) {
+        let failed_ports: Vec<String> = stats.failed_ports.iter().map(|p| p.to_string()).collect();
+        writer.write_record([
+            timestamp.as_str(),
+            ip.as_str(),
+            &stats.protocol,
+            &stats.successes.to_string(),
+            &stats.failures.to_string(),
+            &stats.timeouts.to_string(),
+            &format!("{:.2}", stats.timeout_rate()),
+            &stats.average_latency().as_millis().to_string(),
+            &failed_ports.join(","),
+        ])?;
+    }
+    writer.flush()
+}
+
+/// Writes the certificate inventory, soonest expiry first, one row per certificate.
+pub fn write_certificate_inventory_csv(
+    filename: &str,
+    inventory: &CertificateInventory,
+) -> std::io::Result<()> {
+    let mut writer = csv::Writer::from_path(filename)?;
+    writer.write_record([
+        "Subject", "SANs", "Issuer", "Serial", "NotAfter", "Key", "Endpoints",
+    ])?;
+    for entry in inventory.entries() {
+        let cert = &entry.certificate;
+        let endpoints: Vec<String> = entry
+            .endpoints
+            .iter()
+            .map(|(ip, port)| format!("{}:{}", redact::ip(*ip), port))
+            .collect();
+        let sans: Vec<String> = cert.sans.iter().map(|san| redact::name(san)).collect();
+        writer.write_record([
+            redact::name(&cert.subject).as_str(),
+            &sans.join(" "),
+            // A self-signed issuer is the subject again, redacted above
+            &redact::text(&cert.issuer),
+            &cert.serial,
+            &cert.not_after.to_rfc3339(),
+            &cert.key_description(),
+            &endpoints.join(" "),
+        ])?;
+    }
+    writer.flush()
+}
+
+/// Writes every captured login banner verbatim, one row per host:port.
+pub fn write_banners_csv(filename: &str, inventory: &BannerInventory) -> std::io::Result<()> {
+    let mut writer = csv::Writer::from_path(filename)?;
+    writer.write_record(["Host", "Port", "Service", "Legal Warning", "Banner"])?;
+    for banner in inventory.banners() {
+        writer.write_record([
+            redact::ip(banner.ip).to_string().as_str(),
+            &banner.port.to_string(),
+            banner.source.label(),
+            if banner.has_legal_warning() { "yes" } else { "no" },
+            &redact::text(&banner.text),
+        ])?;
+    }
+    writer.flush()
+}
+
+/// Writes one row per suggested module and service.
+pub fn write_msf_suggestions_csv(filename: &str, suggestions: &MsfSuggestions) -> std::io::Result<()> {
+    let mut writer = csv::Writer::from_path(filename)?;
+    writer.write_record(["Host", "Port", "Service", "Module", "Reason"])?;
+    // By host and port, however the hosts' detections finished
+    let mut entries: Vec<_> = suggestions.entries().iter().collect();
+    entries.sort_by_key(|(info, _)| (info.host.parse::<std::net::IpAddr>().ok(), info.port));
+    for (info, modules) in entries {
+        for suggestion in modules {
+            writer.write_record([
+                redact::name(&info.host).as_str(),
+                &info.port.to_string(),
+                &info.name,
+                &suggestion.module,
+                &redact::text(&suggestion.reason),
+            ])?;
+        }
+    }
+    writer.flush()
+}
+
+pub fn write_compliance_csv(filename: &str, matrix: &ComplianceMatrix) -> std::io::Result<()> {
+    let mut writer = csv::Writer::from_path(filename)?;
+    writer.write_record(["Host", "Profile", "Control", "Statement", "Result", "Evidence"])?;
+    for host in matrix.hosts() {
+        for result in &host.results {
+            writer.write_record([
+                redact::ip(host.ip).to_string().as_str(),
+                matrix.profile.label(),
+                result.control.id,
+                result.control.statement,
+                result.status.label(),
+                &redact::text(&result.evidence.join("; ")),
+            ])?;
         }
     }
+    writer.flush()
+}
 
-    writeln!(file, "Timestamp,Target,Protocol,FailCount,Ports")?;
-    for (proto, ports) in protocol_counts {
-        writeln!(
-            file,
-            "{},{},{},{},\"{}\"",
-            Utc::now().to_rfc3339(),
-            ip,
-            proto,
-            ports.len(),
-            ports.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(",")
-        )?;
+/// Writes one row per probe that needed more than one attempt: echo requests
+/// of the ping sweep (no port) and TCP and UDP port probes.
+pub fn write_retries_csv(filename: &str, report: &ScanReport) -> std::io::Result<()> {
+    let mut writer = csv::Writer::from_path(filename)?;
+    writer.write_record(["Host", "Transport", "Port", "Attempts"])?;
+    for host in &report.hosts {
+        let ip = redact::ip(host.ip).to_string();
+        if let Some(attempts) = host.ping_attempts {
+            writer.write_record([ip.as_str(), "icmp", "", &attempts.to_string()])?;
+        }
+        for (transport, ports) in [("tcp", &host.tcp), ("udp", &host.udp)] {
+            for (port, attempts) in ports.iter().flat_map(|ports| &ports.retried) {
+                writer.write_record([
+                    ip.as_str(),
+                    transport,
+                    &port.to_string(),
+                    &attempts.to_string(),
+                ])?;
+            }
+        }
     }
-    Ok(())
-}
\ No newline at end of file
+    writer.flush()
+}
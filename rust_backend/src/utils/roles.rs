@@ -0,0 +1,151 @@
+use std::fmt;
+
+/// A port combination that suggests what a host is for
+#[derive(Debug, Clone, Copy)]
+pub struct RoleRule {
+    pub role: &'static str,
+    /// Ports that count as evidence for the role
+    pub ports: &'static [u16],
+    /// Ports that must all be open for the rule to match
+    pub required: &'static [u16],
+    /// Minimum number of evidence ports that must be open
+    pub min_matches: usize,
+}
+
+pub const DOMAIN_CONTROLLER: &str = "Domain controller";
+
+pub const ROLE_RULES: &[RoleRule] = &[
+    RoleRule {
+        role: DOMAIN_CONTROLLER,
+        ports: &[53, 88, 135, 389, 445, 464, 636, 3268, 3269],
+        required: &[88, 389],
+        min_matches: 4,
+    },
+    RoleRule {
+        role: "Mail server",
+        ports: &[25, 110, 143, 465, 587, 993, 995],
+        required: &[],
+        min_matches: 3,
+    },
+    RoleRule {
+        role: "LAMP stack",
+        ports: &[80, 443, 3306],
+        required: &[3306],
+        min_matches: 2,
+    },
+    RoleRule {
+        role: "Database server",
+        ports: &[1433, 1521, 3306, 5432, 5984, 6379, 9200, 27017],
+        required: &[],
+        min_matches: 1,
+    },
+    RoleRule {
+        role: "File server",
+        ports: &[21, 139, 445, 2049],
+        required: &[],
+        min_matches: 2,
+    },
+    RoleRule {
+        role: "Print server",
+        ports: &[515, 631, 9100],
+        required: &[],
+        min_matches: 1,
+    },
+    RoleRule {
+        role: "VoIP/SIP endpoint",
+        ports: &[5060, 5061],
+        required: &[],
+        min_matches: 1,
+    },
+    RoleRule {
+        role: "IP camera",
+        ports: &[554, 8554],
+        required: &[],
+        min_matches: 1,
+    },
+    RoleRule {
+        role: "Remote desktop host",
+        ports: &[3389, 5900],
+        required: &[],
+        min_matches: 1,
+    },
+    RoleRule {
+        role: "DNS server",
+        ports: &[53],
+        required: &[53],
+        min_matches: 1,
+    },
+    RoleRule {
+        role: "Web server",
+        ports: &[80, 443, 8080, 8443],
+        required: &[],
+        min_matches: 1,
+    },
+];
+
+/// A role inferred for a host, with the open ports that support it
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferredRole {
+    pub role: &'static str,
+    pub evidence: Vec<u16>,
+    /// Fraction of the rule's evidence ports that were open
+    pub confidence: f64,
+}
+
+impl fmt::Display for InferredRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ports: Vec<String> = self.evidence.iter().map(|p| p.to_string()).collect();
+        write!(f, "{} ({})", self.role, ports.join(", "))
+    }
+}
+
+/// Infers likely roles from a host's open ports. A role whose evidence is
+/// wholly covered by a stronger match (DNS inside a DC, MySQL inside LAMP) is
+/// dropped so each line says something new.
+pub fn infer_roles(open_ports: &[u16]) -> Vec<InferredRole> {
+    let mut matches: Vec<InferredRole> = ROLE_RULES
+        .iter()
+        .filter(|rule| rule.required.iter().all(|p| open_ports.contains(p)))
+        .filter_map(|rule| {
+            let evidence: Vec<u16> = rule
+                .ports
+                .iter()
+                .copied()
+                .filter(|p| open_ports.contains(p))
+                .collect();
+            (evidence.len() >= rule.min_matches).then(|| InferredRole {
+                role: rule.role,
+                confidence: evidence.len() as f64 / rule.ports.len() as f64,
+                evidence,
+            })
+        })
+        .collect();
+    // Most specific first: more supporting ports, then rule order
+    matches.sort_by_key(|role| std::cmp::Reverse(role.evidence.len()));
+
+    let mut roles: Vec<InferredRole> = Vec::new();
+    for candidate in matches {
+        let covered = roles.iter().any(|kept| {
+            candidate.evidence.len() < kept.evidence.len()
+                && candidate.evidence.iter().all(|p| kept.evidence.contains(p))
+        });
+        if !covered {
+            roles.push(candidate);
+        }
+    }
+    roles
+}
+
+/// Whether the open ports suggest `role`, after the same subsumption as `infer_roles`.
+pub fn has_role(open_ports: &[u16], role: &str) -> bool {
+    infer_roles(open_ports).iter().any(|r| r.role == role)
+}
+
+/// One-line summary, e.g. "Mail server (25, 110, 143, 993), Web server (80)".
+pub fn format_roles(roles: &[InferredRole]) -> String {
+    roles
+        .iter()
+        .map(|role| role.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
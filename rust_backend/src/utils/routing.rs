@@ -0,0 +1,313 @@
+use pnet::datalink;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
+
+const ROUTE_TABLE_PATH: &str = "/proc/net/route";
+const RP_FILTER_PATH: &str = "/proc/sys/net/ipv4/conf";
+/// Most hosts --auto-expand grows a single target to (a /22)
+pub const MAX_AUTO_EXPAND_HOSTS: u64 = 1022;
+
+/// A single IPv4 route from the kernel routing table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteEntry {
+    pub interface: String,
+    pub destination: Ipv4Addr,
+    pub gateway: Ipv4Addr,
+    pub mask: Ipv4Addr,
+    pub metric: u32,
+}
+
+impl RouteEntry {
+    pub fn matches(&self, target: Ipv4Addr) -> bool {
+        let mask = u32::from(self.mask);
+        u32::from(target) & mask == u32::from(self.destination) & mask
+    }
+
+    pub fn prefix_len(&self) -> u32 {
+        u32::from(self.mask).count_ones()
+    }
+}
+
+/// The interface and source address used to reach a target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EgressRoute {
+    pub interface: String,
+    pub source_ip: Option<Ipv4Addr>,
+    pub gateway: Option<Ipv4Addr>,
+}
+
+impl std::fmt::Display for EgressRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "via {}", self.interface)?;
+        if let Some(src) = self.source_ip {
+            write!(f, ", src {}", src)?;
+        }
+        if let Some(gw) = self.gateway {
+            write!(f, ", gw {}", gw)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the contents of /proc/net/route. Addresses are hex in host byte order.
+pub fn parse_route_table(contents: &str) -> Vec<RouteEntry> {
+    let parse_addr = |hex: &str| {
+        u32::from_str_radix(hex, 16)
+            .ok()
+            .map(|raw| Ipv4Addr::from(raw.to_ne_bytes()))
+    };
+
+    contents
+        .lines()
+        .skip(1) // header
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 8 {
+                return None;
+            }
+            Some(RouteEntry {
+                interface: fields[0].to_string(),
+                destination: parse_addr(fields[1])?,
+                gateway: parse_addr(fields[2])?,
+                metric: fields[6].parse().ok()?,
+                mask: parse_addr(fields[7])?,
+            })
+        })
+        .collect()
+}
+
+/// Reads the kernel IPv4 routing table.
+pub fn read_route_table() -> Result<Vec<RouteEntry>, String> {
+    let contents = fs::read_to_string(ROUTE_TABLE_PATH)
+        .map_err(|e| format!("Failed to read {ROUTE_TABLE_PATH}: {e}"))?;
+    Ok(parse_route_table(&contents))
+}
+
+/// Longest-prefix match, ties broken by lowest metric.
+pub fn lookup_route(routes: &[RouteEntry], target: Ipv4Addr) -> Option<&RouteEntry> {
+    routes
+        .iter()
+        .filter(|r| r.matches(target))
+        .max_by(|a, b| {
+            a.prefix_len()
+                .cmp(&b.prefix_len())
+                .then(b.metric.cmp(&a.metric))
+        })
+}
+
+/// Whether this machine has an interface named `name`.
+pub fn interface_exists(name: &str) -> bool {
+    datalink::interfaces().iter().any(|iface| iface.name == name)
+}
+
+/// Returns the first IPv4 address configured on the named interface.
+pub fn interface_ipv4(name: &str) -> Option<Ipv4Addr> {
+    datalink::interfaces()
+        .into_iter()
+        .find(|iface| iface.name == name)?
+        .ips
+        .iter()
+        .find_map(|net| match net.ip() {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(_) => None,
+        })
+}
+
+/// Returns the name of the interface that owns `ip`, if any.
+pub fn interface_for_ip(ip: IpAddr) -> Option<String> {
+    datalink::interfaces()
+        .into_iter()
+        .find(|iface| iface.ips.iter().any(|net| net.ip() == ip))
+        .map(|iface| iface.name)
+}
+
+/// Picks the egress interface and source address for `target` from the routing
+/// table, or validates and uses `source_override` (`--source-ip`) when given.
+pub fn select_source(
+    target: Ipv4Addr,
+    source_override: Option<Ipv4Addr>,
+) -> Result<EgressRoute, String> {
+    if let Some(source) = source_override {
+        let interface = interface_for_ip(IpAddr::V4(source))
+            .ok_or_else(|| format!("Source IP {source} is not assigned to any local interface"))?;
+        return Ok(EgressRoute {
+            interface,
+            source_ip: Some(source),
+            gateway: None,
+        });
+    }
+
+    if target.is_loopback() {
+        return Ok(EgressRoute {
+            interface: interface_for_ip(IpAddr::V4(Ipv4Addr::LOCALHOST)).unwrap_or_else(|| "lo".to_string()),
+            source_ip: Some(Ipv4Addr::LOCALHOST),
+            gateway: None,
+        });
+    }
+
+    let routes = read_route_table()?;
+    let route = lookup_route(&routes, target).ok_or_else(|| format!("No route to {target}"))?;
+    Ok(EgressRoute {
+        interface: route.interface.clone(),
+        source_ip: interface_ipv4(&route.interface),
+        gateway: (!route.gateway.is_unspecified()).then_some(route.gateway),
+    })
+}
+
+/// The directly attached subnet a target sits in, i.e. its broadcast domain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnLinkSubnet {
+    pub interface: String,
+    pub network: Ipv4Addr,
+    pub prefix_len: u32,
+    /// Default gateway reached through the same interface, if any
+    pub gateway: Option<Ipv4Addr>,
+}
+
+impl OnLinkSubnet {
+    /// The subnet in --ip syntax, e.g. "192.168.1.0/24".
+    pub fn cidr(&self) -> String {
+        format!("{}/{}", self.network, self.prefix_len)
+    }
+
+    /// Usable host addresses, excluding network and broadcast for /30 and larger.
+    pub fn host_count(&self) -> u64 {
+        let size = 1u64 << (32 - self.prefix_len);
+        if size > 2 { size - 2 } else { size }
+    }
+
+    /// The block of this subnet around `target` with at most `max_hosts`
+    /// usable addresses; the subnet itself when it is small enough.
+    pub fn around(&self, target: Ipv4Addr, max_hosts: u64) -> OnLinkSubnet {
+        let mut prefix_len = self.prefix_len;
+        while prefix_len < 30 && (1u64 << (32 - prefix_len)) - 2 > max_hosts {
+            prefix_len += 1;
+        }
+        let mask = u32::MAX.checked_shl(32 - prefix_len).unwrap_or(0);
+        let network = Ipv4Addr::from(u32::from(target) & mask);
+        OnLinkSubnet {
+            interface: self.interface.clone(),
+            network,
+            prefix_len,
+            gateway: self
+                .gateway
+                .filter(|gw| u32::from(*gw) & mask == u32::from(network)),
+        }
+    }
+}
+
+impl std::fmt::Display for OnLinkSubnet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} on {} ({} hosts",
+            self.cidr(),
+            self.interface,
+            self.host_count()
+        )?;
+        if let Some(gw) = self.gateway {
+            write!(f, ", gateway {}", gw)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// The on-link subnet containing `target`: the most specific route to it
+/// that needs no gateway. `None` for targets reached through a router or
+/// covered only by a host route.
+pub fn on_link_subnet(routes: &[RouteEntry], target: Ipv4Addr) -> Option<OnLinkSubnet> {
+    let route = routes
+        .iter()
+        .filter(|r| r.matches(target) && r.gateway.is_unspecified() && r.prefix_len() > 0)
+        .max_by_key(|r| r.prefix_len())?;
+    if route.prefix_len() >= 32 {
+        return None;
+    }
+    let gateway = routes
+        .iter()
+        .filter(|r| r.interface == route.interface && r.prefix_len() == 0)
+        .min_by_key(|r| r.metric)
+        .map(|r| r.gateway)
+        .filter(|gw| !gw.is_unspecified() && route.matches(*gw));
+    Some(OnLinkSubnet {
+        interface: route.interface.clone(),
+        network: Ipv4Addr::from(u32::from(target) & u32::from(route.mask)),
+        prefix_len: route.prefix_len(),
+        gateway,
+    })
+}
+
+/// `on_link_subnet` against the kernel routing table.
+pub fn local_subnet(target: Ipv4Addr) -> Option<OnLinkSubnet> {
+    on_link_subnet(&read_route_table().ok()?, target)
+}
+
+/// Probes to a target leave through one interface while its replies, addressed
+/// to the probe source, come back on another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReversePathMismatch {
+    pub target: Ipv4Addr,
+    pub source: Ipv4Addr,
+    pub egress: String,
+    pub ingress: String,
+    /// Reverse path filter on the ingress interface: 1 (strict) drops the replies
+    pub rp_filter: Option<u8>,
+}
+
+impl std::fmt::Display for ReversePathMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "probes to {} leave via {} but replies to {} arrive on {}",
+            self.target, self.egress, self.source, self.ingress
+        )?;
+        if self.rp_filter == Some(1) {
+            write!(f, " (strict rp_filter drops them)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the route to `target` with `ingress`, the interface owning the
+/// probe source address.
+pub fn reverse_path_mismatch(
+    routes: &[RouteEntry],
+    target: Ipv4Addr,
+    source: Ipv4Addr,
+    ingress: &str,
+) -> Option<ReversePathMismatch> {
+    let egress = &lookup_route(routes, target)?.interface;
+    (egress != ingress).then(|| ReversePathMismatch {
+        target,
+        source,
+        egress: egress.clone(),
+        ingress: ingress.to_string(),
+        rp_filter: None,
+    })
+}
+
+/// Reverse path filter mode of `interface`; the kernel applies the higher of
+/// the interface and "all" settings.
+pub fn rp_filter(interface: &str) -> Option<u8> {
+    let read = |name: &str| {
+        fs::read_to_string(format!("{RP_FILTER_PATH}/{name}/rp_filter"))
+            .ok()?
+            .trim()
+            .parse::<u8>()
+            .ok()
+    };
+    [read("all"), read(interface)].into_iter().flatten().max()
+}
+
+/// Checks whether raw probes from `source` to `target` travel an asymmetric
+/// path, in which case their replies may never reach the scanner.
+pub fn check_reverse_path(target: Ipv4Addr, source: Ipv4Addr) -> Option<ReversePathMismatch> {
+    if target.is_loopback() {
+        return None;
+    }
+    let ingress = interface_for_ip(IpAddr::V4(source))?;
+    let routes = read_route_table().ok()?;
+    let mut mismatch = reverse_path_mismatch(&routes, target, source, &ingress)?;
+    mismatch.rp_filter = rp_filter(&mismatch.ingress);
+    Some(mismatch)
+}
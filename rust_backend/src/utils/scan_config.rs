@@ -0,0 +1,138 @@
+use crate::utils::progress::{ProgressEvent, ScanProgress};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Longest wait between two attempts, however many retries came before
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Speed vs reliability settings shared by the scanners (`--timeout`,
+/// `--concurrency`, `--retries`, `--retry-delay`). Unset fields keep each
+/// scanner's default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanConfig {
+    /// How long to wait for a connection or reply
+    pub timeout: Option<Duration>,
+    /// Probes in flight at once
+    pub concurrency: Option<usize>,
+    /// Extra attempts for probes that timed out
+    pub retries: Option<usize>,
+    /// Wait before the first retry, doubled before each further one
+    pub retry_delay: Option<Duration>,
+    /// Timeouts of single hosts, e.g. derived from their measured round trip
+    /// (`--adaptive-timing`); never longer than `timeout`
+    pub host_timeouts: Option<Arc<BTreeMap<IpAddr, Duration>>>,
+    /// Receives an event per finished probe
+    pub progress: Option<ScanProgress>,
+    /// Where to save responses no detector recognized (`--collect-unknowns`)
+    pub collect_unknowns: Option<PathBuf>,
+    /// Interface every probe socket is bound to (SO_BINDTODEVICE), so a
+    /// segment's probes leave through its own interface
+    pub interface: Option<String>,
+}
+
+impl ScanConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn timeout_or(&self, default: Duration) -> Duration {
+        self.timeout.unwrap_or(default)
+    }
+
+    /// Timeout for probes of `ip`: its own one if it has one, capped by the
+    /// general timeout.
+    pub fn timeout_for(&self, ip: IpAddr, default: Duration) -> Duration {
+        let general = self.timeout_or(default);
+        self.host_timeouts
+            .as_ref()
+            .and_then(|timeouts| timeouts.get(&ip))
+            .map_or(general, |timeout| (*timeout).min(general))
+    }
+
+    /// The same settings, with per-host timeouts.
+    pub fn with_host_timeouts(&self, timeouts: BTreeMap<IpAddr, Duration>) -> Self {
+        Self {
+            host_timeouts: Some(Arc::new(timeouts)),
+            ..self.clone()
+        }
+    }
+
+    pub fn concurrency_or(&self, default: usize) -> usize {
+        self.concurrency.unwrap_or(default).max(1)
+    }
+
+    pub fn retries_or(&self, default: usize) -> usize {
+        self.retries.unwrap_or(default)
+    }
+
+    /// How long to wait before retry number `retry` (1 for the first):
+    /// `retry_delay`, doubled for each retry after the first, at most
+    /// `MAX_RETRY_DELAY`. Zero without a `retry_delay`.
+    pub fn retry_backoff(&self, retry: usize) -> Duration {
+        let Some(delay) = self.retry_delay else {
+            return Duration::ZERO;
+        };
+        let factor = 1u32 << retry.saturating_sub(1).min(16);
+        delay.saturating_mul(factor).min(MAX_RETRY_DELAY)
+    }
+
+    /// Sleeps out the backoff before attempt number `attempt` (0 for the
+    /// first, which does not wait).
+    pub async fn wait_before_attempt(&self, attempt: usize) {
+        let delay = self.retry_backoff(attempt);
+        if attempt > 0 && !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// The same settings with `concurrency` when none was given, for the
+    /// port probes; other scanners keep their own caps.
+    pub fn with_default_concurrency(&self, concurrency: Option<usize>) -> Self {
+        Self {
+            concurrency: self.concurrency.or(concurrency),
+            ..self.clone()
+        }
+    }
+
+    /// The same settings, reporting progress to `progress`.
+    pub fn with_progress(&self, progress: ScanProgress) -> Self {
+        Self {
+            progress: Some(progress),
+            ..self.clone()
+        }
+    }
+
+    /// Passes `event` on if anyone is listening.
+    pub fn report(&self, event: ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            progress.report(event);
+        }
+    }
+}
+
+/// Parses a timeout such as "500ms", "2s", "1.5s" or "1m"; bare numbers are seconds.
+pub fn parse_timeout(text: &str) -> Result<Duration, String> {
+    let text = text.trim().to_ascii_lowercase();
+    let (number, scale) = if let Some(ms) = text.strip_suffix("ms") {
+        (ms, 0.001)
+    } else if let Some(s) = text.strip_suffix('s') {
+        (s, 1.0)
+    } else if let Some(m) = text.strip_suffix('m') {
+        (m, 60.0)
+    } else {
+        (text.as_str(), 1.0)
+    };
+    let value: f64 = number.trim().parse().map_err(|_| {
+        format!(
+            "Invalid timeout \"{}\"; expected e.g. 500ms, 2s or 1m",
+            text
+        )
+    })?;
+    if !value.is_finite() || value <= 0.0 {
+        return Err(format!("Timeout must be positive, got \"{}\"", text));
+    }
+    Ok(Duration::from_secs_f64(value * scale))
+}
@@ -0,0 +1,202 @@
+use crate::report::{HostReport, PortsReport, ScanReport};
+use crate::utils::{rdns, redact};
+use colored::*;
+use std::net::IpAddr;
+
+/// A port that opened or closed between two runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortChange {
+    pub ip: IpAddr,
+    /// "tcp" or "udp"
+    pub transport: &'static str,
+    pub port: u16,
+    /// Service detected on the port in the run where it was open
+    pub service: Option<String>,
+}
+
+/// A port that answered as a different service, or with a different banner
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceChange {
+    pub ip: IpAddr,
+    pub port: u16,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// What changed from one scan run to a later one
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanDiff {
+    /// Hosts that answered discovery only in the later run
+    pub new_hosts: Vec<IpAddr>,
+    /// Hosts that answered discovery only in the earlier run
+    pub gone_hosts: Vec<IpAddr>,
+    pub opened: Vec<PortChange>,
+    pub closed: Vec<PortChange>,
+    pub changed_services: Vec<ServiceChange>,
+}
+
+fn service_on(host: &HostReport, port: u16) -> Option<String> {
+    host.services
+        .iter()
+        .find(|service| service.port == port)
+        .and_then(|service| service.service.clone())
+}
+
+fn is_alive(report: &ScanReport, ip: IpAddr) -> bool {
+    report.host(ip).is_some_and(|host| host.alive)
+}
+
+/// Whether the run probed `port`; runs recorded without their scanned ports
+/// are assumed to have probed every port.
+fn was_scanned(ports: &PortsReport, port: u16) -> bool {
+    ports.scanned.is_empty() || ports.scanned.binary_search(&port).is_ok()
+}
+
+/// Compares two runs. Ports are only compared on hosts both runs scanned
+/// with the same transport, and only where both runs probed the port, so a
+/// rescan of other ports does not look like closed ones; every open port of
+/// a host new in `after` counts as opened.
+pub fn diff(before: &ScanReport, after: &ScanReport) -> ScanDiff {
+    let mut changes = ScanDiff::default();
+    for host in after.hosts.iter().filter(|host| host.alive) {
+        if !is_alive(before, host.ip) {
+            changes.new_hosts.push(host.ip);
+        }
+    }
+    for host in before.hosts.iter().filter(|host| host.alive) {
+        if !is_alive(after, host.ip) {
+            changes.gone_hosts.push(host.ip);
+        }
+    }
+
+    let empty = PortsReport::default();
+    for new in &after.hosts {
+        let old = before.host(new.ip);
+        let is_new = changes.new_hosts.contains(&new.ip);
+        for (transport, new_ports, old_ports) in [
+            ("tcp", &new.tcp, old.and_then(|old| old.tcp.as_ref())),
+            ("udp", &new.udp, old.and_then(|old| old.udp.as_ref())),
+        ] {
+            let Some(new_ports) = new_ports else {
+                continue;
+            };
+            let old_ports = match old_ports {
+                Some(ports) => ports,
+                None if is_new => &empty,
+                None => continue,
+            };
+            for &port in new_ports
+                .open
+                .iter()
+                .filter(|p| !old_ports.open.contains(p) && was_scanned(old_ports, **p))
+            {
+                changes.opened.push(PortChange {
+                    ip: new.ip,
+                    transport,
+                    port,
+                    service: service_on(new, port),
+                });
+            }
+            if let Some(old) = old {
+                for &port in old_ports
+                    .open
+                    .iter()
+                    .filter(|p| !new_ports.open.contains(p) && was_scanned(new_ports, **p))
+                {
+                    changes.closed.push(PortChange {
+                        ip: new.ip,
+                        transport,
+                        port,
+                        service: service_on(old, port),
+                    });
+                }
+            }
+        }
+
+        let Some(old) = old else {
+            continue;
+        };
+        for service in &new.services {
+            let Some(previous) = old.services.iter().find(|s| s.port == service.port) else {
+                continue;
+            };
+            // A probe that failed either time says nothing about the service
+            if service.error.is_some() || previous.error.is_some() {
+                continue;
+            }
+            if service.service != previous.service {
+                changes.changed_services.push(ServiceChange {
+                    ip: new.ip,
+                    port: service.port,
+                    before: previous.service.clone(),
+                    after: service.service.clone(),
+                });
+            }
+        }
+    }
+    changes
+}
+
+impl ScanDiff {
+    pub fn is_empty(&self) -> bool {
+        self.new_hosts.is_empty()
+            && self.gone_hosts.is_empty()
+            && self.opened.is_empty()
+            && self.closed.is_empty()
+            && self.changed_services.is_empty()
+    }
+
+    pub fn print(&self) {
+        if self.is_empty() {
+            println!("{}", "No changes between the two runs.".green());
+            return;
+        }
+        for ip in &self.new_hosts {
+            println!("  {} {}", "+ host".green().bold(), rdns::label(*ip));
+        }
+        for ip in &self.gone_hosts {
+            println!("  {} {}", "- host".red().bold(), rdns::label(*ip));
+        }
+        for (sign, changes) in [
+            ("+".green().bold(), &self.opened),
+            ("-".red().bold(), &self.closed),
+        ] {
+            for change in changes {
+                println!(
+                    "  {} {}:{}/{} {}",
+                    sign,
+                    redact::ip(change.ip),
+                    change.port,
+                    change.transport,
+                    change.service.as_deref().unwrap_or("").dimmed()
+                );
+            }
+        }
+        for change in &self.changed_services {
+            println!(
+                "  {} {}:{} {} -> {}",
+                "~".yellow().bold(),
+                redact::ip(change.ip),
+                change.port,
+                change
+                    .before
+                    .as_deref()
+                    .unwrap_or("Unknown Service")
+                    .dimmed(),
+                change.after.as_deref().unwrap_or("Unknown Service")
+            );
+        }
+        println!(
+            "{}",
+            format!(
+                "{} new host(s), {} gone, {} port(s) opened, {} closed, {} service(s) changed",
+                self.new_hosts.len(),
+                self.gone_hosts.len(),
+                self.opened.len(),
+                self.closed.len(),
+                self.changed_services.len()
+            )
+            .bold()
+        );
+    }
+}
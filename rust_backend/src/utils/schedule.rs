@@ -0,0 +1,197 @@
+use crate::utils::i18n::Msg;
+use crate::utils::ratelimit::RateLimiter;
+use crate::utils::timezone::Zone;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Timelike, Utc};
+use colored::*;
+use std::fmt;
+
+const DAY_NAMES: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+/// How far ahead `Schedule::next_change` looks for a window edge
+const LOOKAHEAD: Duration = Duration::days(8);
+/// Longest the enforcer sleeps before re-reading the clock (e.g. after a suspend)
+const RECHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// A recurring maintenance window such as "Mon-Fri 22:00-05:00 Europe/Berlin".
+/// A window whose end is not after its start runs past midnight and belongs
+/// to the day it opens on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanWindow {
+    /// Opening days, Monday first
+    days: [bool; 7],
+    start: NaiveTime,
+    end: NaiveTime,
+    zone: Zone,
+    spec: String,
+}
+
+impl ScanWindow {
+    /// Parses "[DAYS] HH:MM-HH:MM [ZONE]". DAYS is a list of names and ranges
+    /// ("Mon-Fri", "Sat,Sun", "daily"); ZONE is an IANA name, "UTC", an offset
+    /// or "local" (the default).
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let tokens: Vec<&str> = spec.split_whitespace().collect();
+        let (days, rest) = match tokens.split_first() {
+            Some((first, rest)) if !first.contains(':') => (parse_days(first)?, rest),
+            _ => ([true; 7], tokens.as_slice()),
+        };
+        let (times, zone) = match rest {
+            [times] => (*times, Zone::Local),
+            [times, zone] => (*times, Zone::parse(zone)?),
+            _ => {
+                return Err(format!(
+                    "Invalid window \"{}\"; expected e.g. \"Mon-Fri 22:00-05:00 Europe/Berlin\"",
+                    spec
+                ));
+            }
+        };
+        let (start, end) = times
+            .split_once('-')
+            .ok_or_else(|| format!("Invalid window times \"{}\"; expected HH:MM-HH:MM", times))?;
+        Ok(Self {
+            days,
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+            zone,
+            spec: spec.trim().to_string(),
+        })
+    }
+
+    /// Whether the window is open at `now`.
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        let local = self.zone.to_local(now);
+        let today = local.weekday().num_days_from_monday() as usize;
+        let yesterday = (today + 6) % 7;
+        let time = local.time();
+        if self.start < self.end {
+            self.days[today] && self.start <= time && time < self.end
+        } else {
+            (self.days[today] && time >= self.start) || (self.days[yesterday] && time < self.end)
+        }
+    }
+}
+
+impl fmt::Display for ScanWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.spec)
+    }
+}
+
+/// The windows recurring scans may run in; scans are allowed whenever any
+/// window is open, and at any time when there are none.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schedule {
+    windows: Vec<ScanWindow>,
+}
+
+impl Schedule {
+    pub fn new(windows: Vec<ScanWindow>) -> Self {
+        Self { windows }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    pub fn windows(&self) -> &[ScanWindow] {
+        &self.windows
+    }
+
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        self.windows.is_empty() || self.windows.iter().any(|window| window.is_open(now))
+    }
+
+    /// The next minute at which the schedule opens or closes, or `None` if it
+    /// stays as it is for the coming week.
+    pub fn next_change(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let open = self.is_open(now);
+        let first_minute = now.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        (0..LOOKAHEAD.num_minutes())
+            .map(|minute| first_minute + Duration::minutes(minute))
+            .find(|&at| self.is_open(at) != open)
+    }
+}
+
+/// Pauses `limiter` while the schedule is closed and resumes it when a window
+/// opens; monitor mode stops the pass in flight when it pauses. Runs until
+/// the task is dropped.
+pub async fn enforce(schedule: Schedule, limiter: &RateLimiter) {
+    loop {
+        let now = Utc::now();
+        let open = schedule.is_open(now);
+        let next = schedule.next_change(now);
+        if limiter.is_paused() == open {
+            limiter.set_paused(!open);
+            if open {
+                println!("{}", Msg::WindowOpen.text().green());
+            } else {
+                println!(
+                    "{}",
+                    Msg::WindowClosed
+                        .with(&[("next", &describe_next(next))])
+                        .yellow()
+                );
+            }
+        }
+        let wait = next
+            .and_then(|at| (at - now).to_std().ok())
+            .map_or(RECHECK_INTERVAL, |wait| wait.min(RECHECK_INTERVAL));
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// "paused until Mon 22:00" in the scanner's local time, for status lines.
+pub fn describe_next(next: Option<DateTime<Utc>>) -> String {
+    match next {
+        Some(at) => Msg::PausedUntil.with(&[(
+            "time",
+            &at.with_timezone(&Local).format("%a %Y-%m-%d %H:%M"),
+        )]),
+        None => Msg::NoWindowThisWeek.text().into_owned(),
+    }
+}
+
+/// "Mon-Fri", "Sat,Sun", "Mon,Wed-Fri" or "daily" as opening days.
+fn parse_days(text: &str) -> Result<[bool; 7], String> {
+    if text.eq_ignore_ascii_case("daily") || text == "*" {
+        return Ok([true; 7]);
+    }
+    let day = |name: &str| {
+        let name = name.to_ascii_lowercase();
+        DAY_NAMES
+            .iter()
+            .position(|day| name.len() >= 3 && day.starts_with(&name))
+            .ok_or_else(|| format!("Unknown day \"{}\" in window", name))
+    };
+    let mut days = [false; 7];
+    for part in text.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (day(first)?, day(last)?);
+                // ranges may wrap past Sunday ("Fri-Mon")
+                let len = (last + 7 - first) % 7 + 1;
+                for offset in 0..len {
+                    days[(first + offset) % 7] = true;
+                }
+            }
+            None => days[day(part)?] = true,
+        }
+    }
+    Ok(days)
+}
+
+fn parse_time(text: &str) -> Result<NaiveTime, String> {
+    let text = text.trim();
+    if text == "24:00" {
+        return Ok(NaiveTime::MIN);
+    }
+    NaiveTime::parse_from_str(text, "%H:%M")
+        .map_err(|_| format!("Invalid time \"{}\" in window; expected HH:MM", text))
+}
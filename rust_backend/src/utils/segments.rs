@@ -0,0 +1,98 @@
+use std::fmt;
+
+/// A network segment the scanner reaches through one interface (or VRF
+/// device), optionally with the targets that live behind it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub interface: String,
+    /// Target list in --ip syntax; `None` means the --ip targets
+    pub targets: Option<String>,
+}
+
+impl Segment {
+    /// Parses "IFACE" or "IFACE=TARGETS", e.g. "eth1=10.20.0.0/24,10.20.1.0/24".
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (interface, targets) = match spec.split_once('=') {
+            Some((interface, targets)) => (interface.trim(), Some(targets.trim())),
+            None => (spec.trim(), None),
+        };
+        if interface.is_empty() || interface.contains(char::is_whitespace) {
+            return Err(format!(
+                "Invalid interface \"{}\"; expected IFACE or IFACE=TARGETS",
+                spec
+            ));
+        }
+        if targets.is_some_and(str::is_empty) {
+            return Err(format!("No targets given for interface {}", interface));
+        }
+        Ok(Self {
+            interface: interface.to_string(),
+            targets: targets.map(String::from),
+        })
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.targets {
+            Some(targets) => write!(f, "{}={}", self.interface, targets),
+            None => write!(f, "{}", self.interface),
+        }
+    }
+}
+
+/// Pairs each segment with the targets to scan through it. Segments without
+/// their own targets take `default_targets` (--ip); only one may do so, since
+/// the same targets are not reachable over two segments.
+pub fn plan(
+    segments: &[Segment],
+    default_targets: Option<&str>,
+) -> Result<Vec<(Segment, String)>, String> {
+    let unmapped: Vec<&str> = segments
+        .iter()
+        .filter(|segment| segment.targets.is_none())
+        .map(|segment| segment.interface.as_str())
+        .collect();
+    if unmapped.len() > 1 {
+        return Err(format!(
+            "Interfaces {} have no targets; map them with --interface IFACE=TARGETS",
+            unmapped.join(", ")
+        ));
+    }
+    for (i, segment) in segments.iter().enumerate() {
+        if segments[..i]
+            .iter()
+            .any(|s| s.interface == segment.interface)
+        {
+            return Err(format!(
+                "Interface {} given more than once",
+                segment.interface
+            ));
+        }
+    }
+    segments
+        .iter()
+        .map(|segment| {
+            let targets = segment
+                .targets
+                .as_deref()
+                .or(default_targets)
+                .ok_or_else(|| {
+                    format!(
+                        "No targets for interface {}; use --ip or --interface {}=TARGETS",
+                        segment.interface, segment.interface
+                    )
+                })?;
+            Ok((segment.clone(), targets.to_string()))
+        })
+        .collect()
+}
+
+/// `file` with `interface` added before its extension, so each segment
+/// writes its own copy: "netscan_banners.csv" becomes "netscan_banners_eth1.csv".
+pub fn output_path(file: &str, interface: &str) -> String {
+    match file.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}_{}.{}", stem, interface, extension),
+        None => format!("{}_{}", file, interface),
+    }
+}
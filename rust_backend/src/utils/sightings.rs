@@ -0,0 +1,135 @@
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::Path;
+
+const STATE_HEADER: [&str; 5] = ["ip", "mac", "vendor", "first_seen", "last_seen"];
+
+/// One device monitor mode has seen answer discovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceSighting {
+    /// Address it answered on most recently
+    pub ip: IpAddr,
+    /// Lowercase and colon-separated, when discovery learned it
+    pub mac: Option<String>,
+    /// NIC maker from the OUI registry
+    pub vendor: Option<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl DeviceSighting {
+    /// What identifies the device across runs: its MAC, else its address.
+    pub fn key(&self) -> String {
+        device_key(self.ip, self.mac.as_deref())
+    }
+}
+
+/// `mac` (lowercased) when known, else `ip`.
+pub fn device_key(ip: IpAddr, mac: Option<&str>) -> String {
+    mac.map(str::to_ascii_lowercase)
+        .unwrap_or_else(|| ip.to_string())
+}
+
+/// Every device seen across monitor runs, keyed by MAC where known so a
+/// device keeps its first-seen time when its address changes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceSightings {
+    devices: BTreeMap<String, DeviceSighting>,
+}
+
+impl DeviceSightings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    pub fn get(&self, ip: IpAddr, mac: Option<&str>) -> Option<&DeviceSighting> {
+        self.devices.get(&device_key(ip, mac))
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = &DeviceSighting> {
+        self.devices.values()
+    }
+
+    /// Records that the device at `ip` (with `mac`, if known) answered at
+    /// `now` and returns its sighting.
+    pub fn record(
+        &mut self,
+        ip: IpAddr,
+        mac: Option<&str>,
+        vendor: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> &DeviceSighting {
+        let sighting = self
+            .devices
+            .entry(device_key(ip, mac))
+            .or_insert_with(|| DeviceSighting {
+                ip,
+                mac: mac.map(str::to_ascii_lowercase),
+                vendor: None,
+                first_seen: now,
+                last_seen: now,
+            });
+        sighting.ip = ip;
+        sighting.last_seen = now;
+        if let Some(vendor) = vendor {
+            sighting.vendor = Some(vendor.to_string());
+        }
+        sighting
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut writer = csv::Writer::from_path(path)
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+        writer
+            .write_record(STATE_HEADER)
+            .map_err(|e| e.to_string())?;
+        for device in self.devices.values() {
+            writer
+                .write_record([
+                    device.ip.to_string(),
+                    device.mac.clone().unwrap_or_default(),
+                    device.vendor.clone().unwrap_or_default(),
+                    device.first_seen.to_rfc3339(),
+                    device.last_seen.to_rfc3339(),
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+        writer.flush().map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut reader = csv::Reader::from_path(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let mut sightings = Self::new();
+        for (line, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| e.to_string())?;
+            let bad_row = || format!("{}: malformed row {}", path.display(), line + 2);
+            let field = |i: usize| record.get(i).ok_or_else(bad_row);
+            let optional =
+                |i: usize| field(i).map(|value| Some(value.to_string()).filter(|v| !v.is_empty()));
+            let time = |i: usize| {
+                DateTime::parse_from_rfc3339(field(i)?)
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|_| bad_row())
+            };
+            let device = DeviceSighting {
+                ip: field(0)?.parse().map_err(|_| bad_row())?,
+                mac: optional(1)?,
+                vendor: optional(2)?,
+                first_seen: time(3)?,
+                last_seen: time(4)?,
+            };
+            sightings.devices.insert(device.key(), device);
+        }
+        Ok(sightings)
+    }
+}
@@ -0,0 +1,205 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::sync::Mutex;
+use tokio::net::{TcpSocket, UdpSocket};
+
+/// Opens the sockets a scanner probes with. Scanners take one of these so
+/// tests can substitute [`MockSockets`] for the real network.
+pub trait SocketFactory: Send + Sync + 'static {
+    /// Completes a TCP handshake with `addr`, optionally from `source_ip`.
+    fn tcp_connect(
+        &self,
+        addr: SocketAddr,
+        source_ip: Option<IpAddr>,
+    ) -> impl Future<Output = io::Result<()>> + Send;
+
+    /// Sends `payload` to `addr` over UDP and waits for a reply, returning its length.
+    fn udp_exchange(
+        &self,
+        addr: SocketAddr,
+        source_ip: Option<IpAddr>,
+        payload: &[u8],
+    ) -> impl Future<Output = io::Result<usize>> + Send;
+}
+
+/// Real sockets from the operating system
+#[derive(Debug, Clone, Default)]
+pub struct SystemSockets {
+    /// Interface the sockets are bound to; the routing table decides if `None`
+    device: Option<String>,
+}
+
+impl SystemSockets {
+    /// Sockets bound to `device` (SO_BINDTODEVICE, needs CAP_NET_RAW).
+    pub fn bound_to(device: Option<String>) -> Self {
+        Self { device }
+    }
+}
+
+fn unspecified_for(addr: &SocketAddr) -> IpAddr {
+    match addr {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    }
+}
+
+/// Wildcard address with an ephemeral port, in the same family as `target`,
+/// for binding a socket that will talk to it.
+pub fn local_bind_addr(target: IpAddr) -> SocketAddr {
+    SocketAddr::new(unspecified_for(&SocketAddr::new(target, 0)), 0)
+}
+
+/// Interface index of each link-local address found on the link, since the
+/// address alone does not say which link it is on
+static LINK_SCOPES: Lazy<Mutex<HashMap<Ipv6Addr, u32>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Remembers that link-local `ip` is on the interface with index `scope_id`.
+pub fn register_link_scope(ip: Ipv6Addr, scope_id: u32) {
+    LINK_SCOPES.lock().unwrap().insert(ip, scope_id);
+}
+
+/// Address to reach `port` of `ip` at; link-local addresses carry the scope
+/// of the interface they were found on.
+pub fn socket_addr(ip: IpAddr, port: u16) -> SocketAddr {
+    match ip {
+        IpAddr::V6(v6) if v6.is_unicast_link_local() => {
+            let scope_id = LINK_SCOPES.lock().unwrap().get(&v6).copied().unwrap_or(0);
+            SocketAddr::V6(SocketAddrV6::new(v6, port, 0, scope_id))
+        }
+        _ => SocketAddr::new(ip, port),
+    }
+}
+
+/// `ip` as the host part of a URI or Host header; IPv6 literals are
+/// bracketed (RFC 3986 § 3.2.2).
+pub fn uri_host(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => ip.to_string(),
+        IpAddr::V6(ip) => format!("[{}]", ip),
+    }
+}
+
+impl SocketFactory for SystemSockets {
+    async fn tcp_connect(&self, addr: SocketAddr, source_ip: Option<IpAddr>) -> io::Result<()> {
+        let socket = match addr {
+            SocketAddr::V4(_) => TcpSocket::new_v4()?,
+            SocketAddr::V6(_) => TcpSocket::new_v6()?,
+        };
+        if let Some(device) = &self.device {
+            socket.bind_device(Some(device.as_bytes()))?;
+        }
+        if let Some(source) = source_ip {
+            socket.bind(SocketAddr::new(source, 0))?;
+        }
+        socket.connect(addr).await.map(|_| ())
+    }
+
+    fn udp_exchange(
+        &self,
+        addr: SocketAddr,
+        source_ip: Option<IpAddr>,
+        payload: &[u8],
+    ) -> impl Future<Output = io::Result<usize>> + Send {
+        let payload = payload.to_vec();
+        let device = self.device.clone();
+        async move {
+            let bind_ip = source_ip.unwrap_or_else(|| unspecified_for(&addr));
+            let socket = UdpSocket::bind(SocketAddr::new(bind_ip, 0)).await?;
+            if let Some(device) = device {
+                socket.bind_device(Some(device.as_bytes()))?;
+            }
+            socket.connect(addr).await?;
+            socket.send(&payload).await?;
+
+            // A connected UDP socket surfaces ICMP port-unreachable as ECONNREFUSED
+            let mut buf = [0u8; 1024];
+            socket.recv(&mut buf).await
+        }
+    }
+}
+
+/// What a [`MockSockets`] endpoint does when probed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockResponse {
+    /// TCP accepts the connection; UDP replies with this many bytes.
+    Open(usize),
+    /// Fails immediately with the given OS error (e.g. 111 for ECONNREFUSED).
+    Fail(i32),
+    /// Never answers, so the caller's timeout fires.
+    Silent,
+}
+
+/// Scripted sockets for deterministic scanner tests. Unscripted endpoints
+/// use the default response; every probe is recorded in order.
+#[derive(Debug)]
+pub struct MockSockets {
+    responses: HashMap<SocketAddr, MockResponse>,
+    default: MockResponse,
+    probes: Mutex<Vec<SocketAddr>>,
+}
+
+impl MockSockets {
+    /// A network where every port refuses connections.
+    pub fn new() -> Self {
+        Self::with_default(MockResponse::Fail(111))
+    }
+
+    pub fn with_default(default: MockResponse) -> Self {
+        Self {
+            responses: HashMap::new(),
+            default,
+            probes: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn respond(mut self, addr: SocketAddr, response: MockResponse) -> Self {
+        self.responses.insert(addr, response);
+        self
+    }
+
+    /// Every address probed so far, in the order the probes were made.
+    pub fn probes(&self) -> Vec<SocketAddr> {
+        self.probes.lock().unwrap().clone()
+    }
+
+    fn respond_to(&self, addr: SocketAddr) -> impl Future<Output = io::Result<usize>> + Send {
+        self.probes.lock().unwrap().push(addr);
+        let response = self.responses.get(&addr).copied().unwrap_or(self.default);
+        async move {
+            match response {
+                MockResponse::Open(len) => Ok(len),
+                MockResponse::Fail(code) => Err(io::Error::from_raw_os_error(code)),
+                MockResponse::Silent => std::future::pending().await,
+            }
+        }
+    }
+}
+
+impl Default for MockSockets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SocketFactory for MockSockets {
+    fn tcp_connect(
+        &self,
+        addr: SocketAddr,
+        _source_ip: Option<IpAddr>,
+    ) -> impl Future<Output = io::Result<()>> + Send {
+        let reply = self.respond_to(addr);
+        async move { reply.await.map(|_| ()) }
+    }
+
+    fn udp_exchange(
+        &self,
+        addr: SocketAddr,
+        _source_ip: Option<IpAddr>,
+        _payload: &[u8],
+    ) -> impl Future<Output = io::Result<usize>> + Send {
+        self.respond_to(addr)
+    }
+}
@@ -0,0 +1,669 @@
+use crate::report::{
+    FingerprintReport, HostReport, PortsReport, ProtocolFailure, ScanReport, ServiceReport,
+};
+use crate::scanners::service_detection::{self, DetectError, Protocol};
+use crate::scanners::udpscan::UdpPortState;
+use crate::utils::prettyprint;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started TEXT NOT NULL,
+    finished TEXT NOT NULL,
+    targets TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS hosts (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    ip TEXT NOT NULL,
+    hostname TEXT,
+    alive INTEGER NOT NULL,
+    fingerprinted INTEGER NOT NULL,
+    os TEXT,
+    vendor TEXT,
+    model TEXT,
+    serial TEXT,
+    details TEXT,
+    tcp_scanned INTEGER NOT NULL,
+    udp_scanned INTEGER NOT NULL,
+    PRIMARY KEY (run_id, ip)
+);
+CREATE TABLE IF NOT EXISTS os_guesses (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    ip TEXT NOT NULL,
+    rank INTEGER NOT NULL,
+    os TEXT NOT NULL,
+    confidence REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS open_ports (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    ip TEXT NOT NULL,
+    transport TEXT NOT NULL,
+    port INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS scanned_ports (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    ip TEXT NOT NULL,
+    transport TEXT NOT NULL,
+    ports TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS udp_states (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    ip TEXT NOT NULL,
+    state TEXT NOT NULL,
+    ports TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS port_errors (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    ip TEXT NOT NULL,
+    transport TEXT NOT NULL,
+    category TEXT NOT NULL,
+    count INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS services (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    ip TEXT NOT NULL,
+    port INTEGER NOT NULL,
+    service TEXT,
+    error TEXT,
+    protocol_failures TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS retried_probes (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    ip TEXT NOT NULL,
+    transport TEXT NOT NULL,
+    port INTEGER,
+    attempts INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS host_notes (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    ip TEXT NOT NULL,
+    note TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS runs_by_targets ON runs(targets, started);
+";
+
+/// One recorded scan run, without its results
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunSummary {
+    pub id: i64,
+    pub started: DateTime<Utc>,
+    pub finished: DateTime<Utc>,
+    /// Targets as given on the command line, e.g. "10.0.0.0/24"
+    pub targets: String,
+    pub host_count: usize,
+}
+
+/// SQLite database of past scan runs (`--history`)
+pub struct ScanHistory {
+    conn: Connection,
+}
+
+fn db_error(e: rusqlite::Error) -> String {
+    format!("Scan history database error: {}", e)
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| format!("Invalid timestamp \"{}\" in scan history", value))
+}
+
+fn parse_ip(value: &str) -> Result<IpAddr, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid address \"{}\" in scan history", value))
+}
+
+impl ScanHistory {
+    /// Opens the database at `path`, creating it and its tables if needed.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn = Connection::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+        Ok(Self { conn })
+    }
+
+    /// Stores `report` as a new run and returns its id.
+    pub fn record(
+        &mut self,
+        started: DateTime<Utc>,
+        finished: DateTime<Utc>,
+        targets: &str,
+        report: &ScanReport,
+    ) -> Result<i64, String> {
+        let tx = self.conn.transaction().map_err(db_error)?;
+        tx.execute(
+            "INSERT INTO runs (started, finished, targets) VALUES (?1, ?2, ?3)",
+            params![started.to_rfc3339(), finished.to_rfc3339(), targets],
+        )
+        .map_err(db_error)?;
+        let run_id = tx.last_insert_rowid();
+        for host in &report.hosts {
+            let ip = host.ip.to_string();
+            let fingerprint = host.fingerprint.as_ref();
+            tx.execute(
+                "INSERT INTO hosts (run_id, ip, hostname, alive, fingerprinted, os, vendor, model,
+                 serial, details, tcp_scanned, udp_scanned)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    run_id,
+                    ip,
+                    host.hostname,
+                    host.alive,
+                    fingerprint.is_some(),
+                    fingerprint.and_then(|fp| fp.os.as_deref()),
+                    fingerprint.and_then(|fp| fp.vendor.as_deref()),
+                    fingerprint.and_then(|fp| fp.model.as_deref()),
+                    fingerprint.and_then(|fp| fp.serial.as_deref()),
+                    fingerprint.and_then(|fp| fp.details.as_deref()),
+                    host.tcp.is_some(),
+                    host.udp.is_some(),
+                ],
+            )
+            .map_err(db_error)?;
+            for (rank, (os, confidence)) in fingerprint
+                .map_or(&[][..], |fp| &fp.os_guesses[..])
+                .iter()
+                .enumerate()
+            {
+                tx.execute(
+                    "INSERT INTO os_guesses (run_id, ip, rank, os, confidence)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![run_id, ip, rank as i64, os, confidence],
+                )
+                .map_err(db_error)?;
+            }
+            if let Some(attempts) = host.ping_attempts {
+                tx.execute(
+                    "INSERT INTO retried_probes (run_id, ip, transport, port, attempts)
+                     VALUES (?1, ?2, 'icmp', NULL, ?3)",
+                    params![run_id, ip, attempts as i64],
+                )
+                .map_err(db_error)?;
+            }
+            for (transport, ports) in [("tcp", &host.tcp), ("udp", &host.udp)] {
+                let Some(ports) = ports else {
+                    continue;
+                };
+                for port in &ports.open {
+                    tx.execute(
+                        "INSERT INTO open_ports (run_id, ip, transport, port) VALUES (?1, ?2, ?3, ?4)",
+                        params![run_id, ip, transport, port],
+                    )
+                    .map_err(db_error)?;
+                }
+                if !ports.scanned.is_empty() {
+                    tx.execute(
+                        "INSERT INTO scanned_ports (run_id, ip, transport, ports)
+                         VALUES (?1, ?2, ?3, ?4)",
+                        params![
+                            run_id,
+                            ip,
+                            transport,
+                            prettyprint::format_port_ranges(&ports.scanned)
+                        ],
+                    )
+                    .map_err(db_error)?;
+                }
+                for (state, state_ports) in &ports.states {
+                    tx.execute(
+                        "INSERT INTO udp_states (run_id, ip, state, ports) VALUES (?1, ?2, ?3, ?4)",
+                        params![
+                            run_id,
+                            ip,
+                            state.label(),
+                            prettyprint::format_port_ranges(state_ports)
+                        ],
+                    )
+                    .map_err(db_error)?;
+                }
+                for (category, count) in &ports.errors {
+                    tx.execute(
+                        "INSERT INTO port_errors (run_id, ip, transport, category, count)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![run_id, ip, transport, category, *count as i64],
+                    )
+                    .map_err(db_error)?;
+                }
+                for (port, attempts) in &ports.retried {
+                    tx.execute(
+                        "INSERT INTO retried_probes (run_id, ip, transport, port, attempts)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![run_id, ip, transport, port, *attempts as i64],
+                    )
+                    .map_err(db_error)?;
+                }
+            }
+            for service in &host.services {
+                tx.execute(
+                    "INSERT INTO services (run_id, ip, port, service, error, protocol_failures)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        run_id,
+                        ip,
+                        service.port,
+                        service.service,
+                        service.error,
+                        encode_failures(&service.protocol_failures),
+                    ],
+                )
+                .map_err(db_error)?;
+            }
+            for note in &host.notes {
+                tx.execute(
+                    "INSERT INTO host_notes (run_id, ip, note) VALUES (?1, ?2, ?3)",
+                    params![run_id, ip, note],
+                )
+                .map_err(db_error)?;
+            }
+        }
+        tx.commit().map_err(db_error)?;
+        Ok(run_id)
+    }
+
+    /// Recorded runs, newest first; only those of `targets` when given.
+    pub fn runs(&self, targets: Option<&str>) -> Result<Vec<RunSummary>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT runs.id, runs.started, runs.finished, runs.targets,
+                 (SELECT COUNT(*) FROM hosts WHERE hosts.run_id = runs.id)
+                 FROM runs WHERE ?1 IS NULL OR runs.targets = ?1
+                 ORDER BY runs.started DESC, runs.id DESC",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![targets], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })
+            .map_err(db_error)?;
+        let mut runs = Vec::new();
+        for row in rows {
+            let (id, started, finished, targets, host_count) = row.map_err(db_error)?;
+            runs.push(RunSummary {
+                id,
+                started: parse_time(&started)?,
+                finished: parse_time(&finished)?,
+                targets,
+                host_count: host_count as usize,
+            });
+        }
+        Ok(runs)
+    }
+
+    /// Rebuilds the report of run `id` as it was recorded.
+    pub fn load(&self, id: i64) -> Result<ScanReport, String> {
+        let exists = self
+            .conn
+            .query_row("SELECT id FROM runs WHERE id = ?1", params![id], |row| {
+                row.get::<_, i64>(0)
+            })
+            .optional()
+            .map_err(db_error)?;
+        if exists.is_none() {
+            return Err(format!("No run #{} in the scan history", id));
+        }
+
+        let mut hosts: BTreeMap<IpAddr, HostReport> = BTreeMap::new();
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT ip, hostname, alive, fingerprinted, os, vendor, model, serial, details,
+                 tcp_scanned, udp_scanned FROM hosts WHERE run_id = ?1",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                let fingerprint = if row.get::<_, bool>(3)? {
+                    Some(FingerprintReport {
+                        os: row.get(4)?,
+                        vendor: row.get(5)?,
+                        model: row.get(6)?,
+                        serial: row.get(7)?,
+                        details: row.get(8)?,
+                        os_guesses: Vec::new(),
+                    })
+                } else {
+                    None
+                };
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, bool>(2)?,
+                    fingerprint,
+                    row.get::<_, bool>(9)?,
+                    row.get::<_, bool>(10)?,
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (ip, hostname, alive, fingerprint, tcp, udp) = row.map_err(db_error)?;
+            let ip = parse_ip(&ip)?;
+            hosts.insert(
+                ip,
+                HostReport {
+                    ip,
+                    hostname,
+                    alive,
+                    ping_attempts: None,
+                    fingerprint,
+                    tcp: tcp.then(PortsReport::default),
+                    udp: udp.then(PortsReport::default),
+                    services: Vec::new(),
+                    notes: Vec::new(),
+                },
+            );
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT ip, os, confidence FROM os_guesses WHERE run_id = ?1 ORDER BY rank")
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (ip, os, confidence) = row.map_err(db_error)?;
+            if let Some(fingerprint) = hosts
+                .get_mut(&parse_ip(&ip)?)
+                .and_then(|host| host.fingerprint.as_mut())
+            {
+                fingerprint.os_guesses.push((os, confidence));
+            }
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT ip, transport, port FROM open_ports WHERE run_id = ?1 ORDER BY ip, port",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get(2)?,
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (ip, transport, port) = row.map_err(db_error)?;
+            if let Some(ports) = hosts
+                .get_mut(&parse_ip(&ip)?)
+                .and_then(|host| ports_mut(host, &transport))
+            {
+                ports.open.push(port);
+            }
+        }
+
+        // Runs recorded before the scanned ports were kept have none
+        let mut stmt = self
+            .conn
+            .prepare("SELECT ip, transport, ports FROM scanned_ports WHERE run_id = ?1")
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (ip, transport, scanned) = row.map_err(db_error)?;
+            if let Some(ports) = hosts
+                .get_mut(&parse_ip(&ip)?)
+                .and_then(|host| ports_mut(host, &transport))
+            {
+                ports.scanned = decode_port_ranges(&scanned)?;
+            }
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT ip, state, ports FROM udp_states WHERE run_id = ?1")
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (ip, state, state_ports) = row.map_err(db_error)?;
+            if let Some(ports) = hosts
+                .get_mut(&parse_ip(&ip)?)
+                .and_then(|host| host.udp.as_mut())
+            {
+                ports
+                    .states
+                    .insert(parse_udp_state(&state)?, decode_port_ranges(&state_ports)?);
+            }
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT ip, transport, category, count FROM port_errors WHERE run_id = ?1")
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (ip, transport, category, count) = row.map_err(db_error)?;
+            if let Some(ports) = hosts
+                .get_mut(&parse_ip(&ip)?)
+                .and_then(|host| ports_mut(host, &transport))
+            {
+                ports.errors.insert(category, count as usize);
+            }
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT ip, transport, port, attempts FROM retried_probes WHERE run_id = ?1")
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<u16>>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (ip, transport, port, attempts) = row.map_err(db_error)?;
+            let Some(host) = hosts.get_mut(&parse_ip(&ip)?) else {
+                continue;
+            };
+            match port {
+                None => host.ping_attempts = Some(attempts as usize),
+                Some(port) => {
+                    if let Some(ports) = ports_mut(host, &transport) {
+                        ports.retried.insert(port, attempts as usize);
+                    }
+                }
+            }
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT ip, port, service, error, protocol_failures FROM services
+                 WHERE run_id = ?1 ORDER BY port",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (ip, port, service, error, failures) = row.map_err(db_error)?;
+            if let Some(host) = hosts.get_mut(&parse_ip(&ip)?) {
+                host.services.push(ServiceReport {
+                    port,
+                    service,
+                    error,
+                    protocol_failures: decode_failures(&failures)?,
+                });
+            }
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT ip, note FROM host_notes WHERE run_id = ?1 ORDER BY rowid")
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (ip, note) = row.map_err(db_error)?;
+            if let Some(host) = hosts.get_mut(&parse_ip(&ip)?) {
+                host.notes.push(note);
+            }
+        }
+
+        Ok(ScanReport {
+            hosts: hosts.into_values().collect(),
+        })
+    }
+}
+
+fn ports_mut<'a>(host: &'a mut HostReport, transport: &str) -> Option<&'a mut PortsReport> {
+    match transport {
+        "tcp" => host.tcp.as_mut(),
+        "udp" => host.udp.as_mut(),
+        _ => None,
+    }
+}
+
+/// Reverses `prettyprint::format_port_ranges`: "1-3,8" is 1, 2, 3, 8.
+fn decode_port_ranges(text: &str) -> Result<Vec<u16>, String> {
+    let invalid = || format!("Invalid port list \"{}\" in scan history", text);
+    let mut ports = Vec::new();
+    for part in text.split(',').filter(|part| !part.is_empty()) {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        let first: u16 = first.parse().map_err(|_| invalid())?;
+        let last: u16 = last.parse().map_err(|_| invalid())?;
+        ports.extend(first..=last);
+    }
+    Ok(ports)
+}
+
+fn parse_udp_state(label: &str) -> Result<UdpPortState, String> {
+    [
+        UdpPortState::Open,
+        UdpPortState::Closed,
+        UdpPortState::Filtered,
+        UdpPortState::OpenFiltered,
+        UdpPortState::Unscanned,
+    ]
+    .into_iter()
+    .find(|state| state.label() == label)
+    .ok_or_else(|| format!("Invalid UDP port state \"{}\" in scan history", label))
+}
+
+/// One "protocol<TAB>kind<TAB>message" line per failure.
+fn encode_failures(failures: &[ProtocolFailure]) -> String {
+    failures
+        .iter()
+        .map(|failure| {
+            format!(
+                "{}\t{}\t{}",
+                failure.protocol.label(),
+                failure.error.kind(),
+                failure.error.message().replace(['\t', '\n'], " ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decode_failures(text: &str) -> Result<Vec<ProtocolFailure>, String> {
+    if !text.is_empty() && !text.contains('\t') {
+        return decode_untyped_failures(text);
+    }
+    text.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let (label, kind, message) = (
+                parts.next().unwrap_or_default(),
+                parts.next().unwrap_or_default(),
+                parts.next().unwrap_or_default(),
+            );
+            Ok(ProtocolFailure {
+                protocol: Protocol::from_label(label)
+                    .ok_or_else(|| format!("Unknown protocol \"{}\" in scan history", label))?,
+                error: DetectError::from_parts(kind, message)
+                    .ok_or_else(|| format!("Unknown failure kind \"{}\" in scan history", kind))?,
+            })
+        })
+        .collect()
+}
+
+/// Histories recorded before failures were typed hold a comma-separated list
+/// of detector names ("SSH,HTTP proxy"), or the enum name ("Ssh") for a
+/// protocol that had no detector. The reason for each failure was not kept.
+fn decode_untyped_failures(text: &str) -> Result<Vec<ProtocolFailure>, String> {
+    let registry = service_detection::registry();
+    text.split(',')
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let detector = Protocol::ALL.into_iter().find(|protocol| {
+                registry
+                    .get(*protocol)
+                    .is_some_and(|detector| detector.name() == name)
+            });
+            if let Some(protocol) = detector {
+                return Ok(ProtocolFailure {
+                    protocol,
+                    error: DetectError::Mismatch(format!("{} detection failed", name)),
+                });
+            }
+            Protocol::ALL
+                .into_iter()
+                .find(|protocol| format!("{:?}", protocol) == name)
+                .map(|protocol| ProtocolFailure {
+                    protocol,
+                    error: DetectError::Unsupported,
+                })
+                .ok_or_else(|| format!("Unknown protocol \"{}\" in scan history", name))
+        })
+        .collect()
+}
@@ -0,0 +1,396 @@
+use crate::scanners::pingsweep::parse_network;
+use crate::utils::happy_eyeballs;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+/// Canonical identity of a scan target. IPv4-mapped IPv6 addresses
+/// (::ffff:a.b.c.d) collapse to their IPv4 form so the same host always
+/// compares equal however it was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TargetKey(IpAddr);
+
+impl TargetKey {
+    pub fn new(ip: IpAddr) -> Self {
+        TargetKey(ip.to_canonical())
+    }
+
+    pub fn ip(&self) -> IpAddr {
+        self.0
+    }
+
+    pub fn ipv4(&self) -> Option<Ipv4Addr> {
+        match self.0 {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(_) => None,
+        }
+    }
+}
+
+impl From<IpAddr> for TargetKey {
+    fn from(ip: IpAddr) -> Self {
+        TargetKey::new(ip)
+    }
+}
+
+impl From<Ipv4Addr> for TargetKey {
+    fn from(ip: Ipv4Addr) -> Self {
+        TargetKey::new(IpAddr::V4(ip))
+    }
+}
+
+impl fmt::Display for TargetKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// One entry of a target list as the user wrote it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetSpec {
+    Network(String),
+    /// First and last address of "192.168.1.10-50" or "fd00::10-fd00::1f"
+    Range(IpAddr, IpAddr),
+    Address(IpAddr),
+    Hostname(String),
+}
+
+impl fmt::Display for TargetSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetSpec::Network(cidr) => write!(f, "{}", cidr),
+            TargetSpec::Range(first, last) => write!(f, "{}-{}", first, last),
+            TargetSpec::Address(ip) => write!(f, "{}", ip),
+            TargetSpec::Hostname(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Most addresses a single range may name
+pub const MAX_RANGE_HOSTS: u128 = 65_536;
+
+/// Parses "192.168.1.10-50" (last octet only) or "first-last" in full;
+/// `None` if `input` is not a range at all, e.g. a hostname with a dash.
+fn parse_range(input: &str) -> Result<Option<(IpAddr, IpAddr)>, String> {
+    let Some((first, last)) = input.split_once('-') else {
+        return Ok(None);
+    };
+    let Ok(first) = first.parse::<IpAddr>() else {
+        return Ok(None);
+    };
+    let invalid = || format!("Invalid range '{}'.", input);
+    let last = match (first, last.parse::<IpAddr>(), last.parse::<u8>()) {
+        (_, Ok(last), _) => last,
+        (IpAddr::V4(first), Err(_), Ok(octet)) => {
+            let [a, b, c, _] = first.octets();
+            IpAddr::V4(Ipv4Addr::new(a, b, c, octet))
+        }
+        _ => return Err(invalid()),
+    };
+    let count = match (first, last) {
+        (IpAddr::V4(first), IpAddr::V4(last)) if first <= last => {
+            u128::from(u32::from(last) - u32::from(first)) + 1
+        }
+        (IpAddr::V6(first), IpAddr::V6(last)) if first <= last => {
+            (u128::from(last) - u128::from(first)).saturating_add(1)
+        }
+        _ => return Err(invalid()),
+    };
+    if count > MAX_RANGE_HOSTS {
+        return Err(format!(
+            "Range '{}' names {} addresses; at most {} are allowed.",
+            input, count, MAX_RANGE_HOSTS
+        ));
+    }
+    Ok(Some((first, last)))
+}
+
+/// Every address from `first` to `last`, both included.
+pub fn expand_range(first: IpAddr, last: IpAddr) -> Vec<IpAddr> {
+    match (first, last) {
+        (IpAddr::V4(first), IpAddr::V4(last)) => (u32::from(first)..=u32::from(last))
+            .map(|ip| IpAddr::V4(Ipv4Addr::from(ip)))
+            .collect(),
+        (IpAddr::V6(first), IpAddr::V6(last)) => (u128::from(first)..=u128::from(last))
+            .map(|ip| IpAddr::V6(Ipv6Addr::from(ip)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Parses a comma-separated target list ("10.0.0.0/24,10.0.0.5-20,db.example.com").
+pub fn parse_targets(input: &str) -> Result<Vec<TargetSpec>, String> {
+    let specs: Vec<TargetSpec> = input
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            if s.contains('/') {
+                parse_network(s)?;
+                Ok(TargetSpec::Network(s.to_string()))
+            } else if let Some((first, last)) = parse_range(s)? {
+                Ok(TargetSpec::Range(first, last))
+            } else if let Ok(ip) = s.parse::<IpAddr>() {
+                Ok(TargetSpec::Address(ip))
+            } else {
+                Ok(TargetSpec::Hostname(s.to_string()))
+            }
+        })
+        .collect::<Result<_, String>>()?;
+    if specs.is_empty() {
+        return Err("No targets given.".to_string());
+    }
+    Ok(specs)
+}
+
+/// `--ip` value that reads the target list from stdin
+pub const STDIN_TARGETS: &str = "-";
+
+/// Joins a target list written one per line (as `cat cidrs.txt` gives it)
+/// into the comma-separated form `parse_targets` reads. Blank lines and "#"
+/// comments are skipped; a line may also hold several comma- or
+/// space-separated targets.
+pub fn join_target_lines(text: &str) -> Result<String, String> {
+    let list = target_lines(text).join(",");
+    parse_targets(&list)?;
+    Ok(list)
+}
+
+fn target_lines(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(|line| line.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|target| !target.is_empty())
+        .collect()
+}
+
+/// Reads the target list piped to stdin for `--ip -`.
+pub fn read_stdin_targets() -> Result<String, String> {
+    let text = std::io::read_to_string(std::io::stdin())
+        .map_err(|e| format!("Failed to read targets from stdin: {}", e))?;
+    join_target_lines(&text).map_err(|e| format!("Targets from stdin: {}", e))
+}
+
+/// Reads a `--target-file`, written like the stdin list.
+pub fn read_target_file(path: &Path) -> Result<String, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    join_target_lines(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Deduplicated set of target addresses, remembering every spec that named each one
+#[derive(Debug, Clone, Default)]
+pub struct TargetSet {
+    targets: BTreeMap<TargetKey, BTreeSet<String>>,
+    duplicates: usize,
+}
+
+impl TargetSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `ip` as named by `source`; returns false if it was already present.
+    pub fn insert(&mut self, ip: IpAddr, source: &str) -> bool {
+        let sources = self.targets.entry(TargetKey::new(ip)).or_default();
+        let is_new = sources.is_empty();
+        sources.insert(source.to_string());
+        if !is_new {
+            self.duplicates += 1;
+        }
+        is_new
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Number of insertions that named an address already in the set.
+    pub fn duplicates(&self) -> usize {
+        self.duplicates
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = TargetKey> + '_ {
+        self.targets.keys().copied()
+    }
+
+    /// The specs ("10.0.0.0/24", "db.example.com") that named `key`.
+    pub fn sources(&self, key: TargetKey) -> Option<&BTreeSet<String>> {
+        self.targets.get(&key)
+    }
+
+    pub fn hosts(&self) -> Vec<IpAddr> {
+        self.keys().map(|key| key.ip()).collect()
+    }
+
+    pub fn ipv4_hosts(&self) -> Vec<Ipv4Addr> {
+        self.keys().filter_map(|key| key.ipv4()).collect()
+    }
+
+    /// Drops every target `exclusions` covers; returns how many were dropped.
+    pub fn exclude(&mut self, exclusions: &ExclusionList) -> usize {
+        let before = self.targets.len();
+        self.targets.retain(|key, _| !exclusions.contains(key.ip()));
+        before - self.targets.len()
+    }
+}
+
+/// An address or network kept out of scope (`--exclude 10.0.0.5,10.0.0.0/28`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exclusion {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Exclusion {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let (base, prefix) = match input.split_once('/') {
+            Some((base, prefix)) => (base, Some(prefix)),
+            None => (input, None),
+        };
+        let ip = base
+            .parse::<IpAddr>()
+            .map(|ip| ip.to_canonical())
+            .map_err(|_| {
+                format!(
+                    "Cannot exclude '{}': expected an address or network.",
+                    input
+                )
+            })?;
+        let max = if ip.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| format!("Cannot exclude '{}': invalid prefix.", input))?,
+            None => max,
+        };
+        Ok(Exclusion {
+            network: mask(ip, prefix),
+            prefix,
+        })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        ip.is_ipv4() == self.network.is_ipv4() && mask(ip, self.prefix) == self.network
+    }
+}
+
+impl fmt::Display for Exclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+fn mask(ip: IpAddr, prefix: u8) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let bits = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(ip) & bits))
+        }
+        IpAddr::V6(ip) => {
+            let bits = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(ip) & bits))
+        }
+    }
+}
+
+/// Addresses and networks that must never be probed, from `--exclude` and `--exclude-file`
+#[derive(Debug, Clone, Default)]
+pub struct ExclusionList {
+    entries: Vec<Exclusion>,
+}
+
+impl ExclusionList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a comma-separated list ("10.0.0.5,10.0.0.0/28").
+    pub fn add_list(&mut self, list: &str) -> Result<(), String> {
+        for entry in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            self.entries.push(Exclusion::parse(entry)?);
+        }
+        Ok(())
+    }
+
+    /// Adds an exclusion file: one or more entries per line, "#" comments allowed.
+    pub fn add_file(&mut self, path: &Path) -> Result<(), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        for entry in target_lines(&text) {
+            self.entries
+                .push(Exclusion::parse(entry).map_err(|e| format!("{}: {}", path.display(), e))?);
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.entries.iter().any(|entry| entry.contains(ip))
+    }
+
+    pub fn entries(&self) -> &[Exclusion] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Expands specs into a deduplicated target set, resolving hostnames.
+pub async fn resolve_targets(specs: &[TargetSpec]) -> Result<TargetSet, String> {
+    let mut set = TargetSet::new();
+    for spec in specs {
+        let label = spec.to_string();
+        match spec {
+            TargetSpec::Network(cidr) => {
+                for ip in parse_network(cidr)? {
+                    set.insert(ip, &label);
+                }
+            }
+            TargetSpec::Range(first, last) => {
+                for ip in expand_range(*first, *last) {
+                    set.insert(ip, &label);
+                }
+            }
+            TargetSpec::Address(ip) => {
+                set.insert(*ip, &label);
+            }
+            TargetSpec::Hostname(name) => {
+                for addr in happy_eyeballs::resolve(name, 0).await? {
+                    set.insert(addr.ip(), &label);
+                }
+            }
+        }
+    }
+    Ok(set)
+}
+
+/// Collapses results that share a canonical key, folding later duplicates
+/// into the first with `merge`. First-seen order is preserved.
+pub fn merge_duplicates<T>(
+    items: Vec<T>,
+    key: impl Fn(&T) -> TargetKey,
+    mut merge: impl FnMut(&mut T, T),
+) -> Vec<T> {
+    let mut positions: BTreeMap<TargetKey, usize> = BTreeMap::new();
+    let mut merged: Vec<T> = Vec::with_capacity(items.len());
+    for item in items {
+        match positions.get(&key(&item)) {
+            Some(&index) => merge(&mut merged[index], item),
+            None => {
+                positions.insert(key(&item), merged.len());
+                merged.push(item);
+            }
+        }
+    }
+    merged
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Duration, Local, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// A time zone that wall-clock schedules are written in
+#[derive(Debug, Clone, PartialEq)]
+pub enum Zone {
+    /// The scanner host's local time
+    Local,
+    /// A fixed offset, in seconds east of UTC
+    Fixed(i32),
+    /// An IANA zone from the tz database built into netscan
+    Named(Tz),
+}
+
+impl Zone {
+    /// Accepts "local", "UTC", a fixed offset ("+02:00", "UTC-5") or an IANA
+    /// name ("Europe/Berlin").
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("local") {
+            return Ok(Zone::Local);
+        }
+        if name.eq_ignore_ascii_case("utc") || name.eq_ignore_ascii_case("z") {
+            return Ok(Zone::Fixed(0));
+        }
+        let offset = name
+            .strip_prefix("UTC")
+            .or_else(|| name.strip_prefix("GMT"))
+            .unwrap_or(name);
+        if offset.starts_with(['+', '-']) {
+            return parse_fixed_offset(offset)
+                .map(Zone::Fixed)
+                .ok_or_else(|| format!("Invalid UTC offset: {}", name));
+        }
+        name.parse::<Tz>()
+            .map(Zone::Named)
+            .map_err(|_| format!("Unknown time zone: {}", name))
+    }
+
+    /// Seconds east of UTC in effect at `instant`.
+    pub fn offset_at(&self, instant: DateTime<Utc>) -> i32 {
+        match self {
+            Zone::Local => Local
+                .offset_from_utc_datetime(&instant.naive_utc())
+                .fix()
+                .local_minus_utc(),
+            Zone::Fixed(offset) => *offset,
+            Zone::Named(tz) => tz
+                .offset_from_utc_datetime(&instant.naive_utc())
+                .fix()
+                .local_minus_utc(),
+        }
+    }
+
+    /// Wall-clock time in this zone at `instant`.
+    pub fn to_local(&self, instant: DateTime<Utc>) -> NaiveDateTime {
+        instant.naive_utc() + Duration::seconds(i64::from(self.offset_at(instant)))
+    }
+}
+
+/// "+02:00", "-0530" or "+5" as seconds east of UTC.
+fn parse_fixed_offset(text: &str) -> Option<i32> {
+    let (sign, digits) = match text.as_bytes().first()? {
+        b'+' => (1, &text[1..]),
+        b'-' => (-1, &text[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if digits.len() == 4 => digits.split_at(2),
+        None => (digits, "0"),
+    };
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
@@ -0,0 +1,130 @@
+use crate::utils::prettyprint;
+use colored::*;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// A host is an outlier when it took this many times the phase's median...
+const OUTLIER_FACTOR: u32 = 3;
+/// ...and at least this much longer than the median
+const OUTLIER_MIN_EXCESS: Duration = Duration::from_secs(2);
+
+/// How long one phase of a run took, with the time spent on each host where
+/// the phase works host by host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhaseTiming {
+    pub phase: &'static str,
+    pub elapsed: Duration,
+    pub hosts: Vec<(IpAddr, Duration)>,
+}
+
+impl PhaseTiming {
+    fn median(&self) -> Option<Duration> {
+        let mut times: Vec<Duration> = self.hosts.iter().map(|(_, elapsed)| *elapsed).collect();
+        times.sort_unstable();
+        times.get(times.len() / 2).copied()
+    }
+
+    /// Hosts that took far longer than most, slowest first.
+    pub fn outliers(&self) -> Vec<(IpAddr, Duration)> {
+        let Some(median) = self.median() else {
+            return Vec::new();
+        };
+        let threshold = (median * OUTLIER_FACTOR).max(median + OUTLIER_MIN_EXCESS);
+        let mut outliers: Vec<(IpAddr, Duration)> = self
+            .hosts
+            .iter()
+            .copied()
+            .filter(|(_, elapsed)| *elapsed > threshold)
+            .collect();
+        outliers.sort_by_key(|(_, elapsed)| std::cmp::Reverse(*elapsed));
+        outliers
+    }
+}
+
+/// Job log of a scan run: every phase in the order it ran
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunTimings {
+    pub phases: Vec<PhaseTiming>,
+}
+
+impl RunTimings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, phase: &'static str, elapsed: Duration) {
+        self.record_hosts(phase, elapsed, Vec::new());
+    }
+
+    pub fn record_hosts(
+        &mut self,
+        phase: &'static str,
+        elapsed: Duration,
+        hosts: Vec<(IpAddr, Duration)>,
+    ) {
+        self.phases.push(PhaseTiming {
+            phase,
+            elapsed,
+            hosts,
+        });
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|phase| phase.elapsed).sum()
+    }
+
+    /// Prints each phase with its share of the run and the hosts that held it up.
+    pub fn print(&self) {
+        if self.phases.is_empty() {
+            return;
+        }
+        let total = self.total();
+        println!(
+            "\n{}",
+            prettyprint::console("⏱ Time spent")
+                .bold()
+                .underline()
+                .blue()
+        );
+        for phase in &self.phases {
+            let share = if total.is_zero() {
+                0.0
+            } else {
+                phase.elapsed.as_secs_f64() * 100.0 / total.as_secs_f64()
+            };
+            println!(
+                "  {:<20} {:>10} {:>5.1}%",
+                phase.phase,
+                format_duration(phase.elapsed),
+                share
+            );
+            let median = phase.median();
+            for (ip, elapsed) in phase.outliers() {
+                println!(
+                    "    {} {} {}",
+                    "slow:".yellow(),
+                    ip,
+                    format!(
+                        "{} (median {})",
+                        format_duration(elapsed),
+                        format_duration(median.unwrap_or_default())
+                    )
+                    .dimmed()
+                );
+            }
+        }
+        println!("  {:<20} {:>10}", "Total".bold(), format_duration(total));
+    }
+}
+
+/// "850ms", "12.4s" or "41m 03s".
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else if secs >= 1 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
+}
@@ -0,0 +1,122 @@
+use crate::utils::scan_config::ScanConfig;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Shortest per-host timeout adaptive timing sets, however close the host
+pub const MIN_ADAPTIVE_TIMEOUT: Duration = Duration::from_millis(100);
+/// Round trips a probe may take before adaptive timing gives up on it
+const ADAPTIVE_RTT_FACTOR: u32 = 4;
+/// Allowance on top of them for the host's own processing
+const ADAPTIVE_SLACK: Duration = Duration::from_millis(50);
+
+/// Timing presets in the spirit of nmap's -T0 to -T5, from stealthiest to fastest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimingTemplate {
+    Paranoid,
+    Sneaky,
+    Polite,
+    Normal,
+    Aggressive,
+    Insane,
+}
+
+impl TimingTemplate {
+    pub const ALL: [TimingTemplate; 6] = [
+        TimingTemplate::Paranoid,
+        TimingTemplate::Sneaky,
+        TimingTemplate::Polite,
+        TimingTemplate::Normal,
+        TimingTemplate::Aggressive,
+        TimingTemplate::Insane,
+    ];
+
+    /// 0 for paranoid to 5 for insane, as in `-T3`.
+    pub fn level(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_level(level: u8) -> Option<Self> {
+        Self::ALL.get(level as usize).copied()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TimingTemplate::Paranoid => "paranoid",
+            TimingTemplate::Sneaky => "sneaky",
+            TimingTemplate::Polite => "polite",
+            TimingTemplate::Normal => "normal",
+            TimingTemplate::Aggressive => "aggressive",
+            TimingTemplate::Insane => "insane",
+        }
+    }
+
+    /// Per-probe timeout; `None` keeps each scanner's default.
+    pub fn timeout(&self) -> Option<Duration> {
+        match self {
+            TimingTemplate::Paranoid | TimingTemplate::Sneaky => Some(Duration::from_secs(5)),
+            TimingTemplate::Polite => Some(Duration::from_secs(3)),
+            TimingTemplate::Normal => None,
+            TimingTemplate::Aggressive => Some(Duration::from_millis(1250)),
+            TimingTemplate::Insane => Some(Duration::from_millis(300)),
+        }
+    }
+
+    /// Probes in flight; `None` keeps the default derived from the system limits.
+    pub fn concurrency(&self) -> Option<usize> {
+        match self {
+            TimingTemplate::Paranoid | TimingTemplate::Sneaky => Some(1),
+            TimingTemplate::Polite => Some(8),
+            _ => None,
+        }
+    }
+
+    pub fn retries(&self) -> Option<usize> {
+        match self {
+            TimingTemplate::Paranoid | TimingTemplate::Sneaky => Some(2),
+            TimingTemplate::Polite | TimingTemplate::Aggressive => Some(1),
+            TimingTemplate::Normal => None,
+            TimingTemplate::Insane => Some(0),
+        }
+    }
+
+    /// Least time between two probes, to stay under IDS thresholds.
+    pub fn probe_delay(&self) -> Option<Duration> {
+        match self {
+            TimingTemplate::Paranoid => Some(Duration::from_secs(300)),
+            TimingTemplate::Sneaky => Some(Duration::from_secs(15)),
+            TimingTemplate::Polite => Some(Duration::from_millis(400)),
+            _ => None,
+        }
+    }
+
+    /// `config` with the settings it leaves unset taken from the template.
+    pub fn apply(&self, config: ScanConfig) -> ScanConfig {
+        ScanConfig {
+            timeout: config.timeout.or(self.timeout()),
+            concurrency: config.concurrency.or(self.concurrency()),
+            retries: config.retries.or(self.retries()),
+            ..config
+        }
+    }
+}
+
+impl fmt::Display for TimingTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "T{} ({})", self.level(), self.label())
+    }
+}
+
+/// Timeout for a host that answered in `rtt`: a few round trips, at
+/// least `MIN_ADAPTIVE_TIMEOUT`.
+pub fn adaptive_timeout(rtt: Duration) -> Duration {
+    (rtt * ADAPTIVE_RTT_FACTOR + ADAPTIVE_SLACK).max(MIN_ADAPTIVE_TIMEOUT)
+}
+
+/// Per-host timeouts for measured round trip times, from pings or TCP handshakes.
+pub fn adaptive_timeouts(rtts: &BTreeMap<IpAddr, Duration>) -> BTreeMap<IpAddr, Duration> {
+    rtts.iter()
+        .map(|(ip, rtt)| (*ip, adaptive_timeout(*rtt)))
+        .collect()
+}
@@ -0,0 +1,202 @@
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+
+use crate::utils::findings::digest;
+
+/// Stands in for the scanned host and any IPv4 address a response mentions
+/// (TEST-NET-1, RFC 5737)
+const PLACEHOLDER_V4: &str = "192.0.2.1";
+/// Stands in for an IPv6 target (documentation prefix, RFC 3849)
+const PLACEHOLDER_V6: &str = "2001:db8::1";
+
+/// A probe/response exchange that no detector recognized
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSample {
+    pub port: u16,
+    /// Bytes sent before the response; empty when the service spoke first
+    pub probe: Vec<u8>,
+    pub response: Vec<u8>,
+    pub captured: DateTime<Utc>,
+}
+
+impl UnknownSample {
+    /// The exchange with `ip` and every IPv4 address in it replaced by
+    /// documentation addresses.
+    pub fn anonymized(
+        port: u16,
+        probe: &[u8],
+        response: &[u8],
+        ip: IpAddr,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            port,
+            probe: anonymize(probe, ip),
+            response: anonymize(response, ip),
+            captured: now,
+        }
+    }
+
+    /// File name unique to the port and response, so repeats overwrite
+    /// rather than pile up.
+    pub fn file_name(&self) -> String {
+        let id = digest(&[&escape(&self.probe), &escape(&self.response)]);
+        format!("port{}-{}.txt", self.port, id)
+    }
+
+    /// Reads a record written by `render`; "#" lines and unknown keys are skipped.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut port = None;
+        let mut probe = Vec::new();
+        let mut response = None;
+        let mut captured = Utc::now();
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let Some((key, value)) = line.split_once(": ") else {
+                continue;
+            };
+            match key {
+                "port" => {
+                    port = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid port \"{}\"", value))?,
+                    )
+                }
+                "probe" if value != "NULL" => probe = unescape(value)?,
+                "response" => response = Some(unescape(value)?),
+                "captured" => {
+                    captured = DateTime::parse_from_rfc3339(value)
+                        .map_err(|_| format!("Invalid capture time \"{}\"", value))?
+                        .with_timezone(&Utc)
+                }
+                _ => {}
+            }
+        }
+        Ok(Self {
+            port: port.ok_or("Sample has no port")?,
+            probe,
+            response: response.ok_or("Sample has no response")?,
+            captured,
+        })
+    }
+
+    /// Plain-text record with the bytes escaped as in nmap probe files.
+    pub fn render(&self) -> String {
+        format!(
+            "# netscan unknown service sample\nport: {}\ncaptured: {}\nprobe: {}\nresponse: {}\n",
+            self.port,
+            self.captured.to_rfc3339(),
+            if self.probe.is_empty() {
+                "NULL".to_string()
+            } else {
+                escape(&self.probe)
+            },
+            escape(&self.response)
+        )
+    }
+}
+
+/// Printable ASCII as is, \r \n \t and backslash escaped, everything else as \xHH.
+pub fn escape(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for &byte in bytes {
+        match byte {
+            b'\r' => out.push_str("\\r"),
+            b'\n' => out.push_str("\\n"),
+            b'\t' => out.push_str("\\t"),
+            b'\\' => out.push_str("\\\\"),
+            0x20..=0x7e => out.push(byte as char),
+            _ => out.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    out
+}
+
+/// Reverses `escape`.
+pub fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut bytes = text.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            out.push(byte);
+            continue;
+        }
+        match bytes.next() {
+            Some(b'r') => out.push(b'\r'),
+            Some(b'n') => out.push(b'\n'),
+            Some(b't') => out.push(b'\t'),
+            Some(b'\\') => out.push(b'\\'),
+            Some(b'x') => {
+                let hex: Vec<u8> = bytes.by_ref().take(2).collect();
+                let value = std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .filter(|_| hex.len() == 2)
+                    .ok_or_else(|| format!("Invalid \\x escape in \"{}\"", text))?;
+                out.push(value);
+            }
+            _ => return Err(format!("Invalid escape in \"{}\"", text)),
+        }
+    }
+    Ok(out)
+}
+
+/// Replaces the target address and any dotted-quad IPv4 address in `bytes`.
+pub fn anonymize(bytes: &[u8], ip: IpAddr) -> Vec<u8> {
+    let target = ip.to_string();
+    let target_placeholder = match ip {
+        IpAddr::V4(_) => PLACEHOLDER_V4,
+        IpAddr::V6(_) => PLACEHOLDER_V6,
+    };
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(target.as_bytes()) {
+            out.extend_from_slice(target_placeholder.as_bytes());
+            i += target.len();
+            continue;
+        }
+        let starts_word = i == 0 || !(bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'.');
+        if starts_word && bytes[i].is_ascii_digit() {
+            let len = bytes[i..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit() || **b == b'.')
+                .count();
+            let candidate = String::from_utf8_lossy(&bytes[i..i + len]);
+            if candidate.parse::<Ipv4Addr>().is_ok() {
+                out.extend_from_slice(PLACEHOLDER_V4.as_bytes());
+                i += len;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Directory of unknown-service samples (`--collect-unknowns`)
+#[derive(Debug, Clone)]
+pub struct UnknownCorpus {
+    dir: PathBuf,
+}
+
+impl UnknownCorpus {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Writes `sample` into the corpus, creating the directory if needed.
+    pub fn save(&self, sample: &UnknownSample) -> Result<PathBuf, String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create {}: {}", self.dir.display(), e))?;
+        let path = self.dir.join(sample.file_name());
+        fs::write(&path, sample.render())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
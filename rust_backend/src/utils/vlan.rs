@@ -0,0 +1,66 @@
+/// Tag Protocol Identifier for IEEE 802.1Q frames
+pub const TPID_8021Q: u16 = 0x8100;
+/// Offset of the EtherType field in an untagged Ethernet frame (after dst + src MAC)
+const ETHERTYPE_OFFSET: usize = 12;
+const TAG_LEN: usize = 4;
+
+/// Validates a VLAN ID (0 and 4095 are reserved).
+pub fn validate_vlan_id(vlan_id: u16) -> Result<u16, String> {
+    if (1..=4094).contains(&vlan_id) {
+        Ok(vlan_id)
+    } else {
+        Err(format!("Invalid VLAN ID {vlan_id}. Must be between 1 and 4094."))
+    }
+}
+
+/// Inserts an 802.1Q tag (priority 0) into an untagged Ethernet frame.
+pub fn tag_frame(frame: &[u8], vlan_id: u16) -> Result<Vec<u8>, String> {
+    validate_vlan_id(vlan_id)?;
+    if frame.len() < ETHERTYPE_OFFSET + 2 {
+        return Err("Frame too short to tag".to_string());
+    }
+    let mut tagged = Vec::with_capacity(frame.len() + TAG_LEN);
+    tagged.extend_from_slice(&frame[..ETHERTYPE_OFFSET]);
+    tagged.extend_from_slice(&TPID_8021Q.to_be_bytes());
+    tagged.extend_from_slice(&(vlan_id & 0x0FFF).to_be_bytes());
+    tagged.extend_from_slice(&frame[ETHERTYPE_OFFSET..]);
+    Ok(tagged)
+}
+
+/// Returns the VLAN ID of a tagged frame, if it carries an 802.1Q tag.
+pub fn frame_vlan_id(frame: &[u8]) -> Option<u16> {
+    if frame.len() < ETHERTYPE_OFFSET + TAG_LEN {
+        return None;
+    }
+    let tpid = u16::from_be_bytes([frame[ETHERTYPE_OFFSET], frame[ETHERTYPE_OFFSET + 1]]);
+    if tpid != TPID_8021Q {
+        return None;
+    }
+    let tci = u16::from_be_bytes([frame[ETHERTYPE_OFFSET + 2], frame[ETHERTYPE_OFFSET + 3]]);
+    Some(tci & 0x0FFF)
+}
+
+/// Whether a reply tagged `frame_vlan` belongs to the VLAN that was probed:
+/// with a VLAN requested, untagged replies and other VLANs' are not.
+pub fn accepts(requested: Option<u16>, frame_vlan: Option<u16>) -> bool {
+    requested.is_none() || frame_vlan == requested
+}
+
+/// Strips the 802.1Q tag from a frame. Untagged frames are returned unchanged.
+pub fn untag_frame(frame: &[u8]) -> Vec<u8> {
+    if frame_vlan_id(frame).is_none() {
+        return frame.to_vec();
+    }
+    let mut untagged = Vec::with_capacity(frame.len() - TAG_LEN);
+    untagged.extend_from_slice(&frame[..ETHERTYPE_OFFSET]);
+    untagged.extend_from_slice(&frame[ETHERTYPE_OFFSET + TAG_LEN..]);
+    untagged
+}
+
+/// Prepares an outgoing frame for the wire, tagging it when a VLAN is set.
+pub fn encapsulate(frame: Vec<u8>, vlan_id: Option<u16>) -> Result<Vec<u8>, String> {
+    match vlan_id {
+        Some(id) => tag_frame(&frame, id),
+        None => Ok(frame),
+    }
+}
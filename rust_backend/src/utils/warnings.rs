@@ -0,0 +1,229 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::sync::Mutex;
+
+const CONNTRACK_COUNT_PATH: &str = "/proc/sys/net/netfilter/nf_conntrack_count";
+const CONNTRACK_MAX_PATH: &str = "/proc/sys/net/netfilter/nf_conntrack_max";
+const PORT_RANGE_PATH: &str = "/proc/sys/net/ipv4/ip_local_port_range";
+const LIMITS_PATH: &str = "/proc/self/limits";
+const STATUS_PATH: &str = "/proc/self/status";
+const CAP_NET_RAW: u32 = 13;
+
+const CONNTRACK_PRESSURE_RATIO: f64 = 0.8; // Warn when the conntrack table is this full
+const STORM_THRESHOLD: usize = 25; // Identical OS errors before a storm warning is raised
+
+static STORMS: Lazy<Mutex<HashMap<WarningKind, usize>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Conditions on the scanning host that degrade or falsify scan results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum WarningKind {
+    ConntrackPressure,
+    EphemeralPortExhaustion,
+    FileDescriptorLimit,
+    PermissionDenied,
+    NoBufferSpace,
+}
+
+/// An actionable warning about the scanning environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanWarning {
+    pub kind: WarningKind,
+    pub message: String,
+    pub remediation: String,
+}
+
+impl fmt::Display for ScanWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "⚠️  {}. {}", self.message, self.remediation)
+    }
+}
+
+/// The kernel's ephemeral port range, if it can be read.
+pub fn ephemeral_port_range() -> Option<(u16, u16)> {
+    fs::read_to_string(PORT_RANGE_PATH)
+        .ok()
+        .as_deref()
+        .and_then(parse_port_range)
+}
+
+/// Parses /proc/sys/net/ipv4/ip_local_port_range ("32768\t60999").
+pub fn parse_port_range(contents: &str) -> Option<(u16, u16)> {
+    let mut parts = contents.split_whitespace();
+    let low = parts.next()?.parse().ok()?;
+    let high = parts.next()?.parse().ok()?;
+    Some((low, high))
+}
+
+/// Whether the effective capabilities in /proc/self/status include
+/// CAP_NET_RAW, which raw sockets need.
+pub fn parse_cap_net_raw(status: &str) -> Option<bool> {
+    let effective = status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))?
+        .trim();
+    let mask = u64::from_str_radix(effective, 16).ok()?;
+    Some(mask & (1 << CAP_NET_RAW) != 0)
+}
+
+/// Whether this process may open raw sockets (root or CAP_NET_RAW).
+pub fn has_raw_socket_privilege() -> bool {
+    fs::read_to_string(STATUS_PATH)
+        .ok()
+        .as_deref()
+        .and_then(parse_cap_net_raw)
+        .unwrap_or(false)
+}
+
+/// Parses the soft "Max open files" limit from /proc/self/limits.
+pub fn parse_open_files_limit(contents: &str) -> Option<u64> {
+    contents
+        .lines()
+        .find(|line| line.starts_with("Max open files"))?
+        .trim_start_matches("Max open files")
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Current and maximum conntrack entries, when netfilter conntrack is loaded.
+pub fn conntrack_usage() -> Option<(u64, u64)> {
+    let count = fs::read_to_string(CONNTRACK_COUNT_PATH)
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    let max = fs::read_to_string(CONNTRACK_MAX_PATH)
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some((count, max))
+}
+
+/// Checks the host before scanning. `planned_connections` is the number of
+/// connect() probes the run will make and `concurrency` how many run at once.
+pub fn check_environment(planned_connections: usize, concurrency: usize) -> Vec<ScanWarning> {
+    let mut warnings = Vec::new();
+
+    if let Some((count, max)) = conntrack_usage() {
+        let headroom = max.saturating_sub(count) as usize;
+        if max > 0
+            && (count as f64 / max as f64 >= CONNTRACK_PRESSURE_RATIO
+                || planned_connections > headroom)
+        {
+            warnings.push(ScanWarning {
+                kind: WarningKind::ConntrackPressure,
+                message: format!(
+                    "Conntrack table is at {count}/{max} entries and this scan plans {planned_connections} connections"
+                ),
+                remediation: "Raise net.netfilter.nf_conntrack_max or scan fewer targets per run, \
+                    otherwise new probes will be silently dropped"
+                    .to_string(),
+            });
+        }
+    }
+
+    if let Some((low, high)) = ephemeral_port_range() {
+        let available = usize::from(high.saturating_sub(low)) + 1;
+        if planned_connections > available {
+            warnings.push(ScanWarning {
+                kind: WarningKind::EphemeralPortExhaustion,
+                message: format!(
+                    "{planned_connections} connections exceed the {available} local ephemeral ports ({low}-{high})"
+                ),
+                remediation: "Expect \"Cannot assign requested address\" errors; widen \
+                    net.ipv4.ip_local_port_range or enable net.ipv4.tcp_tw_reuse"
+                    .to_string(),
+            });
+        }
+    }
+
+    if let Some(limit) = fs::read_to_string(LIMITS_PATH)
+        .ok()
+        .as_deref()
+        .and_then(parse_open_files_limit)
+        && (concurrency as u64) * 2 > limit
+    {
+        warnings.push(ScanWarning {
+            kind: WarningKind::FileDescriptorLimit,
+            message: format!(
+                "Open file limit ({limit}) is too low for {concurrency} concurrent probes"
+            ),
+            remediation: "Raise it with `ulimit -n 65535` or lower concurrency".to_string(),
+        });
+    }
+
+    warnings
+}
+
+fn classify(e: &io::Error) -> Option<WarningKind> {
+    match e.raw_os_error() {
+        Some(1) | Some(13) => Some(WarningKind::PermissionDenied), // EPERM, EACCES
+        Some(23) | Some(24) => Some(WarningKind::FileDescriptorLimit), // ENFILE, EMFILE
+        Some(99) => Some(WarningKind::EphemeralPortExhaustion),    // EADDRNOTAVAIL
+        Some(105) => Some(WarningKind::NoBufferSpace),             // ENOBUFS
+        _ if e.kind() == io::ErrorKind::PermissionDenied => Some(WarningKind::PermissionDenied),
+        _ => None,
+    }
+}
+
+fn storm_warning(kind: WarningKind, count: usize) -> ScanWarning {
+    let (message, remediation) = match kind {
+        WarningKind::PermissionDenied => (
+            "\"Operation not permitted\"",
+            "A local firewall or missing privileges is blocking probes; run as root or check iptables/nftables rules",
+        ),
+        WarningKind::FileDescriptorLimit => (
+            "\"Too many open files\"",
+            "Raise the limit with `ulimit -n 65535` or lower concurrency",
+        ),
+        WarningKind::EphemeralPortExhaustion => (
+            "\"Cannot assign requested address\"",
+            "Local ephemeral ports are exhausted; lower concurrency or widen net.ipv4.ip_local_port_range",
+        ),
+        WarningKind::NoBufferSpace => (
+            "\"No buffer space available\"",
+            "Kernel send buffers or the conntrack table are full; lower concurrency or cap bandwidth with --max-bandwidth",
+        ),
+        WarningKind::ConntrackPressure => (
+            "Conntrack",
+            "Raise net.netfilter.nf_conntrack_max or lower concurrency",
+        ),
+    };
+    ScanWarning {
+        kind,
+        message: format!("{count} {message} errors during this run"),
+        remediation: remediation.to_string(),
+    }
+}
+
+/// Records an OS error from a probe; storms of the same environmental error
+/// are reported once, by `storm_warnings` at the end of the run.
+pub fn record_io_error(e: &io::Error) {
+    let Some(kind) = classify(e) else {
+        return;
+    };
+    *STORMS.lock().unwrap().entry(kind).or_insert(0) += 1;
+}
+
+/// Records an OS error and returns its message, for use in `map_err`.
+pub fn note(e: io::Error) -> String {
+    record_io_error(&e);
+    e.to_string()
+}
+
+/// Warnings for every error storm observed so far in this run.
+pub fn storm_warnings() -> Vec<ScanWarning> {
+    let storms = STORMS.lock().unwrap();
+    let mut warnings: Vec<ScanWarning> = storms
+        .iter()
+        .filter(|(_, count)| **count >= STORM_THRESHOLD)
+        .map(|(kind, count)| storm_warning(*kind, *count))
+        .collect();
+    warnings.sort_by_key(|w| w.kind);
+    warnings
+}
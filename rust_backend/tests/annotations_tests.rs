@@ -0,0 +1,151 @@
+use chrono::{TimeZone, Utc};
+use rust_backend::utils::annotations::{self, AnnotationStore, Verdict};
+use rust_backend::utils::findings::{self, Finding, Severity};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+fn sample_findings() -> BTreeMap<IpAddr, Vec<Finding>> {
+    let mut host_findings = BTreeMap::new();
+    host_findings.insert(
+        "10.0.0.5".parse().unwrap(),
+        vec![
+            Finding::new(
+                Severity::High,
+                Some(23),
+                "Telnet exposed",
+                "cleartext login".to_string(),
+            ),
+            Finding::new(
+                Severity::Medium,
+                Some(443),
+                "TLS 1.0 enabled",
+                String::new(),
+            ),
+        ],
+    );
+    host_findings
+}
+
+#[test]
+fn test_finding_id_is_stable_per_host() {
+    let ip: IpAddr = "10.0.0.5".parse().unwrap();
+    let finding = Finding::new(Severity::Low, Some(21), "FTP banner", "vsFTPd".to_string());
+    let same = Finding::new(Severity::Low, Some(21), "FTP banner", "other".to_string());
+    assert_eq!(
+        findings::finding_id(ip, &finding),
+        findings::finding_id(ip, &same)
+    );
+    assert_ne!(
+        findings::finding_id(ip, &finding),
+        findings::finding_id("10.0.0.6".parse().unwrap(), &finding)
+    );
+    let tagged = finding.with_id("abc".to_string());
+    assert_eq!(findings::finding_id(ip, &tagged), "abc");
+}
+
+#[test]
+fn test_annotations_suppress_findings_and_persist() {
+    let dir = std::env::temp_dir().join(format!("netscan-annotations-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let host_findings = sample_findings();
+    let ip: IpAddr = "10.0.0.5".parse().unwrap();
+    let telnet_id = findings::finding_id(ip, &host_findings[&ip][0]);
+    let now = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+
+    let mut store = AnnotationStore::load(&dir).unwrap();
+    assert!(store.is_empty());
+    // IDs must come from a recorded scan
+    assert!(
+        store
+            .annotate(&telnet_id, Verdict::AcceptedRisk, "", now)
+            .is_err()
+    );
+
+    annotations::save_findings(&dir, &host_findings, |_| None).unwrap();
+    let annotation = store
+        .annotate(&telnet_id, Verdict::AcceptedRisk, "isolated VLAN", now)
+        .unwrap();
+    assert!(annotation.finding.starts_with("10.0.0.5: "));
+    assert!(annotation.finding.contains("Telnet exposed"));
+    store.save().unwrap();
+
+    let reloaded = AnnotationStore::load(&dir).unwrap();
+    let annotation = reloaded.get(&telnet_id).unwrap();
+    assert_eq!(annotation.verdict, Verdict::AcceptedRisk);
+    assert_eq!(annotation.comment, "isolated VLAN");
+    assert_eq!(annotation.at, now);
+
+    let applied = reloaded.apply(&host_findings);
+    assert_eq!(applied.reported_for(ip).len(), 1);
+    assert_eq!(applied.reported_for(ip)[0].title, "TLS 1.0 enabled");
+    assert_eq!(applied.suppressed.len(), 1);
+    assert_eq!(applied.suppressed[0].finding.title, "Telnet exposed");
+    assert_eq!(applied.suppressed[0].ip, ip);
+    // Check summaries ask the store finding by finding
+    assert!(reloaded.suppresses(ip, &host_findings[&ip][0]));
+    assert!(!reloaded.suppresses(ip, &host_findings[&ip][1]));
+    assert!(!reloaded.suppresses("10.0.0.6".parse().unwrap(), &host_findings[&ip][0]));
+
+    let mut store = reloaded;
+    assert!(store.remove(&telnet_id).is_some());
+    assert!(store.remove(&telnet_id).is_none());
+    store.save().unwrap();
+    let applied = AnnotationStore::load(&dir).unwrap().apply(&host_findings);
+    assert_eq!(applied.reported_for(ip).len(), 2);
+    assert!(applied.suppressed.is_empty());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_annotations_follow_their_host_by_mac() {
+    let dir = std::env::temp_dir().join(format!("netscan-annotations-mac-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let host_findings = sample_findings();
+    let old_ip: IpAddr = "10.0.0.5".parse().unwrap();
+    let new_ip: IpAddr = "10.0.0.9".parse().unwrap();
+    let telnet = host_findings[&old_ip][0].clone();
+    let old_id = findings::finding_id(old_ip, &telnet);
+    let now = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+
+    annotations::save_findings(&dir, &host_findings, |_| {
+        Some("AA:BB:CC:00:11:22".to_string())
+    })
+    .unwrap();
+    let mut store = AnnotationStore::load(&dir).unwrap();
+    store
+        .annotate(&old_id, Verdict::FalsePositive, "lab box", now)
+        .unwrap();
+    store.save().unwrap();
+
+    // The host came back on another address
+    let mut moved = BTreeMap::new();
+    moved.insert(new_ip, vec![telnet.clone()]);
+    let mut store = AnnotationStore::load(&dir).unwrap();
+    assert!(!store.suppresses(new_ip, &telnet));
+    store.follow(new_ip, Some("aa:bb:cc:00:11:22"));
+    assert!(store.suppresses(new_ip, &telnet));
+    assert_eq!(store.for_host("aa:bb:cc:00:11:22").len(), 1);
+    // Another host with the same finding stays reported
+    store.follow(old_ip, Some("aa:bb:cc:99:99:99"));
+    assert!(!store.suppresses(old_ip, &telnet));
+
+    assert!(store.carry_forward(&moved));
+    assert!(!store.carry_forward(&moved));
+    store.save().unwrap();
+    let reloaded = AnnotationStore::load(&dir).unwrap();
+    let new_id = findings::finding_id(new_ip, &telnet);
+    assert!(reloaded.get(&old_id).is_none());
+    assert_eq!(reloaded.get(&new_id).unwrap().comment, "lab box");
+    assert_eq!(reloaded.apply(&moved).suppressed.len(), 1);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_verdict_labels_round_trip() {
+    for verdict in [Verdict::FalsePositive, Verdict::AcceptedRisk] {
+        assert_eq!(Verdict::parse(verdict.label()), Some(verdict));
+    }
+    assert_eq!(Verdict::parse("ignored"), None);
+}
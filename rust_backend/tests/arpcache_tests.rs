@@ -0,0 +1,127 @@
+use pnet::datalink::{InterfaceType, MacAddr, NetworkInterface};
+use rust_backend::scanners::arpcache::{self, ARP_FILTER, ArpCache, ArpSegment, CACHE_TTL};
+use rust_backend::scanners::arpsweep::build_arp_request;
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+
+fn segment(interface: &str, vlan_id: Option<u16>) -> ArpSegment {
+    ArpSegment {
+        interface: interface.to_string(),
+        src_mac: MacAddr::new(0x02, 0, 0, 0, 0, 1),
+        src_ip: Ipv4Addr::new(192, 168, 1, 2),
+        vlan_id,
+    }
+}
+
+#[test]
+fn test_cache_entries_expire() {
+    let ip = Ipv4Addr::new(192, 168, 1, 20);
+    let mac = MacAddr::new(0xb8, 0x27, 0xeb, 1, 2, 3);
+    let start = Instant::now();
+    let mut cache = ArpCache::new();
+    cache.insert(ip, mac, &segment("eth0", None), start);
+
+    assert_eq!(cache.get(ip, start + Duration::from_secs(1)), Some(mac));
+    assert_eq!(cache.get(Ipv4Addr::new(192, 168, 1, 21), start), None);
+    assert_eq!(cache.get(ip, start + CACHE_TTL), None);
+
+    cache.expire(start + Duration::from_secs(1));
+    assert_eq!(cache.len(), 1);
+    cache.expire(start + CACHE_TTL);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_cache_answers_are_per_segment() {
+    let ip = Ipv4Addr::new(10, 0, 20, 5);
+    let mac = MacAddr::new(0x00, 0x50, 0x56, 1, 2, 3);
+    let now = Instant::now();
+    let mut cache = ArpCache::new();
+    cache.insert(ip, mac, &segment("eth1", Some(20)), now);
+
+    assert_eq!(cache.get_on(ip, &segment("eth1", Some(20)), now), Some(mac));
+    // The same address on another VLAN or interface is a different host
+    assert_eq!(cache.get_on(ip, &segment("eth1", Some(30)), now), None);
+    assert_eq!(cache.get_on(ip, &segment("eth0", None), now), None);
+    // Fingerprinting takes an answer from any segment
+    assert_eq!(cache.get(ip, now), Some(mac));
+}
+
+// IFF_* values from <net/if.h>
+const IFF_UP: InterfaceType = 0x1;
+const IFF_LOOPBACK: InterfaceType = 0x8;
+const IFF_POINTOPOINT: InterfaceType = 0x10;
+
+fn interface(name: &str, mac: Option<MacAddr>, flags: InterfaceType) -> NetworkInterface {
+    NetworkInterface {
+        name: name.to_string(),
+        description: String::new(),
+        index: 1,
+        mac,
+        ips: Vec::new(),
+        flags,
+    }
+}
+
+#[test]
+fn test_only_ethernet_interfaces_can_arp() {
+    let mac = MacAddr::new(0x02, 0, 0, 0, 0, 1);
+    assert_eq!(
+        arpcache::arp_capable(&interface("eth0", Some(mac), IFF_UP)),
+        Ok(mac)
+    );
+
+    let tun = arpcache::arp_capable(&interface("tun0", None, IFF_UP | IFF_POINTOPOINT));
+    assert!(tun.unwrap_err().contains("point-to-point or VPN"));
+    // WireGuard reports an all-zero MAC rather than none
+    let wg = interface("wg0", Some(MacAddr::zero()), IFF_UP | IFF_POINTOPOINT);
+    assert!(arpcache::arp_capable(&wg).is_err());
+    let lo = interface("lo", Some(MacAddr::zero()), IFF_UP | IFF_LOOPBACK);
+    assert!(arpcache::arp_capable(&lo).unwrap_err().contains("loopback"));
+    let down = interface("eth1", Some(mac), 0);
+    assert_eq!(
+        arpcache::arp_capable(&down),
+        Err("Interface eth1 is down".to_string())
+    );
+}
+
+/// Runs the subset of classic BPF that `ARP_FILTER` uses; true if the frame is kept.
+fn filter_accepts(frame: &[u8]) -> bool {
+    let mut pc = 0;
+    let mut acc = 0u32;
+    loop {
+        let (code, jt, jf, k) = ARP_FILTER[pc];
+        pc += 1;
+        match code {
+            0x28 => {
+                let at = k as usize;
+                let Some(half) = frame.get(at..at + 2) else {
+                    return false;
+                };
+                acc = u16::from_be_bytes([half[0], half[1]]) as u32;
+            }
+            0x15 => pc += if acc == k { jt } else { jf } as usize,
+            0x06 => return k > 0,
+            _ => panic!("unexpected BPF opcode {code:#x}"),
+        }
+    }
+}
+
+#[test]
+fn test_packet_filter_keeps_only_arp() {
+    let src_mac = MacAddr::new(0x02, 0, 0, 0, 0, 1);
+    let src_ip = Ipv4Addr::new(192, 168, 1, 2);
+    let target = Ipv4Addr::new(192, 168, 1, 20);
+    let untagged = build_arp_request(src_mac, src_ip, target, None).unwrap();
+    let tagged = build_arp_request(src_mac, src_ip, target, Some(30)).unwrap();
+    assert!(filter_accepts(&untagged));
+    assert!(filter_accepts(&tagged));
+
+    let mut ipv4 = untagged.clone();
+    ipv4[12..14].copy_from_slice(&[0x08, 0x00]);
+    assert!(!filter_accepts(&ipv4));
+    let mut tagged_ipv4 = tagged.clone();
+    tagged_ipv4[16..18].copy_from_slice(&[0x08, 0x00]);
+    assert!(!filter_accepts(&tagged_ipv4));
+    assert!(!filter_accepts(&untagged[..10]));
+}
@@ -0,0 +1,47 @@
+use rust_backend::utils::autotune::{
+    DEFAULT_CONCURRENCY, MAX_CONCURRENCY, MIN_CONCURRENCY, SystemLimits, parse_mem_available,
+    parse_open_files,
+};
+
+#[test]
+fn test_parse_proc_listings() {
+    let limits = "Limit                     Soft Limit           Hard Limit           Units     \n\
+                  Max cpu time              unlimited            unlimited            seconds   \n\
+                  Max open files            1024                 524288               files     \n";
+    assert_eq!(parse_open_files(limits), Some(1024));
+    assert_eq!(
+        parse_open_files(
+            "Max open files            unlimited            unlimited            files"
+        ),
+        None
+    );
+    let meminfo =
+        "MemTotal:       16318480 kB\nMemFree:         1203340 kB\nMemAvailable:    8159240 kB\n";
+    assert_eq!(parse_mem_available(meminfo), Some(8159240 * 1024));
+}
+
+#[test]
+fn test_concurrency_follows_the_tightest_limit() {
+    assert_eq!(SystemLimits::default().concurrency(), DEFAULT_CONCURRENCY);
+
+    let mut limits = SystemLimits {
+        open_files: Some(1024),
+        available_memory: Some(8 * 1024 * 1024 * 1024),
+        conntrack_max: None,
+        conntrack_count: None,
+    };
+    assert_eq!(limits.concurrency(), 1024 - 128);
+
+    limits.conntrack_max = Some(65536);
+    limits.conntrack_count = Some(65000);
+    assert_eq!(limits.concurrency(), 268);
+
+    limits.open_files = Some(100);
+    assert_eq!(limits.concurrency(), MIN_CONCURRENCY);
+
+    let big = SystemLimits {
+        open_files: Some(1 << 20),
+        ..SystemLimits::default()
+    };
+    assert_eq!(big.concurrency(), MAX_CONCURRENCY);
+}
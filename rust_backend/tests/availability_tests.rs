@@ -0,0 +1,121 @@
+use chrono::{Duration, TimeZone, Utc};
+use rust_backend::utils::availability::{AvailabilityTracker, StateChange};
+use std::net::IpAddr;
+
+fn ip(text: &str) -> IpAddr {
+    text.parse().unwrap()
+}
+
+fn ports(range: std::ops::Range<u16>) -> Vec<u16> {
+    range.collect()
+}
+
+#[test]
+fn test_availability_and_flaps() {
+    let web = ip("10.0.0.5");
+    let db = ip("10.0.0.6");
+    let targets = [web, db];
+    let start = Utc.with_ymd_and_hms(2025, 5, 1, 0, 0, 0).unwrap();
+    let mut tracker = AvailabilityTracker::new();
+
+    // up, up, down, up
+    let runs: [&[(IpAddr, u16)]; 4] = [
+        &[(web, 443), (db, 5432)],
+        &[(web, 443), (db, 5432)],
+        &[(web, 443)],
+        &[(web, 443), (db, 5432)],
+    ];
+    let mut changes = Vec::new();
+    for (hour, open) in runs.iter().enumerate() {
+        let now = start + Duration::hours(hour as i64);
+        changes.extend(tracker.record(&targets, &ports(1..10000), open, now));
+    }
+    assert_eq!(
+        changes,
+        vec![
+            StateChange {
+                ip: db,
+                port: 5432,
+                up: false
+            },
+            StateChange {
+                ip: db,
+                port: 5432,
+                up: true
+            },
+        ]
+    );
+
+    let web_443 = tracker.get(web, 443).unwrap();
+    assert_eq!((web_443.checks, web_443.flaps), (4, 0));
+    assert_eq!(web_443.availability(), 100.0);
+    let db_5432 = tracker.get(db, 5432).unwrap();
+    assert_eq!(
+        (db_5432.checks, db_5432.up_checks, db_5432.flaps),
+        (4, 3, 2)
+    );
+    assert_eq!(db_5432.availability(), 75.0);
+    assert!(db_5432.up);
+    assert_eq!(db_5432.first_seen, start);
+    assert_eq!(db_5432.last_change, start + Duration::hours(3));
+}
+
+#[test]
+fn test_unscanned_services_are_not_counted() {
+    let host = ip("10.0.0.5");
+    let now = Utc.with_ymd_and_hms(2025, 5, 1, 0, 0, 0).unwrap();
+    let mut tracker = AvailabilityTracker::new();
+    tracker.record(&[host], &ports(1..1025), &[(host, 22), (host, 443)], now);
+
+    // A narrower port set and a scan of other targets leave the history alone
+    assert!(
+        tracker
+            .record(&[host], &ports(20..30), &[(host, 22)], now)
+            .is_empty()
+    );
+    assert!(
+        tracker
+            .record(&[ip("10.0.0.9")], &ports(1..1025), &[], now)
+            .is_empty()
+    );
+    assert_eq!(tracker.get(host, 443).unwrap().checks, 1);
+    assert_eq!(tracker.get(host, 22).unwrap().checks, 2);
+
+    // The host dropping out of discovery counts as every service down
+    let changes = tracker.record(&[host], &ports(1..1025), &[], now);
+    assert_eq!(changes.len(), 2);
+    assert!(changes.iter().all(|change| !change.up));
+}
+
+#[test]
+fn test_history_round_trips() {
+    let host = ip("fd00::5");
+    let now = Utc.with_ymd_and_hms(2025, 5, 1, 12, 0, 0).unwrap();
+    let mut tracker = AvailabilityTracker::new();
+    tracker.record(&[host], &ports(1..1025), &[(host, 22)], now);
+    tracker.record(&[host], &ports(1..1025), &[], now + Duration::hours(1));
+
+    let path =
+        std::env::temp_dir().join(format!("netscan-availability-{}.csv", std::process::id()));
+    tracker.save(&path).unwrap();
+    let loaded = AvailabilityTracker::load(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(loaded, tracker);
+    assert!(!loaded.get(host, 22).unwrap().up);
+}
+
+#[test]
+fn test_only_listed_ports_are_counted() {
+    let host = ip("10.0.0.5");
+    let now = Utc.with_ymd_and_hms(2025, 5, 1, 0, 0, 0).unwrap();
+    let mut tracker = AvailabilityTracker::new();
+    tracker.record(&[host], &[22, 8080], &[(host, 22), (host, 8080)], now);
+
+    // A port list like --top-ports does not cover the ports between its members
+    assert!(
+        tracker
+            .record(&[host], &[22, 9000], &[(host, 22)], now)
+            .is_empty()
+    );
+    assert_eq!(tracker.get(host, 8080).unwrap().checks, 1);
+}
@@ -0,0 +1,73 @@
+use rust_backend::scanners::service_detection::ServiceDetectionResult;
+use rust_backend::utils::banner_parse::{self, BannerInfo};
+
+fn info(product: &str, version: Option<&str>, os_hint: Option<&str>) -> BannerInfo {
+    BannerInfo {
+        product: Some(product.to_string()),
+        version: version.map(str::to_string),
+        os_hint: os_hint.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_ssh_software_field() {
+    assert_eq!(
+        banner_parse::parse("SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.6\r\n"),
+        info("OpenSSH", Some("8.9p1"), Some("Linux (Ubuntu)"))
+    );
+    assert_eq!(
+        banner_parse::parse("SSH-2.0-dropbear_2020.81"),
+        info("dropbear", Some("2020.81"), None)
+    );
+    assert_eq!(
+        banner_parse::parse("SSH-1.99-Cisco-1.25"),
+        info("Cisco", Some("1.25"), Some("Cisco IOS"))
+    );
+    assert_eq!(
+        banner_parse::parse("SSH-2.0-RomSShell"),
+        info("RomSShell", None, None)
+    );
+}
+
+#[test]
+fn test_ftp_and_smtp_greetings() {
+    assert_eq!(
+        banner_parse::parse("220 (vsFTPd 3.0.3)"),
+        info("vsftpd", Some("3.0.3"), None)
+    );
+    assert_eq!(
+        banner_parse::parse("220 ProFTPD 1.3.5e Server (Debian) [::ffff:10.0.0.5]"),
+        info("ProFTPD", Some("1.3.5e"), Some("Linux (Debian)"))
+    );
+    assert_eq!(
+        banner_parse::parse("220 mail.example.com ESMTP Postfix (Ubuntu)"),
+        info("Postfix", None, Some("Linux (Ubuntu)"))
+    );
+    assert_eq!(
+        banner_parse::parse("220 mx.example.com ESMTP Sendmail 8.15.2/8.15.2; Mon, 1 Jan 2024"),
+        info("Sendmail", Some("8.15.2"), None)
+    );
+    assert_eq!(
+        banner_parse::parse("220 mx ESMTP Exim 4.96 Mon, 01 Jan 2024 10:00:00 +0000"),
+        info("Exim", Some("4.96"), None)
+    );
+    assert_eq!(
+        banner_parse::parse("220 Microsoft FTP Service"),
+        info("Microsoft FTP Service", None, Some("Windows"))
+    );
+    assert_eq!(banner_parse::parse("220 Welcome"), BannerInfo::default());
+}
+
+#[test]
+fn test_result_carries_banner_fields() {
+    let res = ServiceDetectionResult::new(22, Some("SSH".to_string()), None, Vec::new())
+        .with_banner_info(Some("SSH-2.0-OpenSSH_9.6 FreeBSD-20240104"));
+    assert_eq!(res.product.as_deref(), Some("OpenSSH"));
+    assert_eq!(res.version.as_deref(), Some("9.6"));
+    assert_eq!(res.os_hint.as_deref(), Some("FreeBSD"));
+    assert_eq!(res.software().as_deref(), Some("OpenSSH 9.6"));
+
+    let unparsed = ServiceDetectionResult::new(21, Some("FTP".to_string()), None, Vec::new())
+        .with_banner_info(None);
+    assert_eq!(unparsed.software(), None);
+}
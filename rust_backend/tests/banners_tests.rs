@@ -0,0 +1,95 @@
+use rust_backend::utils::banners::{self, BannerSource, LoginBanner};
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+fn banner(source: BannerSource, text: &str) -> LoginBanner {
+    LoginBanner {
+        ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+        port: 21,
+        source,
+        text: text.to_string(),
+        reached_userauth: false,
+    }
+}
+
+#[test]
+fn test_missing_legal_warning_finding() {
+    let plain = banner(BannerSource::Ftp, "220 ProFTPD Server (Debian)\r\n");
+    let finding = plain.finding().unwrap();
+    assert_eq!(finding.title, banners::MISSING_LEGAL_BANNER);
+
+    let legal = banner(
+        BannerSource::Ftp,
+        "220-Authorized use only. Activity is monitored.\r\n220 FTP ready\r\n",
+    );
+    assert!(legal.has_legal_warning());
+    assert_eq!(legal.finding(), None);
+}
+
+#[test]
+fn test_ssh_identification_alone_is_not_judged() {
+    let ident = banner(BannerSource::Ssh, "SSH-2.0-OpenSSH_9.6\r\n");
+    assert_eq!(ident.finding(), None);
+    let pre = banner(
+        BannerSource::Ssh,
+        "Welcome to build01\r\nSSH-2.0-OpenSSH_9.6\r\n",
+    );
+    assert_eq!(
+        banners::ssh_pre_identification(&pre.text),
+        vec!["Welcome to build01"]
+    );
+    assert!(pre.finding().is_some());
+}
+
+#[test]
+fn test_ssh_userauth_banner_is_judged() {
+    let stderr = "Warning: Permanently added '[10.0.0.1]:22' (ED25519) to the list of known hosts.\r\n\
+                  Authorized use only.\r\n\
+                  netscan@10.0.0.1: Permission denied (publickey,password).\r\n";
+    let text = banners::parse_ssh_client_output(stderr).unwrap();
+    assert_eq!(text.trim(), "Authorized use only.");
+    let refused = "ssh: connect to host 10.0.0.1 port 22: Connection refused\n";
+    assert_eq!(banners::parse_ssh_client_output(refused), None);
+
+    let mut silent = banner(BannerSource::Ssh, "SSH-2.0-OpenSSH_9.6\r\n");
+    silent.reached_userauth = true;
+    assert!(silent.is_judged());
+    assert!(silent.finding().is_some());
+}
+
+#[test]
+fn test_strip_telnet_negotiation() {
+    let input = [
+        0xff, 0xfd, 0x18, // DO terminal type
+        0xff, 0xfb, 0x01, // WILL echo
+        b'l', b'o', b'g', b'i', b'n', b':',
+    ];
+    let (text, replies) = banners::strip_telnet_negotiation(&input);
+    assert_eq!(text, b"login:");
+    assert_eq!(replies, vec![0xff, 0xfc, 0x18, 0xff, 0xfe, 0x01]);
+}
+
+#[tokio::test]
+async fn test_capture_multiline_ftp_greeting() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        socket
+            .write_all(b"220-WARNING: unauthorized access is prohibited\r\n")
+            .await
+            .unwrap();
+        socket
+            .write_all(b"220 vsFTPd 3.0.5 ready\r\n")
+            .await
+            .unwrap();
+        let mut buf = [0u8; 16];
+        let _ = socket.read(&mut buf).await;
+    });
+    let captured = banners::capture(IpAddr::V4(Ipv4Addr::LOCALHOST), port, BannerSource::Ftp)
+        .await
+        .unwrap();
+    assert!(captured.text.contains("vsFTPd"));
+    assert!(captured.has_legal_warning());
+}
@@ -0,0 +1,111 @@
+use chrono::{TimeZone, Utc};
+use rust_backend::detect_tls::CertificateInfo;
+use rust_backend::utils::cert_inventory::{CertificateChange, CertificateInventory};
+use std::net::{IpAddr, Ipv4Addr};
+
+fn certificate(serial: &str, subject: &str, expires_month: u32) -> CertificateInfo {
+    CertificateInfo {
+        serial: serial.to_string(),
+        subject: subject.to_string(),
+        issuer: "Example CA".to_string(),
+        sans: vec![subject.to_string()],
+        key_algorithm: "EC".to_string(),
+        key_bits: Some(256),
+        key_fingerprint: format!("{:016x}", expires_month),
+        not_before: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(),
+        not_after: Utc
+            .with_ymd_and_hms(2027, expires_month, 1, 0, 0, 0)
+            .unwrap(),
+        self_signed: false,
+    }
+}
+
+#[test]
+fn test_inventory_groups_endpoints_and_sorts_by_expiry() {
+    let wildcard = certificate("0a", "*.example.com", 9);
+    let mail = certificate("0b", "mail.example.com", 2);
+    let a = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let b = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+    let mut inventory = CertificateInventory::new();
+    assert!(inventory.record(a, 443, &wildcard));
+    assert!(!inventory.record(b, 8443, &wildcard));
+    assert!(!inventory.record(a, 443, &wildcard));
+    assert!(inventory.record(b, 993, &mail));
+
+    assert_eq!(inventory.len(), 2);
+    let entries = inventory.entries();
+    assert_eq!(entries[0].certificate.subject, "mail.example.com");
+    assert_eq!(entries[1].endpoints, vec![(a, 443), (b, 8443)]);
+}
+
+#[test]
+fn test_inventory_order_does_not_depend_on_scan_order() {
+    let a = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let b = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+    // Same expiry, so only the subject tells them apart
+    let web = certificate("0a", "www.example.com", 4);
+    let api = certificate("0b", "api.example.com", 4);
+
+    let mut forward = CertificateInventory::new();
+    forward.record(a, 443, &web);
+    forward.record(b, 443, &web);
+    forward.record(b, 8443, &api);
+    let mut backward = CertificateInventory::new();
+    backward.record(b, 8443, &api);
+    backward.record(b, 443, &web);
+    backward.record(a, 443, &web);
+
+    assert_eq!(forward.entries(), backward.entries());
+    assert_eq!(forward.entries()[0].certificate.subject, "api.example.com");
+    assert_eq!(backward.entries()[1].endpoints, vec![(a, 443), (b, 443)]);
+}
+
+#[test]
+fn test_inventory_round_trips_through_state_file() {
+    let mut inventory = CertificateInventory::new();
+    inventory.record(
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+        443,
+        &certificate("0a", "a.example.com", 3),
+    );
+    inventory.record(
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+        443,
+        &certificate("0a", "a.example.com", 3),
+    );
+    inventory.record(
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 3)),
+        636,
+        &certificate("0c", "dc.example.com", 5),
+    );
+
+    let path = std::env::temp_dir().join(format!("netscan-certs-{}.csv", std::process::id()));
+    inventory.save(&path).unwrap();
+    let loaded = CertificateInventory::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded.entries(), inventory.entries());
+}
+
+#[test]
+fn test_changes_since_reports_issuer_and_key() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let mut before = CertificateInventory::new();
+    before.record(ip, 443, &certificate("0a", "www.example.com", 3));
+
+    let mut renewed = certificate("0b", "www.example.com", 9);
+    renewed.issuer = "Other CA".to_string();
+    let mut after = CertificateInventory::new();
+    after.record(ip, 443, &renewed);
+    after.record(ip, 8443, &certificate("0c", "new.example.com", 9));
+
+    let changes = after.changes_since(&before);
+    assert_eq!(changes.len(), 2);
+    assert_eq!(
+        changes[0].to_string(),
+        "10.0.0.1:443 issuer changed from Example CA to Other CA"
+    );
+    assert!(matches!(changes[1], CertificateChange::KeyChanged { .. }));
+}
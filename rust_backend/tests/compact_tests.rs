@@ -0,0 +1,81 @@
+use rust_backend::utils::compact::{Interner, PortState, PortStates, ProbeTable};
+use rust_backend::utils::errors::ErrorCategory;
+use std::net::{IpAddr, Ipv4Addr};
+
+#[test]
+fn test_port_states_round_trip() {
+    let mut states = PortStates::new();
+    states.set(443, PortState::Open);
+    states.set(444, PortState::Failed(ErrorCategory::Refused));
+    states.set(22, PortState::Failed(ErrorCategory::Timeout)); // Below the first port recorded
+
+    assert_eq!(states.get(443), PortState::Open);
+    assert_eq!(states.get(444), PortState::Failed(ErrorCategory::Refused));
+    assert_eq!(states.get(22), PortState::Failed(ErrorCategory::Timeout));
+    assert_eq!(states.get(80), PortState::Unscanned);
+    assert_eq!(states.get(65535), PortState::Unscanned);
+    let ports: Vec<u16> = states.iter().map(|(port, _)| port).collect();
+    assert_eq!(ports, vec![22, 443, 444]);
+}
+
+#[test]
+fn test_interner_stores_each_message_once() {
+    let mut interner = Interner::new();
+    let a = interner.intern("Network is unreachable (os error 101)");
+    let b = interner.intern("Network is unreachable (os error 101)");
+    assert_eq!(a, b);
+    assert_eq!(interner.len(), 1);
+    assert_eq!(interner.resolve(a), "Network is unreachable (os error 101)");
+}
+
+#[test]
+fn test_probe_table_views_and_memory() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let mut table = ProbeTable::new();
+    for port in 1..=1024 {
+        if port == 22 {
+            table.add_open_port(ip, port);
+        } else {
+            table.add_port_error(ip, port, ErrorCategory::Refused, "Connection refused");
+        }
+    }
+    table.add_port_error(
+        ip,
+        2000,
+        ErrorCategory::Unreachable,
+        "No route to host (os error 113)",
+    );
+
+    assert_eq!(table.open_ports().len(), 1);
+    assert_eq!(
+        table.open_ports().iter().collect::<Vec<_>>(),
+        vec![(ip, 22)]
+    );
+    assert_eq!(table.errors().len(), 1024);
+    let last = table.errors().iter().last().unwrap();
+    assert_eq!(
+        last.to_string(),
+        "10.0.0.1:2000 - No route to host (os error 113)"
+    );
+    // ~1 KB of packed states instead of a String per refused port
+    assert!(table.heap_bytes() < 2048, "{} bytes", table.heap_bytes());
+
+    let mut merged = ProbeTable::new();
+    merged.merge(table);
+    assert_eq!(merged.errors().len(), 1024);
+    assert_eq!(merged.state(ip, 22), PortState::Open);
+}
+
+#[test]
+fn test_reclassified_probe_is_counted_once() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let mut table = ProbeTable::new();
+    table.add_port_error(ip, 161, ErrorCategory::Other, "Message too long");
+    table.add_port_error(ip, 161, ErrorCategory::Refused, "Connection refused");
+
+    assert_eq!(table.errors().len(), 1);
+    assert_eq!(table.error_counts()[&ip][&ErrorCategory::Other], 0);
+    assert_eq!(table.error_counts()[&ip][&ErrorCategory::Refused], 1);
+    let error = table.errors().iter().next().unwrap();
+    assert_eq!(error.message, "Connection refused");
+}
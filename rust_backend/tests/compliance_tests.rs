@@ -0,0 +1,119 @@
+use rust_backend::utils::compliance::{
+    self, Check, ComplianceMatrix, ComplianceProfile, ControlStatus,
+};
+use rust_backend::utils::findings::{Finding, Severity, code};
+use std::net::{IpAddr, Ipv4Addr};
+
+const ALL_CHECKS: &[Check] = &[
+    Check::WeakProtocols,
+    Check::TlsGrade,
+    Check::LoginBanners,
+    Check::SmbSigning,
+];
+
+fn finding(severity: Severity, port: u16, code: &'static str) -> Finding {
+    Finding::new(severity, Some(port), "Reworded title", "detail".to_string()).with_code(code)
+}
+
+#[test]
+fn test_findings_fail_the_mapped_controls() {
+    let found = vec![
+        finding(Severity::High, 23, code::TELNET),
+        finding(Severity::Medium, 443, code::CERT_SELF_SIGNED),
+    ];
+    let host = compliance::evaluate(
+        ComplianceProfile::CisBasic,
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+        &found,
+        ALL_CHECKS,
+    );
+
+    let failed: Vec<&str> = host
+        .failed_controls()
+        .iter()
+        .map(|r| r.control.id)
+        .collect();
+    assert_eq!(failed, vec!["CIS-1", "CIS-4"]);
+    assert_eq!(host.risk_score, 14);
+    assert!(host.results[0].evidence[0].contains("(port 23)"));
+}
+
+#[test]
+fn test_controls_match_codes_not_titles() {
+    let untagged = Finding::new(Severity::High, Some(23), "Telnet", "detail".to_string());
+    let host = compliance::evaluate(
+        ComplianceProfile::CisBasic,
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+        &[untagged],
+        ALL_CHECKS,
+    );
+    assert!(host.passed());
+}
+
+#[test]
+fn test_risk_control_fails_at_threshold() {
+    let found = vec![Finding::new(
+        Severity::Critical,
+        Some(5900),
+        "Unrelated",
+        "detail".to_string(),
+    )];
+    let host = compliance::evaluate(
+        ComplianceProfile::Pci,
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+        &found,
+        ALL_CHECKS,
+    );
+    let failed: Vec<&str> = host
+        .failed_controls()
+        .iter()
+        .map(|r| r.control.id)
+        .collect();
+    assert_eq!(failed, vec!["6.3.1"]);
+}
+
+#[test]
+fn test_controls_without_evidence_are_not_assessed() {
+    let host = compliance::evaluate(
+        ComplianceProfile::CisBasic,
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+        &[],
+        &[Check::WeakProtocols],
+    );
+    let status = |id: &str| {
+        host.results
+            .iter()
+            .find(|r| r.control.id == id)
+            .map(|r| r.status)
+            .unwrap()
+    };
+
+    assert_eq!(status("CIS-1"), ControlStatus::Pass);
+    assert_eq!(status("CIS-3"), ControlStatus::NotAssessed);
+    assert_eq!(status("CIS-5"), ControlStatus::NotAssessed);
+    assert_eq!(status("CIS-7"), ControlStatus::Pass);
+    assert_eq!(status("CIS-8"), ControlStatus::NotAssessed);
+    assert!(host.passed());
+    assert!(ComplianceProfile::CisBasic.uses(Check::SmbSigning));
+    assert!(!ComplianceProfile::Pci.uses(Check::SmbSigning));
+}
+
+#[test]
+fn test_matrix_rows_sorted_by_host() {
+    let mut matrix = ComplianceMatrix::new(ComplianceProfile::Pci);
+    matrix.add_host(
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 9)),
+        &[finding(Severity::High, 445, code::SMB1)],
+        ALL_CHECKS,
+    );
+    matrix.add_host(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), &[], ALL_CHECKS);
+
+    let hosts = matrix.hosts();
+    assert_eq!(hosts[0].ip, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+    assert!(hosts[0].passed());
+    assert!(!hosts[1].passed());
+    assert_eq!(
+        hosts[1].results.len(),
+        ComplianceProfile::Pci.controls().len()
+    );
+}
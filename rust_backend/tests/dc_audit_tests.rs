@@ -0,0 +1,73 @@
+use rust_backend::detect_ldap::LdapDetection;
+use rust_backend::detect_ntp::NtpDetection;
+use rust_backend::detect_ntp::{build_request, parse_response};
+use rust_backend::detect_smb::SmbDetection;
+use rust_backend::scanners::dc_audit::DcAuditReport;
+use rust_backend::utils::findings::{Severity, code};
+use std::net::{IpAddr, Ipv4Addr};
+
+fn smb(signing_required: bool) -> SmbDetection {
+    SmbDetection {
+        detected: true,
+        dialect: Some("3.0.2".to_string()),
+        signing_enabled: true,
+        signing_required,
+        smb1_accepted: false,
+        auth_mechanisms: vec!["NTLM".to_string()],
+        server_time: None,
+        host_info: None,
+        error: None,
+    }
+}
+
+#[test]
+fn test_assess_flags_unsigned_smb_and_skew() {
+    let mut report = DcAuditReport::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 10)), &[88, 389, 445]);
+    report.smb = Some(smb(false));
+    report.ldap = Some(LdapDetection {
+        detected: true,
+        root_dse: vec![(
+            "ldapServiceName".to_string(),
+            vec!["corp.example.com:dc01$@CORP.EXAMPLE.COM".to_string()],
+        )],
+        error: None,
+    });
+    report.ntp = Some(NtpDetection {
+        detected: true,
+        stratum: Some(3),
+        offset_secs: Some(-900.0),
+        error: None,
+    });
+    report.assess();
+
+    assert_eq!(report.realm().as_deref(), Some("CORP.EXAMPLE.COM"));
+    assert_eq!(report.findings[0].severity, Severity::High);
+    assert_eq!(report.findings[0].title, "SMB signing not required");
+    assert_eq!(
+        report.findings[0].code,
+        Some(code::SMB_SIGNING_NOT_REQUIRED)
+    );
+    let titles: Vec<&str> = report.findings.iter().map(|f| f.title.as_str()).collect();
+    assert!(titles.contains(&"Clock skew exceeds Kerberos tolerance"));
+    assert!(titles.contains(&"No LDAPS listener"));
+}
+
+#[test]
+fn test_assess_quiet_for_hardened_dc() {
+    let mut report = DcAuditReport::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 11)), &[88, 389, 445, 636]);
+    report.smb = Some(smb(true));
+    report.assess();
+    assert!(report.findings.iter().all(|f| f.severity == Severity::Info));
+}
+
+#[test]
+fn test_ntp_response_parsing() {
+    assert_eq!(build_request()[0], 0x1b);
+    let mut reply = [0u8; 48];
+    reply[0] = 0x1c; // version 3, server mode
+    reply[1] = 2;
+    reply[40..44].copy_from_slice(&(1_767_225_600u32.wrapping_add(2_208_988_800)).to_be_bytes());
+    let (stratum, time) = parse_response(&reply).unwrap();
+    assert_eq!(stratum, 2);
+    assert_eq!(time, 1_767_225_600.0);
+}
@@ -0,0 +1,55 @@
+use rust_backend::detect_couchdb;
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[test]
+fn test_parse_welcome() {
+    let reply = "HTTP/1.1 200 OK\r\nServer: CouchDB/3.3.2 (Erlang OTP/24)\r\n\r\n{\"couchdb\":\"Welcome\",\"version\":\"3.3.2\",\"vendor\":{\"name\":\"The Apache Software Foundation\"}}\n";
+    assert_eq!(
+        detect_couchdb::parse_welcome(reply),
+        Some(Some("3.3.2".to_string()))
+    );
+    assert_eq!(
+        detect_couchdb::parse_welcome("HTTP/1.1 200 OK\r\n\r\n{}"),
+        None
+    );
+}
+
+#[test]
+fn test_count_databases() {
+    assert_eq!(
+        detect_couchdb::count_databases("[\"_users\",\"orders\"]\n"),
+        Some(2)
+    );
+    assert_eq!(detect_couchdb::count_databases("[]"), Some(0));
+    assert_eq!(detect_couchdb::count_databases("{\"error\":1}"), None);
+}
+
+#[tokio::test]
+async fn test_detect_lists_databases_without_auth() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        for _ in 0..2 {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let body = if request.starts_with("GET /_all_dbs ") {
+                "[\"_replicator\",\"_users\",\"orders\"]"
+            } else {
+                "{\"couchdb\":\"Welcome\",\"version\":\"2.3.1\"}"
+            };
+            let reply = format!("HTTP/1.1 200 OK\r\n\r\n{}", body);
+            socket.write_all(reply.as_bytes()).await.unwrap();
+        }
+    });
+    let couch = detect_couchdb::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(couch.detected);
+    assert!(couch.anonymous_read());
+    assert_eq!(
+        couch.summary(),
+        "CouchDB 2.3.1 (no authentication, 3 database(s) listed)"
+    );
+}
@@ -0,0 +1,188 @@
+use rust_backend::detect_db;
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Protocol v10 handshake packet announcing `version` and `plugin`
+fn mysql_greeting(version: &str, plugin: &str) -> Vec<u8> {
+    let mut payload = vec![10];
+    payload.extend_from_slice(version.as_bytes());
+    payload.push(0);
+    payload.extend_from_slice(&42u32.to_le_bytes()); // connection id
+    payload.extend_from_slice(b"abcdefgh\0"); // auth data part 1 + filler
+    payload.extend_from_slice(&[0xff, 0xf7, 0xff, 0x02, 0x00, 0xff, 0xdf]);
+    payload.push(21); // auth data length
+    payload.extend_from_slice(&[0; 10]);
+    payload.extend_from_slice(b"ijklmnopqrst\0");
+    payload.extend_from_slice(plugin.as_bytes());
+    payload.push(0);
+    let mut packet = (payload.len() as u32).to_le_bytes()[..3].to_vec();
+    packet.push(0);
+    packet.extend(payload);
+    packet
+}
+
+/// One PostgreSQL backend message
+fn pg_message(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut message = vec![kind];
+    message.extend_from_slice(&((body.len() + 4) as i32).to_be_bytes());
+    message.extend_from_slice(body);
+    message
+}
+
+#[test]
+fn test_parse_mysql_greeting() {
+    let mysql = detect_db::parse_mysql_greeting(&mysql_greeting("8.0.36", "caching_sha2_password"))
+        .unwrap();
+    assert!(mysql.detected);
+    assert_eq!(
+        mysql.summary(),
+        "MySQL 8.0.36 (auth: caching_sha2_password)"
+    );
+
+    let mariadb = detect_db::parse_mysql_greeting(&mysql_greeting(
+        "5.5.5-10.11.6-MariaDB",
+        "mysql_native_password",
+    ))
+    .unwrap();
+    assert_eq!(
+        mariadb.summary(),
+        "MariaDB 10.11.6 (auth: mysql_native_password)"
+    );
+
+    let mut refused = vec![0xff, 0x6a, 0x04];
+    refused.extend_from_slice(b"Host '10.0.0.9' is not allowed to connect to this MySQL server");
+    let mut packet = (refused.len() as u32).to_le_bytes()[..3].to_vec();
+    packet.push(0);
+    packet.extend(refused);
+    let mysql = detect_db::parse_mysql_greeting(&packet).unwrap();
+    assert!(mysql.detected);
+    assert!(
+        mysql
+            .summary()
+            .starts_with("MySQL (refused: Host '10.0.0.9'")
+    );
+
+    assert!(detect_db::parse_mysql_greeting(b"SSH-2.0-OpenSSH_9.6\r\n").is_none());
+}
+
+#[test]
+fn test_startup_message_encoding() {
+    let request = detect_db::build_ssl_request();
+    assert_eq!(request, [0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f]);
+
+    let startup = detect_db::build_startup_message("netscan", "netscan");
+    assert_eq!(
+        i32::from_be_bytes(startup[..4].try_into().unwrap()) as usize,
+        startup.len()
+    );
+    assert_eq!(&startup[4..8], &[0, 3, 0, 0]);
+    assert!(startup.ends_with(b"database\0netscan\0\0"));
+}
+
+#[test]
+fn test_parse_startup_response() {
+    let mut sasl = 10i32.to_be_bytes().to_vec();
+    sasl.extend_from_slice(b"SCRAM-SHA-256-PLUS\0SCRAM-SHA-256\0\0");
+    let postgres = detect_db::parse_startup_response(&pg_message(b'R', &sasl)).unwrap();
+    assert_eq!(
+        postgres.auth.as_deref(),
+        Some("SCRAM-SHA-256-PLUS/SCRAM-SHA-256")
+    );
+
+    let error = pg_message(
+        b'E',
+        b"SFATAL\0C28000\0Mno pg_hba.conf entry for host \"10.0.0.9\"\0\0",
+    );
+    let postgres = detect_db::parse_startup_response(&error).unwrap();
+    assert_eq!(
+        postgres.summary(),
+        "PostgreSQL (refused: no pg_hba.conf entry for host \"10.0.0.9\")"
+    );
+
+    // Trust authentication: the server reports its version right away
+    let mut trusted = pg_message(b'R', &0i32.to_be_bytes());
+    trusted.extend(pg_message(b'S', b"server_version\x0016.2\0"));
+    trusted.extend(pg_message(b'Z', b"I"));
+    let postgres = detect_db::parse_startup_response(&trusted).unwrap();
+    assert!(postgres.trust_auth());
+    assert_eq!(postgres.summary(), "PostgreSQL 16.2 (no authentication)");
+
+    assert!(detect_db::parse_startup_response(b"HTTP/1.1 400 Bad Request\r\n").is_none());
+}
+
+#[test]
+fn test_parse_redis_replies() {
+    assert_eq!(
+        detect_db::build_redis_command(&["PING"]),
+        b"*1\r\n$4\r\nPING\r\n"
+    );
+
+    let open = detect_db::parse_ping_reply(b"+PONG\r\n").unwrap();
+    assert!(open.anonymous_access());
+    let locked = detect_db::parse_ping_reply(b"-NOAUTH Authentication required.\r\n").unwrap();
+    assert!(locked.auth_required);
+    assert_eq!(locked.summary(), "Redis (authentication required)");
+    let protected =
+        detect_db::parse_ping_reply(b"-DENIED Redis is running in protected mode\r\n").unwrap();
+    assert!(protected.protected_mode);
+    assert!(detect_db::parse_ping_reply(b"220 mail ESMTP\r\n").is_none());
+
+    let mut detection = open;
+    detect_db::parse_info(
+        "$120\r\n# Server\r\nredis_version:7.2.4\r\nredis_mode:standalone\r\nos:Linux 6.1.0-18-amd64 x86_64\r\n",
+        &mut detection,
+    );
+    assert_eq!(detection.os.as_deref(), Some("Linux 6.1.0-18-amd64 x86_64"));
+    assert_eq!(
+        detection.summary(),
+        "Redis 7.2.4 (standalone, no authentication)"
+    );
+}
+
+#[tokio::test]
+async fn test_detect_redis_without_auth() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0))
+        .await
+        .unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        for reply in [
+            "+PONG\r\n",
+            "$40\r\n# Server\r\nredis_version:6.2.14\r\nredis_mode:standalone\r\n",
+        ] {
+            let mut buf = [0u8; 256];
+            let _ = socket.read(&mut buf).await.unwrap();
+            socket.write_all(reply.as_bytes()).await.unwrap();
+        }
+    });
+    let redis = detect_db::detect_redis(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(redis.detected);
+    assert_eq!(
+        redis.summary(),
+        "Redis 6.2.14 (standalone, no authentication)"
+    );
+}
+
+#[tokio::test]
+async fn test_detect_postgres_without_ssl() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0))
+        .await
+        .unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 256];
+        let _ = socket.read(&mut buf).await.unwrap();
+        socket.write_all(b"N").await.unwrap();
+        let _ = socket.read(&mut buf).await.unwrap();
+        socket
+            .write_all(&pg_message(b'R', &5i32.to_be_bytes()))
+            .await
+            .unwrap();
+    });
+    let postgres = detect_db::detect_postgres(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(postgres.detected);
+    assert_eq!(postgres.summary(), "PostgreSQL (no SSL, auth: MD5)");
+}
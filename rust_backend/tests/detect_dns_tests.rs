@@ -1,9 +1,9 @@
 use rust_backend::detect_dns;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr};
 
 #[tokio::test]
 async fn test_detect_dns_on_localhost() {
-    let ip = Ipv4Addr::LOCALHOST;
+    let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
     let port = 53;
     let result = detect_dns::detect(ip, port).await;
     assert!(result.detected || result.error.is_some());
@@ -11,7 +11,7 @@ async fn test_detect_dns_on_localhost() {
 
 #[tokio::test]
 async fn test_detect_dns_on_invalid_port() {
-    let ip = Ipv4Addr::LOCALHOST;
+    let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
     let port = 65000;
     let result = detect_dns::detect(ip, port).await;
     assert!(!result.detected);
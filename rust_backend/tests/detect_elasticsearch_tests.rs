@@ -0,0 +1,44 @@
+use rust_backend::detect_elasticsearch;
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const ROOT: &str = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\r\n{\n  \"name\" : \"node-1\",\n  \"cluster_name\" : \"logs\",\n  \"version\" : {\n    \"number\" : \"7.17.9\",\n    \"build_flavor\" : \"default\"\n  },\n  \"tagline\" : \"You Know, for Search\"\n}";
+
+#[test]
+fn test_parse_open_root() {
+    let es = detect_elasticsearch::parse_root(ROOT).unwrap();
+    assert!(es.anonymous_read());
+    assert_eq!(es.version.as_deref(), Some("7.17.9"));
+    assert_eq!(
+        es.summary(),
+        "Elasticsearch 7.17.9 (cluster logs, no authentication)"
+    );
+}
+
+#[test]
+fn test_parse_secured_root() {
+    let reply = "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"security\" charset=\"UTF-8\"\r\n\r\n";
+    let es = detect_elasticsearch::parse_root(reply).unwrap();
+    assert!(es.auth_required);
+    assert!(!es.anonymous_read());
+    assert_eq!(
+        detect_elasticsearch::parse_root("HTTP/1.1 200 OK\r\n\r\n<html></html>"),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_detect_open_node() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 1024];
+        let _ = socket.read(&mut buf).await.unwrap();
+        socket.write_all(ROOT.as_bytes()).await.unwrap();
+    });
+    let es = detect_elasticsearch::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(es.detected);
+    assert_eq!(es.cluster_name.as_deref(), Some("logs"));
+}
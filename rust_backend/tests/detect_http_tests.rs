@@ -1,10 +1,24 @@
 use rust_backend::detect_http;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr};
 
 #[tokio::test]
 async fn test_detect_http_on_localhost() {
-    let ip = Ipv4Addr::LOCALHOST;
+    let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
     let port = 80;
     let result = detect_http::detect(ip, port).await;
     assert!(result.detected || result.error.is_some());
-}
\ No newline at end of file
+}
+#[test]
+fn test_admin_panel_hint_recognizes_login_pages() {
+    let basic_auth = "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"router\"\r\n\r\n";
+    assert!(detect_http::admin_panel_hint(basic_auth).is_some());
+
+    let form = "HTTP/1.1 200 OK\r\n\r\n<form><input type=\"password\" name=\"pw\"></form>";
+    assert_eq!(
+        detect_http::admin_panel_hint(form).as_deref(),
+        Some("serves a password form")
+    );
+
+    let plain = "HTTP/1.1 200 OK\r\n\r\n<html><title>Welcome</title></html>";
+    assert_eq!(detect_http::admin_panel_hint(plain), None);
+}
@@ -0,0 +1,27 @@
+use rust_backend::detect_imap;
+
+#[test]
+fn test_parse_greeting_with_capabilities() {
+    let imap = detect_imap::parse_greeting(
+        "* OK [CAPABILITY IMAP4rev1 SASL-IR LITERAL+ STARTTLS LOGINDISABLED AUTH=PLAIN] Dovecot ready.\r\n",
+    )
+    .unwrap();
+    assert!(imap.starttls());
+    assert!(imap.login_disabled());
+    assert_eq!(imap.summary(), "IMAP (Dovecot ready.)");
+
+    let preauth =
+        detect_imap::parse_greeting("* PREAUTH IMAP4rev1 server logged in as admin\r\n").unwrap();
+    assert!(preauth.preauth);
+    assert!(preauth.summary().ends_with(", preauthenticated"));
+
+    assert!(detect_imap::parse_greeting("+OK POP3 ready\r\n").is_none());
+}
+
+#[test]
+fn test_parse_capability_response() {
+    let caps = detect_imap::parse_capability_response(
+        "* CAPABILITY IMAP4rev1 STARTTLS AUTH=PLAIN\r\na1 OK Capability completed.\r\n",
+    );
+    assert_eq!(caps, vec!["IMAP4rev1", "STARTTLS", "AUTH=PLAIN"]);
+}
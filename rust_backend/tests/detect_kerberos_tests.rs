@@ -0,0 +1,51 @@
+use rust_backend::detect_kerberos::{build_as_req, error_name, parse_reply};
+use rust_backend::utils::ber;
+
+fn krb_error(code: i64, realm: &str) -> Vec<u8> {
+    let general = |s: &str| ber::tlv(ber::TAG_GENERAL_STRING, s.as_bytes());
+    let fields = ber::sequence(&[
+        ber::constructed(ber::context(0), &[ber::integer(5)]),
+        ber::constructed(ber::context(1), &[ber::integer(30)]),
+        ber::constructed(
+            ber::context(4),
+            &[ber::tlv(ber::TAG_GENERALIZED_TIME, b"20260101120000Z")],
+        ),
+        ber::constructed(ber::context(5), &[ber::integer(0)]),
+        ber::constructed(ber::context(6), &[ber::integer(code)]),
+        ber::constructed(ber::context(9), &[general(realm)]),
+    ]);
+    ber::constructed(ber::application(30), &[fields])
+}
+
+#[test]
+fn test_as_req_is_well_formed() {
+    let request = build_as_req("CORP.EXAMPLE.COM", 42);
+    let (message, rest) = ber::read_tlv(&request).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(message.tag, ber::application(10));
+    let body = message.child(ber::TAG_SEQUENCE).unwrap();
+    let msg_type = body.child(ber::context(2)).unwrap();
+    assert_eq!(
+        msg_type.child(ber::TAG_INTEGER).unwrap().as_integer(),
+        Some(10)
+    );
+}
+
+#[test]
+fn test_parse_krb_error() {
+    let reply = parse_reply(&krb_error(6, "CORP.EXAMPLE.COM")).unwrap();
+    assert!(reply.detected);
+    assert!(!reply.as_rep_without_preauth);
+    assert_eq!(reply.realm.as_deref(), Some("CORP.EXAMPLE.COM"));
+    assert_eq!(reply.error_code, Some(6));
+    assert_eq!(error_name(6), "KDC_ERR_C_PRINCIPAL_UNKNOWN");
+    assert_eq!(
+        reply.server_time.unwrap().to_rfc3339(),
+        "2026-01-01T12:00:00+00:00"
+    );
+}
+
+#[test]
+fn test_parse_reply_rejects_garbage() {
+    assert!(parse_reply(b"HTTP/1.1 400 Bad Request").is_none());
+}
@@ -0,0 +1,61 @@
+use rust_backend::detect_ldap::{self, LdapDetection, build_root_dse_request, parse_search_entry};
+use rust_backend::utils::ber;
+use std::net::{IpAddr, Ipv4Addr};
+
+fn search_entry(attributes: &[(&str, &[&str])]) -> Vec<u8> {
+    let attrs: Vec<Vec<u8>> = attributes
+        .iter()
+        .map(|(name, values)| {
+            let vals: Vec<Vec<u8>> = values
+                .iter()
+                .map(|v| ber::octet_string(v.as_bytes()))
+                .collect();
+            ber::sequence(&[
+                ber::octet_string(name.as_bytes()),
+                ber::constructed(ber::TAG_SET, &vals),
+            ])
+        })
+        .collect();
+    let entry = ber::constructed(
+        ber::application(4),
+        &[ber::octet_string(b""), ber::sequence(&attrs)],
+    );
+    ber::sequence(&[ber::integer(1), entry])
+}
+
+#[test]
+fn test_root_dse_request_is_well_formed() {
+    let request = build_root_dse_request(1);
+    let (message, rest) = ber::read_tlv(&request).unwrap();
+    assert!(rest.is_empty());
+    let parts = message.children().unwrap();
+    assert_eq!(parts[0].as_integer(), Some(1));
+    assert_eq!(parts[1].tag, ber::application(3));
+}
+
+#[test]
+fn test_parse_root_dse_and_realm() {
+    let message = search_entry(&[
+        ("defaultNamingContext", &["DC=corp,DC=example,DC=com"]),
+        ("dnsHostName", &["dc01.corp.example.com"]),
+        ("domainFunctionality", &["4"]),
+    ]);
+    let root_dse = parse_search_entry(&message).unwrap();
+    let detection = LdapDetection {
+        detected: true,
+        root_dse,
+        error: None,
+    };
+    assert_eq!(
+        detection.attribute("dnshostname"),
+        Some("dc01.corp.example.com")
+    );
+    assert_eq!(detection.realm().as_deref(), Some("CORP.EXAMPLE.COM"));
+}
+
+#[tokio::test]
+async fn test_detect_ldap_on_invalid_port() {
+    let result = detect_ldap::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), 65000).await;
+    assert!(!result.detected);
+    assert!(result.error.is_some());
+}
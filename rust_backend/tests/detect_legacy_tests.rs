@@ -0,0 +1,61 @@
+use rust_backend::detect_legacy::{self, LegacyService};
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+fn chargen(lines: usize) -> Vec<u8> {
+    let pattern: Vec<u8> = (b' '..=b'~').collect();
+    let mut data = Vec::new();
+    for start in 0..lines {
+        for i in 0..72 {
+            data.push(pattern[(start + i) % pattern.len()]);
+        }
+        data.extend_from_slice(b"\r\n");
+    }
+    data
+}
+
+#[test]
+fn test_is_chargen_stream() {
+    let mut data = chargen(3);
+    data.extend_from_slice(b"#$%"); // partial line
+    assert!(detect_legacy::is_chargen_stream(&data));
+    assert!(!detect_legacy::is_chargen_stream(
+        b"HTTP/1.1 400 Bad Request\r\n\r\n"
+    ));
+}
+
+#[test]
+fn test_r_request_and_reply() {
+    assert_eq!(
+        detect_legacy::build_r_request(LegacyService::Rlogin),
+        b"\0netscan\0netscan\0dumb/9600\0".to_vec()
+    );
+    assert_eq!(detect_legacy::parse_r_reply(b"\0"), Some(String::new()));
+    assert_eq!(
+        detect_legacy::parse_r_reply(b"\x01Permission denied.\n"),
+        Some("Permission denied.".to_string())
+    );
+    assert_eq!(detect_legacy::parse_r_reply(b"SSH-2.0"), None);
+}
+
+#[tokio::test]
+async fn test_detect_daytime() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        socket
+            .write_all(b"Thursday, October 15, 2026 10:00:00-UTC\r\n")
+            .await
+            .unwrap();
+    });
+
+    let result = detect_legacy::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), port, LegacyService::Daytime).await;
+    assert!(result.detected);
+    assert_eq!(
+        result.summary(),
+        "daytime (Thursday, October 15, 2026 10:00:00-UTC)"
+    );
+    assert_eq!(LegacyService::from_port(513), Some(LegacyService::Rlogin));
+}
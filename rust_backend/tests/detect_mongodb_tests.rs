@@ -0,0 +1,112 @@
+use rust_backend::detect_mongodb::{self, BsonValue};
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// OP_MSG reply carrying `doc`
+fn op_msg(doc: Vec<u8>) -> Vec<u8> {
+    let mut message = ((21 + doc.len()) as i32).to_le_bytes().to_vec();
+    message.extend_from_slice(&[0; 8]);
+    message.extend_from_slice(&2013i32.to_le_bytes());
+    message.extend_from_slice(&[0, 0, 0, 0, 0]);
+    message.extend_from_slice(&doc);
+    message
+}
+
+#[test]
+fn test_document_round_trip() {
+    let doc = detect_mongodb::build_document(&[
+        ("ismaster", BsonValue::Bool(true)),
+        ("maxWireVersion", BsonValue::Int32(17)),
+        ("setName", BsonValue::String("rs0".to_string())),
+    ]);
+    let fields = detect_mongodb::parse_document(&doc).unwrap();
+    assert_eq!(fields.len(), 3);
+    assert_eq!(fields[1].1.as_i64(), Some(17));
+    assert_eq!(fields[2].1, BsonValue::String("rs0".to_string()));
+    assert_eq!(detect_mongodb::parse_document(&doc[..6]), None);
+}
+
+#[test]
+fn test_negative_lengths_are_rejected() {
+    for (kind, length) in [(0x02u8, -1i32), (0x05, -5), (0x03, -8)] {
+        let mut doc = vec![0; 4];
+        doc.extend_from_slice(&[kind, b'a', 0]);
+        doc.extend_from_slice(&length.to_le_bytes());
+        doc.extend_from_slice(&[0; 8]);
+        let len = doc.len() as i32;
+        doc[..4].copy_from_slice(&len.to_le_bytes());
+        assert_eq!(detect_mongodb::parse_document(&doc), None);
+    }
+    let mut doc = (-1i32).to_le_bytes().to_vec();
+    doc.push(0);
+    assert_eq!(detect_mongodb::parse_document(&doc), None);
+}
+
+#[test]
+fn test_parse_reply() {
+    let reply = op_msg(detect_mongodb::build_document(&[(
+        "ok",
+        BsonValue::Int32(1),
+    )]));
+    let fields = detect_mongodb::parse_reply(&reply).unwrap();
+    assert_eq!(fields[0].0, "ok");
+    // the isMaster request itself is an OP_QUERY, not a reply
+    assert_eq!(
+        detect_mongodb::parse_reply(&detect_mongodb::build_is_master(1)),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_detect_open_server() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let replies = [
+            detect_mongodb::build_document(&[
+                ("ismaster", BsonValue::Bool(true)),
+                ("maxWireVersion", BsonValue::Int32(21)),
+            ]),
+            detect_mongodb::build_document(&[("version", BsonValue::String("7.0.2".to_string()))]),
+        ];
+        for doc in replies {
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await.unwrap();
+            socket.write_all(&op_msg(doc)).await.unwrap();
+        }
+        // listDatabases: { databases: [ {name: "admin"}, {name: "shop"} ], ok: 1 }
+        let entry = |name: &str| {
+            detect_mongodb::build_document(&[("name", BsonValue::String(name.to_string()))])
+        };
+        let mut list = vec![0, 0, 0, 0];
+        for (i, name) in ["admin", "shop"].iter().enumerate() {
+            list.push(0x03);
+            list.extend_from_slice(format!("{}\0", i).as_bytes());
+            list.extend_from_slice(&entry(name));
+        }
+        list.push(0);
+        let len = list.len() as i32;
+        list[..4].copy_from_slice(&len.to_le_bytes());
+        let mut doc = vec![0, 0, 0, 0, 0x04];
+        doc.extend_from_slice(b"databases\0");
+        doc.extend_from_slice(&list);
+        doc.push(0x10);
+        doc.extend_from_slice(b"ok\0");
+        doc.extend_from_slice(&1i32.to_le_bytes());
+        doc.push(0);
+        let len = doc.len() as i32;
+        doc[..4].copy_from_slice(&len.to_le_bytes());
+        let mut buf = [0u8; 1024];
+        let _ = socket.read(&mut buf).await.unwrap();
+        socket.write_all(&op_msg(doc)).await.unwrap();
+    });
+    let mongo = detect_mongodb::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(mongo.detected);
+    assert!(mongo.anonymous_read());
+    assert_eq!(
+        mongo.summary(),
+        "MongoDB 7.0.2 (no authentication, 2 database(s) listed)"
+    );
+}
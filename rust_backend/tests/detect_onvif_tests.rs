@@ -0,0 +1,55 @@
+use rust_backend::detect_onvif;
+
+const PROBE_MATCH: &str = r#"<?xml version="1.0"?><SOAP-ENV:Envelope><SOAP-ENV:Body>
+<d:ProbeMatches><d:ProbeMatch>
+<d:Types>dn:NetworkVideoTransmitter</d:Types>
+<d:Scopes>onvif://www.onvif.org/type/video_encoder onvif://www.onvif.org/hardware/DS-2CD2042WD onvif://www.onvif.org/name/HIKVISION%20Camera</d:Scopes>
+<d:XAddrs>http://192.168.1.64:8000/onvif/device_service</d:XAddrs>
+</d:ProbeMatch></d:ProbeMatches></SOAP-ENV:Body></SOAP-ENV:Envelope>"#;
+
+#[test]
+fn test_parse_probe_match() {
+    let probe = detect_onvif::parse_probe_match(PROBE_MATCH).unwrap();
+    assert_eq!(
+        probe.xaddrs,
+        vec!["http://192.168.1.64:8000/onvif/device_service"]
+    );
+    assert_eq!(
+        probe.scope_value("hardware").as_deref(),
+        Some("DS-2CD2042WD")
+    );
+    assert_eq!(
+        probe.scope_value("name").as_deref(),
+        Some("HIKVISION Camera")
+    );
+    assert_eq!(
+        detect_onvif::xaddr_target(&probe.xaddrs[0]),
+        Some((8000, "/onvif/device_service".to_string()))
+    );
+    assert!(detect_onvif::build_probe("1").contains("dn:NetworkVideoTransmitter"));
+}
+
+#[test]
+fn test_xaddr_target_with_ipv6_literal() {
+    assert_eq!(
+        detect_onvif::xaddr_target("http://[fd00::64]:8000/onvif/device_service"),
+        Some((8000, "/onvif/device_service".to_string()))
+    );
+    assert_eq!(
+        detect_onvif::xaddr_target("http://[fd00::64]/onvif/device_service"),
+        Some((80, "/onvif/device_service".to_string()))
+    );
+}
+
+#[test]
+fn test_parse_device_information() {
+    let response = "HTTP/1.1 200 OK\r\n\r\n<env:Envelope><env:Body><tds:GetDeviceInformationResponse>\
+<tds:Manufacturer>HIKVISION</tds:Manufacturer><tds:Model>DS-2CD2042WD-I</tds:Model>\
+<tds:FirmwareVersion>V5.4.5</tds:FirmwareVersion><tds:SerialNumber>DS-2CD2042WD-I20160101</tds:SerialNumber>\
+</tds:GetDeviceInformationResponse></env:Body></env:Envelope>";
+    let device = detect_onvif::parse_device_information(response).unwrap();
+    assert_eq!(device.manufacturer.as_deref(), Some("HIKVISION"));
+    assert_eq!(device.model.as_deref(), Some("DS-2CD2042WD-I"));
+    assert_eq!(device.firmware.as_deref(), Some("V5.4.5"));
+    assert_eq!(detect_onvif::parse_device_information(PROBE_MATCH), None);
+}
@@ -0,0 +1,42 @@
+use rust_backend::detect_pop3;
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[test]
+fn test_parse_greeting_and_capa() {
+    assert_eq!(
+        detect_pop3::parse_greeting("+OK Dovecot ready.\r\n").as_deref(),
+        Some("Dovecot ready.")
+    );
+    assert_eq!(detect_pop3::parse_greeting("* OK IMAP4rev1\r\n"), None);
+
+    let capa = detect_pop3::parse_capa(
+        "+OK\r\nCAPA\r\nTOP\r\nUIDL\r\nSASL PLAIN\r\nIMPLEMENTATION Cyrus POP3 v3.4\r\n.\r\n",
+    )
+    .unwrap();
+    assert_eq!(capa.len(), 5);
+    assert!(detect_pop3::parse_capa("-ERR unknown command\r\n").is_none());
+}
+
+#[tokio::test]
+async fn test_detect_pop3_without_stls() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0))
+        .await
+        .unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        socket.write_all(b"+OK POP3 ready\r\n").await.unwrap();
+        let mut buf = [0u8; 64];
+        let _ = socket.read(&mut buf).await.unwrap();
+        socket
+            .write_all(b"+OK\r\nUSER\r\nIMPLEMENTATION Cyrus POP3 v3.4\r\n.\r\n")
+            .await
+            .unwrap();
+    });
+    let pop3 = detect_pop3::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(pop3.detected);
+    assert!(!pop3.starttls());
+    assert_eq!(pop3.summary(), "POP3 (Cyrus POP3 v3.4), no STLS");
+}
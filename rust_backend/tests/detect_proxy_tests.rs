@@ -0,0 +1,65 @@
+use rust_backend::detect_proxy::{self, SocksMethod};
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+async fn serve_once(reply: &'static [u8]) -> u16 {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 1024];
+        let _ = socket.read(&mut buf).await.unwrap();
+        socket.write_all(reply).await.unwrap();
+    });
+    port
+}
+
+#[test]
+fn test_parse_socks_reply() {
+    assert_eq!(
+        detect_proxy::parse_socks_reply(&[0x05, 0x00]),
+        Some(SocksMethod::NoAuth)
+    );
+    assert_eq!(
+        detect_proxy::parse_socks_reply(&[0x05, 0xff]),
+        Some(SocksMethod::Refused)
+    );
+    assert_eq!(detect_proxy::parse_socks_reply(b"SSH-2.0"), None);
+}
+
+#[test]
+fn test_parse_connect_response() {
+    let reply = "HTTP/1.1 407 Proxy Authentication Required\r\nServer: squid/5.7\r\n\r\n";
+    assert_eq!(
+        detect_proxy::parse_connect_response(reply),
+        Some((407, Some("squid/5.7".to_string())))
+    );
+    assert_eq!(detect_proxy::parse_connect_response("\x05\x00"), None);
+}
+
+#[tokio::test]
+async fn test_detect_open_socks() {
+    let port = serve_once(&[0x05, 0x00]).await;
+    let result = detect_proxy::detect_socks(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(result.detected);
+    assert!(result.open());
+    assert_eq!(result.summary(), "SOCKS5 (no authentication)");
+}
+
+#[tokio::test]
+async fn test_detect_http_proxy_requiring_auth() {
+    let port =
+        serve_once(b"HTTP/1.1 407 Proxy Authentication Required\r\nVia: 1.1 proxy\r\n\r\n").await;
+    let result = detect_proxy::detect_http_proxy(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(result.detected);
+    assert!(result.auth_required());
+    assert!(!result.open());
+}
+
+#[tokio::test]
+async fn test_web_server_is_not_a_proxy() {
+    let port = serve_once(b"HTTP/1.1 405 Method Not Allowed\r\n\r\n").await;
+    let result = detect_proxy::detect_http_proxy(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(!result.detected);
+}
@@ -0,0 +1,145 @@
+use rust_backend::detect_remote_admin::{self, RemoteAdminTool};
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::net::UdpSocket;
+
+#[test]
+fn test_from_port() {
+    assert_eq!(
+        RemoteAdminTool::from_port(5938),
+        Some(RemoteAdminTool::TeamViewer)
+    );
+    assert_eq!(
+        RemoteAdminTool::from_port(7070),
+        Some(RemoteAdminTool::AnyDesk)
+    );
+    assert_eq!(
+        RemoteAdminTool::from_port(21118),
+        Some(RemoteAdminTool::RustDesk)
+    );
+    assert_eq!(
+        RemoteAdminTool::from_port(27036),
+        Some(RemoteAdminTool::Steam)
+    );
+    assert_eq!(RemoteAdminTool::from_port(443), None);
+}
+
+#[test]
+fn test_from_discovery_port() {
+    for port in [50001, 50002, 50003] {
+        assert_eq!(
+            RemoteAdminTool::from_discovery_port(port),
+            Some(RemoteAdminTool::AnyDesk)
+        );
+    }
+    assert_eq!(
+        RemoteAdminTool::from_discovery_port(5938),
+        Some(RemoteAdminTool::TeamViewer)
+    );
+    assert_eq!(
+        RemoteAdminTool::from_discovery_port(21116),
+        Some(RemoteAdminTool::RustDesk)
+    );
+    assert_eq!(
+        RemoteAdminTool::from_discovery_port(27036),
+        Some(RemoteAdminTool::Steam)
+    );
+    assert_eq!(RemoteAdminTool::from_discovery_port(7070), None);
+}
+
+#[test]
+fn test_parse_discovery_reply() {
+    assert!(
+        RemoteAdminTool::TeamViewer
+            .parse_discovery_reply(&[0x17, 0x24, 0x11, 0x04])
+            .is_some()
+    );
+    assert!(
+        RemoteAdminTool::AnyDesk
+            .parse_discovery_reply(b"AnyDesk beacon")
+            .is_some()
+    );
+    // RegisterPeerResponse = 7, request_pk = 2
+    assert_eq!(
+        RemoteAdminTool::RustDesk.parse_discovery_reply(&[0x3a, 0x02, 0x10, 0x01]),
+        Some("rendezvous server".to_string())
+    );
+    // the probe itself is a RegisterPeer, not a response
+    let probe = RemoteAdminTool::RustDesk.discovery_probe();
+    assert_eq!(&probe[..2], &[0x32, 0x0f]);
+    assert_eq!(
+        RemoteAdminTool::RustDesk.parse_discovery_reply(&probe),
+        None
+    );
+    for tool in RemoteAdminTool::ALL {
+        assert_eq!(tool.parse_discovery_reply(b"HTTP/1.1 400"), None);
+    }
+}
+
+#[tokio::test]
+async fn test_discover_needs_matching_reply() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let port = server.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut buf = [0u8; 512];
+        let (_, peer) = server.recv_from(&mut buf).await.unwrap();
+        server.send_to(&[0x3a, 0x00], peer).await.unwrap();
+        let (_, peer) = server.recv_from(&mut buf).await.unwrap();
+        server.send_to(b"HTTP/1.1 400", peer).await.unwrap();
+    });
+    let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    let found = detect_remote_admin::discover(ip, RemoteAdminTool::RustDesk, port).await;
+    assert!(found.detected && found.confirmed);
+    assert_eq!(found.detail.as_deref(), Some("rendezvous server"));
+
+    let other = detect_remote_admin::discover(ip, RemoteAdminTool::RustDesk, port).await;
+    assert!(!other.detected);
+    assert!(other.error.is_some());
+}
+
+#[test]
+fn test_teamviewer_reply() {
+    assert!(detect_remote_admin::is_teamviewer_reply(&[
+        0x17, 0x24, 0x11, 0x04
+    ]));
+    assert!(detect_remote_admin::is_teamviewer_reply(&[
+        0x11, 0x30, 0x00
+    ]));
+    assert!(!detect_remote_admin::is_teamviewer_reply(b"HTTP/1.1"));
+    assert!(!detect_remote_admin::is_teamviewer_reply(&[0x17]));
+}
+
+#[test]
+fn test_steam_status_round_trip() {
+    let request = detect_remote_admin::build_steam_discovery(1);
+    assert_eq!(
+        &request[..8],
+        &[0xff, 0xff, 0xff, 0xff, 0x21, 0x4c, 0x5f, 0xa0]
+    );
+    // a request carries no hostname but is still well-formed
+    assert_eq!(
+        detect_remote_admin::parse_steam_status(&request),
+        Some(String::new())
+    );
+
+    let mut reply = request[..8].to_vec();
+    let header = [0x08, 0x02, 0x10, 0x00];
+    reply.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    reply.extend_from_slice(&header);
+    // version = 8, hostname = "GAMING-PC"
+    let mut body = vec![0x08, 0x08, 0x22, 0x09];
+    body.extend_from_slice(b"GAMING-PC");
+    reply.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    reply.extend_from_slice(&body);
+    assert_eq!(
+        detect_remote_admin::parse_steam_status(&reply),
+        Some("GAMING-PC".to_string())
+    );
+    assert_eq!(detect_remote_admin::parse_steam_status(b"\xff\xff"), None);
+}
+
+#[tokio::test]
+async fn test_identify_skips_unknown_ports() {
+    let found = detect_remote_admin::identify(IpAddr::V4(Ipv4Addr::LOCALHOST), &[22, 80]).await;
+    assert!(found.is_empty());
+}
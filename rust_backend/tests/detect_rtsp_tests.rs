@@ -0,0 +1,53 @@
+use rust_backend::detect_rtsp;
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[test]
+fn test_parse_response() {
+    let reply = "RTSP/1.0 401 Unauthorized\r\nCSeq: 2\r\nWWW-Authenticate: Digest realm=\"IP Camera\"\r\n\r\n";
+    let response = detect_rtsp::parse_response(reply).unwrap();
+    assert_eq!(response.status, 401);
+    assert!(response.www_authenticate.unwrap().starts_with("Digest"));
+    assert_eq!(detect_rtsp::parse_response("HTTP/1.1 200 OK\r\n\r\n"), None);
+}
+
+#[test]
+fn test_build_request_brackets_ipv6() {
+    let request = detect_rtsp::build_request("OPTIONS", "fd00::64".parse().unwrap(), 554, "/", 1);
+    assert!(request.starts_with("OPTIONS rtsp://[fd00::64]:554/ RTSP/1.0\r\n"));
+}
+
+#[tokio::test]
+async fn test_detect_flags_anonymous_stream() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 1024];
+        loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            if n == 0 {
+                break;
+            }
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let reply = if request.starts_with("OPTIONS") {
+                "RTSP/1.0 200 OK\r\nCSeq: 1\r\nServer: Hipcam RealServer/V1.0\r\nPublic: OPTIONS, DESCRIBE, PLAY\r\n\r\n"
+            } else if request.contains("/live ") {
+                "RTSP/1.0 200 OK\r\nCSeq: 3\r\nContent-Type: application/sdp\r\nContent-Length: 0\r\n\r\n"
+            } else {
+                "RTSP/1.0 404 Not Found\r\nCSeq: 2\r\n\r\n"
+            };
+            socket.write_all(reply.as_bytes()).await.unwrap();
+        }
+    });
+
+    let result = detect_rtsp::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(result.detected);
+    assert_eq!(result.methods, vec!["OPTIONS", "DESCRIBE", "PLAY"]);
+    assert_eq!(result.anonymous_stream.as_deref(), Some("/live"));
+    assert_eq!(
+        result.summary(),
+        "RTSP (Hipcam RealServer/V1.0, anonymous stream at /live)"
+    );
+}
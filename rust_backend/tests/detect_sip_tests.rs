@@ -0,0 +1,85 @@
+use rust_backend::detect_sip::{self, SipTransport};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use tokio::net::UdpSocket;
+
+#[test]
+fn test_options_request_routes_back_to_local_address() {
+    let local: SocketAddr = "192.168.1.10:40000".parse().unwrap();
+    let request = detect_sip::build_options_request(
+        IpAddr::V4(Ipv4Addr::new(192, 168, 1, 50)),
+        5060,
+        local,
+        SipTransport::Udp,
+    );
+    assert!(request.starts_with("OPTIONS sip:probe@192.168.1.50:5060 SIP/2.0\r\n"));
+    assert!(request.contains("Via: SIP/2.0/UDP 192.168.1.10:40000;branch=z9hG4bK"));
+    assert!(request.ends_with("Content-Length: 0\r\n\r\n"));
+}
+
+#[test]
+fn test_options_request_brackets_ipv6_hosts() {
+    let local: SocketAddr = "[fd00::10]:40000".parse().unwrap();
+    let request = detect_sip::build_options_request(
+        "fd00::50".parse().unwrap(),
+        5060,
+        local,
+        SipTransport::Udp,
+    );
+    assert!(request.starts_with("OPTIONS sip:probe@[fd00::50]:5060 SIP/2.0\r\n"));
+    assert!(request.contains("To: <sip:probe@[fd00::50]>\r\n"));
+    assert!(request.contains("From: <sip:netscan@[fd00::10]>;tag=netscan40000\r\n"));
+}
+
+#[test]
+fn test_parse_response_headers() {
+    let reply = "SIP/2.0 200 OK\r\nVia: SIP/2.0/UDP 10.0.0.1\r\nuser-agent: Yealink SIP-T46S 66.86.0.15\r\nAllow: INVITE, ACK, OPTIONS\r\n\r\n";
+    let response = detect_sip::parse_response(reply).unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.user_agent.as_deref(),
+        Some("Yealink SIP-T46S 66.86.0.15")
+    );
+    assert_eq!(response.allow, vec!["INVITE", "ACK", "OPTIONS"]);
+    assert_eq!(detect_sip::parse_response("HTTP/1.1 200 OK\r\n\r\n"), None);
+}
+
+#[test]
+fn test_parse_user_agent_vendor_and_model() {
+    assert_eq!(
+        detect_sip::parse_user_agent("Cisco-CP8845/12.8.1"),
+        (Some("Cisco".to_string()), Some("CP8845".to_string()))
+    );
+    assert_eq!(
+        detect_sip::parse_user_agent("Grandstream GXP2170 1.0.11.3"),
+        (Some("Grandstream".to_string()), Some("GXP2170".to_string()))
+    );
+    assert_eq!(
+        detect_sip::parse_user_agent("Polycom/5.9.5"),
+        (Some("Polycom".to_string()), None)
+    );
+    assert_eq!(detect_sip::parse_user_agent("Unknown UA"), (None, None));
+}
+
+#[tokio::test]
+async fn test_detect_over_udp() {
+    let server = UdpSocket::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = server.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut buf = [0u8; 2048];
+        let (_, client) = server.recv_from(&mut buf).await.unwrap();
+        server
+            .send_to(
+                b"SIP/2.0 200 OK\r\nServer: Asterisk PBX 18.2.0\r\n\r\n",
+                client,
+            )
+            .await
+            .unwrap();
+    });
+
+    let result = detect_sip::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(result.detected);
+    assert_eq!(result.transport, Some(SipTransport::Udp));
+    assert_eq!(result.vendor.as_deref(), Some("Asterisk"));
+    assert_eq!(result.model.as_deref(), Some("PBX"));
+    assert_eq!(result.summary(), "SIP over UDP (Asterisk PBX 18.2.0)");
+}
@@ -0,0 +1,178 @@
+use rust_backend::detect_smb::{
+    self, build_negotiate_request, build_session_request, build_session_setup_request,
+    build_smb1_negotiate_request, parse_negotiate_response, parse_ntlm_challenge,
+    parse_session_setup_response, parse_smb1_negotiate_response,
+};
+use std::net::{IpAddr, Ipv4Addr};
+
+fn negotiate_response(security_mode: u16, dialect: u16) -> Vec<u8> {
+    let mut smb = b"\xfeSMB".to_vec();
+    smb.resize(64, 0);
+    smb.extend_from_slice(&65u16.to_le_bytes());
+    smb.extend_from_slice(&security_mode.to_le_bytes());
+    smb.extend_from_slice(&dialect.to_le_bytes());
+    smb.resize(64 + 40, 0);
+    // 2026-01-01T00:00:00Z as a FILETIME
+    smb.extend_from_slice(&((1_767_225_600u64 + 11_644_473_600) * 10_000_000).to_le_bytes());
+    smb.resize(64 + 56, 0);
+    // SPNEGO hint listing NTLMSSP only
+    let blob: &[u8] = &[
+        0x06, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x02, 0x0a,
+    ];
+    smb.extend_from_slice(&128u16.to_le_bytes());
+    smb.extend_from_slice(&(blob.len() as u16).to_le_bytes());
+    smb.resize(128, 0);
+    smb.extend_from_slice(blob);
+    smb
+}
+
+fn smb1_negotiate_response(status: u32, dialect_index: u16, security_mode: u8) -> Vec<u8> {
+    let mut smb = b"\xffSMB\x72".to_vec();
+    smb.extend_from_slice(&status.to_le_bytes());
+    smb.resize(32, 0);
+    smb.push(17); // WordCount
+    smb.extend_from_slice(&dialect_index.to_le_bytes());
+    smb.push(security_mode);
+    smb.resize(32 + 1 + 34 + 2, 0);
+    smb
+}
+
+fn utf16(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+/// NTLM CHALLENGE from WS01 in CORP, Windows 10.0 build 19041
+fn ntlm_challenge() -> Vec<u8> {
+    let mut pairs = Vec::new();
+    for (id, value) in [
+        (1u16, "WS01"),
+        (2, "CORP"),
+        (3, "ws01.corp.example.com"),
+        (4, "corp.example.com"),
+    ] {
+        pairs.extend_from_slice(&id.to_le_bytes());
+        pairs.extend_from_slice(&(utf16(value).len() as u16).to_le_bytes());
+        pairs.extend(utf16(value));
+    }
+    pairs.extend_from_slice(&[0, 0, 0, 0]); // MsvAvEOL
+
+    let mut message = b"NTLMSSP\0".to_vec();
+    message.extend_from_slice(&2u32.to_le_bytes());
+    message.resize(20, 0);
+    message.extend_from_slice(&0xe28a_8215u32.to_le_bytes());
+    message.resize(40, 0);
+    message.extend_from_slice(&(pairs.len() as u16).to_le_bytes());
+    message.extend_from_slice(&(pairs.len() as u16).to_le_bytes());
+    message.extend_from_slice(&56u32.to_le_bytes());
+    message.extend_from_slice(&[10, 0, 0x61, 0x4a, 0, 0, 0, 15]);
+    message.extend(pairs);
+    message
+}
+
+#[test]
+fn test_negotiate_request_framing() {
+    let request = build_negotiate_request();
+    let len = u32::from_be_bytes([0, request[1], request[2], request[3]]) as usize;
+    assert_eq!(request.len(), 4 + len);
+    assert_eq!(&request[4..8], b"\xfeSMB");
+}
+
+#[test]
+fn test_parse_signing_modes() {
+    let required = parse_negotiate_response(&negotiate_response(0x03, 0x0302)).unwrap();
+    assert!(required.signing_enabled && required.signing_required);
+    assert_eq!(required.dialect.as_deref(), Some("3.0.2"));
+    assert_eq!(required.server_time, Some(1_767_225_600));
+
+    let optional = parse_negotiate_response(&negotiate_response(0x01, 0x0210)).unwrap();
+    assert!(optional.signing_enabled);
+    assert!(!optional.signing_required);
+    assert!(!optional.smb1_accepted);
+}
+
+#[test]
+fn test_parse_auth_mechanisms() {
+    let result = parse_negotiate_response(&negotiate_response(0x01, 0x0300)).unwrap();
+    assert_eq!(result.auth_mechanisms, vec!["NTLM".to_string()]);
+    assert!(result.ntlm_offered());
+    assert_eq!(
+        result.summary(),
+        "SMB 3.0 (signing enabled, not required, SMB1 rejected, auth: NTLM)"
+    );
+}
+
+#[test]
+fn test_smb1_negotiate() {
+    let request = build_smb1_negotiate_request();
+    assert_eq!(&request[4..9], b"\xffSMB\x72");
+    assert!(request.ends_with(b"\x02NT LM 0.12\0"));
+
+    let accepted = parse_smb1_negotiate_response(&smb1_negotiate_response(0, 0, 0x07)).unwrap();
+    assert!(accepted.smb1_accepted);
+    assert!(accepted.signing_enabled);
+    assert!(!accepted.signing_required);
+
+    assert!(parse_smb1_negotiate_response(&smb1_negotiate_response(0, 0xffff, 0)).is_none());
+    assert!(parse_smb1_negotiate_response(&smb1_negotiate_response(0xc000_0022, 0, 0)).is_none());
+}
+
+#[tokio::test]
+async fn test_detect_smb_on_invalid_port() {
+    let result = detect_smb::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), 65000).await;
+    assert!(!result.detected);
+    assert!(result.error.is_some());
+}
+
+#[test]
+fn test_session_request_encodes_netbios_names() {
+    let request = build_session_request();
+    assert_eq!(request[0], 0x81);
+    assert_eq!(request.len(), 4 + 68);
+    // "*SMBSERVER" padded with spaces, first-level encoded
+    assert_eq!(&request[5..25], b"CKFDENECFDEFFCFGEFFC");
+    assert_eq!(&request[25..37], b"CACACACACACA");
+    assert_eq!(request[37], 0);
+}
+
+#[test]
+fn test_parse_ntlm_challenge() {
+    let info = parse_ntlm_challenge(&ntlm_challenge()).unwrap();
+    assert_eq!(info.netbios_name.as_deref(), Some("WS01"));
+    assert_eq!(info.netbios_domain.as_deref(), Some("CORP"));
+    assert_eq!(info.dns_name.as_deref(), Some("ws01.corp.example.com"));
+    assert_eq!(info.dns_domain.as_deref(), Some("corp.example.com"));
+    assert_eq!(info.os_version, Some((10, 0, 19041)));
+    assert_eq!(info.windows_release(), Some("Windows (10/Server 2016+)"));
+
+    assert!(parse_ntlm_challenge(b"NTLMSSP\0\x01\0\0\0").is_none());
+}
+
+#[test]
+fn test_session_setup_round_trip() {
+    let request = build_session_setup_request();
+    let len = u32::from_be_bytes([0, request[1], request[2], request[3]]) as usize;
+    assert_eq!(request.len(), 4 + len);
+    assert!(request[4..].windows(8).any(|w| w == b"NTLMSSP\0"));
+
+    // Response wrapped in a few bytes of SPNEGO, as Windows sends it
+    let blob = [vec![0xa1, 0x81, 0xd0, 0x30], ntlm_challenge()].concat();
+    let mut smb = b"\xfeSMB".to_vec();
+    smb.resize(12, 0);
+    smb.extend_from_slice(&1u16.to_le_bytes());
+    smb.resize(64, 0);
+    smb.extend_from_slice(&9u16.to_le_bytes());
+    smb.extend_from_slice(&0u16.to_le_bytes());
+    smb.extend_from_slice(&72u16.to_le_bytes());
+    smb.extend_from_slice(&(blob.len() as u16).to_le_bytes());
+    smb.extend(blob);
+    let info = parse_session_setup_response(&smb).unwrap();
+    assert_eq!(info.netbios_name.as_deref(), Some("WS01"));
+
+    let mut detection = parse_negotiate_response(&negotiate_response(0x01, 0x0311)).unwrap();
+    detection.host_info = Some(info);
+    assert!(
+        detection
+            .summary()
+            .ends_with("auth: NTLM, name WS01, domain CORP, Windows 10.0 build 19041)")
+    );
+}
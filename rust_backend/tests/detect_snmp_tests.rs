@@ -0,0 +1,110 @@
+use rust_backend::detect_snmp::{self, SnmpVersion};
+use rust_backend::utils::ber;
+
+/// GetResponse carrying sysDescr.0 with the given error-status
+fn response(error_status: i64, descr: &str) -> Vec<u8> {
+    let varbind = ber::sequence(&[
+        ber::tlv(
+            ber::TAG_OBJECT_IDENTIFIER,
+            &[0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00],
+        ),
+        ber::octet_string(descr.as_bytes()),
+    ]);
+    let pdu = ber::constructed(
+        0xa2,
+        &[
+            ber::integer(1),
+            ber::integer(error_status),
+            ber::integer(0),
+            ber::sequence(&[varbind]),
+        ],
+    );
+    ber::sequence(&[ber::integer(1), ber::octet_string(b"public"), pdu])
+}
+
+#[test]
+fn test_get_request_encodes_version_and_community() {
+    let request = detect_snmp::build_get_request(SnmpVersion::V2c, "public", 7);
+    let (message, rest) = ber::read_tlv(&request).unwrap();
+    assert!(rest.is_empty());
+    let fields = message.children().unwrap();
+    assert_eq!(fields[0].as_integer(), Some(1));
+    assert_eq!(fields[1].as_str(), "public");
+    assert_eq!(fields[2].tag, 0xa0);
+}
+
+#[test]
+fn test_parse_response_returns_sys_descr() {
+    assert_eq!(
+        detect_snmp::parse_response(&response(0, "Linux switch 4.19")).as_deref(),
+        Some("Linux switch 4.19")
+    );
+    assert_eq!(detect_snmp::parse_response(&response(2, "")), None);
+    assert_eq!(detect_snmp::parse_response(&[0x30, 0x00]), None);
+}
+
+/// Discovery report from an agent whose engine ID embeds a MAC address
+fn discovery_report(engine_id: &[u8]) -> Vec<u8> {
+    let usm = ber::sequence(&[
+        ber::octet_string(engine_id),
+        ber::integer(4),
+        ber::integer(86400),
+        ber::octet_string(&[]),
+        ber::octet_string(&[]),
+        ber::octet_string(&[]),
+    ]);
+    let report = ber::constructed(
+        0xa8,
+        &[
+            ber::integer(0x4e53),
+            ber::integer(0),
+            ber::integer(0),
+            ber::sequence(&[]),
+        ],
+    );
+    ber::sequence(&[
+        ber::integer(3),
+        ber::sequence(&[
+            ber::integer(0x4e53),
+            ber::integer(65507),
+            ber::octet_string(&[0x00]),
+            ber::integer(3),
+        ]),
+        ber::octet_string(&usm),
+        ber::sequence(&[ber::octet_string(engine_id), ber::octet_string(&[]), report]),
+    ])
+}
+
+#[test]
+fn test_discovery_request_is_unauthenticated_v3() {
+    let request = detect_snmp::build_discovery_request(1);
+    let (message, _) = ber::read_tlv(&request).unwrap();
+    let fields = message.children().unwrap();
+    assert_eq!(fields[0].as_integer(), Some(3));
+    let global = fields[1].children().unwrap();
+    assert_eq!(global[2].value, &[0x04]);
+    assert_eq!(global[3].as_integer(), Some(3));
+    let (usm, _) = ber::read_tlv(fields[2].value).unwrap();
+    assert!(usm.children().unwrap()[0].value.is_empty());
+}
+
+#[test]
+fn test_parse_discovery_response_reads_engine() {
+    let engine_id = [
+        0x80, 0x00, 0x00, 0x09, 0x03, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55,
+    ];
+    let engine = detect_snmp::parse_discovery_response(&discovery_report(&engine_id)).unwrap();
+    assert_eq!(engine.engine_id, engine_id);
+    assert_eq!(engine.boots, 4);
+    assert_eq!(engine.time, 86400);
+    assert_eq!(engine.enterprise(), Some(9));
+    assert_eq!(
+        engine.describe(),
+        "8000000903001122334455 (enterprise 9, MAC 00:11:22:33:44:55)"
+    );
+
+    assert_eq!(
+        detect_snmp::parse_discovery_response(&response(0, "x")),
+        None
+    );
+}
@@ -0,0 +1,47 @@
+use rust_backend::detect_telnet;
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[test]
+fn test_parse_greeting() {
+    // IAC DO TERMINAL-TYPE, IAC WILL ECHO, then a prompt
+    let telnet =
+        detect_telnet::parse_greeting(b"\xff\xfd\x18\xff\xfb\x01\r\nrouter login: ").unwrap();
+    assert_eq!(telnet.options, vec!["TERMINAL-TYPE", "ECHO"]);
+    assert_eq!(telnet.prompt.as_deref(), Some("router login:"));
+    assert_eq!(
+        telnet.summary(),
+        "Telnet (prompt \"router login:\", options TERMINAL-TYPE/ECHO)"
+    );
+
+    // A bare prompt without negotiation still identifies Telnet
+    let cisco =
+        detect_telnet::parse_greeting(b"\r\nUser Access Verification\r\n\r\nUsername: ").unwrap();
+    assert!(cisco.options.is_empty());
+    assert!(detect_telnet::parse_greeting(b"220 ftp.example.com FTP ready\r\n").is_none());
+}
+
+#[tokio::test]
+async fn test_detect_refuses_options_and_reads_prompt() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0))
+        .await
+        .unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        socket.write_all(b"\xff\xfd\x1f").await.unwrap(); // IAC DO NAWS
+        let mut refusal = [0u8; 3];
+        socket.read_exact(&mut refusal).await.unwrap();
+        socket
+            .write_all(b"Debian GNU/Linux 12\r\nlogin: ")
+            .await
+            .unwrap();
+        refusal
+    });
+    let telnet = detect_telnet::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(telnet.detected);
+    assert_eq!(telnet.prompt.as_deref(), Some("login:"));
+    // IAC WONT NAWS
+    assert_eq!(server.await.unwrap(), [0xff, 0xfc, 0x1f]);
+}
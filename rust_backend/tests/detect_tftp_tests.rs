@@ -0,0 +1,47 @@
+use rust_backend::detect_tftp::{self, TftpReply};
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::net::UdpSocket;
+
+#[test]
+fn test_read_request_layout() {
+    assert_eq!(
+        detect_tftp::build_read_request("a.txt"),
+        b"\x00\x01a.txt\x00octet\x00".to_vec()
+    );
+}
+
+#[test]
+fn test_parse_response() {
+    assert_eq!(
+        detect_tftp::parse_response(b"\x00\x05\x00\x01File not found\x00"),
+        Some(TftpReply::Error {
+            code: 1,
+            message: "File not found".to_string()
+        })
+    );
+    assert_eq!(
+        detect_tftp::parse_response(b"\x00\x03\x00\x01data"),
+        Some(TftpReply::Data { block: 1 })
+    );
+    assert_eq!(detect_tftp::parse_response(b"\x00\x04"), None);
+}
+
+#[tokio::test]
+async fn test_detect_accepts_reply_from_new_port() {
+    let server = UdpSocket::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = server.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut buf = [0u8; 512];
+        let (_, client) = server.recv_from(&mut buf).await.unwrap();
+        // Like a real server, answer from a fresh transfer port
+        let transfer = UdpSocket::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+        transfer
+            .send_to(b"\x00\x05\x00\x02Access violation\x00", client)
+            .await
+            .unwrap();
+    });
+
+    let result = detect_tftp::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(result.detected);
+    assert_eq!(result.summary(), "TFTP (error 2: Access violation)");
+}
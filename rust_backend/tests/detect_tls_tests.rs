@@ -0,0 +1,188 @@
+use rust_backend::detect_tls::{
+    CipherStrength, TlsDetection, TlsVersion, build_client_hello, build_client_hello_with_alpn,
+    cipher_info, parse_alpn, parse_certificate, parse_server_hello,
+};
+use rust_backend::utils::ber;
+
+fn name(common_name: &str) -> Vec<u8> {
+    let attribute = ber::sequence(&[
+        ber::tlv(ber::TAG_OBJECT_IDENTIFIER, &[0x55, 0x04, 0x03]),
+        ber::tlv(0x0c, common_name.as_bytes()), // UTF8String
+    ]);
+    ber::sequence(&[ber::constructed(ber::TAG_SET, &[attribute])])
+}
+
+/// SubjectPublicKeyInfo with a 2048-bit RSA modulus.
+fn rsa_public_key() -> Vec<u8> {
+    let mut modulus = vec![0x00, 0xc5];
+    modulus.extend_from_slice(&[0x11; 255]);
+    let key = ber::sequence(&[ber::tlv(ber::TAG_INTEGER, &modulus), ber::integer(65537)]);
+    let mut bits = vec![0x00];
+    bits.extend(key);
+    ber::sequence(&[
+        ber::sequence(&[ber::tlv(
+            ber::TAG_OBJECT_IDENTIFIER,
+            &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01],
+        )]),
+        ber::tlv(ber::TAG_BIT_STRING, &bits),
+    ])
+}
+
+fn certificate(subject: &str, issuer: &str, not_after: &[u8]) -> Vec<u8> {
+    let algorithm = ber::sequence(&[ber::tlv(
+        ber::TAG_OBJECT_IDENTIFIER,
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b],
+    )]);
+    let tbs = ber::sequence(&[
+        ber::constructed(ber::context(0), &[ber::integer(2)]),
+        ber::integer(0x1234),
+        algorithm.clone(),
+        name(issuer),
+        ber::sequence(&[
+            ber::tlv(ber::TAG_UTC_TIME, b"240101000000Z"),
+            ber::tlv(ber::TAG_GENERALIZED_TIME, not_after),
+        ]),
+        name(subject),
+        rsa_public_key(),
+        ber::constructed(
+            ber::context(3),
+            &[ber::sequence(&[ber::sequence(&[
+                ber::tlv(ber::TAG_OBJECT_IDENTIFIER, &[0x55, 0x1d, 0x11]),
+                ber::octet_string(&ber::sequence(&[
+                    ber::tlv(0x82, subject.as_bytes()),
+                    ber::tlv(0x87, &[10, 0, 0, 5]),
+                ])),
+            ])])],
+        ),
+    ]);
+    ber::sequence(&[tbs, algorithm, ber::tlv(ber::TAG_BIT_STRING, &[0x00])])
+}
+
+fn server_hello(version: u16, cipher: u16, supported_version: Option<u16>) -> Vec<u8> {
+    let mut hello = version.to_be_bytes().to_vec();
+    hello.extend_from_slice(&[0x11; 32]);
+    hello.push(0);
+    hello.extend_from_slice(&cipher.to_be_bytes());
+    hello.push(0);
+    if let Some(selected) = supported_version {
+        hello.extend_from_slice(&6u16.to_be_bytes());
+        hello.extend_from_slice(&[0x00, 0x2b, 0x00, 0x02]);
+        hello.extend_from_slice(&selected.to_be_bytes());
+    }
+    let mut handshake = vec![0x02];
+    handshake.extend_from_slice(&(hello.len() as u32).to_be_bytes()[1..]);
+    handshake.extend(hello);
+    let mut record = vec![0x16, 0x03, 0x03];
+    record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+    record.extend(handshake);
+    record
+}
+
+#[test]
+fn test_client_hello_layout() {
+    let hello = build_client_hello(TlsVersion::Tls11, &[0xc02f, 0x000a], None);
+    assert_eq!(&hello[..3], &[0x16, 0x03, 0x01]);
+    assert_eq!(
+        u16::from_be_bytes([hello[3], hello[4]]) as usize,
+        hello.len() - 5
+    );
+    assert_eq!(hello[5], 0x01); // ClientHello
+    assert_eq!(&hello[9..11], &[0x03, 0x02]);
+    assert_eq!(&hello[44..50], &[0x00, 0x04, 0xc0, 0x2f, 0x00, 0x0a]);
+
+    let ssl3 = build_client_hello(TlsVersion::Ssl3, &[0x000a], None);
+    assert_eq!(&ssl3[1..3], &[0x03, 0x00]);
+    // No extensions after the compression methods
+    assert!(ssl3.ends_with(&[0x00, 0x0a, 0x01, 0x00]));
+}
+
+#[test]
+fn test_parse_server_hello() {
+    let tls12 = parse_server_hello(&server_hello(0x0303, 0xc02f, None)).unwrap();
+    assert_eq!(tls12.version, TlsVersion::Tls12.wire());
+    assert_eq!(tls12.cipher, 0xc02f);
+
+    let tls13 = parse_server_hello(&server_hello(0x0303, 0x1301, Some(0x0304))).unwrap();
+    assert_eq!(
+        TlsVersion::from_wire(tls13.version),
+        Some(TlsVersion::Tls13)
+    );
+
+    // handshake_failure alert
+    assert!(parse_server_hello(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28]).is_none());
+}
+
+#[test]
+fn test_alpn_offer_and_selection() {
+    let plain = build_client_hello(TlsVersion::Tls12, &[0xc02f], None);
+    let hello =
+        build_client_hello_with_alpn(TlsVersion::Tls12, &[0xc02f], None, &["h2", "http/1.1"]);
+    let offer = [
+        0x00, 0x10, 0x00, 0x0e, 0x00, 0x0c, 0x02, b'h', b'2', 0x08, b'h', b't', b't', b'p', b'/',
+        b'1', b'.', b'1',
+    ];
+    assert!(hello.windows(offer.len()).any(|w| w == offer));
+    assert_eq!(hello.len(), plain.len() + offer.len());
+
+    // ServerHello with a single ALPN extension selecting "h2"
+    let mut reply = server_hello(0x0303, 0xc02f, None);
+    let extension = [0x00, 0x10, 0x00, 0x05, 0x00, 0x03, 0x02, b'h', b'2'];
+    reply.extend_from_slice(&(extension.len() as u16).to_be_bytes());
+    reply.extend_from_slice(&extension);
+    let handshake_len = reply.len() - 9;
+    reply[6..9].copy_from_slice(&(handshake_len as u32).to_be_bytes()[1..]);
+    let record_len = reply.len() - 5;
+    reply[3..5].copy_from_slice(&(record_len as u16).to_be_bytes());
+    assert_eq!(parse_alpn(&reply).as_deref(), Some("h2"));
+    assert_eq!(parse_server_hello(&reply).unwrap().cipher, 0xc02f);
+
+    assert!(parse_alpn(&server_hello(0x0303, 0xc02f, None)).is_none());
+}
+
+#[test]
+fn test_tls_detection_summary() {
+    let mut tls = TlsDetection {
+        detected: true,
+        certificate: None,
+        alpn: Some("h2".to_string()),
+        error: None,
+    };
+    assert_eq!(tls.summary(), "HTTPS");
+    tls.alpn = Some("imap".to_string());
+    assert_eq!(tls.summary(), "TLS");
+    tls.alpn = None;
+    assert_eq!(tls.summary(), "TLS");
+}
+
+#[test]
+fn test_cipher_info() {
+    assert_eq!(
+        cipher_info(0x0005),
+        Some(("RSA-RC4-128-SHA", CipherStrength::Weak))
+    );
+    assert_eq!(cipher_info(0x0001).unwrap().1, CipherStrength::Insecure);
+    assert_eq!(cipher_info(0x1302).unwrap().1, CipherStrength::Strong);
+    assert!(cipher_info(0xbeef).is_none());
+}
+
+#[test]
+fn test_parse_certificate() {
+    let cert = parse_certificate(&certificate(
+        "www.example.com",
+        "Example CA",
+        b"20300101000000Z",
+    ))
+    .unwrap();
+    assert_eq!(cert.subject, "www.example.com");
+    assert_eq!(cert.issuer, "Example CA");
+    assert_eq!(cert.not_before.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+    assert_eq!(cert.not_after.to_rfc3339(), "2030-01-01T00:00:00+00:00");
+    assert!(!cert.self_signed);
+    assert_eq!(cert.serial, "1234");
+    assert_eq!(cert.sans, vec!["www.example.com", "10.0.0.5"]);
+    assert_eq!(cert.key_description(), "RSA 2048");
+
+    let self_signed =
+        parse_certificate(&certificate("printer", "printer", b"20200101000000Z")).unwrap();
+    assert!(self_signed.self_signed);
+}
@@ -0,0 +1,31 @@
+use rust_backend::detect_vnc::{self, SECURITY_NONE, SECURITY_VNC_AUTH};
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[test]
+fn test_parse_version() {
+    assert_eq!(detect_vnc::parse_version(b"RFB 003.008\n"), Some((3, 8)));
+    assert_eq!(detect_vnc::parse_version(b"SSH-2.0-Open"), None);
+}
+
+#[tokio::test]
+async fn test_detect_reports_security_types() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        socket.write_all(b"RFB 003.008\n").await.unwrap();
+        let mut reply = [0u8; 12];
+        socket.read_exact(&mut reply).await.unwrap();
+        socket
+            .write_all(&[2, SECURITY_NONE, SECURITY_VNC_AUTH])
+            .await
+            .unwrap();
+    });
+
+    let result = detect_vnc::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), port).await;
+    assert!(result.detected);
+    assert_eq!(result.version.as_deref(), Some("3.8"));
+    assert!(result.no_auth());
+}
@@ -0,0 +1,77 @@
+use rust_backend::detect_vpn::{self, VpnKind};
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::net::UdpSocket;
+
+#[test]
+fn test_openvpn_reset_round_trip() {
+    let request = detect_vpn::build_openvpn_reset(0x1122334455667788);
+    assert_eq!(request[0], 0x38);
+    assert_eq!(request.len(), 14);
+
+    // P_CONTROL_HARD_RESET_SERVER_V2 acking packet 0 of our session
+    let mut reply = vec![0x40];
+    reply.extend_from_slice(&0xaabbccddeeff0011u64.to_be_bytes());
+    reply.push(1);
+    reply.extend_from_slice(&0u32.to_be_bytes());
+    reply.extend_from_slice(&0x1122334455667788u64.to_be_bytes());
+    reply.extend_from_slice(&0u32.to_be_bytes());
+    assert_eq!(
+        detect_vpn::parse_openvpn_reset(&reply, 0x1122334455667788),
+        Some(0xaabbccddeeff0011)
+    );
+    assert_eq!(detect_vpn::parse_openvpn_reset(&reply, 1), None);
+    assert_eq!(detect_vpn::parse_openvpn_reset(&request, 1), None);
+}
+
+#[test]
+fn test_ike_main_mode() {
+    let request = detect_vpn::build_ike_main_mode(42);
+    assert_eq!(request.len(), 84);
+    let length = u32::from_be_bytes(request[24..28].try_into().unwrap());
+    assert_eq!(length as usize, request.len());
+
+    // responder answers with a NO-PROPOSAL-CHOSEN notification
+    let mut reply = request[..28].to_vec();
+    reply[8..16].copy_from_slice(&7u64.to_be_bytes());
+    reply[16] = 11;
+    reply[18] = 5;
+    let ike = detect_vpn::parse_ike_response(&reply, 42).unwrap();
+    assert_eq!(ike.major_version, 1);
+    assert!(ike.notification);
+    assert_eq!(detect_vpn::parse_ike_response(&reply, 43), None);
+}
+
+#[test]
+fn test_wireguard_reply() {
+    let request = detect_vpn::build_wireguard_initiation(9);
+    assert_eq!(request.len(), 148);
+    let mut cookie = vec![3, 0, 0, 0];
+    cookie.extend_from_slice(&9u32.to_le_bytes());
+    cookie.resize(64, 0);
+    assert!(detect_vpn::is_wireguard_reply(&cookie, 9));
+    assert!(!detect_vpn::is_wireguard_reply(&cookie, 10));
+    assert!(!detect_vpn::is_wireguard_reply(&request, 9));
+}
+
+#[test]
+fn test_kind_from_port() {
+    assert_eq!(VpnKind::from_port(51820), Some(VpnKind::WireGuard));
+    assert_eq!(VpnKind::from_port(4500), Some(VpnKind::Ike));
+    assert_eq!(VpnKind::from_port(443), None);
+}
+
+#[tokio::test]
+async fn test_detect_ike_responder() {
+    let server = UdpSocket::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = server.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut buf = [0u8; 512];
+        let (n, from) = server.recv_from(&mut buf).await.unwrap();
+        let mut reply = buf[..28.min(n)].to_vec();
+        reply[8..16].copy_from_slice(&1u64.to_be_bytes());
+        server.send_to(&reply, from).await.unwrap();
+    });
+    let result = detect_vpn::detect(IpAddr::V4(Ipv4Addr::LOCALHOST), port, VpnKind::Ike).await;
+    assert!(result.detected);
+    assert_eq!(result.summary(), "IKE/IPsec (IKEv1 exchange 2)");
+}
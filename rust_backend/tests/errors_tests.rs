@@ -0,0 +1,68 @@
+use rust_backend::scanners::tcpscan::TcpScanResult;
+use rust_backend::utils::errors::{AbortCode, ErrorCategory, RunError, format_counts};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+
+#[test]
+fn test_from_io_categories() {
+    let refused = io::Error::from_raw_os_error(111);
+    assert_eq!(ErrorCategory::from_io(&refused), ErrorCategory::Refused);
+    let unreachable = io::Error::from_raw_os_error(113);
+    assert_eq!(
+        ErrorCategory::from_io(&unreachable),
+        ErrorCategory::Unreachable
+    );
+    let timeout = io::Error::new(io::ErrorKind::TimedOut, "timed out");
+    assert_eq!(ErrorCategory::from_io(&timeout), ErrorCategory::Timeout);
+    let other = io::Error::other("boom");
+    assert_eq!(ErrorCategory::from_io(&other), ErrorCategory::Other);
+}
+
+#[test]
+fn test_error_counts_are_grouped_per_host() {
+    let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10));
+    let mut result = TcpScanResult::new();
+    for port in 1..4 {
+        result.add_port_error(ip, port, ErrorCategory::Refused, "Connection refused");
+    }
+    result.add_port_error(ip, 4, ErrorCategory::Timeout, "Timed out");
+
+    let counts = &result.get_error_counts()[&ip];
+    assert_eq!(counts[&ErrorCategory::Refused], 3);
+    assert_eq!(format_counts(counts), "3 refused, 1 timeout");
+    assert_eq!(result.get_errors().len(), 4);
+}
+
+#[test]
+fn test_run_error_keeps_the_code_it_is_given() {
+    // The caller knows what failed; the message does not decide the code
+    let failed = RunError::new(
+        AbortCode::CheckFailed,
+        "Failed to read rules.txt: Permission denied",
+    );
+    assert_eq!(failed.code, AbortCode::CheckFailed);
+    let discovery = RunError::new(
+        AbortCode::DiscoveryFailed,
+        "No interface is on-link for fe80::1",
+    );
+    assert_eq!(discovery.code, AbortCode::DiscoveryFailed);
+}
+
+#[test]
+fn test_run_error_json_shape() {
+    let error = RunError::new(
+        AbortCode::NothingToScan,
+        "Every target is excluded; nothing to scan.",
+    );
+    let json = error.to_json();
+    assert_eq!(json["error"]["code"], "nothing_to_scan");
+    assert_eq!(
+        json["error"]["message"],
+        "Every target is excluded; nothing to scan."
+    );
+    assert_eq!(json["error"]["hint"], AbortCode::NothingToScan.hint());
+    assert_eq!(
+        error.to_string(),
+        "Every target is excluded; nothing to scan."
+    );
+}
@@ -0,0 +1,55 @@
+use rust_backend::utils::evidence::{self, Attribute, Evidence, Source};
+use rust_backend::utils::fingerprinting::HostFingerprintResult;
+use std::net::{IpAddr, Ipv4Addr};
+
+#[test]
+fn test_os_from_banner_points_at_the_match() {
+    let os = evidence::os_from_banner("SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.1", 22).unwrap();
+    assert_eq!(os.attribute, Attribute::Os);
+    assert_eq!(os.value, "Linux (Ubuntu)");
+    assert_eq!(os.source, Source::Banner("Ubuntu".to_string()));
+    assert_eq!(
+        os.to_string(),
+        "OS Linux (Ubuntu) <- banner \"Ubuntu\" on port 22"
+    );
+    assert!(evidence::os_from_banner("SSH-2.0-dropbear_2022.83", 22).is_none());
+}
+
+#[test]
+fn test_os_from_ttl_counts_the_hops() {
+    let linux = evidence::os_from_ttl(61).unwrap();
+    assert_eq!(linux.value, "Linux");
+    assert_eq!(linux.to_string(), "OS Linux <- TTL 61");
+    assert_eq!(evidence::os_from_ttl(127).unwrap().value, "Windows");
+    assert!(evidence::os_from_ttl(250).is_none());
+}
+
+#[test]
+fn test_merged_results_keep_all_evidence_once() {
+    let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20));
+    let oui = Evidence::new(
+        Attribute::Vendor,
+        "Raspberry Pi Trading Ltd",
+        Source::Oui("dc:a6:32".to_string()),
+    );
+    let ssh =
+        Evidence::new(Attribute::Service, "SSH", Source::Probe("SSH".to_string())).on_port(22);
+
+    let mut first = HostFingerprintResult::new(ip);
+    first.add_evidence(oui.clone());
+    first.add_evidence(oui.clone());
+    let mut second = HostFingerprintResult::new(ip);
+    second.add_evidence(oui.clone());
+    second.add_evidence(ssh.clone());
+    first.merge(second);
+
+    assert_eq!(first.evidence, vec![oui.clone(), ssh]);
+    assert_eq!(
+        first.evidence_for(Attribute::Vendor).collect::<Vec<_>>(),
+        vec![&oui]
+    );
+    assert_eq!(
+        oui.to_string(),
+        "Vendor Raspberry Pi Trading Ltd <- OUI dc:a6:32"
+    );
+}
@@ -0,0 +1,82 @@
+use rust_backend::scanners::tcpscan;
+use rust_backend::utils::fastpath::{self, LOCAL_CONCURRENCY, LOCAL_TIMEOUT};
+use rust_backend::utils::scan_config::ScanConfig;
+use rust_backend::utils::targets::parse_targets;
+use std::net::IpAddr;
+use std::time::Duration;
+
+#[test]
+fn test_only_this_machine_takes_the_fast_path() {
+    let own: Vec<IpAddr> = vec!["192.168.1.20".parse().unwrap()];
+    let local = |spec: &str| fastpath::all_local(&parse_targets(spec).unwrap(), &own);
+    assert!(local("127.0.0.1"));
+    assert!(local("::1,localhost"));
+    assert!(local("127.0.0.0/8"));
+    assert!(local("127.0.0.1-5"));
+    assert!(local("192.168.1.20"));
+    assert!(!local("192.168.1.21"));
+    assert!(!local("127.0.0.1,10.0.0.1"));
+    assert!(!local("192.168.1.0/24"));
+    assert!(!local("example.com"));
+}
+
+#[test]
+fn test_tune_keeps_given_settings() {
+    let tuned = fastpath::tune(ScanConfig::new());
+    assert_eq!(tuned.timeout, Some(LOCAL_TIMEOUT));
+    assert_eq!(tuned.concurrency, Some(LOCAL_CONCURRENCY));
+    assert_eq!(tuned.retries, Some(0));
+
+    let given = ScanConfig {
+        timeout: Some(Duration::from_secs(2)),
+        retries: Some(1),
+        ..ScanConfig::new()
+    };
+    let tuned = fastpath::tune(given);
+    assert_eq!(tuned.timeout, Some(Duration::from_secs(2)));
+    assert_eq!(tuned.retries, Some(1));
+}
+
+#[test]
+fn test_own_address_is_kept_when_it_is_the_target() {
+    let own: IpAddr = "192.168.1.20".parse().unwrap();
+    let peer: IpAddr = "192.168.1.21".parse().unwrap();
+    assert_eq!(
+        fastpath::hosts_to_scan(vec![own, peer], Some(own), false),
+        vec![peer]
+    );
+    assert_eq!(
+        fastpath::hosts_to_scan(vec![own], Some(own), true),
+        vec![own]
+    );
+    assert_eq!(fastpath::hosts_to_scan(vec![own], None, false), vec![own]);
+}
+
+#[tokio::test]
+async fn test_fast_path_scans_own_non_loopback_address() {
+    let local = fastpath::local_addresses();
+    let Some(own) = local
+        .iter()
+        .copied()
+        .find(|ip| ip.is_ipv4() && !ip.is_loopback())
+    else {
+        return; // no network interface to scan
+    };
+    let listener = std::net::TcpListener::bind((own, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let specs = parse_targets(&own.to_string()).unwrap();
+    assert!(fastpath::all_local(&specs, &local));
+    let live_hosts = fastpath::hosts_to_scan(vec![own], Some(own), true);
+    let result = tcpscan::tcp_scan_from(
+        &live_hosts,
+        [port],
+        None,
+        &fastpath::tune(ScanConfig::new()),
+    )
+    .await;
+    assert_eq!(
+        result.get_open_ports().iter().collect::<Vec<_>>(),
+        vec![(own, port)]
+    );
+}
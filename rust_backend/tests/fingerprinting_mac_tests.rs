@@ -7,4 +7,30 @@ async fn test_fingerprint_mac_on_localhost() {
     let result = fingerprint_mac::fingerprint(ip).await;
     // Accept None for now, but must not panic
     assert!(result.mac.is_none() || result.error.is_some());
-}
\ No newline at end of file
+}
+#[test]
+fn test_mac_from_arp_table() {
+    let table = "\
+IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.1      0x1         0x2         B8:27:EB:12:34:56     *        eth0
+192.168.1.7      0x1         0x0         00:00:00:00:00:00     *        eth0
+";
+    assert_eq!(
+        fingerprint_mac::mac_from_arp_table(table, Ipv4Addr::new(192, 168, 1, 1)).as_deref(),
+        Some("b8:27:eb:12:34:56")
+    );
+    // incomplete entries never answered
+    assert!(fingerprint_mac::mac_from_arp_table(table, Ipv4Addr::new(192, 168, 1, 7)).is_none());
+    assert!(fingerprint_mac::mac_from_arp_table(table, Ipv4Addr::new(10, 0, 0, 1)).is_none());
+}
+
+#[tokio::test]
+async fn test_fingerprint_on_missing_interface_is_an_error() {
+    let ip = Ipv4Addr::new(192, 0, 2, 10);
+    let result = fingerprint_mac::fingerprint_on(ip, Some("netscan-missing0")).await;
+    assert!(result.mac.is_none());
+    assert_eq!(
+        result.error.as_deref(),
+        Some("Interface netscan-missing0 not found")
+    );
+}
@@ -0,0 +1,60 @@
+use rust_backend::scanners::tcpscan::tcp_scan_host;
+use rust_backend::utils::happy_eyeballs::{connect_addrs, interleave_families, AddressFamily};
+use rust_backend::utils::scan_config::ScanConfig;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+#[test]
+fn test_interleave_starts_with_ipv6_and_alternates() {
+    let addrs: Vec<SocketAddr> = vec![
+        "10.0.0.1:80".parse().unwrap(),
+        "10.0.0.2:80".parse().unwrap(),
+        "[2001:db8::1]:80".parse().unwrap(),
+        "10.0.0.1:80".parse().unwrap(), // duplicate
+    ];
+    let ordered = interleave_families(addrs);
+    assert_eq!(
+        ordered,
+        vec![
+            "[2001:db8::1]:80".parse::<SocketAddr>().unwrap(),
+            "10.0.0.1:80".parse().unwrap(),
+            "10.0.0.2:80".parse().unwrap(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_connect_addrs_falls_back_to_working_address() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let live = listener.local_addr().unwrap();
+
+    // Grab a free port and release it so connecting to it is refused
+    let closed = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let conn = connect_addrs(&[closed, live], Duration::from_secs(2))
+        .await
+        .expect("second address should win");
+    assert_eq!(conn.addr, live);
+    assert_eq!(conn.family, AddressFamily::Ipv4);
+}
+
+#[tokio::test]
+async fn test_connect_addrs_empty() {
+    let result = connect_addrs(&[], Duration::from_secs(1)).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_tcp_scan_host_records_family() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let result = tcp_scan_host("127.0.0.1", port..port + 1, &ScanConfig::default()).await.unwrap();
+    assert_eq!(result.open_ports.len(), 1);
+    assert_eq!(result.open_ports[0].family, AddressFamily::Ipv4);
+}
@@ -0,0 +1,121 @@
+use rust_backend::report::ScanReport;
+use rust_backend::scanners::service_detection::ServiceDetectionResult;
+use rust_backend::scanners::tcpscan::TcpScanResult;
+use rust_backend::scanners::udpscan::UdpScanResult;
+use rust_backend::utils::hooks::{self, FindingHook, HookEvent, HookFilter, split_command};
+use std::net::IpAddr;
+
+#[test]
+fn test_split_command_keeps_quoted_words() {
+    assert_eq!(
+        split_command("shot.sh --title 'web {service}' \"{ip}\":{port}").unwrap(),
+        vec!["shot.sh", "--title", "web {service}", "{ip}:{port}"]
+    );
+    assert!(split_command("shot.sh 'unclosed").is_err());
+    assert!(split_command("   ").is_err());
+}
+
+#[test]
+fn test_placeholders_become_plain_arguments() {
+    let hook = FindingHook::new("echo {ip} {port}/{transport} {service}", 2).unwrap();
+    let event = HookEvent {
+        ip: "10.0.0.5".parse().unwrap(),
+        port: 8080,
+        transport: "tcp",
+        service: Some("HTTP; rm -rf /".to_string()),
+    };
+    // A hostile service name stays one argument; no shell ever sees it
+    assert_eq!(
+        hook.command_for(&event),
+        vec!["echo", "10.0.0.5", "8080/tcp", "HTTP; rm -rf /"]
+    );
+}
+
+#[test]
+fn test_events_cover_open_ports_with_their_services() {
+    let web: IpAddr = "10.0.0.20".parse().unwrap();
+    let mut report = ScanReport::new();
+    let mut tcp = TcpScanResult::new();
+    tcp.add_open_port(web, 443);
+    tcp.add_open_port(web, 22);
+    report.add_tcp(&tcp);
+    let mut udp = UdpScanResult::new();
+    udp.add_open_port(web, 161);
+    report.add_udp(&udp);
+    report.add_services(
+        web,
+        &[
+            ServiceDetectionResult::new(443, Some("HTTPS".to_string()), None, Vec::new()),
+            ServiceDetectionResult::new(8080, Some("HTTP".to_string()), None, Vec::new()),
+            ServiceDetectionResult::new(
+                9000,
+                None,
+                Some("Connection refused".to_string()),
+                Vec::new(),
+            ),
+        ],
+    );
+
+    let events = hooks::events(&report);
+    let summary: Vec<(u16, &str, Option<&str>)> = events
+        .iter()
+        .map(|e| (e.port, e.transport, e.service.as_deref()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (22, "tcp", None),
+            (443, "tcp", Some("HTTPS")),
+            (8080, "tcp", Some("HTTP")),
+            (161, "udp", None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_failed_commands_are_reported() {
+    let event = HookEvent {
+        ip: "10.0.0.5".parse().unwrap(),
+        port: 22,
+        transport: "tcp",
+        service: None,
+    };
+    let ok = FindingHook::new("true {ip}", 1).unwrap();
+    let outcomes = ok.run(vec![event.clone(), event.clone()]).await;
+    assert_eq!(outcomes.len(), 2);
+    assert!(outcomes.iter().all(|o| o.result.is_ok()));
+
+    let failing = FindingHook::new("false", 1).unwrap();
+    assert!(failing.run(vec![event.clone()]).await[0].result.is_err());
+    let missing = FindingHook::new("netscan-no-such-hook-program", 1).unwrap();
+    assert!(missing.run(vec![event]).await[0].result.is_err());
+}
+
+#[test]
+fn test_filter_picks_qualifying_ports() {
+    let event = |port: u16, transport: &'static str, service: Option<&str>| HookEvent {
+        ip: "10.0.0.5".parse().unwrap(),
+        port,
+        transport,
+        service: service.map(String::from),
+    };
+    let web = HookFilter::parse("service:http, service:HTTPS").unwrap();
+    assert!(web.matches(&event(8080, "tcp", Some("HTTP"))));
+    assert!(web.matches(&event(443, "tcp", Some("https"))));
+    assert!(!web.matches(&event(22, "tcp", Some("SSH"))));
+    // No identified service, nothing to match a service term
+    assert!(!web.matches(&event(80, "tcp", None)));
+
+    // Each kind given must match
+    let udp_dns = HookFilter::parse("transport:udp,port:53,port:5353").unwrap();
+    assert!(udp_dns.matches(&event(53, "udp", None)));
+    assert!(!udp_dns.matches(&event(53, "tcp", None)));
+    assert!(!udp_dns.matches(&event(161, "udp", None)));
+
+    assert!(HookFilter::new().matches(&event(22, "tcp", None)));
+    assert!(HookFilter::parse("host:10.0.0.5").is_err());
+    assert!(HookFilter::parse("port:http").is_err());
+    assert!(HookFilter::parse("transport:sctp").is_err());
+    assert!(HookFilter::parse("service:").is_err());
+    assert!(HookFilter::parse(" , ").is_err());
+}
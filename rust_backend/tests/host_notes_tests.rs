@@ -0,0 +1,51 @@
+use chrono::{TimeZone, Utc};
+use rust_backend::utils::host_notes::HostNotes;
+use std::net::IpAddr;
+
+#[test]
+fn test_notes_follow_the_host_by_mac() {
+    let dir = std::env::temp_dir().join(format!("netscan-host-notes-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let old_ip: IpAddr = "10.0.0.42".parse().unwrap();
+    let new_ip: IpAddr = "10.0.0.77".parse().unwrap();
+    let mac = "AA:BB:CC:00:11:22";
+    let now = Utc.with_ymd_and_hms(2026, 5, 4, 9, 30, 0).unwrap();
+
+    let mut notes = HostNotes::load(&dir).unwrap();
+    assert!(notes.is_empty());
+    let note = notes.add(old_ip, Some(mac), "Lab printer, ignore telnet", now);
+    assert_eq!(note.key(), "aa:bb:cc:00:11:22");
+    notes.save().unwrap();
+
+    let mut notes = HostNotes::load(&dir).unwrap();
+    assert_eq!(notes.for_host(old_ip, Some(mac)).len(), 1);
+    // Another device now holding the old address does not inherit the note
+    assert!(notes.for_host(old_ip, Some("aa:bb:cc:99:99:99")).is_empty());
+    // Without a MAC to compare, the last known address decides
+    assert_eq!(notes.for_host(old_ip, None).len(), 1);
+
+    assert!(notes.carry_forward(new_ip, Some(mac)));
+    assert!(!notes.carry_forward(new_ip, Some(mac)));
+    let moved = notes.for_host(new_ip, None);
+    assert_eq!(moved.len(), 1);
+    assert_eq!(moved[0].note, "Lab printer, ignore telnet");
+    assert_eq!(moved[0].at, now);
+    assert!(notes.for_host(old_ip, None).is_empty());
+
+    assert_eq!(notes.clear(new_ip, Some(mac)), 1);
+    assert!(notes.is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_notes_taken_by_address_adopt_the_mac_a_scan_learns() {
+    let dir = std::env::temp_dir().join(format!("netscan-host-notes-ip-{}", std::process::id()));
+    let ip: IpAddr = "192.168.1.9".parse().unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 5, 4, 9, 30, 0).unwrap();
+    let mut notes = HostNotes::load(&dir).unwrap();
+    notes.add(ip, None, "NAS", now);
+    assert!(notes.carry_forward(ip, Some("00:11:32:AA:BB:CC")));
+    let adopted = notes.for_host("192.168.1.50".parse().unwrap(), Some("00:11:32:aa:bb:cc"));
+    assert_eq!(adopted.len(), 1);
+    assert_eq!(adopted[0].key(), "00:11:32:aa:bb:cc");
+}
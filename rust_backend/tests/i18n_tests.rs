@@ -0,0 +1,70 @@
+use rust_backend::utils::i18n::{self, Locale, Msg, parse_locale};
+
+#[test]
+fn test_locale_from_tags_and_environment() {
+    assert_eq!(Locale::from_tag("de_DE.UTF-8"), Some(Locale::De));
+    assert_eq!(Locale::from_tag("de-AT"), Some(Locale::De));
+    assert_eq!(Locale::from_tag("C"), Some(Locale::En));
+    assert_eq!(Locale::from_tag("fr_FR"), None);
+    assert!(parse_locale("fr").is_err());
+
+    // LC_ALL wins over LC_MESSAGES wins over LANG; empty ones are unset
+    assert_eq!(
+        Locale::from_env_vars(Some(""), Some("de_CH.UTF-8"), Some("en_US.UTF-8")),
+        Locale::De
+    );
+    assert_eq!(
+        Locale::from_env_vars(Some("C"), None, Some("de_DE")),
+        Locale::En
+    );
+    assert_eq!(Locale::from_env_vars(None, None, Some("ja_JP")), Locale::En);
+    assert_eq!(Locale::from_env_vars(None, None, None), Locale::En);
+}
+
+#[test]
+fn test_messages_fill_placeholders_per_locale() {
+    let english = Msg::TargetsExcluded.text_in(Locale::En);
+    assert_eq!(
+        i18n::fill(english, &[("excluded", &3), ("left", &250)]),
+        "🚫 3 target(s) excluded; 250 left"
+    );
+    let german = Msg::TargetsExcluded.text_in(Locale::De);
+    assert_eq!(
+        i18n::fill(german, &[("left", &250), ("excluded", &3)]),
+        "🚫 3 Ziel(e) ausgeschlossen; 250 verbleiben"
+    );
+    // A missing argument leaves its placeholder visible rather than panicking
+    assert_eq!(
+        i18n::fill("{done} of {total}", &[("done", &1)]),
+        "1 of {total}"
+    );
+
+    // Translations may put the arguments in another order
+    assert_eq!(
+        i18n::fill(
+            Msg::Marked.text_in(Locale::En),
+            &[("id", &"a1"), ("verdict", &"accepted")]
+        ),
+        "✅ Marked a1 as accepted:"
+    );
+    assert_eq!(
+        i18n::fill(
+            Msg::ReportWritten.text_in(Locale::De),
+            &[
+                ("path", &"x.csv"),
+                ("what", &Msg::LoginBanners.text_in(Locale::De))
+            ]
+        ),
+        "📄 Login-Banner nach x.csv geschrieben"
+    );
+    assert_eq!(
+        i18n::fill(
+            Msg::HooksDone.text_in(Locale::De),
+            &[("failed", &1), ("succeeded", &4)]
+        ),
+        "🪝 4 Hook(s) erfolgreich, 1 fehlgeschlagen"
+    );
+
+    // Without an installed locale, messages are English
+    assert_eq!(Msg::NoLiveHosts.text(), "No live hosts found. Exiting.");
+}
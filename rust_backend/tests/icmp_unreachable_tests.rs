@@ -0,0 +1,52 @@
+use rust_backend::scanners::icmp_unreachable::{parse_icmpv4, parse_icmpv6};
+use rust_backend::utils::errors::ErrorCategory;
+use std::net::IpAddr;
+
+/// ICMPv4 destination unreachable quoting a UDP datagram to 10.0.0.5:161
+fn icmpv4(code: u8, protocol: u8) -> Vec<u8> {
+    let mut message = vec![3, code, 0, 0, 0, 0, 0, 0];
+    let mut ip = vec![0x45, 0, 0, 28, 0, 0, 0, 0, 64, protocol, 0, 0];
+    ip.extend_from_slice(&[10, 0, 0, 1, 10, 0, 0, 5]);
+    message.extend_from_slice(&ip);
+    message.extend_from_slice(&[0xc3, 0x50, 0, 161, 0, 8, 0, 0]);
+    message
+}
+
+#[test]
+fn test_parse_icmpv4_unreachable() {
+    let target: IpAddr = "10.0.0.5".parse().unwrap();
+    assert_eq!(
+        parse_icmpv4(&icmpv4(3, 17)),
+        Some((target, 161, ErrorCategory::Refused))
+    );
+    assert_eq!(
+        parse_icmpv4(&icmpv4(13, 17)),
+        Some((target, 161, ErrorCategory::Unreachable))
+    );
+    // Quoting TCP, or a code that says nothing about filtering
+    assert_eq!(parse_icmpv4(&icmpv4(3, 6)), None);
+    assert_eq!(parse_icmpv4(&icmpv4(4, 17)), None);
+    assert_eq!(parse_icmpv4(&icmpv4(3, 17)[..30]), None);
+    // Echo reply
+    assert_eq!(parse_icmpv4(&[0, 0, 0, 0, 0, 1, 0, 1]), None);
+}
+
+#[test]
+fn test_parse_icmpv6_unreachable() {
+    let mut message = vec![1, 4, 0, 0, 0, 0, 0, 0];
+    let mut ip = vec![0x60, 0, 0, 0, 0, 8, 17, 64];
+    ip.extend_from_slice(&"fd00::1".parse::<std::net::Ipv6Addr>().unwrap().octets());
+    ip.extend_from_slice(&"fd00::5".parse::<std::net::Ipv6Addr>().unwrap().octets());
+    message.extend_from_slice(&ip);
+    message.extend_from_slice(&[0xc3, 0x50, 0x01, 0xf4, 0, 8, 0, 0]);
+    let target: IpAddr = "fd00::5".parse().unwrap();
+    assert_eq!(
+        parse_icmpv6(&message),
+        Some((target, 500, ErrorCategory::Refused))
+    );
+    message[1] = 1;
+    assert_eq!(
+        parse_icmpv6(&message),
+        Some((target, 500, ErrorCategory::Unreachable))
+    );
+}
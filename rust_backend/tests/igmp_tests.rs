@@ -0,0 +1,134 @@
+use pnet::datalink::MacAddr;
+use pnet::packet::Packet;
+use pnet::packet::ethernet::EthernetPacket;
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv4::{self, Ipv4Packet};
+use rust_backend::scanners::igmp::{self, GroupMemberships};
+use rust_backend::utils::vlan;
+use std::net::Ipv4Addr;
+
+/// An untagged frame carrying `igmp` from `src` to `dst`.
+fn frame(src: Ipv4Addr, dst: Ipv4Addr, igmp: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x01, 0x00, 0x5e, 0x00, 0x00, 0x16];
+    frame.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x08, 0x00]);
+    let total = (20 + igmp.len()) as u16;
+    frame.extend_from_slice(&[0x45, 0x00]);
+    frame.extend_from_slice(&total.to_be_bytes());
+    frame.extend_from_slice(&[0, 0, 0, 0, 1, 2, 0, 0]);
+    frame.extend_from_slice(&src.octets());
+    frame.extend_from_slice(&dst.octets());
+    frame.extend_from_slice(igmp);
+    frame
+}
+
+#[test]
+fn test_general_query_is_well_formed() {
+    let query = igmp::build_query(
+        MacAddr::new(0x02, 0, 0, 0, 0, 1),
+        Ipv4Addr::new(10, 0, 0, 2),
+        None,
+    )
+    .unwrap();
+    let ethernet = EthernetPacket::new(&query).unwrap();
+    assert_eq!(
+        ethernet.get_destination(),
+        MacAddr::new(0x01, 0x00, 0x5e, 0x00, 0x00, 0x01)
+    );
+    let ip = Ipv4Packet::new(ethernet.payload()).unwrap();
+    assert_eq!(ip.get_destination(), Ipv4Addr::new(224, 0, 0, 1));
+    assert_eq!(ip.get_ttl(), 1);
+    assert_eq!(ip.get_next_level_protocol(), IpNextHeaderProtocols::Igmp);
+    assert_eq!(ip.get_checksum(), ipv4::checksum(&ip));
+    // Router Alert
+    assert_eq!(&ip.packet()[20..24], &[0x94, 0x04, 0x00, 0x00]);
+
+    let igmp = ip.payload();
+    assert_eq!(igmp.len(), 12);
+    assert_eq!(igmp[0], 0x11);
+    assert_eq!(&igmp[4..8], &[0, 0, 0, 0]);
+    // A packet with a correct checksum sums to zero
+    assert_eq!(pnet::util::checksum(igmp, 99), 0);
+
+    let tagged = igmp::build_query(
+        MacAddr::new(0x02, 0, 0, 0, 0, 1),
+        Ipv4Addr::new(10, 0, 0, 2),
+        Some(20),
+    )
+    .unwrap();
+    assert_eq!(vlan::frame_vlan_id(&tagged), Some(20));
+}
+
+#[test]
+fn test_v2_report_names_its_group() {
+    let host = Ipv4Addr::new(10, 0, 0, 7);
+    let group = Ipv4Addr::new(239, 255, 255, 250);
+    let mut report = vec![0x16, 0, 0, 0];
+    report.extend_from_slice(&group.octets());
+    assert_eq!(
+        igmp::parse_report(&frame(host, group, &report)),
+        Some((host, vec![group]))
+    );
+
+    // Queries from other routers are not reports
+    let query = [0x11, 100, 0, 0, 0, 0, 0, 0];
+    assert_eq!(
+        igmp::parse_report(&frame(host, Ipv4Addr::new(224, 0, 0, 1), &query)),
+        None
+    );
+}
+
+#[test]
+fn test_v3_report_skips_groups_being_left() {
+    let host = Ipv4Addr::new(10, 0, 0, 9);
+    let mut report = vec![0x22, 0, 0, 0, 0, 0, 0, 3];
+    // MODE_IS_EXCLUDE, no sources: member of 224.0.0.251
+    report.extend_from_slice(&[2, 0, 0, 0, 224, 0, 0, 251]);
+    // MODE_IS_INCLUDE, one source: member of 232.1.1.1 for 10.9.9.9
+    report.extend_from_slice(&[1, 0, 0, 1, 232, 1, 1, 1, 10, 9, 9, 9]);
+    // CHANGE_TO_INCLUDE_MODE, no sources: leaving 239.1.2.3
+    report.extend_from_slice(&[3, 0, 0, 0, 239, 1, 2, 3]);
+
+    let (reporter, groups) =
+        igmp::parse_report(&frame(host, Ipv4Addr::new(224, 0, 0, 22), &report)).unwrap();
+    assert_eq!(reporter, host);
+    assert_eq!(
+        groups,
+        [Ipv4Addr::new(224, 0, 0, 251), Ipv4Addr::new(232, 1, 1, 1)]
+    );
+
+    // A record count beyond the packet is malformed
+    report[7] = 4;
+    assert_eq!(
+        igmp::parse_report(&frame(host, Ipv4Addr::new(224, 0, 0, 22), &report)),
+        None
+    );
+}
+
+#[test]
+fn test_memberships_are_per_host() {
+    let a = Ipv4Addr::new(10, 0, 0, 7);
+    let b = Ipv4Addr::new(10, 0, 0, 8);
+    let mut memberships = GroupMemberships::new();
+    memberships.add_report(a, vec![Ipv4Addr::new(239, 255, 255, 250)]);
+    memberships.add_report(a, vec![Ipv4Addr::new(224, 0, 0, 251)]);
+    memberships.add_report(b, Vec::new());
+
+    assert_eq!(
+        memberships.groups(a),
+        [
+            Ipv4Addr::new(224, 0, 0, 251),
+            Ipv4Addr::new(239, 255, 255, 250)
+        ]
+    );
+    assert!(memberships.groups(b).is_empty());
+    assert_eq!(memberships.hosts().collect::<Vec<_>>(), [a]);
+    assert_eq!(
+        igmp::describe_group(Ipv4Addr::new(224, 0, 0, 251)),
+        "224.0.0.251 (mDNS)"
+    );
+    assert_eq!(
+        igmp::group_name(Ipv4Addr::new(239, 7, 7, 7)),
+        Some("site-local")
+    );
+    assert_eq!(igmp::group_name(Ipv4Addr::new(225, 1, 1, 1)), None);
+}
@@ -0,0 +1,54 @@
+use rust_backend::utils::inventory::{Inventory, InventoryEntry};
+use std::net::IpAddr;
+
+fn ip(text: &str) -> IpAddr {
+    text.parse().unwrap()
+}
+
+const INVENTORY: &str = "\
+hostname,ip,mac,owner,role
+core-rtr,10.0.0.1,,netops,router
+printer-2f,,00-80-77-AA-BB-CC,facilities,printer
+,10.0.0.9,,,
+";
+
+#[test]
+fn test_inventory_matches_by_ip_then_mac() {
+    let inventory = Inventory::parse_csv(INVENTORY).unwrap();
+    assert_eq!(inventory.len(), 3);
+
+    let router = inventory.lookup(ip("10.0.0.1"), None).unwrap();
+    assert_eq!(router.to_string(), "core-rtr, owner netops, role router");
+    // The printer got a new DHCP lease but keeps its MAC
+    let printer = inventory
+        .lookup(ip("10.0.0.77"), Some("00:80:77:aa:bb:cc"))
+        .unwrap();
+    assert_eq!(printer.hostname.as_deref(), Some("printer-2f"));
+    assert_eq!(
+        inventory.lookup(ip("10.0.0.9"), None),
+        Some(&InventoryEntry {
+            ip: Some(ip("10.0.0.9")),
+            ..InventoryEntry::default()
+        })
+    );
+    assert!(inventory.lookup(ip("10.0.0.50"), None).is_none());
+}
+
+#[test]
+fn test_unknown_devices_are_flagged() {
+    let inventory = Inventory::parse_csv(INVENTORY).unwrap();
+    let hosts = [ip("10.0.0.1"), ip("10.0.0.77"), ip("10.0.0.50")];
+    let mac_of = |host: IpAddr| (host == ip("10.0.0.77")).then(|| "00:80:77:aa:bb:cc".to_string());
+    assert_eq!(
+        inventory.unknown_hosts(&hosts, mac_of),
+        vec![ip("10.0.0.50")]
+    );
+}
+
+#[test]
+fn test_invalid_inventories_are_rejected() {
+    assert!(Inventory::parse_csv("hostname,owner\nweb01,ops\n").is_err());
+    let error = Inventory::parse_csv("ip,hostname\n10.0.0.300,web01\n").unwrap_err();
+    assert!(error.contains("line 2"), "{}", error);
+    assert!(Inventory::parse_csv("mac\n00:11:22\n").is_err());
+}
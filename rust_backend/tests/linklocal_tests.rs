@@ -0,0 +1,101 @@
+use pnet::datalink::MacAddr;
+use pnet::packet::Packet;
+use pnet::packet::ethernet::EthernetPacket;
+use pnet::packet::icmpv6::{self, Icmpv6Packet, Icmpv6Types};
+use pnet::packet::ipv6::Ipv6Packet;
+use rust_backend::scanners::linklocal::{self, LinkEnumeration};
+use std::net::Ipv6Addr;
+
+const OUR_MAC: MacAddr = MacAddr(0x02, 0, 0, 0, 0, 1);
+
+#[test]
+fn test_eui64_identifiers_give_the_mac_back() {
+    let slaac: Ipv6Addr = "fe80::21a:2bff:fe3c:4d5e".parse().unwrap();
+    assert_eq!(
+        linklocal::eui64_mac(slaac),
+        Some(MacAddr::new(0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e))
+    );
+    // Privacy and stable-opaque identifiers embed no MAC
+    let random: Ipv6Addr = "2001:db8::8c4e:91a2:7b3d:1f06".parse().unwrap();
+    assert_eq!(linklocal::eui64_mac(random), None);
+}
+
+#[test]
+fn test_all_nodes_echo_round_trip() {
+    let src: Ipv6Addr = "fe80::1".parse().unwrap();
+    let request = linklocal::build_all_nodes_echo(OUR_MAC, src, 0x4242, None).unwrap();
+    let ethernet = EthernetPacket::new(&request).unwrap();
+    assert_eq!(
+        ethernet.get_destination(),
+        MacAddr::new(0x33, 0x33, 0, 0, 0, 1)
+    );
+    let ipv6 = Ipv6Packet::new(ethernet.payload()).unwrap();
+    assert_eq!(
+        ipv6.get_destination(),
+        "ff02::1".parse::<Ipv6Addr>().unwrap()
+    );
+    let echo = Icmpv6Packet::new(ipv6.payload()).unwrap();
+    assert_eq!(echo.get_icmpv6_type(), Icmpv6Types::EchoRequest);
+    assert_eq!(
+        echo.get_checksum(),
+        icmpv6::checksum(&echo, &src, &ipv6.get_destination())
+    );
+
+    // Turn it into the reply a neighbor would send
+    let neighbor_mac = MacAddr::new(0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e);
+    let neighbor: Ipv6Addr = "fe80::21a:2bff:fe3c:4d5e".parse().unwrap();
+    let mut reply = request.clone();
+    reply[6..12].copy_from_slice(&neighbor_mac.octets());
+    reply[14 + 8..14 + 24].copy_from_slice(&neighbor.octets());
+    reply[14 + 40] = 129;
+    assert_eq!(
+        linklocal::parse_echo_reply(&reply, 0x4242),
+        Some((neighbor, neighbor_mac, None))
+    );
+    // Replies to someone else's ping are not ours
+    assert_eq!(linklocal::parse_echo_reply(&reply, 0x4243), None);
+    assert_eq!(linklocal::parse_echo_reply(&request, 0x4242), None);
+}
+
+#[test]
+fn test_neighbor_table_parsing() {
+    let output = "\
+fe80::1 dev eth0 lladdr 00:11:22:33:44:55 router REACHABLE
+2001:db8::10 dev eth0 lladdr 00:1a:2b:3c:4d:5e STALE
+fe80::99 dev eth0 FAILED
+fe80::7 dev wlan0 lladdr 00:aa:bb:cc:dd:ee STALE
+fe80::21a:2bff:fe3c:4d5e lladdr 00:1a:2b:3c:4d:5e DELAY
+";
+    let entries = linklocal::parse_neighbor_table(output, "eth0");
+    assert_eq!(entries.len(), 3);
+    assert_eq!(
+        entries[0],
+        (
+            "fe80::1".parse().unwrap(),
+            MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55),
+            true
+        )
+    );
+    assert!(!entries[1].2);
+
+    let mut link = LinkEnumeration::new("eth0".to_string(), 2);
+    for (ip, mac, router) in entries {
+        link.add(ip, mac, router);
+    }
+    // The same MAC with two addresses is one host
+    assert_eq!(link.get_neighbors().len(), 2);
+    let host = &link.get_neighbors()[1];
+    assert!(host.uses_eui64());
+    assert_eq!(host.link_local().count(), 1);
+    // The router has only its link-local address, so that one is scanned
+    assert_eq!(
+        link.scan_addresses(),
+        [
+            "fe80::1".parse::<Ipv6Addr>().unwrap(),
+            "2001:db8::10".parse().unwrap()
+        ]
+    );
+    let router = &link.get_neighbors()[0];
+    assert!(router.router);
+    assert!(!router.uses_eui64());
+}
@@ -0,0 +1,86 @@
+use rust_backend::utils::listeners::{
+    ListenerAudit, parse_expected, parse_socket_link, parse_socket_table,
+};
+use std::net::IpAddr;
+
+const TCP_TABLE: &str = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 21401 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:1538 00000000:0000 0A 00000000:00000000 00:00000000 00000000   113        0 23850 1 0000000000000000 100 0 0 10 0
+   2: 0201A8C0:0016 0A01A8C0:D431 01 00000000:00000000 02:00089A4B 00000000     0        0 51233 4 0000000000000000 20 4 29 10 -1
+";
+
+const TCP6_TABLE: &str = "\
+  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:0277 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 30112 1 0000000000000000 100 0 0 10 0
+";
+
+#[test]
+fn test_parse_socket_table_keeps_listeners() {
+    let listeners = parse_socket_table(TCP_TABLE, "tcp");
+    assert_eq!(listeners.len(), 2);
+    assert_eq!(listeners[0].port, 22);
+    assert_eq!(listeners[0].inode, 21401);
+    assert!(listeners[0].is_exposed());
+    assert_eq!(listeners[1].address, "127.0.0.1".parse::<IpAddr>().unwrap());
+    assert_eq!(listeners[1].port, 5432);
+    assert!(!listeners[1].is_exposed());
+
+    let listeners = parse_socket_table(TCP6_TABLE, "tcp");
+    assert_eq!(listeners[0].address, "::1".parse::<IpAddr>().unwrap());
+    assert_eq!(listeners[0].to_string(), "tcp/[::1]:631");
+
+    assert_eq!(parse_socket_link("socket:[21401]"), Some(21401));
+    assert_eq!(parse_socket_link("/dev/null"), None);
+}
+
+#[test]
+fn test_audit_flags_exposed_ports_outside_allowlist() {
+    let expected = parse_expected("443, udp/53").unwrap();
+    assert!(expected.contains(&("tcp", 443)));
+    assert!(expected.contains(&("udp", 53)));
+    assert!(parse_expected("sctp/9").is_err());
+
+    let listeners = parse_socket_table(TCP_TABLE, "tcp");
+    let audit = ListenerAudit::new(&listeners, &expected);
+    assert!(!audit.passed());
+    // The loopback-only listener on 5432 is not reachable, so not flagged
+    assert_eq!(audit.unexpected.len(), 1);
+    assert_eq!(audit.unexpected[0].port, 22);
+    assert_eq!(audit.missing, vec![("tcp", 443), ("udp", 53)]);
+
+    let audit = ListenerAudit::new(&listeners, &parse_expected("22").unwrap());
+    assert!(audit.passed());
+}
+
+const TCP6_MAPPED_TABLE: &str = "\
+  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0000000000000000FFFF00000100007F:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 40211 1 0000000000000000 100 0 0 10 0
+";
+
+const UDP_TABLE: &str = "\
+   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  1: 00000000:0035 00000000:0000 07 00000000:00000000 00:00000000 00000000     0        0 19034 2 0000000000000000 0
+  2: 00000000:D431 00000000:0000 07 00000000:00000000 00:00000000 00000000  1000        0 52117 2 0000000000000000 0
+";
+
+#[test]
+fn test_mapped_loopback_is_not_exposed() {
+    let listeners = parse_socket_table(TCP6_MAPPED_TABLE, "tcp");
+    assert_eq!(
+        listeners[0].address,
+        "::ffff:127.0.0.1".parse::<IpAddr>().unwrap()
+    );
+    assert!(!listeners[0].is_exposed());
+}
+
+#[test]
+fn test_udp_client_sockets_are_not_listeners() {
+    let listeners = parse_socket_table(UDP_TABLE, "udp");
+    assert_eq!(listeners.len(), 2);
+    assert!(!listeners[0].is_udp_client((32768, 60999)));
+    assert!(listeners[1].is_udp_client((32768, 60999)));
+    // A TCP listener on an ephemeral port is still a listener
+    let tcp = parse_socket_table(TCP_TABLE, "tcp");
+    assert!(!tcp[0].is_udp_client((1, 65535)));
+}
@@ -0,0 +1,87 @@
+use rust_backend::scanners::service_detection::{
+    self, Detection, DetectorRegistry, FnDetector, Protocol,
+};
+use rust_backend::utils::logging;
+use rust_backend::utils::scan_config::ScanConfig;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, Mutex};
+use tracing::level_filters::LevelFilter;
+
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_detector_probes_are_logged_in_a_host_port_span() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_current_span(true)
+        .with_max_level(LevelFilter::DEBUG)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let mut registry = DetectorRegistry::new();
+    registry.register(
+        Protocol::Pop3,
+        FnDetector::new("POP3", &[110], |_, _| async {
+            Detection::new(true, "POP3".to_string(), None)
+        }),
+    );
+    service_detection::detect_service_with(
+        &registry,
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+        1110,
+        &[Protocol::Pop3],
+        &ScanConfig::default(),
+    )
+    .await;
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    let events: Vec<serde_json::Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let probe = events
+        .iter()
+        .find(|event| event["fields"]["message"] == "probe finished")
+        .expect("no probe event");
+    assert_eq!(probe["level"], "DEBUG");
+    assert_eq!(probe["fields"]["detector"], "POP3");
+    assert_eq!(probe["fields"]["detected"], true);
+    assert_eq!(probe["span"]["name"], "detect_service");
+    assert_eq!(probe["span"]["ip"], "127.0.0.1");
+    assert_eq!(probe["span"]["port"], 1110);
+}
+
+#[test]
+fn test_verbosity_levels() {
+    assert_eq!(logging::verbosity_level(0), LevelFilter::WARN);
+    assert_eq!(logging::verbosity_level(1), LevelFilter::INFO);
+    assert_eq!(logging::verbosity_level(2), LevelFilter::DEBUG);
+    assert_eq!(logging::verbosity_level(7), LevelFilter::TRACE);
+    // -v never hides what --log-level asked for
+    assert_eq!(
+        LevelFilter::DEBUG.max(logging::verbosity_level(1)),
+        LevelFilter::DEBUG
+    );
+}
+
+#[test]
+fn test_logging_is_set_up_once() {
+    assert!(logging::init(LevelFilter::WARN, false).is_ok());
+    assert!(logging::init(LevelFilter::WARN, true).is_err());
+}
@@ -0,0 +1,255 @@
+use chrono::{TimeZone, Utc};
+use rust_backend::detect_tls::CertificateInfo;
+use rust_backend::utils::cert_inventory::CertificateInventory;
+use rust_backend::utils::findings::Severity;
+use rust_backend::utils::inventory::Inventory;
+use rust_backend::utils::monitor::{self, MonitorState};
+use std::net::{IpAddr, Ipv4Addr};
+
+fn certificate(subject: &str, issuer: &str, expires_day: u32) -> CertificateInfo {
+    CertificateInfo {
+        serial: format!("{:02x}", expires_day),
+        subject: subject.to_string(),
+        issuer: issuer.to_string(),
+        sans: Vec::new(),
+        key_algorithm: "RSA".to_string(),
+        key_bits: Some(2048),
+        key_fingerprint: "0123456789abcdef".to_string(),
+        not_before: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(),
+        not_after: Utc
+            .with_ymd_and_hms(2026, 11, expires_day, 0, 0, 0)
+            .unwrap(),
+        self_signed: false,
+    }
+}
+
+#[test]
+fn test_alerts_for_expiring_certificates() {
+    let now = Utc.with_ymd_and_hms(2026, 11, 10, 0, 0, 0).unwrap();
+    let mut inventory = CertificateInventory::new();
+    inventory.record(
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+        443,
+        &certificate("old", "CA", 5),
+    );
+    inventory.record(
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+        443,
+        &certificate("soon", "CA", 20),
+    );
+    inventory.record(
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 3)),
+        443,
+        &certificate("fine", "CA", 30),
+    );
+
+    let alerts = monitor::certificate_alerts(None, &inventory, now, 14);
+    assert_eq!(alerts.len(), 2);
+    assert_eq!(alerts[0].severity, Severity::High);
+    assert_eq!(alerts[0].title, "Certificate expired");
+    assert_eq!(alerts[1].title, "Certificate expiring soon");
+    assert!(alerts[1].detail.contains("10.0.0.2:443"));
+}
+
+#[test]
+fn test_check_certificates_compares_with_previous_run() {
+    let dir = std::env::temp_dir().join(format!("netscan-monitor-{}", std::process::id()));
+    let state = MonitorState::open(&dir).unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 1, 10, 0, 0, 0).unwrap();
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    let mut first = CertificateInventory::new();
+    first.record(ip, 443, &certificate("www", "Example CA", 30));
+    assert!(
+        monitor::check_certificates(&state, &first, now, 30)
+            .unwrap()
+            .is_empty()
+    );
+
+    let mut second = CertificateInventory::new();
+    second.record(ip, 443, &certificate("www", "Rogue CA", 29));
+    let alerts = monitor::check_certificates(&state, &second, now, 30).unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].title, "Certificate issuer changed");
+    assert!(dir.join("alerts.log").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_muted_alerts_are_still_logged() {
+    let dir = std::env::temp_dir().join(format!("netscan-mutes-{}", std::process::id()));
+    let state = MonitorState::open(&dir).unwrap();
+    std::fs::write(dir.join(monitor::MUTE_FILE), "10.0.0.0/24 # patching\n").unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 11, 10, 0, 0, 0).unwrap();
+
+    let mut inventory = CertificateInventory::new();
+    inventory.record(
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+        443,
+        &certificate("muted", "CA", 5),
+    );
+    inventory.record(
+        IpAddr::V4(Ipv4Addr::new(10, 0, 1, 1)),
+        443,
+        &certificate("loud", "CA", 6),
+    );
+    let alerts = monitor::check_certificates(&state, &inventory, now, 14).unwrap();
+    assert_eq!(alerts.len(), 2);
+
+    let log = std::fs::read_to_string(dir.join("alerts.log")).unwrap();
+    let muted: Vec<&str> = log.lines().filter(|l| l.contains("[muted]")).collect();
+    assert_eq!(muted.len(), 1);
+    assert!(muted[0].contains("muted expires"));
+    assert_eq!(log.lines().count(), 2);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_expiry_alert_id_is_stable_as_days_count_down() {
+    let mut inventory = CertificateInventory::new();
+    inventory.record(
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+        443,
+        &certificate("soon", "CA", 20),
+    );
+    let monday = Utc.with_ymd_and_hms(2026, 11, 10, 0, 0, 0).unwrap();
+    let tuesday = Utc.with_ymd_and_hms(2026, 11, 11, 0, 0, 0).unwrap();
+    let first = monitor::certificate_alerts(None, &inventory, monday, 14);
+    let second = monitor::certificate_alerts(None, &inventory, tuesday, 14);
+    assert_ne!(first[0].detail, second[0].detail);
+    assert_eq!(
+        monitor::alert_id(&first[0]),
+        monitor::alert_id(&second[0])
+    );
+}
+
+#[test]
+fn test_changes_keep_alerting_until_acknowledged() {
+    let dir = std::env::temp_dir().join(format!("netscan-ack-{}", std::process::id()));
+    let state = MonitorState::open(&dir).unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 1, 10, 0, 0, 0).unwrap();
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    let mut before = CertificateInventory::new();
+    before.record(ip, 443, &certificate("www", "Example CA", 30));
+    monitor::check_certificates(&state, &before, now, 30).unwrap();
+
+    let mut after = CertificateInventory::new();
+    after.record(ip, 443, &certificate("www", "New CA", 29));
+    let alerts = monitor::check_certificates(&state, &after, now, 30).unwrap();
+    assert_eq!(alerts.len(), 1);
+    // Unacknowledged, the same change is reported again on the next run
+    let again = monitor::check_certificates(&state, &after, now, 30).unwrap();
+    assert_eq!(again, alerts);
+
+    let id = monitor::alert_id(&alerts[0]);
+    let ack = state.acknowledge(&id, "planned CA migration", now).unwrap();
+    assert!(ack.alert.contains("issuer changed"));
+    assert!(state.acknowledge(&id, "", now).is_err());
+
+    // The acknowledged change is logged once more, silenced, and then becomes the baseline
+    monitor::check_certificates(&state, &after, now, 30).unwrap();
+    assert!(
+        monitor::check_certificates(&state, &after, now, 30)
+            .unwrap()
+            .is_empty()
+    );
+    let log = std::fs::read_to_string(dir.join("alerts.log")).unwrap();
+    assert_eq!(log.lines().filter(|l| l.contains("[acknowledged]")).count(), 1);
+
+    let history = state.acknowledgements().unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].id, id);
+    assert_eq!(history[0].note, "planned CA migration");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_acknowledging_unknown_alert_fails() {
+    let dir = std::env::temp_dir().join(format!("netscan-ack-unknown-{}", std::process::id()));
+    let state = MonitorState::open(&dir).unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 1, 10, 0, 0, 0).unwrap();
+    assert!(state.acknowledge("000000000000", "", now).is_err());
+    assert!(state.acknowledgements().unwrap().is_empty());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_expiry_alerts_are_raised_once() {
+    let dir = std::env::temp_dir().join(format!("netscan-expiry-once-{}", std::process::id()));
+    let state = MonitorState::open(&dir).unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 11, 10, 0, 0, 0).unwrap();
+    let mut inventory = CertificateInventory::new();
+    inventory.record(
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+        443,
+        &certificate("soon", "CA", 20),
+    );
+
+    assert_eq!(
+        monitor::check_certificates(&state, &inventory, now, 14)
+            .unwrap()
+            .len(),
+        1
+    );
+    let tomorrow = now + chrono::Duration::days(1);
+    assert!(
+        monitor::check_certificates(&state, &inventory, tomorrow, 14)
+            .unwrap()
+            .is_empty()
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_run_without_certificates_keeps_the_baseline() {
+    let dir = std::env::temp_dir().join(format!("netscan-empty-run-{}", std::process::id()));
+    let state = MonitorState::open(&dir).unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 1, 10, 0, 0, 0).unwrap();
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    let mut first = CertificateInventory::new();
+    first.record(ip, 443, &certificate("www", "Example CA", 30));
+    monitor::check_certificates(&state, &first, now, 30).unwrap();
+    // The endpoint was unreachable this time
+    monitor::check_certificates(&state, &CertificateInventory::new(), now, 30).unwrap();
+
+    let mut third = CertificateInventory::new();
+    third.record(ip, 443, &certificate("www", "Rogue CA", 29));
+    let alerts = monitor::check_certificates(&state, &third, now, 30).unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].title, "Certificate issuer changed");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_acknowledged_devices_are_no_longer_unknown() {
+    let dir = std::env::temp_dir().join(format!("netscan-ack-device-{}", std::process::id()));
+    let inventory = Inventory::parse_csv("hostname,ip,mac,owner,role\ncore-rtr,10.0.0.1,,,\n").unwrap();
+    let hosts: Vec<IpAddr> = (1..=3).map(|last| IpAddr::V4(Ipv4Addr::new(10, 0, 0, last))).collect();
+    let no_mac = |_| None;
+    // Without a state directory every unlisted host is unknown, and none is created
+    assert_eq!(
+        monitor::unapproved_hosts(&dir, &inventory, &hosts, no_mac),
+        &hosts[1..]
+    );
+    assert!(!dir.exists());
+
+    let state = MonitorState::open(&dir).unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 1, 10, 0, 0, 0).unwrap();
+    let alerts = monitor::check_devices(&state, &inventory, &hosts, no_mac, now).unwrap();
+    assert_eq!(alerts.len(), 2);
+    state
+        .acknowledge(&monitor::alert_id(&alerts[0]), "lab switch", now)
+        .unwrap();
+    assert_eq!(
+        monitor::unapproved_hosts(&dir, &inventory, &hosts, no_mac),
+        &hosts[2..]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,171 @@
+use rust_backend::scanners::service_detection::{self, ServiceDetectionResult};
+use rust_backend::utils::evidence::{Attribute, Evidence, Source};
+use metasploit_tools::ServiceInfo;
+use rust_backend::utils::findings::{Severity, code};
+use rust_backend::utils::msf::{self, CveLookup, MsfSuggestions};
+use rust_backend::utils::reports;
+use std::net::IpAddr;
+
+fn detected(port: u16, service: &str, source: Source) -> ServiceDetectionResult {
+    let mut result = ServiceDetectionResult::new(port, Some(service.to_string()), None, Vec::new());
+    result
+        .evidence
+        .push(Evidence::new(Attribute::Service, service, source).on_port(port));
+    result
+}
+
+#[test]
+fn test_detection_results_convert_to_service_info() {
+    let registry = service_detection::registry();
+    let ip: IpAddr = "10.0.0.5".parse().unwrap();
+
+    let mut ftp = detected(21, "FTP", Source::Probe("FTP".to_string()));
+    ftp.product = Some("vsFTPd".to_string());
+    ftp.version = Some("2.3.4".to_string());
+    let info = msf::service_info(&registry, ip, &ftp).unwrap();
+    assert_eq!(info.host, "10.0.0.5");
+    assert_eq!(info.port, 21);
+    assert_eq!(info.name, "ftp");
+    assert_eq!(info.version.as_deref(), Some("2.3.4"));
+    assert_eq!(info.banner, None);
+    assert_eq!(info.transport, "tcp");
+
+    let mut snmp = detected(161, "SNMP", Source::Probe("SNMP".to_string()));
+    snmp.transport = "udp";
+    assert_eq!(msf::service_info(&registry, ip, &snmp).unwrap().transport, "udp");
+
+    // Detector names map to the protocol labels suggestion rules use
+    let postgres = detected(
+        5432,
+        "PostgreSQL (no SSL)",
+        Source::Probe("PostgreSQL".to_string()),
+    );
+    assert_eq!(
+        msf::service_info(&registry, ip, &postgres).unwrap().name,
+        "postgres"
+    );
+
+    let banner = detected(
+        6667,
+        "Banner: :irc.local NOTICE * :UnrealIRCd 3.2.8.1",
+        Source::Banner(":irc.local NOTICE * :UnrealIRCd 3.2.8.1".to_string()),
+    );
+    let info = msf::service_info(&registry, ip, &banner).unwrap();
+    assert_eq!(info.name, "unknown");
+    assert!(info.banner.unwrap().contains("UnrealIRCd"));
+
+    let unknown =
+        ServiceDetectionResult::new(8080, Some("Unknown Service".to_string()), None, Vec::new());
+    assert!(msf::service_info(&registry, ip, &unknown).is_none());
+}
+
+#[test]
+fn test_suggestions_cover_every_detected_service() {
+    let registry = service_detection::registry();
+    let ip: IpAddr = "10.0.0.5".parse().unwrap();
+    let mut ftp = detected(21, "FTP", Source::Probe("FTP".to_string()));
+    ftp.product = Some("vsFTPd".to_string());
+    ftp.version = Some("2.3.4".to_string());
+    let results = vec![
+        ftp,
+        detected(22, "SSH", Source::Banner("SSH-2.0-OpenSSH_7.4".to_string())),
+        detected(7, "echo (echoed probe)", Source::Probe("ECHO".to_string())),
+    ];
+    let mut suggestions = MsfSuggestions::new();
+    suggestions.add_results(&registry, ip, &results).unwrap();
+    let earlier: IpAddr = "10.0.0.2".parse().unwrap();
+    suggestions
+        .add_results(&registry, earlier, &results[1..2])
+        .unwrap();
+    // Echo has no modules, so it is left out; entries are by host, then port
+    let entries = suggestions.entries();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].0.host, "10.0.0.2");
+    assert_eq!(
+        entries[1].1[0].module,
+        "exploit/unix/ftp/vsftpd_234_backdoor"
+    );
+    assert_eq!(entries[2].0.name, "ssh");
+    // The version in the real banner fires the version-specific rule
+    assert_eq!(entries[2].0.banner.as_deref(), Some("SSH-2.0-OpenSSH_7.4"));
+    assert!(
+        entries[2]
+            .1
+            .iter()
+            .any(|s| s.module == "auxiliary/scanner/ssh/ssh_enumusers")
+    );
+    assert_eq!(suggestions.services().len(), 3);
+}
+
+#[test]
+fn test_suggestions_csv_is_ordered_by_host_and_port() {
+    let registry = service_detection::registry();
+    let ssh = vec![detected(
+        22,
+        "SSH",
+        Source::Banner("SSH-2.0-OpenSSH_7.4".to_string()),
+    )];
+    let mut suggestions = MsfSuggestions::new();
+    for host in ["10.0.0.20", "10.0.0.3", "10.0.0.100"] {
+        suggestions
+            .add_results(&registry, host.parse().unwrap(), &ssh)
+            .unwrap();
+    }
+    let path = std::env::temp_dir().join(format!("netscan_msf_{}.csv", std::process::id()));
+    reports::write_msf_suggestions_csv(path.to_str().unwrap(), &suggestions).unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let hosts: Vec<&str> = text
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').next().unwrap())
+        .collect();
+    let mut deduped = hosts.clone();
+    deduped.dedup();
+    assert_eq!(deduped, ["10.0.0.3", "10.0.0.20", "10.0.0.100"]);
+}
+
+const VSFTPD_FEED: &str = r#"{"vulnerabilities": [
+  {"cve": {
+    "id": "CVE-2011-2523",
+    "descriptions": [{"lang": "en", "value": "vsftpd 2.3.4 contains a backdoor."}],
+    "metrics": {"cvssMetricV31": [{"cvssData": {"baseScore": 9.8, "baseSeverity": "CRITICAL"}}]},
+    "configurations": [{"nodes": [{"operator": "OR", "negate": false, "cpeMatch": [
+      {"vulnerable": true, "criteria": "cpe:2.3:a:vsftpd_project:vsftpd:2.3.4:*:*:*:*:*:*:*"}
+    ]}]}]
+  }}
+]}"#;
+
+#[test]
+fn test_cached_cve_feeds_become_findings() {
+    let registry = service_detection::registry();
+    let ip: IpAddr = "10.0.0.5".parse().unwrap();
+    let mut ftp = detected(21, "FTP", Source::Probe("FTP".to_string()));
+    ftp.product = Some("vsFTPd".to_string());
+    ftp.version = Some("2.3.4".to_string());
+    let info = msf::service_info(&registry, ip, &ftp).unwrap();
+
+    // A fresh cached feed is used without going to the network
+    let dir = std::env::temp_dir().join(format!("netscan_cve_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut lookup = CveLookup::new(&dir);
+    assert_eq!(lookup.feed_path("vsFTPd"), dir.join("vsftpd.json"));
+    std::fs::write(lookup.feed_path("vsFTPd"), VSFTPD_FEED).unwrap();
+    assert!(lookup.fetch(std::slice::from_ref(&info)).is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let findings = lookup.findings(&info);
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].title, "CVE-2011-2523");
+    assert_eq!(findings[0].severity, Severity::Critical);
+    assert_eq!(findings[0].port, Some(21));
+    assert_eq!(findings[0].code, Some(code::KNOWN_CVE));
+    assert!(findings[0].detail.contains("CVSS 9.8"));
+
+    // Other versions are not affected
+    let patched = ServiceInfo {
+        version: Some("3.0.5".to_string()),
+        ..info
+    };
+    assert!(lookup.findings(&patched).is_empty());
+}
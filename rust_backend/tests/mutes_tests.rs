@@ -0,0 +1,99 @@
+use chrono::{TimeZone, Utc};
+use rust_backend::utils::findings::{Finding, Severity};
+use rust_backend::utils::mutes::{MuteList, MuteRule, MuteScope};
+use std::net::IpAddr;
+
+fn alert(hosts: &[&str]) -> Finding {
+    Finding::new(
+        Severity::High,
+        Some(443),
+        "Certificate expired",
+        String::new(),
+    )
+    .with_hosts(hosts.iter().map(|h| h.parse().unwrap()).collect())
+}
+
+#[test]
+fn test_scope_matches_hosts_and_networks() {
+    let net = MuteScope::parse("10.0.0.0/24").unwrap();
+    assert!(net.contains("10.0.0.77".parse().unwrap()));
+    assert!(!net.contains("10.0.1.1".parse().unwrap()));
+    assert!(net.contains("::ffff:10.0.0.5".parse().unwrap()));
+    assert!(!net.contains("fd00::1".parse().unwrap()));
+
+    let host = MuteScope::parse("10.0.0.5").unwrap();
+    assert!(host.contains("10.0.0.5".parse().unwrap()));
+    assert!(!host.contains("10.0.0.6".parse().unwrap()));
+
+    let v6 = MuteScope::parse("fd00:10::/64").unwrap();
+    assert!(v6.contains("fd00:10::abcd".parse().unwrap()));
+    assert!(
+        MuteScope::parse("0.0.0.0/0")
+            .unwrap()
+            .contains(IpAddr::from([192, 0, 2, 1]))
+    );
+    assert_eq!(MuteScope::parse("*").unwrap(), MuteScope::All);
+
+    assert!(MuteScope::parse("10.0.0.0/33").is_err());
+    assert!(MuteScope::parse("server01").is_err());
+}
+
+#[test]
+fn test_rule_mutes_only_covered_hosts() {
+    let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+    let rule = MuteRule::parse("10.0.0.0/24").unwrap();
+    assert!(rule.mutes(&alert(&["10.0.0.1"]), now));
+    assert!(rule.mutes(&alert(&["10.0.0.1", "10.0.0.2"]), now));
+    // One endpoint outside the subnet still deserves a notification
+    assert!(!rule.mutes(&alert(&["10.0.0.1", "10.0.1.1"]), now));
+    assert!(!rule.mutes(&alert(&[]), now));
+    assert!(MuteRule::parse("*").unwrap().mutes(&alert(&[]), now));
+}
+
+#[test]
+fn test_rule_expires() {
+    let rule = MuteRule::parse("10.0.0.5 until 2026-11-01T06:00:00Z").unwrap();
+    let before = Utc.with_ymd_and_hms(2026, 11, 1, 5, 59, 0).unwrap();
+    let after = Utc.with_ymd_and_hms(2026, 11, 1, 6, 0, 0).unwrap();
+    assert!(rule.is_active(before));
+    assert!(!rule.is_active(after));
+    assert!(!rule.mutes(&alert(&["10.0.0.5"]), after));
+}
+
+#[test]
+fn test_rule_applies_during_window() {
+    // 2026-10-17 is a Saturday
+    let rule = MuteRule::parse("* during Sat 02:00-06:00 UTC").unwrap();
+    assert!(rule.is_active(Utc.with_ymd_and_hms(2026, 10, 17, 3, 0, 0).unwrap()));
+    assert!(!rule.is_active(Utc.with_ymd_and_hms(2026, 10, 17, 7, 0, 0).unwrap()));
+    assert!(!rule.is_active(Utc.with_ymd_and_hms(2026, 10, 18, 3, 0, 0).unwrap()));
+}
+
+#[test]
+fn test_rule_rejects_bad_syntax() {
+    assert!(MuteRule::parse("10.0.0.5 until tomorrow").is_err());
+    assert!(MuteRule::parse("10.0.0.5 until").is_err());
+    assert!(MuteRule::parse("10.0.0.5 during Funday 01:00-02:00").is_err());
+    assert!(MuteRule::parse("10.0.0.5 forever").is_err());
+}
+
+#[test]
+fn test_list_parses_file_with_comments() {
+    let text = "# planned patching\n\n10.0.0.0/24 until 2026-11-01T06:00:00Z\n* during Sun 01:00-03:00 UTC # weekly reboot\n";
+    let list = MuteList::parse(text).unwrap();
+    assert_eq!(list.rules().len(), 2);
+    assert_eq!(list.rules()[1].to_string(), "* during Sun 01:00-03:00 UTC");
+
+    let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+    assert!(list.is_muted(&alert(&["10.0.0.9"]), now));
+    assert!(!list.is_muted(&alert(&["10.0.1.9"]), now));
+
+    let err = MuteList::parse("10.0.0.1\nbogus\n").unwrap_err();
+    assert!(err.starts_with("line 2:"));
+}
+
+#[test]
+fn test_missing_file_means_no_rules() {
+    let path = std::env::temp_dir().join("netscan-no-such-mutes.conf");
+    assert!(MuteList::load(&path).unwrap().is_empty());
+}
@@ -0,0 +1,74 @@
+use pnet::datalink::MacAddr;
+use rust_backend::scanners::ndpsweep::{
+    build_neighbor_solicitation, multicast_mac, parse_neighbor_advertisement,
+    solicited_node_multicast,
+};
+use rust_backend::utils::vlan::{frame_vlan_id, tag_frame};
+use std::net::Ipv6Addr;
+
+const SCANNER_MAC: MacAddr = MacAddr(0x02, 0, 0, 0, 0, 1);
+const HOST_MAC: MacAddr = MacAddr(0x02, 0, 0, 0, 0, 9);
+
+fn scanner_ip() -> Ipv6Addr {
+    "fe80::1".parse().unwrap()
+}
+
+fn host_ip() -> Ipv6Addr {
+    "fd00:10::ab:cdef".parse().unwrap()
+}
+
+/// Neighbor Advertisement from the host, with its link-layer address in the
+/// target option.
+fn advertisement() -> Vec<u8> {
+    let mut frame = build_neighbor_solicitation(HOST_MAC, host_ip(), scanner_ip(), None).unwrap();
+    frame[54] = 136; // ICMPv6 type: Neighbor Advertisement
+    frame[58] = 0x60; // Solicited + Override
+    frame[62..78].copy_from_slice(&host_ip().octets());
+    frame[78] = 2; // option: target link-layer address
+    frame
+}
+
+#[test]
+fn test_solicited_node_multicast() {
+    let group = solicited_node_multicast(host_ip());
+    assert_eq!(group, "ff02::1:ffab:cdef".parse::<Ipv6Addr>().unwrap());
+    assert_eq!(
+        multicast_mac(group),
+        MacAddr(0x33, 0x33, 0xff, 0xab, 0xcd, 0xef)
+    );
+}
+
+#[test]
+fn test_neighbor_solicitation() {
+    let frame = build_neighbor_solicitation(SCANNER_MAC, scanner_ip(), host_ip(), None).unwrap();
+    assert_eq!(frame.len(), 86);
+    assert_eq!(&frame[0..6], &[0x33, 0x33, 0xff, 0xab, 0xcd, 0xef]);
+    assert_eq!(&frame[12..14], &[0x86, 0xdd]);
+    assert_eq!(frame[20], 58); // next header: ICMPv6
+    assert_eq!(frame[21], 255); // hop limit
+    assert_eq!(frame[54], 135); // Neighbor Solicitation
+    assert_ne!(&frame[56..58], &[0, 0]); // checksum filled in
+    assert_eq!(&frame[62..78], &host_ip().octets());
+    assert_eq!(&frame[78..86], &[1, 1, 0x02, 0, 0, 0, 0, 1]);
+    // Solicitations are not advertisements
+    assert_eq!(parse_neighbor_advertisement(&frame), None);
+
+    let tagged =
+        build_neighbor_solicitation(SCANNER_MAC, scanner_ip(), host_ip(), Some(20)).unwrap();
+    assert_eq!(tagged.len(), 90);
+    assert_eq!(frame_vlan_id(&tagged), Some(20));
+}
+
+#[test]
+fn test_parse_neighbor_advertisement() {
+    let (ip, mac, vlan) = parse_neighbor_advertisement(&advertisement()).unwrap();
+    assert_eq!(ip, host_ip());
+    assert_eq!(mac, HOST_MAC);
+    assert_eq!(vlan, None);
+
+    let tagged = tag_frame(&advertisement(), 30).unwrap();
+    assert_eq!(
+        parse_neighbor_advertisement(&tagged),
+        Some((host_ip(), HOST_MAC, Some(30)))
+    );
+}
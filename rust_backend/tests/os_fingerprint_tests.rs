@@ -0,0 +1,132 @@
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv4::{Ipv4Packet, MutableIpv4Packet};
+use pnet::packet::tcp::{MutableTcpPacket, TcpFlags, TcpOption, TcpPacket};
+use rust_backend::scanners::os_fingerprint::{self, StackFingerprint, StackSignature};
+use rust_backend::utils::evidence::{Attribute, Source};
+use std::net::Ipv4Addr;
+
+const SRC_PORT: u16 = 50_123;
+const SEQ: u32 = 0x1000;
+
+fn syn_ack(port: u16, window: u16, options: &[TcpOption]) -> Vec<u8> {
+    let options_len: usize = options
+        .iter()
+        .map(|option| match option.number.0 {
+            0 | 1 => 1,
+            2 => 4,
+            3 => 3,
+            4 => 2,
+            _ => 10,
+        })
+        .sum();
+    let len = 20 + options_len.div_ceil(4) * 4;
+    let mut buffer = vec![0u8; len];
+    let mut segment = MutableTcpPacket::new(&mut buffer).unwrap();
+    segment.set_source(port);
+    segment.set_destination(SRC_PORT);
+    segment.set_acknowledgement(SEQ + 1);
+    segment.set_data_offset((len / 4) as u8);
+    segment.set_flags(TcpFlags::SYN | TcpFlags::ACK);
+    segment.set_window(window);
+    segment.set_options(options);
+    buffer
+}
+
+fn signature(ttl: u8, window: u16, options: &[TcpOption]) -> StackSignature {
+    let segment = syn_ack(22, window, options);
+    StackSignature::from_reply(ttl, &TcpPacket::new(&segment).unwrap())
+}
+
+fn linux_options() -> Vec<TcpOption> {
+    vec![
+        TcpOption::mss(1460),
+        TcpOption::sack_perm(),
+        TcpOption::timestamp(1, SEQ),
+        TcpOption::nop(),
+        TcpOption::wscale(7),
+    ]
+}
+
+#[test]
+fn test_signature_reads_ttl_window_and_option_order() {
+    let linux = signature(61, 65160, &linux_options());
+    assert_eq!(linux.initial_ttl(), 64);
+    assert_eq!(linux.layout(), "mss,sok,ts,nop,ws");
+    assert_eq!(linux.mss, Some(1460));
+    assert_eq!(linux.window_scale, Some(7));
+    assert_eq!(
+        linux.to_string(),
+        "TTL 61, window 65160, options mss,sok,ts,nop,ws, wscale 7"
+    );
+    assert_eq!(signature(120, 8192, &[]).initial_ttl(), 128);
+    assert_eq!(signature(250, 8192, &[]).initial_ttl(), 255);
+}
+
+#[test]
+fn test_stacks_are_classified_by_family() {
+    let classify = |sig: StackSignature| os_fingerprint::classify(&sig).map(|guess| guess.os);
+    assert_eq!(
+        classify(signature(61, 65160, &linux_options())).as_deref(),
+        Some("Linux")
+    );
+    let windows = [
+        TcpOption::mss(1460),
+        TcpOption::nop(),
+        TcpOption::wscale(8),
+        TcpOption::nop(),
+        TcpOption::nop(),
+        TcpOption::sack_perm(),
+    ];
+    assert_eq!(
+        classify(signature(127, 65535, &windows)).as_deref(),
+        Some("Windows")
+    );
+    let bsd = [
+        TcpOption::mss(1460),
+        TcpOption::nop(),
+        TcpOption::wscale(6),
+        TcpOption::sack_perm(),
+        TcpOption::timestamp(1, SEQ),
+    ];
+    assert_eq!(classify(signature(64, 65535, &bsd)).as_deref(), Some("BSD"));
+    assert_eq!(
+        classify(signature(254, 5840, &[TcpOption::mss(536)])).as_deref(),
+        Some("Embedded")
+    );
+
+    // Nothing but a TTL is not enough to name a family
+    let unknown = signature(60, 1234, &[TcpOption::nop(), TcpOption::nop()]);
+    assert_eq!(classify(unknown), None);
+
+    let linux = os_fingerprint::rank(&signature(61, 65160, &linux_options()));
+    assert_eq!(linux[0].confidence, 1.0);
+    assert!(linux[1].confidence < 0.5);
+}
+
+#[test]
+fn test_only_answers_to_the_probe_are_read() {
+    let segment = syn_ack(443, 65160, &linux_options());
+    let mut buffer = vec![0u8; 20 + segment.len()];
+    let mut packet = MutableIpv4Packet::new(&mut buffer).unwrap();
+    packet.set_version(4);
+    packet.set_header_length(5);
+    packet.set_total_length((20 + segment.len()) as u16);
+    packet.set_ttl(57);
+    packet.set_next_level_protocol(IpNextHeaderProtocols::Tcp);
+    packet.set_source(Ipv4Addr::new(10, 0, 0, 5));
+    packet.set_payload(&segment);
+    let packet = Ipv4Packet::new(&buffer).unwrap();
+
+    let (port, signature) = os_fingerprint::read_reply(&packet, SRC_PORT, SEQ).unwrap();
+    assert_eq!(port, 443);
+    assert_eq!(signature.ttl, 57);
+    assert!(os_fingerprint::read_reply(&packet, SRC_PORT + 1, SEQ).is_none());
+    assert!(os_fingerprint::read_reply(&packet, SRC_PORT, SEQ + 7).is_none());
+
+    let stack = StackFingerprint::new(port, signature);
+    let evidence = stack.evidence().unwrap();
+    assert_eq!(evidence.attribute, Attribute::Os);
+    assert_eq!(evidence.value, "Linux");
+    assert_eq!(evidence.port, Some(443));
+    assert!(matches!(evidence.source, Source::Stack(_)));
+}
@@ -0,0 +1,80 @@
+use rust_backend::utils::evidence::{self, Attribute, Evidence, Source};
+use rust_backend::utils::fingerprinting::HostFingerprintResult;
+use rust_backend::utils::os_fusion::{self, OsGuess};
+use std::net::{IpAddr, Ipv4Addr};
+
+#[test]
+fn test_disagreeing_signals_are_ranked() {
+    let evidence = vec![
+        Evidence::new(Attribute::Os, "Linux", Source::Ttl(64)),
+        evidence::os_from_banner("SSH-2.0-OpenSSH_8.9p1 Ubuntu-3", 22).unwrap(),
+        Evidence::new(Attribute::Os, "Windows", Source::Probe("SMB".to_string())).on_port(445),
+        // Not about the OS, so it does not count
+        Evidence::new(
+            Attribute::Vendor,
+            "Dell Inc.",
+            Source::Oui("00:14:22".to_string()),
+        ),
+    ];
+    let guesses = os_fusion::fuse(&evidence);
+
+    // The TTL's "Linux" backs the banner's more specific "Linux (Ubuntu)"
+    let names: Vec<&str> = guesses.iter().map(|guess| guess.os.as_str()).collect();
+    assert_eq!(names, vec!["Linux (Ubuntu)", "Windows"]);
+    assert!((guesses[0].confidence - 0.9 / 1.4).abs() < 1e-9);
+    assert!((guesses[1].confidence - 0.5 / 1.4).abs() < 1e-9);
+    assert_eq!(
+        os_fusion::format_guesses(&guesses),
+        "Linux (Ubuntu) (64%), Windows (36%)"
+    );
+}
+
+#[test]
+fn test_no_os_evidence_means_no_guess() {
+    assert!(os_fusion::fuse(&[]).is_empty());
+    let only_family = [evidence::os_from_vendor("Raspberry Pi Trading Ltd", "dc:a6:32").unwrap()];
+    assert_eq!(
+        os_fusion::fuse(&only_family),
+        vec![OsGuess {
+            os: "Linux".to_string(),
+            confidence: 1.0
+        }]
+    );
+}
+
+#[test]
+fn test_equal_guesses_are_ordered_by_name() {
+    let windows = Evidence::new(Attribute::Os, "Windows", Source::Ttl(128));
+    let bsd = Evidence::new(Attribute::Os, "BSD", Source::Ttl(64));
+    let names = |evidence: &[Evidence]| -> Vec<String> {
+        os_fusion::fuse(evidence)
+            .into_iter()
+            .map(|guess| guess.os)
+            .collect()
+    };
+    assert_eq!(
+        names(&[windows.clone(), bsd.clone()]),
+        vec!["BSD", "Windows"]
+    );
+    assert_eq!(names(&[bsd, windows]), vec!["BSD", "Windows"]);
+}
+
+#[test]
+fn test_merged_fingerprints_fuse_again() {
+    let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20));
+    let mut first = HostFingerprintResult::new(ip);
+    first.add_evidence(Evidence::new(
+        Attribute::Os,
+        "Windows",
+        Source::Probe("SMB".to_string()),
+    ));
+    first.fuse_os();
+    assert_eq!(first.os.as_deref(), Some("Windows"));
+
+    let mut second = HostFingerprintResult::new(ip);
+    second.add_evidence(evidence::os_from_banner("220 FreeBSD FTP server", 21).unwrap());
+    second.add_evidence(Evidence::new(Attribute::Os, "FreeBSD", Source::Ttl(64)));
+    first.merge(second);
+    assert_eq!(first.os.as_deref(), Some("FreeBSD"));
+    assert_eq!(first.os_guesses.len(), 2);
+}
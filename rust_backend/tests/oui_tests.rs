@@ -0,0 +1,44 @@
+use rust_backend::utils::oui::{self, OuiDatabase};
+
+#[test]
+fn test_embedded_lookup_accepts_common_notations() {
+    let db = OuiDatabase::embedded();
+    assert_eq!(
+        db.lookup("b8:27:eb:12:34:56"),
+        Some("Raspberry Pi Foundation")
+    );
+    assert_eq!(
+        db.lookup("B8-27-EB-12-34-56"),
+        Some("Raspberry Pi Foundation")
+    );
+    assert_eq!(db.lookup("b827.eb12.3456"), Some("Raspberry Pi Foundation"));
+    assert_eq!(db.lookup("00:00:0c:01:02:03"), Some("Cisco Systems, Inc"));
+    assert_eq!(db.lookup("02:00:00:00:00:01"), None);
+    assert_eq!(db.lookup("b8:27"), None);
+    assert_eq!(db.lookup("zz:27:eb:00:00:00"), None);
+    assert_eq!(
+        oui::lookup("00:50:56:aa:bb:cc").as_deref(),
+        Some("VMware, Inc.")
+    );
+}
+
+#[test]
+fn test_ieee_registry_overrides_embedded() {
+    let registry = "\
+Registry,Assignment,Organization Name,Organization Address
+MA-L,B827EB,Raspberry Pi Foundation (registry),Mitchell Wood House Caldecote GB CB23 7NU
+MA-L,F01898,\"Apple, Inc.\",1 Infinite Loop Cupertino CA US 95014
+MA-L,NOTHEX,Broken Row,
+";
+    let parsed = OuiDatabase::parse_ieee_csv(registry).unwrap();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed.lookup("f0:18:98:00:00:01"), Some("Apple, Inc."));
+
+    let mut db = OuiDatabase::embedded();
+    db.extend(parsed);
+    assert_eq!(
+        db.lookup("b8:27:eb:00:00:01"),
+        Some("Raspberry Pi Foundation (registry)")
+    );
+    assert_eq!(db.lookup("00:00:0c:00:00:01"), Some("Cisco Systems, Inc"));
+}
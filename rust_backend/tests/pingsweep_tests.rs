@@ -1,4 +1,5 @@
-use rust_backend::scanners::pingsweep::{ping_sweep, parse_subnet};
+use rust_backend::scanners::pingsweep::{ping_sweep, parse_network, parse_subnet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 #[test]
 fn test_valid_subnet_parsing() {
@@ -57,4 +58,43 @@ async fn test_ping_sweep_empty_subnet() {
         result.unwrap_err(),
         "Invalid subnet format. Use CIDR notation (e.g., 192.168.1.0/24)."
     );
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_parse_network_ipv6() {
+    let hosts = parse_network("fd00::17/126").unwrap();
+    assert_eq!(
+        hosts,
+        vec![
+            IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 0x14)),
+            IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 0x15)),
+            IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 0x16)),
+            IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 0x17)),
+        ]
+    );
+    assert_eq!(parse_network("fd00::1/128").unwrap().len(), 1);
+    assert_eq!(parse_network("fd00::/112").unwrap().len(), 65536);
+}
+
+#[test]
+fn test_parse_network_rejects_wide_ipv6_prefix() {
+    assert!(parse_network("fd00::/64").unwrap_err().contains("too large"));
+    assert_eq!(parse_network("fd00::/129").unwrap_err(), "Invalid prefix.");
+    assert_eq!(parse_network("fd00::zz/120").unwrap_err(), "Invalid IP address.");
+}
+
+#[test]
+fn test_parse_network_ipv4() {
+    let hosts = parse_network("192.168.1.0/31").unwrap();
+    assert_eq!(
+        hosts,
+        vec![
+            IpAddr::V4(Ipv4Addr::new(192, 168, 1, 0)),
+            IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+        ]
+    );
+    assert_eq!(
+        parse_network("192.168.1.0").unwrap_err(),
+        "Invalid subnet format. Use CIDR notation (e.g., 192.168.1.0/24)."
+    );
+}
@@ -0,0 +1,19 @@
+use rust_backend::utils::ports_presets::{self, TOP_TCP_PORTS};
+
+#[test]
+fn test_top_ports_are_ranked_and_unique() {
+    assert_eq!(ports_presets::top_ports(3), Ok(&[80, 23, 443][..]));
+    assert_eq!(ports_presets::top_ports_spec(3).unwrap(), "80,23,443");
+    assert_eq!(ports_presets::top_ports(100).unwrap().len(), 100);
+
+    let mut sorted = TOP_TCP_PORTS.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    assert_eq!(sorted.len(), TOP_TCP_PORTS.len());
+}
+
+#[test]
+fn test_top_ports_out_of_range() {
+    assert!(ports_presets::top_ports(0).is_err());
+    assert!(ports_presets::top_ports(TOP_TCP_PORTS.len() + 1).is_err());
+}
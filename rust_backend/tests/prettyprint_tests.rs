@@ -0,0 +1,165 @@
+use rust_backend::scanners::service_detection::{
+    DetectError, ProbeAttempt, Protocol, ServiceDetectionResult,
+};
+use rust_backend::utils::evidence::{Attribute, Evidence, Source};
+use rust_backend::utils::prettyprint::{
+    AsciiFilter, Column, RowFilter, SortKey, TableLayout, display_width, format_port_ranges,
+    to_ascii,
+};
+use std::time::Duration;
+
+#[test]
+fn test_emoji_are_dropped_with_their_padding() {
+    assert_eq!(
+        to_ascii("🛰️  NetScan - Network Service Scanner"),
+        "NetScan - Network Service Scanner"
+    );
+    assert_eq!(
+        to_ascii("\x1b[33m🔎 Performing ping sweep\x1b[0m"),
+        "\x1b[33mPerforming ping sweep\x1b[0m"
+    );
+    assert_eq!(to_ascii("  ⚠️  10.0.0.5"), "  !  10.0.0.5");
+}
+
+#[test]
+fn test_glyphs_keep_their_column() {
+    let line = format!("{:<12}|", "a — b ✗");
+    assert_eq!(to_ascii(&line).len(), line.chars().count());
+    assert_eq!(to_ascii(&line), "a - b x     |");
+    assert_eq!(to_ascii("Banner: Grüße"), "Banner: Gr??e");
+}
+
+#[test]
+fn test_split_characters_wait_for_the_rest() {
+    let bytes = "🔎 ping — done".as_bytes();
+    let mut filter = AsciiFilter::new();
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        out.push_str(&filter.push(chunk));
+    }
+    assert_eq!(out, "ping - done");
+}
+
+#[test]
+fn test_wide_labels_keep_columns_aligned() {
+    colored::control::set_override(false);
+    assert_eq!(display_width("\x1b[1mウェブ\x1b[0m ok"), 9);
+    assert_eq!(display_width("Grüße"), 5);
+
+    let res = ServiceDetectionResult::new(80, Some("ウェブ管理".to_string()), None, Vec::new());
+    assert_eq!(
+        TableLayout::default().format_row(&res),
+        format!("{:<8} ウェブ管理{} {:<10} -", 80, " ".repeat(10), "OK")
+    );
+}
+
+#[test]
+fn test_format_port_ranges() {
+    assert_eq!(format_port_ranges(&[1, 2, 3, 5, 7, 8]), "1-3,5,7-8");
+    assert_eq!(format_port_ranges(&[]), "");
+}
+
+#[test]
+fn test_table_columns_follow_the_layout() {
+    colored::control::set_override(false);
+    let mut res = ServiceDetectionResult::new(
+        22,
+        Some("SSH (OpenSSH_8.9p1)".to_string()),
+        None,
+        Vec::new(),
+    );
+    res.evidence.push(Evidence::new(
+        Attribute::Service,
+        "SSH (OpenSSH_8.9p1)",
+        Source::Banner("SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.6".to_string()),
+    ));
+    for elapsed in [120, 30] {
+        res.attempts.push(ProbeAttempt {
+            protocol: "SSH".to_string(),
+            detected: true,
+            timed_out: false,
+            elapsed: Duration::from_millis(elapsed),
+        });
+    }
+
+    let default = TableLayout::default();
+    assert_eq!(
+        default.header(),
+        format!("{:<8} {:<20} {:<10} Error", "Port", "Service", "Status")
+    );
+    assert_eq!(
+        default.format_row(&res),
+        format!("{:<8} {:<20} {:<10} -", 22, "SSH (OpenSSH_8.9p1)", "OK")
+    );
+
+    let layout = TableLayout {
+        columns: vec![
+            Column::Service,
+            Column::Version,
+            Column::Latency,
+            Column::Banner,
+        ],
+        banner_width: 20,
+        ..TableLayout::default()
+    };
+    assert_eq!(
+        layout.format_row(&res),
+        format!(
+            "{:<20} {:<20} {:<10} SSH-2.0-OpenSSH_8...",
+            "SSH", "OpenSSH_8.9p1", "150ms"
+        )
+    );
+
+    let unknown = ServiceDetectionResult::new(9, None, Some("refused".to_string()), Vec::new());
+    assert_eq!(
+        layout.format_row(&unknown),
+        format!("{:<20} {:<20} {:<10} -", "-", "-", "-")
+    );
+}
+
+fn timed(port: u16, service: &str, millis: u64) -> ServiceDetectionResult {
+    let mut res = ServiceDetectionResult::new(port, Some(service.to_string()), None, Vec::new());
+    res.attempts.push(ProbeAttempt {
+        protocol: service.to_string(),
+        detected: service != "Unknown Service",
+        timed_out: false,
+        elapsed: Duration::from_millis(millis),
+    });
+    res
+}
+
+#[test]
+fn test_rows_are_filtered_then_sorted() {
+    let mut answered = timed(8080, "Unknown Service", 900);
+    answered.protocol_failures.push((
+        Protocol::Http,
+        DetectError::Mismatch("Not an HTTP response".to_string()),
+    ));
+    let results = vec![
+        timed(443, "HTTPS", 300),
+        timed(9, "Unknown Service", 4000),
+        answered,
+        timed(22, "SSH (OpenSSH_9.6)", 50),
+    ];
+    let ports = |layout: &TableLayout| -> Vec<u16> {
+        layout
+            .arrange(&results)
+            .iter()
+            .map(|res| res.port)
+            .collect()
+    };
+
+    let mut layout = TableLayout::default();
+    assert_eq!(ports(&layout), vec![443, 9, 8080, 22]);
+    layout.sort_by = Some(SortKey::Port);
+    assert_eq!(ports(&layout), vec![9, 22, 443, 8080]);
+    layout.sort_by = Some(SortKey::Service);
+    assert_eq!(ports(&layout), vec![443, 22, 9, 8080]);
+    layout.sort_by = Some(SortKey::Latency);
+    assert_eq!(ports(&layout), vec![9, 8080, 443, 22]);
+
+    layout.only = Some(RowFilter::Open);
+    assert_eq!(ports(&layout), vec![8080, 443, 22]);
+    layout.only = Some(RowFilter::Detected);
+    assert_eq!(ports(&layout), vec![443, 22]);
+}
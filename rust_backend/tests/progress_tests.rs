@@ -0,0 +1,85 @@
+use rust_backend::scanners::tcpscan::tcp_scan_with;
+use rust_backend::utils::progress::{Phase, ProgressEvent, ProgressState, ScanProgress};
+use rust_backend::utils::scan_config::ScanConfig;
+use rust_backend::utils::sockets::{MockResponse, MockSockets};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test(start_paused = true)]
+async fn test_tcp_scan_reports_every_probe() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let sockets =
+        Arc::new(MockSockets::new().respond(SocketAddr::from((ip, 22)), MockResponse::Open(0)));
+    let (progress, mut events) = ScanProgress::channel();
+    let config = ScanConfig::default().with_progress(progress);
+    tcp_scan_with(&[ip], 20..25, None, &config, sockets).await;
+    drop(config);
+
+    let mut received = Vec::new();
+    while let Some(event) = events.recv().await {
+        received.push(event);
+    }
+    assert_eq!(
+        received.first(),
+        Some(&ProgressEvent::Started {
+            phase: Phase::TcpScan,
+            total: 5
+        })
+    );
+    assert_eq!(
+        received.last(),
+        Some(&ProgressEvent::Finished {
+            phase: Phase::TcpScan
+        })
+    );
+    let mut ports: Vec<u16> = received
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::Probed { port, .. } => *port,
+            _ => None,
+        })
+        .collect();
+    ports.sort();
+    assert_eq!(ports, vec![20, 21, 22, 23, 24]);
+}
+
+#[test]
+fn test_progress_state_accumulates_phases() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let mut state = ProgressState::new();
+    assert_eq!(state.percent(), 0.0);
+    // Service detection starts once per host
+    for total in [3, 1] {
+        state.update(&ProgressEvent::Started {
+            phase: Phase::ServiceScan,
+            total,
+        });
+    }
+    state.update(&ProgressEvent::Probed {
+        phase: Phase::ServiceScan,
+        ip,
+        port: Some(443),
+    });
+    assert_eq!((state.done, state.total), (1, 4));
+    assert_eq!(
+        state.to_string(),
+        "[#####---------------] 1/4 probes (25%), 1 host, last 10.0.0.5:443"
+    );
+
+    state.update(&ProgressEvent::Probed {
+        phase: Phase::PingSweep,
+        ip,
+        port: None,
+    });
+    assert_eq!(
+        state.to_string(),
+        "[##########----------] 2/4 probes (50%), 1 host, last 10.0.0.5"
+    );
+    // The other half takes as long again
+    assert_eq!(
+        state.eta(Duration::from_secs(30)),
+        Some(Duration::from_secs(30))
+    );
+    assert_eq!(ProgressState::new().eta(Duration::from_secs(30)), None);
+}
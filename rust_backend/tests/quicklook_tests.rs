@@ -0,0 +1,71 @@
+use rust_backend::utils::quicklook;
+use rust_backend::utils::scan_config::ScanConfig;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_default_tcp_ports() {
+    let ports = quicklook::default_tcp_ports();
+    assert_eq!(ports[..1024], (1..1025).collect::<Vec<u16>>()[..]);
+    // Common ports above 1024 are added, in port order
+    assert!(ports.contains(&8080));
+    assert!(ports.contains(&3389));
+    assert!(ports.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn test_tcp_batches() {
+    let ports: Vec<u16> = (1..=130).rev().chain([8080, 22]).collect();
+    let batches = quicklook::tcp_batches(&ports);
+    assert_eq!(batches.len(), 3);
+    assert_eq!(batches[0], (1..=64).collect::<Vec<u16>>());
+    assert_eq!(batches[2], vec![129, 130, 8080]);
+}
+
+#[tokio::test]
+async fn test_budget_cuts_phases_short() {
+    let started = Instant::now();
+    let look = quicklook::quick_look(
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+        None,
+        &quicklook::default_tcp_ports(),
+        Duration::ZERO,
+        &ScanConfig::default(),
+    )
+    .await;
+    assert!(started.elapsed() < Duration::from_secs(5));
+    for phase in ["TCP scan", "UDP scan"] {
+        assert!(look.cut_short.contains(&phase), "{:?}", look.cut_short);
+    }
+    // Phases that did not finish leave nothing behind in the report
+    let host = look.report.host(look.ip);
+    assert!(host.is_none_or(|host| !host.alive && host.tcp.is_none() && host.udp.is_none()));
+}
+
+#[tokio::test]
+async fn test_scans_only_the_given_ports() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let config = ScanConfig {
+        timeout: Some(Duration::from_millis(200)),
+        ..ScanConfig::default()
+    };
+    let look = quicklook::quick_look(
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+        None,
+        &[port, 1],
+        Duration::from_secs(10),
+        &config,
+    )
+    .await;
+    assert!(
+        !look.cut_short.contains(&"TCP scan"),
+        "{:?}",
+        look.cut_short
+    );
+    let tcp = look.report.host(look.ip).unwrap().tcp.as_ref().unwrap();
+    assert_eq!(tcp.open, vec![port]);
+    let mut scanned = vec![1, port];
+    scanned.sort_unstable();
+    assert_eq!(tcp.scanned, scanned);
+}
@@ -0,0 +1,132 @@
+use rust_backend::utils::clock::ManualClock;
+use rust_backend::utils::ratelimit::{format_bandwidth, parse_bandwidth, RateLimiter};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_parse_bandwidth_units() {
+    assert_eq!(parse_bandwidth("5mbps"), Ok(5_000_000));
+    assert_eq!(parse_bandwidth("512kbps"), Ok(512_000));
+    assert_eq!(parse_bandwidth("1.5M"), Ok(1_500_000));
+    assert_eq!(parse_bandwidth("1gbps"), Ok(1_000_000_000));
+    assert_eq!(parse_bandwidth("9600"), Ok(9600));
+    assert_eq!(parse_bandwidth("5 Mbps"), Ok(5_000_000));
+}
+
+#[test]
+fn test_parse_bandwidth_invalid() {
+    assert!(parse_bandwidth("fast").is_err());
+    assert!(parse_bandwidth("5tbps").is_err());
+    assert!(parse_bandwidth("0").is_err());
+}
+
+#[test]
+fn test_format_bandwidth() {
+    assert_eq!(format_bandwidth(5_000_000), "5.00 Mbps");
+    assert_eq!(format_bandwidth(1_500), "1.5 kbps");
+    assert_eq!(format_bandwidth(800), "800 bps");
+}
+
+#[tokio::test]
+async fn test_unlimited_only_counts() {
+    let limiter = RateLimiter::new();
+    for _ in 0..10 {
+        limiter.acquire(60).await;
+    }
+    let snapshot = limiter.snapshot();
+    assert_eq!(snapshot.packets, 10);
+    assert_eq!(snapshot.bytes, 600);
+}
+
+#[tokio::test]
+async fn test_cap_is_enforced() {
+    let limiter = RateLimiter::new();
+    limiter.set_max_bandwidth(Some(80_000)); // 10 KB/s, 1 KB burst
+    assert_eq!(limiter.max_bandwidth(), Some(80_000));
+
+    let start = Instant::now();
+    for _ in 0..30 {
+        limiter.acquire(100).await; // 3 KB total
+    }
+    // 2 KB over the burst at 10 KB/s needs roughly 200ms
+    assert!(start.elapsed() >= Duration::from_millis(150));
+}
+
+#[tokio::test]
+async fn test_cap_waits_are_deterministic_with_manual_clock() {
+    let clock = Arc::new(ManualClock::new());
+    let limiter = RateLimiter::with_clock(clock.clone());
+    limiter.set_max_bandwidth(Some(80_000)); // 10 KB/s, 1 KB burst
+
+    for _ in 0..30 {
+        limiter.acquire(100).await;
+    }
+    // The burst covers 10 packets; each of the other 20 waits exactly 10ms
+    let sleeps = clock.sleeps();
+    assert_eq!(sleeps.len(), 20);
+    let total: Duration = sleeps.iter().sum();
+    assert!(total.abs_diff(Duration::from_millis(200)) < Duration::from_millis(1));
+    assert_eq!(limiter.snapshot().elapsed, total);
+}
+
+#[tokio::test]
+async fn test_packet_rate_cap_applies_alongside_bandwidth() {
+    let clock = Arc::new(ManualClock::new());
+    let limiter = RateLimiter::with_clock(clock.clone());
+    limiter.set_max_rate(Some(100)); // 10-packet burst
+    assert_eq!(limiter.max_rate(), Some(100));
+
+    for _ in 0..30 {
+        limiter.acquire(60).await;
+    }
+    // Past the burst every probe waits its 10ms slot, whatever its size
+    let sleeps = clock.sleeps();
+    assert_eq!(sleeps.len(), 20);
+    assert!(sleeps.iter().all(|s| s.abs_diff(Duration::from_millis(10)) < Duration::from_micros(10)));
+
+    // With both caps set the stricter one decides
+    limiter.set_max_bandwidth(Some(4_800)); // 600 bytes/s: 10 probes of 60 bytes
+    limiter.acquire(60).await;
+    limiter.acquire(60).await;
+    // 60 bytes at 600 bytes/s is a 100ms slot, far longer than the 10ms packet slot
+    let last = *clock.sleeps().last().unwrap();
+    assert!(last >= Duration::from_millis(80));
+
+    limiter.set_max_rate(None);
+    limiter.set_max_bandwidth(None);
+    let before = clock.sleeps().len();
+    for _ in 0..100 {
+        limiter.acquire(60).await;
+    }
+    assert_eq!(clock.sleeps().len(), before);
+}
+
+#[tokio::test]
+async fn test_paused_limiter_holds_acquire_until_resumed() {
+    let limiter = Arc::new(RateLimiter::new());
+    limiter.set_paused(true);
+    assert!(limiter.is_paused());
+
+    let waiter = {
+        let limiter = limiter.clone();
+        tokio::spawn(async move { limiter.acquire(100).await })
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!waiter.is_finished());
+    assert_eq!(limiter.snapshot().packets, 0);
+
+    limiter.set_paused(false);
+    waiter.await.unwrap();
+    assert_eq!(limiter.snapshot().packets, 1);
+}
+
+#[tokio::test]
+async fn test_wait_until_paused_returns_once_paused() {
+    let limiter = RateLimiter::new();
+    let waiting = tokio::time::timeout(Duration::from_millis(50), limiter.wait_until_paused());
+    assert!(waiting.await.is_err());
+    limiter.set_paused(true);
+    tokio::time::timeout(Duration::from_millis(50), limiter.wait_until_paused())
+        .await
+        .expect("already paused");
+}
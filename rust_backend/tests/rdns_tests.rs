@@ -0,0 +1,48 @@
+use rust_backend::scanners::pingsweep::LiveHost;
+use rust_backend::utils::rdns;
+use rust_backend::utils::scan_config::ScanConfig;
+use std::net::{IpAddr, Ipv4Addr};
+
+#[tokio::test]
+async fn test_resolved_names_label_hosts() {
+    let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    // 192.0.2.0/24 (TEST-NET-1) has no PTR records
+    let unnamed = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    let hosts = rdns::resolve_hosts(&[localhost, unnamed], &ScanConfig::default()).await;
+
+    assert_eq!(hosts.len(), 2);
+    assert_eq!(hosts[0].ip, localhost);
+    // The hosts file names the loopback address on any sane system
+    let name = hosts[0].hostname.clone().expect("127.0.0.1 has a name");
+    assert_eq!(rdns::cached(localhost), Some(name.clone()));
+    assert_eq!(rdns::label(localhost), format!("127.0.0.1 ({})", name));
+    assert_eq!(hosts[1].hostname, None);
+    assert_eq!(rdns::label(unnamed), "192.0.2.1");
+}
+
+#[test]
+fn test_live_host_display() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let named = LiveHost {
+        ip,
+        hostname: Some("gw.lan".to_string()),
+    };
+    assert_eq!(named.to_string(), "10.0.0.1 (gw.lan)");
+    assert_eq!(LiveHost { ip, hostname: None }.to_string(), "10.0.0.1");
+}
+
+#[tokio::test]
+async fn test_ptr_sweep_keeps_named_targets() {
+    let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let unnamed = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2));
+    let named = rdns::ptr_sweep(&[unnamed, localhost], &ScanConfig::default()).await;
+    assert_eq!(named.len(), 1);
+    assert_eq!(named[0].ip, localhost);
+}
+
+#[test]
+fn test_prioritize_puts_named_hosts_first() {
+    let ip = |last: u8| IpAddr::V4(Ipv4Addr::new(10, 0, 0, last));
+    let hosts = rdns::prioritize(&[ip(1), ip(2), ip(3)], &[ip(3), ip(9)]);
+    assert_eq!(hosts, vec![ip(3), ip(9), ip(1), ip(2)]);
+}
@@ -0,0 +1,108 @@
+use rust_backend::report::ScanReport;
+use rust_backend::scanners::pingsweep::LiveHost;
+use rust_backend::utils::redact::{self, Redactor};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+#[test]
+fn test_pseudonyms_are_consistent_per_key() {
+    let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20));
+    let first = Redactor::new(b"ticket-42");
+    let second = Redactor::new(b"ticket-42");
+    let other = Redactor::new(b"another key");
+
+    let pseudonym = first.ip(ip);
+    assert_ne!(pseudonym, ip);
+    assert_eq!(first.ip(ip), pseudonym);
+    assert_eq!(second.ip(ip), pseudonym);
+    assert_ne!(other.ip(ip), pseudonym);
+    assert_eq!(
+        first.hostname("DC01.corp.example"),
+        second.hostname("dc01.corp.example")
+    );
+}
+
+#[test]
+fn test_pseudonyms_keep_the_address_shape() {
+    let redactor = Redactor::new(b"key");
+    let IpAddr::V4(v4) = redactor.ip(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 1))) else {
+        panic!("IPv4 became IPv6");
+    };
+    assert_eq!(v4.octets()[0], 10);
+    let IpAddr::V6(v6) = redactor.ip(IpAddr::V6(Ipv6Addr::LOCALHOST)) else {
+        panic!("IPv6 became IPv4");
+    };
+    assert_eq!(v6.octets()[0], 0xfd);
+
+    let mac = redactor.mac("00:1A:2B:3C:4D:5E");
+    assert!(mac.starts_with("00:1a:2b:"));
+    assert_ne!(mac, "00:1a:2b:3c:4d:5e");
+    assert_eq!(redactor.mac("00-1a-2b-3c-4d-5e"), mac);
+    assert!(redactor.hostname("*.corp.example").starts_with("*.host-"));
+}
+
+#[test]
+fn test_distinct_addresses_get_distinct_pseudonyms() {
+    let redactor = Redactor::new(b"key");
+    let mut seen = std::collections::BTreeSet::new();
+    for host in 0..=255u8 {
+        for net in 0..64u8 {
+            assert!(seen.insert(redactor.ip(IpAddr::V4(Ipv4Addr::new(10, 1, net, host)))));
+        }
+    }
+}
+
+#[test]
+fn test_text_scrubs_addresses_and_known_names() {
+    let redactor = Redactor::new(b"key");
+    let name = redactor.hostname("mail.corp.example");
+    let ip = redactor.ip("192.168.1.5".parse().unwrap());
+    let text =
+        redactor.text("220 mail.corp.example ESMTP ready at 192.168.1.5 (aa:bb:cc:dd:ee:ff).");
+
+    assert_eq!(
+        text,
+        format!(
+            "220 {} ESMTP ready at {} ({}).",
+            name,
+            ip,
+            redactor.mac("aa:bb:cc:dd:ee:ff")
+        )
+    );
+    assert_eq!(
+        redactor.text("OpenSSH_8.9p1 Ubuntu"),
+        "OpenSSH_8.9p1 Ubuntu"
+    );
+}
+
+#[test]
+fn test_registered_names_are_scrubbed_everywhere() {
+    let low = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5));
+    let high = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 200));
+    redact::install(Redactor::new(b"key"));
+    redact::register([high, low], ["mail.corp.example", "10.9.8.7"]);
+
+    // Pseudonyms follow address order, whatever order the hosts came in
+    let expected = Redactor::new(b"key");
+    assert_eq!(redact::ip(low), expected.ip(low));
+    assert_eq!(redact::ip(high), expected.ip(high));
+    let name = expected.hostname("mail.corp.example");
+    assert_eq!(
+        redact::text("220 mail.corp.example ESMTP"),
+        format!("220 {} ESMTP", name)
+    );
+
+    // What the history stores
+    let mut report = ScanReport::new();
+    report.add_discovery(&[LiveHost {
+        ip: low,
+        hostname: Some("mail.corp.example".to_string()),
+    }]);
+    report.add_notes(low, vec!["relay for 192.168.1.200".to_string()]);
+    let redacted = report.redacted();
+    assert_eq!(redacted.hosts[0].ip, expected.ip(low));
+    assert_eq!(redacted.hosts[0].hostname.as_deref(), Some(name.as_str()));
+    assert_eq!(
+        redacted.hosts[0].notes,
+        vec![format!("relay for {}", expected.ip(high))]
+    );
+}
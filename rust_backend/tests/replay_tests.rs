@@ -0,0 +1,75 @@
+use rust_backend::scanners::service_detection::{self, Protocol};
+use rust_backend::utils::replay;
+
+/// Ethernet + IPv4 + TCP frame from `src` to `dst` port
+fn frame(src: (u8, u16), dst: (u8, u16), flags: u8, seq: u32, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x02, 0, 0, 0, 0, dst.0, 0x02, 0, 0, 0, 0, src.0, 0x08, 0x00];
+    let total = (20 + 20 + payload.len()) as u16;
+    frame.extend_from_slice(&[0x45, 0]);
+    frame.extend_from_slice(&total.to_be_bytes());
+    frame.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0]);
+    frame.extend_from_slice(&[10, 0, 0, src.0, 10, 0, 0, dst.0]);
+    frame.extend_from_slice(&src.1.to_be_bytes());
+    frame.extend_from_slice(&dst.1.to_be_bytes());
+    frame.extend_from_slice(&seq.to_be_bytes());
+    frame.extend_from_slice(&[0, 0, 0, 0, 0x50, flags, 0xff, 0xff, 0, 0, 0, 0]);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn pcap(frames: &[Vec<u8>]) -> Vec<u8> {
+    let mut pcap = 0xa1b2_c3d4u32.to_le_bytes().to_vec();
+    pcap.extend_from_slice(&[2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    pcap.extend_from_slice(&65535u32.to_le_bytes());
+    pcap.extend_from_slice(&1u32.to_le_bytes());
+    for (i, frame) in frames.iter().enumerate() {
+        pcap.extend_from_slice(&(1_700_000_000 + i as u32).to_le_bytes());
+        pcap.extend_from_slice(&0u32.to_le_bytes());
+        pcap.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        pcap.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        pcap.extend_from_slice(frame);
+    }
+    pcap
+}
+
+#[test]
+fn test_read_pcap_first_exchange() {
+    let client = (9, 40000);
+    let server = (5, 2525);
+    let capture = pcap(&[
+        frame(client, server, 0x02, 100, b""),
+        frame(server, client, 0x12, 500, b""),
+        frame(server, client, 0x18, 501, b"220 mail.lan ESMTP\r\n"),
+        frame(server, client, 0x18, 501, b"220 mail.lan ESMTP\r\n"), // retransmission
+        frame(client, server, 0x18, 101, b"EHLO scanner\r\n"),
+        frame(server, client, 0x18, 521, b"250 mail.lan\r\n"),
+    ]);
+    let samples = replay::read_pcap(&capture).unwrap();
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].port, 2525);
+    assert!(samples[0].probe.is_empty());
+    assert_eq!(samples[0].response, b"220 mail.lan ESMTP\r\n");
+
+    assert!(replay::read_pcap(b"\x0a\x0d\x0d\x0a not a classic pcap").is_err());
+}
+
+#[tokio::test]
+async fn test_replay_checks_expectations() {
+    let registry = service_detection::registry();
+    let sample = replay::parse_sample(
+        "ssh.txt",
+        "port: 22\nprobe: NULL\nresponse: SSH-2.0-OpenSSH_9.6\\r\\n\nexpect: SSH\n",
+    )
+    .unwrap();
+    // No protocols given: the detectors registered for port 22 run
+    let outcome = replay::replay(&registry, &sample, &[]).await.unwrap();
+    assert_eq!(outcome.port, 22);
+    assert_eq!(outcome.passed(), Some(true));
+
+    let mut wrong = sample.clone();
+    wrong.expected = Some("FTP".to_string());
+    let outcome = replay::replay(&registry, &wrong, &[Protocol::Ssh])
+        .await
+        .unwrap();
+    assert_eq!(outcome.passed(), Some(false));
+}
@@ -0,0 +1,89 @@
+use rust_backend::report::ScanReport;
+use rust_backend::scanners::pingsweep::LiveHost;
+use rust_backend::scanners::service_detection::{DetectError, Protocol, ServiceDetectionResult};
+use rust_backend::scanners::tcpscan::TcpScanResult;
+use rust_backend::scanners::udpscan::UdpScanResult;
+use rust_backend::utils::errors::ErrorCategory;
+use rust_backend::utils::fingerprinting::HostFingerprintResult;
+use std::net::IpAddr;
+
+#[test]
+fn test_phases_merge_per_host() {
+    let web: IpAddr = "10.0.0.20".parse().unwrap();
+    let gateway: IpAddr = "10.0.0.1".parse().unwrap();
+    let mut report = ScanReport::new();
+    report.add_discovery(&[
+        LiveHost {
+            ip: web,
+            hostname: Some("web.lan".to_string()),
+        },
+        LiveHost {
+            ip: gateway,
+            hostname: None,
+        },
+    ]);
+
+    let mut tcp = TcpScanResult::new();
+    tcp.add_open_port(web, 443);
+    tcp.add_open_port(web, 22);
+    tcp.add_port_error(web, 23, ErrorCategory::Refused, "Connection refused");
+    report.add_tcp(&tcp);
+    let mut udp = UdpScanResult::new();
+    udp.add_open_port(gateway, 53);
+    udp.add_port_error(gateway, 67, ErrorCategory::Timeout, "No response");
+    udp.add_port_error(gateway, 68, ErrorCategory::Timeout, "No response");
+    udp.add_port_error(gateway, 69, ErrorCategory::Refused, "Port unreachable");
+    udp.record_attempts(gateway, 53, 2);
+    report.add_udp(&udp);
+    report.add_ping_attempts(&[(gateway, 3)].into_iter().collect());
+
+    let mut fingerprint = HostFingerprintResult::new(web);
+    fingerprint.os = Some("Linux".to_string());
+    report.add_fingerprint(&fingerprint);
+    report.add_services(
+        web,
+        &[
+            ServiceDetectionResult::new(443, Some("HTTPS".to_string()), None, Vec::new()),
+            ServiceDetectionResult::new(
+                22,
+                Some("SSH".to_string()),
+                None,
+                vec![(
+                    Protocol::Ftp,
+                    DetectError::Mismatch("Not an FTP greeting".to_string()),
+                )],
+            ),
+        ],
+    );
+
+    // Hosts are ordered by address whatever order the phases ran in
+    assert_eq!(
+        report.hosts.iter().map(|h| h.ip).collect::<Vec<_>>(),
+        vec![gateway, web]
+    );
+    let host = report.host(web).unwrap();
+    assert!(host.alive);
+    assert_eq!(host.tcp.as_ref().unwrap().open, vec![22, 443]);
+    assert_eq!(host.tcp.as_ref().unwrap().errors["refused"], 1);
+    assert_eq!(host.services[0].port, 22);
+    assert!(report.host(gateway).unwrap().tcp.is_none());
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["hosts"][0]["udp"]["open"], serde_json::json!([53]));
+    assert_eq!(
+        json["hosts"][0]["udp"]["states"],
+        serde_json::json!({"open": "53", "closed": "69", "open|filtered": "67-68"})
+    );
+    assert!(json["hosts"][1]["tcp"].get("states").is_none());
+    assert_eq!(json["hosts"][0]["udp"]["retried"], serde_json::json!({"53": 2}));
+    assert_eq!(json["hosts"][0]["ping_attempts"], 3);
+    assert!(json["hosts"][1]["tcp"].get("retried").is_none());
+    assert!(json["hosts"][1].get("ping_attempts").is_none());
+    assert_eq!(json["hosts"][1]["ip"], "10.0.0.20");
+    assert_eq!(json["hosts"][1]["hostname"], "web.lan");
+    assert_eq!(json["hosts"][1]["fingerprint"]["os"], "Linux");
+    assert_eq!(
+        json["hosts"][1]["services"][0]["protocol_failures"][0],
+        serde_json::json!({"protocol": "ftp", "kind": "mismatch", "message": "Not an FTP greeting"})
+    );
+}
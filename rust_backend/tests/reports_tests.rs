@@ -0,0 +1,93 @@
+use rust_backend::report::ScanReport;
+use rust_backend::scanners::service_detection::{ProbeAttempt, ServiceDetectionResult};
+use rust_backend::scanners::tcpscan::TcpScanResult;
+use rust_backend::utils::reports;
+use std::net::IpAddr;
+use std::time::Duration;
+
+fn attempt(protocol: &str, detected: bool, timed_out: bool, ms: u64) -> ProbeAttempt {
+    ProbeAttempt {
+        protocol: protocol.to_string(),
+        detected,
+        timed_out,
+        elapsed: Duration::from_millis(ms),
+    }
+}
+
+fn result(port: u16, attempts: Vec<ProbeAttempt>) -> ServiceDetectionResult {
+    let mut result = ServiceDetectionResult::new(port, None, None, Vec::new());
+    result.attempts = attempts;
+    result
+}
+
+#[test]
+fn test_protocol_stats() {
+    let results = vec![
+        result(
+            22,
+            vec![
+                attempt("HTTP", false, false, 40),
+                attempt("SSH", true, false, 20),
+            ],
+        ),
+        result(8080, vec![attempt("HTTP", true, false, 60)]),
+        result(
+            2222,
+            vec![
+                attempt("HTTP", false, true, 5000),
+                attempt("SSH", false, true, 9000),
+            ],
+        ),
+    ];
+    let stats = reports::protocol_stats(&results);
+    assert_eq!(stats.len(), 2);
+
+    let http = &stats[0];
+    assert_eq!(http.protocol, "HTTP");
+    assert_eq!((http.successes, http.failures, http.timeouts), (1, 2, 1));
+    assert_eq!(http.failed_ports, vec![22, 2222]);
+    assert_eq!(http.average_latency(), Duration::from_millis(1700));
+
+    let ssh = &stats[1];
+    assert_eq!(ssh.attempts(), 2);
+    assert_eq!(ssh.timeout_rate(), 0.5);
+}
+
+#[test]
+fn test_summary_csv_header_written_once() {
+    let path = std::env::temp_dir().join(format!("netscan_stats_{}.csv", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let filename = path.to_str().unwrap();
+    let results = vec![result(22, vec![attempt("SSH", true, false, 12)])];
+    reports::append_summary_to_csv(filename, "10.0.0.5", &results).unwrap();
+    reports::append_summary_to_csv(filename, "10.0.0.6", &results).unwrap();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("Timestamp,Target,Protocol,Successes,Failures,Timeouts"));
+    assert!(lines[2].ends_with(",10.0.0.6,SSH,1,0,0,0.00,12,"));
+}
+
+#[test]
+fn test_retries_csv_lists_ping_and_port_attempts() {
+    let host: IpAddr = "10.0.0.7".parse().unwrap();
+    let mut tcp = TcpScanResult::new();
+    tcp.add_open_port(host, 443);
+    tcp.record_attempts(host, 443, 2);
+    let mut report = ScanReport::new();
+    report.add_tcp(&tcp);
+    report.add_ping_attempts(&[(host, 3)].into_iter().collect());
+
+    let path = std::env::temp_dir().join(format!("netscan_retries_{}.csv", std::process::id()));
+    let filename = path.to_str().unwrap();
+    reports::write_retries_csv(filename, &report).unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines,
+        ["Host,Transport,Port,Attempts", "10.0.0.7,icmp,,3", "10.0.0.7,tcp,443,2"]
+    );
+}
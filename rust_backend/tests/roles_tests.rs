@@ -0,0 +1,23 @@
+use rust_backend::utils::roles::{format_roles, infer_roles};
+
+fn role_names(ports: &[u16]) -> Vec<&'static str> {
+    infer_roles(ports).iter().map(|r| r.role).collect()
+}
+
+#[test]
+fn test_infer_common_roles() {
+    assert_eq!(role_names(&[25, 110, 143, 993]), vec!["Mail server"]);
+    assert_eq!(role_names(&[53, 88, 389, 445]), vec!["Domain controller"]);
+    // MySQL and the web ports are folded into the LAMP match
+    assert_eq!(role_names(&[80, 443, 3306]), vec!["LAMP stack"]);
+    assert_eq!(role_names(&[22]), Vec::<&str>::new());
+}
+
+#[test]
+fn test_independent_roles_are_all_reported() {
+    let roles = infer_roles(&[25, 80, 110, 143, 9100]);
+    assert_eq!(
+        format_roles(&roles),
+        "Mail server (25, 110, 143), Print server (9100), Web server (80)"
+    );
+}
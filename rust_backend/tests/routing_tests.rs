@@ -0,0 +1,109 @@
+use rust_backend::utils::routing::{
+    MAX_AUTO_EXPAND_HOSTS, interface_for_ip, lookup_route, on_link_subnet, parse_route_table,
+    reverse_path_mismatch, select_source,
+};
+use std::net::{IpAddr, Ipv4Addr};
+
+const ROUTE_TABLE: &str = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0
+eth1\t00000A0A\t00000000\t0001\t0\t0\t0\t0000FFFF\t0\t0\t0
+wg0\t00000000\t00000000\t0001\t0\t0\t50\t00000000\t0\t0\t0
+";
+
+#[test]
+fn test_parse_route_table() {
+    let routes = parse_route_table(ROUTE_TABLE);
+    assert_eq!(routes.len(), 4);
+    assert_eq!(routes[0].interface, "eth0");
+    assert_eq!(routes[0].gateway, Ipv4Addr::new(192, 168, 1, 1));
+    assert_eq!(routes[1].destination, Ipv4Addr::new(192, 168, 1, 0));
+    assert_eq!(routes[1].mask, Ipv4Addr::new(255, 255, 255, 0));
+}
+
+#[test]
+fn test_lookup_route_longest_prefix() {
+    let routes = parse_route_table(ROUTE_TABLE);
+    let route = lookup_route(&routes, Ipv4Addr::new(192, 168, 1, 20)).unwrap();
+    assert_eq!(route.interface, "eth0");
+    assert_eq!(route.prefix_len(), 24);
+
+    let route = lookup_route(&routes, Ipv4Addr::new(10, 10, 3, 4)).unwrap();
+    assert_eq!(route.interface, "eth1");
+}
+
+#[test]
+fn test_lookup_route_default_prefers_lowest_metric() {
+    let routes = parse_route_table(ROUTE_TABLE);
+    let route = lookup_route(&routes, Ipv4Addr::new(8, 8, 8, 8)).unwrap();
+    assert_eq!(route.interface, "wg0");
+}
+
+#[test]
+fn test_select_source_rejects_foreign_source_ip() {
+    let result = select_source(Ipv4Addr::new(192, 0, 2, 1), Some(Ipv4Addr::new(192, 0, 2, 250)));
+    assert!(result.is_err());
+    // --source-ip may be IPv6, checked against the interfaces the same way
+    let foreign: IpAddr = "2001:db8::250".parse().unwrap();
+    assert!(interface_for_ip(foreign).is_none());
+}
+
+#[test]
+fn test_select_source_loopback() {
+    let route = select_source(Ipv4Addr::LOCALHOST, None).unwrap();
+    assert_eq!(route.source_ip, Some(Ipv4Addr::LOCALHOST));
+}
+
+#[test]
+fn test_reverse_path_mismatch() {
+    let routes = parse_route_table(ROUTE_TABLE);
+    let target = Ipv4Addr::new(10, 10, 3, 4);
+    let source = Ipv4Addr::new(192, 168, 1, 50);
+
+    // Probes leave via eth1 but the eth0 source pulls the replies onto eth0
+    let mismatch = reverse_path_mismatch(&routes, target, source, "eth0").unwrap();
+    assert_eq!(mismatch.egress, "eth1");
+    assert_eq!(mismatch.ingress, "eth0");
+    assert!(mismatch.to_string().contains("leave via eth1"));
+
+    assert!(reverse_path_mismatch(&routes, target, source, "eth1").is_none());
+}
+
+#[test]
+fn test_on_link_subnet() {
+    let routes = parse_route_table(ROUTE_TABLE);
+    let subnet = on_link_subnet(&routes, Ipv4Addr::new(192, 168, 1, 20)).unwrap();
+    assert_eq!(subnet.cidr(), "192.168.1.0/24");
+    assert_eq!(subnet.interface, "eth0");
+    assert_eq!(subnet.gateway, Some(Ipv4Addr::new(192, 168, 1, 1)));
+    assert_eq!(subnet.host_count(), 254);
+    assert_eq!(
+        subnet.to_string(),
+        "192.168.1.0/24 on eth0 (254 hosts, gateway 192.168.1.1)"
+    );
+
+    let subnet = on_link_subnet(&routes, Ipv4Addr::new(10, 10, 3, 4)).unwrap();
+    assert_eq!(subnet.cidr(), "10.10.0.0/16");
+    assert_eq!(subnet.gateway, None);
+
+    // Reached through a gateway, so not in any local broadcast domain
+    assert!(on_link_subnet(&routes, Ipv4Addr::new(8, 8, 8, 8)).is_none());
+}
+
+#[test]
+fn test_auto_expansion_is_capped() {
+    let routes = parse_route_table(ROUTE_TABLE);
+    let target = Ipv4Addr::new(192, 168, 1, 20);
+    let subnet = on_link_subnet(&routes, target).unwrap();
+    assert_eq!(subnet.around(target, MAX_AUTO_EXPAND_HOSTS), subnet);
+
+    // A /16 is narrowed to the /22 holding the target
+    let target = Ipv4Addr::new(10, 10, 7, 9);
+    let subnet = on_link_subnet(&routes, target).unwrap();
+    let capped = subnet.around(target, MAX_AUTO_EXPAND_HOSTS);
+    assert_eq!(capped.cidr(), "10.10.4.0/22");
+    assert_eq!(capped.host_count(), 1022);
+    assert_eq!(capped.interface, "eth1");
+    assert_eq!(subnet.around(target, 100).cidr(), "10.10.7.0/26");
+}
@@ -0,0 +1,70 @@
+use rust_backend::utils::scan_config::{MAX_RETRY_DELAY, ScanConfig, parse_timeout};
+use std::time::Duration;
+
+#[test]
+fn test_parse_timeout() {
+    assert_eq!(parse_timeout("500ms"), Ok(Duration::from_millis(500)));
+    assert_eq!(parse_timeout("2s"), Ok(Duration::from_secs(2)));
+    assert_eq!(parse_timeout("1.5"), Ok(Duration::from_millis(1500)));
+    assert_eq!(parse_timeout("1m"), Ok(Duration::from_secs(60)));
+    assert!(parse_timeout("0s").is_err());
+    assert!(parse_timeout("-1").is_err());
+    assert!(parse_timeout("soon").is_err());
+}
+
+#[test]
+fn test_unset_fields_keep_scanner_defaults() {
+    let config = ScanConfig::default();
+    assert_eq!(
+        config.timeout_or(Duration::from_secs(3)),
+        Duration::from_secs(3)
+    );
+    assert_eq!(config.concurrency_or(64), 64);
+    assert_eq!(config.retries_or(1), 1);
+
+    let config = ScanConfig {
+        timeout: Some(Duration::from_millis(250)),
+        concurrency: Some(0),
+        retries: Some(0),
+        ..ScanConfig::default()
+    };
+    assert_eq!(
+        config.timeout_or(Duration::from_secs(3)),
+        Duration::from_millis(250)
+    );
+    // A zero-permit semaphore would never let a probe through
+    assert_eq!(config.concurrency_or(64), 1);
+    assert_eq!(config.retries_or(1), 0);
+}
+
+#[test]
+fn test_retry_backoff_doubles_up_to_the_cap() {
+    assert_eq!(ScanConfig::default().retry_backoff(1), Duration::ZERO);
+    let config = ScanConfig {
+        retry_delay: Some(Duration::from_millis(200)),
+        ..ScanConfig::default()
+    };
+    assert_eq!(config.retry_backoff(1), Duration::from_millis(200));
+    assert_eq!(config.retry_backoff(2), Duration::from_millis(400));
+    assert_eq!(config.retry_backoff(3), Duration::from_millis(800));
+    assert_eq!(config.retry_backoff(40), MAX_RETRY_DELAY);
+}
+
+#[test]
+fn test_default_concurrency_only_fills_an_unset_one() {
+    let probes = ScanConfig::default().with_default_concurrency(Some(4096));
+    assert_eq!(probes.concurrency_or(64), 4096);
+    // Other scanners built from the plain config keep their own caps
+    assert_eq!(ScanConfig::default().concurrency_or(32), 32);
+
+    let given = ScanConfig {
+        concurrency: Some(100),
+        ..ScanConfig::default()
+    };
+    assert_eq!(
+        given
+            .with_default_concurrency(Some(4096))
+            .concurrency_or(64),
+        100
+    );
+}
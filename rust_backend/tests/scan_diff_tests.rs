@@ -0,0 +1,116 @@
+use rust_backend::report::ScanReport;
+use rust_backend::scanners::pingsweep::LiveHost;
+use rust_backend::scanners::service_detection::ServiceDetectionResult;
+use rust_backend::scanners::tcpscan::TcpScanResult;
+use rust_backend::scanners::udpscan::UdpScanResult;
+use rust_backend::utils::errors::ErrorCategory;
+use rust_backend::utils::scan_diff::{self, PortChange, ServiceChange};
+use std::net::IpAddr;
+
+fn ip(last: u8) -> IpAddr {
+    IpAddr::from([10, 0, 0, last])
+}
+
+fn run(hosts: &[(u8, &[u16], Option<&str>)]) -> ScanReport {
+    run_scanning(&[22, 80, 443], hosts)
+}
+
+/// A run that probed `scanned` on every host, of which the listed ports answered
+fn run_scanning(scanned: &[u16], hosts: &[(u8, &[u16], Option<&str>)]) -> ScanReport {
+    let mut report = ScanReport::new();
+    let live: Vec<LiveHost> = hosts
+        .iter()
+        .map(|(last, _, _)| LiveHost {
+            ip: ip(*last),
+            hostname: None,
+        })
+        .collect();
+    report.add_discovery(&live);
+    let mut tcp = TcpScanResult::new();
+    for (last, ports, _) in hosts {
+        for port in scanned {
+            if ports.contains(port) {
+                tcp.add_open_port(ip(*last), *port);
+            } else {
+                tcp.add_port_error(ip(*last), *port, ErrorCategory::Refused, "Connection refused");
+            }
+        }
+    }
+    report.add_tcp(&tcp);
+    for (last, _, ssh) in hosts {
+        report.add_services(
+            ip(*last),
+            &[ServiceDetectionResult::new(
+                22,
+                ssh.map(String::from),
+                None,
+                Vec::new(),
+            )],
+        );
+    }
+    report
+}
+
+#[test]
+fn test_diff_reports_hosts_ports_and_services() {
+    let before = run(&[
+        (1, &[22, 80], Some("SSH (OpenSSH_8.9)")),
+        (2, &[22], Some("SSH")),
+    ]);
+    let after = run(&[
+        (1, &[22, 443], Some("SSH (OpenSSH_9.6)")),
+        (3, &[22], Some("SSH")),
+    ]);
+    let changes = scan_diff::diff(&before, &after);
+
+    assert_eq!(changes.new_hosts, vec![ip(3)]);
+    assert_eq!(changes.gone_hosts, vec![ip(2)]);
+    let opened: Vec<(IpAddr, u16)> = changes.opened.iter().map(|c| (c.ip, c.port)).collect();
+    assert_eq!(opened, vec![(ip(1), 443), (ip(3), 22)]);
+    assert_eq!(
+        changes.closed,
+        vec![PortChange {
+            ip: ip(1),
+            transport: "tcp",
+            port: 80,
+            service: None,
+        }]
+    );
+    assert_eq!(
+        changes.changed_services,
+        vec![ServiceChange {
+            ip: ip(1),
+            port: 22,
+            before: Some("SSH (OpenSSH_8.9)".to_string()),
+            after: Some("SSH (OpenSSH_9.6)".to_string()),
+        }]
+    );
+    assert!(scan_diff::diff(&after, &after).is_empty());
+}
+
+#[test]
+fn test_unscanned_transport_is_not_a_closed_port() {
+    let mut before = run(&[(1, &[22], None)]);
+    let mut udp = UdpScanResult::new();
+    udp.add_open_port(ip(1), 161);
+    before.add_udp(&udp);
+    // The later run skipped UDP, so 161 is unknown rather than closed
+    let after = run(&[(1, &[22], None)]);
+    assert!(scan_diff::diff(&before, &after).is_empty());
+}
+
+#[test]
+fn test_ports_only_one_run_probed_are_not_compared() {
+    let before = run_scanning(&[22, 80], &[(1, &[22, 80], None)]);
+    let after = run_scanning(&[22, 443], &[(1, &[22, 443], None)]);
+    // 80 was not rescanned and 443 not scanned before: neither changed as far as we know
+    assert!(scan_diff::diff(&before, &after).is_empty());
+
+    let after = run_scanning(&[22, 80], &[(1, &[22], None)]);
+    let closed: Vec<u16> = scan_diff::diff(&before, &after)
+        .closed
+        .iter()
+        .map(|c| c.port)
+        .collect();
+    assert_eq!(closed, vec![80]);
+}
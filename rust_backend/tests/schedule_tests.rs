@@ -0,0 +1,102 @@
+use chrono::{DateTime, TimeZone, Utc};
+use rust_backend::utils::schedule::{ScanWindow, Schedule};
+
+fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+}
+
+#[test]
+fn test_parse_window_specs() {
+    assert!(ScanWindow::parse("Mon-Fri 22:00-05:00 UTC").is_ok());
+    assert!(ScanWindow::parse("01:00-03:00").is_ok());
+    assert!(ScanWindow::parse("Sat,Sun 00:00-24:00 +02:00").is_ok());
+    assert!(ScanWindow::parse("daily 12:00-13:00 UTC-5").is_ok());
+    let window = ScanWindow::parse("  Mon-Fri 22:00-05:00 UTC ").unwrap();
+    assert_eq!(window.to_string(), "Mon-Fri 22:00-05:00 UTC");
+}
+
+#[test]
+fn test_parse_window_rejects_bad_specs() {
+    assert!(ScanWindow::parse("").is_err());
+    assert!(ScanWindow::parse("Mon-Fri").is_err());
+    assert!(ScanWindow::parse("Funday 01:00-02:00").is_err());
+    assert!(ScanWindow::parse("Mo 01:00-02:00").is_err());
+    assert!(ScanWindow::parse("Mon 25:00-02:00").is_err());
+    assert!(ScanWindow::parse("Mon 01:00 UTC").is_err());
+    assert!(ScanWindow::parse("Mon 01:00-02:00 Mars/Olympus").is_err());
+    assert!(ScanWindow::parse("Mon 01:00-02:00 UTC extra").is_err());
+}
+
+#[test]
+fn test_daytime_window() {
+    // 2026-10-12 is a Monday
+    let window = ScanWindow::parse("Mon-Fri 09:00-17:00 UTC").unwrap();
+    assert!(window.is_open(utc(2026, 10, 12, 9, 0)));
+    assert!(window.is_open(utc(2026, 10, 16, 16, 59)));
+    assert!(!window.is_open(utc(2026, 10, 12, 17, 0)));
+    assert!(!window.is_open(utc(2026, 10, 12, 8, 59)));
+    assert!(!window.is_open(utc(2026, 10, 17, 12, 0))); // Saturday
+}
+
+#[test]
+fn test_overnight_window_belongs_to_opening_day() {
+    let window = ScanWindow::parse("Fri 22:00-05:00 UTC").unwrap();
+    assert!(window.is_open(utc(2026, 10, 16, 23, 0))); // Friday night
+    assert!(window.is_open(utc(2026, 10, 17, 4, 59))); // early Saturday
+    assert!(!window.is_open(utc(2026, 10, 17, 5, 0)));
+    assert!(!window.is_open(utc(2026, 10, 16, 4, 0))); // early Friday belongs to Thursday
+}
+
+#[test]
+fn test_window_in_fixed_offset_zone() {
+    // 22:00 at +02:00 is 20:00 UTC
+    let window = ScanWindow::parse("Mon 22:00-23:00 +02:00").unwrap();
+    assert!(window.is_open(utc(2026, 10, 12, 20, 30)));
+    assert!(!window.is_open(utc(2026, 10, 12, 22, 30)));
+}
+
+#[test]
+fn test_day_ranges_wrap_past_sunday() {
+    let window = ScanWindow::parse("Sat-Mon 10:00-11:00 UTC").unwrap();
+    assert!(window.is_open(utc(2026, 10, 17, 10, 30))); // Saturday
+    assert!(window.is_open(utc(2026, 10, 18, 10, 30))); // Sunday
+    assert!(window.is_open(utc(2026, 10, 19, 10, 30))); // Monday
+    assert!(!window.is_open(utc(2026, 10, 20, 10, 30))); // Tuesday
+}
+
+#[test]
+fn test_empty_schedule_is_always_open() {
+    let schedule = Schedule::new(Vec::new());
+    assert!(schedule.is_empty());
+    assert!(schedule.is_open(utc(2026, 10, 12, 3, 0)));
+    assert_eq!(schedule.next_change(utc(2026, 10, 12, 3, 0)), None);
+}
+
+#[test]
+fn test_schedule_opens_when_any_window_does() {
+    let schedule = Schedule::new(vec![
+        ScanWindow::parse("Mon 01:00-02:00 UTC").unwrap(),
+        ScanWindow::parse("Tue 01:00-02:00 UTC").unwrap(),
+    ]);
+    assert!(schedule.is_open(utc(2026, 10, 12, 1, 30)));
+    assert!(schedule.is_open(utc(2026, 10, 13, 1, 30)));
+    assert!(!schedule.is_open(utc(2026, 10, 14, 1, 30)));
+}
+
+#[test]
+fn test_next_change_finds_window_edges() {
+    let schedule = Schedule::new(vec![ScanWindow::parse("Mon-Fri 22:00-05:00 UTC").unwrap()]);
+    // Friday afternoon: opens at 22:00
+    let now = Utc.with_ymd_and_hms(2026, 10, 16, 15, 12, 30).unwrap();
+    assert_eq!(schedule.next_change(now), Some(utc(2026, 10, 16, 22, 0)));
+    // Inside the window: closes at 05:00 the next morning
+    assert_eq!(
+        schedule.next_change(utc(2026, 10, 16, 23, 0)),
+        Some(utc(2026, 10, 17, 5, 0))
+    );
+    // Saturday morning: the next window is Monday night
+    assert_eq!(
+        schedule.next_change(utc(2026, 10, 17, 6, 0)),
+        Some(utc(2026, 10, 19, 22, 0))
+    );
+}
@@ -0,0 +1,56 @@
+use rust_backend::utils::segments::{self, Segment};
+
+#[test]
+fn test_parse_segment() {
+    let plain = Segment::parse("eth1").unwrap();
+    assert_eq!(plain.interface, "eth1");
+    assert_eq!(plain.targets, None);
+
+    let mapped = Segment::parse("vrf-blue=10.20.0.0/24,10.20.1.0/24").unwrap();
+    assert_eq!(mapped.interface, "vrf-blue");
+    assert_eq!(mapped.targets.as_deref(), Some("10.20.0.0/24,10.20.1.0/24"));
+    assert_eq!(mapped.to_string(), "vrf-blue=10.20.0.0/24,10.20.1.0/24");
+
+    assert!(Segment::parse("").is_err());
+    assert!(Segment::parse("=10.0.0.0/24").is_err());
+    assert!(Segment::parse("eth1=").is_err());
+}
+
+#[test]
+fn test_plan_assigns_targets() {
+    let parsed = |specs: &[&str]| -> Vec<Segment> {
+        specs.iter().map(|s| Segment::parse(s).unwrap()).collect()
+    };
+
+    let plan = segments::plan(
+        &parsed(&["eth1=10.20.0.0/24", "eth2"]),
+        Some("10.30.0.0/24"),
+    )
+    .unwrap();
+    let targets: Vec<(&str, &str)> = plan
+        .iter()
+        .map(|(segment, targets)| (segment.interface.as_str(), targets.as_str()))
+        .collect();
+    assert_eq!(
+        targets,
+        vec![("eth1", "10.20.0.0/24"), ("eth2", "10.30.0.0/24")]
+    );
+
+    // --ip can only go to one segment, and an unmapped one needs it
+    assert!(segments::plan(&parsed(&["eth1", "eth2"]), Some("10.0.0.0/24")).is_err());
+    assert!(segments::plan(&parsed(&["eth1"]), None).is_err());
+    assert!(segments::plan(&parsed(&["eth1=10.0.0.1", "eth1=10.0.0.2"]), None).is_err());
+    assert!(segments::plan(&parsed(&["eth1=10.0.0.1", "eth2=10.0.1.1"]), None).is_ok());
+}
+
+#[test]
+fn test_output_path_names_the_segment() {
+    assert_eq!(
+        segments::output_path("netscan_banners.csv", "eth1"),
+        "netscan_banners_eth1.csv"
+    );
+    assert_eq!(
+        segments::output_path("findings", "vrf-blue"),
+        "findings_vrf-blue"
+    );
+}
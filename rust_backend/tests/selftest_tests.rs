@@ -0,0 +1,51 @@
+use rust_backend::scanners::service_detection::{self, DetectError, Protocol};
+use rust_backend::utils::selftest;
+
+#[tokio::test]
+async fn test_every_emulated_protocol_is_detected() {
+    let registry = service_detection::registry();
+    let outcomes = selftest::run(&registry).await;
+    assert_eq!(
+        outcomes.len() + selftest::uncovered().len(),
+        Protocol::ALL.len()
+    );
+    for (protocol, outcome) in outcomes {
+        let outcome = outcome.unwrap();
+        assert!(
+            selftest::passed(&outcome),
+            "{:?}: {:?}",
+            protocol,
+            outcome.service
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_a_broken_detector_fails_its_check() {
+    let mut registry = service_detection::registry();
+    registry.register(
+        Protocol::Ssh,
+        service_detection::FnDetector::new("SSH", &[22], |_, _| async {
+            service_detection::Detection::failed(DetectError::Mismatch("broken".to_string()))
+        }),
+    );
+    let outcomes = selftest::run(&registry).await;
+    let (_, ssh) = outcomes.iter().find(|(p, _)| *p == Protocol::Ssh).unwrap();
+    // The banner grab still names SSH, but the detector did not
+    assert_eq!(ssh.as_ref().unwrap().service.as_deref(), Some("SSH"));
+    assert!(!selftest::passed(ssh.as_ref().unwrap()));
+}
+
+#[test]
+fn test_tls_and_udp_servers_are_emulated() {
+    let uncovered = selftest::uncovered();
+    for protocol in [
+        Protocol::Https,
+        Protocol::Dns,
+        Protocol::Snmp,
+        Protocol::Tftp,
+        Protocol::Sip,
+    ] {
+        assert!(!uncovered.contains(&protocol), "{:?}", protocol);
+    }
+}
@@ -1,7 +1,11 @@
-use rust_backend::scanners::service_detection::{detect_service, service_scan};
+use rust_backend::scanners::service_detection::{
+    detect_service, pipeline_scan, service_scan, service_scan_host, service_scan_many,
+    service_scan_many_stream,
+};
 use rust_backend::utils::prettyprint::pretty_print_service_results;
+use rust_backend::utils::scan_config::ScanConfig;
 
-use rust_backend::scanners::service_detection::Protocol;
+use rust_backend::scanners::service_detection::{Protocol, ProtocolSelection};
 
 const PROTOCOLS: &[Protocol] = &[
     Protocol::Ssh,
@@ -11,18 +15,18 @@ const PROTOCOLS: &[Protocol] = &[
     Protocol::Dns,
 ];
 
-fn get_test_ip() -> std::net::Ipv4Addr {
+fn get_test_ip() -> std::net::IpAddr {
     std::env::var("TEST_IP")
         .ok()
         .and_then(|ip| ip.parse().ok())
-        .unwrap_or(std::net::Ipv4Addr::new(127, 0, 0, 1))
+        .unwrap_or(std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
 }
 
 #[tokio::test]
 async fn test_service_scan() {
     let open_ports = vec![80, 443, 22, 30778, 53, 21, 153, 20, 19, 23, 148, 9999];
     let ip = get_test_ip();
-    let results = service_scan(ip, Some(open_ports.clone()), PROTOCOLS).await;
+    let results = service_scan(ip, Some(open_ports.clone()), &ProtocolSelection::Only(PROTOCOLS.to_vec()), &ScanConfig::default()).await;
 
     pretty_print_service_results("Service Scan Results", &results);
 
@@ -33,7 +37,7 @@ async fn test_service_scan() {
 async fn test_service_scan_default() {
     let ip = get_test_ip();
     let open_ports = vec![80, 443, 22, 30778, 53, 21, 153, 20, 19, 23, 148, 9999];
-    let results = service_scan(ip, Some(open_ports), PROTOCOLS).await;
+    let results = service_scan(ip, Some(open_ports), &ProtocolSelection::Only(PROTOCOLS.to_vec()), &ScanConfig::default()).await;
 
     print!("\n\t\tresults length: {}\n", results.len());
 
@@ -41,6 +45,19 @@ async fn test_service_scan_default() {
     assert_eq!(results.len(), 12); // ports 0..=1024
 }
 
+#[tokio::test]
+async fn test_service_scan_host_keeps_port_order() {
+    let ports = vec![30778, 9999, 148, 19];
+    let config = ScanConfig {
+        concurrency: Some(2),
+        ..ScanConfig::default()
+    };
+    let results = service_scan_host("localhost", &ports, &ProtocolSelection::Only(PROTOCOLS.to_vec()), &config).await;
+
+    let scanned: Vec<u16> = results.iter().map(|result| result.port).collect();
+    assert_eq!(scanned, ports);
+}
+
 #[tokio::test]
 async fn test_detect_service_http() {
     let port = 80;
@@ -130,7 +147,7 @@ async fn test_detect_service_unknown() {
 async fn test_service_scan_() {
     let open_ports = vec![80, 443, 22, 30778, 53, 21, 153, 20, 19, 23, 148, 9999];
     let ip = get_test_ip();
-    let results = service_scan(ip, Some(open_ports.clone()), PROTOCOLS).await;
+    let results = service_scan(ip, Some(open_ports.clone()), &ProtocolSelection::Only(PROTOCOLS.to_vec()), &ScanConfig::default()).await;
 
     println!("Service scan results:");
     for res in &results {
@@ -200,3 +217,329 @@ async fn test_detect_service_pop3() {
         result.service
     );
 }
+
+mod registry {
+    use rust_backend::scanners::service_detection::{
+        self, DetectError, Detection, DetectorRegistry, FnDetector, Protocol, ProtocolSelection,
+    };
+    use rust_backend::utils::evidence::{Attribute, Source};
+    use rust_backend::utils::scan_config::ScanConfig;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    /// A port on localhost with nothing listening
+    fn closed_port() -> u16 {
+        let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    #[test]
+    fn test_auto_selection_uses_port_hints() {
+        let mut registry = DetectorRegistry::new();
+        registry.register(
+            Protocol::Pop3,
+            FnDetector::new("POP3", &[110, 995], |_, _| async {
+                Detection::new(true, "POP3".to_string(), None)
+            }),
+        );
+        let auto = ProtocolSelection::default();
+        assert_eq!(auto.for_port(&registry, 110), vec![Protocol::Pop3]);
+        assert!(auto.for_port(&registry, 9999).is_empty());
+        let only = ProtocolSelection::Only(vec![Protocol::Ssh, Protocol::Http]);
+        assert_eq!(only.for_port(&registry, 110), vec![Protocol::Ssh, Protocol::Http]);
+    }
+
+    #[test]
+    fn test_udp_selection_keeps_udp_detectors() {
+        let registry = DetectorRegistry::with_builtin();
+        assert!(registry.get(Protocol::Snmp).unwrap().over_udp());
+        assert!(!registry.get(Protocol::Ssh).unwrap().over_udp());
+
+        let auto = ProtocolSelection::default();
+        assert_eq!(auto.udp_for_port(&registry, 161), vec![Protocol::Snmp]);
+        assert!(auto.udp_for_port(&registry, 22).is_empty());
+        let only = ProtocolSelection::Only(vec![Protocol::Ssh, Protocol::Dns]);
+        assert_eq!(only.udp_for_port(&registry, 2222), vec![Protocol::Dns]);
+    }
+
+    #[test]
+    fn test_failures_have_stable_names() {
+        for protocol in Protocol::ALL {
+            assert_eq!(Protocol::from_label(protocol.label()), Some(protocol));
+        }
+        assert_eq!(Protocol::HttpProxy.label(), "http-proxy");
+        assert_eq!(
+            DetectError::unrecognized(true, "No SSH banner"),
+            DetectError::Timeout("No SSH banner".to_string())
+        );
+        assert_eq!(DetectError::unrecognized(false, "No SSH banner").kind(), "mismatch");
+        let error = DetectError::Timeout("Read timed out".to_string());
+        assert_eq!(
+            DetectError::from_parts(error.kind(), error.message()),
+            Some(error)
+        );
+    }
+
+    #[test]
+    fn test_builtin_registry_covers_supported_protocols() {
+        let registry = DetectorRegistry::with_builtin();
+        for protocol in [
+            Protocol::Ssh,
+            Protocol::Https,
+            Protocol::Smb,
+            Protocol::Vpn,
+            Protocol::RemoteAdmin,
+            Protocol::Echo,
+            Protocol::Couchdb,
+            Protocol::Redis,
+        ] {
+            assert!(registry.contains(protocol), "{:?} has no detector", protocol);
+        }
+        assert!(registry.contains(Protocol::Telnet));
+        assert!(!DetectorRegistry::new().contains(Protocol::Pop3));
+
+        let ssh = registry.get(Protocol::Ssh).unwrap();
+        assert_eq!(ssh.name(), "SSH");
+        assert_eq!(ssh.default_ports(), &[22]);
+        let finger = registry.get(Protocol::Finger).unwrap();
+        assert_eq!(finger.name(), "FINGER");
+        assert_eq!(finger.default_ports(), &[79]);
+        assert!(
+            registry
+                .get(Protocol::RemoteAdmin)
+                .unwrap()
+                .default_ports()
+                .contains(&5938)
+        );
+    }
+
+    #[test]
+    fn test_well_known_ports_match_the_builtin_detectors() {
+        assert_eq!(Protocol::for_port(80), &[Protocol::Http, Protocol::Onvif]);
+        assert_eq!(Protocol::for_port(8080), &[Protocol::Http, Protocol::HttpProxy]);
+        assert_eq!(Protocol::for_port(7), &[Protocol::Echo]);
+        assert!(Protocol::for_port(9999).is_empty());
+        assert!(Protocol::RemoteAdmin.default_ports().contains(&5938));
+
+        let registry = DetectorRegistry::with_builtin();
+        for protocol in Protocol::ALL {
+            if let Some(detector) = registry.get(protocol) {
+                assert_eq!(detector.default_ports(), protocol.default_ports(), "{:?}", protocol);
+            }
+            for &port in protocol.default_ports() {
+                assert!(Protocol::for_port(port).contains(&protocol));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_detector_is_dispatched() {
+        let mut registry = DetectorRegistry::new();
+        registry.register(
+            Protocol::Pop3,
+            FnDetector::new("POP3", &[110], |_, port| async move {
+                Detection::new(true, format!("POP3 on {}", port), None)
+            }),
+        );
+        let result = service_detection::detect_service_with(
+            &registry,
+            LOCALHOST,
+            1110,
+            &[Protocol::Pop3],
+            &ScanConfig::default(),
+        )
+        .await;
+        assert_eq!(result.service.as_deref(), Some("POP3 on 1110"));
+        assert!(result.protocol_failures.is_empty());
+        // The detector that answered is recorded as the evidence
+        assert_eq!(result.evidence.len(), 1);
+        assert_eq!(result.evidence[0].attribute, Attribute::Service);
+        assert_eq!(result.evidence[0].source, Source::Probe("POP3".to_string()));
+        assert_eq!(result.evidence[0].port, Some(1110));
+        assert_eq!(result.transport, "tcp");
+    }
+
+    #[tokio::test]
+    async fn test_detection_carries_its_transport() {
+        let mut registry = DetectorRegistry::new();
+        registry.register(
+            Protocol::Snmp,
+            FnDetector::new("SNMP", &[161], |_, _| async {
+                Detection::new(true, "SNMPv2c".to_string(), None)
+            })
+            .over_udp(),
+        );
+        registry.register(
+            Protocol::Sip,
+            FnDetector::new("SIP", &[5060], |_, _| async {
+                Detection::new(true, "SIP over TCP".to_string(), None).over("tcp")
+            })
+            .over_udp(),
+        );
+        let config = ScanConfig::default();
+        let snmp =
+            service_detection::detect_service_with(&registry, LOCALHOST, 161, &[Protocol::Snmp], &config)
+                .await;
+        assert_eq!(snmp.transport, "udp");
+        // A detector that tried both says which one answered
+        let sip =
+            service_detection::detect_service_with(&registry, LOCALHOST, 5060, &[Protocol::Sip], &config)
+                .await;
+        assert_eq!(sip.transport, "tcp");
+    }
+
+    #[tokio::test]
+    async fn test_failed_detectors_are_listed_in_order() {
+        let mut registry = DetectorRegistry::new();
+        registry.register(
+            Protocol::Imap,
+            FnDetector::new("IMAP", &[143], |_, _| async {
+                Detection::failed(DetectError::Mismatch("no greeting".to_string()))
+            }),
+        );
+        let port = closed_port();
+        let result = service_detection::detect_service_with(
+            &registry,
+            LOCALHOST,
+            port,
+            &[Protocol::Imap, Protocol::Telnet],
+            &ScanConfig::default(),
+        )
+        .await;
+        assert_eq!(result.service.as_deref(), Some("Unknown Service"));
+        assert_eq!(
+            result.protocol_failures,
+            vec![
+                (
+                    Protocol::Imap,
+                    DetectError::Mismatch("no greeting".to_string())
+                ),
+                (Protocol::Telnet, DetectError::Unsupported),
+            ]
+        );
+        assert_eq!(result.error.as_deref(), Some("no greeting"));
+        // Only the registered detector ran
+        assert_eq!(result.attempts.len(), 1);
+        assert_eq!(result.attempts[0].protocol, "IMAP");
+        assert!(!result.attempts[0].detected && !result.attempts[0].timed_out);
+    }
+
+    #[tokio::test]
+    async fn test_detectors_report_why_they_failed() {
+        let registry = service_detection::registry();
+        let result = service_detection::detect_service_with(
+            &registry,
+            LOCALHOST,
+            closed_port(),
+            &[Protocol::Ftp],
+            &ScanConfig::default(),
+        )
+        .await;
+        assert_eq!(result.protocol_failures.len(), 1);
+        assert_eq!(result.protocol_failures[0].1.kind(), "connect");
+
+        let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            use tokio::io::AsyncWriteExt;
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let _ = stream.write_all(b"HELLO\r\n").await;
+            }
+        });
+        let result = service_detection::detect_service_with(
+            &registry,
+            LOCALHOST,
+            port,
+            &[Protocol::Ftp],
+            &ScanConfig::default(),
+        )
+        .await;
+        assert_eq!(result.protocol_failures.len(), 1);
+        assert_eq!(result.protocol_failures[0].1.kind(), "mismatch");
+    }
+
+    #[tokio::test]
+    async fn test_registered_detector_reaches_detect_service() {
+        service_detection::register_detector(
+            Protocol::Telnet,
+            FnDetector::new("Telnet", &[23], |_, _| async {
+                Detection::new(true, "Telnet (test)".to_string(), None)
+            }),
+        );
+        let result =
+            service_detection::detect_service(LOCALHOST, closed_port(), &[Protocol::Telnet]).await;
+        assert_eq!(result.service.as_deref(), Some("Telnet (test)"));
+    }
+}
+
+#[tokio::test]
+async fn test_service_scan_many_groups_by_host() {
+    let a: std::net::IpAddr = "127.0.0.1".parse().unwrap();
+    let b: std::net::IpAddr = "127.0.0.2".parse().unwrap();
+    let endpoints = vec![(b, 9), (a, 7), (b, 1), (a, 3)];
+    let grouped = service_scan_many(
+        &endpoints,
+        &ProtocolSelection::Only(vec![Protocol::Echo]),
+        &ScanConfig::default(),
+    )
+    .await;
+
+    let hosts: Vec<_> = grouped.iter().map(|(ip, _)| *ip).collect();
+    assert_eq!(hosts, vec![b, a]);
+    let ports: Vec<Vec<u16>> = grouped
+        .iter()
+        .map(|(_, results)| results.iter().map(|r| r.port).collect())
+        .collect();
+    assert_eq!(ports, vec![vec![9, 1], vec![7, 3]]);
+}
+
+#[tokio::test]
+async fn test_pipeline_scan_skips_closed_tcp_ports() {
+    let a: std::net::IpAddr = "127.0.0.1".parse().unwrap();
+    let b: std::net::IpAddr = "127.0.0.2".parse().unwrap();
+    let open = std::collections::BTreeMap::from([(a, vec![7])]);
+    let grouped = pipeline_scan(
+        &[a, b],
+        &[7, 9],
+        &open,
+        &ProtocolSelection::Only(vec![Protocol::Echo]),
+        &ScanConfig::default(),
+    )
+    .await;
+
+    // No UDP detector was selected, so only the open TCP port is probed
+    assert_eq!(grouped.len(), 1);
+    assert_eq!(grouped[0].0, a);
+    assert_eq!(grouped[0].1.len(), 1);
+    assert_eq!(grouped[0].1[0].port, 7);
+}
+
+#[tokio::test]
+async fn test_hosts_stream_as_they_finish_with_their_own_time() {
+    // 127.0.0.2 accepts and stays silent; nothing listens on 127.0.0.1
+    let slow: std::net::IpAddr = "127.0.0.2".parse().unwrap();
+    let fast: std::net::IpAddr = "127.0.0.1".parse().unwrap();
+    let listener = tokio::net::TcpListener::bind((slow, 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            held.push(stream);
+        }
+    });
+
+    let mut hosts = service_scan_many_stream(
+        &[(slow, port), (fast, port)],
+        &ProtocolSelection::Only(vec![Protocol::Echo]),
+        &ScanConfig::default(),
+    );
+    let first = hosts.recv().await.unwrap();
+    let second = hosts.recv().await.unwrap();
+    assert!(hosts.recv().await.is_none());
+    assert_eq!((first.ip, second.ip), (fast, slow));
+    assert_eq!(first.results[0].port, port);
+    assert!(first.elapsed < second.elapsed);
+}
@@ -0,0 +1,84 @@
+use chrono::{Duration, TimeZone, Utc};
+use rust_backend::utils::inventory::Inventory;
+use rust_backend::utils::monitor::{self, MonitorState};
+use rust_backend::utils::sightings::DeviceSightings;
+use std::net::IpAddr;
+
+fn ip(text: &str) -> IpAddr {
+    text.parse().unwrap()
+}
+
+#[test]
+fn test_device_keeps_first_seen_across_addresses() {
+    let start = Utc.with_ymd_and_hms(2026, 3, 1, 8, 0, 0).unwrap();
+    let mut sightings = DeviceSightings::new();
+    sightings.record(ip("10.0.0.7"), Some("B8:27:EB:01:02:03"), None, start);
+    let later = start + Duration::hours(5);
+    let device = sightings.record(
+        ip("10.0.0.9"),
+        Some("b8:27:eb:01:02:03"),
+        Some("Raspberry Pi Foundation"),
+        later,
+    );
+    assert_eq!(device.ip, ip("10.0.0.9"));
+    assert_eq!(device.first_seen, start);
+    assert_eq!(device.last_seen, later);
+    assert_eq!(sightings.len(), 1);
+
+    // Without a MAC the address is all that identifies a device
+    sightings.record(ip("10.0.0.7"), None, None, later);
+    assert_eq!(sightings.len(), 2);
+    assert!(sightings.get(ip("10.0.0.7"), None).is_some());
+}
+
+#[test]
+fn test_sightings_round_trip() {
+    let path = std::env::temp_dir().join(format!("netscan-devices-{}.csv", std::process::id()));
+    let now = Utc.with_ymd_and_hms(2026, 3, 1, 8, 0, 0).unwrap();
+    let mut sightings = DeviceSightings::new();
+    sightings.record(
+        ip("10.0.0.7"),
+        Some("b8:27:eb:01:02:03"),
+        Some("Raspberry Pi"),
+        now,
+    );
+    sightings.record(ip("10.0.0.8"), None, None, now);
+    sightings.save(&path).unwrap();
+    assert_eq!(DeviceSightings::load(&path).unwrap(), sightings);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_unapproved_devices_alert_with_first_seen() {
+    let dir = std::env::temp_dir().join(format!("netscan-rogue-{}", std::process::id()));
+    let state = MonitorState::open(&dir).unwrap();
+    let inventory = Inventory::parse_csv("ip,mac,hostname\n10.0.0.1,,gw\n").unwrap();
+    let hosts = [ip("10.0.0.1"), ip("10.0.0.7")];
+    let mac_of = |host: IpAddr| (host == ip("10.0.0.7")).then(|| "b8:27:eb:01:02:03".to_string());
+    let first = Utc.with_ymd_and_hms(2026, 3, 1, 8, 0, 0).unwrap();
+
+    let alerts = monitor::check_devices(&state, &inventory, &hosts, mac_of, first).unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].title, "Unapproved device");
+    assert_eq!(alerts[0].hosts, vec![ip("10.0.0.7")]);
+    assert!(alerts[0].detail.contains("b8:27:eb:01:02:03"));
+    assert!(
+        alerts[0]
+            .detail
+            .ends_with("first seen 2026-03-01 08:00 UTC")
+    );
+
+    // The next run still reports when the device first appeared, under the same ID
+    let again = monitor::check_devices(
+        &state,
+        &inventory,
+        &hosts,
+        mac_of,
+        first + Duration::hours(1),
+    )
+    .unwrap();
+    assert_eq!(again[0].detail, alerts[0].detail);
+    assert_eq!(again[0].id, alerts[0].id);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,38 @@
+use rust_backend::utils::sockets::{self, SocketFactory, SystemSockets};
+use rust_backend::utils::warnings;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+#[test]
+fn test_link_local_addresses_carry_their_scope() {
+    let ip: Ipv6Addr = "fe80::21a:2bff:fe3c:4d5e".parse().unwrap();
+    sockets::register_link_scope(ip, 3);
+    assert_eq!(
+        sockets::socket_addr(IpAddr::V6(ip), 22).to_string(),
+        "[fe80::21a:2bff:fe3c:4d5e%3]:22"
+    );
+    let global: IpAddr = "2001:db8::10".parse().unwrap();
+    assert_eq!(sockets::socket_addr(global, 22), SocketAddr::new(global, 22));
+}
+
+#[tokio::test]
+async fn test_bound_sockets_leave_through_their_interface() {
+    if !warnings::has_raw_socket_privilege() {
+        return; // SO_BINDTODEVICE needs CAP_NET_RAW
+    }
+    let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let addr = SocketAddr::new(
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+        listener.local_addr().unwrap().port(),
+    );
+
+    let loopback = SystemSockets::bound_to(Some("lo".to_string()));
+    assert!(loopback.tcp_connect(addr, None).await.is_ok());
+    let missing = SystemSockets::bound_to(Some("netscan-none0".to_string()));
+    assert!(missing.tcp_connect(addr, None).await.is_err());
+    assert!(
+        SystemSockets::default()
+            .tcp_connect(addr, None)
+            .await
+            .is_ok()
+    );
+}
@@ -0,0 +1,160 @@
+use chrono::{TimeZone, Utc};
+use rust_backend::report::ScanReport;
+use rust_backend::scanners::pingsweep::LiveHost;
+use rust_backend::scanners::service_detection::{DetectError, Protocol, ServiceDetectionResult};
+use rust_backend::scanners::tcpscan::TcpScanResult;
+use rust_backend::scanners::udpscan::UdpScanResult;
+use rust_backend::utils::errors::ErrorCategory;
+use rust_backend::utils::fingerprinting::HostFingerprintResult;
+use rust_backend::utils::os_fusion::OsGuess;
+use rust_backend::utils::storage::ScanHistory;
+use std::net::IpAddr;
+
+fn sample_report() -> ScanReport {
+    let web: IpAddr = "10.0.0.20".parse().unwrap();
+    let gateway: IpAddr = "10.0.0.1".parse().unwrap();
+    let mut report = ScanReport::new();
+    report.add_discovery(&[
+        LiveHost {
+            ip: web,
+            hostname: Some("web.lan".to_string()),
+        },
+        LiveHost {
+            ip: gateway,
+            hostname: None,
+        },
+    ]);
+    let mut tcp = TcpScanResult::new();
+    tcp.add_open_port(web, 443);
+    tcp.add_open_port(web, 22);
+    tcp.add_port_error(web, 23, ErrorCategory::Refused, "Connection refused");
+    tcp.record_attempts(web, 443, 2);
+    report.add_tcp(&tcp);
+    let mut udp = UdpScanResult::new();
+    udp.add_open_port(gateway, 53);
+    udp.add_port_error(gateway, 161, ErrorCategory::Timeout, "No response");
+    udp.record_attempts(gateway, 53, 3);
+    report.add_udp(&udp);
+    report.add_ping_attempts(&[(gateway, 2)].into_iter().collect());
+    let mut fingerprint = HostFingerprintResult::new(web);
+    fingerprint.os = Some("Linux".to_string());
+    fingerprint.os_guesses = vec![
+        OsGuess {
+            os: "Linux".to_string(),
+            confidence: 0.75,
+        },
+        OsGuess {
+            os: "FreeBSD".to_string(),
+            confidence: 0.25,
+        },
+    ];
+    report.add_fingerprint(&fingerprint);
+    report.add_services(
+        web,
+        &[
+            ServiceDetectionResult::new(443, Some("HTTPS".to_string()), None, Vec::new()),
+            ServiceDetectionResult::new(
+                22,
+                Some("SSH".to_string()),
+                None,
+                vec![
+                    (
+                        Protocol::Ftp,
+                        DetectError::Timeout("Read timed out".to_string()),
+                    ),
+                    (Protocol::Telnet, DetectError::Unsupported),
+                ],
+            ),
+        ],
+    );
+    report.add_notes(
+        web,
+        vec![
+            "Staging web server".to_string(),
+            "Ignore port 23".to_string(),
+        ],
+    );
+    report
+}
+
+#[test]
+fn test_recorded_runs_load_back_unchanged() {
+    let path = std::env::temp_dir().join(format!("netscan-history-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let report = sample_report();
+    let first = Utc.with_ymd_and_hms(2026, 3, 1, 22, 0, 0).unwrap();
+    let second = Utc.with_ymd_and_hms(2026, 3, 2, 22, 0, 0).unwrap();
+
+    let mut history = ScanHistory::open(&path).unwrap();
+    let older = history
+        .record(first, first, "10.0.0.0/24", &report)
+        .unwrap();
+    history
+        .record(second, second, "10.0.1.0/24", &ScanReport::new())
+        .unwrap();
+    drop(history);
+
+    // A reopened database keeps every run, newest first
+    let history = ScanHistory::open(&path).unwrap();
+    let runs = history.runs(None).unwrap();
+    assert_eq!(
+        runs.iter()
+            .map(|run| run.targets.as_str())
+            .collect::<Vec<_>>(),
+        vec!["10.0.1.0/24", "10.0.0.0/24"]
+    );
+    let same_network = history.runs(Some("10.0.0.0/24")).unwrap();
+    assert_eq!(same_network.len(), 1);
+    assert_eq!(same_network[0].id, older);
+    assert_eq!(same_network[0].started, first);
+    assert_eq!(same_network[0].host_count, 2);
+
+    assert_eq!(history.load(older).unwrap(), report);
+    assert!(history.load(older + 100).is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_untyped_failures_from_older_histories_still_load() {
+    let path = std::env::temp_dir().join(format!("netscan-untyped-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut history = ScanHistory::open(&path).unwrap();
+    let first = Utc.with_ymd_and_hms(2026, 3, 1, 22, 0, 0).unwrap();
+    let id = history
+        .record(first, first, "10.0.0.0/24", &sample_report())
+        .unwrap();
+    drop(history);
+
+    // Older releases stored detector names, or the enum name when none ran
+    let db = rusqlite::Connection::open(&path).unwrap();
+    db.execute(
+        "UPDATE services SET protocol_failures = 'FTP,HTTP proxy,Telnet,Rsh' WHERE port = 22",
+        [],
+    )
+    .unwrap();
+    drop(db);
+
+    let history = ScanHistory::open(&path).unwrap();
+    let report = history.load(id).unwrap();
+    let ssh = report
+        .hosts
+        .iter()
+        .flat_map(|host| &host.services)
+        .find(|service| service.port == 22)
+        .unwrap();
+    let failures: Vec<_> = ssh
+        .protocol_failures
+        .iter()
+        .map(|failure| (failure.protocol, failure.error.kind()))
+        .collect();
+    assert_eq!(
+        failures,
+        vec![
+            (Protocol::Ftp, "mismatch"),
+            (Protocol::HttpProxy, "mismatch"),
+            (Protocol::Telnet, "mismatch"),
+            (Protocol::Rsh, "unsupported"),
+        ]
+    );
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,91 @@
+use pnet::packet::tcp::{self, MutableTcpPacket, TcpFlags, TcpOptionNumbers, TcpPacket};
+use rust_backend::scanners::synscan::{self, SynReply};
+use rust_backend::utils::scan_config::ScanConfig;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tokio::net::TcpListener;
+
+#[test]
+fn test_build_syn_is_a_valid_segment() {
+    let src = Ipv4Addr::new(10, 0, 0, 1);
+    let dst = Ipv4Addr::new(10, 0, 0, 2);
+    let bytes = synscan::build_syn(src.into(), dst.into(), 40123, 443, 0xdead_beef).unwrap();
+    let syn = TcpPacket::new(&bytes).unwrap();
+    assert_eq!(syn.get_source(), 40123);
+    assert_eq!(syn.get_destination(), 443);
+    assert_eq!(syn.get_sequence(), 0xdead_beef);
+    assert_eq!(syn.get_flags(), TcpFlags::SYN);
+    assert_eq!(syn.get_data_offset(), 6);
+    assert!(
+        syn.get_options_iter()
+            .any(|option| option.get_number() == TcpOptionNumbers::MSS)
+    );
+    assert_eq!(syn.get_checksum(), tcp::ipv4_checksum(&syn, &src, &dst));
+}
+
+#[test]
+fn test_build_syn_over_ipv6() {
+    let src: Ipv6Addr = "fd00::1".parse().unwrap();
+    let dst: Ipv6Addr = "fd00::2".parse().unwrap();
+    let bytes = synscan::build_syn(src.into(), dst.into(), 40123, 22, 7).unwrap();
+    let syn = TcpPacket::new(&bytes).unwrap();
+    assert_eq!(syn.get_checksum(), tcp::ipv6_checksum(&syn, &src, &dst));
+    assert!(synscan::build_syn(src.into(), Ipv4Addr::LOCALHOST.into(), 1, 2, 3).is_err());
+}
+
+fn reply(src_port: u16, dst_port: u16, ack: u32, flags: u8) -> Vec<u8> {
+    let mut buffer = vec![0u8; 20];
+    let mut packet = MutableTcpPacket::new(&mut buffer).unwrap();
+    packet.set_source(src_port);
+    packet.set_destination(dst_port);
+    packet.set_acknowledgement(ack);
+    packet.set_data_offset(5);
+    packet.set_flags(flags);
+    buffer
+}
+
+#[test]
+fn test_classify_replies() {
+    let seq = 1000;
+    let open = reply(443, 40123, seq + 1, TcpFlags::SYN | TcpFlags::ACK);
+    let closed = reply(444, 40123, seq + 1, TcpFlags::RST | TcpFlags::ACK);
+    let other_scan = reply(443, 50000, seq + 1, TcpFlags::SYN | TcpFlags::ACK);
+    let stale = reply(443, 40123, 5, TcpFlags::SYN | TcpFlags::ACK);
+    let plain_ack = reply(443, 40123, seq + 1, TcpFlags::ACK);
+
+    let classify =
+        |bytes: &[u8]| synscan::classify_reply(&TcpPacket::new(bytes).unwrap(), 40123, seq);
+    assert_eq!(classify(&open), Some((443, SynReply::Open)));
+    assert_eq!(classify(&closed), Some((444, SynReply::Closed)));
+    assert_eq!(classify(&other_scan), None);
+    assert_eq!(classify(&stale), None);
+    assert_eq!(classify(&plain_ack), None);
+}
+
+#[tokio::test]
+async fn test_scan_finds_listener_with_or_without_root() {
+    // As root this is a real SYN scan of loopback; otherwise it falls back to connect
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let target = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let result = synscan::syn_scan_or_connect(&[target], port..port + 1, None, &ScanConfig::default()).await;
+    assert_eq!(
+        result.get_open_ports().iter().collect::<Vec<_>>(),
+        vec![(target, port)]
+    );
+}
+
+#[tokio::test]
+async fn test_hosts_that_answer_every_port_finish_early() {
+    // Only 127.0.0.1 listens; the other loopback addresses answer with resets
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let targets: Vec<IpAddr> = (1..=4).map(|last| IpAddr::V4(Ipv4Addr::new(127, 0, 0, last))).collect();
+    let started = std::time::Instant::now();
+    let result = synscan::syn_scan_or_connect(&targets, port..port + 1, None, &ScanConfig::default()).await;
+    assert_eq!(
+        result.get_open_ports().iter().collect::<Vec<_>>(),
+        vec![(targets[0], port)]
+    );
+    // Neither a wait per round nor one host after another
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+}
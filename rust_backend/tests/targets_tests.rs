@@ -0,0 +1,150 @@
+use rust_backend::utils::fingerprinting::HostFingerprintResult;
+use rust_backend::utils::targets::{
+    Exclusion, ExclusionList, TargetKey, TargetSpec, join_target_lines, merge_duplicates,
+    parse_targets, read_target_file, resolve_targets,
+};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+#[test]
+fn test_target_key_canonicalizes_mapped_ipv6() {
+    let v4 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let mapped = IpAddr::V6(Ipv4Addr::new(10, 0, 0, 5).to_ipv6_mapped());
+    assert_eq!(TargetKey::new(v4), TargetKey::new(mapped));
+    assert_eq!(
+        TargetKey::new(mapped).ipv4(),
+        Some(Ipv4Addr::new(10, 0, 0, 5))
+    );
+    assert_eq!(TargetKey::new(IpAddr::V6(Ipv6Addr::LOCALHOST)).ipv4(), None);
+}
+
+#[test]
+fn test_parse_targets() {
+    let specs = parse_targets("10.0.0.0/30, 10.0.0.1,localhost").unwrap();
+    assert_eq!(
+        specs,
+        vec![
+            TargetSpec::Network("10.0.0.0/30".to_string()),
+            TargetSpec::Address(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))),
+            TargetSpec::Hostname("localhost".to_string()),
+        ]
+    );
+    assert!(parse_targets("10.0.0.0/33").is_err());
+    assert!(parse_targets(" , ").is_err());
+}
+
+#[test]
+fn test_join_target_lines() {
+    let text = "# office\n10.0.0.0/24\n\n10.0.1.5  db.example.com\r\nfd00::1, 10.0.2.0/30 # lab\n";
+    assert_eq!(
+        join_target_lines(text).unwrap(),
+        "10.0.0.0/24,10.0.1.5,db.example.com,fd00::1,10.0.2.0/30"
+    );
+    assert!(join_target_lines("# nothing here\n\n").is_err());
+    assert!(join_target_lines("10.0.0.0/40\n").is_err());
+}
+
+#[test]
+fn test_parse_ranges() {
+    let v4 = |last| IpAddr::V4(Ipv4Addr::new(192, 168, 1, last));
+    assert_eq!(
+        parse_targets("192.168.1.10-50,192.168.1.60-192.168.1.61,fd00::1-fd00::1f,db-1.example.com").unwrap(),
+        vec![
+            TargetSpec::Range(v4(10), v4(50)),
+            TargetSpec::Range(v4(60), v4(61)),
+            TargetSpec::Range("fd00::1".parse().unwrap(), "fd00::1f".parse().unwrap()),
+            TargetSpec::Hostname("db-1.example.com".to_string()),
+        ]
+    );
+    assert_eq!(TargetSpec::Range(v4(10), v4(50)).to_string(), "192.168.1.10-192.168.1.50");
+    assert!(parse_targets("192.168.1.50-10").is_err());
+    assert!(parse_targets("192.168.1.10-300").is_err());
+    assert!(parse_targets("192.168.1.10-fd00::1").is_err());
+    assert!(parse_targets("10.0.0.0-10.255.255.255").is_err());
+}
+
+#[tokio::test]
+async fn test_target_file_and_ranges_resolve_to_one_set() {
+    let path = std::env::temp_dir().join(format!("netscan_targets_{}.txt", std::process::id()));
+    std::fs::write(&path, "# lab\n10.0.0.1-4\n10.0.0.3 10.0.0.8\n").unwrap();
+    let list = read_target_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(list, "10.0.0.1-4,10.0.0.3,10.0.0.8");
+
+    let specs = parse_targets(&format!("10.0.0.4,{}", list)).unwrap();
+    let set = resolve_targets(&specs).await.unwrap();
+    assert_eq!(set.len(), 5);
+    assert_eq!(set.duplicates(), 2);
+    assert!(read_target_file(std::path::Path::new("/nonexistent/targets.txt")).is_err());
+}
+
+#[tokio::test]
+async fn test_overlapping_targets_are_deduplicated() {
+    let specs = parse_targets("10.0.0.0/30,10.0.0.2/31,10.0.0.1,::ffff:10.0.0.3").unwrap();
+    let set = resolve_targets(&specs).await.unwrap();
+
+    assert_eq!(set.len(), 4);
+    assert_eq!(set.duplicates(), 4);
+    let key = TargetKey::from(Ipv4Addr::new(10, 0, 0, 3));
+    assert_eq!(set.sources(key).unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_ipv6_network_targets() {
+    let specs = parse_targets("fd00::/126,fd00::2,10.0.0.1").unwrap();
+    assert_eq!(specs[0], TargetSpec::Network("fd00::/126".to_string()));
+    let set = resolve_targets(&specs).await.unwrap();
+
+    assert_eq!(set.len(), 5);
+    assert_eq!(set.duplicates(), 1);
+    assert_eq!(set.ipv4_hosts(), vec![Ipv4Addr::new(10, 0, 0, 1)]);
+    assert!(set.hosts().contains(&IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 3))));
+    assert!(parse_targets("fd00::/64").is_err());
+}
+
+#[tokio::test]
+async fn test_excluded_targets_are_dropped() {
+    let mut exclusions = ExclusionList::new();
+    exclusions.add_list("10.0.0.5, 10.0.0.8/30,fd00::1").unwrap();
+    let path = std::env::temp_dir().join(format!("netscan_exclude_{}.txt", std::process::id()));
+    std::fs::write(&path, "# production\n10.0.0.14  10.0.0.15\n").unwrap();
+    exclusions.add_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let specs = parse_targets("10.0.0.0/28,::ffff:10.0.0.1").unwrap();
+    let mut set = resolve_targets(&specs).await.unwrap();
+    assert_eq!(set.exclude(&exclusions), 7);
+    assert_eq!(set.len(), 9);
+    assert!(!set.hosts().contains(&IpAddr::V4(Ipv4Addr::new(10, 0, 0, 9))));
+    assert!(exclusions.contains(IpAddr::V6(Ipv4Addr::new(10, 0, 0, 5).to_ipv6_mapped())));
+    assert!(exclusions.contains("fd00::1".parse().unwrap()));
+    assert!(!exclusions.contains("fd00::2".parse().unwrap()));
+}
+
+#[test]
+fn test_parse_exclusion() {
+    assert_eq!(Exclusion::parse("10.0.0.9/30").unwrap().to_string(), "10.0.0.8/30");
+    assert_eq!(Exclusion::parse("10.0.0.9").unwrap().to_string(), "10.0.0.9/32");
+    assert!(Exclusion::parse("0.0.0.0/0").unwrap().contains(IpAddr::V4(Ipv4Addr::BROADCAST)));
+    assert!(Exclusion::parse("10.0.0.0/33").is_err());
+    assert!(Exclusion::parse("db.example.com").is_err());
+}
+
+#[test]
+fn test_merge_duplicate_results() {
+    let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20));
+    let mut first = HostFingerprintResult::new(ip);
+    first.os = Some("Linux".to_string());
+    let mut second = HostFingerprintResult::new(ip);
+    second.vendor = Some("Raspberry Pi Trading".to_string());
+    second.os = Some("Windows".to_string());
+    let other = HostFingerprintResult::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 21)));
+
+    let merged = merge_duplicates(
+        vec![first, other, second],
+        |fp| fp.ip.into(),
+        |a, b| a.merge(b),
+    );
+    assert_eq!(merged.len(), 2);
+    assert_eq!(merged[0].os.as_deref(), Some("Linux")); // First answer wins
+    assert_eq!(merged[0].vendor.as_deref(), Some("Raspberry Pi Trading"));
+}
@@ -1,9 +1,17 @@
-use rust_backend::scanners::tcpscan::tcp_scan;
-use std::net::Ipv4Addr;
+use rust_backend::scanners::tcpscan::{
+    TcpScanResult, measure_rtts_with, tcp_scan, tcp_scan_stream_with, tcp_scan_with,
+};
+use rust_backend::utils::compact::PortState;
+use rust_backend::utils::errors::ErrorCategory;
+use rust_backend::utils::scan_config::ScanConfig;
+use rust_backend::utils::sockets::{MockResponse, MockSockets};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[tokio::test]
 async fn test_tcp_scan_valid_host() {
-    let live_hosts = vec![Ipv4Addr::new(192,168,1,158)]; // Localhost for testing
+    let live_hosts = vec![IpAddr::V4(Ipv4Addr::new(192,168,1,158))]; // Localhost for testing
     let port_range = 30778..30779; // Common ports (e.g., SSH, Telnet)
     let result = tcp_scan(&live_hosts, port_range).await;
 
@@ -13,7 +21,7 @@ async fn test_tcp_scan_valid_host() {
 
 #[tokio::test]
 async fn test_tcp_scan_invalid_host() {
-    let live_hosts = vec![Ipv4Addr::new(192, 0, 2, 1)]; // Reserved IP (unreachable)
+    let live_hosts = vec![IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))]; // Reserved IP (unreachable)
     let port_range = 1..10;
     let result = tcp_scan(&live_hosts, port_range).await;
 
@@ -23,10 +31,214 @@ async fn test_tcp_scan_invalid_host() {
 
 #[tokio::test]
 async fn test_tcp_scan_empty_port_range() {
-    let live_hosts = vec![Ipv4Addr::new(127, 0, 0, 1)];
+    let live_hosts = vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))];
     let port_range = 0..0; // Empty range
     let result = tcp_scan(&live_hosts, port_range).await;
 
     assert!(result.get_open_ports().is_empty()); // No open ports expected
     assert!(result.get_errors().is_empty()); // No errors expected
-}
\ No newline at end of file
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tcp_scan_with_mock_sockets() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let sockets = Arc::new(
+        MockSockets::new()
+            .respond(SocketAddr::from((ip, 22)), MockResponse::Open(0))
+            .respond(SocketAddr::from((ip, 23)), MockResponse::Silent),
+    );
+    let result = tcp_scan_with(&[ip], 20..25, None, &ScanConfig::default(), sockets.clone()).await;
+
+    assert_eq!(result.get_open_ports().iter().collect::<Vec<_>>(), vec![(ip, 22)]);
+    // The silent port hits the connect timeout without the test waiting for it
+    assert_eq!(
+        result.get_port_state(ip, 23),
+        PortState::Failed(ErrorCategory::Timeout)
+    );
+    assert_eq!(
+        result.get_port_state(ip, 24),
+        PortState::Failed(ErrorCategory::Refused)
+    );
+    assert_eq!(sockets.probes().len(), 5);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tcp_scan_retries_only_silent_ports() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let sockets = Arc::new(
+        MockSockets::new()
+            .respond(SocketAddr::from((ip, 22)), MockResponse::Open(0))
+            .respond(SocketAddr::from((ip, 23)), MockResponse::Silent),
+    );
+    let config = ScanConfig {
+        timeout: Some(Duration::from_millis(500)),
+        concurrency: Some(1),
+        retries: Some(2),
+        ..ScanConfig::default()
+    };
+    let result = tcp_scan_with(&[ip], 22..25, None, &config, sockets.clone()).await;
+
+    assert_eq!(
+        result.get_port_state(ip, 23),
+        PortState::Failed(ErrorCategory::Timeout)
+    );
+    // 22 and 24 answer the first time; 23 is asked three times
+    let probes = sockets.probes();
+    assert_eq!(probes.len(), 5);
+    assert_eq!(
+        probes.iter().filter(|addr| addr.port() == 23).count(),
+        3
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tcp_scan_flags_host_that_accepts_everything() {
+    let tarpit = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 6));
+    let normal = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7));
+    let sockets = MockSockets::new()
+        .respond(SocketAddr::from((normal, 22)), MockResponse::Open(0))
+        .respond(SocketAddr::from((normal, 80)), MockResponse::Open(0));
+    let sockets = (1..=100).fold(sockets, |sockets, port| {
+        sockets.respond(SocketAddr::from((tarpit, port)), MockResponse::Open(0))
+    });
+    let result = tcp_scan_with(&[tarpit, normal], 1..101, None, &ScanConfig::default(), Arc::new(sockets)).await;
+
+    assert_eq!(result.get_open_ports().len(), 102);
+    let suspects = result.get_suspect_hosts();
+    assert_eq!(suspects.len(), 1);
+    assert_eq!(suspects[0].ip, tarpit);
+    assert!(!result.is_suspect(normal));
+    assert_eq!(
+        result.get_trusted_open_ports(),
+        vec![(normal, 22), (normal, 80)]
+    );
+}
+
+#[test]
+fn test_open_ports_by_host_feeds_service_detection() {
+    let web = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let db = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 6));
+    let quiet = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7));
+    let mut result = TcpScanResult::new();
+    result.add_open_port(web, 80);
+    result.add_open_port(db, 5432);
+    result.add_open_port(web, 443);
+    result.add_port_error(web, 22, ErrorCategory::Refused, "Connection refused");
+    result.add_port_error(quiet, 22, ErrorCategory::Timeout, "Timed out");
+
+    let by_host = result.open_ports_by_host();
+    assert_eq!(by_host.len(), 2);
+    assert_eq!(by_host[&web], vec![80, 443]);
+    assert_eq!(by_host[&db], vec![5432]);
+    // Hosts with nothing open get no service probes at all
+    assert!(!by_host.contains_key(&quiet));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tcp_scan_stream_delivers_open_ports_first() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let sockets = Arc::new(
+        MockSockets::new()
+            .respond(SocketAddr::from((ip, 22)), MockResponse::Open(0))
+            .respond(SocketAddr::from((ip, 23)), MockResponse::Silent),
+    );
+    let mut results = tcp_scan_stream_with(&[ip], 22..25, None, &ScanConfig::default(), sockets);
+
+    // The silent port is still waiting out its timeout when the others arrive
+    let mut seen = Vec::new();
+    while let Some(result) = results.recv().await {
+        seen.push((result.port, result.is_open()));
+    }
+    assert_eq!(seen.len(), 3);
+    assert_eq!(seen.last(), Some(&(23, false)));
+    assert!(seen.contains(&(22, true)));
+    assert!(seen.contains(&(24, false)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tcp_scan_records_attempts_and_backs_off() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let sockets = Arc::new(
+        MockSockets::new()
+            .respond(SocketAddr::from((ip, 22)), MockResponse::Open(0))
+            .respond(SocketAddr::from((ip, 23)), MockResponse::Silent),
+    );
+    let config = ScanConfig {
+        timeout: Some(Duration::from_millis(500)),
+        retries: Some(2),
+        retry_delay: Some(Duration::from_millis(200)),
+        ..ScanConfig::default()
+    };
+    let started = tokio::time::Instant::now();
+    let result = tcp_scan_with(&[ip], 22..24, None, &config, sockets).await;
+
+    assert_eq!(result.get_attempts(ip, 22), 1);
+    assert_eq!(result.get_attempts(ip, 23), 3);
+    assert_eq!(result.get_retried().len(), 1);
+    // Three timeouts, with 200ms and then 400ms between them
+    assert!(started.elapsed() >= Duration::from_millis(3 * 500 + 200 + 400));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tcp_scan_probes_only_listed_ports() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let sockets = Arc::new(MockSockets::new().respond(SocketAddr::from((ip, 8080)), MockResponse::Open(0)));
+    let result = tcp_scan_with(&[ip], vec![22, 8080], None, &ScanConfig::default(), sockets.clone()).await;
+
+    assert_eq!(result.get_open_ports().iter().collect::<Vec<_>>(), vec![(ip, 8080)]);
+    // Nothing between the listed ports is probed
+    assert_eq!(sockets.probes().len(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tcp_scan_stream_keeps_concurrency_probes_in_flight() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let sockets = Arc::new(MockSockets::with_default(MockResponse::Silent));
+    let config = ScanConfig {
+        concurrency: Some(2),
+        timeout: Some(Duration::from_secs(1)),
+        ..ScanConfig::default()
+    };
+    let mut results = tcp_scan_stream_with(&[ip], 1..11, None, &config, sockets.clone());
+
+    // Halfway through the first timeouts only two probes have been started
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(sockets.probes().len(), 2);
+
+    let mut count = 0;
+    while results.recv().await.is_some() {
+        count += 1;
+    }
+    assert_eq!(count, 10);
+    assert_eq!(sockets.probes().len(), 10);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_rtts_measured_by_handshake() {
+    let answering = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let refusing = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 6));
+    let silent = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7));
+    let sockets = Arc::new(
+        MockSockets::with_default(MockResponse::Silent)
+            .respond(SocketAddr::from((answering, 443)), MockResponse::Open(0))
+            .respond(SocketAddr::from((refusing, 22)), MockResponse::Fail(111)),
+    );
+    let config = ScanConfig {
+        timeout: Some(Duration::from_millis(200)),
+        ..ScanConfig::default()
+    };
+    let rtts = measure_rtts_with(
+        &[answering, refusing, silent],
+        &[22, 80, 443, 8080],
+        None,
+        &config,
+        sockets.clone(),
+    )
+    .await;
+
+    assert_eq!(rtts.keys().copied().collect::<Vec<_>>(), vec![answering, refusing]);
+    // Only the first few ports are tried, and a refusal ends the search
+    let probes = sockets.probes();
+    assert_eq!(probes.iter().filter(|addr| addr.ip() == silent).count(), 3);
+    assert_eq!(probes.iter().filter(|addr| addr.ip() == refusing).count(), 1);
+}
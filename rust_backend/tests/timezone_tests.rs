@@ -0,0 +1,35 @@
+use chrono::{TimeZone, Utc};
+use rust_backend::utils::timezone::Zone;
+
+#[test]
+fn test_fixed_zones() {
+    let now = Utc.with_ymd_and_hms(2026, 7, 1, 12, 0, 0).unwrap();
+    assert_eq!(Zone::parse("UTC").unwrap().offset_at(now), 0);
+    assert_eq!(Zone::parse("+02:00").unwrap().offset_at(now), 7200);
+    assert_eq!(Zone::parse("UTC-5").unwrap().offset_at(now), -18000);
+    assert_eq!(Zone::parse("-03:30").unwrap().offset_at(now), -12600);
+    assert!(Zone::parse("local").is_ok());
+    assert!(Zone::parse("+25:00").is_err());
+    assert!(Zone::parse("../etc/passwd").is_err());
+}
+
+#[test]
+fn test_named_zones_follow_daylight_saving() {
+    let zone = Zone::parse("Europe/Berlin").unwrap();
+    let winter = Utc.with_ymd_and_hms(2026, 1, 15, 12, 0, 0).unwrap();
+    let summer = Utc.with_ymd_and_hms(2026, 7, 15, 12, 0, 0).unwrap();
+    assert_eq!(zone.offset_at(winter), 3600);
+    assert_eq!(zone.offset_at(summer), 7200);
+    // Summer time starts 2026-03-29 01:00 UTC and ends 2026-10-25 01:00 UTC
+    let at = |m, d, h, mi| Utc.with_ymd_and_hms(2026, m, d, h, mi, 0).unwrap();
+    assert_eq!(zone.offset_at(at(3, 29, 0, 59)), 3600);
+    assert_eq!(zone.offset_at(at(3, 29, 1, 0)), 7200);
+    assert_eq!(zone.offset_at(at(10, 25, 0, 59)), 7200);
+    assert_eq!(zone.offset_at(at(10, 25, 1, 0)), 3600);
+
+    // Southern hemisphere: summer time around the new year
+    let sydney = Zone::parse("Australia/Sydney").unwrap();
+    assert_eq!(sydney.offset_at(at(1, 15, 0, 0)), 11 * 3600);
+    assert_eq!(sydney.offset_at(at(7, 15, 0, 0)), 10 * 3600);
+    assert!(Zone::parse("Mars/Olympus_Mons").is_err());
+}
@@ -0,0 +1,60 @@
+use rust_backend::utils::scan_config::ScanConfig;
+use rust_backend::utils::timing_template::{self, MIN_ADAPTIVE_TIMEOUT, TimingTemplate};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+#[test]
+fn test_templates_fill_only_unset_settings() {
+    assert_eq!(
+        TimingTemplate::from_level(4),
+        Some(TimingTemplate::Aggressive)
+    );
+    assert_eq!(TimingTemplate::from_level(6), None);
+    assert_eq!(TimingTemplate::Polite.to_string(), "T2 (polite)");
+    assert_eq!(
+        TimingTemplate::Normal.apply(ScanConfig::new()),
+        ScanConfig::new()
+    );
+
+    let given = ScanConfig {
+        retries: Some(3),
+        ..ScanConfig::new()
+    };
+    let config = TimingTemplate::Insane.apply(given);
+    assert_eq!(config.timeout, Some(Duration::from_millis(300)));
+    assert_eq!(config.retries, Some(3));
+    assert_eq!(
+        TimingTemplate::Sneaky.apply(ScanConfig::new()).concurrency,
+        Some(1)
+    );
+    assert!(TimingTemplate::Paranoid.probe_delay() > TimingTemplate::Polite.probe_delay());
+}
+
+#[test]
+fn test_adaptive_timeouts_follow_round_trips() {
+    let near: IpAddr = "10.0.0.2".parse().unwrap();
+    let far: IpAddr = "10.8.0.2".parse().unwrap();
+    let rtts = BTreeMap::from([
+        (near, Duration::from_millis(1)),
+        (far, Duration::from_millis(200)),
+    ]);
+    let timeouts = timing_template::adaptive_timeouts(&rtts);
+    assert_eq!(timeouts[&near], MIN_ADAPTIVE_TIMEOUT);
+    assert_eq!(timeouts[&far], Duration::from_millis(850));
+
+    let config = ScanConfig {
+        timeout: Some(Duration::from_millis(500)),
+        ..ScanConfig::new()
+    }
+    .with_host_timeouts(timeouts);
+    let default = Duration::from_secs(3);
+    assert_eq!(config.timeout_for(near, default), MIN_ADAPTIVE_TIMEOUT);
+    // Never past the general timeout
+    assert_eq!(config.timeout_for(far, default), Duration::from_millis(500));
+    let other: IpAddr = "10.0.0.9".parse().unwrap();
+    assert_eq!(
+        config.timeout_for(other, default),
+        Duration::from_millis(500)
+    );
+}
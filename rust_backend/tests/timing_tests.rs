@@ -0,0 +1,52 @@
+use rust_backend::utils::timing::{RunTimings, format_duration};
+use std::net::IpAddr;
+use std::time::Duration;
+
+fn ip(last: u8) -> IpAddr {
+    IpAddr::from([10, 0, 0, last])
+}
+
+#[test]
+fn test_slow_hosts_stand_out_from_the_median() {
+    let mut timings = RunTimings::new();
+    timings.record("Discovery", Duration::from_secs(4));
+    timings.record_hosts(
+        "Service detection",
+        Duration::from_secs(100),
+        vec![
+            (ip(1), Duration::from_secs(3)),
+            (ip(2), Duration::from_secs(4)),
+            (ip(3), Duration::from_secs(60)),
+            (ip(4), Duration::from_secs(5)),
+            (ip(5), Duration::from_secs(30)),
+        ],
+    );
+    assert_eq!(timings.total(), Duration::from_secs(104));
+    assert!(timings.phases[0].outliers().is_empty());
+    assert_eq!(
+        timings.phases[1].outliers(),
+        vec![
+            (ip(3), Duration::from_secs(60)),
+            (ip(5), Duration::from_secs(30))
+        ]
+    );
+
+    // Sub-second phases: a few hundred milliseconds more is not an outlier
+    timings.record_hosts(
+        "Fingerprinting",
+        Duration::from_secs(1),
+        vec![
+            (ip(1), Duration::from_millis(100)),
+            (ip(2), Duration::from_millis(900)),
+            (ip(3), Duration::from_millis(120)),
+        ],
+    );
+    assert!(timings.phases[2].outliers().is_empty());
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!(format_duration(Duration::from_millis(850)), "850ms");
+    assert_eq!(format_duration(Duration::from_millis(12_400)), "12.4s");
+    assert_eq!(format_duration(Duration::from_secs(41 * 60 + 3)), "41m 03s");
+}
@@ -0,0 +1,142 @@
+use chrono::{TimeZone, Utc};
+use rust_backend::detect_tls::{CertificateInfo, TlsVersion};
+use rust_backend::scanners::tls_audit::{TlsReport, enumerate_ciphers};
+use rust_backend::utils::findings::Severity;
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+fn certificate(self_signed: bool, expires_year: i32) -> CertificateInfo {
+    CertificateInfo {
+        serial: "01".to_string(),
+        subject: "host.example.com".to_string(),
+        issuer: if self_signed {
+            "host.example.com"
+        } else {
+            "Example CA"
+        }
+        .to_string(),
+        sans: vec!["host.example.com".to_string()],
+        key_algorithm: "RSA".to_string(),
+        key_bits: Some(2048),
+        key_fingerprint: "00000000000000aa".to_string(),
+        not_before: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        not_after: Utc.with_ymd_and_hms(expires_year, 1, 1, 0, 0, 0).unwrap(),
+        self_signed,
+    }
+}
+
+fn report(versions: &[TlsVersion], ciphers: &[u16]) -> TlsReport {
+    let mut report = TlsReport::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5)), 443);
+    report.versions = versions.to_vec();
+    report.ciphers = ciphers
+        .iter()
+        .map(|c| (*versions.last().unwrap(), *c))
+        .collect();
+    report
+}
+
+#[test]
+fn test_modern_endpoint_grades_a() {
+    let mut modern = report(&[TlsVersion::Tls12, TlsVersion::Tls13], &[0xc02f, 0x1301]);
+    modern.certificate = Some(certificate(false, 2030));
+    modern.assess(Utc.with_ymd_and_hms(2026, 6, 1, 0, 0, 0).unwrap());
+    assert_eq!(modern.grade, Some('A'));
+    assert_eq!(modern.risk_score(), 0);
+    assert_eq!(modern.findings.len(), 1);
+    assert_eq!(modern.findings[0].title, "TLS grade A");
+}
+
+#[test]
+fn test_legacy_endpoint_grades_down() {
+    let now = Utc.with_ymd_and_hms(2026, 6, 1, 0, 0, 0).unwrap();
+
+    let mut legacy = report(&[TlsVersion::Tls10, TlsVersion::Tls12], &[0xc02f, 0x000a]);
+    legacy.certificate = Some(certificate(true, 2030));
+    legacy.assess(now);
+    assert_eq!(legacy.grade, Some('C'));
+    let titles: Vec<&str> = legacy.findings.iter().map(|f| f.title.as_str()).collect();
+    assert!(titles.contains(&"Deprecated TLS versions accepted"));
+    assert!(titles.contains(&"Weak cipher suites accepted"));
+    assert!(titles.contains(&"Self-signed certificate"));
+
+    let mut broken = report(&[TlsVersion::Ssl3, TlsVersion::Tls10], &[0x0004]);
+    broken.certificate = Some(certificate(false, 2025));
+    broken.assess(now);
+    assert_eq!(broken.grade, Some('F'));
+    assert_eq!(broken.findings[0].severity, Severity::High);
+    assert!(
+        broken
+            .findings
+            .iter()
+            .any(|f| f.title == "Certificate expired")
+    );
+    assert!(broken.risk_score() > legacy.risk_score());
+}
+
+#[test]
+fn test_non_tls_endpoint_is_not_graded() {
+    let mut report = TlsReport::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 22);
+    report.assess(Utc::now());
+    assert!(!report.is_tls());
+    assert_eq!(report.grade, None);
+}
+
+/// Fake TLS 1.2 server that only accepts ECDHE-RSA-AES128-GCM-SHA256 and
+/// RSA-AES128-SHA, preferring the former.
+async fn spawn_fake_tls_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            let mut buf = vec![0u8; 4096];
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            let hello = &buf[..n];
+            if hello.len() < 46 {
+                continue;
+            }
+            let client_version = u16::from_be_bytes([hello[9], hello[10]]);
+            let suites_len = u16::from_be_bytes([hello[44], hello[45]]) as usize;
+            let offered: Vec<u16> = hello[46..46 + suites_len]
+                .chunks(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            let choice = [0xc02fu16, 0x002f]
+                .into_iter()
+                .find(|c| offered.contains(c));
+            let reply = match choice {
+                Some(cipher) if client_version == 0x0303 => {
+                    let mut body = vec![0x03, 0x03];
+                    body.extend_from_slice(&[0u8; 32]);
+                    body.push(0);
+                    body.extend_from_slice(&cipher.to_be_bytes());
+                    body.push(0);
+                    let mut handshake = vec![0x02, 0, 0, body.len() as u8];
+                    handshake.extend(body);
+                    let mut record = vec![0x16, 0x03, 0x03, 0, handshake.len() as u8];
+                    record.extend(handshake);
+                    record
+                }
+                _ => vec![0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28],
+            };
+            let _ = stream.write_all(&reply).await;
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn test_enumerate_ciphers_against_fake_server() {
+    let port = spawn_fake_tls_server().await;
+    let tls12 = enumerate_ciphers(IpAddr::V4(Ipv4Addr::LOCALHOST), port, TlsVersion::Tls12)
+        .await
+        .unwrap();
+    assert_eq!(tls12, vec![0xc02f, 0x002f]);
+    let tls10 = enumerate_ciphers(IpAddr::V4(Ipv4Addr::LOCALHOST), port, TlsVersion::Tls10)
+        .await
+        .unwrap();
+    assert!(tls10.is_empty());
+}
@@ -0,0 +1,62 @@
+use rust_backend::detect_ntp;
+use rust_backend::scanners::udp_probes::{self, dns_query};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+fn addr(port: u16) -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5)), port)
+}
+
+#[test]
+fn test_well_known_ports_get_their_protocol() {
+    for (port, name) in [
+        (53, "DNS"),
+        (69, "TFTP"),
+        (123, "NTP"),
+        (137, "NetBIOS-NS"),
+        (161, "SNMP"),
+        (1900, "SSDP"),
+        (5353, "mDNS"),
+        (5060, "SIP"),
+    ] {
+        assert_eq!(udp_probes::probe_for(port).map(|p| p.name), Some(name));
+    }
+    assert!(udp_probes::probe_for(9999).is_none());
+    assert_eq!(udp_probes::payload_for(addr(9999)), vec![0u8]);
+}
+
+#[test]
+fn test_payloads() {
+    // The query udpscan always sent to port 53
+    assert_eq!(
+        udp_probes::payload_for(addr(53)),
+        vec![
+            0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, b'w',
+            b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm',
+            0x00, 0x00, 0x01, 0x00, 0x01,
+        ]
+    );
+    assert_eq!(
+        udp_probes::payload_for(addr(123)),
+        detect_ntp::build_request().to_vec()
+    );
+
+    let nbstat = udp_probes::payload_for(addr(137));
+    assert_eq!(nbstat.len(), 50);
+    assert_eq!(&nbstat[12..16], &[32, b'C', b'K', b'A']);
+    assert_eq!(&nbstat[46..], &[0x00, 0x21, 0x00, 0x01]);
+
+    // mDNS asks for a unicast answer so it reaches the probe socket
+    let mdns = udp_probes::payload_for(addr(5353));
+    assert_eq!(&mdns[mdns.len() - 4..], &[0x00, 0x0c, 0x80, 0x01]);
+    assert_eq!(
+        dns_query(0, 0, "local", 12, 1),
+        vec![
+            0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 5, b'l', b'o', b'c', b'a', b'l', 0, 0, 12, 0, 1
+        ]
+    );
+
+    let ssdp = String::from_utf8(udp_probes::payload_for(addr(1900))).unwrap();
+    assert!(ssdp.starts_with("M-SEARCH * HTTP/1.1\r\n"));
+    let sip = String::from_utf8(udp_probes::payload_for(addr(5060))).unwrap();
+    assert!(sip.starts_with("OPTIONS sip:probe@10.0.0.5"));
+}
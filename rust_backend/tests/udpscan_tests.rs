@@ -1,9 +1,14 @@
-use rust_backend::scanners::udpscan::udp_scan;
-use std::net::Ipv4Addr;
+use rust_backend::scanners::udpscan::{UdpPortState, udp_scan, udp_scan_with};
+use rust_backend::utils::errors::ErrorCategory;
+use std::collections::HashMap;
+use rust_backend::utils::scan_config::ScanConfig;
+use rust_backend::utils::sockets::{MockResponse, MockSockets};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
 
 #[tokio::test]
 async fn test_udp_scan_valid_host() {
-    let live_hosts = vec![Ipv4Addr::new(192, 168, 1, 1)]; // Replace with a valid host on your network
+    let live_hosts = vec![IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1))]; // Replace with a valid host on your network
     let port_range = 53..54; // Example: DNS port
     let result = udp_scan(&live_hosts, port_range).await;
 
@@ -19,7 +24,7 @@ async fn test_udp_scan_valid_host() {
 
 #[tokio::test]
 async fn test_udp_scan_invalid_host() {
-    let live_hosts = vec![Ipv4Addr::new(192, 0, 2, 1)]; // Reserved IP (unreachable)
+    let live_hosts = vec![IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))]; // Reserved IP (unreachable)
     let port_range = 1..10;
     let result = udp_scan(&live_hosts, port_range).await;
 
@@ -35,7 +40,7 @@ async fn test_udp_scan_invalid_host() {
 
 #[tokio::test]
 async fn test_udp_scan_empty_port_range() {
-    let live_hosts = vec![Ipv4Addr::new(127, 0, 0, 1)]; // Localhost
+    let live_hosts = vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))]; // Localhost
     let port_range = 0..0; // Empty range
     let result = udp_scan(&live_hosts, port_range).await;
 
@@ -52,9 +57,9 @@ async fn test_udp_scan_empty_port_range() {
 #[tokio::test]
 async fn test_udp_scan_multiple_hosts() {
     let live_hosts = vec![
-        Ipv4Addr::new(127, 0, 0, 1),   // Localhost
-        Ipv4Addr::new(192, 168, 1, 1), // Replace with a valid host
-        Ipv4Addr::new(192, 0, 2, 1),   // Reserved IP (unreachable)
+        IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),   // Localhost
+        IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)), // Replace with a valid host
+        IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),   // Reserved IP (unreachable)
     ];
     let port_range = 53..55; // Example: DNS and another port
     let result = udp_scan(&live_hosts, port_range).await;
@@ -74,3 +79,54 @@ async fn test_udp_scan_multiple_hosts() {
         "No errors recorded for unreachable hosts!"
     );
 }
+
+#[tokio::test(start_paused = true)]
+async fn test_udp_scan_with_mock_sockets() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let sockets = Arc::new(
+        MockSockets::with_default(MockResponse::Silent)
+            .respond(SocketAddr::from((ip, 53)), MockResponse::Open(48)),
+    );
+    let result = udp_scan_with(&[ip], 50..55, None, &ScanConfig::default(), sockets).await;
+
+    assert_eq!(result.get_open_ports().iter().collect::<Vec<_>>(), vec![(ip, 53)]);
+    assert_eq!(result.get_errors().len(), 4); // The rest time out
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_udp_port_states() {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+    let sockets = Arc::new(
+        MockSockets::with_default(MockResponse::Silent)
+            .respond(SocketAddr::from((ip, 53)), MockResponse::Open(48))
+            .respond(SocketAddr::from((ip, 54)), MockResponse::Fail(111))
+            .respond(SocketAddr::from((ip, 55)), MockResponse::Fail(113)),
+    );
+    let mut result = udp_scan_with(&[ip], 53..58, None, &ScanConfig::default(), sockets).await;
+    assert_eq!(result.get_udp_state(ip, 53), UdpPortState::Open);
+    assert_eq!(result.get_udp_state(ip, 54), UdpPortState::Closed);
+    assert_eq!(result.get_udp_state(ip, 55), UdpPortState::Filtered);
+    assert_eq!(result.get_udp_state(ip, 56), UdpPortState::OpenFiltered);
+    assert_eq!(result.get_udp_state(ip, 99), UdpPortState::Unscanned);
+
+    // ICMP heard on the raw socket settles ports the probe socket saw time out
+    let unreachable = HashMap::from([
+        ((ip, 53), ErrorCategory::Refused),
+        ((ip, 56), ErrorCategory::Refused),
+        ((ip, 57), ErrorCategory::Unreachable),
+    ]);
+    result.apply_unreachable(&unreachable);
+    assert_eq!(result.get_udp_state(ip, 53), UdpPortState::Open);
+    assert_eq!(result.get_udp_state(ip, 56), UdpPortState::Closed);
+    assert_eq!(result.get_udp_state(ip, 57), UdpPortState::Filtered);
+    assert_eq!(
+        result.state_counts().into_iter().collect::<Vec<_>>(),
+        vec![
+            (UdpPortState::Open, 1),
+            (UdpPortState::Closed, 2),
+            (UdpPortState::Filtered, 2),
+        ]
+    );
+    assert_eq!(result.get_error_counts()[&ip][&ErrorCategory::Timeout], 0);
+    assert_eq!(result.get_errors().len(), 4);
+}
@@ -0,0 +1,75 @@
+use chrono::{TimeZone, Utc};
+use rust_backend::scanners::service_detection::{self, DetectorRegistry};
+use rust_backend::utils::scan_config::ScanConfig;
+use rust_backend::utils::unknowns::{self, UnknownSample};
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+#[test]
+fn test_anonymize_and_render() {
+    let ip: IpAddr = "10.1.2.3".parse().unwrap();
+    let response = b"\x00\x01hello 10.1.2.3, gateway 172.16.0.1 build 1.2.3 v10.0.0.1\r\n";
+    assert_eq!(
+        unknowns::anonymize(response, ip),
+        b"\x00\x01hello 192.0.2.1, gateway 192.0.2.1 build 1.2.3 v10.0.0.1\r\n"
+    );
+
+    let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+    let sample = UnknownSample::anonymized(9999, &[], response, ip, now);
+    assert_eq!(
+        sample.render(),
+        "# netscan unknown service sample\nport: 9999\ncaptured: 2026-10-16T12:00:00+00:00\nprobe: NULL\n\
+         response: \\x00\\x01hello 192.0.2.1, gateway 192.0.2.1 build 1.2.3 v10.0.0.1\\r\\n\n"
+    );
+    // The same exchange seen again maps to the same file
+    let again = UnknownSample::anonymized(9999, &[], response, ip, Utc::now());
+    assert_eq!(sample.file_name(), again.file_name());
+    assert!(sample.file_name().starts_with("port9999-"));
+}
+
+#[test]
+fn test_rendered_sample_parses_back() {
+    let sample = UnknownSample {
+        port: 7000,
+        probe: b"HELLO\\\r\n".to_vec(),
+        response: vec![0x00, 0xff, b'o', b'k', b'\t', b'\n'],
+        captured: Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap(),
+    };
+    assert_eq!(UnknownSample::parse(&sample.render()), Ok(sample));
+    assert!(unknowns::unescape("bad \\x4").is_err());
+    assert!(UnknownSample::parse("port: 80\n").is_err());
+}
+
+#[tokio::test]
+async fn test_unmatched_banner_is_collected() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0))
+        .await
+        .unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        socket.write_all(b"WIDGETD 2.1 ready\r\n").await.unwrap();
+    });
+    let dir = std::env::temp_dir().join(format!("netscan_unknowns_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let config = ScanConfig {
+        collect_unknowns: Some(dir.clone()),
+        ..ScanConfig::default()
+    };
+    let result = service_detection::detect_service_with(
+        &DetectorRegistry::new(),
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+        port,
+        &[],
+        &config,
+    )
+    .await;
+    assert_eq!(result.service.as_deref(), Some("Banner: WIDGETD 2.1 ready"));
+
+    let files: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+    assert_eq!(files.len(), 1);
+    let text = std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(text.ends_with("response: WIDGETD 2.1 ready\\r\\n\n"));
+}
@@ -0,0 +1,67 @@
+use pnet::datalink::MacAddr;
+use rust_backend::scanners::arpsweep::{build_arp_request, parse_arp_reply};
+use rust_backend::utils::vlan::{accepts, frame_vlan_id, tag_frame, untag_frame, validate_vlan_id};
+use std::net::Ipv4Addr;
+
+#[test]
+fn test_validate_vlan_id() {
+    assert!(validate_vlan_id(1).is_ok());
+    assert!(validate_vlan_id(4094).is_ok());
+    assert!(validate_vlan_id(0).is_err());
+    assert!(validate_vlan_id(4095).is_err());
+}
+
+#[test]
+fn test_tag_and_untag_roundtrip() {
+    let frame: Vec<u8> = (0u8..20).collect();
+    let tagged = tag_frame(&frame, 20).unwrap();
+    assert_eq!(tagged.len(), frame.len() + 4);
+    assert_eq!(&tagged[12..16], &[0x81, 0x00, 0x00, 20]);
+    assert_eq!(frame_vlan_id(&tagged), Some(20));
+    assert_eq!(untag_frame(&tagged), frame);
+    assert_eq!(frame_vlan_id(&frame), None);
+}
+
+#[test]
+fn test_accepts_only_the_probed_vlan() {
+    assert!(accepts(None, None));
+    assert!(accepts(None, Some(30)));
+    assert!(accepts(Some(20), Some(20)));
+    // An untagged reply comes from the native VLAN, not the one probed
+    assert!(!accepts(Some(20), None));
+    assert!(!accepts(Some(20), Some(30)));
+}
+
+#[test]
+fn test_tagged_arp_request() {
+    let mac = MacAddr::new(0x02, 0, 0, 0, 0, 1);
+    let frame = build_arp_request(
+        mac,
+        Ipv4Addr::new(10, 20, 0, 5),
+        Ipv4Addr::new(10, 20, 0, 1),
+        Some(20),
+    )
+    .unwrap();
+    assert_eq!(frame.len(), 46);
+    assert_eq!(frame_vlan_id(&frame), Some(20));
+    // Requests are not replies
+    assert_eq!(parse_arp_reply(&frame), None);
+}
+
+#[test]
+fn test_parse_tagged_arp_reply() {
+    let mut frame = build_arp_request(
+        MacAddr::new(0x02, 0, 0, 0, 0, 9),
+        Ipv4Addr::new(10, 20, 0, 1),
+        Ipv4Addr::new(10, 20, 0, 5),
+        None,
+    )
+    .unwrap();
+    frame[21] = 2; // ARP operation: reply
+    let tagged = tag_frame(&frame, 30).unwrap();
+
+    let (ip, mac, vlan) = parse_arp_reply(&tagged).unwrap();
+    assert_eq!(ip, Ipv4Addr::new(10, 20, 0, 1));
+    assert_eq!(mac, MacAddr::new(0x02, 0, 0, 0, 0, 9));
+    assert_eq!(vlan, Some(30));
+}
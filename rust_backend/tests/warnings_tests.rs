@@ -0,0 +1,47 @@
+use rust_backend::utils::warnings::{
+    WarningKind, note, parse_cap_net_raw, parse_open_files_limit, parse_port_range, storm_warnings,
+};
+use std::io;
+
+#[test]
+fn test_parse_port_range() {
+    assert_eq!(parse_port_range("32768\t60999\n"), Some((32768, 60999)));
+    assert_eq!(parse_port_range("garbage"), None);
+}
+
+#[test]
+fn test_parse_open_files_limit() {
+    let limits = "\
+Limit                     Soft Limit           Hard Limit           Units
+Max cpu time              unlimited            unlimited            seconds
+Max open files            1024                 524288               files
+";
+    assert_eq!(parse_open_files_limit(limits), Some(1024));
+    assert_eq!(parse_open_files_limit("Max cpu time unlimited"), None);
+}
+
+#[test]
+fn test_permission_storm_is_reported_once_threshold_reached() {
+    for _ in 0..30 {
+        let message = note(io::Error::from_raw_os_error(1));
+        assert!(message.contains("Operation not permitted"));
+    }
+    // Unclassified errors never count towards a storm
+    note(io::Error::from_raw_os_error(111));
+
+    let storms = storm_warnings();
+    assert_eq!(storms.len(), 1);
+    assert_eq!(storms[0].kind, WarningKind::PermissionDenied);
+    assert!(storms[0].message.starts_with("30 "));
+}
+
+#[test]
+fn test_parse_cap_net_raw() {
+    let root = "Name:\tnetscan\nCapInh:\t0000000000000000\nCapEff:\t000001ffffffffff\n";
+    assert_eq!(parse_cap_net_raw(root), Some(true));
+    let user = "Name:\tnetscan\nCapEff:\t0000000000000000\n";
+    assert_eq!(parse_cap_net_raw(user), Some(false));
+    // setcap cap_net_raw+ep
+    assert_eq!(parse_cap_net_raw("CapEff:\t0000000000002000\n"), Some(true));
+    assert_eq!(parse_cap_net_raw("Name:\tnetscan\n"), None);
+}
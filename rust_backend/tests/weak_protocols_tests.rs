@@ -0,0 +1,49 @@
+use rust_backend::scanners::weak_protocols::{self, WeakKind, WeakProtocolReport, WeakService};
+use rust_backend::utils::findings::Severity;
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+#[test]
+fn test_report_groups_by_kind_then_endpoint() {
+    let a = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let b = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+    let mut report = WeakProtocolReport::new();
+    report.extend(vec![
+        WeakService::new(b, 5900, WeakKind::VncWithoutAuth, "RFB 3.8".to_string()),
+        WeakService::new(b, 23, WeakKind::Telnet, "option negotiation".to_string()),
+        WeakService::new(a, 2323, WeakKind::Telnet, "option negotiation".to_string()),
+    ]);
+
+    let order: Vec<(WeakKind, IpAddr, u16)> = report
+        .exposures()
+        .iter()
+        .map(|e| (e.kind, e.ip, e.port))
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            (WeakKind::Telnet, a, 2323),
+            (WeakKind::Telnet, b, 23),
+            (WeakKind::VncWithoutAuth, b, 5900),
+        ]
+    );
+
+    let findings = report.findings();
+    assert_eq!(findings[2].severity, Severity::Critical);
+    assert_eq!(findings[0].detail, "10.0.0.1: option negotiation");
+}
+
+#[tokio::test]
+async fn test_check_host_ignores_ports_without_weak_services() {
+    let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let _ = socket.write_all(b"hello\r\n").await;
+    });
+
+    // Port isn't one the checks look at, so only the SNMP probe runs
+    let found = weak_protocols::check_host(IpAddr::V4(Ipv4Addr::LOCALHOST), &[port]).await;
+    assert!(found.iter().all(|e| e.kind == WeakKind::SnmpV1V2c));
+}